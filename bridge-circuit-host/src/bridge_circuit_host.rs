@@ -1,17 +1,21 @@
+use crate::diagnostics::diagnose_bridge_circuit;
 use crate::docker::{stark_to_bitvm2_g16, stark_to_bitvm2_g16_dev_mode};
+use crate::mock_zkvm::MockZkvmHost;
 use crate::structs::{
     BridgeCircuitBitvmInputs, BridgeCircuitHostParams, SuccinctBridgeCircuitPublicInputs,
 };
-use crate::utils::{calculate_succinct_output_prefix, is_dev_mode};
+use crate::utils::{calculate_succinct_output_prefix, is_dev_mode, panic_message};
 use ark_bn254::Bn254;
 use bitcoin::Transaction;
 use borsh;
+use circuits_lib::bridge_circuit::bridge_circuit;
 use circuits_lib::bridge_circuit::constants::{
     DEVNET_LC_IMAGE_ID, MAINNET_LC_IMAGE_ID, REGTEST_LC_IMAGE_ID, TESTNET4_LC_IMAGE_ID,
 };
 use circuits_lib::bridge_circuit::groth16::CircuitGroth16Proof;
 use circuits_lib::bridge_circuit::merkle_tree::BitcoinMerkleTree;
 use circuits_lib::bridge_circuit::spv::SPV;
+use circuits_lib::bridge_circuit::structs::BridgeCircuitInput;
 use circuits_lib::bridge_circuit::transaction::CircuitTransaction;
 use citrea_sov_rollup_interface::zk::light_client_proof::output::LightClientCircuitOutput;
 use eyre::{eyre, Result, WrapErr};
@@ -20,8 +24,12 @@ use circuits_lib::common::constants::{
     MAINNET_HEADER_CHAIN_METHOD_ID, REGTEST_HEADER_CHAIN_METHOD_ID, SIGNET_HEADER_CHAIN_METHOD_ID,
     TESTNET4_HEADER_CHAIN_METHOD_ID,
 };
+use circuits_lib::common::zkvm::ZkvmHost;
+use circuits_lib::common::{BitcoinNetwork, NETWORK};
 use circuits_lib::header_chain::mmr_native::MMRNative;
-use risc0_zkvm::{compute_image_id, default_prover, ExecutorEnv, ProverOpts};
+use risc0_zkvm::{compute_image_id, default_prover, ExecutorEnv, ProverOpts, Receipt};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use thiserror::Error;
 
 pub const REGTEST_BRIDGE_CIRCUIT_ELF: &[u8] =
     include_bytes!("../../risc0-circuits/elfs/regtest-bridge-circuit-guest.bin");
@@ -95,12 +103,15 @@ pub const REGTEST_WORK_ONLY_ELF: &[u8] =
 /// - Any serialization/deserialization operation fails.
 /// - The network is unsupported.
 /// - The execution environment cannot be built.
-/// - Proof generation fails.
+/// - Proof generation fails. The error then includes a report of the circuit's
+///   checks run in mock mode, see [`diagnose_bridge_circuit`].
 /// - Receipt journal conversion fails.
 /// - Computing the image ID fails.
 /// - Converting succinct receipt fails.
 /// - Converting groth16 seal to array fails.
 ///
+/// Returns the succinct STARK receipt alongside the Groth16 proof, so the
+/// caller can keep it.
 pub fn prove_bridge_circuit(
     bridge_circuit_host_params: BridgeCircuitHostParams,
     bridge_circuit_elf: &[u8],
@@ -108,12 +119,22 @@ pub fn prove_bridge_circuit(
     ark_groth16::Proof<Bn254>,
     [u8; 31],
     BridgeCircuitBitvmInputs,
+    Receipt,
 )> {
     tracing::info!("Starting bridge circuit proof generation");
     let bridge_circuit_input = bridge_circuit_host_params
         .clone()
         .into_bridge_circuit_input();
 
+    // Run the circuit's checks natively first, so a failing proof can be
+    // traced back to the check that made the guest panic.
+    let network = BitcoinNetwork::from_bitcoin_network(bridge_circuit_host_params.network.0)
+        .ok_or_else(|| eyre!("Unsupported network"))?;
+    let report = diagnose_bridge_circuit(&bridge_circuit_input, network);
+    if !report.passed() {
+        tracing::warn!("Bridge circuit checks failed in mock execution:\n{report}");
+    }
+
     let header_chain_proof_output_serialized = borsh::to_vec(&bridge_circuit_input.hcp)
         .wrap_err("Could not serialize header chain output")?;
 
@@ -224,7 +245,13 @@ pub fn prove_bridge_circuit(
 
     let succinct_receipt = prover
         .prove_with_opts(env, bridge_circuit_elf, &ProverOpts::succinct())
-        .map_err(|e| eyre!("Failed to generate bridge circuit proof: {}", e))?
+        .map_err(|e| {
+            eyre!(
+                "Failed to generate bridge circuit proof: {}\nBridge circuit diagnostics:\n{}",
+                e,
+                report
+            )
+        })?
         .receipt;
 
     tracing::info!("Bridge circuit proof (STARK) generated");
@@ -247,7 +274,7 @@ pub fn prove_bridge_circuit(
         calculate_succinct_output_prefix(bridge_circuit_method_id.as_bytes());
 
     let (g16_proof, g16_output) = if is_dev_mode() {
-        stark_to_bitvm2_g16_dev_mode(succinct_receipt, &succinct_receipt_journal)?
+        stark_to_bitvm2_g16_dev_mode(succinct_receipt.clone(), &succinct_receipt_journal)?
     } else {
         stark_to_bitvm2_g16(
             succinct_receipt
@@ -293,6 +320,7 @@ pub fn prove_bridge_circuit(
             deposit_constant: public_inputs.deposit_constant.0,
             combined_method_id: combined_method_id_constant,
         },
+        succinct_receipt,
     ))
 }
 
@@ -391,10 +419,55 @@ pub fn create_spv(
     Ok(spv)
 }
 
+/// Error returned by [`execute_bridge_circuit_mock`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MockExecutionError {
+    #[error("This build of the bridge circuit checks {built} constants, cannot execute an input for {requested}")]
+    NetworkMismatch {
+        built: BitcoinNetwork,
+        requested: BitcoinNetwork,
+    },
+    #[error("Bridge circuit assertion failed: {0}")]
+    AssertionFailed(String),
+}
+
+/// Executes the bridge circuit on `input` natively with a [`MockZkvmHost`],
+/// returning the committed journal. The header chain proof is not verified
+/// by the mock host, all other checks run as they would in the zkVM.
+///
+/// The circuit constants (header chain method ID, light client image ID) are
+/// fixed when this crate is built, so `network` must match the network the
+/// build targets.
+///
+/// # Errors
+///
+/// Returns [`MockExecutionError::AssertionFailed`] with the panic message of
+/// the first failing check.
+pub fn execute_bridge_circuit_mock(
+    input: &BridgeCircuitInput,
+    network: BitcoinNetwork,
+) -> Result<Vec<u8>, MockExecutionError> {
+    if network != NETWORK {
+        return Err(MockExecutionError::NetworkMismatch {
+            built: NETWORK,
+            requested: network,
+        });
+    }
+
+    let host = MockZkvmHost::new();
+    host.write(input);
+
+    catch_unwind(AssertUnwindSafe(|| {
+        bridge_circuit(&host, network.work_only_method_id())
+    }))
+    .map_err(|payload| MockExecutionError::AssertionFailed(panic_message(payload.as_ref())))?;
+
+    Ok(host.prove(&[0; 8]).journal)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::mock_zkvm::MockZkvmHost;
     #[cfg(feature = "use-test-vk")]
     use crate::utils::total_work_from_wt_tx_test_util;
 
@@ -412,7 +485,6 @@ mod tests {
     };
     use circuits_lib::{
         bridge_circuit::{parse_op_return_data, structs::WorkOnlyCircuitOutput},
-        common::zkvm::ZkvmHost,
         header_chain::{
             header_chain_circuit, BlockHeaderCircuitOutput, ChainState, CircuitBlockHeader,
             HeaderChainCircuitInput, HeaderChainPrevProofType,
@@ -913,7 +985,7 @@ mod tests {
         let bridge_circuit_host_params: BridgeCircuitHostParams = borsh::from_slice(input_bytes)
             .expect("Failed to deserialize BridgeCircuitHostParams from file");
 
-        let (proof, public_output, bitvm_inputs) =
+        let (proof, public_output, bitvm_inputs, _) =
             prove_bridge_circuit(bridge_circuit_host_params, REGTEST_BRIDGE_CIRCUIT_ELF_TEST)
                 .unwrap();
         println!("Proof: {proof:?}");
@@ -929,7 +1001,7 @@ mod tests {
         );
         assert_single_op_return_commitment_outputs(&bridge_circuit_host_params);
 
-        let (proof, public_output, bitvm_inputs) =
+        let (proof, public_output, bitvm_inputs, _) =
             prove_bridge_circuit(bridge_circuit_host_params, REGTEST_BRIDGE_CIRCUIT_ELF_TEST)
                 .unwrap();
         println!("Proof: {proof:?}");
@@ -954,7 +1026,7 @@ mod tests {
                 .watchtower_challenge_witness
                 .push(large_data);
         }
-        let (_, _, _) =
+        let (_, _, _, _) =
             prove_bridge_circuit(bridge_circuit_host_params, REGTEST_BRIDGE_CIRCUIT_ELF_TEST)
                 .unwrap();
     }
@@ -976,7 +1048,7 @@ mod tests {
                 .watchtower_challenge_witness
                 .push(large_data);
         }
-        let (_, _, _) =
+        let (_, _, _, _) =
             prove_bridge_circuit(bridge_circuit_host_params, REGTEST_BRIDGE_CIRCUIT_ELF_TEST)
                 .unwrap();
     }
@@ -989,7 +1061,7 @@ mod tests {
         let bridge_circuit_host_params: BridgeCircuitHostParams = borsh::from_slice(input_bytes)
             .expect("Failed to deserialize BridgeCircuitHostParams from file");
 
-        let (proof, public_output, bitvm_inputs) =
+        let (proof, public_output, bitvm_inputs, _) =
             prove_bridge_circuit(bridge_circuit_host_params, REGTEST_BRIDGE_CIRCUIT_ELF_TEST)
                 .unwrap();
         println!("Proof: {proof:?}");
@@ -1006,7 +1078,7 @@ mod tests {
         assert_large_output_shape(&bridge_circuit_host_params);
         assert_single_op_return_commitment_outputs(&bridge_circuit_host_params);
 
-        let (proof, public_output, bitvm_inputs) =
+        let (proof, public_output, bitvm_inputs, _) =
             prove_bridge_circuit(bridge_circuit_host_params, REGTEST_BRIDGE_CIRCUIT_ELF_TEST)
                 .unwrap();
         println!("Proof: {proof:?}");
@@ -1022,7 +1094,7 @@ mod tests {
         let bridge_circuit_host_params: BridgeCircuitHostParams = borsh::from_slice(input_bytes)
             .expect("Failed to deserialize BridgeCircuitHostParams from file");
 
-        let (proof, public_output, bitvm_inputs) =
+        let (proof, public_output, bitvm_inputs, _) =
             prove_bridge_circuit(bridge_circuit_host_params, REGTEST_BRIDGE_CIRCUIT_ELF_TEST)
                 .unwrap();
         println!("Proof: {proof:?}");
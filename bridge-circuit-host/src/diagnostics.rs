@@ -0,0 +1,636 @@
+//! # Bridge Circuit Diagnostics
+//!
+//! The bridge circuit panics on the first failed check, which only surfaces as
+//! a bare prover error. This module runs the same checks one by one on a
+//! [`BridgeCircuitInput`] through a [`MockZkvmHost`] without stopping at the
+//! first failure, and reports the values each check compared along with its
+//! result.
+//!
+//! Receipts are not verified here: the header chain proof is checked by the
+//! mock host's no-op `verify`, and only the method ID of the light client
+//! proof output is checked.
+
+use std::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use bitcoin::hashes::Hash;
+use circuits_lib::bridge_circuit::lc_proof::check_method_id;
+use circuits_lib::bridge_circuit::storage_proof::verify_storage_proofs;
+use circuits_lib::bridge_circuit::structs::{
+    BridgeCircuitInput, ChallengeSendingWatchtowers, TotalWork,
+};
+use circuits_lib::bridge_circuit::{
+    deposit_constant, get_first_op_return_output, journal_hash, parse_op_return_data,
+    total_work_and_watchtower_flags,
+};
+use circuits_lib::common::zkvm::{ZkvmGuest, ZkvmHost};
+use circuits_lib::common::BitcoinNetwork;
+use citrea_sov_rollup_interface::zk::light_client_proof::output::LightClientCircuitOutput;
+
+use crate::mock_zkvm::MockZkvmHost;
+use crate::utils::panic_message;
+
+/// A check of the bridge circuit, in the order the circuit runs them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BridgeCircuitCheck {
+    HeaderChainMethodId,
+    WatchtowerChallenges,
+    TotalWork,
+    PayoutSpv,
+    LightClientProof,
+    LightClientBlockHash,
+    StorageProofs,
+    WithdrawalOutpoint,
+    OperatorPublicKey,
+    JournalHash,
+}
+
+impl fmt::Display for BridgeCircuitCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BridgeCircuitCheck::HeaderChainMethodId => "header chain method ID",
+            BridgeCircuitCheck::WatchtowerChallenges => "watchtower challenges",
+            BridgeCircuitCheck::TotalWork => "total work",
+            BridgeCircuitCheck::PayoutSpv => "payout SPV",
+            BridgeCircuitCheck::LightClientProof => "light client proof",
+            BridgeCircuitCheck::LightClientBlockHash => "light client L1 block hash",
+            BridgeCircuitCheck::StorageProofs => "storage proofs",
+            BridgeCircuitCheck::WithdrawalOutpoint => "withdrawal outpoint",
+            BridgeCircuitCheck::OperatorPublicKey => "operator public key",
+            BridgeCircuitCheck::JournalHash => "journal hash",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckStatus {
+    Passed,
+    Failed(String),
+    /// Not run because a check it depends on failed.
+    Skipped(BridgeCircuitCheck),
+}
+
+/// Result of a single check, with the values it compared or produced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckReport {
+    pub check: BridgeCircuitCheck,
+    pub status: CheckStatus,
+    pub details: Vec<(&'static str, String)>,
+}
+
+/// Results of all checks of the bridge circuit on an input.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BridgeCircuitReport {
+    pub checks: Vec<CheckReport>,
+    /// Journal the circuit would commit, if all checks passed.
+    pub journal: Option<Vec<u8>>,
+}
+
+impl BridgeCircuitReport {
+    /// Returns true if all checks passed.
+    pub fn passed(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|report| report.status == CheckStatus::Passed)
+    }
+
+    /// Returns the first failed check, which is the one the circuit panics on.
+    pub fn first_failure(&self) -> Option<&CheckReport> {
+        self.checks
+            .iter()
+            .find(|report| matches!(report.status, CheckStatus::Failed(_)))
+    }
+
+    pub fn get(&self, check: BridgeCircuitCheck) -> Option<&CheckReport> {
+        self.checks.iter().find(|report| report.check == check)
+    }
+
+    fn push(
+        &mut self,
+        check: BridgeCircuitCheck,
+        status: CheckStatus,
+        details: Vec<(&'static str, String)>,
+    ) {
+        self.checks.push(CheckReport {
+            check,
+            status,
+            details,
+        });
+    }
+
+    /// Records `check` as skipped if any of `dependencies` didn't pass, and
+    /// returns whether it was skipped.
+    fn skip_unless_passed(
+        &mut self,
+        check: BridgeCircuitCheck,
+        dependencies: &[BridgeCircuitCheck],
+    ) -> bool {
+        let failed = dependencies.iter().copied().find(|dependency| {
+            self.get(*dependency)
+                .is_none_or(|report| report.status != CheckStatus::Passed)
+        });
+        if let Some(dependency) = failed {
+            self.push(check, CheckStatus::Skipped(dependency), vec![]);
+            return true;
+        }
+        false
+    }
+}
+
+impl fmt::Display for BridgeCircuitReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for report in &self.checks {
+            match &report.status {
+                CheckStatus::Passed => write!(f, "[PASS] {}", report.check)?,
+                CheckStatus::Failed(reason) => write!(f, "[FAIL] {}: {reason}", report.check)?,
+                CheckStatus::Skipped(dependency) => {
+                    write!(f, "[SKIP] {}: {dependency} did not pass", report.check)?
+                }
+            }
+            for (name, value) in &report.details {
+                write!(f, "\n    {name}: {value}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs every check of the bridge circuit on `input` and reports the results.
+/// Checks whose inputs come from a failed check are skipped.
+///
+/// `network` selects the header chain method ID, the light client image ID
+/// and the work-only image ID, which the circuit fixes at compile time.
+pub fn diagnose_bridge_circuit(
+    input: &BridgeCircuitInput,
+    network: BitcoinNetwork,
+) -> BridgeCircuitReport {
+    let host = MockZkvmHost::new();
+    host.write(input);
+    let input: BridgeCircuitInput = host.read_from_host();
+
+    let mut report = BridgeCircuitReport::default();
+
+    // Header chain proof
+    let expected_method_id = network.header_chain_method_id();
+    report.push(
+        BridgeCircuitCheck::HeaderChainMethodId,
+        if input.hcp.method_id == expected_method_id {
+            CheckStatus::Passed
+        } else {
+            CheckStatus::Failed("Header chain output is for another method ID".to_string())
+        },
+        vec![
+            ("expected", format!("{expected_method_id:?}")),
+            ("actual", format!("{:?}", input.hcp.method_id)),
+        ],
+    );
+    host.verify(input.hcp.method_id, &input.hcp);
+
+    // Watchtower challenges and total work
+    let work_only_image_id = network.work_only_method_id();
+    let mut challenge_senders = ChallengeSendingWatchtowers([0u8; 20]);
+    match catch_panic(|| total_work_and_watchtower_flags(&input, &work_only_image_id)) {
+        Ok((max_total_work, senders)) => {
+            report.push(
+                BridgeCircuitCheck::WatchtowerChallenges,
+                CheckStatus::Passed,
+                vec![
+                    ("challenge txs", input.watchtower_inputs.len().to_string()),
+                    (
+                        "challenge senders",
+                        senders
+                            .0
+                            .iter()
+                            .map(|b| b.count_ones())
+                            .sum::<u32>()
+                            .to_string(),
+                    ),
+                ],
+            );
+            challenge_senders = senders;
+
+            let total_work = TotalWork(
+                input.hcp.chain_state.total_work[16..32]
+                    .try_into()
+                    .expect("Cannot fail: slicing 16 bytes from 32-byte array"),
+            );
+            report.push(
+                BridgeCircuitCheck::TotalWork,
+                if total_work < max_total_work {
+                    CheckStatus::Failed(
+                        "Header chain has less work than a watchtower challenge".to_string(),
+                    )
+                } else {
+                    CheckStatus::Passed
+                },
+                vec![
+                    ("header chain", hex::encode(total_work.0)),
+                    ("max watchtower", hex::encode(max_total_work.0)),
+                ],
+            );
+        }
+        Err(reason) => {
+            report.push(
+                BridgeCircuitCheck::WatchtowerChallenges,
+                CheckStatus::Failed(reason),
+                vec![],
+            );
+            report.skip_unless_passed(
+                BridgeCircuitCheck::TotalWork,
+                &[BridgeCircuitCheck::WatchtowerChallenges],
+            );
+        }
+    }
+
+    // Payout SPV
+    let payout_txid = input.payout_spv.transaction.compute_txid();
+    let spv_l1_block_hash = input.payout_spv.block_header.compute_block_hash();
+    report.push(
+        BridgeCircuitCheck::PayoutSpv,
+        if input
+            .payout_spv
+            .verify(input.hcp.chain_state.block_hashes_mmr.clone())
+        {
+            CheckStatus::Passed
+        } else {
+            CheckStatus::Failed("Payout tx is not in a block of the header chain".to_string())
+        },
+        vec![
+            ("payout txid", payout_txid.to_string()),
+            (
+                "block hash",
+                bitcoin::BlockHash::from_byte_array(spv_l1_block_hash).to_string(),
+            ),
+        ],
+    );
+
+    // Light client proof
+    let lc_image_id = network.light_client_image_id();
+    let lc_output = match borsh::from_slice::<LightClientCircuitOutput>(&input.lcp.lc_journal) {
+        Ok(output) => {
+            report.push(
+                BridgeCircuitCheck::LightClientProof,
+                if network == BitcoinNetwork::Regtest || check_method_id(&output, lc_image_id) {
+                    CheckStatus::Passed
+                } else {
+                    CheckStatus::Failed(
+                        "Light client proof method ID does not match the expected LC image ID"
+                            .to_string(),
+                    )
+                },
+                vec![
+                    ("l2 height", output.last_l2_height.to_string()),
+                    ("l2 state root", hex::encode(output.l2_state_root)),
+                ],
+            );
+            Some(output)
+        }
+        Err(e) => {
+            report.push(
+                BridgeCircuitCheck::LightClientProof,
+                CheckStatus::Failed(format!(
+                    "Failed to deserialize light client circuit output: {e}"
+                )),
+                vec![],
+            );
+            None
+        }
+    };
+
+    if !report.skip_unless_passed(
+        BridgeCircuitCheck::LightClientBlockHash,
+        &[BridgeCircuitCheck::LightClientProof],
+    ) {
+        let lc_l1_block_hash = lc_output
+            .as_ref()
+            .expect("Light client proof passed")
+            .latest_da_state
+            .block_hash;
+        report.push(
+            BridgeCircuitCheck::LightClientBlockHash,
+            if lc_l1_block_hash == spv_l1_block_hash {
+                CheckStatus::Passed
+            } else {
+                CheckStatus::Failed("Light client proof is not for the payout block".to_string())
+            },
+            vec![
+                (
+                    "light client",
+                    bitcoin::BlockHash::from_byte_array(lc_l1_block_hash).to_string(),
+                ),
+                (
+                    "payout",
+                    bitcoin::BlockHash::from_byte_array(spv_l1_block_hash).to_string(),
+                ),
+            ],
+        );
+    }
+
+    // Storage proofs
+    let mut storage_values = None;
+    if !report.skip_unless_passed(
+        BridgeCircuitCheck::StorageProofs,
+        &[BridgeCircuitCheck::LightClientProof],
+    ) {
+        let state_root = lc_output
+            .as_ref()
+            .expect("Light client proof passed")
+            .l2_state_root;
+        match catch_panic(|| verify_storage_proofs(&input.sp, state_root)) {
+            Ok((wd_outpoint_txid, vout, move_txid)) => {
+                let wd_txid = bitcoin::Txid::from_byte_array(*wd_outpoint_txid);
+                report.push(
+                    BridgeCircuitCheck::StorageProofs,
+                    CheckStatus::Passed,
+                    vec![
+                        ("deposit index", input.sp.index.to_string()),
+                        ("withdrawal outpoint", format!("{wd_txid}:{vout}")),
+                        (
+                            "move txid",
+                            bitcoin::Txid::from_byte_array(*move_txid).to_string(),
+                        ),
+                    ],
+                );
+                storage_values = Some((wd_txid, vout, *move_txid));
+            }
+            Err(reason) => report.push(
+                BridgeCircuitCheck::StorageProofs,
+                CheckStatus::Failed(reason),
+                vec![("deposit index", input.sp.index.to_string())],
+            ),
+        }
+    }
+
+    if !report.skip_unless_passed(
+        BridgeCircuitCheck::WithdrawalOutpoint,
+        &[BridgeCircuitCheck::StorageProofs],
+    ) {
+        let (wd_txid, vout, _) = storage_values.expect("Storage proofs passed");
+        let payout_input = input
+            .payout_spv
+            .transaction
+            .input
+            .get(input.payout_input_index as usize);
+        let status = match payout_input {
+            None => CheckStatus::Failed(format!(
+                "Payout input index {} is out of bounds",
+                input.payout_input_index
+            )),
+            Some(payout_input) if payout_input.previous_output.txid != wd_txid => {
+                CheckStatus::Failed("Invalid withdrawal transaction ID".to_string())
+            }
+            Some(payout_input) if payout_input.previous_output.vout != vout => {
+                CheckStatus::Failed("Invalid withdrawal transaction output index".to_string())
+            }
+            Some(_) => CheckStatus::Passed,
+        };
+        report.push(
+            BridgeCircuitCheck::WithdrawalOutpoint,
+            status,
+            vec![
+                ("expected", format!("{wd_txid}:{vout}")),
+                (
+                    "payout input",
+                    payout_input
+                        .map(|input| input.previous_output.to_string())
+                        .unwrap_or_default(),
+                ),
+            ],
+        );
+    }
+
+    // Operator public key in the payout tx
+    let operator_xonlypk: Option<[u8; 32]> =
+        get_first_op_return_output(&input.payout_spv.transaction)
+            .and_then(|output| parse_op_return_data(&output.script_pubkey))
+            .and_then(|data| data.try_into().ok());
+    match operator_xonlypk {
+        Some(pk) => report.push(
+            BridgeCircuitCheck::OperatorPublicKey,
+            CheckStatus::Passed,
+            vec![("operator", hex::encode(pk))],
+        ),
+        None => report.push(
+            BridgeCircuitCheck::OperatorPublicKey,
+            CheckStatus::Failed(
+                "Payout tx has no OP_RETURN output with a 32 byte x-only public key".to_string(),
+            ),
+            vec![],
+        ),
+    }
+
+    // Journal
+    if !report.skip_unless_passed(
+        BridgeCircuitCheck::JournalHash,
+        &[
+            BridgeCircuitCheck::HeaderChainMethodId,
+            BridgeCircuitCheck::TotalWork,
+            BridgeCircuitCheck::PayoutSpv,
+            BridgeCircuitCheck::LightClientBlockHash,
+            BridgeCircuitCheck::WithdrawalOutpoint,
+            BridgeCircuitCheck::OperatorPublicKey,
+        ],
+    ) {
+        let (_, _, move_txid) = storage_values.expect("Storage proofs passed");
+        match input.kickoff_tx.input.first() {
+            None => report.push(
+                BridgeCircuitCheck::JournalHash,
+                CheckStatus::Failed("Kickoff tx has no inputs".to_string()),
+                vec![],
+            ),
+            Some(round_input) => {
+                let deposit_constant = deposit_constant(
+                    operator_xonlypk.expect("Operator public key passed"),
+                    input.watchtower_challenge_connector_start_idx,
+                    &input.all_tweaked_watchtower_pubkeys,
+                    move_txid,
+                    round_input.previous_output.txid.to_byte_array(),
+                    round_input.previous_output.vout,
+                    input.hcp.genesis_state_hash,
+                );
+                let journal_hash = journal_hash(
+                    spv_l1_block_hash[12..32]
+                        .try_into()
+                        .expect("Cannot fail: slicing 20 bytes from 32-byte array"),
+                    input.hcp.chain_state.best_block_hash[12..32]
+                        .try_into()
+                        .expect("Cannot fail: slicing 20 bytes from 32-byte array"),
+                    challenge_senders,
+                    deposit_constant,
+                );
+                host.commit(journal_hash.as_bytes());
+                report.push(
+                    BridgeCircuitCheck::JournalHash,
+                    CheckStatus::Passed,
+                    vec![
+                        ("deposit constant", hex::encode(deposit_constant.0)),
+                        ("journal hash", journal_hash.to_hex().to_string()),
+                    ],
+                );
+                report.journal = Some(host.prove(&[0; 8]).journal);
+            }
+        }
+    }
+
+    report
+}
+
+fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    catch_unwind(AssertUnwindSafe(f)).map_err(|payload| panic_message(payload.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge_circuit_host::{execute_bridge_circuit_mock, MockExecutionError};
+    use crate::structs::{BridgeCircuitHostParams, SuccinctBridgeCircuitPublicInputs};
+    use circuits_lib::common::NETWORK;
+
+    fn load_input(filename: &str) -> (BridgeCircuitInput, BitcoinNetwork) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("bin-files")
+            .join(filename);
+        let params: BridgeCircuitHostParams = borsh::from_slice(&std::fs::read(path).unwrap())
+            .expect("Failed to deserialize BridgeCircuitHostParams");
+        let network = BitcoinNetwork::from_bitcoin_network(params.network.0).unwrap();
+        (params.into_bridge_circuit_input(), network)
+    }
+
+    #[test]
+    fn valid_input_passes_all_checks() {
+        let (input, network) = load_input("bch_params_varying_total_works.bin");
+
+        let report = diagnose_bridge_circuit(&input, network);
+
+        assert!(report.passed(), "{report}");
+        let expected_journal = SuccinctBridgeCircuitPublicInputs::new(input)
+            .unwrap()
+            .host_journal_hash();
+        assert_eq!(
+            report.journal.unwrap(),
+            expected_journal.as_bytes().to_vec()
+        );
+    }
+
+    #[test]
+    fn reports_every_failed_check() {
+        let (mut input, network) = load_input("bch_params_varying_total_works.bin");
+        input.hcp.method_id = [0; 8];
+        input.sp.index += 1;
+
+        let report = diagnose_bridge_circuit(&input, network);
+
+        assert!(!report.passed());
+        assert_eq!(
+            report.first_failure().map(|report| report.check),
+            Some(BridgeCircuitCheck::HeaderChainMethodId)
+        );
+        let status = |check| report.get(check).map(|report| report.status.clone());
+        assert!(matches!(
+            status(BridgeCircuitCheck::StorageProofs),
+            Some(CheckStatus::Failed(_))
+        ));
+        // Checks that don't depend on the failed ones still run
+        assert_eq!(
+            status(BridgeCircuitCheck::PayoutSpv),
+            Some(CheckStatus::Passed)
+        );
+        assert_eq!(
+            status(BridgeCircuitCheck::OperatorPublicKey),
+            Some(CheckStatus::Passed)
+        );
+        assert_eq!(
+            status(BridgeCircuitCheck::WithdrawalOutpoint),
+            Some(CheckStatus::Skipped(BridgeCircuitCheck::StorageProofs))
+        );
+        assert!(matches!(
+            status(BridgeCircuitCheck::JournalHash),
+            Some(CheckStatus::Skipped(_))
+        ));
+        assert!(report.journal.is_none());
+    }
+
+    /// Asserts that the diagnostics pass exactly when the circuit does, and
+    /// produce the same journal.
+    fn assert_agrees_with_mock_execution(input: &BridgeCircuitInput, network: BitcoinNetwork) {
+        let report = diagnose_bridge_circuit(input, network);
+        let execution = execute_bridge_circuit_mock(input, network);
+        assert_eq!(
+            report.passed(),
+            execution.is_ok(),
+            "{report}\nexecution: {execution:?}"
+        );
+        if let Ok(journal) = execution {
+            assert_eq!(report.journal, Some(journal));
+        }
+    }
+
+    /// The circuit constants are fixed at build time, so this only compares
+    /// the results if the crate is built for the fixtures' network
+    /// (`BITCOIN_NETWORK=regtest`).
+    #[test]
+    fn agrees_with_mock_execution() {
+        let mut fixtures = vec![
+            "bch_params_varying_total_works.bin",
+            "bch_params_varying_total_works_large_op_return.bin",
+            "bch_params_challenge_tx_with_annex.bin",
+            "bch_params_challenge_tx_with_large_output.bin",
+        ];
+        if cfg!(feature = "use-test-vk") {
+            fixtures.extend([
+                "bch_params_varying_total_works_first_two_valid.bin",
+                "bch_params_varying_total_works_insufficient_total_work.bin",
+            ]);
+        }
+
+        for fixture in fixtures {
+            let (input, network) = load_input(fixture);
+            if network != NETWORK {
+                assert!(matches!(
+                    execute_bridge_circuit_mock(&input, network),
+                    Err(MockExecutionError::NetworkMismatch { .. })
+                ));
+                eprintln!("Skipping {fixture}: built for {NETWORK}, fixture is for {network}");
+                continue;
+            }
+
+            assert_agrees_with_mock_execution(&input, network);
+
+            let mutations: [(&str, fn(&mut BridgeCircuitInput)); 6] = [
+                ("header chain method ID", |input| {
+                    input.hcp.method_id = [0; 8]
+                }),
+                ("deposit index", |input| input.sp.index += 1),
+                ("light client journal", |input| input.lcp.lc_journal.clear()),
+                ("payout input index", |input| {
+                    input.payout_input_index = u32::MAX
+                }),
+                ("payout outputs", |input| {
+                    input.payout_spv.transaction.output.clear()
+                }),
+                ("kickoff inputs", |input| input.kickoff_tx.input.clear()),
+            ];
+            for (name, mutate) in mutations {
+                let mut mutated = input.clone();
+                mutate(&mut mutated);
+                eprintln!("Checking {fixture} with a mutated {name}");
+                assert_agrees_with_mock_execution(&mutated, network);
+            }
+        }
+    }
+
+    #[cfg(feature = "use-test-vk")]
+    #[test]
+    fn reports_insufficient_total_work() {
+        let (input, network) =
+            load_input("bch_params_varying_total_works_insufficient_total_work.bin");
+
+        let report = diagnose_bridge_circuit(&input, network);
+
+        assert_eq!(
+            report.first_failure().map(|report| report.check),
+            Some(BridgeCircuitCheck::TotalWork)
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! # Native Header Chain Verifier
+//!
+//! Runs the header chain circuit's validation logic (proof of work,
+//! difficulty retargeting, median time past and the testnet4 rules) natively,
+//! without a zkVM. The resulting [`BlockHeaderCircuitOutput`] is the same one
+//! the circuit would commit, so it can be used to pre-check prover inputs and
+//! to cross-check published header chain proofs.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+
+use borsh::BorshDeserialize;
+use circuits_lib::common::BitcoinNetwork;
+use circuits_lib::header_chain::{
+    BlockHeaderCircuitOutput, ChainState, CircuitBlockHeader, HeaderChainPrevProofType,
+};
+use eyre::{eyre, Context, Result};
+use risc0_zkvm::Receipt;
+use thiserror::Error;
+
+use crate::utils::panic_message;
+
+/// Size of a serialized block header.
+pub const BLOCK_HEADER_SIZE: usize = 80;
+
+/// Error returned when a block header fails validation.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Block header at height {height} is invalid: {reason}")]
+pub struct HeaderChainVerificationError {
+    /// Height of the first invalid header.
+    pub height: u32,
+    /// Reason reported by the header chain logic.
+    pub reason: String,
+}
+
+/// A field of a [`BlockHeaderCircuitOutput`] that differs between two outputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputMismatch {
+    pub field: &'static str,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// Parses block headers serialized back to back, as in the header files used
+/// by the header chain prover.
+pub fn parse_block_headers(bytes: &[u8]) -> Result<Vec<CircuitBlockHeader>> {
+    if bytes.len() % BLOCK_HEADER_SIZE != 0 {
+        return Err(eyre!(
+            "Header data length {} is not a multiple of {BLOCK_HEADER_SIZE}",
+            bytes.len()
+        ));
+    }
+
+    bytes
+        .chunks_exact(BLOCK_HEADER_SIZE)
+        .enumerate()
+        .map(|(i, header)| {
+            CircuitBlockHeader::try_from_slice(header)
+                .wrap_err(format!("Failed to parse block header {i}"))
+        })
+        .collect()
+}
+
+/// Reads a file of block headers serialized back to back.
+pub fn read_block_headers(path: &Path) -> Result<Vec<CircuitBlockHeader>> {
+    let bytes = std::fs::read(path).wrap_err(format!("Failed to read {}", path.display()))?;
+    parse_block_headers(&bytes)
+}
+
+/// Reads a header chain circuit output from a file containing either a
+/// borsh-serialized header chain proof [`Receipt`] or a borsh-serialized
+/// [`BlockHeaderCircuitOutput`].
+pub fn read_circuit_output(path: &Path) -> Result<BlockHeaderCircuitOutput> {
+    let bytes = std::fs::read(path).wrap_err(format!("Failed to read {}", path.display()))?;
+
+    if let Ok(receipt) = borsh::from_slice::<Receipt>(&bytes) {
+        return borsh::from_slice(&receipt.journal.bytes).wrap_err(format!(
+            "Journal of the receipt in {} is not a header chain output",
+            path.display()
+        ));
+    }
+
+    borsh::from_slice(&bytes).wrap_err(format!(
+        "{} is neither a header chain receipt nor a header chain output",
+        path.display()
+    ))
+}
+
+/// Applies `block_headers` on top of `prev_proof` natively, producing the
+/// output the header chain circuit would commit for the same input.
+///
+/// The previous proof is not verified, only its chain state is used.
+pub fn verify_header_chain(
+    prev_proof: HeaderChainPrevProofType,
+    block_headers: Vec<CircuitBlockHeader>,
+    network: BitcoinNetwork,
+) -> Result<BlockHeaderCircuitOutput, HeaderChainVerificationError> {
+    let (genesis_state_hash, mut chain_state) = match prev_proof {
+        HeaderChainPrevProofType::GenesisBlock(genesis_state) => {
+            (genesis_state.to_hash(), genesis_state)
+        }
+        HeaderChainPrevProofType::PrevProof(prev_output) => {
+            (prev_output.genesis_state_hash, prev_output.chain_state)
+        }
+    };
+
+    apply_block_headers(&mut chain_state, block_headers, network)?;
+
+    Ok(BlockHeaderCircuitOutput {
+        method_id: network.header_chain_method_id(),
+        genesis_state_hash,
+        chain_state,
+    })
+}
+
+/// Applies `block_headers` to `chain_state`, turning the panics of the
+/// circuit logic into an error carrying the height of the offending header.
+pub fn apply_block_headers(
+    chain_state: &mut ChainState,
+    block_headers: Vec<CircuitBlockHeader>,
+    network: BitcoinNetwork,
+) -> Result<(), HeaderChainVerificationError> {
+    let mut state = chain_state.clone();
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        state.apply_block_headers_for_network(block_headers, network)
+    }));
+
+    match result {
+        Ok(()) => {
+            *chain_state = state;
+            Ok(())
+        }
+        Err(payload) => {
+            let reason = panic_message(payload.as_ref());
+            // The height is incremented before a header is validated, so it
+            // points at the header that failed.
+            Err(HeaderChainVerificationError {
+                height: state.block_height,
+                reason,
+            })
+        }
+    }
+}
+
+/// Compares two header chain outputs field by field. Returns an empty vector
+/// if they are equal.
+pub fn diff_circuit_outputs(
+    expected: &BlockHeaderCircuitOutput,
+    actual: &BlockHeaderCircuitOutput,
+) -> Vec<OutputMismatch> {
+    let mut mismatches = Vec::new();
+    let mut compare = |field: &'static str, expected: String, actual: String| {
+        if expected != actual {
+            mismatches.push(OutputMismatch {
+                field,
+                expected,
+                actual,
+            });
+        }
+    };
+
+    let (e, a) = (&expected.chain_state, &actual.chain_state);
+    compare(
+        "method_id",
+        format!("{:?}", expected.method_id),
+        format!("{:?}", actual.method_id),
+    );
+    compare(
+        "genesis_state_hash",
+        hex::encode(expected.genesis_state_hash),
+        hex::encode(actual.genesis_state_hash),
+    );
+    compare(
+        "chain_state_hash",
+        hex::encode(e.to_hash()),
+        hex::encode(a.to_hash()),
+    );
+    compare(
+        "block_height",
+        e.block_height.to_string(),
+        a.block_height.to_string(),
+    );
+    compare(
+        "total_work",
+        hex::encode(e.total_work),
+        hex::encode(a.total_work),
+    );
+    compare(
+        "best_block_hash",
+        hex::encode(e.best_block_hash),
+        hex::encode(a.best_block_hash),
+    );
+    compare(
+        "current_target_bits",
+        format!("{:#010x}", e.current_target_bits),
+        format!("{:#010x}", a.current_target_bits),
+    );
+    compare(
+        "epoch_start_time",
+        e.epoch_start_time.to_string(),
+        a.epoch_start_time.to_string(),
+    );
+    compare(
+        "prev_11_timestamps",
+        format!("{:?}", e.prev_11_timestamps),
+        format!("{:?}", a.prev_11_timestamps),
+    );
+    compare(
+        "block_hashes_mmr",
+        format!("{:?}", e.block_hashes_mmr),
+        format!("{:?}", a.block_hashes_mmr),
+    );
+
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_zkvm::MockZkvmHost;
+    use circuits_lib::{
+        common::zkvm::ZkvmHost,
+        header_chain::{header_chain_circuit, HeaderChainCircuitInput},
+    };
+
+    const MAINNET_HEADERS: &[u8] = include_bytes!("../bin-files/mainnet-headers.bin");
+
+    #[test]
+    fn matches_header_chain_circuit() {
+        let headers = parse_block_headers(MAINNET_HEADERS).unwrap();
+
+        let host = MockZkvmHost::new();
+        let method_id = BitcoinNetwork::Mainnet.header_chain_method_id();
+        host.write(&HeaderChainCircuitInput {
+            method_id,
+            prev_proof: HeaderChainPrevProofType::GenesisBlock(ChainState::genesis_state()),
+            block_headers: headers[..50].to_vec(),
+        });
+        header_chain_circuit(&host);
+        let proof = host.prove(&method_id);
+        let circuit_output: BlockHeaderCircuitOutput = borsh::from_slice(&proof.journal).unwrap();
+
+        let first = verify_header_chain(
+            HeaderChainPrevProofType::GenesisBlock(ChainState::genesis_state()),
+            headers[..20].to_vec(),
+            BitcoinNetwork::Mainnet,
+        )
+        .unwrap();
+        let native_output = verify_header_chain(
+            HeaderChainPrevProofType::PrevProof(first),
+            headers[20..50].to_vec(),
+            BitcoinNetwork::Mainnet,
+        )
+        .unwrap();
+
+        assert!(diff_circuit_outputs(&circuit_output, &native_output).is_empty());
+
+        let shorter = verify_header_chain(
+            HeaderChainPrevProofType::GenesisBlock(ChainState::genesis_state()),
+            headers[..49].to_vec(),
+            BitcoinNetwork::Mainnet,
+        )
+        .unwrap();
+        let fields = diff_circuit_outputs(&circuit_output, &shorter)
+            .into_iter()
+            .map(|mismatch| mismatch.field)
+            .collect::<Vec<_>>();
+        assert!(fields.contains(&"block_height"));
+        assert!(fields.contains(&"chain_state_hash"));
+    }
+
+    #[test]
+    fn reports_invalid_header_height() {
+        let mut headers = parse_block_headers(MAINNET_HEADERS).unwrap()[..10].to_vec();
+        headers[7].nonce = headers[7].nonce.wrapping_add(1);
+
+        let mut chain_state = ChainState::genesis_state();
+        let err =
+            apply_block_headers(&mut chain_state, headers, BitcoinNetwork::Mainnet).unwrap_err();
+
+        assert_eq!(err.height, 7);
+        // The chain state is left untouched on failure.
+        assert_eq!(chain_state, ChainState::genesis_state());
+
+        assert!(parse_block_headers(&MAINNET_HEADERS[..81]).is_err());
+    }
+}
@@ -2,11 +2,14 @@ use eyre::bail;
 use risc0_zkvm::{InnerReceipt, Receipt};
 
 pub mod bridge_circuit_host;
+pub mod diagnostics;
 pub mod docker;
+pub mod header_chain_verifier;
 pub mod mock_zkvm;
 mod seal_format;
 pub mod structs;
 pub mod utils;
+pub mod watchtower_challenge;
 
 /// Converts an `InnerReceipt` into a `Receipt`, ensuring all required fields are present.
 ///
@@ -1,7 +1,7 @@
 use alloy_rpc_types::EIP1186StorageProof;
 use ark_bn254::Bn254;
 use ark_ff::PrimeField;
-use bitcoin::{hashes::Hash, Network, Transaction, Txid, XOnlyPublicKey};
+use bitcoin::{hashes::Hash, Network, OutPoint, Transaction, Txid, XOnlyPublicKey};
 use borsh::{BorshDeserialize, BorshSerialize};
 use circuits_lib::{
     bridge_circuit::{
@@ -180,30 +180,18 @@ impl BridgeCircuitHostParams {
         watchtower_contexts: &[WatchtowerContext],
         watchtower_challenge_connector_start_idx: u32,
     ) -> Result<Self, BridgeCircuitHostParamsError> {
-        let watchtower_inputs = get_wt_inputs(
-            kickoff_tx.compute_txid(),
-            watchtower_contexts,
-            watchtower_challenge_connector_start_idx,
-        )?;
-
         let block_header_circuit_output: BlockHeaderCircuitOutput =
             borsh::from_slice(&headerchain_receipt.journal.bytes)
                 .map_err(|_| BridgeCircuitHostParamsError::InvalidHeaderchainReceipt)?;
 
-        let all_tweaked_watchtower_pubkeys =
-            get_all_pubkeys(&kickoff_tx, watchtower_challenge_connector_start_idx)?;
-
-        let storage_proof_utxo: EIP1186StorageProof =
-            serde_json::from_str(&storage_proof.storage_proof_utxo).map_err(|e| {
-                BridgeCircuitHostParamsError::StorageProofDeserializationError(e.to_string())
-            })?;
-
-        let wd_txid_bytes: [u8; 32] = storage_proof_utxo.value.to_be_bytes();
-
-        let wd_txid: Txid = bitcoin::consensus::deserialize(&wd_txid_bytes)
-            .map_err(|_| BridgeCircuitHostParamsError::InvalidStorageProof)?;
-
-        let payout_input_index = get_payout_input_index(wd_txid, &spv.transaction.0)?;
+        let (watchtower_inputs, all_tweaked_watchtower_pubkeys, payout_input_index) =
+            derive_wt_params(
+                &kickoff_tx,
+                &spv,
+                &storage_proof,
+                watchtower_contexts,
+                watchtower_challenge_connector_start_idx,
+            )?;
 
         let all_tweaked_watchtower_pubkeys: Vec<CircuitXOnlyPublicKey> =
             all_tweaked_watchtower_pubkeys
@@ -270,6 +258,115 @@ impl BridgeCircuitHostParams {
     }
 }
 
+/// Creates a bridge circuit input from watchtower transactions, the same way
+/// [`BridgeCircuitHostParams::new_with_wt_tx`] does, but without the header
+/// chain and light client proof receipts that are only needed for proving.
+///
+/// This allows executing the circuit on inputs collected from chain, e.g. with
+/// a natively computed header chain output.
+///
+/// # Errors
+///
+/// Returns the same errors as [`BridgeCircuitHostParams::new_with_wt_tx`],
+/// except for the ones related to the header chain receipt.
+pub fn bridge_circuit_input_from_wt_tx(
+    kickoff_tx: Transaction,
+    spv: SPV,
+    block_header_circuit_output: BlockHeaderCircuitOutput,
+    light_client_proof: LightClientProof,
+    storage_proof: StorageProof,
+    watchtower_contexts: &[WatchtowerContext],
+    watchtower_challenge_connector_start_idx: u32,
+) -> Result<BridgeCircuitInput, BridgeCircuitHostParamsError> {
+    let (watchtower_inputs, all_tweaked_watchtower_pubkeys, payout_input_index) = derive_wt_params(
+        &kickoff_tx,
+        &spv,
+        &storage_proof,
+        watchtower_contexts,
+        watchtower_challenge_connector_start_idx,
+    )?;
+
+    Ok(BridgeCircuitInput::new(
+        kickoff_tx,
+        watchtower_inputs,
+        all_tweaked_watchtower_pubkeys
+            .iter()
+            .map(XOnlyPublicKey::serialize)
+            .collect(),
+        block_header_circuit_output,
+        spv,
+        payout_input_index,
+        light_client_proof,
+        storage_proof,
+        watchtower_challenge_connector_start_idx,
+    ))
+}
+
+/// Derives the watchtower inputs, the tweaked watchtower public keys and the
+/// payout input index shared by the constructors that take watchtower
+/// transactions.
+fn derive_wt_params(
+    kickoff_tx: &Transaction,
+    spv: &SPV,
+    storage_proof: &StorageProof,
+    watchtower_contexts: &[WatchtowerContext],
+    watchtower_challenge_connector_start_idx: u32,
+) -> Result<(Vec<WatchtowerInput>, Vec<XOnlyPublicKey>, u32), BridgeCircuitHostParamsError> {
+    let watchtower_inputs = get_wt_inputs(
+        kickoff_tx.compute_txid(),
+        watchtower_contexts,
+        watchtower_challenge_connector_start_idx,
+    )?;
+
+    let all_tweaked_watchtower_pubkeys =
+        get_all_pubkeys(kickoff_tx, watchtower_challenge_connector_start_idx)?;
+
+    let wd_outpoint = withdrawal_outpoint(storage_proof)?;
+
+    let payout_input_index = get_payout_input_index(wd_outpoint.txid, &spv.transaction.0)?;
+
+    Ok((
+        watchtower_inputs,
+        all_tweaked_watchtower_pubkeys,
+        payout_input_index,
+    ))
+}
+
+/// Decodes the withdrawal outpoint stored in the bridge contract from a
+/// storage proof, as the bridge circuit does.
+///
+/// # Errors
+///
+/// Returns an error if the UTXO or vout storage proofs can't be deserialized
+/// or don't hold a valid value.
+pub fn withdrawal_outpoint(
+    storage_proof: &StorageProof,
+) -> Result<OutPoint, BridgeCircuitHostParamsError> {
+    let deserialize = |proof: &str| -> Result<EIP1186StorageProof, BridgeCircuitHostParamsError> {
+        serde_json::from_str(proof).map_err(|e| {
+            BridgeCircuitHostParamsError::StorageProofDeserializationError(e.to_string())
+        })
+    };
+    let storage_proof_utxo = deserialize(&storage_proof.storage_proof_utxo)?;
+    let storage_proof_vout = deserialize(&storage_proof.storage_proof_vout)?;
+
+    let wd_txid_bytes: [u8; 32] = storage_proof_utxo.value.to_be_bytes();
+
+    let txid: Txid = bitcoin::consensus::deserialize(&wd_txid_bytes)
+        .map_err(|_| BridgeCircuitHostParamsError::InvalidStorageProof)?;
+
+    // The contract stores the vout as 4 little endian bytes at the end of the
+    // storage slot.
+    let vout_bytes: [u8; 32] = storage_proof_vout.value.to_be_bytes();
+    let vout = u32::from_le_bytes(
+        vout_bytes[28..32]
+            .try_into()
+            .map_err(|_| BridgeCircuitHostParamsError::InvalidStorageProof)?,
+    );
+
+    Ok(OutPoint { txid, vout })
+}
+
 /// Finds the index of the payout input in the payout transaction based on the withdrawal transaction ID.
 ///
 /// # Arguments
@@ -519,7 +616,7 @@ fn host_deposit_constant(
 ///
 /// This struct contains all the inputs needed to verify a bridge circuit proof
 /// in the BitVM2, including block hashes, watchtower data, and method IDs.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, BorshSerialize, BorshDeserialize)]
 pub struct BridgeCircuitBitvmInputs {
     pub payout_tx_block_hash: [u8; 20],
     pub latest_block_hash: [u8; 20],
@@ -266,6 +266,15 @@ pub fn get_verifying_key() -> ark_groth16::VerifyingKey<Bn254> {
     }
 }
 
+/// Returns the message of a panic caught with [`std::panic::catch_unwind`].
+pub fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+        .unwrap_or_else(|| "Unknown panic".to_string())
+}
+
 /// Sha256(control_root, pre_state_digest, post_state_digest, id_bn254_fr)
 pub fn calculate_succinct_output_prefix(method_id: &[u8]) -> [u8; 32] {
     let succinct_verifier_params = SuccinctReceiptVerifierParameters::default();
@@ -0,0 +1,442 @@
+//! # Watchtower Challenge Inspection
+//!
+//! Decodes a watchtower challenge transaction the way the bridge circuit does
+//! in [`verify_watchtower_challenges`] and [`total_work_and_watchtower_flags`]
+//! and reports whether the circuit counts the watchtower as a challenge sender
+//! and which total work it would take from the challenge.
+//!
+//! The checks are repeated here instead of being shared with the circuit, as
+//! changing the circuit code would change its method ID. They must be kept in
+//! sync with the circuit, which the tests cross-check.
+//!
+//! [`verify_watchtower_challenges`]: circuits_lib::bridge_circuit::verify_watchtower_challenges
+//! [`total_work_and_watchtower_flags`]: circuits_lib::bridge_circuit::total_work_and_watchtower_flags
+
+use std::borrow::BorrowMut;
+use std::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use bitcoin::hashes::Hash;
+use bitcoin::secp256k1::{schnorr, Message, Secp256k1, XOnlyPublicKey};
+use bitcoin::sighash::{Prevouts, SighashCache};
+use bitcoin::{TapSighash, TapSighashType, Transaction, TxOut, Txid};
+use circuits_lib::bridge_circuit::groth16::CircuitGroth16Proof;
+use circuits_lib::bridge_circuit::groth16_verifier::CircuitGroth16WithTotalWork;
+use circuits_lib::bridge_circuit::structs::{
+    WatchTowerChallengeTxCommitment, WatchtowerInput, WorkOnlyCircuitOutput,
+};
+use circuits_lib::bridge_circuit::{
+    parse_op_return_data, taproot_encode_signing_data_to_with_annex_digest,
+};
+use circuits_lib::common::BitcoinNetwork;
+
+use crate::structs::{get_all_pubkeys, BridgeCircuitHostParamsError, WatchtowerContext};
+use crate::utils::panic_message;
+
+/// Groth16 proof and work-only circuit output committed in the outputs of a
+/// watchtower challenge.
+#[derive(Debug, Clone)]
+pub struct ChallengeCommitment {
+    pub g16_proof: CircuitGroth16Proof,
+    pub work_only_output: WorkOnlyCircuitOutput,
+    /// Whether the proof verifies against the work-only circuit of the
+    /// network.
+    pub proof_valid: bool,
+}
+
+impl ChallengeCommitment {
+    pub fn total_work(&self) -> u128 {
+        u128::from_be_bytes(self.work_only_output.work_u128)
+    }
+}
+
+/// What the bridge circuit sees in a watchtower challenge transaction.
+#[derive(Debug, Clone)]
+pub struct WatchtowerChallengeInspection {
+    pub txid: Txid,
+    pub watchtower_idx: u32,
+    /// Reason the circuit rejects the challenge. The circuit panics on such
+    /// challenges, so no proof can be generated with them.
+    pub rejection: Option<String>,
+    /// `Err` holds the reason the committed proof couldn't be decoded.
+    pub commitment: Result<ChallengeCommitment, String>,
+}
+
+impl WatchtowerChallengeInspection {
+    /// Returns true if the circuit marks the watchtower as a challenge sender.
+    pub fn is_challenge_sender(&self) -> bool {
+        self.rejection.is_none()
+    }
+
+    /// Total work the circuit compares against the header chain proof, or
+    /// `None` if the challenge is ignored when computing the maximum work.
+    pub fn verified_total_work(&self) -> Option<u128> {
+        match &self.commitment {
+            Ok(commitment) if self.is_challenge_sender() && commitment.proof_valid => {
+                Some(commitment.total_work())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for WatchtowerChallengeInspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Watchtower challenge: {}", self.txid)?;
+        writeln!(f, "Watchtower index: {}", self.watchtower_idx)?;
+        match &self.rejection {
+            None => writeln!(f, "Challenge sender: yes")?,
+            Some(reason) => writeln!(f, "Challenge sender: no ({reason})")?,
+        }
+        match &self.commitment {
+            Ok(commitment) => {
+                writeln!(f, "Committed total work: {}", commitment.total_work())?;
+                writeln!(
+                    f,
+                    "Committed genesis state hash: {}",
+                    hex::encode(commitment.work_only_output.genesis_state_hash)
+                )?;
+                writeln!(f, "Groth16 proof valid: {}", commitment.proof_valid)?;
+            }
+            Err(reason) => writeln!(f, "Commitment: {reason}")?,
+        }
+        match self.verified_total_work() {
+            Some(total_work) => write!(f, "Total work seen by the bridge circuit: {total_work}"),
+            None => write!(f, "Total work seen by the bridge circuit: none"),
+        }
+    }
+}
+
+/// Inspects a watchtower challenge transaction of `kickoff_tx`.
+///
+/// `genesis_state_hash` is the genesis state hash of the header chain proof
+/// given to the bridge circuit, which the committed proof must be for.
+///
+/// # Errors
+///
+/// Returns an error if the transaction doesn't spend a watchtower challenge
+/// connector of the kickoff, or the kickoff outputs can't be parsed.
+pub fn inspect_watchtower_challenge(
+    kickoff_tx: &Transaction,
+    context: &WatchtowerContext,
+    watchtower_challenge_connector_start_idx: u32,
+    genesis_state_hash: [u8; 32],
+    network: BitcoinNetwork,
+) -> Result<WatchtowerChallengeInspection, BridgeCircuitHostParamsError> {
+    let kickoff_txid = kickoff_tx.compute_txid();
+    let watchtower_input = WatchtowerInput::from_txs(
+        kickoff_txid,
+        context.watchtower_tx.clone(),
+        &context.prevout_txs,
+        watchtower_challenge_connector_start_idx,
+    )
+    .map_err(|_| BridgeCircuitHostParamsError::InvalidWatchtowerInputs)?;
+    let all_tweaked_watchtower_pubkeys: Vec<[u8; 32]> =
+        get_all_pubkeys(kickoff_tx, watchtower_challenge_connector_start_idx)?
+            .iter()
+            .map(|pubkey| pubkey.serialize())
+            .collect();
+
+    Ok(inspect_watchtower_input(
+        &watchtower_input,
+        kickoff_txid,
+        &all_tweaked_watchtower_pubkeys,
+        watchtower_challenge_connector_start_idx,
+        genesis_state_hash,
+        network,
+    ))
+}
+
+/// Inspects a watchtower input of a bridge circuit input. See
+/// [`inspect_watchtower_challenge`].
+pub fn inspect_watchtower_input(
+    watchtower_input: &WatchtowerInput,
+    kickoff_txid: Txid,
+    all_tweaked_watchtower_pubkeys: &[[u8; 32]],
+    watchtower_challenge_connector_start_idx: u32,
+    genesis_state_hash: [u8; 32],
+    network: BitcoinNetwork,
+) -> WatchtowerChallengeInspection {
+    // The sighash computation panics on malformed prevouts, as in the circuit.
+    let rejection = catch_unwind(AssertUnwindSafe(|| {
+        check_watchtower_challenge(
+            watchtower_input,
+            kickoff_txid,
+            all_tweaked_watchtower_pubkeys,
+            watchtower_challenge_connector_start_idx,
+        )
+    }))
+    .unwrap_or_else(|payload| Err(panic_message(payload.as_ref())))
+    .err();
+
+    let commitment = challenge_commitment(&watchtower_input.watchtower_challenge_tx.output)
+        .ok_or_else(|| "Outputs don't match any watchtower challenge format".to_string())
+        .and_then(|commitment| {
+            let g16_proof = CircuitGroth16Proof::from_compressed(&commitment.compressed_g16_proof)
+                .map_err(|e| format!("Failed to decompress Groth16 proof: {e}"))?;
+            let proof_valid = CircuitGroth16WithTotalWork::new(
+                g16_proof,
+                commitment.total_work,
+                genesis_state_hash,
+            )
+            .verify(&network.work_only_method_id());
+            Ok(ChallengeCommitment {
+                g16_proof,
+                work_only_output: WorkOnlyCircuitOutput {
+                    work_u128: commitment.total_work,
+                    genesis_state_hash,
+                },
+                proof_valid,
+            })
+        });
+
+    WatchtowerChallengeInspection {
+        txid: watchtower_input.watchtower_challenge_tx.compute_txid(),
+        watchtower_idx: watchtower_input.watchtower_idx,
+        rejection,
+        commitment,
+    }
+}
+
+/// Runs the checks [`verify_watchtower_challenges`] makes on a watchtower
+/// challenge, returning the message the circuit panics with if one fails.
+///
+/// [`verify_watchtower_challenges`]: circuits_lib::bridge_circuit::verify_watchtower_challenges
+fn check_watchtower_challenge(
+    watchtower_input: &WatchtowerInput,
+    kickoff_txid: Txid,
+    all_tweaked_watchtower_pubkeys: &[[u8; 32]],
+    watchtower_challenge_connector_start_idx: u32,
+) -> Result<(), String> {
+    let watchtower_idx = watchtower_input.watchtower_idx;
+    let reject = |reason: &str| Err(format!("{reason}, watchtower index: {watchtower_idx}"));
+
+    let inner_txouts: Vec<TxOut> = watchtower_input
+        .watchtower_challenge_utxos
+        .iter()
+        .map(|utxo| utxo.0.clone())
+        .collect();
+    let prevouts = Prevouts::All(&inner_txouts);
+
+    let watchtower_input_idx = watchtower_input.watchtower_challenge_input_idx as usize;
+    let Some(input) = watchtower_input
+        .watchtower_challenge_tx
+        .input
+        .get(watchtower_input_idx)
+    else {
+        return reject("Invalid watchtower challenge input index");
+    };
+
+    if watchtower_input.watchtower_challenge_witness.0.len() != 1 {
+        return reject("Invalid witness length, expected 1 element");
+    }
+    let signature = watchtower_input.watchtower_challenge_witness.0.to_vec()[0].clone();
+    let sighash_type = match signature.len() {
+        64 => TapSighashType::Default,
+        65 => match TapSighashType::from_consensus_u8(signature[64]) {
+            Ok(sighash_type) => sighash_type,
+            Err(_) => return reject("Invalid sighash type"),
+        },
+        _ => return reject("Invalid witness length, expected 64 or 65 bytes"),
+    };
+
+    let mut enc = TapSighash::engine();
+    let mut sighash_cache = SighashCache::new(&watchtower_input.watchtower_challenge_tx);
+    taproot_encode_signing_data_to_with_annex_digest::<_, TxOut, &Transaction>(
+        sighash_cache.borrow_mut(),
+        enc.borrow_mut(),
+        watchtower_input_idx,
+        &prevouts,
+        watchtower_input.annex_digest,
+        None,
+        sighash_type,
+    );
+    let sighash = TapSighash::from_engine(enc);
+
+    if input.previous_output.txid != kickoff_txid {
+        return Err(format!(
+            "Invalid input: expected input to reference an output from the kickoff transaction (txid: {}), but got txid: {}, vout: {}, watchtower index: {}",
+            kickoff_txid, input.previous_output.txid, input.previous_output.vout, watchtower_idx
+        ));
+    }
+
+    let Some(output) = inner_txouts.get(watchtower_input_idx) else {
+        return reject("Invalid watchtower challenge input index");
+    };
+    if !output.script_pubkey.is_p2tr() {
+        return reject("Invalid output script type - kickoff");
+    }
+
+    let Some(expected_pubkey) = all_tweaked_watchtower_pubkeys.get(watchtower_idx as usize) else {
+        return Err(format!(
+            "Invalid watchtower index, watchtower index: {}, number of watchtowers: {}",
+            watchtower_idx,
+            all_tweaked_watchtower_pubkeys.len()
+        ));
+    };
+    let pubkey = &output.script_pubkey.as_bytes()[2..34];
+    if expected_pubkey.as_slice() != pubkey {
+        return reject("Invalid watchtower public key");
+    }
+
+    let vout = watchtower_idx
+        .checked_mul(2)
+        .and_then(|x| x.checked_add(watchtower_challenge_connector_start_idx))
+        .expect("Overflow occurred while calculating vout");
+    if vout != input.previous_output.vout {
+        return reject("Invalid output index");
+    }
+
+    let Ok(verifying_key) = XOnlyPublicKey::from_slice(pubkey) else {
+        return reject("Invalid verifying key");
+    };
+    let Ok(signature) = schnorr::Signature::from_slice(&signature[0..64]) else {
+        return reject("Invalid signature");
+    };
+    if Secp256k1::verification_only()
+        .verify_schnorr(
+            &signature,
+            &Message::from_digest(sighash.to_byte_array()),
+            &verifying_key,
+        )
+        .is_err()
+    {
+        return Err(format!(
+            "Invalid signature for watchtower challenge with watchtower index: {watchtower_idx}, should not happen"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Extracts the compressed Groth16 proof and total work committed in the
+/// outputs of a watchtower challenge, accepting the same output formats as
+/// [`total_work_and_watchtower_flags`].
+///
+/// [`total_work_and_watchtower_flags`]: circuits_lib::bridge_circuit::total_work_and_watchtower_flags
+fn challenge_commitment(outputs: &[TxOut]) -> Option<WatchTowerChallengeTxCommitment> {
+    match outputs {
+        // Single OP_RETURN output with 144 bytes
+        [op_return_output, ..] if op_return_output.script_pubkey.is_op_return() => {
+            let whole_output: [u8; 144] = parse_op_return_data(&op_return_output.script_pubkey)?
+                .try_into()
+                .ok()?;
+            Some(WatchTowerChallengeTxCommitment {
+                compressed_g16_proof: whole_output[0..128]
+                    .try_into()
+                    .expect("Cannot fail: slicing 128 bytes from 144-byte array"),
+                total_work: whole_output[128..144]
+                    .try_into()
+                    .expect("Cannot fail: slicing 16 bytes from 144-byte array"),
+            })
+        }
+        // Two P2TR outputs with the first 64 bytes of the proof, followed by an
+        // 80-byte OP_RETURN output with the rest of the proof and the total work
+        [out1, out2, out3, ..]
+            if out1.script_pubkey.is_p2tr()
+                && out2.script_pubkey.is_p2tr()
+                && out3.script_pubkey.is_op_return() =>
+        {
+            let third_output: [u8; 80] =
+                parse_op_return_data(&out3.script_pubkey)?.try_into().ok()?;
+            Some(WatchTowerChallengeTxCommitment {
+                compressed_g16_proof: [
+                    &out1.script_pubkey.as_bytes()[2..],
+                    &out2.script_pubkey.as_bytes()[2..],
+                    &third_output[0..64],
+                ]
+                .concat()
+                .try_into()
+                .expect("Cannot fail: concatenating and converting to 128-byte array"),
+                total_work: third_output[64..]
+                    .try_into()
+                    .expect("Cannot fail: slicing 16 bytes from 80-byte array"),
+            })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::BridgeCircuitHostParams;
+    use circuits_lib::bridge_circuit::structs::CircuitWitness;
+    use circuits_lib::bridge_circuit::{
+        total_work_and_watchtower_flags, verify_watchtower_challenges,
+    };
+
+    #[test]
+    fn inspection_matches_circuit_total_work() {
+        let params: BridgeCircuitHostParams = borsh::from_slice(include_bytes!(
+            "../bin-files/bch_params_varying_total_works.bin"
+        ))
+        .expect("Failed to deserialize BridgeCircuitHostParams");
+        let network = BitcoinNetwork::from_bitcoin_network(params.network.0).unwrap();
+        let input = params.into_bridge_circuit_input();
+        let kickoff_txid = input.kickoff_tx.compute_txid();
+
+        let inspections: Vec<_> = input
+            .watchtower_inputs
+            .iter()
+            .map(|watchtower_input| {
+                inspect_watchtower_input(
+                    watchtower_input,
+                    kickoff_txid,
+                    &input.all_tweaked_watchtower_pubkeys,
+                    input.watchtower_challenge_connector_start_idx,
+                    input.hcp.genesis_state_hash,
+                    network,
+                )
+            })
+            .collect();
+        assert!(!inspections.is_empty());
+        assert!(inspections.iter().all(|i| i.is_challenge_sender()));
+        assert!(inspections.iter().all(|i| i.commitment.is_ok()));
+
+        let (total_work, _) =
+            total_work_and_watchtower_flags(&input, &network.work_only_method_id());
+        let max_verified = inspections
+            .iter()
+            .filter_map(|i| i.verified_total_work())
+            .max()
+            .unwrap_or(0);
+        assert_eq!(max_verified, u128::from_be_bytes(total_work.0));
+    }
+
+    #[test]
+    fn rejection_matches_circuit_panic() {
+        let params: BridgeCircuitHostParams = borsh::from_slice(include_bytes!(
+            "../bin-files/bch_params_varying_total_works.bin"
+        ))
+        .expect("Failed to deserialize BridgeCircuitHostParams");
+        let network = BitcoinNetwork::from_bitcoin_network(params.network.0).unwrap();
+        let mut input = params.into_bridge_circuit_input();
+        let kickoff_txid = input.kickoff_tx.compute_txid();
+
+        let mut watchtower_input = input.watchtower_inputs[0].clone();
+        let mut signature = watchtower_input.watchtower_challenge_witness.0.to_vec()[0].clone();
+        signature[63] ^= 1;
+        watchtower_input.watchtower_challenge_witness =
+            CircuitWitness(bitcoin::Witness::from_slice(&[signature]));
+
+        let inspection = inspect_watchtower_input(
+            &watchtower_input,
+            kickoff_txid,
+            &input.all_tweaked_watchtower_pubkeys,
+            input.watchtower_challenge_connector_start_idx,
+            input.hcp.genesis_state_hash,
+            network,
+        );
+        assert!(!inspection.is_challenge_sender());
+        assert_eq!(inspection.verified_total_work(), None);
+
+        input.watchtower_inputs = vec![watchtower_input];
+        let circuit_panic = catch_unwind(AssertUnwindSafe(|| verify_watchtower_challenges(&input)))
+            .expect_err("The circuit should reject the challenge");
+        assert_eq!(
+            inspection.rejection,
+            Some(panic_message(circuit_panic.as_ref()))
+        );
+    }
+}
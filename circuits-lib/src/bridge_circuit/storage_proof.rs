@@ -177,12 +177,128 @@ fn storage_verify(storage_proof: &EIP1186StorageProof, expected_root_hash: [u8;
         .expect("Account storage proof must be valid");
 }
 
+/// Storage layout of the Citrea bridge contract: where the contract lives
+/// and which storage slots hold the withdrawal UTXOs and the deposit txids.
+///
+/// Only used by host code that produces storage proofs. The bridge circuit
+/// verifies proofs against the layout with version 0, so a new layout needs a
+/// circuit release that selects it by the L2 height of the light client proof.
+#[cfg(not(target_os = "zkvm"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BridgeContractLayout {
+    pub version: u32,
+    /// First L2 height at which the contract uses this layout.
+    pub start_l2_height: u64,
+    pub address: [u8; 20],
+    /// Slot of the withdrawal UTXOs array. Each UTXO takes two slots, the
+    /// txid followed by the vout.
+    pub utxos_storage_index: [u8; 32],
+    /// Slot of the deposit txids array.
+    pub deposit_storage_index: [u8; 32],
+}
+
+/// Storage keys of a deposit's data in the bridge contract.
+#[cfg(not(target_os = "zkvm"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BridgeStorageKeys {
+    pub withdrawal_utxo_txid: [u8; 32],
+    pub withdrawal_utxo_vout: [u8; 32],
+    pub deposit_txid: [u8; 32],
+}
+
+/// All storage layouts the bridge contract has had, sorted by
+/// `start_l2_height`.
+#[cfg(not(target_os = "zkvm"))]
+pub const BRIDGE_CONTRACT_LAYOUTS: &[BridgeContractLayout] = &[BridgeContractLayout {
+    version: 0,
+    start_l2_height: 0,
+    address: ADDRESS,
+    utxos_storage_index: UTXOS_STORAGE_INDEX,
+    deposit_storage_index: DEPOSIT_STORAGE_INDEX,
+}];
+
+/// Returns the storage layout of the bridge contract at the given L2 height.
+#[cfg(not(target_os = "zkvm"))]
+pub fn bridge_contract_layout(l2_height: u64) -> &'static BridgeContractLayout {
+    BRIDGE_CONTRACT_LAYOUTS
+        .iter()
+        .rev()
+        .find(|layout| layout.start_l2_height <= l2_height)
+        .expect("The first bridge contract layout starts at L2 height 0")
+}
+
+#[cfg(not(target_os = "zkvm"))]
+impl BridgeContractLayout {
+    /// Computes the storage keys of the deposit with the given index, as
+    /// [`verify_storage_proofs`] expects them.
+    pub fn storage_keys(&self, deposit_index: u32) -> BridgeStorageKeys {
+        let storage_address = |slot: [u8; 32]| {
+            let mut keccak = Keccak256::new();
+            keccak.update(slot);
+            let hash = keccak.finalize();
+            U256::from_be_bytes(
+                <[u8; 32]>::try_from(&hash[..]).expect("Hash slice has incorrect length"),
+            )
+        };
+        let utxos_address = storage_address(self.utxos_storage_index);
+        let deposit_address = storage_address(self.deposit_storage_index);
+        let deposit_index = U256::from(deposit_index);
+
+        BridgeStorageKeys {
+            withdrawal_utxo_txid: (utxos_address + deposit_index * U256::from(2)).to_be_bytes(),
+            withdrawal_utxo_vout: (utxos_address + deposit_index * U256::from(2) + U256::from(1))
+                .to_be_bytes(),
+            deposit_txid: (deposit_address + deposit_index).to_be_bytes(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     const STORAGE_PROOF: &[u8] = include_bytes!("../../test_data/storage_proof.bin");
 
+    #[test]
+    fn test_bridge_contract_layouts() {
+        assert_eq!(BRIDGE_CONTRACT_LAYOUTS[0].start_l2_height, 0);
+        assert!(BRIDGE_CONTRACT_LAYOUTS
+            .windows(2)
+            .all(|pair| pair[0].start_l2_height < pair[1].start_l2_height
+                && pair[0].version < pair[1].version));
+
+        for layout in BRIDGE_CONTRACT_LAYOUTS {
+            assert_eq!(bridge_contract_layout(layout.start_l2_height), layout);
+        }
+        assert_eq!(
+            bridge_contract_layout(u64::MAX),
+            BRIDGE_CONTRACT_LAYOUTS.last().unwrap()
+        );
+
+        // The keys of the proofs the circuit accepts.
+        let storage_proof: StorageProof = borsh::from_slice(STORAGE_PROOF).unwrap();
+        let key = |proof: &str| {
+            serde_json::from_str::<EIP1186StorageProof>(proof)
+                .unwrap()
+                .key
+                .as_b256()
+                .0
+        };
+        let keys = bridge_contract_layout(0).storage_keys(storage_proof.index);
+        assert_eq!(
+            keys.withdrawal_utxo_txid,
+            key(&storage_proof.storage_proof_utxo)
+        );
+        assert_eq!(
+            keys.withdrawal_utxo_vout,
+            key(&storage_proof.storage_proof_vout)
+        );
+        assert_eq!(
+            keys.deposit_txid,
+            key(&storage_proof.storage_proof_deposit_txid)
+        );
+    }
+
     #[test]
     fn test_verify_storage_proofs() {
         let storage_proof: StorageProof = borsh::from_slice(STORAGE_PROOF).unwrap();
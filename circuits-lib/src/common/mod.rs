@@ -8,8 +8,13 @@
 
 pub mod constants;
 pub mod hashes;
+#[cfg(not(target_os = "zkvm"))]
+mod network;
 pub mod zkvm;
 
+#[cfg(not(target_os = "zkvm"))]
+pub use network::{BitcoinNetwork, NETWORK};
+
 pub const NETWORK_TYPE: &str = {
     #[cfg(test)]
     {
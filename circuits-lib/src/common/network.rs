@@ -0,0 +1,119 @@
+//! # Bitcoin Network
+//! Runtime representation of the Bitcoin networks the circuits support, for host-side code
+//! that handles more than one network per build. The guests keep selecting their network at
+//! compile time through the `BITCOIN_NETWORK` environment variable, so this module is not
+//! compiled into them.
+
+use crate::bridge_circuit::constants::{
+    DEVNET_LC_IMAGE_ID, MAINNET_LC_IMAGE_ID, MAINNET_WORK_ONLY_METHOD_ID, REGTEST_LC_IMAGE_ID,
+    REGTEST_WORK_ONLY_METHOD_ID, SIGNET_WORK_ONLY_METHOD_ID, TESTNET4_LC_IMAGE_ID,
+    TESTNET4_WORK_ONLY_METHOD_ID,
+};
+
+use super::constants::{
+    MAINNET_HEADER_CHAIN_METHOD_ID, REGTEST_HEADER_CHAIN_METHOD_ID, SIGNET_HEADER_CHAIN_METHOD_ID,
+    TESTNET4_HEADER_CHAIN_METHOD_ID,
+};
+
+/// Bitcoin networks supported by the circuits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BitcoinNetwork {
+    Mainnet,
+    Testnet4,
+    Signet,
+    Regtest,
+}
+
+impl BitcoinNetwork {
+    /// Parses a network name as used by the `BITCOIN_NETWORK` environment
+    /// variable.
+    pub const fn from_name(name: &str) -> Option<Self> {
+        match name.as_bytes() {
+            b"mainnet" => Some(BitcoinNetwork::Mainnet),
+            b"testnet4" => Some(BitcoinNetwork::Testnet4),
+            b"signet" => Some(BitcoinNetwork::Signet),
+            b"regtest" => Some(BitcoinNetwork::Regtest),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the network as used by the `BITCOIN_NETWORK`
+    /// environment variable.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            BitcoinNetwork::Mainnet => "mainnet",
+            BitcoinNetwork::Testnet4 => "testnet4",
+            BitcoinNetwork::Signet => "signet",
+            BitcoinNetwork::Regtest => "regtest",
+        }
+    }
+
+    /// Converts a `bitcoin::Network`. Returns `None` for networks that the
+    /// circuits do not support, such as testnet3.
+    pub fn from_bitcoin_network(network: bitcoin::Network) -> Option<Self> {
+        match network {
+            bitcoin::Network::Bitcoin => Some(BitcoinNetwork::Mainnet),
+            bitcoin::Network::Testnet4 => Some(BitcoinNetwork::Testnet4),
+            bitcoin::Network::Signet => Some(BitcoinNetwork::Signet),
+            bitcoin::Network::Regtest => Some(BitcoinNetwork::Regtest),
+            _ => None,
+        }
+    }
+
+    /// Method ID of the header chain circuit built for this network.
+    pub const fn header_chain_method_id(self) -> [u32; 8] {
+        match self {
+            BitcoinNetwork::Mainnet => MAINNET_HEADER_CHAIN_METHOD_ID,
+            BitcoinNetwork::Testnet4 => TESTNET4_HEADER_CHAIN_METHOD_ID,
+            BitcoinNetwork::Signet => SIGNET_HEADER_CHAIN_METHOD_ID,
+            BitcoinNetwork::Regtest => REGTEST_HEADER_CHAIN_METHOD_ID,
+        }
+    }
+
+    /// Image ID of Citrea's light client proof circuit for this network.
+    pub const fn light_client_image_id(self) -> [u8; 32] {
+        match self {
+            BitcoinNetwork::Mainnet => MAINNET_LC_IMAGE_ID,
+            BitcoinNetwork::Testnet4 => TESTNET4_LC_IMAGE_ID,
+            BitcoinNetwork::Signet => DEVNET_LC_IMAGE_ID,
+            BitcoinNetwork::Regtest => REGTEST_LC_IMAGE_ID,
+        }
+    }
+
+    /// Image ID of the work-only circuit built for this network.
+    pub fn work_only_method_id(self) -> [u8; 32] {
+        match self {
+            BitcoinNetwork::Mainnet => MAINNET_WORK_ONLY_METHOD_ID,
+            BitcoinNetwork::Testnet4 => TESTNET4_WORK_ONLY_METHOD_ID,
+            BitcoinNetwork::Signet => SIGNET_WORK_ONLY_METHOD_ID,
+            BitcoinNetwork::Regtest => REGTEST_WORK_ONLY_METHOD_ID,
+        }
+    }
+}
+
+impl std::str::FromStr for BitcoinNetwork {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BitcoinNetwork::from_name(s).ok_or_else(|| format!("Unsupported network: {s}"))
+    }
+}
+
+impl std::fmt::Display for BitcoinNetwork {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The network the guests linked with this build are specialized for.
+///
+/// Like `HEADER_CHAIN_METHOD_ID` and `NETWORK_CONSTANTS`, this follows the
+/// `BITCOIN_NETWORK` environment variable at compile time and defaults to
+/// mainnet if it is not set.
+pub const NETWORK: BitcoinNetwork = match option_env!("BITCOIN_NETWORK") {
+    Some(name) => match BitcoinNetwork::from_name(name) {
+        Some(network) => network,
+        None => panic!("Invalid network type"),
+    },
+    None => BitcoinNetwork::Mainnet,
+};
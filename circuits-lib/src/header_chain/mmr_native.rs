@@ -75,53 +75,16 @@ impl MMRNative {
     /// Returns both the leaf value and the proof needed to verify its inclusion.
     /// The proof can be verified against the MMR subroots.
     pub fn generate_proof(&self, index: u32) -> Result<([u8; 32], MMRInclusionProof)> {
-        if self.nodes[0].is_empty() {
-            return Err(eyre!("MMR Native is empty"));
-        }
-        if self.nodes[0].len() <= index as usize {
-            return Err(eyre!(
-                "Index out of bounds: {} >= {}",
-                index,
-                self.nodes[0].len()
-            ));
-        }
-
-        let mut proof: Vec<[u8; 32]> = vec![];
-        let mut current_index = index;
-        let mut current_level = 0;
-        // Returns the subtree proof for the subroot.
-        while !(current_index == self.nodes[current_level].len() as u32 - 1
-            && self.nodes[current_level].len() % 2 == 1)
-        {
-            let sibling_index = if current_index % 2 == 0 {
-                current_index + 1
-            } else {
-                current_index - 1
-            };
-            proof.push(self.nodes[current_level][sibling_index as usize]);
-            current_index /= 2;
-            current_level += 1;
-        }
-        let (subroot_idx, internal_idx) = self.get_helpers_from_index(index);
+        let (positions, subroot_idx, internal_idx) =
+            inclusion_proof_positions(self.nodes[0].len() as u32, index)?;
+        let proof = positions
+            .iter()
+            .map(|(level, level_idx)| self.nodes[*level][*level_idx as usize])
+            .collect();
         let mmr_proof = MMRInclusionProof::new(subroot_idx, internal_idx, proof);
         Ok((self.nodes[0][index as usize], mmr_proof))
     }
 
-    /// Determines subroot index and internal position for a given leaf index.
-    fn get_helpers_from_index(&self, index: u32) -> (usize, u32) {
-        let xor = (self.nodes[0].len() as u32) ^ index;
-        let xor_leading_digit = 31 - xor.leading_zeros() as usize;
-        let internal_idx = index & ((1 << xor_leading_digit) - 1);
-        let leading_zeros_size = 31 - (self.nodes[0].len() as u32).leading_zeros() as usize;
-        let mut subtree_idx = 0;
-        for i in xor_leading_digit + 1..=leading_zeros_size {
-            if self.nodes[0].len() & (1 << i) != 0 {
-                subtree_idx += 1;
-            }
-        }
-        (subtree_idx, internal_idx)
-    }
-
     /// Verifies an inclusion proof against the current MMR subroots.
     pub fn verify_proof(&self, leaf: [u8; 32], mmr_proof: &MMRInclusionProof) -> bool {
         let subroot = mmr_proof.get_subroot(leaf);
@@ -137,6 +100,59 @@ impl MMRNative {
     }
 }
 
+/// Position of an MMR node: its level, leaves being level 0, and its index
+/// within the level.
+pub type MMRNodePosition = (usize, u32);
+
+/// Returns the positions of the nodes in the inclusion proof of the leaf at
+/// `index` in an MMR with `leaf_count` leaves, followed by the subroot index
+/// and the index of the leaf within its subtree.
+///
+/// Level `l` of such an MMR holds `leaf_count >> l` nodes, so MMRs whose nodes
+/// are kept elsewhere, e.g. in a database, can generate the same proofs as
+/// [`MMRNative::generate_proof`] by fetching the nodes at these positions.
+pub fn inclusion_proof_positions(
+    leaf_count: u32,
+    index: u32,
+) -> Result<(Vec<MMRNodePosition>, usize, u32)> {
+    if leaf_count == 0 {
+        return Err(eyre!("MMR Native is empty"));
+    }
+    if leaf_count <= index {
+        return Err(eyre!("Index out of bounds: {} >= {}", index, leaf_count));
+    }
+
+    let level_len = |level: usize| leaf_count >> level;
+    let mut positions = vec![];
+    let mut current_index = index;
+    let mut current_level = 0;
+    // Returns the subtree proof for the subroot.
+    while !(current_index == level_len(current_level) - 1 && level_len(current_level) % 2 == 1) {
+        let sibling_index = if current_index % 2 == 0 {
+            current_index + 1
+        } else {
+            current_index - 1
+        };
+        positions.push((current_level, sibling_index));
+        current_index /= 2;
+        current_level += 1;
+    }
+
+    // Determines subroot index and internal position for the leaf.
+    let xor = leaf_count ^ index;
+    let xor_leading_digit = 31 - xor.leading_zeros() as usize;
+    let internal_idx = index & ((1 << xor_leading_digit) - 1);
+    let leading_zeros_size = 31 - leaf_count.leading_zeros() as usize;
+    let mut subroot_idx = 0;
+    for i in xor_leading_digit + 1..=leading_zeros_size {
+        if leaf_count & (1 << i) != 0 {
+            subroot_idx += 1;
+        }
+    }
+
+    Ok((positions, subroot_idx, internal_idx))
+}
+
 /// Proof of inclusion for an element in the MMR.
 ///
 /// Contains all data needed to verify that a specific leaf exists at a given
@@ -175,7 +191,7 @@ impl MMRInclusionProof {
 
 #[cfg(test)]
 mod tests {
-    use super::MMRNative;
+    use super::{inclusion_proof_positions, MMRInclusionProof, MMRNative};
     use crate::header_chain::mmr_guest::MMRGuest;
 
     #[test]
@@ -216,6 +232,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_mmr_prefix_proofs_from_larger_mmr() {
+        let mut full_mmr = MMRNative::new();
+        for i in 0..42 {
+            full_mmr.append([i as u8; 32]);
+        }
+
+        let mut mmr = MMRNative::new();
+        for leaf_count in 1..=42u32 {
+            mmr.append([(leaf_count - 1) as u8; 32]);
+
+            for j in 0..leaf_count {
+                let (positions, subroot_idx, internal_idx) =
+                    inclusion_proof_positions(leaf_count, j).unwrap();
+                let proof = positions
+                    .iter()
+                    .map(|(level, idx)| full_mmr.nodes[*level][*idx as usize])
+                    .collect();
+                let mmr_proof = MMRInclusionProof::new(subroot_idx, internal_idx, proof);
+                assert!(mmr.verify_proof([j as u8; 32], &mmr_proof));
+            }
+        }
+    }
+
     #[test]
     fn test_mmr_crosscheck() {
         let mut mmr_native = MMRNative::new();
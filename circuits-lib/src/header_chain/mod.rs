@@ -23,6 +23,8 @@ use crate::common::{get_network, zkvm::ZkvmGuest};
 
 pub mod mmr_guest;
 pub mod mmr_native;
+#[cfg(not(target_os = "zkvm"))]
+pub mod network;
 
 /// The main entry point of the header chain circuit.
 ///
@@ -0,0 +1,300 @@
+//! # Header Chain Rules for a Runtime Network
+//! Host-side counterparts of the header chain logic in the parent module that take the Bitcoin
+//! network as an argument instead of reading `BITCOIN_NETWORK` at compile time, so that native
+//! verification tools can handle several networks with a single build.
+//!
+//! The guests keep running [`ChainState::apply_block_headers`], which is specialized for the
+//! network of the guest build; changing it would change the method IDs of the circuits. This
+//! module is not compiled into the guests and must be kept in sync with the parent module.
+
+use crypto_bigint::{Encoding, U256};
+
+use super::{
+    bits_to_target, calculate_work, check_hash_valid, mmr_guest::MMRGuest, target_to_bits,
+    validate_timestamp, ChainState, CircuitBlockHeader, NetworkConstants, BLOCKS_PER_EPOCH,
+    MINIMUM_WORK_TESTNET,
+};
+use crate::common::BitcoinNetwork;
+
+impl NetworkConstants {
+    /// Returns the network constants of the given network. See
+    /// [`NETWORK_CONSTANTS`](super::NETWORK_CONSTANTS) for the values of each network.
+    pub const fn for_network(network: BitcoinNetwork) -> Self {
+        match network {
+            BitcoinNetwork::Signet => NetworkConstants {
+                max_bits: 0x1E0377AE,
+                max_target: U256::from_be_hex(
+                    "00000377AE000000000000000000000000000000000000000000000000000000",
+                ),
+                max_target_bytes: [
+                    0, 0, 3, 119, 174, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                    0, 0, 0, 0, 0, 0, 0,
+                ],
+            },
+            BitcoinNetwork::Regtest => NetworkConstants {
+                max_bits: 0x207FFFFF,
+                max_target: U256::from_be_hex(
+                    "7FFFFF0000000000000000000000000000000000000000000000000000000000",
+                ),
+                max_target_bytes: [
+                    127, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                    0, 0, 0, 0, 0, 0, 0, 0,
+                ],
+            },
+            BitcoinNetwork::Mainnet | BitcoinNetwork::Testnet4 => NetworkConstants {
+                max_bits: 0x1D00FFFF,
+                max_target: U256::from_be_hex(
+                    "00000000FFFF0000000000000000000000000000000000000000000000000000",
+                ),
+                max_target_bytes: [
+                    0, 0, 0, 0, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                    0, 0, 0, 0, 0, 0, 0,
+                ],
+            },
+        }
+    }
+}
+
+/// Expected duration of a difficulty adjustment epoch of the given network in
+/// seconds, the runtime counterpart of `EXPECTED_EPOCH_TIMESPAN`.
+pub const fn expected_epoch_timespan(network: BitcoinNetwork) -> u32 {
+    match network {
+        BitcoinNetwork::Signet => 60 * 24 * 14,
+        _ => 60 * 60 * 24 * 14,
+    }
+}
+
+impl ChainState {
+    /// Creates a new chain state with default values for the given network.
+    pub fn new_for_network(network: BitcoinNetwork) -> Self {
+        ChainState {
+            block_height: u32::MAX,
+            total_work: [0u8; 32],
+            best_block_hash: [0u8; 32],
+            current_target_bits: NetworkConstants::for_network(network).max_bits,
+            epoch_start_time: 0,
+            prev_11_timestamps: [0u32; 11],
+            block_hashes_mmr: MMRGuest::new(),
+        }
+    }
+
+    /// Applies a sequence of block headers to the chain state using the rules
+    /// of the given network.
+    ///
+    /// Same as [`ChainState::apply_block_headers`], but with the network chosen
+    /// at runtime instead of at compile time.
+    pub fn apply_block_headers_for_network(
+        &mut self,
+        block_headers: Vec<CircuitBlockHeader>,
+        network: BitcoinNetwork,
+    ) {
+        let network_constants = NetworkConstants::for_network(network);
+        let is_regtest = network == BitcoinNetwork::Regtest;
+        let is_testnet4 = network == BitcoinNetwork::Testnet4;
+
+        let mut current_target_bytes = if is_regtest {
+            network_constants.max_target.to_be_bytes()
+        } else {
+            bits_to_target(self.current_target_bits)
+        };
+        let mut current_work: U256 = U256::from_be_bytes(self.total_work);
+
+        let mut last_block_time = if is_testnet4 {
+            if self.block_height == u32::MAX {
+                0
+            } else {
+                self.prev_11_timestamps[self.block_height as usize % 11]
+            }
+        } else {
+            0
+        };
+
+        for block_header in block_headers {
+            self.block_height = self.block_height.wrapping_add(1);
+
+            let (target_to_use, expected_bits, work_to_add) = if is_testnet4 {
+                if block_header.time > last_block_time + 1200 {
+                    // If the block is an epoch block, then it still has to have the real target.
+                    if self.block_height % BLOCKS_PER_EPOCH == 0 {
+                        (
+                            current_target_bytes,
+                            self.current_target_bits,
+                            calculate_work(&current_target_bytes),
+                        )
+                    }
+                    // Otherwise, if the timestamp is more than 20 minutes ahead of the last block, the block is allowed to use the maximum target.
+                    else {
+                        (
+                            network_constants.max_target_bytes,
+                            network_constants.max_bits,
+                            MINIMUM_WORK_TESTNET,
+                        )
+                    }
+                } else {
+                    (
+                        current_target_bytes,
+                        self.current_target_bits,
+                        calculate_work(&current_target_bytes),
+                    )
+                }
+            } else {
+                (
+                    current_target_bytes,
+                    self.current_target_bits,
+                    calculate_work(&current_target_bytes),
+                )
+            };
+
+            let new_block_hash = block_header.compute_block_hash();
+
+            assert_eq!(
+                block_header.prev_block_hash, self.best_block_hash,
+                "Previous block hash does not match the best block hash. Expected: {:?}, got: {:?}",
+                self.best_block_hash, block_header.prev_block_hash
+            );
+
+            if is_regtest {
+                assert_eq!(
+                    block_header.bits, network_constants.max_bits,
+                    "Bits for regtest must be equal to the maximum bits: {}. Got: {}",
+                    network_constants.max_bits, block_header.bits
+                );
+            } else {
+                assert_eq!(
+                    block_header.bits, expected_bits,
+                    "Bits for the block header must match the expected bits: {}. Got: {}",
+                    expected_bits, block_header.bits
+                );
+            }
+
+            check_hash_valid(&new_block_hash, &target_to_use);
+
+            if !validate_timestamp(block_header.time, self.prev_11_timestamps) {
+                panic!("Timestamp is not valid, it must be greater than the median of the last 11 timestamps");
+            }
+
+            self.block_hashes_mmr.append(new_block_hash);
+            self.best_block_hash = new_block_hash;
+            current_work = current_work.wrapping_add(&work_to_add);
+
+            if !is_regtest && self.block_height % BLOCKS_PER_EPOCH == 0 {
+                self.epoch_start_time = block_header.time;
+            }
+
+            self.prev_11_timestamps[self.block_height as usize % 11] = block_header.time;
+
+            if is_testnet4 {
+                last_block_time = block_header.time;
+            }
+
+            if !is_regtest && self.block_height % BLOCKS_PER_EPOCH == BLOCKS_PER_EPOCH - 1 {
+                current_target_bytes = calculate_new_difficulty(
+                    self.epoch_start_time,
+                    block_header.time,
+                    self.current_target_bits,
+                    network,
+                );
+                self.current_target_bits = target_to_bits(&current_target_bytes);
+            }
+        }
+
+        self.total_work = current_work.to_be_bytes();
+    }
+}
+
+/// Calculates the new difficulty target after a difficulty adjustment epoch
+/// using the epoch timespan and maximum target of the given network. See
+/// the `calculate_new_difficulty` function of the parent module.
+fn calculate_new_difficulty(
+    epoch_start_time: u32,
+    last_timestamp: u32,
+    current_target: u32,
+    network: BitcoinNetwork,
+) -> [u8; 32] {
+    let expected_timespan = expected_epoch_timespan(network);
+    let max_target = NetworkConstants::for_network(network).max_target;
+
+    let mut actual_timespan = last_timestamp - epoch_start_time;
+    if actual_timespan < expected_timespan / 4 {
+        actual_timespan = expected_timespan / 4;
+    } else if actual_timespan > expected_timespan * 4 {
+        actual_timespan = expected_timespan * 4;
+    }
+
+    let current_target_bytes = bits_to_target(current_target);
+    let mut new_target = U256::from_be_bytes(current_target_bytes)
+        .wrapping_mul(&U256::from(actual_timespan))
+        .wrapping_div(&U256::from(expected_timespan));
+
+    if new_target > max_target {
+        new_target = max_target;
+    }
+    new_target.to_be_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::NETWORK;
+    use crate::header_chain::{EXPECTED_EPOCH_TIMESPAN, NETWORK_CONSTANTS};
+
+    const NETWORKS: [BitcoinNetwork; 4] = [
+        BitcoinNetwork::Mainnet,
+        BitcoinNetwork::Testnet4,
+        BitcoinNetwork::Signet,
+        BitcoinNetwork::Regtest,
+    ];
+
+    #[test]
+    fn test_network_constants() {
+        for network in NETWORKS {
+            let constants = NetworkConstants::for_network(network);
+            assert_eq!(
+                constants.max_target.to_be_bytes(),
+                constants.max_target_bytes
+            );
+            assert_eq!(
+                bits_to_target(constants.max_bits),
+                constants.max_target_bytes
+            );
+            assert_eq!(
+                ChainState::new_for_network(network).current_target_bits,
+                constants.max_bits
+            );
+            assert_eq!(BitcoinNetwork::from_name(network.as_str()), Some(network));
+        }
+
+        assert_eq!(
+            NetworkConstants::for_network(BitcoinNetwork::Regtest).max_bits,
+            0x207FFFFF
+        );
+        assert_eq!(
+            expected_epoch_timespan(BitcoinNetwork::Signet),
+            60 * 24 * 14
+        );
+    }
+
+    #[test]
+    fn test_matches_compile_time_network() {
+        let constants = NetworkConstants::for_network(NETWORK);
+        assert_eq!(constants.max_bits, NETWORK_CONSTANTS.max_bits);
+        assert_eq!(constants.max_target, NETWORK_CONSTANTS.max_target);
+        assert_eq!(
+            constants.max_target_bytes,
+            NETWORK_CONSTANTS.max_target_bytes
+        );
+        assert_eq!(expected_epoch_timespan(NETWORK), EXPECTED_EPOCH_TIMESPAN);
+        assert_eq!(ChainState::new_for_network(NETWORK), ChainState::new());
+
+        for (start_time, end_time, bits) in [
+            (1231006505, 1233061996, 0x1D00FFFF),
+            (1261130161, 1262152739, 0x1D00D86A),
+            (1700000000, 1700000001, 0x17034219),
+        ] {
+            assert_eq!(
+                calculate_new_difficulty(start_time, end_time, bits, NETWORK),
+                super::super::calculate_new_difficulty(start_time, end_time, bits)
+            );
+        }
+    }
+}
@@ -2,13 +2,21 @@ use std::collections::HashSet;
 use std::ops::Deref;
 use std::sync::Arc;
 
+use crate::builder::address::create_taproot_address;
 use crate::compatibility::{ActorWithConfig, CompatibilityParams};
+use crate::config::protocol::ProtocolParamset;
 use crate::constants::{
-    ENTITY_COMP_DATA_POLL_TIMEOUT, ENTITY_STATUS_POLL_TIMEOUT, OPERATOR_GET_KEYS_TIMEOUT,
-    PUBLIC_KEY_COLLECTION_TIMEOUT, RESTART_BACKGROUND_TASKS_TIMEOUT, VERIFIER_SEND_KEYS_TIMEOUT,
+    DEPOSIT_VERIFIER_PROBE_TIMEOUT, ENTITY_COMP_DATA_POLL_TIMEOUT, ENTITY_STATUS_POLL_TIMEOUT,
+    OPERATOR_GET_KEYS_TIMEOUT, PUBLIC_KEY_COLLECTION_TIMEOUT, RESTART_BACKGROUND_TASKS_TIMEOUT,
+    VERIFIER_SEND_KEYS_TIMEOUT,
 };
-use crate::deposit::DepositData;
+use crate::deposit::{Actors, DepositData, DepositInfo};
 use crate::extended_bitcoin_rpc::ExtendedBitcoinRpc;
+use crate::health::{aggregator_readiness, Readiness, ReadinessProvider};
+use crate::membership::{
+    check_effective_height, MemberKey, MemberKind, MemberSchedule, MembershipAction,
+    MembershipChange,
+};
 use crate::rpc::clementine::entity_data_with_id::DataResult;
 use crate::rpc::clementine::entity_status_with_id::StatusResult;
 use crate::rpc::clementine::{
@@ -22,9 +30,10 @@ use crate::task::TaskExt;
 use crate::tx_sender::TxSenderClient;
 use crate::utils::{
     flatten_join_named_results, join_all_partition_results, timed_request, timed_try_join_all,
+    try_join_all_combine_errors,
 };
 use crate::{
-    config::BridgeConfig,
+    config::{BridgeConfig, GrpcCompression},
     database::Database,
     rpc::{
         self,
@@ -32,16 +41,18 @@ use crate::{
             clementine_operator_client::ClementineOperatorClient,
             clementine_verifier_client::ClementineVerifierClient,
         },
+        RequestCompression,
     },
 };
 use bitcoin::secp256k1::PublicKey;
-use bitcoin::XOnlyPublicKey;
-use clementine_errors::BridgeError;
-use eyre::Context;
+use bitcoin::{OutPoint, ScriptBuf, XOnlyPublicKey};
+use bitcoincore_rpc::RpcApi;
+use clementine_errors::{AggregatorError, BridgeError};
+use eyre::{Context, OptionExt};
 use futures::future::join_all;
 use std::future::Future;
 use std::hash::Hash as StdHash;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 use tonic::{Request, Status};
 use tracing::{debug_span, Instrument};
 
@@ -60,10 +71,108 @@ pub struct Aggregator {
     pub(crate) config: BridgeConfig,
     #[cfg(feature = "automation")]
     pub(crate) tx_sender: TxSenderClient,
-    operator_clients: Vec<ClementineOperatorClient<tonic::transport::Channel>>,
-    verifier_clients: Vec<ClementineVerifierClient<tonic::transport::Channel>>,
-    verifier_keys: Arc<RwLock<Vec<Option<PublicKey>>>>,
-    operator_keys: Arc<RwLock<Vec<Option<XOnlyPublicKey>>>>,
+    verifiers: Members<VerifierClient, PublicKey>,
+    operators: Members<OperatorClient, XOnlyPublicKey>,
+    /// Held while a membership change is recorded, so that changes are
+    /// checked against the latest one.
+    membership_change_lock: Arc<Mutex<()>>,
+}
+
+type VerifierClient = ClementineVerifierClient<crate::rpc::TracedChannel>;
+type OperatorClient = ClementineOperatorClient<crate::rpc::TracedChannel>;
+
+/// A verifier or operator known to the aggregator. Members from the config
+/// come first, followed by the ones added with membership changes.
+#[derive(Debug, Clone)]
+struct Member<C, K> {
+    endpoint: String,
+    client: C,
+    /// Keys of members from the config are collected lazily.
+    key: Option<K>,
+    schedule: MemberSchedule,
+    /// Request compression negotiated in the last compatibility check.
+    compression: Option<GrpcCompression>,
+    /// Signatures per message the member accepts in the signature streams
+    /// it receives, as returned in the last compatibility check.
+    max_sigs_per_message: u32,
+}
+
+type Members<C, K> = Arc<RwLock<Vec<Member<C, K>>>>;
+
+impl<C, K> Member<C, K> {
+    fn from_config(endpoints: Vec<String>, clients: Vec<C>) -> Vec<Self> {
+        endpoints
+            .into_iter()
+            .zip(clients)
+            .map(|(endpoint, client)| Member {
+                endpoint,
+                client,
+                key: None,
+                schedule: MemberSchedule::default(),
+                compression: None,
+                max_sigs_per_message: 0,
+            })
+            .collect()
+    }
+}
+
+impl<C: RequestCompression + Clone, K> Member<C, K> {
+    /// Returns the client, compressing requests if a compression was
+    /// negotiated with the member.
+    fn client(&self) -> C {
+        self.client
+            .clone()
+            .with_request_compression(self.compression)
+    }
+}
+
+/// Applies recorded membership changes of one entity type to the members
+/// created from the config. `added_clients` are the clients of the added
+/// members, in the order of the changes.
+fn apply_membership_changes<C, K: MemberKey + PartialEq>(
+    members: &mut Vec<Member<C, K>>,
+    changes: &[&MembershipChange],
+    added_clients: Vec<C>,
+) -> Result<(), BridgeError> {
+    let mut added_clients = added_clients.into_iter();
+    for change in changes {
+        let key = K::from_member_bytes(&change.public_key)?;
+        match change.action {
+            MembershipAction::Add => members.push(Member {
+                endpoint: change.endpoint.clone(),
+                client: added_clients
+                    .next()
+                    .ok_or_eyre("Missing client for added member")?,
+                key: Some(key),
+                schedule: MemberSchedule {
+                    added_at: Some(change.effective_height),
+                    removed_at: None,
+                },
+                compression: None,
+                max_sigs_per_message: 0,
+            }),
+            MembershipAction::Remove => {
+                let member = members
+                    .iter_mut()
+                    .find(|member| {
+                        member.endpoint == change.endpoint
+                            && member.schedule.removed_at.is_none()
+                            && member.key.as_ref().is_none_or(|k| *k == key)
+                    })
+                    .ok_or_else(|| {
+                        eyre::eyre!(
+                            "Removed {} {} of epoch {} is not a member",
+                            change.kind.as_str(),
+                            change.endpoint,
+                            change.epoch
+                        )
+                    })?;
+                member.key = Some(key);
+                member.schedule.removed_at = Some(change.effective_height);
+            }
+        }
+    }
+    Ok(())
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -73,6 +182,23 @@ pub enum EntityId {
     Aggregator,
 }
 
+/// A verifier that was left out of a deposit signed with a subset of the
+/// verifiers, see [`BridgeConfig::min_deposit_verifiers`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExcludedVerifier {
+    pub endpoint: String,
+    /// [`None`] if the key of the verifier could not be collected.
+    pub public_key: Option<PublicKey>,
+    pub reason: String,
+}
+
+/// Verifiers chosen to sign a new deposit.
+#[derive(Debug, Clone, Default)]
+pub struct DepositVerifierSelection {
+    pub verifiers: Vec<PublicKey>,
+    pub excluded: Vec<ExcludedVerifier>,
+}
+
 /// Specifies which entity types to include when checking compatibility.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompatibilityCheckScope {
@@ -118,7 +244,7 @@ impl std::fmt::Display for OperatorId {
 #[derive(Debug, Clone)]
 pub struct ParticipatingVerifiers(
     pub  Vec<(
-        ClementineVerifierClient<tonic::transport::Channel>,
+        ClementineVerifierClient<crate::rpc::TracedChannel>,
         VerifierId,
     )>,
 );
@@ -126,14 +252,14 @@ pub struct ParticipatingVerifiers(
 impl ParticipatingVerifiers {
     pub fn new(
         verifiers: Vec<(
-            ClementineVerifierClient<tonic::transport::Channel>,
+            ClementineVerifierClient<crate::rpc::TracedChannel>,
             VerifierId,
         )>,
     ) -> Self {
         Self(verifiers)
     }
 
-    pub fn clients(&self) -> Vec<ClementineVerifierClient<tonic::transport::Channel>> {
+    pub fn clients(&self) -> Vec<ClementineVerifierClient<crate::rpc::TracedChannel>> {
         self.0.iter().map(|(client, _)| client.clone()).collect()
     }
 
@@ -146,7 +272,7 @@ impl ParticipatingVerifiers {
 #[derive(Debug, Clone)]
 pub struct ParticipatingOperators(
     pub  Vec<(
-        ClementineOperatorClient<tonic::transport::Channel>,
+        ClementineOperatorClient<crate::rpc::TracedChannel>,
         OperatorId,
     )>,
 );
@@ -154,14 +280,14 @@ pub struct ParticipatingOperators(
 impl ParticipatingOperators {
     pub fn new(
         operators: Vec<(
-            ClementineOperatorClient<tonic::transport::Channel>,
+            ClementineOperatorClient<crate::rpc::TracedChannel>,
             OperatorId,
         )>,
     ) -> Self {
         Self(operators)
     }
 
-    pub fn clients(&self) -> Vec<ClementineOperatorClient<tonic::transport::Channel>> {
+    pub fn clients(&self) -> Vec<ClementineOperatorClient<crate::rpc::TracedChannel>> {
         self.0.iter().map(|(client, _)| client.clone()).collect()
     }
 
@@ -174,13 +300,7 @@ impl Aggregator {
     pub async fn new(config: BridgeConfig) -> Result<Self, BridgeError> {
         let db = Database::new(&config).await?;
 
-        let rpc = ExtendedBitcoinRpc::connect(
-            config.bitcoin_rpc_url.clone(),
-            config.bitcoin_rpc_user.clone(),
-            config.bitcoin_rpc_password.clone(),
-            None,
-        )
-        .await?;
+        let rpc = config.connect_bitcoin_rpc().await?;
 
         let verifier_endpoints =
             config
@@ -200,7 +320,7 @@ impl Aggregator {
 
         // Create clients to connect to all verifiers
         let verifier_clients = rpc::get_clients(
-            verifier_endpoints,
+            verifier_endpoints.clone(),
             crate::rpc::verifier_client_builder(&config),
             &config,
             true,
@@ -209,7 +329,7 @@ impl Aggregator {
 
         // Create clients to connect to all operators
         let operator_clients = rpc::get_clients(
-            operator_endpoints,
+            operator_endpoints.clone(),
             crate::rpc::operator_client_builder(&config),
             &config,
             true,
@@ -226,8 +346,52 @@ impl Aggregator {
             operator_clients.len(),
         );
 
-        let operator_keys = Arc::new(RwLock::new(vec![None; operator_clients.len()]));
-        let verifier_keys = Arc::new(RwLock::new(vec![None; verifier_clients.len()]));
+        let mut verifiers = Member::from_config(verifier_endpoints, verifier_clients);
+        let mut operators = Member::from_config(operator_endpoints, operator_clients);
+
+        let changes = db.get_membership_changes(None).await?;
+        if !changes.is_empty() {
+            let verifier_changes = changes
+                .iter()
+                .filter(|change| change.kind == MemberKind::Verifier)
+                .collect::<Vec<_>>();
+            let operator_changes = changes
+                .iter()
+                .filter(|change| change.kind == MemberKind::Operator)
+                .collect::<Vec<_>>();
+            let added_endpoints = |changes: &[&MembershipChange]| {
+                changes
+                    .iter()
+                    .filter(|change| change.action == MembershipAction::Add)
+                    .map(|change| change.endpoint.clone())
+                    .collect::<Vec<_>>()
+            };
+
+            let added_verifier_clients = rpc::get_clients(
+                added_endpoints(&verifier_changes[..]),
+                crate::rpc::verifier_client_builder(&config),
+                &config,
+                true,
+            )
+            .await?;
+            let added_operator_clients = rpc::get_clients(
+                added_endpoints(&operator_changes[..]),
+                crate::rpc::operator_client_builder(&config),
+                &config,
+                true,
+            )
+            .await?;
+
+            apply_membership_changes(&mut verifiers, &verifier_changes, added_verifier_clients)?;
+            apply_membership_changes(&mut operators, &operator_changes, added_operator_clients)?;
+
+            tracing::info!(
+                "Applied {} membership changes, aggregator knows {} verifiers and {} operators",
+                changes.len(),
+                verifiers.len(),
+                operators.len(),
+            );
+        }
 
         Ok(Aggregator {
             rpc,
@@ -235,61 +399,120 @@ impl Aggregator {
             config,
             #[cfg(feature = "automation")]
             tx_sender,
-            verifier_clients,
-            operator_clients,
-            verifier_keys,
-            operator_keys,
+            verifiers: Arc::new(RwLock::new(verifiers)),
+            operators: Arc::new(RwLock::new(operators)),
+            membership_change_lock: Arc::new(Mutex::new(())),
         })
     }
 
-    pub fn get_verifier_clients(&self) -> &[ClementineVerifierClient<tonic::transport::Channel>] {
-        &self.verifier_clients
+    /// Height the membership is evaluated at: the next block, which new
+    /// deposits can be included in.
+    pub async fn membership_height(&self) -> Result<u32, BridgeError> {
+        Ok(self.rpc.get_current_chain_height().await? + 1)
     }
 
-    /// Generic helper function to fetch keys from clients
-    async fn fetch_pubkeys_from_entities<T, C, F, Fut>(
+    /// Height membership is evaluated at for a deposit: the height of the
+    /// block its deposit tx is included in. Verifiers check the members of the
+    /// deposit at the same height.
+    pub async fn deposit_membership_height(
         &self,
-        clients: &[C],
-        keys_storage: &RwLock<Vec<Option<T>>>,
+        deposit_outpoint: OutPoint,
+    ) -> Result<u32, BridgeError> {
+        let blockhash = self
+            .rpc
+            .get_blockhash_of_tx(&deposit_outpoint.txid)
+            .await
+            .wrap_err("Deposit transaction is not confirmed")?;
+        let height = self
+            .rpc
+            .get_block_info(&blockhash)
+            .await
+            .wrap_err(format!(
+                "Failed to get block info for deposit tx block hash: {blockhash}"
+            ))?
+            .height;
+        Ok(u32::try_from(height).wrap_err("Deposit block height overflow")?)
+    }
+
+    /// Generic helper function to fetch keys from clients. Collects the missing
+    /// keys of the members for which `is_member` is true at `height` and
+    /// returns these members with their keys.
+    async fn fetch_pubkeys_from_entities<T, C, F, Fut>(
+        members: &RwLock<Vec<Member<C, T>>>,
+        height: u32,
+        is_member: fn(&MemberSchedule, u32) -> bool,
+        pubkey_fetcher: F,
+        key_type_name: &str,
+    ) -> Result<Vec<(C, T)>, BridgeError>
+    where
+        T: Clone + Send + Sync + Eq + StdHash + std::fmt::Debug,
+        C: RequestCompression + Clone + Send + Sync,
+        F: Fn(C) -> Fut + Send + Sync,
+        Fut: Future<Output = Result<T, BridgeError>> + Send,
+    {
+        let missing_keys =
+            Self::collect_member_keys(members, height, is_member, pubkey_fetcher, key_type_name)
+                .await?;
+
+        // if not all keys were collected, return an error
+        if !missing_keys.is_empty() {
+            return Err(eyre::eyre!(
+                "Not all {} keys were able to be collected, missing keys of: {:?}",
+                key_type_name,
+                missing_keys
+            )
+            .into());
+        }
+
+        // return all members if their keys were collected
+        Ok(members
+            .read()
+            .await
+            .iter()
+            .filter(|member| is_member(&member.schedule, height))
+            .map(|member| {
+                (
+                    member.client(),
+                    member.key.clone().expect("should all be collected"),
+                )
+            })
+            .collect())
+    }
+
+    /// Collects the missing keys of the members for which `is_member` is true
+    /// at `height`. Returns the endpoints of the members whose keys could not
+    /// be collected; other errors, such as duplicate keys, are returned as
+    /// errors.
+    async fn collect_member_keys<T, C, F, Fut>(
+        members: &RwLock<Vec<Member<C, T>>>,
+        height: u32,
+        is_member: fn(&MemberSchedule, u32) -> bool,
         pubkey_fetcher: F,
         key_type_name: &str,
-    ) -> Result<Vec<T>, BridgeError>
+    ) -> Result<Vec<String>, BridgeError>
     where
         T: Clone + Send + Sync + Eq + StdHash + std::fmt::Debug,
-        C: Clone + Send + Sync,
+        C: RequestCompression + Clone + Send + Sync,
         F: Fn(C) -> Fut + Send + Sync,
         Fut: Future<Output = Result<T, BridgeError>> + Send,
     {
-        // Check if all keys are collected
+        // Check if all keys of the members are collected
         let all_collected = {
-            let keys = keys_storage.read().await;
-            keys.iter().all(|key| key.is_some())
+            let members = members.read().await;
+            members
+                .iter()
+                .all(|member| member.key.is_some() || !is_member(&member.schedule, height))
         };
 
         if !all_collected {
             // get a write lock early, so that only one thread can try to collect keys
-            let mut keys = keys_storage.write().await;
+            let mut members = members.write().await;
 
-            // sanity check because we directly use indexes below
-            if keys.len() != clients.len() {
-                return Err(eyre::eyre!(
-                    "Keys storage length does not match clients length, should not happen, keys length: {}, clients length: {}",
-                    keys.len(),
-                    clients.len()
-                )
-                .into());
-            }
-
-            let key_collection_futures = clients
+            let key_collection_futures = members
                 .iter()
-                .zip(keys.iter().enumerate())
-                .filter_map(|(client, (idx, key))| {
-                    if key.is_none() {
-                        Some((idx, pubkey_fetcher(client.clone())))
-                    } else {
-                        None
-                    }
-                })
+                .enumerate()
+                .filter(|(_, member)| member.key.is_none() && is_member(&member.schedule, height))
+                .map(|(idx, member)| (idx, pubkey_fetcher(member.client.clone())))
                 .map(|(idx, fut)| async move { (idx, fut.await) });
 
             let collected_keys = join_all(key_collection_futures).await;
@@ -298,94 +521,665 @@ impl Aggregator {
             // Fill in keys with the results of the futures
             for (idx, new_key) in collected_keys {
                 match new_key {
-                    Ok(new_key) => keys[idx] = Some(new_key),
+                    Ok(new_key) => members[idx].key = Some(new_key),
                     Err(e) => {
                         tracing::debug!(
-                            "Failed to collect {} {} (order in config) key: {}",
+                            "Failed to collect {} {} key: {}",
                             key_type_name,
-                            idx,
+                            members[idx].endpoint,
                             e
                         );
-                        missing_keys.push(idx);
+                        missing_keys.push(members[idx].endpoint.clone());
                     }
                 }
             }
 
             // if keys are not unique, return an error if so
-            let non_none_keys: Vec<_> = keys.iter().filter_map(|key| key.as_ref()).collect();
+            let non_none_keys: Vec<_> = members
+                .iter()
+                .filter(|member| is_member(&member.schedule, height))
+                .filter_map(|member| member.key.as_ref())
+                .collect();
             let unique_keys: HashSet<_> = non_none_keys.iter().cloned().collect();
 
             if unique_keys.len() != non_none_keys.len() {
-                let reason = format!("{key_type_name} keys are not unique: {keys:?}");
-                // reset all keys to None so that faulty keys are not used
-                for key in keys.iter_mut() {
-                    *key = None;
+                let reason = format!("{key_type_name} keys are not unique: {non_none_keys:?}");
+                // reset the keys of members from the config to None so that
+                // faulty keys are not used, keys of added members were
+                // checked when they were added
+                for member in members.iter_mut() {
+                    if member.schedule.added_at.is_none() {
+                        member.key = None;
+                    }
                 }
                 return Err(eyre::eyre!(reason).into());
             }
 
-            // if not all keys were collected, return an error
-            if keys.iter().any(|key| key.is_none()) {
-                return Err(eyre::eyre!(
-                    "Not all {} keys were able to be collected, missing keys at indices: {:?}",
-                    key_type_name,
-                    missing_keys
-                )
-                .into());
-            }
+            return Ok(missing_keys);
         }
 
-        // return all keys if they were all collected
-        Ok(keys_storage
-            .read()
+        Ok(vec![])
+    }
+
+    async fn fetch_verifier_key(mut client: VerifierClient) -> Result<PublicKey, BridgeError> {
+        let mut request = Request::new(Empty {});
+        request.set_timeout(PUBLIC_KEY_COLLECTION_TIMEOUT);
+        let verifier_params = client.get_params(request).await?.into_inner();
+        let public_key = PublicKey::from_slice(&verifier_params.public_key)
+            .map_err(|e| eyre::eyre!("Failed to parse verifier public key: {}", e))?;
+        Ok(public_key)
+    }
+
+    async fn fetch_operator_key(mut client: OperatorClient) -> Result<XOnlyPublicKey, BridgeError> {
+        let mut request = Request::new(Empty {});
+        request.set_timeout(PUBLIC_KEY_COLLECTION_TIMEOUT);
+        let operator_xonly_pk: XOnlyPublicKey = client
+            .get_x_only_public_key(request)
+            .await?
+            .into_inner()
+            .try_into()?;
+        Ok(operator_xonly_pk)
+    }
+
+    /// Returns the clients and keys of the verifiers that are active at the
+    /// next block. Keys that are not collected yet are collected from the
+    /// verifiers.
+    pub async fn get_active_verifiers(
+        &self,
+    ) -> Result<Vec<(VerifierClient, PublicKey)>, BridgeError> {
+        self.get_verifiers_active_at(self.membership_height().await?)
             .await
-            .iter()
-            .map(|key| key.clone().expect("should all be collected"))
-            .collect())
     }
 
-    /// If all verifier keys are already collected, returns them.
-    /// Otherwise, it tries to collect them from the verifiers, saves them and returns them.
-    pub async fn fetch_verifier_keys(&self) -> Result<Vec<PublicKey>, BridgeError> {
-        self.fetch_pubkeys_from_entities(
-            &self.verifier_clients,
-            &self.verifier_keys,
-            |mut client| async move {
-                let mut request = Request::new(Empty {});
-                request.set_timeout(PUBLIC_KEY_COLLECTION_TIMEOUT);
-                let verifier_params = client.get_params(request).await?.into_inner();
-                let public_key = PublicKey::from_slice(&verifier_params.public_key)
-                    .map_err(|e| eyre::eyre!("Failed to parse verifier public key: {}", e))?;
-                Ok::<_, BridgeError>(public_key)
-            },
+    /// Returns the clients and keys of the verifiers that are active at
+    /// `height`.
+    pub async fn get_verifiers_active_at(
+        &self,
+        height: u32,
+    ) -> Result<Vec<(VerifierClient, PublicKey)>, BridgeError> {
+        Self::fetch_pubkeys_from_entities(
+            &self.verifiers,
+            height,
+            MemberSchedule::is_active,
+            Self::fetch_verifier_key,
+            "verifier",
+        )
+        .await
+    }
+
+    /// Collects the keys of the verifiers that are active at `height` like
+    /// [`Self::get_verifiers_active_at`], but verifiers that can't be reached
+    /// are logged instead of failing.
+    pub async fn collect_verifier_keys_at(&self, height: u32) -> Result<(), BridgeError> {
+        let missing = Self::collect_member_keys(
+            &self.verifiers,
+            height,
+            MemberSchedule::is_active,
+            Self::fetch_verifier_key,
+            "verifier",
+        )
+        .await?;
+        if !missing.is_empty() {
+            tracing::warn!("Failed to collect the keys of verifiers: {missing:?}");
+        }
+        Ok(())
+    }
+
+    /// Returns the clients and keys of the operators that are active at the
+    /// next block. Keys that are not collected yet are collected from the
+    /// operators.
+    pub async fn get_active_operators(
+        &self,
+    ) -> Result<Vec<(OperatorClient, XOnlyPublicKey)>, BridgeError> {
+        self.get_operators_active_at(self.membership_height().await?)
+            .await
+    }
+
+    /// Returns the clients and keys of the operators that are active at
+    /// `height`.
+    pub async fn get_operators_active_at(
+        &self,
+        height: u32,
+    ) -> Result<Vec<(OperatorClient, XOnlyPublicKey)>, BridgeError> {
+        Self::fetch_pubkeys_from_entities(
+            &self.operators,
+            height,
+            MemberSchedule::is_active,
+            Self::fetch_operator_key,
+            "operator",
+        )
+        .await
+    }
+
+    /// Collects the keys of the operators that are active at `height` like
+    /// [`Self::get_operators_active_at`], but operators that can't be reached
+    /// are logged instead of failing.
+    pub async fn collect_operator_keys_at(&self, height: u32) -> Result<(), BridgeError> {
+        let missing = Self::collect_member_keys(
+            &self.operators,
+            height,
+            MemberSchedule::is_active,
+            Self::fetch_operator_key,
+            "operator",
+        )
+        .await?;
+        if !missing.is_empty() {
+            tracing::warn!("Failed to collect the keys of operators: {missing:?}");
+        }
+        Ok(())
+    }
+
+    /// Returns the verifiers that are active at the next block or added for a
+    /// later height. Setup data is distributed to these verifiers, so that
+    /// added verifiers are ready when their change becomes effective.
+    pub async fn get_setup_verifiers(
+        &self,
+    ) -> Result<Vec<(VerifierClient, PublicKey)>, BridgeError> {
+        Self::fetch_pubkeys_from_entities(
+            &self.verifiers,
+            self.membership_height().await?,
+            MemberSchedule::is_active_or_upcoming,
+            Self::fetch_verifier_key,
             "verifier",
         )
         .await
     }
 
-    /// If all operator keys are already collected, returns them.
-    /// Otherwise, it tries to collect them from the operators, saves them and returns them.
+    /// Returns the operators that are active at the next block or added for a
+    /// later height, see [`Self::get_setup_verifiers`].
+    pub async fn get_setup_operators(
+        &self,
+    ) -> Result<Vec<(OperatorClient, XOnlyPublicKey)>, BridgeError> {
+        Self::fetch_pubkeys_from_entities(
+            &self.operators,
+            self.membership_height().await?,
+            MemberSchedule::is_active_or_upcoming,
+            Self::fetch_operator_key,
+            "operator",
+        )
+        .await
+    }
+
+    /// Sends the recorded membership changes to the verifiers returned by
+    /// [`Self::get_setup_verifiers`].
+    pub async fn sync_membership_changes(&self) -> Result<(), BridgeError> {
+        let changes = clementine::MembershipChanges {
+            changes: self
+                .db
+                .get_membership_changes(None)
+                .await?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        };
+        let verifiers = self.get_setup_verifiers().await?;
+        tracing::info!(
+            "Sending {} membership changes to {} verifiers",
+            changes.changes.len(),
+            verifiers.len()
+        );
+
+        try_join_all_combine_errors(verifiers.into_iter().map(|(mut client, key)| {
+            let changes = changes.clone();
+            async move {
+                client
+                    .set_membership_changes(Request::new(changes))
+                    .await
+                    .wrap_err_with(|| AggregatorError::RequestFailed {
+                        request_name: format!(
+                            "Verifier set_membership_changes for {}",
+                            VerifierId(key)
+                        ),
+                    })?;
+                Ok::<_, BridgeError>(())
+            }
+        }))
+        .await
+        .wrap_err("Failed to send membership changes to all verifiers")?;
+        Ok(())
+    }
+
+    /// Returns the keys of the verifiers that are active at the next block.
+    pub async fn fetch_verifier_keys(&self) -> Result<Vec<PublicKey>, BridgeError> {
+        Ok(self
+            .get_active_verifiers()
+            .await?
+            .into_iter()
+            .map(|(_, key)| key)
+            .collect())
+    }
+
+    /// Returns the keys of the operators that are active at the next block.
     pub async fn fetch_operator_keys(&self) -> Result<Vec<XOnlyPublicKey>, BridgeError> {
-        self.fetch_pubkeys_from_entities(
-            &self.operator_clients,
-            &self.operator_keys,
-            |mut client| async move {
-                let mut request = Request::new(Empty {});
-                request.set_timeout(PUBLIC_KEY_COLLECTION_TIMEOUT);
-                let operator_xonly_pk: XOnlyPublicKey = client
-                    .get_x_only_public_key(request)
-                    .await?
-                    .into_inner()
-                    .try_into()?;
-                Ok::<_, BridgeError>(operator_xonly_pk)
+        Ok(self
+            .get_active_operators()
+            .await?
+            .into_iter()
+            .map(|(_, key)| key)
+            .collect())
+    }
+
+    /// Returns the recorded membership changes.
+    pub async fn get_membership_changes(&self) -> Result<Vec<MembershipChange>, BridgeError> {
+        self.db.get_membership_changes(None).await
+    }
+
+    /// Checks the effective height of a new membership change, defaulting to
+    /// the next block.
+    async fn membership_change_height(
+        &self,
+        effective_height: Option<u32>,
+    ) -> Result<u32, BridgeError> {
+        let current_height = self.rpc.get_current_chain_height().await?;
+        let effective_height = effective_height.unwrap_or(current_height + 1);
+        let changes = self.db.get_membership_changes(None).await?;
+        check_effective_height(&changes, current_height, effective_height)?;
+        Ok(effective_height)
+    }
+
+    /// Adds a member to `members`. The change is recorded in the database and
+    /// the member takes part in deposits from `effective_height` on.
+    async fn record_member_addition<C, K, F, Fut>(
+        &self,
+        members: &RwLock<Vec<Member<C, K>>>,
+        kind: MemberKind,
+        endpoint: String,
+        client: C,
+        key_fetcher: F,
+        effective_height: Option<u32>,
+    ) -> Result<MembershipChange, BridgeError>
+    where
+        K: MemberKey + Clone + PartialEq + std::fmt::Display,
+        F: FnOnce(C) -> Fut,
+        C: Clone,
+        Fut: Future<Output = Result<K, BridgeError>>,
+    {
+        let _lock = self.membership_change_lock.lock().await;
+        let key = key_fetcher(client.clone())
+            .await
+            .wrap_err_with(|| format!("Failed to get the key of {} {endpoint}", kind.as_str()))?;
+
+        let effective_height = self.membership_change_height(effective_height).await?;
+        {
+            let members = members.read().await;
+            if members.iter().any(|member| {
+                member.schedule.removed_at.is_none()
+                    && (member.endpoint == endpoint || member.key.as_ref() == Some(&key))
+            }) {
+                return Err(eyre::eyre!(
+                    "{} {endpoint} with key {key} is already a member",
+                    kind.as_str()
+                )
+                .into());
+            }
+        }
+
+        let epoch = self
+            .db
+            .insert_membership_change(
+                None,
+                kind,
+                MembershipAction::Add,
+                &endpoint,
+                &key.to_member_bytes(),
+                effective_height,
+            )
+            .await?;
+
+        tracing::info!(
+            "Added {} {endpoint} with key {key}, effective at height {effective_height}, epoch {epoch}",
+            kind.as_str()
+        );
+
+        let change = MembershipChange {
+            epoch,
+            kind,
+            action: MembershipAction::Add,
+            endpoint: endpoint.clone(),
+            public_key: key.to_member_bytes(),
+            effective_height,
+        };
+        members.write().await.push(Member {
+            endpoint,
+            client,
+            key: Some(key),
+            schedule: MemberSchedule {
+                added_at: Some(effective_height),
+                removed_at: None,
             },
-            "operator",
+            compression: None,
+            max_sigs_per_message: 0,
+        });
+        Ok(change)
+    }
+
+    /// Removes the member with `key` from `members`. The member stops taking
+    /// part in new deposits from `effective_height` on, but is still used for
+    /// the deposits it signed.
+    async fn record_member_removal<C, K>(
+        &self,
+        members: &RwLock<Vec<Member<C, K>>>,
+        kind: MemberKind,
+        key: K,
+        effective_height: Option<u32>,
+    ) -> Result<MembershipChange, BridgeError>
+    where
+        K: MemberKey + Clone + PartialEq + std::fmt::Display,
+    {
+        let _lock = self.membership_change_lock.lock().await;
+        let effective_height = self.membership_change_height(effective_height).await?;
+
+        let endpoint = {
+            let members = members.read().await;
+            let member = members
+                .iter()
+                .find(|member| {
+                    member.schedule.removed_at.is_none() && member.key.as_ref() == Some(&key)
+                })
+                .ok_or_else(|| {
+                    eyre::eyre!(
+                        "{} with key {key} is not a member or its key was not collected yet",
+                        kind.as_str()
+                    )
+                })?;
+            if member
+                .schedule
+                .added_at
+                .is_some_and(|added_at| added_at > effective_height)
+            {
+                return Err(eyre::eyre!(
+                    "{} {key} can't be removed before it is added",
+                    kind.as_str()
+                )
+                .into());
+            }
+            let remaining = members
+                .iter()
+                .filter(|other| {
+                    other.schedule.removed_at.is_none() && other.key.as_ref() != Some(&key)
+                })
+                .count();
+            if kind == MemberKind::Verifier && remaining == 0 {
+                return Err(eyre::eyre!("The last verifier can't be removed").into());
+            }
+            member.endpoint.clone()
+        };
+
+        let epoch = self
+            .db
+            .insert_membership_change(
+                None,
+                kind,
+                MembershipAction::Remove,
+                &endpoint,
+                &key.to_member_bytes(),
+                effective_height,
+            )
+            .await?;
+
+        tracing::info!(
+            "Removed {} {endpoint} with key {key}, effective at height {effective_height}, epoch {epoch}",
+            kind.as_str()
+        );
+
+        if let Some(member) = members.write().await.iter_mut().find(|member| {
+            member.schedule.removed_at.is_none() && member.key.as_ref() == Some(&key)
+        }) {
+            member.schedule.removed_at = Some(effective_height);
+        }
+
+        Ok(MembershipChange {
+            epoch,
+            kind,
+            action: MembershipAction::Remove,
+            endpoint,
+            public_key: key.to_member_bytes(),
+            effective_height,
+        })
+    }
+
+    /// Adds the verifier at `endpoint`, effective at `effective_height` or the
+    /// next block.
+    pub async fn add_verifier(
+        &self,
+        endpoint: String,
+        effective_height: Option<u32>,
+    ) -> Result<MembershipChange, BridgeError> {
+        let client = rpc::get_clients(
+            vec![endpoint.clone()],
+            crate::rpc::verifier_client_builder(&self.config),
+            &self.config,
+            true,
+        )
+        .await?
+        .pop()
+        .ok_or_eyre("Failed to create verifier client")?;
+        self.record_member_addition(
+            &self.verifiers,
+            MemberKind::Verifier,
+            endpoint,
+            client,
+            Self::fetch_verifier_key,
+            effective_height,
+        )
+        .await
+    }
+
+    /// Adds the operator at `endpoint`, effective at `effective_height` or the
+    /// next block.
+    pub async fn add_operator(
+        &self,
+        endpoint: String,
+        effective_height: Option<u32>,
+    ) -> Result<MembershipChange, BridgeError> {
+        let client = rpc::get_clients(
+            vec![endpoint.clone()],
+            crate::rpc::operator_client_builder(&self.config),
+            &self.config,
+            true,
+        )
+        .await?
+        .pop()
+        .ok_or_eyre("Failed to create operator client")?;
+        self.record_member_addition(
+            &self.operators,
+            MemberKind::Operator,
+            endpoint,
+            client,
+            Self::fetch_operator_key,
+            effective_height,
         )
         .await
     }
 
-    pub fn get_operator_clients(&self) -> &[ClementineOperatorClient<tonic::transport::Channel>] {
-        &self.operator_clients
+    /// Removes the verifier with `public_key`, effective at
+    /// `effective_height` or the next block.
+    pub async fn remove_verifier(
+        &self,
+        public_key: PublicKey,
+        effective_height: Option<u32>,
+    ) -> Result<MembershipChange, BridgeError> {
+        // make sure the keys of the verifiers from the config are known
+        self.collect_verifier_keys_at(self.membership_height().await?)
+            .await?;
+        self.record_member_removal(
+            &self.verifiers,
+            MemberKind::Verifier,
+            public_key,
+            effective_height,
+        )
+        .await
+    }
+
+    /// Removes the operator with `xonly_pk`, effective at `effective_height`
+    /// or the next block.
+    pub async fn remove_operator(
+        &self,
+        xonly_pk: XOnlyPublicKey,
+        effective_height: Option<u32>,
+    ) -> Result<MembershipChange, BridgeError> {
+        // make sure the keys of the operators from the config are known
+        self.collect_operator_keys_at(self.membership_height().await?)
+            .await?;
+        self.record_member_removal(
+            &self.operators,
+            MemberKind::Operator,
+            xonly_pk,
+            effective_height,
+        )
+        .await
+    }
+
+    /// Chooses the verifiers that sign a new deposit from the verifiers that
+    /// are active at `height`, see [`Self::deposit_membership_height`].
+    /// Without [`BridgeConfig::min_deposit_verifiers`] all of them are
+    /// required. Otherwise every active verifier is asked for its
+    /// compatibility params, and the set of at least `min_deposit_verifiers`
+    /// responding and compatible verifiers whose N-of-N key the deposit pays
+    /// to is chosen. Fails if there is no such set, before any signing session
+    /// is opened.
+    pub async fn select_deposit_verifiers(
+        &self,
+        deposit_info: &DepositInfo,
+        height: u32,
+    ) -> Result<DepositVerifierSelection, BridgeError> {
+        let Some(min_verifiers) = self.config.min_deposit_verifiers else {
+            return Ok(DepositVerifierSelection {
+                verifiers: self
+                    .get_verifiers_active_at(height)
+                    .await?
+                    .into_iter()
+                    .map(|(_, key)| key)
+                    .collect(),
+                excluded: vec![],
+            });
+        };
+
+        // Collect as many keys as possible, verifiers without a key are excluded
+        self.collect_verifier_keys_at(height).await?;
+        let verifiers = Self::active_members(&self.verifiers, height).await;
+        let own_params = self.get_compatibility_params()?;
+
+        let probes = verifiers.into_iter().map(|member| {
+            let own_params = own_params.clone();
+            async move {
+                let Some(key) = member.key else {
+                    return (
+                        member.endpoint,
+                        None,
+                        Err("Verifier key was not able to be collected".to_string()),
+                    );
+                };
+                let mut client = member.client;
+                let mut request = Request::new(Empty {});
+                request.set_timeout(DEPOSIT_VERIFIER_PROBE_TIMEOUT);
+                let result: Result<CompatibilityParams, BridgeError> = async {
+                    let params: CompatibilityParams = client
+                        .get_compatibility_params(request)
+                        .await
+                        .wrap_err("Compatibility params retrieval failed")?
+                        .into_inner()
+                        .try_into()
+                        .wrap_err("Compatibility params conversion failed")?;
+                    own_params.is_compatible(&params)?;
+                    Ok(params)
+                }
+                .await;
+                (
+                    member.endpoint,
+                    Some(key),
+                    result.map_err(|e| e.to_string()),
+                )
+            }
+        });
+
+        let mut selection = DepositVerifierSelection::default();
+        let mut compatible_params = Vec::new();
+        for (endpoint, public_key, result) in join_all(probes).await {
+            match (public_key, result) {
+                (Some(public_key), Ok(params)) => {
+                    selection.verifiers.push(public_key);
+                    compatible_params.push((public_key, endpoint, params));
+                }
+                (public_key, result) => selection.excluded.push(ExcludedVerifier {
+                    endpoint,
+                    public_key,
+                    reason: result.err().unwrap_or_default(),
+                }),
+            }
+        }
+        Self::set_negotiated_stream_params(&self.verifiers, &own_params, &compatible_params).await;
+
+        if selection.verifiers.len() < min_verifiers {
+            return Err(eyre::eyre!(
+                "Only {} verifiers can sign the deposit, at least {} are required. Excluded verifiers: {:?}",
+                selection.verifiers.len(),
+                min_verifiers,
+                selection.excluded
+            )
+            .into());
+        }
+
+        // The deposit pays to the N-of-N key of the verifiers its address was
+        // generated for, no other set can sign it.
+        let deposit_outpoint = deposit_info.deposit_outpoint;
+        let deposit_tx = self
+            .rpc
+            .get_tx_of_txid(&deposit_outpoint.txid)
+            .await
+            .wrap_err("Deposit tx could not be found on chain")?;
+        let deposit_script_pubkey = &deposit_tx
+            .output
+            .get(deposit_outpoint.vout as usize)
+            .ok_or_eyre("Deposit vout not found in deposit tx")?
+            .script_pubkey;
+        let deposit_template = DepositData {
+            deposit: deposit_info.clone(),
+            nofn_xonly_pk: None,
+            actors: Actors {
+                verifiers: vec![],
+                watchtowers: vec![],
+                operators: vec![],
+            },
+            security_council: self.config.security_council.clone(),
+        };
+        let Some(deposit_verifiers) = find_deposit_verifier_set(
+            &deposit_template,
+            &selection.verifiers,
+            min_verifiers,
+            deposit_script_pubkey,
+            self.config.protocol_paramset(),
+        )?
+        else {
+            return Err(eyre::eyre!(
+                "No set of at least {} of the {} reachable verifiers has the N-of-N key the deposit {} pays to. Excluded verifiers: {:?}",
+                min_verifiers,
+                selection.verifiers.len(),
+                deposit_outpoint,
+                selection.excluded
+            )
+            .into());
+        };
+        for (public_key, endpoint, _) in &compatible_params {
+            if !deposit_verifiers.contains(public_key) {
+                selection.excluded.push(ExcludedVerifier {
+                    endpoint: endpoint.clone(),
+                    public_key: Some(*public_key),
+                    reason: "Not part of the verifier set the deposit address was generated for"
+                        .to_string(),
+                });
+            }
+        }
+        selection.verifiers = deposit_verifiers;
+        if !selection.excluded.is_empty() {
+            tracing::warn!(
+                "Signing the deposit with {} verifiers, excluded verifiers: {:?}",
+                selection.verifiers.len(),
+                selection.excluded
+            );
+        }
+
+        Ok(selection)
     }
 
     /// Collects and distributes keys to verifiers from operators and watchtowers for the new deposit
@@ -559,24 +1353,34 @@ impl Aggregator {
     }
 
     /// Returns a list of verifier clients that are participating in the deposit.
+    /// Verifiers that were removed after the deposit was created are still
+    /// returned, so that existing deposits keep their verifier set.
     pub async fn get_participating_verifiers(
         &self,
         deposit_data: &DepositData,
     ) -> Result<ParticipatingVerifiers, BridgeError> {
-        let verifier_keys = self.fetch_verifier_keys().await?;
+        // collect the keys of the active verifiers, a deposit can be signed by
+        // a subset of them, so verifiers whose keys can't be collected are
+        // only an error if they are part of the deposit
+        self.collect_verifier_keys_at(self.membership_height().await?)
+            .await?;
+        let members = self.verifiers.read().await;
         let mut participating_verifiers = Vec::new();
 
         let verifiers = deposit_data.get_verifiers();
 
         for verifier_pk in verifiers {
-            if let Some(pos) = verifier_keys.iter().position(|key| key == &verifier_pk) {
-                participating_verifiers
-                    .push((self.verifier_clients[pos].clone(), VerifierId(verifier_pk)));
+            if let Some(member) = members
+                .iter()
+                .rev()
+                .find(|member| member.key == Some(verifier_pk))
+            {
+                participating_verifiers.push((member.client(), VerifierId(verifier_pk)));
             } else {
                 tracing::error!(
                     "Verifier public key not found. Deposit data verifier keys: {:?}, self verifier keys: {:?}",
                     deposit_data.get_verifiers(),
-                    self.verifier_keys
+                    members.iter().map(|member| member.key).collect::<Vec<_>>()
                 );
                 return Err(BridgeError::VerifierNotFound(verifier_pk));
             }
@@ -586,19 +1390,26 @@ impl Aggregator {
     }
 
     /// Returns a list of operator clients that are participating in the deposit.
+    /// Operators that were removed after the deposit was created are still
+    /// returned.
     pub async fn get_participating_operators(
         &self,
         deposit_data: &DepositData,
     ) -> Result<ParticipatingOperators, BridgeError> {
-        let operator_keys = self.fetch_operator_keys().await?;
+        // collect the keys of the active operators
+        self.get_active_operators().await?;
+        let members = self.operators.read().await;
         let mut participating_operators = Vec::new();
 
         let operators = deposit_data.get_operators();
 
         for operator_pk in operators {
-            if let Some(pos) = operator_keys.iter().position(|key| key == &operator_pk) {
-                participating_operators
-                    .push((self.operator_clients[pos].clone(), OperatorId(operator_pk)));
+            if let Some(member) = members
+                .iter()
+                .rev()
+                .find(|member| member.key == Some(operator_pk))
+            {
+                participating_operators.push((member.client(), OperatorId(operator_pk)));
             } else {
                 return Err(BridgeError::OperatorNotFound(operator_pk));
             }
@@ -608,41 +1419,64 @@ impl Aggregator {
     }
 
     /// Helper function to fetch keys for both operators and verifiers.
-    /// Returns (operator_keys, verifier_keys) without failing if some entities are unreachable.
-    async fn fetch_all_entity_keys(&self) -> (Vec<Option<XOnlyPublicKey>>, Vec<Option<PublicKey>>) {
+    /// Returns the active operators and verifiers without failing if some
+    /// entities are unreachable, their keys are [`None`].
+    async fn fetch_all_entity_keys(
+        &self,
+    ) -> Result<
+        (
+            Vec<Member<OperatorClient, XOnlyPublicKey>>,
+            Vec<Member<VerifierClient, PublicKey>>,
+        ),
+        BridgeError,
+    > {
         // Try to reach all operators and verifiers to collect keys, but do not return err if some can't be reached
-        let _ = self.fetch_operator_keys().await;
-        let _ = self.fetch_verifier_keys().await;
+        let height = self.membership_height().await?;
+        self.collect_operator_keys_at(height).await?;
+        self.collect_verifier_keys_at(height).await?;
+
+        let operators = Self::active_members(&self.operators, height).await;
+        let verifiers = Self::active_members(&self.verifiers, height).await;
 
-        let operator_keys = self.operator_keys.read().await.clone();
-        let verifier_keys = self.verifier_keys.read().await.clone();
+        Ok((operators, verifiers))
+    }
 
-        (operator_keys, verifier_keys)
+    async fn active_members<C: Clone, K: Clone>(
+        members: &RwLock<Vec<Member<C, K>>>,
+        height: u32,
+    ) -> Vec<Member<C, K>> {
+        members
+            .read()
+            .await
+            .iter()
+            .filter(|member| member.schedule.is_active(height))
+            .cloned()
+            .collect()
     }
 
     /// Helper function to add error entries for entities where keys couldn't be collected.
     fn add_unreachable_entity_errors<T, F>(
         results: &mut Vec<T>,
-        operator_keys: &[Option<XOnlyPublicKey>],
-        verifier_keys: &[Option<PublicKey>],
+        operators: &[Member<OperatorClient, XOnlyPublicKey>],
+        verifiers: &[Member<VerifierClient, PublicKey>],
         error_constructor: F,
     ) where
         F: Fn(EntityType, String, String) -> T,
     {
-        for (index, key) in operator_keys.iter().enumerate() {
-            if key.is_none() {
+        for operator in operators {
+            if operator.key.is_none() {
                 results.push(error_constructor(
                     EntityType::Operator,
-                    format!("Index {index} in config (0-based)"),
+                    operator.endpoint.clone(),
                     "Operator key was not able to be collected".to_string(),
                 ));
             }
         }
-        for (index, key) in verifier_keys.iter().enumerate() {
-            if key.is_none() {
+        for verifier in verifiers {
+            if verifier.key.is_none() {
                 results.push(error_constructor(
                     EntityType::Verifier,
-                    format!("Index {index} in config (0-based)"),
+                    verifier.endpoint.clone(),
                     "Verifier key was not able to be collected".to_string(),
                 ));
             }
@@ -657,18 +1491,14 @@ impl Aggregator {
     ) -> Result<Vec<EntityStatusWithId>, BridgeError> {
         tracing::debug!("Getting entities status");
 
-        let operator_clients = self.get_operator_clients();
-        let verifier_clients = self.get_verifier_clients();
-        tracing::debug!("Operator clients: {:?}", operator_clients.len());
-
-        let (operator_keys, verifier_keys) = self.fetch_all_entity_keys().await;
+        let (operators, verifiers) = self.fetch_all_entity_keys().await?;
+        tracing::debug!("Operator clients: {:?}", operators.len());
 
         // Query operators for status
         let operator_status = join_all(
-            operator_clients
+            operators
                 .iter()
-                .zip(operator_keys.iter())
-                .filter_map(|(client, key)| key.as_ref().map(|k| (client, k)))
+                .filter_map(|member| member.key.as_ref().map(|k| (&member.client, k)))
                 .map(|(client, key)| {
                     let mut client = client.clone();
                     let key = *key;
@@ -697,10 +1527,9 @@ impl Aggregator {
 
         // Query verifiers for status
         let verifier_status = join_all(
-            verifier_clients
+            verifiers
                 .iter()
-                .zip(verifier_keys.iter())
-                .filter_map(|(client, key)| key.as_ref().map(|k| (client, k)))
+                .filter_map(|member| member.key.as_ref().map(|k| (&member.client, k)))
                 .map(|(client, key)| {
                     let mut client = client.clone();
                     let key = *key;
@@ -733,10 +1562,9 @@ impl Aggregator {
 
         // try to restart background tasks if requested, with a timeout
         if restart_tasks {
-            let operator_tasks = operator_clients
+            let operator_tasks = operators
                 .iter()
-                .zip(operator_keys.iter())
-                .filter_map(|(client, key)| key.map(|key| (client, key)))
+                .filter_map(|member| member.key.map(|key| (&member.client, key)))
                 .map(|(client, key)| {
                     let mut client = client.clone();
                     async move {
@@ -753,10 +1581,9 @@ impl Aggregator {
                     }
                 });
 
-            let verifier_tasks = verifier_clients
+            let verifier_tasks = verifiers
                 .iter()
-                .zip(verifier_keys.iter())
-                .filter_map(|(client, key)| key.map(|key| (client, key)))
+                .filter_map(|member| member.key.map(|key| (&member.client, key)))
                 .map(|(client, key)| {
                     let mut client = client.clone();
                     async move {
@@ -791,8 +1618,8 @@ impl Aggregator {
         // Add error entries for unreachable entities
         Self::add_unreachable_entity_errors(
             &mut entity_statuses,
-            &operator_keys,
-            &verifier_keys,
+            &operators,
+            &verifiers,
             |entity_type, id, error_msg| EntityStatusWithId {
                 entity_id: Some(RPCEntityId {
                     kind: entity_type as i32,
@@ -810,17 +1637,13 @@ impl Aggregator {
     pub async fn get_compatibility_data_from_entities(
         &self,
     ) -> Result<Vec<EntityDataWithId>, BridgeError> {
-        let operator_clients = self.get_operator_clients();
-        let verifier_clients = self.get_verifier_clients();
-
-        let (operator_keys, verifier_keys) = self.fetch_all_entity_keys().await;
+        let (operators, verifiers) = self.fetch_all_entity_keys().await?;
 
         // Query operators for compatibility data
         let operator_comp_data = join_all(
-            operator_clients
+            operators
                 .iter()
-                .zip(operator_keys.iter())
-                .filter_map(|(client, key)| key.as_ref().map(|k| (client, k)))
+                .filter_map(|member| member.key.as_ref().map(|k| (&member.client, k)))
                 .map(|(client, key)| {
                     let mut client = client.clone();
                     let key = *key;
@@ -847,10 +1670,9 @@ impl Aggregator {
 
         // Query verifiers for compatibility data
         let verifier_comp_data = join_all(
-            verifier_clients
+            verifiers
                 .iter()
-                .zip(verifier_keys.iter())
-                .filter_map(|(client, key)| key.as_ref().map(|k| (client, k)))
+                .filter_map(|member| member.key.as_ref().map(|k| (&member.client, k)))
                 .map(|(client, key)| {
                     let mut client = client.clone();
                     let key = *key;
@@ -900,8 +1722,8 @@ impl Aggregator {
         // Add error entries for unreachable entities
         Self::add_unreachable_entity_errors(
             &mut entities_comp_data,
-            &operator_keys,
-            &verifier_keys,
+            &operators,
+            &verifiers,
             |entity_type, id, error_msg| EntityDataWithId {
                 entity_id: Some(RPCEntityId {
                     kind: entity_type as i32,
@@ -914,6 +1736,46 @@ impl Aggregator {
         Ok(entities_comp_data)
     }
 
+    /// Stores the request compression negotiated with each member that
+    /// returned its compatibility params, and the batch size it accepts.
+    async fn set_negotiated_stream_params<C, K: PartialEq>(
+        members: &RwLock<Vec<Member<C, K>>>,
+        own_params: &CompatibilityParams,
+        results: &[(K, String, CompatibilityParams)],
+    ) {
+        for member in members.write().await.iter_mut() {
+            if let Some((_, id, params)) = results
+                .iter()
+                .find(|(key, _, _)| member.key.as_ref() == Some(key))
+            {
+                member.compression = own_params.negotiate_compression(params);
+                member.max_sigs_per_message = params.max_sigs_per_message;
+                tracing::debug!(
+                    "Negotiated gRPC compression with {id}: {:?}, max signatures per message: {}",
+                    member.compression,
+                    member.max_sigs_per_message
+                );
+            }
+        }
+    }
+
+    /// Returns the number of signatures per message each verifier accepts in
+    /// the streams sent to it, in the order of `verifiers`. Verifiers whose
+    /// compatibility params weren't collected get one signature per message.
+    pub async fn verifiers_max_sigs_per_message(&self, verifiers: &[VerifierId]) -> Vec<u32> {
+        let members = self.verifiers.read().await;
+        verifiers
+            .iter()
+            .map(|id| {
+                members
+                    .iter()
+                    .rev()
+                    .find(|member| member.key == Some(id.0))
+                    .map_or(0, |member| member.max_sigs_per_message)
+            })
+            .collect()
+    }
+
     /// Checks compatibility with other actors.
     /// Returns an error if aggregator is not compatible with any of the other actors, or any other actor returns an error.
     pub async fn check_compatibility_with_actors(
@@ -934,13 +1796,8 @@ impl Aggregator {
         );
 
         if operators_included {
-            let operator_keys = self.fetch_operator_keys().await?;
-            for (operator_id, operator_client) in operator_keys
-                .into_iter()
-                .map(OperatorId)
-                .zip(self.operator_clients.iter())
-            {
-                let mut operator_client = operator_client.clone();
+            for (mut operator_client, operator_key) in self.get_active_operators().await? {
+                let operator_id = OperatorId(operator_key);
                 let operator_id_str = operator_id.to_string();
 
                 operator_futures.push(async move {
@@ -955,19 +1812,14 @@ impl Aggregator {
                         .wrap_err(format!(
                             "{operator_id_str} compatibility params conversion failed"
                         ))?;
-                    Ok::<_, BridgeError>((operator_id_str, compatibility_params))
+                    Ok::<_, BridgeError>((operator_key, operator_id_str, compatibility_params))
                 });
             }
         }
 
         if verifiers_included {
-            let verifier_keys = self.fetch_verifier_keys().await?;
-            for (verifier_id, verifier_client) in verifier_keys
-                .into_iter()
-                .map(VerifierId)
-                .zip(self.verifier_clients.iter())
-            {
-                let mut verifier_client = verifier_client.clone();
+            for (mut verifier_client, verifier_key) in self.get_active_verifiers().await? {
+                let verifier_id = VerifierId(verifier_key);
                 let verifier_id_str = verifier_id.to_string();
 
                 verifier_futures.push(async move {
@@ -982,7 +1834,7 @@ impl Aggregator {
                         .wrap_err(format!(
                             "{verifier_id_str} compatibility params conversion failed"
                         ))?;
-                    Ok::<_, BridgeError>((verifier_id_str, compatibility_params))
+                    Ok::<_, BridgeError>((verifier_key, verifier_id_str, compatibility_params))
                 });
             }
         }
@@ -991,9 +1843,22 @@ impl Aggregator {
         let (operator_results, operator_err) = join_all_partition_results(operator_futures).await;
         let (verifier_results, verifier_err) = join_all_partition_results(verifier_futures).await;
 
+        // negotiate request compression with the actors that responded
+        let own_params = self.get_compatibility_params()?;
+        Self::set_negotiated_stream_params(&self.operators, &own_params, &operator_results).await;
+        Self::set_negotiated_stream_params(&self.verifiers, &own_params, &verifier_results).await;
+
         let mut actors_compat_params = Vec::new();
-        actors_compat_params.extend(operator_results);
-        actors_compat_params.extend(verifier_results);
+        actors_compat_params.extend(
+            operator_results
+                .into_iter()
+                .map(|(_, id, params)| (id, params)),
+        );
+        actors_compat_params.extend(
+            verifier_results
+                .into_iter()
+                .map(|(_, id, params)| (id, params)),
+        );
 
         if let Some(operator_err) = operator_err {
             errors.push(format!(
@@ -1056,6 +1921,18 @@ impl AggregatorServer {
     }
 }
 
+#[tonic::async_trait]
+impl ReadinessProvider for AggregatorServer {
+    async fn readiness(&self) -> Readiness {
+        aggregator_readiness(
+            &self.background_tasks,
+            &self.aggregator.db,
+            &self.aggregator.rpc,
+        )
+        .await
+    }
+}
+
 impl Deref for AggregatorServer {
     type Target = Aggregator;
 
@@ -1063,3 +1940,53 @@ impl Deref for AggregatorServer {
         &self.aggregator
     }
 }
+
+/// Returns the largest subset of `verifiers`, with at least `min_verifiers`
+/// members, whose N-of-N key is used by the deposit script paying to
+/// `deposit_script_pubkey`. `deposit_template` gives the other parts of the
+/// deposit script, see [`DepositData::get_deposit_scripts`].
+fn find_deposit_verifier_set(
+    deposit_template: &DepositData,
+    verifiers: &[PublicKey],
+    min_verifiers: usize,
+    deposit_script_pubkey: &ScriptBuf,
+    paramset: &'static ProtocolParamset,
+) -> Result<Option<Vec<PublicKey>>, BridgeError> {
+    for size in (min_verifiers.max(1)..=verifiers.len()).rev() {
+        for subset in subsets_of_size(verifiers, size) {
+            let mut candidate = deposit_template.clone();
+            candidate.nofn_xonly_pk = None;
+            candidate.actors.verifiers = subset.clone();
+            let scripts: Vec<ScriptBuf> = candidate
+                .get_deposit_scripts(paramset)?
+                .into_iter()
+                .map(|script| script.to_script_buf())
+                .collect();
+            if create_taproot_address(&scripts, None, paramset.network)
+                .0
+                .script_pubkey()
+                == *deposit_script_pubkey
+            {
+                return Ok(Some(subset));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Returns all subsets of `items` with `size` elements, in order.
+fn subsets_of_size<T: Clone>(items: &[T], size: usize) -> Vec<Vec<T>> {
+    let Some((first, rest)) = items.split_first() else {
+        return if size == 0 { vec![vec![]] } else { vec![] };
+    };
+    if size == 0 {
+        return vec![vec![]];
+    }
+
+    let mut subsets: Vec<Vec<T>> = subsets_of_size(rest, size - 1)
+        .into_iter()
+        .map(|subset| std::iter::once(first.clone()).chain(subset).collect())
+        .collect();
+    subsets.extend(subsets_of_size(rest, size));
+    subsets
+}
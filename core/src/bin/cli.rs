@@ -3,15 +3,28 @@
 use std::path::PathBuf;
 use std::str::FromStr;
 
-use bitcoin::{hashes::Hash, secp256k1::SecretKey, Network, ScriptBuf, Txid, XOnlyPublicKey};
+use bitcoin::{hashes::Hash, secp256k1::SecretKey, Network, Psbt, ScriptBuf, Txid, XOnlyPublicKey};
 use bitcoincore_rpc::{json::SignRawTransactionInput, Auth, Client, RpcApi};
 use bridge_circuit_host::docker::pull_or_load_all_images;
+use bridge_circuit_host::header_chain_verifier::{
+    diff_circuit_outputs, read_block_headers, read_circuit_output, verify_header_chain,
+};
+use circuits_lib::common::BitcoinNetwork;
+use circuits_lib::header_chain::{ChainState, CircuitBlockHeader, HeaderChainPrevProofType};
 use clap::{Parser, Subcommand};
 use clementine_core::{
     actor::Actor,
+    bridge_circuit_input::{
+        inspect_watchtower_challenge_tx, BridgeCircuitInputBuilder, DEFAULT_SCAN_DEPTH,
+    },
+    citrea::{CitreaClient, CitreaClientT},
     compatibility::CompatibilityParams,
+    config::protocol::{ProtocolParamset, ProtocolParamsetExt},
     config::BridgeConfig,
+    database::Database,
     deposit::SecurityCouncil,
+    extended_bitcoin_rpc::ExtendedBitcoinRpc,
+    proof_store::{copy_proofs, FsProofStore, PostgresProofStore, ProofId, ProofKind, ProofStore},
     rpc::clementine::{
         self, clementine_aggregator_client::ClementineAggregatorClient, deposit::DepositData,
         entity_data_with_id::DataResult, Actors, AggregatorWithdrawalInput, BaseDeposit, Deposit,
@@ -22,6 +35,7 @@ use clementine_core::{
 };
 use clementine_errors::TransactionType;
 use clementine_primitives::EVMAddress;
+use secrecy::SecretString;
 use tonic::Request;
 
 #[derive(Parser)]
@@ -58,12 +72,256 @@ enum Commands {
         #[command(subcommand)]
         command: BitcoinCommands,
     },
+    /// Tx sender commands, run directly against the tx sender database
+    /// Database is read from DB_HOST, DB_PORT, DB_USER, DB_PASSWORD and DB_NAME
+    #[cfg(feature = "automation")]
+    TxSender {
+        #[command(subcommand)]
+        command: TxSenderCommands,
+    },
+    /// Security council commands to co-sign replacement deposits offline
+    SecurityCouncil {
+        #[command(subcommand)]
+        command: SecurityCouncilCommands,
+    },
+    /// Offline emergency stop commands for the holder of the emergency stop encryption key
+    EmergencyStop {
+        #[command(subcommand)]
+        command: EmergencyStopCommands,
+    },
+    /// Native header chain verification, without a zkVM
+    HeaderChain {
+        #[command(subcommand)]
+        command: HeaderChainCommands,
+    },
+    /// Bridge circuit inspection commands
+    /// Give Bitcoin RPC URL as node-url
+    BridgeCircuit {
+        #[command(subcommand)]
+        command: BridgeCircuitCommands,
+    },
+    /// Proof store commands, run directly against a node's database
+    /// Database is read from DB_HOST, DB_PORT, DB_USER, DB_PASSWORD and DB_NAME
+    ProofStore {
+        #[command(subcommand)]
+        command: ProofStoreCommands,
+    },
     /// Print actor's taproot address and bitcoin wallet's new address
     PrintAddresses,
     /// Pull or load all prover images to ~/.clementine/images
     LoadProverImages,
 }
 
+#[cfg(feature = "automation")]
+#[derive(Subcommand)]
+enum TxSenderCommands {
+    /// Temporarily raise the fee budget, e.g. during incident response
+    RaiseFeeBudget {
+        /// Transaction type whose budget is raised (e.g. Kickoff), global budget if not given
+        #[arg(long, value_parser = parse_tx_type_name)]
+        tx_type: Option<String>,
+        /// Sats added to the hourly limit
+        #[arg(long, default_value_t = 0)]
+        extra_sats_per_hour: u64,
+        /// Sats added to the daily limit
+        #[arg(long, default_value_t = 0)]
+        extra_sats_per_day: u64,
+        /// How long the raise lasts
+        #[arg(long)]
+        duration_minutes: u64,
+        /// Why the budget is raised
+        #[arg(long)]
+        reason: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SecurityCouncilCommands {
+    /// Create an unsigned PSBT that replaces the deposit of a move tx
+    CreateReplacementPsbt {
+        #[arg(long)]
+        old_move_txid: Txid,
+        /// N-of-N x-only public key of the replaced deposit
+        #[arg(long)]
+        old_nofn_xonly_pk: XOnlyPublicKey,
+        /// N-of-N x-only public key of the replacement deposit
+        #[arg(long)]
+        new_nofn_xonly_pk: XOnlyPublicKey,
+        /// Security council in the format threshold:pk1,pk2,...
+        #[arg(long)]
+        security_council: SecurityCouncil,
+        /// Protocol paramset TOML file, for the network and the bridge amount
+        #[arg(long)]
+        protocol_params: PathBuf,
+    },
+    /// Sign a replacement PSBT with the key in SECURITY_COUNCIL_SECRET_KEY
+    SignReplacementPsbt {
+        /// Base64 encoded PSBT
+        #[arg(long)]
+        psbt: String,
+        /// Move txid of the deposit the PSBT must replace
+        #[arg(long)]
+        old_move_txid: Txid,
+        /// N-of-N x-only public key of the replaced deposit
+        #[arg(long)]
+        old_nofn_xonly_pk: XOnlyPublicKey,
+        /// N-of-N x-only public key the replacement deposit must be locked to
+        #[arg(long)]
+        new_nofn_xonly_pk: XOnlyPublicKey,
+        #[arg(long)]
+        security_council: SecurityCouncil,
+        /// Protocol paramset TOML file, for the network and the bridge amount
+        #[arg(long)]
+        protocol_params: PathBuf,
+    },
+    /// Combine replacement PSBTs signed by different council members
+    CombineReplacementPsbts {
+        /// Base64 encoded PSBTs
+        #[arg(long, num_args = 1..)]
+        psbts: Vec<String>,
+    },
+    /// Check the signatures against the threshold and print the signed tx
+    FinalizeReplacementPsbt {
+        /// Base64 encoded PSBT
+        #[arg(long)]
+        psbt: String,
+        #[arg(long)]
+        security_council: SecurityCouncil,
+    },
+}
+
+#[derive(Subcommand)]
+enum HeaderChainCommands {
+    /// Validate block headers natively and print the resulting chain state.
+    /// Headers are read from --headers-file, or fetched from the bitcoind at
+    /// node-url up to --to-height
+    Verify {
+        /// Block headers serialized back to back, 80 bytes each
+        #[arg(long, conflicts_with = "to_height")]
+        headers_file: Option<PathBuf>,
+        /// Last block height to fetch from bitcoind
+        #[arg(long)]
+        to_height: Option<u32>,
+        /// One of mainnet, testnet4, signet, regtest
+        #[arg(long, default_value = "mainnet")]
+        network: BitcoinNetwork,
+        /// Header chain proof or output to continue from, genesis if not given
+        #[arg(long)]
+        prev_proof: Option<PathBuf>,
+        /// Header chain proof or output to compare the result against
+        #[arg(long)]
+        expected: Option<PathBuf>,
+        #[arg(long)]
+        bitcoin_rpc_user: Option<String>,
+        #[arg(long)]
+        bitcoin_rpc_password: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum BridgeCircuitCommands {
+    /// Build the bridge circuit input of a kickoff from Bitcoin and Citrea RPCs
+    /// and execute the circuit with a mock zkVM to find the failing check.
+    /// The protocol paramset is read from --protocol-params, or from the
+    /// environment if not given
+    Inspect {
+        #[arg(long)]
+        kickoff_txid: Txid,
+        #[arg(long)]
+        protocol_params: Option<PathBuf>,
+        #[arg(long)]
+        citrea_rpc_url: String,
+        #[arg(long)]
+        citrea_light_client_prover_url: String,
+        #[arg(long, default_value_t = 5655)]
+        citrea_chain_id: u32,
+        /// Payout txid, searched in the blocks before the kickoff if not given
+        #[arg(long)]
+        payout_txid: Option<Txid>,
+        /// Last block of the header chain proof, latest finalized block if not given
+        #[arg(long)]
+        latest_blockhash: Option<bitcoin::BlockHash>,
+        /// Header chain proof or output to continue from, genesis state of the
+        /// paramset if not given
+        #[arg(long)]
+        prev_proof: Option<PathBuf>,
+        /// Number of blocks searched for the payout and watchtower challenge txs
+        #[arg(long, default_value_t = DEFAULT_SCAN_DEPTH)]
+        scan_depth: u32,
+        /// File to write the borsh-serialized bridge circuit input to
+        #[arg(long)]
+        output: Option<PathBuf>,
+        #[arg(long)]
+        bitcoin_rpc_user: Option<String>,
+        #[arg(long)]
+        bitcoin_rpc_password: Option<String>,
+    },
+    /// Inspect a watchtower challenge tx of a kickoff as the bridge circuit
+    /// would: whether the watchtower counts as a challenge sender, and the
+    /// total work of the committed work-only proof if it verifies
+    WatchtowerChallenge {
+        #[arg(long)]
+        kickoff_txid: Txid,
+        #[arg(long)]
+        challenge_txid: Txid,
+        #[arg(long)]
+        protocol_params: Option<PathBuf>,
+        #[arg(long)]
+        bitcoin_rpc_user: Option<String>,
+        #[arg(long)]
+        bitcoin_rpc_password: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProofStoreCommands {
+    /// List the proofs in the database
+    List {
+        #[arg(long)]
+        kind: Option<ProofKind>,
+    },
+    /// Copy proofs from the database to a directory. All proofs are copied if
+    /// no --kind or --id is given
+    Export {
+        #[arg(long)]
+        dir: PathBuf,
+        #[arg(long)]
+        kind: Option<ProofKind>,
+        /// Claim digest of a proof to export, can be repeated
+        #[arg(long)]
+        id: Vec<ProofId>,
+    },
+    /// Verify the proofs in a directory and copy them to the database
+    Import {
+        #[arg(long)]
+        dir: PathBuf,
+        #[arg(long)]
+        kind: Option<ProofKind>,
+        /// Claim digest of a proof to import, can be repeated
+        #[arg(long)]
+        id: Vec<ProofId>,
+    },
+}
+
+#[derive(Subcommand)]
+enum EmergencyStopCommands {
+    /// Decrypt, validate and batch emergency stop txs with the X25519 private key in
+    /// EMERGENCY_STOP_ENCRYPTION_PRIVATE_KEY
+    Prepare {
+        /// Encrypted emergency stop txs in the format
+        /// move_txid:nofn_xonly_pk:threshold:pk1,pk2,...:hex, as printed by
+        /// `aggregator internal-get-emergency-stop-tx`
+        #[arg(long, num_args = 1..)]
+        encrypted_txs: Vec<String>,
+        /// Protocol paramset TOML file, for the network and the bridge amount
+        #[arg(long)]
+        protocol_params: PathBuf,
+        /// Target fee rate in sat/vB
+        #[arg(long, default_value_t = 10)]
+        fee_rate: u64,
+    },
+}
+
 #[derive(Subcommand)]
 enum OperatorCommands {
     /// Get deposit keys
@@ -227,6 +485,31 @@ enum AggregatorCommands {
     },
     /// Get compatibility parameters for all entities
     GetCompatibilityParamsFromAll,
+    /// Add a verifier or operator, starting a new membership epoch
+    AddMember {
+        /// "verifier" or "operator"
+        #[arg(long)]
+        kind: String,
+        #[arg(long)]
+        endpoint: String,
+        /// Block height the change is effective at, defaults to the next block
+        #[arg(long)]
+        effective_height: Option<u32>,
+    },
+    /// Remove a verifier or operator from new deposits
+    RemoveMember {
+        /// "verifier" or "operator"
+        #[arg(long)]
+        kind: String,
+        /// Hex encoded public key of a verifier or x-only public key of an operator
+        #[arg(long)]
+        public_key: String,
+        /// Block height the change is effective at, defaults to the next block
+        #[arg(long)]
+        effective_height: Option<u32>,
+    },
+    /// List the recorded membership changes
+    GetMembershipChanges,
     /// Get vergen build information
     Vergen,
 }
@@ -340,7 +623,7 @@ async fn handle_operator_call(url: String, command: OperatorCommands) {
         }
         OperatorCommands::GetParams => {
             let params = operator
-                .get_params(Empty {})
+                .get_params(clementine_core::rpc::clementine::GetOperatorParamsRequest::default())
                 .await
                 .expect("Failed to make a request to operator");
             println!("Operator params: {params:?}");
@@ -564,6 +847,28 @@ async fn handle_verifier_call(url: String, command: VerifierCommands) {
     }
 }
 
+fn parse_member_kind(kind: &str) -> EntityType {
+    match kind {
+        "verifier" => EntityType::Verifier,
+        "operator" => EntityType::Operator,
+        _ => panic!("Member kind must be verifier or operator, got {kind}"),
+    }
+}
+
+fn print_membership_change(change: &clementine::MembershipChange) {
+    let kind = EntityType::try_from(change.kind)
+        .map(|kind| kind.as_str_name())
+        .unwrap_or("UNKNOWN");
+    let action = if change.removed { "remove" } else { "add" };
+    println!(
+        "Epoch {}: {action} {kind} {} ({}), effective at height {}",
+        change.epoch,
+        change.endpoint,
+        hex::encode(&change.public_key),
+        change.effective_height
+    );
+}
+
 async fn handle_aggregator_call(url: String, command: AggregatorCommands) {
     println!("Connecting to aggregator at {url}");
     let config = create_minimal_config();
@@ -829,17 +1134,32 @@ async fn handle_aggregator_call(url: String, command: AggregatorCommands) {
                 ))
                 .await
                 .expect("Failed to make a request");
-            println!("Emergency stop tx: {emergency_stop_tx:?}");
-            for (i, tx) in emergency_stop_tx
-                .into_inner()
-                .encrypted_emergency_stop_txs
-                .iter()
-                .enumerate()
+            let response = emergency_stop_tx.into_inner();
+            println!(
+                "Found {} of {} emergency stop txs, pass these to `emergency-stop prepare --encrypted-txs`:",
+                response.txids.len(),
+                move_txids.len()
+            );
+            for (((txid, encrypted_tx), nofn_xonly_pk), security_council) in response
+                .txids
+                .into_iter()
+                .zip(response.encrypted_emergency_stop_txs)
+                .zip(response.nofn_xonly_pks)
+                .zip(response.security_councils)
             {
+                let move_txid = Txid::try_from(txid).expect("Failed to parse move txid");
+                let nofn_xonly_pk =
+                    XOnlyPublicKey::try_from(nofn_xonly_pk).expect("Failed to parse N-of-N key");
+                let security_council = SecurityCouncil::try_from(security_council)
+                    .expect("Failed to parse security council");
                 println!(
-                    "Emergency stop tx {i} for move tx {}: {}",
-                    move_txids[i],
-                    hex::encode(tx)
+                    "{}",
+                    format_emergency_stop_entry(
+                        move_txid,
+                        nofn_xonly_pk,
+                        &security_council,
+                        &encrypted_tx
+                    )
                 );
             }
         }
@@ -1091,6 +1411,45 @@ async fn handle_aggregator_call(url: String, command: AggregatorCommands) {
                 println!("Tasks restart was requested and included in the request.");
             }
         }
+        AggregatorCommands::AddMember {
+            kind,
+            endpoint,
+            effective_height,
+        } => {
+            let response = aggregator
+                .add_member(Request::new(clementine::AddMemberRequest {
+                    kind: parse_member_kind(&kind) as i32,
+                    endpoint,
+                    effective_height: effective_height.unwrap_or(0),
+                }))
+                .await
+                .expect("Failed to make a request");
+            print_membership_change(response.get_ref());
+        }
+        AggregatorCommands::RemoveMember {
+            kind,
+            public_key,
+            effective_height,
+        } => {
+            let response = aggregator
+                .remove_member(Request::new(clementine::RemoveMemberRequest {
+                    kind: parse_member_kind(&kind) as i32,
+                    public_key: hex::decode(public_key).expect("Failed to decode public key"),
+                    effective_height: effective_height.unwrap_or(0),
+                }))
+                .await
+                .expect("Failed to make a request");
+            print_membership_change(response.get_ref());
+        }
+        AggregatorCommands::GetMembershipChanges => {
+            let response = aggregator
+                .get_membership_changes(Request::new(Empty {}))
+                .await
+                .expect("Failed to make a request");
+            for change in &response.get_ref().changes {
+                print_membership_change(change);
+            }
+        }
         AggregatorCommands::Vergen => {
             let params = Empty {};
             let response = aggregator
@@ -1378,6 +1737,623 @@ async fn handle_bitcoin_call(url: String, command: BitcoinCommands) {
     }
 }
 
+async fn handle_header_chain_call(url: Option<String>, command: HeaderChainCommands) {
+    match command {
+        HeaderChainCommands::Verify {
+            headers_file,
+            to_height,
+            network,
+            prev_proof,
+            expected,
+            bitcoin_rpc_user,
+            bitcoin_rpc_password,
+        } => {
+            let prev_proof = match prev_proof {
+                Some(path) => HeaderChainPrevProofType::PrevProof(
+                    read_circuit_output(&path).expect("Failed to read previous proof"),
+                ),
+                None => {
+                    HeaderChainPrevProofType::GenesisBlock(ChainState::new_for_network(network))
+                }
+            };
+            let next_height = match &prev_proof {
+                HeaderChainPrevProofType::GenesisBlock(state) => state.block_height,
+                HeaderChainPrevProofType::PrevProof(output) => output.chain_state.block_height,
+            }
+            .wrapping_add(1);
+
+            let headers = match (headers_file, to_height) {
+                (Some(path), _) => read_block_headers(&path).expect("Failed to read headers"),
+                (None, Some(to_height)) => {
+                    let Some(url) = url else {
+                        eprintln!("Error: Provide bitcoin RPC URL with --node-url");
+                        std::process::exit(1);
+                    };
+                    let rpc = Client::new(
+                        &url,
+                        Auth::UserPass(
+                            bitcoin_rpc_user.unwrap_or_default(),
+                            bitcoin_rpc_password.unwrap_or_default(),
+                        ),
+                    )
+                    .await
+                    .expect("Failed to connect to Bitcoin RPC");
+
+                    let mut headers = Vec::new();
+                    for height in next_height..=to_height {
+                        let hash = rpc
+                            .get_block_hash(height as u64)
+                            .await
+                            .expect("Failed to get block hash");
+                        let header = rpc
+                            .get_block_header(&hash)
+                            .await
+                            .expect("Failed to get block header");
+                        headers.push(CircuitBlockHeader::from(header));
+                    }
+                    headers
+                }
+                (None, None) => {
+                    eprintln!("Error: Provide either --headers-file or --to-height");
+                    std::process::exit(1);
+                }
+            };
+
+            println!(
+                "Verifying {} headers starting at height {next_height} on {network}",
+                headers.len()
+            );
+
+            // Validation failures are reported as errors below, don't print
+            // the panic messages of the circuit logic.
+            std::panic::set_hook(Box::new(|_| {}));
+            let output = match verify_header_chain(prev_proof, headers, network) {
+                Ok(output) => output,
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                }
+            };
+
+            let state = &output.chain_state;
+            println!("Block height: {}", state.block_height);
+            println!(
+                "Best block hash: {}",
+                bitcoin::BlockHash::from_byte_array(state.best_block_hash)
+            );
+            println!("Total work: {}", hex::encode(state.total_work));
+            println!("Chain state hash: {}", hex::encode(state.to_hash()));
+            println!(
+                "Genesis state hash: {}",
+                hex::encode(output.genesis_state_hash)
+            );
+
+            if let Some(path) = expected {
+                let expected = read_circuit_output(&path).expect("Failed to read expected output");
+                let mismatches = diff_circuit_outputs(&expected, &output);
+                if mismatches.is_empty() {
+                    println!("Output matches {}", path.display());
+                } else {
+                    for mismatch in mismatches {
+                        println!(
+                            "Mismatch in {}: expected {}, got {}",
+                            mismatch.field, mismatch.expected, mismatch.actual
+                        );
+                    }
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+async fn handle_bridge_circuit_call(url: Option<String>, command: BridgeCircuitCommands) {
+    match command {
+        BridgeCircuitCommands::Inspect {
+            kickoff_txid,
+            protocol_params,
+            citrea_rpc_url,
+            citrea_light_client_prover_url,
+            citrea_chain_id,
+            payout_txid,
+            latest_blockhash,
+            prev_proof,
+            scan_depth,
+            output,
+            bitcoin_rpc_user,
+            bitcoin_rpc_password,
+        } => {
+            let paramset = bridge_circuit_paramset(protocol_params);
+            let rpc = bridge_circuit_rpc(url, bitcoin_rpc_user, bitcoin_rpc_password).await;
+            let citrea_client = CitreaClient::new(
+                citrea_rpc_url,
+                citrea_light_client_prover_url,
+                citrea_chain_id,
+                None,
+                None,
+            )
+            .await
+            .expect("Failed to connect to Citrea");
+
+            let mut builder = BridgeCircuitInputBuilder::new(&rpc, &citrea_client, paramset)
+                .with_scan_depth(scan_depth);
+            if let Some(payout_txid) = payout_txid {
+                builder = builder.with_payout_txid(payout_txid);
+            }
+            if let Some(latest_blockhash) = latest_blockhash {
+                builder = builder.with_latest_blockhash(latest_blockhash);
+            }
+            if let Some(path) = prev_proof {
+                builder = builder.with_prev_header_chain_output(
+                    read_circuit_output(&path).expect("Failed to read previous proof"),
+                );
+            }
+
+            let built = match builder.build(kickoff_txid).await {
+                Ok(built) => built,
+                Err(e) => {
+                    eprintln!("Failed to build bridge circuit input: {e:?}");
+                    std::process::exit(1);
+                }
+            };
+
+            println!("Deposit index: {}", built.deposit_idx);
+            println!("Move txid: {}", built.move_txid);
+            println!("Operator: {}", built.operator_xonly_pk);
+            println!(
+                "Payout txid: {} (height {})",
+                built.payout_txid, built.payout_block_height
+            );
+            println!("Light client proof L2 height: {}", built.lcp_l2_height);
+            println!(
+                "Header chain height: {}",
+                built.input.hcp.chain_state.block_height
+            );
+            println!(
+                "Watchtower challenges: {}",
+                built.watchtower_challenge_txids.len()
+            );
+            for txid in &built.watchtower_challenge_txids {
+                println!("  {txid}");
+            }
+
+            if let Some(path) = output {
+                let bytes = borsh::to_vec(&built.input).expect("Failed to serialize input");
+                std::fs::write(&path, bytes).expect("Failed to write bridge circuit input");
+                println!("Bridge circuit input written to {}", path.display());
+            }
+
+            // Failures are reported below, don't print the panic messages of
+            // the circuit.
+            std::panic::set_hook(Box::new(|_| {}));
+            print!("{}", built.diagnose());
+            match built.execute_mock() {
+                Ok(journal) => {
+                    println!("All bridge circuit checks passed");
+                    println!("Journal hash: {}", hex::encode(journal));
+                }
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        BridgeCircuitCommands::WatchtowerChallenge {
+            kickoff_txid,
+            challenge_txid,
+            protocol_params,
+            bitcoin_rpc_user,
+            bitcoin_rpc_password,
+        } => {
+            let paramset = bridge_circuit_paramset(protocol_params);
+            let rpc = bridge_circuit_rpc(url, bitcoin_rpc_user, bitcoin_rpc_password).await;
+
+            // Rejections are reported below, don't print the panic messages of
+            // the circuit.
+            std::panic::set_hook(Box::new(|_| {}));
+            match inspect_watchtower_challenge_tx(&rpc, paramset, kickoff_txid, challenge_txid)
+                .await
+            {
+                Ok(inspection) => println!("{inspection}"),
+                Err(e) => {
+                    eprintln!("Failed to inspect watchtower challenge: {e:?}");
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+/// Paramset of the bridge circuit commands, read from a TOML file or from the
+/// environment if no file is given.
+fn bridge_circuit_paramset(protocol_params: Option<PathBuf>) -> &'static ProtocolParamset {
+    let paramset = match protocol_params {
+        Some(path) => ProtocolParamset::from_toml_file(&path),
+        None => ProtocolParamset::from_env(),
+    }
+    .expect("Failed to read protocol paramset");
+    // Leaking is fine in a one-shot command
+    Box::leak(Box::new(paramset))
+}
+
+async fn bridge_circuit_rpc(
+    url: Option<String>,
+    user: Option<String>,
+    password: Option<String>,
+) -> ExtendedBitcoinRpc {
+    let Some(url) = url else {
+        eprintln!("Error: Provide bitcoin RPC URL with --node-url");
+        std::process::exit(1);
+    };
+    ExtendedBitcoinRpc::connect(
+        url,
+        SecretString::new(user.unwrap_or_default().into()),
+        SecretString::new(password.unwrap_or_default().into()),
+        None,
+    )
+    .await
+    .expect("Failed to connect to Bitcoin RPC")
+}
+
+/// Paramset of the offline security council commands, read from a TOML file.
+fn offline_paramset(protocol_params: PathBuf) -> &'static ProtocolParamset {
+    let paramset = ProtocolParamset::from_toml_file(&protocol_params)
+        .expect("Failed to read protocol paramset");
+    // Leaking is fine in a one-shot command
+    Box::leak(Box::new(paramset))
+}
+
+/// Formats an encrypted emergency stop tx with the N-of-N key and security
+/// council of its deposit, the input format of `emergency-stop prepare`.
+fn format_emergency_stop_entry(
+    move_txid: Txid,
+    nofn_xonly_pk: XOnlyPublicKey,
+    security_council: &SecurityCouncil,
+    encrypted_tx: &[u8],
+) -> String {
+    format!(
+        "{move_txid}:{nofn_xonly_pk}:{security_council}:{}",
+        hex::encode(encrypted_tx)
+    )
+}
+
+/// Parses an entry formatted by [`format_emergency_stop_entry`].
+fn parse_emergency_stop_entry(entry: &str) -> (Txid, XOnlyPublicKey, SecurityCouncil, Vec<u8>) {
+    const FORMAT: &str =
+        "Encrypted tx must be in the format move_txid:nofn_xonly_pk:threshold:pk1,pk2,...:hex";
+    let (move_txid, rest) = entry.trim().split_once(':').expect(FORMAT);
+    let (nofn_xonly_pk, rest) = rest.split_once(':').expect(FORMAT);
+    let (security_council, encrypted_tx) = rest.rsplit_once(':').expect(FORMAT);
+
+    (
+        Txid::from_str(move_txid).expect("Failed to parse move txid"),
+        XOnlyPublicKey::from_str(nofn_xonly_pk).expect("Failed to parse N-of-N key"),
+        SecurityCouncil::from_str(security_council).expect("Failed to parse security council"),
+        hex::decode(encrypted_tx).expect("Failed to decode encrypted tx"),
+    )
+}
+
+fn parse_psbt(psbt: &str) -> Psbt {
+    Psbt::from_str(psbt.trim()).expect("Failed to parse base64 PSBT")
+}
+
+fn print_replacement_psbt_summary(psbt: &Psbt) {
+    for input in &psbt.unsigned_tx.input {
+        println!("Spends: {}", input.previous_output);
+    }
+    for (vout, output) in psbt.unsigned_tx.output.iter().enumerate() {
+        println!(
+            "Output {vout}: {} sats to {}",
+            output.value.to_sat(),
+            output.script_pubkey.to_hex_string()
+        );
+    }
+}
+
+fn handle_security_council_call(command: SecurityCouncilCommands) {
+    use clementine_core::builder::transaction::security_council::{
+        combine_replacement_deposit_psbts, create_replacement_deposit_psbt,
+        finalize_replacement_deposit_psbt, replacement_deposit_psbt_signers,
+        sign_replacement_deposit_psbt,
+    };
+
+    match command {
+        SecurityCouncilCommands::CreateReplacementPsbt {
+            old_move_txid,
+            old_nofn_xonly_pk,
+            new_nofn_xonly_pk,
+            security_council,
+            protocol_params,
+        } => {
+            let paramset = offline_paramset(protocol_params);
+
+            let psbt = create_replacement_deposit_psbt(
+                old_move_txid,
+                old_nofn_xonly_pk,
+                new_nofn_xonly_pk,
+                paramset,
+                security_council,
+            )
+            .expect("Failed to create replacement deposit PSBT");
+
+            print_replacement_psbt_summary(&psbt);
+            println!("Unsigned PSBT: {psbt}");
+        }
+        SecurityCouncilCommands::SignReplacementPsbt {
+            psbt,
+            old_move_txid,
+            old_nofn_xonly_pk,
+            new_nofn_xonly_pk,
+            security_council,
+            protocol_params,
+        } => {
+            let paramset = offline_paramset(protocol_params);
+            let secret_key = SecretKey::from_str(
+                &std::env::var("SECURITY_COUNCIL_SECRET_KEY")
+                    .expect("SECURITY_COUNCIL_SECRET_KEY is not set"),
+            )
+            .expect("Failed to parse secret key");
+            let mut psbt = parse_psbt(&psbt);
+
+            print_replacement_psbt_summary(&psbt);
+            sign_replacement_deposit_psbt(
+                &mut psbt,
+                secret_key,
+                old_move_txid,
+                old_nofn_xonly_pk,
+                new_nofn_xonly_pk,
+                paramset,
+                &security_council,
+            )
+            .expect("Failed to sign replacement deposit PSBT");
+
+            println!("Signed PSBT: {psbt}");
+        }
+        SecurityCouncilCommands::CombineReplacementPsbts { psbts } => {
+            let psbt = combine_replacement_deposit_psbts(
+                psbts.iter().map(|psbt| parse_psbt(psbt)).collect(),
+            )
+            .expect("Failed to combine PSBTs");
+
+            println!("Combined PSBT: {psbt}");
+        }
+        SecurityCouncilCommands::FinalizeReplacementPsbt {
+            psbt,
+            security_council,
+        } => {
+            let psbt = parse_psbt(&psbt);
+            let signers = replacement_deposit_psbt_signers(&psbt, &security_council)
+                .expect("Failed to verify signatures");
+            println!(
+                "Signed by {}/{} council members (threshold {})",
+                signers.len(),
+                security_council.pks.len(),
+                security_council.threshold
+            );
+
+            let tx = finalize_replacement_deposit_psbt(&psbt, &security_council)
+                .expect("Failed to finalize replacement deposit PSBT");
+
+            println!("Replacement deposit txid: {}", tx.compute_txid());
+            println!(
+                "Raw transaction: {}",
+                hex::encode(bitcoin::consensus::serialize(&tx))
+            );
+            println!("Broadcast it with the `bitcoin send-tx-with-cpfp` command");
+        }
+    }
+}
+
+fn handle_emergency_stop_call(command: EmergencyStopCommands) {
+    use clementine_core::builder::transaction::emergency_stop::{
+        batch_emergency_stop_txs, decrypt_emergency_stop_tx, emergency_stop_batch_psbt,
+        plan_emergency_stop_fee, validate_emergency_stop_tx,
+    };
+
+    match command {
+        EmergencyStopCommands::Prepare {
+            encrypted_txs,
+            protocol_params,
+            fee_rate,
+        } => {
+            let private_key = hex::decode(
+                std::env::var("EMERGENCY_STOP_ENCRYPTION_PRIVATE_KEY")
+                    .expect("EMERGENCY_STOP_ENCRYPTION_PRIVATE_KEY is not set"),
+            )
+            .expect("Failed to decode private key");
+            let paramset = offline_paramset(protocol_params);
+
+            let (txs, deposits): (Vec<_>, Vec<_>) = encrypted_txs
+                .iter()
+                .map(|entry| {
+                    let (move_txid, nofn_xonly_pk, security_council, encrypted_tx) =
+                        parse_emergency_stop_entry(entry);
+
+                    let tx = decrypt_emergency_stop_tx(&private_key, &encrypted_tx)
+                        .expect("Failed to decrypt emergency stop tx");
+                    validate_emergency_stop_tx(
+                        &tx,
+                        move_txid,
+                        nofn_xonly_pk,
+                        &security_council,
+                        paramset,
+                    )
+                    .unwrap_or_else(|e| {
+                        panic!("Invalid emergency stop tx for move tx {move_txid}: {e:?}")
+                    });
+                    println!(
+                        "Emergency stop tx {} for move tx {move_txid} is valid",
+                        tx.compute_txid()
+                    );
+                    (tx, (nofn_xonly_pk, security_council))
+                })
+                .unzip();
+
+            let batch = batch_emergency_stop_txs(&txs).expect("Failed to batch emergency stop txs");
+            let fee_rate =
+                bitcoin::FeeRate::from_sat_per_vb(fee_rate).expect("Fee rate is too large");
+            let plan =
+                plan_emergency_stop_fee(&batch, paramset, fee_rate).expect("Failed to plan fee");
+
+            println!(
+                "Batch of {} emergency stops: {} vB, pays {} sats, {} sats needed at {} sat/vB",
+                txs.len(),
+                plan.vsize,
+                plan.fee.to_sat(),
+                plan.required_fee.to_sat(),
+                fee_rate.to_sat_per_vb_floor()
+            );
+
+            match plan.funding_input {
+                None => {
+                    println!("Batch is ready to broadcast");
+                    println!(
+                        "Raw transaction: {}",
+                        hex::encode(bitcoin::consensus::serialize(&batch))
+                    );
+                }
+                Some(funding_input) => {
+                    let psbt = emergency_stop_batch_psbt(&batch, &deposits, paramset)
+                        .expect("Failed to create emergency stop PSBT");
+                    println!("Batch pays less than the target fee rate. To fund it, append:");
+                    println!(
+                        "  - a P2TR key path input of at least {} sats",
+                        funding_input.to_sat()
+                    );
+                    println!(
+                        "  - a P2TR change output of (input value - {}) sats",
+                        (plan.required_fee_with_funding - plan.fee).to_sat()
+                    );
+                    println!("The emergency stop inputs are finalized in this PSBT: {psbt}");
+                }
+            }
+        }
+    }
+}
+
+/// Accepts only transaction type names the fee budget is keyed by, so a typo
+/// doesn't raise a budget nothing uses.
+#[cfg(feature = "automation")]
+fn parse_tx_type_name(name: &str) -> Result<String, String> {
+    if !clementine_primitives::TransactionType::is_name(name) {
+        return Err(format!(
+            "unknown transaction type, expected one of {:?}",
+            clementine_primitives::TransactionType::ALL.map(|tx_type| tx_type.name())
+        ));
+    }
+
+    Ok(name.to_string())
+}
+
+#[cfg(feature = "automation")]
+async fn handle_tx_sender_call(command: TxSenderCommands) {
+    use clementine_tx_sender::config::TxSenderPostgresConfig;
+    use clementine_tx_sender::db::{fee_budget::FeeBudgetRaise, TxSenderDb};
+
+    let db_config = TxSenderPostgresConfig::from_env().expect("Failed to read DB_* env vars");
+    let db = TxSenderDb::connect(&db_config)
+        .await
+        .expect("Failed to connect to tx sender database");
+
+    match command {
+        TxSenderCommands::RaiseFeeBudget {
+            tx_type,
+            extra_sats_per_hour,
+            extra_sats_per_day,
+            duration_minutes,
+            reason,
+        } => {
+            let raise = FeeBudgetRaise {
+                extra_per_hour: bitcoin::Amount::from_sat(extra_sats_per_hour),
+                extra_per_day: bitcoin::Amount::from_sat(extra_sats_per_day),
+            };
+            let duration_secs = duration_minutes
+                .checked_mul(60)
+                .expect("Fee budget raise duration is too long");
+            let id = db
+                .save_fee_budget_raise(
+                    None,
+                    tx_type.as_deref(),
+                    raise,
+                    std::time::Duration::from_secs(duration_secs),
+                    reason.as_deref(),
+                )
+                .await
+                .expect("Failed to save fee budget raise");
+            let total = db
+                .get_active_fee_budget_raise(None, tx_type.as_deref())
+                .await
+                .expect("Failed to get active fee budget raises");
+            println!(
+                "Raised {} fee budget for {duration_minutes} minutes (override id {id})",
+                tx_type.as_deref().unwrap_or("global")
+            );
+            println!(
+                "Active raises: +{} sats/hour, +{} sats/day",
+                total.extra_per_hour.to_sat(),
+                total.extra_per_day.to_sat()
+            );
+        }
+    }
+}
+
+async fn handle_proof_store_call(command: ProofStoreCommands) {
+    let env_var = |name: &str| std::env::var(name).unwrap_or_else(|_| panic!("{name} is not set"));
+    let config = BridgeConfig {
+        db_host: env_var("DB_HOST"),
+        db_port: env_var("DB_PORT").parse().expect("Invalid DB_PORT"),
+        db_user: SecretString::new(env_var("DB_USER").into()),
+        db_password: SecretString::new(env_var("DB_PASSWORD").into()),
+        db_name: env_var("DB_NAME"),
+        ..Default::default()
+    };
+    let db = Database::new(&config)
+        .await
+        .expect("Failed to connect to database");
+    let db_store = PostgresProofStore::new(db);
+
+    let (copied, direction) = match command {
+        ProofStoreCommands::List { kind } => {
+            let entries = db_store.list(kind).await.expect("Failed to list proofs");
+            for (id, entry) in &entries {
+                let block_range = entry
+                    .metadata
+                    .block_range
+                    .map(|(start, end)| format!("{start}-{end}"))
+                    .unwrap_or_else(|| "-".to_string());
+                println!(
+                    "{id} {} {} blocks {block_range} method {} created {}",
+                    entry.metadata.kind,
+                    entry.metadata.network,
+                    hex::encode(entry.method_id),
+                    entry.metadata.created_at
+                );
+            }
+            println!("{} proofs", entries.len());
+            return;
+        }
+        ProofStoreCommands::Export { dir, kind, id } => {
+            let fs_store = FsProofStore::new(&dir).expect("Failed to open proof directory");
+            let copied = copy_proofs(&db_store, &fs_store, kind, &id)
+                .await
+                .expect("Failed to export proofs");
+            (copied, format!("to {}", dir.display()))
+        }
+        ProofStoreCommands::Import { dir, kind, id } => {
+            let fs_store = FsProofStore::new(&dir).expect("Failed to open proof directory");
+            let copied = copy_proofs(&fs_store, &db_store, kind, &id)
+                .await
+                .expect("Failed to import proofs");
+            (copied, format!("from {}", dir.display()))
+        }
+    };
+
+    for id in &copied {
+        println!("{id}");
+    }
+    println!("Copied {} proofs {direction}", copied.len());
+}
+
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
@@ -1427,6 +2403,25 @@ async fn main() {
             };
             handle_bitcoin_call(node_url, command).await;
         }
+        #[cfg(feature = "automation")]
+        Commands::TxSender { command } => {
+            handle_tx_sender_call(command).await;
+        }
+        Commands::SecurityCouncil { command } => {
+            handle_security_council_call(command);
+        }
+        Commands::EmergencyStop { command } => {
+            handle_emergency_stop_call(command);
+        }
+        Commands::HeaderChain { command } => {
+            handle_header_chain_call(cli.node_url, command).await;
+        }
+        Commands::BridgeCircuit { command } => {
+            handle_bridge_circuit_call(cli.node_url, command).await;
+        }
+        Commands::ProofStore { command } => {
+            handle_proof_store_call(command).await;
+        }
         Commands::PrintAddresses => {
             handle_print_addresses().await;
         }
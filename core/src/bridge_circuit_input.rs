@@ -0,0 +1,616 @@
+//! # Bridge Circuit Input Builder
+//!
+//! Builds the [`BridgeCircuitInput`] of a kickoff from public data only: the
+//! kickoff, payout and watchtower challenge transactions and the block headers
+//! come from Bitcoin RPC, the light client proof and the storage proofs from
+//! Citrea. Unlike the operator's assert flow, no database or proof receipts are
+//! needed, so anyone can rebuild the input for incident investigation or
+//! third-party verification and execute it with a mock zkVM to see which check
+//! of the bridge circuit fails.
+
+use crate::bitvm_client::ClementineBitVMPublicKeys;
+use crate::citrea::CitreaClientT;
+use crate::config::protocol::ProtocolParamset;
+use crate::extended_bitcoin_rpc::ExtendedBitcoinRpc;
+use crate::header_chain_prover::HeaderChainProver;
+use bitcoin::hashes::Hash;
+use bitcoin::{Block, BlockHash, OutPoint, Transaction, Txid, XOnlyPublicKey};
+use bitcoincore_rpc::RpcApi;
+use bridge_circuit_host::bridge_circuit_host::{
+    create_spv, execute_bridge_circuit_mock, MockExecutionError,
+};
+use bridge_circuit_host::diagnostics::{diagnose_bridge_circuit, BridgeCircuitReport};
+use bridge_circuit_host::header_chain_verifier::verify_header_chain;
+use bridge_circuit_host::structs::{
+    bridge_circuit_input_from_wt_tx, withdrawal_outpoint, WatchtowerContext,
+};
+use bridge_circuit_host::watchtower_challenge::{
+    inspect_watchtower_challenge, WatchtowerChallengeInspection,
+};
+use circuits_lib::bridge_circuit::parse_op_return_data;
+use circuits_lib::bridge_circuit::structs::BridgeCircuitInput;
+use circuits_lib::common::constants::FIRST_FIVE_OUTPUTS;
+use circuits_lib::common::BitcoinNetwork;
+use circuits_lib::header_chain::{
+    BlockHeaderCircuitOutput, CircuitBlockHeader, HeaderChainPrevProofType,
+};
+use clementine_errors::BridgeError;
+use eyre::{Context, OptionExt};
+
+/// Number of blocks searched for the payout transaction before the kickoff and
+/// for watchtower challenges after it, if not set.
+pub const DEFAULT_SCAN_DEPTH: u32 = 1008;
+
+/// A bridge circuit input built from chain data, along with the values found
+/// while building it.
+#[derive(Debug, Clone)]
+pub struct OnChainBridgeCircuitInput {
+    pub input: BridgeCircuitInput,
+    pub network: BitcoinNetwork,
+    pub deposit_idx: u32,
+    pub move_txid: Txid,
+    pub operator_xonly_pk: XOnlyPublicKey,
+    pub payout_txid: Txid,
+    pub payout_block_height: u32,
+    pub lcp_l2_height: u64,
+    pub watchtower_challenge_txids: Vec<Txid>,
+}
+
+impl OnChainBridgeCircuitInput {
+    /// Executes the bridge circuit on the input with a mock zkVM. See
+    /// [`execute_bridge_circuit_mock`].
+    pub fn execute_mock(&self) -> Result<Vec<u8>, MockExecutionError> {
+        execute_bridge_circuit_mock(&self.input, self.network)
+    }
+
+    /// Runs every check of the bridge circuit on the input. See
+    /// [`diagnose_bridge_circuit`].
+    pub fn diagnose(&self) -> BridgeCircuitReport {
+        diagnose_bridge_circuit(&self.input, self.network)
+    }
+}
+
+/// Builds bridge circuit inputs of kickoffs from Bitcoin and Citrea RPCs.
+#[derive(Debug)]
+pub struct BridgeCircuitInputBuilder<'a, C: CitreaClientT> {
+    rpc: &'a ExtendedBitcoinRpc,
+    citrea_client: &'a C,
+    paramset: &'static ProtocolParamset,
+    payout_txid: Option<Txid>,
+    latest_blockhash: Option<BlockHash>,
+    prev_header_chain_output: Option<BlockHeaderCircuitOutput>,
+    scan_depth: u32,
+}
+
+impl<'a, C: CitreaClientT> BridgeCircuitInputBuilder<'a, C> {
+    pub fn new(
+        rpc: &'a ExtendedBitcoinRpc,
+        citrea_client: &'a C,
+        paramset: &'static ProtocolParamset,
+    ) -> Self {
+        Self {
+            rpc,
+            citrea_client,
+            paramset,
+            payout_txid: None,
+            latest_blockhash: None,
+            prev_header_chain_output: None,
+            scan_depth: DEFAULT_SCAN_DEPTH,
+        }
+    }
+
+    /// Uses the given payout transaction instead of searching the blocks
+    /// before the kickoff for it.
+    pub fn with_payout_txid(mut self, payout_txid: Txid) -> Self {
+        self.payout_txid = Some(payout_txid);
+        self
+    }
+
+    /// Sets the last block of the header chain proof, which is the latest
+    /// block hash committed by the operator. The latest finalized block is
+    /// used if not set.
+    pub fn with_latest_blockhash(mut self, latest_blockhash: BlockHash) -> Self {
+        self.latest_blockhash = Some(latest_blockhash);
+        self
+    }
+
+    /// Continues the header chain from the given header chain circuit output
+    /// instead of the genesis chain state of the paramset.
+    pub fn with_prev_header_chain_output(mut self, output: BlockHeaderCircuitOutput) -> Self {
+        self.prev_header_chain_output = Some(output);
+        self
+    }
+
+    /// Sets the number of blocks searched for the payout and the watchtower
+    /// challenge transactions.
+    pub fn with_scan_depth(mut self, scan_depth: u32) -> Self {
+        self.scan_depth = scan_depth;
+        self
+    }
+
+    /// Builds the bridge circuit input of the kickoff with the given txid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the data can't be fetched or doesn't match
+    /// the kickoff, e.g. if the kickoff's deposit has no withdrawal yet.
+    pub async fn build(
+        &self,
+        kickoff_txid: Txid,
+    ) -> Result<OnChainBridgeCircuitInput, BridgeError> {
+        let network = BitcoinNetwork::from_bitcoin_network(self.paramset.network)
+            .ok_or_eyre(format!("Unsupported network {}", self.paramset.network))?;
+
+        let kickoff_tx = self.rpc.get_tx_of_txid(&kickoff_txid).await?;
+        let kickoff_height = self.block_height_of_tx(&kickoff_txid).await?;
+        let (move_txid, operator_xonly_pk) = parse_kickoff_op_return(&kickoff_tx)?;
+        tracing::info!(
+            "Kickoff {kickoff_txid} at height {kickoff_height} is for move txid {move_txid} and operator {operator_xonly_pk}"
+        );
+
+        let (deposit_idx, l2_height) = self.find_deposit_idx(move_txid).await?;
+
+        let current_storage_proof = self
+            .citrea_client
+            .get_storage_proof(l2_height, deposit_idx)
+            .await
+            .wrap_err(format!(
+                "Failed to get storage proof for deposit {deposit_idx} at l2 height {l2_height}"
+            ))?;
+        let wd_outpoint = withdrawal_outpoint(&current_storage_proof)
+            .wrap_err("Failed to decode withdrawal UTXO from storage proof")?;
+        if wd_outpoint.txid == Txid::all_zeros() {
+            return Err(eyre::eyre!("Deposit {deposit_idx} has no withdrawal on Citrea").into());
+        }
+        tracing::info!("Deposit {deposit_idx} was withdrawn with UTXO {wd_outpoint}");
+
+        let (payout_block, payout_block_height, payout_tx_index) =
+            self.find_payout(wd_outpoint, kickoff_height).await?;
+        let payout_tx = payout_block.txdata[payout_tx_index].clone();
+        let payout_txid = payout_tx.compute_txid();
+        tracing::info!("Found payout tx {payout_txid} at height {payout_block_height}");
+
+        let (light_client_proof, _lcp_receipt, lcp_l2_height) = self
+            .citrea_client
+            .get_light_client_proof(payout_block_height.into(), self.paramset)
+            .await?
+            .ok_or_eyre(format!(
+                "No light client proof found for payout block height {payout_block_height}"
+            ))?;
+
+        let storage_proof = self
+            .citrea_client
+            .get_storage_proof(lcp_l2_height, deposit_idx)
+            .await
+            .wrap_err(format!(
+                "Failed to get storage proof for deposit {deposit_idx} at l2 height {lcp_l2_height}"
+            ))?;
+
+        let (header_chain_output, block_hashes) = self.header_chain_output(network).await?;
+        let latest_height = header_chain_output.chain_state.block_height;
+        if payout_block_height > latest_height {
+            return Err(eyre::eyre!(
+                "Payout block height {payout_block_height} is after the last block of the header chain ({latest_height})"
+            )
+            .into());
+        }
+
+        let spv = create_spv(
+            payout_tx,
+            &block_hashes,
+            payout_block,
+            payout_block_height,
+            self.paramset.genesis_height,
+            payout_tx_index as u32,
+        )?;
+
+        let watchtower_challenge_connector_start_idx = watchtower_challenge_connector_start_idx();
+
+        let watchtower_txs = self
+            .find_watchtower_challenges(
+                kickoff_txid,
+                kickoff_height,
+                watchtower_challenge_connector_start_idx,
+            )
+            .await?;
+        let watchtower_challenge_txids = watchtower_txs
+            .iter()
+            .map(Transaction::compute_txid)
+            .collect::<Vec<_>>();
+        tracing::info!(
+            "Found {} watchtower challenges: {:?}",
+            watchtower_challenge_txids.len(),
+            watchtower_challenge_txids
+        );
+
+        let mut wt_contexts = Vec::with_capacity(watchtower_txs.len());
+        for tx in watchtower_txs {
+            let prevout_txs = self.rpc.get_prevout_txs(&tx).await?;
+            wt_contexts.push(WatchtowerContext {
+                watchtower_tx: tx,
+                prevout_txs,
+            });
+        }
+
+        let input = bridge_circuit_input_from_wt_tx(
+            kickoff_tx,
+            spv,
+            header_chain_output,
+            light_client_proof,
+            storage_proof,
+            &wt_contexts,
+            watchtower_challenge_connector_start_idx,
+        )
+        .wrap_err("Failed to create bridge circuit input")?;
+
+        Ok(OnChainBridgeCircuitInput {
+            input,
+            network,
+            deposit_idx,
+            move_txid,
+            operator_xonly_pk,
+            payout_txid,
+            payout_block_height,
+            lcp_l2_height,
+            watchtower_challenge_txids,
+        })
+    }
+
+    async fn block_height_of_tx(&self, txid: &Txid) -> Result<u32, BridgeError> {
+        let block_hash = self.rpc.get_blockhash_of_tx(txid).await?;
+        let height = self
+            .rpc
+            .get_block_info(&block_hash)
+            .await
+            .wrap_err(format!("Failed to get block info of {block_hash}"))?
+            .height;
+        Ok(u32::try_from(height).wrap_err("Block height doesn't fit in u32")?)
+    }
+
+    /// Returns the index of the deposit with the given move txid, checking
+    /// replacement deposits as well, and the L2 height it was searched at.
+    async fn find_deposit_idx(&self, move_txid: Txid) -> Result<(u32, u64), BridgeError> {
+        let l2_height: u64 = self
+            .citrea_client
+            .get_current_l2_block_height()
+            .await?
+            .into();
+
+        let deposits = self
+            .citrea_client
+            .collect_deposit_move_txids(None, l2_height)
+            .await?;
+        let mut deposit_idx = deposits
+            .iter()
+            .find(|(_, txid)| *txid == move_txid)
+            .map(|(idx, _)| *idx);
+
+        if deposit_idx.is_none() {
+            deposit_idx = self
+                .citrea_client
+                .get_replacement_deposit_move_txids(0, l2_height)
+                .await?
+                .iter()
+                .find(|(_, txid)| *txid == move_txid)
+                .map(|(idx, _)| u64::from(*idx));
+        }
+
+        let deposit_idx = deposit_idx.ok_or_eyre(format!(
+            "Move txid {move_txid} of the kickoff is not a deposit on Citrea"
+        ))?;
+
+        Ok((
+            u32::try_from(deposit_idx).wrap_err("Deposit index doesn't fit in u32")?,
+            l2_height,
+        ))
+    }
+
+    /// Returns the block of the payout transaction, its height and the index
+    /// of the payout transaction in it.
+    async fn find_payout(
+        &self,
+        wd_outpoint: OutPoint,
+        kickoff_height: u32,
+    ) -> Result<(Block, u32, usize), BridgeError> {
+        if let Some(payout_txid) = self.payout_txid {
+            let height = self.block_height_of_tx(&payout_txid).await?;
+            let block = self.rpc.get_block_by_height(height.into()).await?;
+            let tx_index = block
+                .txdata
+                .iter()
+                .position(|tx| tx.compute_txid() == payout_txid)
+                .ok_or_eyre(format!(
+                    "Payout tx {payout_txid} not found in block at height {height}"
+                ))?;
+            if !block.txdata[tx_index]
+                .input
+                .iter()
+                .any(|input| input.previous_output == wd_outpoint)
+            {
+                return Err(eyre::eyre!(
+                    "Payout tx {payout_txid} does not spend the withdrawal UTXO {wd_outpoint}"
+                )
+                .into());
+            }
+            return Ok((block, height, tx_index));
+        }
+
+        // The payout always comes before the kickoff, search backwards.
+        let lowest_height = kickoff_height
+            .saturating_sub(self.scan_depth)
+            .max(self.paramset.genesis_height);
+        for height in (lowest_height..=kickoff_height).rev() {
+            let block = self.rpc.get_block_by_height(height.into()).await?;
+            if let Some(tx_index) = block.txdata.iter().position(|tx| {
+                tx.input
+                    .iter()
+                    .any(|input| input.previous_output == wd_outpoint)
+            }) {
+                return Ok((block, height, tx_index));
+            }
+        }
+
+        Err(eyre::eyre!(
+            "No tx spending the withdrawal UTXO {wd_outpoint} in blocks {lowest_height} to {kickoff_height}, give the payout txid instead"
+        )
+        .into())
+    }
+
+    /// Returns the header chain circuit output up to the latest block and the
+    /// hashes of all blocks from the genesis height up to it.
+    async fn header_chain_output(
+        &self,
+        network: BitcoinNetwork,
+    ) -> Result<(BlockHeaderCircuitOutput, Vec<[u8; 32]>), BridgeError> {
+        let genesis_height = self.paramset.genesis_height;
+
+        let latest_height = match self.latest_blockhash {
+            Some(latest_blockhash) => u32::try_from(
+                self.rpc
+                    .get_block_info(&latest_blockhash)
+                    .await
+                    .wrap_err(format!("Failed to get block info of {latest_blockhash}"))?
+                    .height,
+            )
+            .wrap_err("Block height doesn't fit in u32")?,
+            None => self
+                .rpc
+                .get_current_chain_height()
+                .await?
+                .saturating_sub(self.paramset.finality_depth - 1),
+        };
+
+        let prev_proof = match &self.prev_header_chain_output {
+            Some(output) => HeaderChainPrevProofType::PrevProof(output.clone()),
+            None => HeaderChainPrevProofType::GenesisBlock(
+                HeaderChainProver::get_chain_state_from_height(
+                    self.rpc,
+                    genesis_height.into(),
+                    self.paramset.network,
+                )
+                .await?,
+            ),
+        };
+        let start_height = match &prev_proof {
+            HeaderChainPrevProofType::GenesisBlock(state) => state.block_height,
+            HeaderChainPrevProofType::PrevProof(output) => output.chain_state.block_height,
+        };
+        if start_height > latest_height {
+            return Err(eyre::eyre!(
+                "Header chain starts at height {start_height}, after the latest block height {latest_height}"
+            )
+            .into());
+        }
+
+        let mut block_hashes = Vec::new();
+        let mut headers = Vec::new();
+        for height in genesis_height..=latest_height {
+            let block_hash = self
+                .rpc
+                .get_block_hash(height.into())
+                .await
+                .wrap_err(format!("Failed to get block hash at height {height}"))?;
+            if height > start_height {
+                let header = self
+                    .rpc
+                    .get_block_header(&block_hash)
+                    .await
+                    .wrap_err(format!("Failed to get block header of {block_hash}"))?;
+                headers.push(CircuitBlockHeader::from(header));
+            }
+            block_hashes.push(block_hash.to_byte_array());
+        }
+
+        if let Some(latest_blockhash) = self.latest_blockhash {
+            if block_hashes.last() != Some(&latest_blockhash.to_byte_array()) {
+                return Err(eyre::eyre!(
+                    "Latest block hash {latest_blockhash} is not in the best chain"
+                )
+                .into());
+            }
+        }
+
+        let output = verify_header_chain(prev_proof, headers, network)
+            .wrap_err("Block headers are not valid")?;
+        if output.genesis_state_hash != self.paramset.genesis_chain_state_hash {
+            tracing::warn!(
+                "Genesis state hash of the header chain ({}) does not match the paramset ({})",
+                hex::encode(output.genesis_state_hash),
+                hex::encode(self.paramset.genesis_chain_state_hash)
+            );
+        }
+
+        Ok((output, block_hashes))
+    }
+
+    /// Returns the watchtower challenge transactions of the kickoff found in
+    /// the blocks after it.
+    async fn find_watchtower_challenges(
+        &self,
+        kickoff_txid: Txid,
+        kickoff_height: u32,
+        watchtower_challenge_connector_start_idx: u32,
+    ) -> Result<Vec<Transaction>, BridgeError> {
+        let last_height = self
+            .rpc
+            .get_current_chain_height()
+            .await?
+            .min(kickoff_height.saturating_add(self.scan_depth));
+
+        let mut challenges = Vec::new();
+        for height in kickoff_height..=last_height {
+            let block = self.rpc.get_block_by_height(height.into()).await?;
+            challenges.extend(block.txdata.into_iter().filter(|tx| {
+                is_watchtower_challenge(tx, kickoff_txid, watchtower_challenge_connector_start_idx)
+            }));
+        }
+
+        Ok(challenges)
+    }
+}
+
+/// Fetches a watchtower challenge of a kickoff and inspects it as the bridge
+/// circuit would. See [`inspect_watchtower_challenge`].
+///
+/// # Errors
+///
+/// Returns an error if the transactions can't be fetched or the challenge
+/// doesn't spend a watchtower challenge connector of the kickoff.
+pub async fn inspect_watchtower_challenge_tx(
+    rpc: &ExtendedBitcoinRpc,
+    paramset: &ProtocolParamset,
+    kickoff_txid: Txid,
+    challenge_txid: Txid,
+) -> Result<WatchtowerChallengeInspection, BridgeError> {
+    let network = BitcoinNetwork::from_bitcoin_network(paramset.network)
+        .ok_or_eyre(format!("Unsupported network {}", paramset.network))?;
+    let kickoff_tx = rpc.get_tx_of_txid(&kickoff_txid).await?;
+    let challenge_tx = rpc.get_tx_of_txid(&challenge_txid).await?;
+    let prevout_txs = rpc.get_prevout_txs(&challenge_tx).await?;
+
+    let inspection = inspect_watchtower_challenge(
+        &kickoff_tx,
+        &WatchtowerContext {
+            watchtower_tx: challenge_tx,
+            prevout_txs,
+        },
+        watchtower_challenge_connector_start_idx(),
+        paramset.genesis_chain_state_hash,
+        network,
+    )
+    .wrap_err(format!(
+        "Failed to inspect {challenge_txid} as a watchtower challenge of kickoff {kickoff_txid}"
+    ))?;
+    Ok(inspection)
+}
+
+/// Output index of the first watchtower challenge connector of a kickoff.
+fn watchtower_challenge_connector_start_idx() -> u32 {
+    (FIRST_FIVE_OUTPUTS + ClementineBitVMPublicKeys::number_of_assert_txs()) as u32
+}
+
+/// Parses the move txid and the operator's x-only public key from the
+/// OP_RETURN output of a kickoff transaction.
+pub fn parse_kickoff_op_return(
+    kickoff_tx: &Transaction,
+) -> Result<(Txid, XOnlyPublicKey), BridgeError> {
+    let data = kickoff_tx
+        .output
+        .iter()
+        .find(|output| output.script_pubkey.is_op_return())
+        .and_then(|output| parse_op_return_data(&output.script_pubkey))
+        .ok_or_eyre("Kickoff tx has no OP_RETURN output")?;
+
+    if data.len() != 64 {
+        return Err(eyre::eyre!(
+            "Kickoff OP_RETURN data is {} bytes, expected 64",
+            data.len()
+        )
+        .into());
+    }
+
+    let move_txid = Txid::from_slice(&data[..32]).wrap_err("Invalid move txid in kickoff")?;
+    let operator_xonly_pk = XOnlyPublicKey::from_slice(&data[32..])
+        .wrap_err("Invalid operator x-only public key in kickoff")?;
+
+    Ok((move_txid, operator_xonly_pk))
+}
+
+/// A watchtower challenge spends only the challenge connector of its
+/// watchtower, which is at an even offset from the connector start index.
+/// Challenge timeouts spend the acknowledgement connector next to it as well.
+fn is_watchtower_challenge(
+    tx: &Transaction,
+    kickoff_txid: Txid,
+    watchtower_challenge_connector_start_idx: u32,
+) -> bool {
+    let kickoff_vouts = tx
+        .input
+        .iter()
+        .filter(|input| input.previous_output.txid == kickoff_txid)
+        .map(|input| input.previous_output.vout)
+        .collect::<Vec<_>>();
+
+    matches!(
+        kickoff_vouts.as_slice(),
+        [vout] if *vout >= watchtower_challenge_connector_start_idx
+            && (*vout - watchtower_challenge_connector_start_idx) % 2 == 0
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::{absolute::LockTime, transaction::Version, TxIn};
+
+    fn spending(outpoints: &[OutPoint]) -> Transaction {
+        Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: outpoints
+                .iter()
+                .map(|outpoint| TxIn {
+                    previous_output: *outpoint,
+                    ..Default::default()
+                })
+                .collect(),
+            output: vec![],
+        }
+    }
+
+    #[test]
+    fn watchtower_challenge_detection() {
+        let kickoff_txid = Txid::from_byte_array([1; 32]);
+        let other_txid = Txid::from_byte_array([2; 32]);
+        let start_idx = 10;
+        let kickoff_output = |vout| OutPoint {
+            txid: kickoff_txid,
+            vout,
+        };
+
+        let challenge = spending(&[
+            kickoff_output(start_idx + 4),
+            OutPoint {
+                txid: other_txid,
+                vout: 0,
+            },
+        ]);
+        assert!(is_watchtower_challenge(&challenge, kickoff_txid, start_idx));
+
+        // Acknowledgement connector
+        let ack = spending(&[kickoff_output(start_idx + 5)]);
+        assert!(!is_watchtower_challenge(&ack, kickoff_txid, start_idx));
+
+        // Challenge timeout spends both connectors
+        let timeout = spending(&[kickoff_output(start_idx), kickoff_output(start_idx + 1)]);
+        assert!(!is_watchtower_challenge(&timeout, kickoff_txid, start_idx));
+
+        // Outputs before the watchtower connectors
+        let assert_tx = spending(&[kickoff_output(start_idx - 2)]);
+        assert!(!is_watchtower_challenge(
+            &assert_tx,
+            kickoff_txid,
+            start_idx
+        ));
+    }
+}
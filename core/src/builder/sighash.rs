@@ -538,7 +538,7 @@ mod tests {
 
     /// Returns the hash of all round txs txids for a given operator.
     async fn compute_hash_of_round_txs(
-        mut operator: ClementineOperatorClient<tonic::transport::Channel>,
+        mut operator: ClementineOperatorClient<crate::rpc::TracedChannel>,
         deposit_outpoint: OutPoint,
         operator_xonly_pk: XOnlyPublicKey,
         deposit_blockhash: bitcoin::BlockHash,
@@ -0,0 +1,517 @@
+//! # Emergency Stop Transactions
+//!
+//! Signed emergency stop transactions (see [`super::create_emergency_stop_txhandler`]) are
+//! stored by the aggregator encrypted to `emergency_stop_encryption_public_key`. This module
+//! contains the offline tooling for the holder of the matching private key: decrypting the
+//! transactions, checking them against the deposit they stop, batching several of them into
+//! one transaction and calculating how much extra funding is needed for a target fee rate.
+//!
+//! Emergency stop inputs are signed with `SinglePlusAnyoneCanPay`, so input `i` only commits to
+//! output `i`. Transactions can be batched by concatenating their inputs and outputs in the same
+//! order, and fee paying inputs and change outputs can be appended after them.
+
+use super::input::UtxoVout;
+use crate::bitvm_client::SECP;
+use crate::builder::address::create_taproot_address;
+use crate::builder::script::{CheckSig, Multisig, SpendableScript};
+use crate::config::protocol::ProtocolParamset;
+use crate::constants::MIN_TAPROOT_AMOUNT;
+use crate::deposit::SecurityCouncil;
+use bitcoin::hashes::Hash;
+use bitcoin::psbt::Psbt;
+use bitcoin::secp256k1::Message;
+use bitcoin::sighash::{Prevouts, SighashCache};
+use bitcoin::taproot::{self, LeafVersion, TapLeafHash};
+use bitcoin::{
+    Amount, FeeRate, OutPoint, TapSighashType, Transaction, TxOut, Txid, XOnlyPublicKey,
+};
+use clementine_errors::BridgeError;
+use eyre::{Context, OptionExt};
+use std::collections::HashSet;
+
+/// Virtual size of a P2TR key path input, rounded up.
+const P2TR_KEYPATH_INPUT_VBYTES: u64 = 58;
+/// Virtual size of a P2TR output.
+const P2TR_OUTPUT_VBYTES: u64 = 43;
+
+/// Decrypts an emergency stop transaction stored by the aggregator.
+pub fn decrypt_emergency_stop_tx(
+    encryption_private_key: &[u8],
+    encrypted_tx: &[u8],
+) -> Result<Transaction, BridgeError> {
+    let tx_bytes = crate::encryption::decrypt_bytes(encryption_private_key, encrypted_tx)
+        .wrap_err("Failed to decrypt emergency stop tx")?;
+    Ok(bitcoin::consensus::deserialize(&tx_bytes)
+        .wrap_err("Failed to deserialize emergency stop tx")?)
+}
+
+/// Checks that `tx` is a signed emergency stop transaction of the deposit moved with
+/// `move_txid`: it must spend the deposit output of the move tx with a valid N-of-N signature
+/// and send the funds to the security council.
+pub fn validate_emergency_stop_tx(
+    tx: &Transaction,
+    move_txid: Txid,
+    nofn_xonly_pk: XOnlyPublicKey,
+    security_council: &SecurityCouncil,
+    paramset: &'static ProtocolParamset,
+) -> Result<(), BridgeError> {
+    if tx.input.len() != 1 || tx.output.len() != 1 {
+        return Err(
+            eyre::eyre!("Emergency stop tx must have exactly one input and one output").into(),
+        );
+    }
+
+    let expected_outpoint = OutPoint {
+        txid: move_txid,
+        vout: UtxoVout::DepositInMove.get_vout(),
+    };
+    if tx.input[0].previous_output != expected_outpoint {
+        return Err(eyre::eyre!(
+            "Emergency stop tx spends {}, expected {expected_outpoint}",
+            tx.input[0].previous_output
+        )
+        .into());
+    }
+
+    let multisig_script = Multisig::from_security_council(security_council.clone()).to_script_buf();
+    let (council_address, _) =
+        create_taproot_address(&[multisig_script.clone()], None, paramset.network);
+    if tx.output[0].script_pubkey != council_address.script_pubkey() {
+        return Err(eyre::eyre!("Emergency stop tx does not pay to the security council").into());
+    }
+    if tx.output[0].value > paramset.bridge_amount {
+        return Err(
+            eyre::eyre!("Emergency stop tx output is larger than the bridge amount").into(),
+        );
+    }
+
+    let nofn_script = CheckSig::new(nofn_xonly_pk).to_script_buf();
+    let (move_address, spend_info) = create_taproot_address(
+        &[nofn_script.clone(), multisig_script],
+        None,
+        paramset.network,
+    );
+    let control_block = spend_info
+        .control_block(&(nofn_script.clone(), LeafVersion::TapScript))
+        .ok_or_eyre("Failed to find control block for N-of-N script")?;
+
+    let witness = &tx.input[0].witness;
+    if witness.len() != 3
+        || witness.nth(1) != Some(nofn_script.as_bytes())
+        || witness.nth(2) != Some(control_block.serialize().as_slice())
+    {
+        return Err(eyre::eyre!(
+            "Emergency stop tx does not spend the N-of-N script of the move tx"
+        )
+        .into());
+    }
+
+    let signature = taproot::Signature::from_slice(witness.nth(0).unwrap_or_default())
+        .wrap_err("Failed to parse emergency stop signature")?;
+    if signature.sighash_type != TapSighashType::SinglePlusAnyoneCanPay {
+        return Err(eyre::eyre!(
+            "Emergency stop signature uses sighash type {}, expected {}",
+            signature.sighash_type,
+            TapSighashType::SinglePlusAnyoneCanPay
+        )
+        .into());
+    }
+
+    let prevout = TxOut {
+        value: paramset.bridge_amount,
+        script_pubkey: move_address.script_pubkey(),
+    };
+    let sighash = SighashCache::new(tx)
+        .taproot_script_spend_signature_hash(
+            0,
+            &Prevouts::One(0, &prevout),
+            TapLeafHash::from_script(&nofn_script, LeafVersion::TapScript),
+            signature.sighash_type,
+        )
+        .wrap_err("Failed to calculate emergency stop sighash")?;
+    SECP.verify_schnorr(
+        &signature.signature,
+        &Message::from_digest(sighash.to_byte_array()),
+        &nofn_xonly_pk,
+    )
+    .wrap_err("Invalid N-of-N signature in emergency stop tx")?;
+
+    Ok(())
+}
+
+/// Combines emergency stop transactions into one transaction. Inputs and outputs keep their
+/// order so every signature still commits to the output at its own index.
+pub fn batch_emergency_stop_txs(txs: &[Transaction]) -> Result<Transaction, BridgeError> {
+    let first = txs.first().ok_or_eyre("No emergency stop txs to batch")?;
+
+    let mut batch = Transaction {
+        version: first.version,
+        lock_time: first.lock_time,
+        input: vec![],
+        output: vec![],
+    };
+    let mut spent = HashSet::new();
+    for tx in txs {
+        if tx.version != batch.version || tx.lock_time != batch.lock_time {
+            return Err(eyre::eyre!(
+                "Emergency stop tx {} has a different version or locktime",
+                tx.compute_txid()
+            )
+            .into());
+        }
+        if tx.input.len() != tx.output.len() {
+            return Err(eyre::eyre!(
+                "Emergency stop tx {} has different numbers of inputs and outputs",
+                tx.compute_txid()
+            )
+            .into());
+        }
+        for input in &tx.input {
+            if !spent.insert(input.previous_output) {
+                return Err(
+                    eyre::eyre!("{} is spent twice in the batch", input.previous_output).into(),
+                );
+            }
+        }
+        batch.input.extend(tx.input.iter().cloned());
+        batch.output.extend(tx.output.iter().cloned());
+    }
+
+    Ok(batch)
+}
+
+/// Creates a PSBT of a batch created with [`batch_emergency_stop_txs`] with the signed inputs
+/// finalized, so that a wallet can append a funding input and a change output.
+///
+/// `deposits` holds the N-of-N key and security council of the deposit spent by each input of
+/// the batch, in input order.
+pub fn emergency_stop_batch_psbt(
+    batch: &Transaction,
+    deposits: &[(XOnlyPublicKey, SecurityCouncil)],
+    paramset: &'static ProtocolParamset,
+) -> Result<Psbt, BridgeError> {
+    if deposits.len() != batch.input.len() {
+        return Err(eyre::eyre!(
+            "Got the keys of {} deposits for a batch of {} emergency stops",
+            deposits.len(),
+            batch.input.len()
+        )
+        .into());
+    }
+
+    let mut unsigned_tx = batch.clone();
+    for input in unsigned_tx.input.iter_mut() {
+        input.witness.clear();
+    }
+    let mut psbt =
+        Psbt::from_unsigned_tx(unsigned_tx).wrap_err("Failed to create emergency stop PSBT")?;
+    for ((psbt_input, input), (nofn_xonly_pk, security_council)) in psbt
+        .inputs
+        .iter_mut()
+        .zip(batch.input.iter())
+        .zip(deposits.iter())
+    {
+        let (move_address, _) = create_taproot_address(
+            &[
+                CheckSig::new(*nofn_xonly_pk).to_script_buf(),
+                Multisig::from_security_council(security_council.clone()).to_script_buf(),
+            ],
+            None,
+            paramset.network,
+        );
+        psbt_input.witness_utxo = Some(TxOut {
+            value: paramset.bridge_amount,
+            script_pubkey: move_address.script_pubkey(),
+        });
+        psbt_input.final_script_witness = Some(input.witness.clone());
+    }
+
+    Ok(psbt)
+}
+
+/// Fee of a batched emergency stop transaction and the funding needed to reach a fee rate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmergencyStopFeePlan {
+    /// Virtual size of the batch as it is.
+    pub vsize: u64,
+    /// Fee already paid by the signed inputs and outputs.
+    pub fee: Amount,
+    /// Fee needed to broadcast the batch as it is at the target fee rate.
+    pub required_fee: Amount,
+    /// If the batch pays less than [`Self::required_fee`], the minimum value of a P2TR key path
+    /// input that has to be appended together with a P2TR change output. The change output
+    /// receives everything above the fee and is at least [`MIN_TAPROOT_AMOUNT`].
+    pub funding_input: Option<Amount>,
+    /// Fee needed at the target fee rate after the funding input and change output are added.
+    pub required_fee_with_funding: Amount,
+}
+
+/// Calculates the fee of a batch created with [`batch_emergency_stop_txs`] and the funding
+/// input needed to reach `fee_rate`.
+pub fn plan_emergency_stop_fee(
+    batch: &Transaction,
+    paramset: &'static ProtocolParamset,
+    fee_rate: FeeRate,
+) -> Result<EmergencyStopFeePlan, BridgeError> {
+    let input_value = paramset
+        .bridge_amount
+        .checked_mul(batch.input.len() as u64)
+        .ok_or_eyre("Input value overflow")?;
+    let output_value = batch
+        .output
+        .iter()
+        .try_fold(Amount::ZERO, |sum, output| sum.checked_add(output.value))
+        .ok_or_eyre("Output value overflow")?;
+    let fee = input_value
+        .checked_sub(output_value)
+        .ok_or_eyre("Emergency stop outputs are larger than the inputs")?;
+
+    let vsize = batch.vsize() as u64;
+    let required_fee = fee_rate.fee_vb(vsize).ok_or_eyre("Fee overflow")?;
+    let required_fee_with_funding = fee_rate
+        .fee_vb(vsize + P2TR_KEYPATH_INPUT_VBYTES + P2TR_OUTPUT_VBYTES)
+        .ok_or_eyre("Fee overflow")?;
+
+    let funding_input = (fee < required_fee).then(|| {
+        required_fee_with_funding
+            .checked_sub(fee)
+            .unwrap_or(Amount::ZERO)
+            + MIN_TAPROOT_AMOUNT
+    });
+
+    Ok(EmergencyStopFeePlan {
+        vsize,
+        fee,
+        required_fee,
+        funding_input,
+        required_fee_with_funding,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::protocol::REGTEST_PARAMSET;
+    use crate::encryption::encrypt_bytes;
+    use bitcoin::secp256k1::{rand, Keypair, SecretKey};
+    use bitcoin::transaction::Version;
+    use bitcoin::{absolute::LockTime, Sequence, TxIn, Witness};
+    use x25519_dalek::{PublicKey, StaticSecret};
+
+    struct Setup {
+        nofn_keypair: Keypair,
+        security_council: SecurityCouncil,
+    }
+
+    impl Setup {
+        fn new() -> Self {
+            let council_pks = (0..3)
+                .map(|_| {
+                    SecretKey::new(&mut rand::thread_rng())
+                        .x_only_public_key(&SECP)
+                        .0
+                })
+                .collect();
+            Self {
+                nofn_keypair: Keypair::new(&SECP, &mut rand::thread_rng()),
+                security_council: SecurityCouncil {
+                    pks: council_pks,
+                    threshold: 2,
+                },
+            }
+        }
+
+        fn nofn_xonly_pk(&self) -> XOnlyPublicKey {
+            self.nofn_keypair.x_only_public_key().0
+        }
+
+        /// Builds a signed emergency stop tx the way the aggregator does.
+        fn emergency_stop_tx(&self, move_txid: Txid) -> Transaction {
+            let paramset = &REGTEST_PARAMSET;
+            let nofn_script = CheckSig::new(self.nofn_xonly_pk()).to_script_buf();
+            let multisig_script =
+                Multisig::from_security_council(self.security_council.clone()).to_script_buf();
+            let (move_address, spend_info) = create_taproot_address(
+                &[nofn_script.clone(), multisig_script.clone()],
+                None,
+                paramset.network,
+            );
+            let (council_address, _) =
+                create_taproot_address(&[multisig_script], None, paramset.network);
+
+            let mut tx = Transaction {
+                version: Version::TWO,
+                lock_time: LockTime::ZERO,
+                input: vec![TxIn {
+                    previous_output: OutPoint {
+                        txid: move_txid,
+                        vout: UtxoVout::DepositInMove.get_vout(),
+                    },
+                    script_sig: Default::default(),
+                    sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                    witness: Witness::new(),
+                }],
+                output: vec![TxOut {
+                    value: paramset.bridge_amount - Amount::from_sat(378),
+                    script_pubkey: council_address.script_pubkey(),
+                }],
+            };
+
+            let prevout = TxOut {
+                value: paramset.bridge_amount,
+                script_pubkey: move_address.script_pubkey(),
+            };
+            let sighash = SighashCache::new(&tx)
+                .taproot_script_spend_signature_hash(
+                    0,
+                    &Prevouts::One(0, &prevout),
+                    TapLeafHash::from_script(&nofn_script, LeafVersion::TapScript),
+                    TapSighashType::SinglePlusAnyoneCanPay,
+                )
+                .unwrap();
+            let signature = taproot::Signature {
+                signature: SECP.sign_schnorr(
+                    &Message::from_digest(sighash.to_byte_array()),
+                    &self.nofn_keypair,
+                ),
+                sighash_type: TapSighashType::SinglePlusAnyoneCanPay,
+            };
+            let control_block = spend_info
+                .control_block(&(nofn_script.clone(), LeafVersion::TapScript))
+                .unwrap();
+
+            tx.input[0].witness.push(signature.serialize());
+            tx.input[0].witness.push(nofn_script);
+            tx.input[0].witness.push(control_block.serialize());
+            tx
+        }
+    }
+
+    fn random_txid() -> Txid {
+        let mut bytes = [0u8; 32];
+        rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut bytes);
+        Txid::from_byte_array(bytes)
+    }
+
+    #[test]
+    fn decrypt_and_validate() {
+        let setup = Setup::new();
+        let move_txid = random_txid();
+        let tx = setup.emergency_stop_tx(move_txid);
+
+        let private_key = StaticSecret::random_from_rng(rand::thread_rng());
+        let public_key = PublicKey::from(&private_key);
+        let encrypted =
+            encrypt_bytes(*public_key.as_bytes(), &bitcoin::consensus::serialize(&tx)).unwrap();
+
+        let decrypted = decrypt_emergency_stop_tx(private_key.as_bytes(), &encrypted).unwrap();
+        assert_eq!(decrypted, tx);
+
+        validate_emergency_stop_tx(
+            &decrypted,
+            move_txid,
+            setup.nofn_xonly_pk(),
+            &setup.security_council,
+            &REGTEST_PARAMSET,
+        )
+        .unwrap();
+
+        // wrong move txid
+        assert!(validate_emergency_stop_tx(
+            &decrypted,
+            random_txid(),
+            setup.nofn_xonly_pk(),
+            &setup.security_council,
+            &REGTEST_PARAMSET,
+        )
+        .is_err());
+
+        // wrong key
+        let other_key = StaticSecret::random_from_rng(rand::thread_rng());
+        assert!(decrypt_emergency_stop_tx(other_key.as_bytes(), &encrypted).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_tampered_tx() {
+        let setup = Setup::new();
+        let move_txid = random_txid();
+
+        let mut tx = setup.emergency_stop_tx(move_txid);
+        tx.output[0].value -= Amount::from_sat(1);
+        assert!(validate_emergency_stop_tx(
+            &tx,
+            move_txid,
+            setup.nofn_xonly_pk(),
+            &setup.security_council,
+            &REGTEST_PARAMSET,
+        )
+        .is_err());
+
+        let other = Setup::new();
+        let tx = setup.emergency_stop_tx(move_txid);
+        assert!(validate_emergency_stop_tx(
+            &tx,
+            move_txid,
+            setup.nofn_xonly_pk(),
+            &other.security_council,
+            &REGTEST_PARAMSET,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn batch_and_plan_fee() {
+        let setup = Setup::new();
+        // The last deposit was created with another N-of-N key and council.
+        let other = Setup::new();
+        let txs: Vec<Transaction> = vec![
+            setup.emergency_stop_tx(random_txid()),
+            setup.emergency_stop_tx(random_txid()),
+            other.emergency_stop_tx(random_txid()),
+        ];
+
+        let batch = batch_emergency_stop_txs(&txs).unwrap();
+        assert_eq!(batch.input.len(), 3);
+        assert_eq!(batch.output.len(), 3);
+        for (idx, tx) in txs.iter().enumerate() {
+            assert_eq!(batch.input[idx], tx.input[0]);
+            assert_eq!(batch.output[idx], tx.output[0]);
+        }
+
+        assert!(batch_emergency_stop_txs(&[txs[0].clone(), txs[0].clone()]).is_err());
+
+        let deposits = vec![
+            (setup.nofn_xonly_pk(), setup.security_council.clone()),
+            (setup.nofn_xonly_pk(), setup.security_council.clone()),
+            (other.nofn_xonly_pk(), other.security_council.clone()),
+        ];
+        let psbt = emergency_stop_batch_psbt(&batch, &deposits, &REGTEST_PARAMSET).unwrap();
+        assert_eq!(psbt.extract_tx_unchecked_fee_rate(), batch);
+        assert_ne!(
+            psbt.inputs[0].witness_utxo, psbt.inputs[2].witness_utxo,
+            "each input must spend the move output of its own deposit"
+        );
+        assert!(emergency_stop_batch_psbt(&batch, &deposits[..2], &REGTEST_PARAMSET).is_err());
+
+        let low = plan_emergency_stop_fee(
+            &batch,
+            &REGTEST_PARAMSET,
+            FeeRate::from_sat_per_vb_unchecked(1),
+        )
+        .unwrap();
+        assert_eq!(low.fee, Amount::from_sat(378 * 3));
+        assert_eq!(low.funding_input, None);
+
+        let high = plan_emergency_stop_fee(
+            &batch,
+            &REGTEST_PARAMSET,
+            FeeRate::from_sat_per_vb_unchecked(50),
+        )
+        .unwrap();
+        let funding_input = high.funding_input.unwrap();
+        assert_eq!(
+            funding_input,
+            high.required_fee_with_funding - high.fee + MIN_TAPROOT_AMOUNT
+        );
+    }
+}
@@ -27,9 +27,10 @@
 //! - [`input.rs`] - Defines types and utilities for transaction inputs used in the [`TxHandler`].
 //! - [`output.rs`] - Defines types and utilities for transaction outputs used in the [`TxHandler`].
 //! - [`deposit_signature_owner.rs`] - Maps which TxIn signatures are signed by which protocol entities, additionally supporting different Sighash types.
+//! - [`emergency_stop.rs`] - Offline tooling to decrypt, validate and batch signed emergency stop transactions.
+//! - [`security_council.rs`] - PSBT workflow for the security council to co-sign replacement deposit transactions.
 //!
 
-#[cfg(test)]
 use super::script::ReplacementDepositScript;
 use super::script::SpendPath;
 use super::script::{CheckSig, Multisig, SpendableScript};
@@ -44,7 +45,6 @@ use crate::builder::transaction::output::UnspentTxOut;
 use crate::config::protocol::ProtocolParamset;
 use crate::constants::{NON_EPHEMERAL_ANCHOR_AMOUNT, NON_STANDARD_V3};
 use crate::deposit::DepositData;
-#[cfg(test)]
 use crate::deposit::SecurityCouncil;
 use crate::rpc::clementine::grpc_transaction_id;
 use crate::rpc::clementine::GrpcTransactionId;
@@ -56,7 +56,6 @@ use bitcoin::opcodes::all::OP_RETURN;
 use bitcoin::script::Builder;
 use bitcoin::transaction::Version;
 use bitcoin::{Address, Amount, ScriptBuf, TxOut};
-#[cfg(test)]
 use bitcoin::{OutPoint, Txid, XOnlyPublicKey};
 use clementine_errors::BridgeError;
 use clementine_primitives::{RoundIndex, TransactionType};
@@ -78,11 +77,13 @@ pub use txhandler::Unsigned;
 pub mod challenge;
 mod creator;
 pub mod deposit_signature_owner;
+pub mod emergency_stop;
 pub mod input;
 mod operator_assert;
 mod operator_collateral;
 mod operator_reimburse;
 pub mod output;
+pub mod security_council;
 pub mod sign;
 mod txhandler;
 
@@ -400,7 +401,6 @@ pub fn create_emergency_stop_txhandler(
 /// # Returns
 ///
 /// A [`TxHandler`] for the replacement deposit transaction, or a [`BridgeError`] if construction fails.
-#[cfg(test)]
 pub fn create_replacement_deposit_txhandler(
     old_move_txid: Txid,
     input_outpoint: OutPoint,
@@ -0,0 +1,519 @@
+//! # Security Council Replacement Deposit PSBTs
+//!
+//! A deposit can be replaced by the security council by spending the deposit
+//! output of its move transaction with the council's m-of-n
+//! [`Multisig`] script (see [`create_replacement_deposit_txhandler`]). Council
+//! members usually keep their keys offline, so the transaction is signed
+//! through a PSBT:
+//!
+//! 1. [`create_replacement_deposit_psbt`] creates the unsigned PSBT. It
+//!    contains the spent output, the multisig leaf and its control block, so
+//!    signers don't need access to a Bitcoin node.
+//! 2. Each member adds a signature with [`sign_replacement_deposit_psbt`],
+//!    after the PSBT is checked against the deposit they intend to replace.
+//! 3. Signed PSBTs are merged with [`combine_replacement_deposit_psbts`].
+//! 4. [`finalize_replacement_deposit_psbt`] checks the signatures against the
+//!    threshold and returns the signed transaction.
+//!
+//! The input is signed with `SinglePlusAnyoneCanPay` and the transaction has
+//! a 0 sat anchor output, so it can be broadcast with CPFP. Once it is
+//! mined, the replacement deposit is processed like any other deposit by the
+//! aggregator after the `DepositReplaced` event is emitted.
+
+use super::create_replacement_deposit_txhandler;
+use super::input::UtxoVout;
+use crate::bitvm_client::SECP;
+use crate::builder::address::create_taproot_address;
+use crate::builder::script::{CheckSig, Multisig, SpendableScript};
+use crate::config::protocol::ProtocolParamset;
+use crate::deposit::SecurityCouncil;
+use bitcoin::hashes::Hash;
+use bitcoin::psbt::{Psbt, PsbtSighashType};
+use bitcoin::secp256k1::{Keypair, Message, SecretKey};
+use bitcoin::sighash::{Prevouts, SighashCache};
+use bitcoin::taproot::{self, ControlBlock, LeafVersion, TapLeafHash};
+use bitcoin::{
+    OutPoint, ScriptBuf, TapSighash, TapSighashType, Transaction, TxOut, Txid, Witness,
+    XOnlyPublicKey,
+};
+use clementine_errors::BridgeError;
+use eyre::{Context, OptionExt};
+
+/// Sighash type the security council signs the replacement deposit input
+/// with. It allows adding fee paying inputs and outputs after signing.
+pub const REPLACEMENT_DEPOSIT_SIGHASH_TYPE: TapSighashType = TapSighashType::SinglePlusAnyoneCanPay;
+
+/// Creates the unsigned replacement deposit transaction as a PSBT.
+///
+/// The deposit output of `old_move_txid` is spent to a replacement deposit
+/// output for `new_nofn_xonly_pk`.
+///
+/// # Arguments
+///
+/// * `old_move_txid` - The move_to_vault txid of the deposit that is replaced.
+/// * `old_nofn_xonly_pk` - The N-of-N key of the replaced deposit.
+/// * `new_nofn_xonly_pk` - The N-of-N key of the replacement deposit.
+/// * `paramset` - The protocol paramset, used for the bridge amount and the network.
+/// * `security_council` - The security council that signs the transaction.
+pub fn create_replacement_deposit_psbt(
+    old_move_txid: Txid,
+    old_nofn_xonly_pk: XOnlyPublicKey,
+    new_nofn_xonly_pk: XOnlyPublicKey,
+    paramset: &'static ProtocolParamset,
+    security_council: SecurityCouncil,
+) -> Result<Psbt, BridgeError> {
+    let txhandler = create_replacement_deposit_txhandler(
+        old_move_txid,
+        OutPoint {
+            txid: old_move_txid,
+            vout: UtxoVout::DepositInMove.get_vout(),
+        },
+        old_nofn_xonly_pk,
+        new_nofn_xonly_pk,
+        paramset,
+        security_council.clone(),
+    )?;
+
+    let multisig_script = Multisig::from_security_council(security_council).to_script_buf();
+    let (move_address, spend_info) = create_taproot_address(
+        &[
+            CheckSig::new(old_nofn_xonly_pk).to_script_buf(),
+            multisig_script.clone(),
+        ],
+        None,
+        paramset.network,
+    );
+    let control_block = spend_info
+        .control_block(&(multisig_script.clone(), LeafVersion::TapScript))
+        .ok_or_eyre("Failed to find control block for security council script")?;
+
+    let mut psbt = Psbt::from_unsigned_tx(txhandler.get_cached_tx().clone())
+        .wrap_err("Failed to create PSBT from replacement deposit tx")?;
+    let input = &mut psbt.inputs[0];
+    input.witness_utxo = Some(TxOut {
+        value: paramset.bridge_amount,
+        script_pubkey: move_address.script_pubkey(),
+    });
+    input.sighash_type = Some(PsbtSighashType::from(REPLACEMENT_DEPOSIT_SIGHASH_TYPE));
+    input.tap_internal_key = Some(spend_info.internal_key());
+    input.tap_merkle_root = spend_info.merkle_root();
+    input
+        .tap_scripts
+        .insert(control_block, (multisig_script, LeafVersion::TapScript));
+
+    Ok(psbt)
+}
+
+/// Returns the security council leaf of the PSBT with its control block and
+/// the sighash the council signs. Fails if the PSBT does not spend a single
+/// input with the multisig script of `security_council`.
+fn security_council_leaf(
+    psbt: &Psbt,
+    security_council: &SecurityCouncil,
+) -> Result<(ControlBlock, ScriptBuf, TapSighash), BridgeError> {
+    if psbt.inputs.len() != 1 || psbt.unsigned_tx.input.len() != 1 {
+        return Err(eyre::eyre!("Replacement deposit PSBT must have exactly one input").into());
+    }
+    let input = &psbt.inputs[0];
+
+    let expected_script = Multisig::from_security_council(security_council.clone()).to_script_buf();
+    let (control_block, script) = input
+        .tap_scripts
+        .iter()
+        .find(|(_, (script, leaf_version))| {
+            *leaf_version == LeafVersion::TapScript && *script == expected_script
+        })
+        .map(|(control_block, (script, _))| (control_block.clone(), script.clone()))
+        .ok_or_eyre("PSBT does not contain the security council script")?;
+
+    let prevout = input
+        .witness_utxo
+        .as_ref()
+        .ok_or_eyre("PSBT input is missing the spent output")?;
+    let leaf_hash = TapLeafHash::from_script(&script, LeafVersion::TapScript);
+    let sighash = SighashCache::new(&psbt.unsigned_tx)
+        .taproot_script_spend_signature_hash(
+            0,
+            &Prevouts::One(0, prevout),
+            leaf_hash,
+            REPLACEMENT_DEPOSIT_SIGHASH_TYPE,
+        )
+        .wrap_err("Failed to calculate replacement deposit sighash")?;
+
+    Ok((control_block, script, sighash))
+}
+
+/// Checks that the parts of the PSBT a council signature commits to match the
+/// replacement deposit tx built by [`create_replacement_deposit_psbt`] from
+/// the same arguments.
+///
+/// With [`REPLACEMENT_DEPOSIT_SIGHASH_TYPE`] these are the version, the lock
+/// time, the spent deposit output and output 0, the replacement deposit.
+fn check_replacement_deposit_psbt(
+    psbt: &Psbt,
+    old_move_txid: Txid,
+    old_nofn_xonly_pk: XOnlyPublicKey,
+    new_nofn_xonly_pk: XOnlyPublicKey,
+    paramset: &'static ProtocolParamset,
+    security_council: &SecurityCouncil,
+) -> Result<(), BridgeError> {
+    let expected = create_replacement_deposit_psbt(
+        old_move_txid,
+        old_nofn_xonly_pk,
+        new_nofn_xonly_pk,
+        paramset,
+        security_council.clone(),
+    )?;
+    let (tx, expected_tx) = (&psbt.unsigned_tx, &expected.unsigned_tx);
+
+    let spent = |tx: &Transaction| {
+        tx.input
+            .first()
+            .map(|input| (input.previous_output, input.sequence))
+    };
+    if tx.version != expected_tx.version || tx.lock_time != expected_tx.lock_time {
+        return Err(
+            eyre::eyre!("Replacement deposit PSBT has an unexpected version or lock time").into(),
+        );
+    }
+    if spent(tx) != spent(expected_tx)
+        || psbt.inputs.first().map(|input| &input.witness_utxo)
+            != expected.inputs.first().map(|input| &input.witness_utxo)
+    {
+        return Err(eyre::eyre!(
+            "Replacement deposit PSBT does not spend the deposit of move tx {old_move_txid}"
+        )
+        .into());
+    }
+    if tx.output.first() != expected_tx.output.first() {
+        return Err(eyre::eyre!(
+            "Output 0 of the replacement deposit PSBT is not a deposit for N-of-N key {new_nofn_xonly_pk}"
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Adds the signature of a security council member to the PSBT.
+///
+/// The PSBT is rebuilt from the deposit to replace and must match it, see
+/// [`create_replacement_deposit_psbt`] for the arguments. Fails if it doesn't
+/// or if the key of `secret_key` is not in `security_council`.
+pub fn sign_replacement_deposit_psbt(
+    psbt: &mut Psbt,
+    secret_key: SecretKey,
+    old_move_txid: Txid,
+    old_nofn_xonly_pk: XOnlyPublicKey,
+    new_nofn_xonly_pk: XOnlyPublicKey,
+    paramset: &'static ProtocolParamset,
+    security_council: &SecurityCouncil,
+) -> Result<(), BridgeError> {
+    let keypair = Keypair::from_secret_key(&SECP, &secret_key);
+    let (xonly_pk, _parity) = keypair.x_only_public_key();
+    if !security_council.pks.contains(&xonly_pk) {
+        return Err(eyre::eyre!("{xonly_pk} is not a security council member").into());
+    }
+
+    check_replacement_deposit_psbt(
+        psbt,
+        old_move_txid,
+        old_nofn_xonly_pk,
+        new_nofn_xonly_pk,
+        paramset,
+        security_council,
+    )?;
+    let (_, script, sighash) = security_council_leaf(psbt, security_council)?;
+    let signature = SECP.sign_schnorr(&Message::from_digest(sighash.to_byte_array()), &keypair);
+
+    psbt.inputs[0].tap_script_sigs.insert(
+        (
+            xonly_pk,
+            TapLeafHash::from_script(&script, LeafVersion::TapScript),
+        ),
+        taproot::Signature {
+            signature,
+            sighash_type: REPLACEMENT_DEPOSIT_SIGHASH_TYPE,
+        },
+    );
+
+    Ok(())
+}
+
+/// Merges the signatures of PSBTs that were signed separately. All PSBTs
+/// must contain the same unsigned transaction.
+pub fn combine_replacement_deposit_psbts(psbts: Vec<Psbt>) -> Result<Psbt, BridgeError> {
+    let mut psbts = psbts.into_iter();
+    let mut combined = psbts.next().ok_or_eyre("No PSBTs to combine")?;
+    for psbt in psbts {
+        combined
+            .combine(psbt)
+            .wrap_err("Failed to combine replacement deposit PSBTs")?;
+    }
+    Ok(combined)
+}
+
+/// Returns the council members that have a valid signature in the PSBT, in
+/// the order of `security_council.pks`.
+pub fn replacement_deposit_psbt_signers(
+    psbt: &Psbt,
+    security_council: &SecurityCouncil,
+) -> Result<Vec<XOnlyPublicKey>, BridgeError> {
+    Ok(valid_signatures(psbt, security_council)?
+        .into_iter()
+        .zip(security_council.pks.iter())
+        .filter_map(|(sig, pk)| sig.map(|_| *pk))
+        .collect())
+}
+
+/// Returns the valid signature of each council member, [`None`] for members
+/// that didn't sign.
+fn valid_signatures(
+    psbt: &Psbt,
+    security_council: &SecurityCouncil,
+) -> Result<Vec<Option<taproot::Signature>>, BridgeError> {
+    let (_, script, sighash) = security_council_leaf(psbt, security_council)?;
+    let leaf_hash = TapLeafHash::from_script(&script, LeafVersion::TapScript);
+    let message = Message::from_digest(sighash.to_byte_array());
+
+    security_council
+        .pks
+        .iter()
+        .map(|pk| {
+            let Some(sig) = psbt.inputs[0].tap_script_sigs.get(&(*pk, leaf_hash)) else {
+                return Ok(None);
+            };
+            if sig.sighash_type != REPLACEMENT_DEPOSIT_SIGHASH_TYPE {
+                return Err(eyre::eyre!(
+                    "Signature of {pk} uses sighash type {}, expected {}",
+                    sig.sighash_type,
+                    REPLACEMENT_DEPOSIT_SIGHASH_TYPE
+                )
+                .into());
+            }
+            SECP.verify_schnorr(&sig.signature, &message, pk)
+                .wrap_err_with(|| format!("Invalid signature of {pk}"))?;
+            Ok(Some(*sig))
+        })
+        .collect()
+}
+
+/// Builds the signed replacement deposit transaction from a PSBT that has
+/// signatures of at least `security_council.threshold` members.
+///
+/// The multisig script requires exactly `threshold` signatures, so only the
+/// first `threshold` signers in council order are used.
+pub fn finalize_replacement_deposit_psbt(
+    psbt: &Psbt,
+    security_council: &SecurityCouncil,
+) -> Result<Transaction, BridgeError> {
+    let (control_block, script, _) = security_council_leaf(psbt, security_council)?;
+
+    let mut signatures = valid_signatures(psbt, security_council)?;
+    let num_signatures = signatures.iter().flatten().count();
+    let threshold = security_council.threshold as usize;
+    if num_signatures < threshold {
+        return Err(eyre::eyre!(
+            "Replacement deposit PSBT has {num_signatures} valid signatures, {threshold} needed"
+        )
+        .into());
+    }
+    signatures
+        .iter_mut()
+        .filter(|sig| sig.is_some())
+        .skip(threshold)
+        .for_each(|sig| *sig = None);
+
+    let mut witness = Multisig::from_security_council(security_council.clone())
+        .generate_script_inputs(&signatures)?;
+    witness.push(script);
+    witness.push(control_block.serialize());
+
+    let mut tx = psbt.unsigned_tx.clone();
+    tx.input[0].witness = witness;
+    Ok(tx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::protocol::REGTEST_PARAMSET;
+    use bitcoin::secp256k1::rand;
+    use std::str::FromStr;
+
+    fn council(num_members: usize, threshold: u32) -> (Vec<SecretKey>, SecurityCouncil) {
+        let sks: Vec<SecretKey> = (0..num_members)
+            .map(|_| SecretKey::new(&mut rand::thread_rng()))
+            .collect();
+        let pks = sks.iter().map(|sk| sk.x_only_public_key(&SECP).0).collect();
+        (sks, SecurityCouncil { pks, threshold })
+    }
+
+    fn random_xonly() -> XOnlyPublicKey {
+        SecretKey::new(&mut rand::thread_rng())
+            .x_only_public_key(&SECP)
+            .0
+    }
+
+    /// The deposit replaced in the tests.
+    #[derive(Clone)]
+    struct Replacement {
+        old_move_txid: Txid,
+        old_nofn_xonly_pk: XOnlyPublicKey,
+        new_nofn_xonly_pk: XOnlyPublicKey,
+    }
+
+    impl Replacement {
+        fn new() -> Self {
+            Self {
+                old_move_txid: Txid::from_str(
+                    "c5f3d4ddad3e2b6ad6a1cc5d8b7b7a0e69e8fd2cba1f4ea0f6e6e1b25e0f77b1",
+                )
+                .unwrap(),
+                old_nofn_xonly_pk: random_xonly(),
+                new_nofn_xonly_pk: random_xonly(),
+            }
+        }
+
+        fn psbt(&self, security_council: &SecurityCouncil) -> Psbt {
+            create_replacement_deposit_psbt(
+                self.old_move_txid,
+                self.old_nofn_xonly_pk,
+                self.new_nofn_xonly_pk,
+                &REGTEST_PARAMSET,
+                security_council.clone(),
+            )
+            .unwrap()
+        }
+
+        fn sign(
+            &self,
+            psbt: &mut Psbt,
+            secret_key: SecretKey,
+            security_council: &SecurityCouncil,
+        ) -> Result<(), BridgeError> {
+            sign_replacement_deposit_psbt(
+                psbt,
+                secret_key,
+                self.old_move_txid,
+                self.old_nofn_xonly_pk,
+                self.new_nofn_xonly_pk,
+                &REGTEST_PARAMSET,
+                security_council,
+            )
+        }
+    }
+
+    #[test]
+    fn sign_combine_and_finalize() {
+        let (sks, security_council) = council(3, 2);
+        let replacement = Replacement::new();
+        let psbt = replacement.psbt(&security_council);
+
+        // PSBTs are passed around in base64
+        let psbt = Psbt::from_str(&psbt.to_string()).unwrap();
+
+        let mut first = psbt.clone();
+        replacement
+            .sign(&mut first, sks[2], &security_council)
+            .unwrap();
+        assert!(finalize_replacement_deposit_psbt(&first, &security_council).is_err());
+
+        let mut second = psbt.clone();
+        replacement
+            .sign(&mut second, sks[0], &security_council)
+            .unwrap();
+
+        let combined = combine_replacement_deposit_psbts(vec![first, second]).unwrap();
+        assert_eq!(
+            replacement_deposit_psbt_signers(&combined, &security_council).unwrap(),
+            vec![security_council.pks[0], security_council.pks[2]]
+        );
+
+        let tx = finalize_replacement_deposit_psbt(&combined, &security_council).unwrap();
+        // 3 signature slots, script and control block
+        assert_eq!(tx.input[0].witness.len(), 5);
+        assert_eq!(tx.compute_txid(), psbt.unsigned_tx.compute_txid());
+    }
+
+    #[test]
+    fn finalize_uses_exactly_threshold_signatures() {
+        let (sks, security_council) = council(3, 2);
+        let replacement = Replacement::new();
+        let mut psbt = replacement.psbt(&security_council);
+        for sk in &sks {
+            replacement.sign(&mut psbt, *sk, &security_council).unwrap();
+        }
+
+        let tx = finalize_replacement_deposit_psbt(&psbt, &security_council).unwrap();
+        let non_empty_sigs = tx.input[0]
+            .witness
+            .iter()
+            .take(3)
+            .filter(|item| !item.is_empty())
+            .count();
+        assert_eq!(non_empty_sigs, 2);
+    }
+
+    #[test]
+    fn rejects_non_members_and_invalid_signatures() {
+        let (sks, security_council) = council(2, 1);
+        let replacement = Replacement::new();
+        let mut psbt = replacement.psbt(&security_council);
+
+        let outsider = SecretKey::new(&mut rand::thread_rng());
+        assert!(replacement
+            .sign(&mut psbt, outsider, &security_council)
+            .is_err());
+
+        replacement
+            .sign(&mut psbt, sks[0], &security_council)
+            .unwrap();
+        // change the transaction after signing
+        psbt.unsigned_tx.output[0].value = bitcoin::Amount::from_sat(1);
+        assert!(finalize_replacement_deposit_psbt(&psbt, &security_council).is_err());
+    }
+
+    #[test]
+    fn rejects_other_council() {
+        let (sks, security_council) = council(2, 1);
+        let (_, other_council) = council(2, 1);
+        let replacement = Replacement::new();
+        let mut psbt = replacement.psbt(&security_council);
+        replacement
+            .sign(&mut psbt, sks[0], &security_council)
+            .unwrap();
+
+        assert!(finalize_replacement_deposit_psbt(&psbt, &other_council).is_err());
+    }
+
+    #[test]
+    fn rejects_psbt_of_other_deposit() {
+        let (sks, security_council) = council(2, 1);
+        let replacement = Replacement::new();
+        let psbt = replacement.psbt(&security_council);
+
+        // The replacement deposit goes to another N-of-N key.
+        let other_key = Replacement {
+            new_nofn_xonly_pk: random_xonly(),
+            ..replacement.clone()
+        };
+        assert!(other_key
+            .sign(&mut psbt.clone(), sks[0], &security_council)
+            .is_err());
+
+        // The PSBT replaces another deposit.
+        let other_deposit = Replacement {
+            old_move_txid: Txid::from_byte_array([7; 32]),
+            ..replacement.clone()
+        };
+        let mut other_deposit_psbt = other_deposit.psbt(&security_council);
+        assert!(replacement
+            .sign(&mut other_deposit_psbt, sks[0], &security_council)
+            .is_err());
+
+        replacement
+            .sign(&mut psbt.clone(), sks[0], &security_council)
+            .unwrap();
+    }
+}
@@ -2,12 +2,20 @@
 
 use crate::config::protocol::ProtocolParamset;
 use crate::config::protocol::ProtocolParamsetExt;
+use crate::config::BridgeConfig;
 use crate::database::DatabaseTransaction;
-use crate::{citrea::BRIDGE_CONTRACT::DepositReplaced, database::Database};
+use crate::proof_store::{PostgresProofStore, ProofKind, ProofMetadata};
+use crate::task::citrea_indexer::CitreaEventIndex;
+use crate::{
+    citrea::BRIDGE_CONTRACT::{
+        Deposit, DepositReplaced, DepositTransferFailed, SafeWithdrawal, Withdrawal,
+    },
+    database::Database,
+};
 use alloy::{
     eips::{BlockId, BlockNumberOrTag},
     network::EthereumWallet,
-    primitives::{keccak256, U256},
+    primitives::{Address, B256, U256},
     providers::{
         fillers::{
             BlobGasFiller, ChainIdFiller, FillProvider, GasFiller, JoinFill, NonceFiller,
@@ -25,6 +33,7 @@ use bitcoin::{hashes::Hash, OutPoint, Txid, XOnlyPublicKey};
 use bridge_circuit_host::receipt_from_inner;
 use circuits_lib::bridge_circuit::{
     lc_proof::check_method_id,
+    storage_proof::{bridge_contract_layout, BRIDGE_CONTRACT_LAYOUTS},
     structs::{LightClientProof, StorageProof},
 };
 use citrea_sov_rollup_interface::zk::light_client_proof::output::LightClientCircuitOutput;
@@ -33,21 +42,21 @@ use eyre::Context;
 use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
 use jsonrpsee::proc_macros::rpc;
 use risc0_zkvm::{InnerReceipt, Receipt};
-use std::{fmt::Debug, time::Duration};
+use serde::Deserialize;
+use std::{
+    fmt::Debug,
+    future::Future,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
 use tonic::async_trait;
 
 #[cfg(test)]
 pub const SATS_TO_WEI_MULTIPLIER: u64 = 10_000_000_000;
-pub const BRIDGE_CONTRACT_ADDRESS: &str = "0x3100000000000000000000000000000000000002";
 
 #[cfg(test)]
 pub const LIGHT_CLIENT_ADDRESS: &str = "0x3100000000000000000000000000000000000001";
 
-const UTXOS_STORAGE_INDEX: [u8; 32] =
-    hex_literal::hex!("0000000000000000000000000000000000000000000000000000000000000007");
-const DEPOSIT_STORAGE_INDEX: [u8; 32] =
-    hex_literal::hex!("0000000000000000000000000000000000000000000000000000000000000008");
-
 // Codegen from ABI file to interact with the contract.
 sol!(
     #[allow(missing_docs)]
@@ -57,6 +66,34 @@ sol!(
     "../scripts/Bridge.json"
 );
 
+/// Hash and parent hash of a Citrea L2 block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct L2BlockRef {
+    pub hash: B256,
+    pub parent_hash: B256,
+}
+
+/// A bridge contract event relevant to clementine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CitreaBridgeEventKind {
+    /// A deposit was made, `move_txid` is stored at `depositTxIds[idx]`.
+    Deposit { idx: u32, move_txid: Txid },
+    /// A withdrawal was made, `utxo` is stored at `withdrawalUTXOs[idx]`.
+    Withdrawal { idx: u32, utxo: OutPoint },
+    /// The move tx of deposit `idx` was replaced.
+    DepositReplaced { idx: u32, new_move_txid: Txid },
+}
+
+/// A decoded bridge contract event with its position in the L2 chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CitreaBridgeEvent {
+    pub l2_height: u64,
+    pub block_hash: B256,
+    pub log_index: u64,
+    pub kind: CitreaBridgeEventKind,
+}
+
 #[async_trait]
 pub trait CitreaClientT: Send + Sync + Debug + Clone + 'static {
     /// # Parameters
@@ -75,6 +112,19 @@ pub trait CitreaClientT: Send + Sync + Debug + Clone + 'static {
         timeout: Option<Duration>,
     ) -> Result<Self, BridgeError>;
 
+    /// Creates a client from the Citrea settings of `config`. By default only
+    /// the primary Citrea RPC and light client prover URLs are used.
+    async fn from_config(config: &BridgeConfig) -> Result<Self, BridgeError> {
+        Self::new(
+            config.citrea_rpc_url.clone(),
+            config.citrea_light_client_prover_url.clone(),
+            config.citrea_chain_id,
+            None,
+            config.citrea_request_timeout,
+        )
+        .await
+    }
+
     /// Returns deposit move txids, starting from the last deposit index.
     ///
     /// # Parameters
@@ -160,6 +210,9 @@ pub trait CitreaClientT: Send + Sync + Debug + Clone + 'static {
         nofn_xonly_pk: XOnlyPublicKey,
     ) -> Result<(), BridgeError>;
 
+    /// Returns the storage proofs of a deposit's withdrawal UTXO and move
+    /// txid at the given L2 height, for the storage layout the bridge
+    /// contract has at that height.
     async fn get_storage_proof(
         &self,
         l2_height: u64,
@@ -181,20 +234,378 @@ pub trait CitreaClientT: Send + Sync + Debug + Clone + 'static {
     ///
     /// - [`Result<u32, BridgeError>`]: Current L2 block height, or an error if the request fails or the value doesn't fit in u32.
     async fn get_current_l2_block_height(&self) -> Result<u32, BridgeError>;
+
+    /// Returns the hash and parent hash of the L2 block at the given height,
+    /// or [`None`] if the block does not exist yet.
+    async fn get_l2_block_ref(&self, l2_height: u64) -> Result<Option<L2BlockRef>, BridgeError>;
+
+    /// Returns all deposit, withdrawal and deposit replacement events of the
+    /// bridge contract in the given range of blocks, ordered by their position
+    /// in the chain.
+    ///
+    /// # Parameters
+    ///
+    /// - `from_height`: Start block height (inclusive)
+    /// - `to_height`: End block height (inclusive)
+    async fn get_bridge_events(
+        &self,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Vec<CitreaBridgeEvent>, BridgeError>;
+}
+
+/// Time an endpoint is skipped after a failed request.
+const CITREA_ENDPOINT_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Endpoints further behind the highest reported L2 height than this many
+/// blocks are treated as unhealthy.
+const CITREA_MAX_ENDPOINT_LAG: u64 = 10;
+
+/// Minimum time between two comparisons of the endpoints' L2 heights.
+const CITREA_LAG_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A single Citrea RPC endpoint.
+#[derive(Clone, Debug)]
+pub struct CitreaEndpoint {
+    pub url: String,
+    pub client: HttpClient,
+    pub contract: CitreaContract,
+}
+
+#[derive(Debug)]
+struct EndpointHealth {
+    /// Endpoint `i` is skipped until `unhealthy_until[i]`, unless every
+    /// endpoint is unhealthy.
+    unhealthy_until: Vec<Option<Instant>>,
+    last_lag_check: Option<Instant>,
+}
+
+/// An ordered list of equivalent endpoints, the first one being the primary.
+/// Health is shared between clones.
+#[derive(Clone, Debug)]
+struct EndpointSet<E> {
+    endpoints: Vec<E>,
+    health: Arc<Mutex<EndpointHealth>>,
+}
+
+impl<E: Sync> EndpointSet<E> {
+    fn new(endpoints: Vec<E>) -> Self {
+        let health = EndpointHealth {
+            unhealthy_until: vec![None; endpoints.len()],
+            last_lag_check: None,
+        };
+
+        Self {
+            endpoints,
+            health: Arc::new(Mutex::new(health)),
+        }
+    }
+
+    fn health(&self) -> MutexGuard<'_, EndpointHealth> {
+        self.health.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn set_healthy(&self, idx: usize, healthy: bool) {
+        self.health().unhealthy_until[idx] =
+            (!healthy).then(|| Instant::now() + CITREA_ENDPOINT_BACKOFF);
+    }
+
+    /// Returns endpoint indexes in the order they should be tried: healthy
+    /// endpoints by priority, followed by unhealthy ones.
+    fn ordered(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let health = self.health();
+        let (mut healthy, unhealthy): (Vec<usize>, Vec<usize>) = (0..self.endpoints.len())
+            .partition(|&idx| health.unhealthy_until[idx].is_none_or(|until| until <= now));
+        healthy.extend(unhealthy);
+        healthy
+    }
+
+    /// Runs `request` on the endpoints in order until one of them succeeds.
+    async fn failover<'a, T, F, Fut>(&'a self, request: &str, f: F) -> Result<T, BridgeError>
+    where
+        F: Fn(&'a E) -> Fut + Send,
+        Fut: Future<Output = Result<T, BridgeError>> + Send,
+    {
+        let mut last_error = None;
+        for idx in self.ordered() {
+            match f(&self.endpoints[idx]).await {
+                Ok(result) => {
+                    self.set_healthy(idx, true);
+                    return Ok(result);
+                }
+                Err(e) => {
+                    tracing::warn!("Citrea endpoint {idx} failed {request}: {e:?}");
+                    self.set_healthy(idx, false);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(eyre::eyre!(
+            "All {} Citrea endpoints failed {request}, last error: {:?}",
+            self.endpoints.len(),
+            last_error
+        )
+        .into())
+    }
+
+    /// Runs `request` on all endpoints and returns the result at least
+    /// `quorum` of them agree on.
+    async fn quorum<'a, T, F, Fut>(
+        &'a self,
+        quorum: usize,
+        request: &str,
+        f: F,
+    ) -> Result<T, BridgeError>
+    where
+        T: PartialEq + Debug,
+        F: Fn(&'a E) -> Fut + Send,
+        Fut: Future<Output = Result<T, BridgeError>> + Send,
+    {
+        let results = futures::future::join_all(self.endpoints.iter().map(&f)).await;
+
+        let mut tally: Vec<(T, usize)> = vec![];
+        for (idx, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(result) => {
+                    self.set_healthy(idx, true);
+                    match tally.iter_mut().find(|(value, _)| *value == result) {
+                        Some((_, count)) => *count += 1,
+                        None => tally.push((result, 1)),
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Citrea endpoint {idx} failed {request}: {e:?}");
+                    self.set_healthy(idx, false);
+                }
+            }
+        }
+
+        if tally.len() > 1 {
+            tracing::error!(
+                "Citrea endpoints returned conflicting results for {request}: {tally:?}"
+            );
+        }
+
+        match tally.into_iter().max_by_key(|(_, count)| *count) {
+            Some((result, count)) if count >= quorum => Ok(result),
+            best => Err(eyre::eyre!(
+                "Citrea endpoints did not reach quorum of {quorum} for {request}, best agreement: {}",
+                best.map_or(0, |(_, count)| count)
+            )
+            .into()),
+        }
+    }
+}
+
+impl EndpointSet<CitreaEndpoint> {
+    /// Compares the L2 heights of the endpoints and marks the ones that fail
+    /// or lag behind as unhealthy. Does nothing if it was done recently.
+    async fn check_lag(&self) {
+        if self.endpoints.len() < 2 {
+            return;
+        }
+        {
+            let mut health = self.health();
+            if health
+                .last_lag_check
+                .is_some_and(|last| last.elapsed() < CITREA_LAG_CHECK_INTERVAL)
+            {
+                return;
+            }
+            health.last_lag_check = Some(Instant::now());
+        }
+
+        let heights =
+            futures::future::join_all(self.endpoints.iter().map(|ep| ep.block_number())).await;
+        let Some(max_height) = heights
+            .iter()
+            .filter_map(|h| h.as_ref().ok())
+            .max()
+            .copied()
+        else {
+            return;
+        };
+
+        for (idx, height) in heights.into_iter().enumerate() {
+            match height {
+                Ok(height) if max_height - height <= CITREA_MAX_ENDPOINT_LAG => {
+                    self.set_healthy(idx, true);
+                }
+                Ok(height) => {
+                    tracing::warn!(
+                        "Citrea endpoint {} ({}) is lagging: L2 height {height}, highest known {max_height}",
+                        idx,
+                        self.endpoints[idx].url
+                    );
+                    self.set_healthy(idx, false);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Citrea endpoint {} ({}) failed health check: {e:?}",
+                        idx,
+                        self.endpoints[idx].url
+                    );
+                    self.set_healthy(idx, false);
+                }
+            }
+        }
+    }
 }
 
 /// Citrea client is responsible for interacting with the Citrea EVM and Citrea
 /// RPC.
+///
+/// Several Citrea RPC and light client prover endpoints can be given. Requests
+/// go to the first healthy endpoint and fail over to the next ones on errors.
+/// If a quorum is set, critical reads are sent to every Citrea RPC endpoint and
+/// only accepted if enough of them agree.
+///
+/// If a [`CitreaEventIndex`] is attached, bridge events are read from it
+/// whenever it covers the request.
 #[derive(Clone, Debug)]
 pub struct CitreaClient {
-    pub client: HttpClient,
-    pub light_client_prover_client: HttpClient,
     #[cfg(test)]
     pub wallet_address: alloy::primitives::Address,
+    /// Bridge contract on the primary Citrea RPC endpoint, used to send
+    /// transactions.
     pub contract: CitreaContract,
+    rpc_endpoints: EndpointSet<CitreaEndpoint>,
+    light_client_prover_endpoints: EndpointSet<HttpClient>,
+    quorum: Option<usize>,
+    event_index: Option<CitreaEventIndex>,
 }
 
 impl CitreaClient {
+    /// Creates a client with several endpoints. The first URL of each list is
+    /// the primary endpoint.
+    ///
+    /// # Parameters
+    ///
+    /// - `quorum`: If set, number of Citrea RPC endpoints that must agree on
+    ///   the result of a critical read.
+    ///
+    /// See [`CitreaClientT::new`] for the other parameters.
+    pub async fn with_endpoints(
+        citrea_rpc_urls: Vec<String>,
+        light_client_prover_urls: Vec<String>,
+        chain_id: u32,
+        secret_key: Option<PrivateKeySigner>,
+        timeout: Option<Duration>,
+        quorum: Option<usize>,
+    ) -> Result<Self, BridgeError> {
+        if citrea_rpc_urls.is_empty() || light_client_prover_urls.is_empty() {
+            return Err(BridgeError::ConfigError(
+                "At least one Citrea RPC and light client prover URL is required".to_string(),
+            ));
+        }
+        if let Some(quorum) = quorum {
+            if quorum == 0 || quorum > citrea_rpc_urls.len() {
+                return Err(BridgeError::ConfigError(format!(
+                    "Citrea RPC quorum ({quorum}) must be between 1 and the number of Citrea RPC endpoints ({})",
+                    citrea_rpc_urls.len()
+                )));
+            }
+        }
+
+        let timeout = timeout.unwrap_or(Duration::from_secs(60));
+        let secret_key = secret_key.unwrap_or(PrivateKeySigner::random());
+
+        let key = secret_key.with_chain_id(Some(chain_id.into()));
+
+        #[cfg(test)]
+        let wallet_address = key.address();
+
+        tracing::info!("Wallet address: {}", key.address());
+
+        let mut rpc_endpoints = vec![];
+        for citrea_rpc_url in citrea_rpc_urls {
+            let url = Url::parse(&citrea_rpc_url).wrap_err("Can't parse Citrea RPC URL")?;
+
+            let provider = ProviderBuilder::new()
+                .wallet(EthereumWallet::from(key.clone()))
+                .on_http(url.clone());
+            let contract = BRIDGE_CONTRACT::new(bridge_contract_address(u64::MAX), provider);
+
+            let client = HttpClientBuilder::default()
+                .request_timeout(timeout)
+                .build(url)
+                .wrap_err("Failed to create Citrea RPC client")?;
+
+            tracing::info!("Citrea RPC client created for {citrea_rpc_url}");
+
+            rpc_endpoints.push(CitreaEndpoint {
+                url: citrea_rpc_url,
+                client,
+                contract,
+            });
+        }
+
+        let mut light_client_prover_endpoints = vec![];
+        for light_client_prover_url in light_client_prover_urls {
+            let url =
+                Url::parse(&light_client_prover_url).wrap_err("Can't parse Citrea LCP RPC URL")?;
+
+            let client = HttpClientBuilder::default()
+                .request_timeout(timeout)
+                .build(url)
+                .wrap_err("Failed to create Citrea LCP RPC client")?;
+
+            tracing::info!("Citrea LCP RPC client created for {light_client_prover_url}");
+
+            light_client_prover_endpoints.push(client);
+        }
+
+        Ok(CitreaClient {
+            #[cfg(test)]
+            wallet_address,
+            contract: rpc_endpoints[0].contract.clone(),
+            rpc_endpoints: EndpointSet::new(rpc_endpoints),
+            light_client_prover_endpoints: EndpointSet::new(light_client_prover_endpoints),
+            quorum,
+            event_index: None,
+        })
+    }
+
+    /// Serves bridge event queries from `event_index` when it covers them.
+    pub fn with_event_index(mut self, event_index: CitreaEventIndex) -> Self {
+        self.event_index = Some(event_index);
+        self
+    }
+
+    /// Runs a read on a healthy Citrea RPC endpoint, failing over to the
+    /// others on errors.
+    async fn read<'a, T, F, Fut>(&'a self, request: &str, f: F) -> Result<T, BridgeError>
+    where
+        F: Fn(&'a CitreaEndpoint) -> Fut + Send,
+        Fut: Future<Output = Result<T, BridgeError>> + Send,
+    {
+        self.rpc_endpoints.check_lag().await;
+        self.rpc_endpoints.failover(request, f).await
+    }
+
+    /// Same as [`Self::read`] if no quorum is configured. Otherwise the read
+    /// is sent to every endpoint and the quorum must agree on the result.
+    async fn critical_read<'a, T, F, Fut>(&'a self, request: &str, f: F) -> Result<T, BridgeError>
+    where
+        T: PartialEq + Debug,
+        F: Fn(&'a CitreaEndpoint) -> Fut + Send,
+        Fut: Future<Output = Result<T, BridgeError>> + Send,
+    {
+        match self.quorum {
+            Some(quorum) => self.rpc_endpoints.quorum(quorum, request, f).await,
+            None => self.read(request, f).await,
+        }
+    }
+}
+
+impl CitreaEndpoint {
+    /// Returns the bridge contract at the address it has at `l2_height`.
+    fn contract_at(&self, l2_height: u64) -> CitreaContract {
+        self.contract.clone().at(bridge_contract_address(l2_height))
+    }
+
     /// Returns all logs for the given filter and block range while considering
     /// about the 1000 block limit.
     async fn get_logs(
@@ -229,64 +640,39 @@ impl CitreaClient {
 
         Ok(logs)
     }
-}
 
-#[async_trait]
-impl CitreaClientT for CitreaClient {
-    /// Fetches the storage proof for a given deposit index and transaction ID.
-    ///
-    /// This function interacts with an Citrea RPC endpoint to retrieve a storage proof,
-    /// which includes proof details for both the UTXO and the deposit index.
-    ///
-    /// # Arguments
-    /// * `l2_height` - A `u64` representing the L2 block height.
-    /// * `deposit_index` - A `u32` representing the deposit index.
-    ///
-    /// # Returns
-    /// Returns a `StorageProof` struct containing serialized storage proofs for the UTXO and deposit index.
+    async fn block_number(&self) -> Result<u64, BridgeError> {
+        // U256 is automatically deserialized from hex string by jsonrpsee
+        let block_number = self
+            .client
+            .block_number()
+            .await
+            .wrap_err("Failed to get L2 block height from Citrea RPC")?;
+
+        Ok(u64::try_from(block_number)
+            .map_err(|_| eyre::eyre!("L2 block height {} exceeds u64::MAX", block_number))?)
+    }
+
+    /// See [`CitreaClientT::get_storage_proof`].
     async fn get_storage_proof(
         &self,
         l2_height: u64,
         deposit_index: u32,
     ) -> Result<StorageProof, BridgeError> {
-        let ind = deposit_index;
-        let tx_index: u32 = ind * 2;
-
-        let storage_address_wd_utxo_bytes = keccak256(UTXOS_STORAGE_INDEX);
-        let storage_address_wd_utxo: U256 = U256::from_be_bytes(
-            <[u8; 32]>::try_from(&storage_address_wd_utxo_bytes[..])
-                .wrap_err("Storage address wd utxo bytes slice with incorrect length")?,
-        );
-
-        // Storage key address calculation UTXO
-        let storage_key_wd_utxo: U256 = storage_address_wd_utxo + U256::from(tx_index);
-        let storage_key_wd_utxo_hex =
-            format!("0x{}", hex::encode(storage_key_wd_utxo.to_be_bytes::<32>()));
-
-        // Storage key address calculation Vout
-        let storage_key_vout: U256 = storage_address_wd_utxo + U256::from(tx_index + 1);
-        let storage_key_vout_hex =
-            format!("0x{}", hex::encode(storage_key_vout.to_be_bytes::<32>()));
-
-        // Storage key address calculation Deposit
-        let storage_address_deposit_bytes = keccak256(DEPOSIT_STORAGE_INDEX);
-        let storage_address_deposit: U256 = U256::from_be_bytes(
-            <[u8; 32]>::try_from(&storage_address_deposit_bytes[..])
-                .wrap_err("Storage address deposit bytes slice with incorrect length")?,
-        );
-
-        let storage_key_deposit: U256 = storage_address_deposit + U256::from(deposit_index);
-        let storage_key_deposit_hex = hex::encode(storage_key_deposit.to_be_bytes::<32>());
-        let storage_key_deposit_hex = format!("0x{storage_key_deposit_hex}");
+        // Proofs must be for the storage slots the bridge circuit expects at
+        // this height.
+        let layout = bridge_contract_layout(l2_height);
+        let storage_keys = layout.storage_keys(deposit_index);
+        let storage_key_hex = |key: [u8; 32]| format!("0x{}", hex::encode(key));
 
         let response: serde_json::Value = self
             .client
             .get_proof(
-                BRIDGE_CONTRACT_ADDRESS,
+                &format!("0x{}", hex::encode(layout.address)),
                 vec![
-                    storage_key_wd_utxo_hex,
-                    storage_key_vout_hex,
-                    storage_key_deposit_hex,
+                    storage_key_hex(storage_keys.withdrawal_utxo_txid),
+                    storage_key_hex(storage_keys.withdrawal_utxo_vout),
+                    storage_key_hex(storage_keys.deposit_txid),
                 ],
                 format!("0x{l2_height:x}"),
             )
@@ -319,104 +705,11 @@ impl CitreaClientT for CitreaClient {
             storage_proof_utxo: serialized_utxo,
             storage_proof_vout: serialized_vout,
             storage_proof_deposit_txid: serialized_deposit,
-            index: ind,
-        })
-    }
-
-    async fn fetch_validate_and_store_lcp(
-        &self,
-        payout_block_height: u64,
-        deposit_index: u32,
-        db: &Database,
-        mut dbtx: Option<DatabaseTransaction<'_>>,
-        paramset: &'static ProtocolParamset,
-    ) -> Result<Receipt, BridgeError> {
-        let saved_data = db
-            .get_lcp_for_assert(dbtx.as_deref_mut(), deposit_index)
-            .await?;
-        if let Some(lcp) = saved_data {
-            // if already saved, do nothing
-            return Ok(lcp);
-        };
-
-        let lcp_result = self
-            .get_light_client_proof(payout_block_height, paramset)
-            .await?;
-        let (_lcp, lcp_receipt, _l2_height) = match lcp_result {
-            Some(lcp) => lcp,
-            None => {
-                return Err(eyre::eyre!(
-                    "Light client proof could not be fetched found for block height {}",
-                    payout_block_height
-                )
-                .into())
-            }
-        };
-
-        // save the LCP for assert
-        db.insert_lcp_for_assert(dbtx, deposit_index, lcp_receipt.clone())
-            .await?;
-
-        Ok(lcp_receipt)
-    }
-
-    async fn new(
-        citrea_rpc_url: String,
-        light_client_prover_url: String,
-        chain_id: u32,
-        secret_key: Option<PrivateKeySigner>,
-        timeout: Option<Duration>,
-    ) -> Result<Self, BridgeError> {
-        let citrea_rpc_url = Url::parse(&citrea_rpc_url).wrap_err("Can't parse Citrea RPC URL")?;
-        let light_client_prover_url =
-            Url::parse(&light_client_prover_url).wrap_err("Can't parse Citrea LCP RPC URL")?;
-        let secret_key = secret_key.unwrap_or(PrivateKeySigner::random());
-
-        let key = secret_key.with_chain_id(Some(chain_id.into()));
-
-        #[cfg(test)]
-        let wallet_address = key.address();
-
-        tracing::info!("Wallet address: {}", key.address());
-
-        let provider = ProviderBuilder::new()
-            .wallet(EthereumWallet::from(key))
-            .on_http(citrea_rpc_url.clone());
-
-        tracing::info!("Provider created");
-
-        let contract = BRIDGE_CONTRACT::new(
-            BRIDGE_CONTRACT_ADDRESS
-                .parse()
-                .expect("Correct contract address"),
-            provider,
-        );
-
-        tracing::info!("Contract created");
-
-        let client = HttpClientBuilder::default()
-            .request_timeout(timeout.unwrap_or(Duration::from_secs(60)))
-            .build(citrea_rpc_url)
-            .wrap_err("Failed to create Citrea RPC client")?;
-
-        tracing::info!("Citrea RPC client created");
-
-        let light_client_prover_client = HttpClientBuilder::default()
-            .request_timeout(timeout.unwrap_or(Duration::from_secs(60)))
-            .build(light_client_prover_url)
-            .wrap_err("Failed to create Citrea LCP RPC client")?;
-
-        tracing::info!("Citrea LCP RPC client created");
-
-        Ok(CitreaClient {
-            client,
-            light_client_prover_client,
-            #[cfg(test)]
-            wallet_address,
-            contract,
+            index: deposit_index,
         })
     }
 
+    /// See [`CitreaClientT::collect_deposit_move_txids`].
     async fn collect_deposit_move_txids(
         &self,
         last_deposit_idx: Option<u32>,
@@ -431,7 +724,7 @@ impl CitreaClientT for CitreaClient {
 
         loop {
             let deposit_txid = self
-                .contract
+                .contract_at(to_height)
                 .depositTxIds(U256::from(start_idx))
                 .block(BlockId::Number(BlockNumberOrTag::Number(to_height)))
                 .call()
@@ -455,6 +748,7 @@ impl CitreaClientT for CitreaClient {
         Ok(move_txids)
     }
 
+    /// See [`CitreaClientT::collect_withdrawal_utxos`].
     async fn collect_withdrawal_utxos(
         &self,
         last_withdrawal_idx: Option<u32>,
@@ -469,7 +763,7 @@ impl CitreaClientT for CitreaClient {
 
         loop {
             let withdrawal_utxo = self
-                .contract
+                .contract_at(to_height)
                 .withdrawalUTXOs(U256::from(start_idx))
                 .block(BlockId::Number(BlockNumberOrTag::Number(to_height)))
                 .call()
@@ -495,22 +789,288 @@ impl CitreaClientT for CitreaClient {
         Ok(utxos)
     }
 
+    /// See [`CitreaClientT::get_replacement_deposit_move_txids`].
+    async fn get_replacement_deposit_move_txids(
+        &self,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Vec<(u32, Txid)>, BridgeError> {
+        let mut replacement_move_txids = vec![];
+
+        // get logs
+        let filter = self
+            .contract
+            .event_filter::<DepositReplaced>()
+            .filter
+            .address(bridge_contract_addresses(from_height, to_height));
+        let logs = self.get_logs(filter, from_height, to_height).await?;
+
+        for log in logs {
+            let replacement_raw_data = &log.data().data;
+
+            let idx = DepositReplaced::abi_decode_data(replacement_raw_data, false)
+                .wrap_err("Failed to decode replacement deposit data")?
+                .0;
+            let new_move_txid = DepositReplaced::abi_decode_data(replacement_raw_data, false)
+                .wrap_err("Failed to decode replacement deposit data")?
+                .2;
+
+            let idx = u32::try_from(idx).wrap_err("Failed to convert idx to u32")?;
+            let new_move_txid = Txid::from_slice(new_move_txid.as_ref())
+                .wrap_err("Failed to convert new move txid to Txid")?;
+
+            replacement_move_txids.push((idx, new_move_txid));
+        }
+
+        Ok(replacement_move_txids)
+    }
+
+    /// Returns the N-of-N key stored in the bridge contract.
+    async fn get_aggregated_key(&self) -> Result<XOnlyPublicKey, BridgeError> {
+        let contract_nofn_xonly_pk = self
+            .contract
+            .getAggregatedKey()
+            .call()
+            .await
+            .wrap_err("Failed to get script prefix")?
+            ._0;
+
+        Ok(XOnlyPublicKey::from_slice(contract_nofn_xonly_pk.as_ref())
+            .wrap_err("Failed to convert citrea contract script nofn bytes to xonly pk")?)
+    }
+
+    /// See [`CitreaClientT::get_l2_block_ref`].
+    async fn get_l2_block_ref(&self, l2_height: u64) -> Result<Option<L2BlockRef>, BridgeError> {
+        Ok(self
+            .client
+            .get_block_by_number(format!("0x{l2_height:x}"), false)
+            .await
+            .wrap_err_with(|| format!("Failed to get L2 block {l2_height} from Citrea RPC"))?)
+    }
+
+    /// See [`CitreaClientT::get_bridge_events`].
+    async fn get_bridge_events(
+        &self,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Vec<CitreaBridgeEvent>, BridgeError> {
+        let filter = Filter::new().address(bridge_contract_addresses(from_height, to_height));
+        let logs = self.get_logs(filter, from_height, to_height).await?;
+
+        let mut events = vec![];
+        for log in logs {
+            if log.removed {
+                continue;
+            }
+            let Some(kind) = decode_bridge_event(&log)? else {
+                continue;
+            };
+
+            events.push(CitreaBridgeEvent {
+                l2_height: log
+                    .block_number
+                    .ok_or_else(|| eyre::eyre!("Bridge event log has no block number"))?,
+                block_hash: log
+                    .block_hash
+                    .ok_or_else(|| eyre::eyre!("Bridge event log has no block hash"))?,
+                log_index: log
+                    .log_index
+                    .ok_or_else(|| eyre::eyre!("Bridge event log has no log index"))?,
+                kind,
+            });
+        }
+
+        events.sort_by_key(|event| (event.l2_height, event.log_index));
+        Ok(events)
+    }
+}
+
+#[async_trait]
+impl CitreaClientT for CitreaClient {
+    /// Fetches the storage proof for a given deposit index and transaction ID.
+    ///
+    /// This function interacts with an Citrea RPC endpoint to retrieve a storage proof,
+    /// which includes proof details for both the UTXO and the deposit index.
+    ///
+    /// # Arguments
+    /// * `l2_height` - A `u64` representing the L2 block height.
+    /// * `deposit_index` - A `u32` representing the deposit index.
+    ///
+    /// # Returns
+    /// Returns a `StorageProof` struct containing serialized storage proofs for the UTXO and deposit index.
+    async fn get_storage_proof(
+        &self,
+        l2_height: u64,
+        deposit_index: u32,
+    ) -> Result<StorageProof, BridgeError> {
+        self.critical_read("get_storage_proof", |ep| {
+            ep.get_storage_proof(l2_height, deposit_index)
+        })
+        .await
+    }
+
+    async fn fetch_validate_and_store_lcp(
+        &self,
+        payout_block_height: u64,
+        deposit_index: u32,
+        db: &Database,
+        mut dbtx: Option<DatabaseTransaction<'_>>,
+        paramset: &'static ProtocolParamset,
+    ) -> Result<Receipt, BridgeError> {
+        let proof_store = PostgresProofStore::new(db.clone());
+        let saved_data = db
+            .get_lcp_for_assert(dbtx.as_deref_mut(), deposit_index)
+            .await?;
+        if let Some(proof_id) = saved_data {
+            // if already saved, do nothing
+            return proof_store
+                .get_referenced(dbtx.as_deref_mut(), &proof_id)
+                .await;
+        };
+
+        let lcp_result = self
+            .get_light_client_proof(payout_block_height, paramset)
+            .await?;
+        let (_lcp, lcp_receipt, _l2_height) = match lcp_result {
+            Some(lcp) => lcp,
+            None => {
+                return Err(eyre::eyre!(
+                    "Light client proof could not be fetched found for block height {}",
+                    payout_block_height
+                )
+                .into())
+            }
+        };
+
+        let payout_block_height =
+            u32::try_from(payout_block_height).wrap_err(BridgeError::IntConversionError)?;
+        let proof_id = proof_store
+            .put_with_tx(
+                dbtx.as_deref_mut(),
+                &lcp_receipt,
+                ProofMetadata::new(
+                    ProofKind::LightClient,
+                    paramset.network,
+                    Some((payout_block_height, payout_block_height)),
+                ),
+            )
+            .await?;
+
+        // save the LCP for assert
+        db.insert_lcp_for_assert(dbtx, deposit_index, proof_id)
+            .await?;
+
+        Ok(lcp_receipt)
+    }
+
+    async fn new(
+        citrea_rpc_url: String,
+        light_client_prover_url: String,
+        chain_id: u32,
+        secret_key: Option<PrivateKeySigner>,
+        timeout: Option<Duration>,
+    ) -> Result<Self, BridgeError> {
+        Self::with_endpoints(
+            vec![citrea_rpc_url],
+            vec![light_client_prover_url],
+            chain_id,
+            secret_key,
+            timeout,
+            None,
+        )
+        .await
+    }
+
+    async fn from_config(config: &BridgeConfig) -> Result<Self, BridgeError> {
+        let client = Self::with_endpoints(
+            std::iter::once(config.citrea_rpc_url.clone())
+                .chain(config.citrea_rpc_fallback_urls.iter().cloned())
+                .collect(),
+            std::iter::once(config.citrea_light_client_prover_url.clone())
+                .chain(
+                    config
+                        .citrea_light_client_prover_fallback_urls
+                        .iter()
+                        .cloned(),
+                )
+                .collect(),
+            config.citrea_chain_id,
+            None,
+            config.citrea_request_timeout,
+            config.citrea_rpc_quorum,
+        )
+        .await?;
+
+        if !config.citrea_indexer {
+            return Ok(client);
+        }
+        Ok(client.with_event_index(CitreaEventIndex::new(Database::new(config).await?)))
+    }
+
+    async fn collect_deposit_move_txids(
+        &self,
+        last_deposit_idx: Option<u32>,
+        to_height: u64,
+    ) -> Result<Vec<(u64, Txid)>, BridgeError> {
+        if let Some(event_index) = &self.event_index {
+            if let Some(deposits) = event_index
+                .collect_deposit_move_txids(last_deposit_idx, to_height)
+                .await?
+            {
+                return Ok(deposits);
+            }
+            tracing::debug!("Citrea event index doesn't cover deposits after {last_deposit_idx:?} up to L2 height {to_height}");
+        }
+
+        self.read("collect_deposit_move_txids", |ep| {
+            ep.collect_deposit_move_txids(last_deposit_idx, to_height)
+        })
+        .await
+    }
+
+    async fn collect_withdrawal_utxos(
+        &self,
+        last_withdrawal_idx: Option<u32>,
+        to_height: u64,
+    ) -> Result<Vec<(u64, OutPoint)>, BridgeError> {
+        // The index is built from events read with `critical_read`.
+        if let Some(event_index) = &self.event_index {
+            if let Some(withdrawals) = event_index
+                .collect_withdrawal_utxos(last_withdrawal_idx, to_height)
+                .await?
+            {
+                return Ok(withdrawals);
+            }
+            tracing::debug!("Citrea event index doesn't cover withdrawals after {last_withdrawal_idx:?} up to L2 height {to_height}");
+        }
+
+        self.critical_read("collect_withdrawal_utxos", |ep| {
+            ep.collect_withdrawal_utxos(last_withdrawal_idx, to_height)
+        })
+        .await
+    }
+
     async fn get_light_client_proof(
         &self,
         l1_height: u64,
         paramset: &'static ProtocolParamset,
     ) -> Result<Option<(LightClientProof, Receipt, u64)>, BridgeError> {
+        // The proof is verified below (except on regtest), so there is no need
+        // to ask several endpoints.
         let proof_result = self
-            .light_client_prover_client
-            .get_light_client_proof_by_l1_height(l1_height)
-            .await
-            .wrap_err("Failed to get light client proof")?;
+            .light_client_prover_endpoints
+            .failover("get_light_client_proof", |client| async move {
+                Ok(client
+                    .get_light_client_proof_by_l1_height(l1_height)
+                    .await
+                    .wrap_err("Failed to get light client proof")?)
+            })
+            .await?;
         tracing::debug!(
             "Light client proof result {}: {:?}",
             l1_height,
             proof_result
         );
-
         let ret = if let Some(proof_result) = proof_result {
             let decoded: InnerReceipt = bincode::deserialize(&proof_result.proof)
                 .wrap_err("Failed to deserialize light client proof from citrea lcp")?;
@@ -595,30 +1155,22 @@ impl CitreaClientT for CitreaClient {
         from_height: u64,
         to_height: u64,
     ) -> Result<Vec<(u32, Txid)>, BridgeError> {
-        let mut replacement_move_txids = vec![];
-
-        // get logs
-        let filter = self.contract.event_filter::<DepositReplaced>().filter;
-        let logs = self.get_logs(filter, from_height, to_height).await?;
-
-        for log in logs {
-            let replacement_raw_data = &log.data().data;
-
-            let idx = DepositReplaced::abi_decode_data(replacement_raw_data, false)
-                .wrap_err("Failed to decode replacement deposit data")?
-                .0;
-            let new_move_txid = DepositReplaced::abi_decode_data(replacement_raw_data, false)
-                .wrap_err("Failed to decode replacement deposit data")?
-                .2;
-
-            let idx = u32::try_from(idx).wrap_err("Failed to convert idx to u32")?;
-            let new_move_txid = Txid::from_slice(new_move_txid.as_ref())
-                .wrap_err("Failed to convert new move txid to Txid")?;
-
-            replacement_move_txids.push((idx, new_move_txid));
+        if let Some(event_index) = &self.event_index {
+            if let Some(replacements) = event_index
+                .get_replacement_deposit_move_txids(from_height, to_height)
+                .await?
+            {
+                return Ok(replacements);
+            }
+            tracing::debug!(
+                "Citrea event index doesn't cover L2 heights {from_height}..={to_height}"
+            );
         }
 
-        Ok(replacement_move_txids)
+        self.read("get_replacement_deposit_move_txids", |ep| {
+            ep.get_replacement_deposit_move_txids(from_height, to_height)
+        })
+        .await
     }
 
     async fn check_nofn_correctness(
@@ -630,15 +1182,8 @@ impl CitreaClientT for CitreaClient {
         }
 
         let contract_nofn_xonly_pk = self
-            .contract
-            .getAggregatedKey()
-            .call()
-            .await
-            .wrap_err("Failed to get script prefix")?
-            ._0;
-
-        let contract_nofn_xonly_pk = XOnlyPublicKey::from_slice(contract_nofn_xonly_pk.as_ref())
-            .wrap_err("Failed to convert citrea contract script nofn bytes to xonly pk")?;
+            .critical_read("check_nofn_correctness", |ep| ep.get_aggregated_key())
+            .await?;
         if contract_nofn_xonly_pk != nofn_xonly_pk {
             return Err(eyre::eyre!("Nofn of deposit does not match with citrea contract").into());
         }
@@ -647,20 +1192,135 @@ impl CitreaClientT for CitreaClient {
 
     async fn get_current_l2_block_height(&self) -> Result<u32, BridgeError> {
         // Query Citrea RPC to get the current L2 block number
-        // U256 is automatically deserialized from hex string by jsonrpsee
-        let block_number_u256 = self
-            .client
-            .block_number()
-            .await
-            .wrap_err("Failed to get L2 block height from Citrea RPC")?;
+        let block_number = self
+            .read("get_current_l2_block_height", |ep| ep.block_number())
+            .await?;
 
-        // Convert U256 to u32, return error if it doesn't fit
-        let block_number: u32 = block_number_u256
+        // Convert to u32, return error if it doesn't fit
+        let block_number: u32 = block_number
             .try_into()
-            .map_err(|_| eyre::eyre!("L2 block height {} exceeds u32::MAX", block_number_u256))
+            .map_err(|_| eyre::eyre!("L2 block height {} exceeds u32::MAX", block_number))
             .wrap_err("Failed to convert L2 block height to u32")?;
         Ok(block_number)
     }
+
+    async fn get_l2_block_ref(&self, l2_height: u64) -> Result<Option<L2BlockRef>, BridgeError> {
+        self.read("get_l2_block_ref", |ep| ep.get_l2_block_ref(l2_height))
+            .await
+    }
+
+    async fn get_bridge_events(
+        &self,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Vec<CitreaBridgeEvent>, BridgeError> {
+        // Withdrawals are read from the indexed events, so they need the same
+        // agreement as `collect_withdrawal_utxos`.
+        self.critical_read("get_bridge_events", |ep| {
+            ep.get_bridge_events(from_height, to_height)
+        })
+        .await
+    }
+}
+
+/// Returns the address of the bridge contract at the given L2 height, taken
+/// from its storage layout at that height.
+fn bridge_contract_address(l2_height: u64) -> Address {
+    Address::from(bridge_contract_layout(l2_height).address)
+}
+
+/// Returns the addresses the bridge contract has in the given range of L2
+/// heights (inclusive).
+fn bridge_contract_addresses(from_height: u64, to_height: u64) -> Vec<Address> {
+    let mut addresses = vec![bridge_contract_address(from_height)];
+    for layout in BRIDGE_CONTRACT_LAYOUTS {
+        let address = Address::from(layout.address);
+        if layout.start_l2_height > from_height
+            && layout.start_l2_height <= to_height
+            && !addresses.contains(&address)
+        {
+            addresses.push(address);
+        }
+    }
+    addresses
+}
+
+/// Decodes a bridge contract log into a [`CitreaBridgeEventKind`]. Returns
+/// [`None`] for events that are not relevant to clementine.
+fn decode_bridge_event(log: &Log) -> Result<Option<CitreaBridgeEventKind>, BridgeError> {
+    let Some(topic) = log.topic0() else {
+        return Ok(None);
+    };
+
+    let to_u32 = |value: U256| -> Result<u32, BridgeError> {
+        Ok(u32::try_from(value).wrap_err("Failed to convert bridge event index to u32")?)
+    };
+    let to_txid = |txid: B256| -> Result<Txid, BridgeError> {
+        Ok(Txid::from_slice(txid.as_ref()).wrap_err("Failed to convert bridge event txid")?)
+    };
+
+    let kind = if *topic == Deposit::SIGNATURE_HASH {
+        let event = log
+            .log_decode::<Deposit>()
+            .wrap_err("Failed to decode Deposit event")?
+            .inner
+            .data;
+        CitreaBridgeEventKind::Deposit {
+            idx: to_u32(event.depositId)?,
+            move_txid: to_txid(event.txId)?,
+        }
+    } else if *topic == DepositTransferFailed::SIGNATURE_HASH {
+        // The deposit is still recorded by the contract even if the transfer failed.
+        let event = log
+            .log_decode::<DepositTransferFailed>()
+            .wrap_err("Failed to decode DepositTransferFailed event")?
+            .inner
+            .data;
+        CitreaBridgeEventKind::Deposit {
+            idx: to_u32(event.depositId)?,
+            move_txid: to_txid(event.txId)?,
+        }
+    } else if *topic == Withdrawal::SIGNATURE_HASH {
+        let event = log
+            .log_decode::<Withdrawal>()
+            .wrap_err("Failed to decode Withdrawal event")?
+            .inner
+            .data;
+        CitreaBridgeEventKind::Withdrawal {
+            idx: to_u32(event.index)?,
+            utxo: OutPoint {
+                txid: to_txid(event.utxo.txId)?,
+                vout: u32::from_le_bytes(event.utxo.outputId.0),
+            },
+        }
+    } else if *topic == SafeWithdrawal::SIGNATURE_HASH {
+        let event = log
+            .log_decode::<SafeWithdrawal>()
+            .wrap_err("Failed to decode SafeWithdrawal event")?
+            .inner
+            .data;
+        CitreaBridgeEventKind::Withdrawal {
+            idx: to_u32(event.index)?,
+            utxo: OutPoint {
+                txid: to_txid(event.spentUtxo.txId)?,
+                vout: u32::from_le_bytes(event.spentUtxo.outputId.0),
+            },
+        }
+    } else if *topic == DepositReplaced::SIGNATURE_HASH {
+        let event = log
+            .log_decode::<DepositReplaced>()
+            .wrap_err("Failed to decode DepositReplaced event")?
+            .inner
+            .data;
+        CitreaBridgeEventKind::DepositReplaced {
+            idx: to_u32(event.index)?,
+            new_move_txid: to_txid(event.newTxId)?,
+        }
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(kind))
 }
 
 #[rpc(client, namespace = "lightClientProver")]
@@ -685,6 +1345,13 @@ pub trait CitreaRpc {
 
     #[method(name = "blockNumber")]
     async fn block_number(&self) -> RpcResult<U256>;
+
+    #[method(name = "getBlockByNumber")]
+    async fn get_block_by_number(
+        &self,
+        block: String,
+        full_transactions: bool,
+    ) -> RpcResult<Option<L2BlockRef>>;
 }
 
 // Ugly typedefs.
@@ -701,3 +1368,72 @@ type CitreaContract = BRIDGE_CONTRACT::BRIDGE_CONTRACTInstance<
         RootProvider,
     >,
 >;
+
+#[cfg(test)]
+mod tests {
+    use super::{bridge_contract_address, bridge_contract_addresses, EndpointSet};
+    use alloy::primitives::Address;
+    use circuits_lib::bridge_circuit::storage_proof::BRIDGE_CONTRACT_LAYOUTS;
+    use clementine_errors::BridgeError;
+
+    fn respond(endpoint: &(bool, u32)) -> Result<u32, BridgeError> {
+        match endpoint {
+            (true, value) => Ok(*value),
+            (false, _) => Err(eyre::eyre!("endpoint down").into()),
+        }
+    }
+
+    #[tokio::test]
+    async fn endpoint_failover_prefers_healthy_endpoints() {
+        let endpoints = EndpointSet::new(vec![(false, 1), (true, 2), (true, 3)]);
+
+        let result = endpoints
+            .failover("test", |ep| async move { respond(ep) })
+            .await
+            .unwrap();
+        assert_eq!(result, 2);
+        // Failed primary is tried last until its backoff expires.
+        assert_eq!(endpoints.ordered(), vec![1, 2, 0]);
+
+        let all_down = EndpointSet::new(vec![(false, 1), (false, 2)]);
+        assert!(all_down
+            .failover("test", |ep| async move { respond(ep) })
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn endpoint_quorum() {
+        let endpoints = EndpointSet::new(vec![(true, 1), (true, 2), (true, 1), (false, 1)]);
+
+        let result = endpoints
+            .quorum(2, "test", |ep| async move { respond(ep) })
+            .await
+            .unwrap();
+        assert_eq!(result, 1);
+
+        assert!(endpoints
+            .quorum(3, "test", |ep| async move { respond(ep) })
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn bridge_contract_addresses_follow_layouts() {
+        for layout in BRIDGE_CONTRACT_LAYOUTS {
+            assert_eq!(
+                bridge_contract_address(layout.start_l2_height),
+                Address::from(layout.address)
+            );
+            assert_eq!(
+                bridge_contract_addresses(layout.start_l2_height, layout.start_l2_height),
+                vec![Address::from(layout.address)]
+            );
+        }
+
+        let addresses = bridge_contract_addresses(0, u64::MAX);
+        assert!(BRIDGE_CONTRACT_LAYOUTS
+            .iter()
+            .all(|layout| addresses.contains(&Address::from(layout.address))));
+    }
+}
@@ -55,6 +55,8 @@ pub enum Command {
     TestActor,
     /// Generate BitVM cache files
     GenerateBitvmCache,
+    /// Run a prover worker for the proving jobs in the configured database
+    ProverWorker,
 }
 
 /// Parse given iterator with our clap Args and handle help/version cases.
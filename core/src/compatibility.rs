@@ -8,7 +8,7 @@ use crate::aggregator::Aggregator;
 use crate::bitvm_client::{load_or_generate_bitvm_cache, BITVM_CACHE};
 use crate::citrea::CitreaClientT;
 use crate::config::protocol::{ProtocolParamset, ProtocolParamsetExt};
-use crate::config::BridgeConfig;
+use crate::config::{BridgeConfig, GrpcCompression};
 use crate::deposit::SecurityCouncil;
 use crate::operator::Operator;
 use crate::rpc::clementine::CompatibilityParamsRpc;
@@ -24,24 +24,43 @@ pub struct CompatibilityParams {
     pub clementine_version: String,
     pub bridge_circuit_constant: [u8; 32],
     pub sha256_bitvm_cache: [u8; 32],
+    /// Supported gRPC compressions in order of preference. Not checked for
+    /// compatibility, only used to negotiate request compression.
+    pub grpc_compression: Vec<GrpcCompression>,
+    /// Maximum number of signatures accepted in one message of the signature
+    /// streams the actor receives, 0 for versions without batching. Not
+    /// checked for compatibility.
+    pub max_sigs_per_message: u32,
 }
 
 impl std::fmt::Display for CompatibilityParams {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "CompatibilityParams {{\n  protocol_paramset: {:?},\n  security_council: {},\n  citrea_chain_id: {},\n  clementine_version: {},\n  bridge_circuit_constant: {},\n  sha256_bitvm_cache: {}\n}}",
+            "CompatibilityParams {{\n  protocol_paramset: {:?},\n  security_council: {},\n  citrea_chain_id: {},\n  clementine_version: {},\n  bridge_circuit_constant: {},\n  sha256_bitvm_cache: {},\n  grpc_compression: {:?},\n  max_sigs_per_message: {}\n}}",
             self.protocol_paramset,
             self.security_council,
             self.citrea_chain_id,
             self.clementine_version,
             hex::encode(self.bridge_circuit_constant),
             hex::encode(self.sha256_bitvm_cache),
+            self.grpc_compression,
+            self.max_sigs_per_message,
         )
     }
 }
 
 impl CompatibilityParams {
+    /// Returns the first compression in own order of preference that the
+    /// other actor supports. Peers that don't advertise compressions get
+    /// uncompressed requests.
+    pub fn negotiate_compression(&self, other: &CompatibilityParams) -> Option<GrpcCompression> {
+        self.grpc_compression
+            .iter()
+            .find(|compression| other.grpc_compression.contains(compression))
+            .copied()
+    }
+
     /// Returns an error with reason if not compatible, otherwise returns Ok(())
     /// For Protocol paramset, security council and citrea chain ID, we only check if they are different.
     /// For Clementine version, we allow different patch versions, but not different major or minor versions.
@@ -129,6 +148,12 @@ impl TryFrom<CompatibilityParams> for CompatibilityParamsRpc {
             clementine_version: params.clementine_version,
             bridge_circuit_constant: params.bridge_circuit_constant.to_vec(),
             sha256_bitvm_cache: params.sha256_bitvm_cache.to_vec(),
+            grpc_compression: params
+                .grpc_compression
+                .iter()
+                .map(|compression| compression.to_string())
+                .collect(),
+            max_sigs_per_message: params.max_sigs_per_message,
         })
     }
 }
@@ -153,6 +178,13 @@ impl TryFrom<CompatibilityParamsRpc> for CompatibilityParams {
                 .sha256_bitvm_cache
                 .try_into()
                 .map_err(|_| eyre::eyre!("Failed to convert sha256 bitvm cache to [u8; 32]"))?,
+            // compressions unknown to this version are skipped
+            grpc_compression: params
+                .grpc_compression
+                .iter()
+                .filter_map(|compression| compression.parse().ok())
+                .collect(),
+            max_sigs_per_message: params.max_sigs_per_message,
         })
     }
 }
@@ -171,6 +203,8 @@ pub trait ActorWithConfig {
             sha256_bitvm_cache: BITVM_CACHE
                 .get_or_try_init(load_or_generate_bitvm_cache)?
                 .sha256_bitvm_cache,
+            grpc_compression: config.grpc.compression.clone(),
+            max_sigs_per_message: config.grpc.max_sigs_per_message,
         })
     }
 
@@ -393,6 +427,8 @@ mod tests {
             security_council: create_test_security_council(),
             citrea_chain_id: 1234,
             clementine_version: version.to_string(),
+            grpc_compression: vec![GrpcCompression::Zstd, GrpcCompression::Gzip],
+            max_sigs_per_message: 100,
         }
     }
 
@@ -551,6 +587,39 @@ mod tests {
         );
         assert_eq!(params.sha256_bitvm_cache, params_back.sha256_bitvm_cache);
         assert_eq!(params.clementine_version, params_back.clementine_version);
+        assert_eq!(params.grpc_compression, params_back.grpc_compression);
+        assert_eq!(
+            params.max_sigs_per_message,
+            params_back.max_sigs_per_message
+        );
+    }
+
+    #[test]
+    fn test_negotiate_compression() {
+        let own = create_test_compatibility_params("1.2.3");
+
+        let mut other = create_test_compatibility_params("1.2.3");
+        other.grpc_compression = vec![GrpcCompression::Gzip, GrpcCompression::Zstd];
+        // own order of preference wins
+        assert_eq!(
+            own.negotiate_compression(&other),
+            Some(GrpcCompression::Zstd)
+        );
+
+        other.grpc_compression = vec![GrpcCompression::Gzip];
+        assert_eq!(
+            own.negotiate_compression(&other),
+            Some(GrpcCompression::Gzip)
+        );
+
+        // compressions unknown to this version are skipped
+        let mut rpc_params: CompatibilityParamsRpc = other.try_into().unwrap();
+        rpc_params.grpc_compression = vec!["brotli".to_string()];
+        let other: CompatibilityParams = rpc_params.try_into().unwrap();
+        assert!(other.grpc_compression.is_empty());
+        assert_eq!(own.negotiate_compression(&other), None);
+        // compression is not part of the compatibility check
+        assert!(own.is_compatible(&other).is_ok());
     }
 
     #[test]
@@ -3,14 +3,17 @@
 use super::BridgeConfig;
 use crate::{
     config::{
-        default_grpc_limits, default_tx_sender_limits, GrpcLimits, GrpcLimitsExt, TelemetryConfig,
+        default_bitcoin_rpc_max_tip_divergence, default_grpc_limits, default_tx_sender_limits,
+        GrpcCompression, GrpcLimits, GrpcLimitsExt, HealthConfig, HealthConfigExt, TelemetryConfig,
         TelemetryConfigExt, TxSenderLimits, TxSenderLimitsExt,
     },
     deposit::SecurityCouncil,
+    extended_bitcoin_rpc::BitcoindBackend,
 };
 use bitcoin::{address::NetworkUnchecked, secp256k1::SecretKey, Amount};
 use clementine_errors::BridgeError;
 use eyre::Context;
+use secrecy::SecretString;
 use std::{path::PathBuf, str::FromStr, time::Duration};
 
 pub(crate) fn read_string_from_env(env_var: &'static str) -> Result<String, BridgeError> {
@@ -28,6 +31,20 @@ where
         .map_err(|e| BridgeError::EnvVarMalformed(env_var, format!("{e:?}")))
 }
 
+/// Reads a comma separated list of URLs. Returns an empty list if the variable
+/// is not set.
+fn read_url_list_from_env(env_var: &'static str) -> Vec<String> {
+    std::env::var(env_var)
+        .map(|urls| {
+            urls.split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 impl GrpcLimitsExt for GrpcLimits {
     fn from_env() -> Result<GrpcLimits, BridgeError> {
         let defaults = default_grpc_limits();
@@ -50,10 +67,36 @@ impl GrpcLimitsExt for GrpcLimits {
                 "GRPC_RATELIMIT_REQ_INTERVAL_SECS",
             )
             .unwrap_or(defaults.ratelimit_req_interval_secs),
+            compression: match read_string_from_env("GRPC_COMPRESSION") {
+                Ok(compression) => parse_grpc_compression(&compression)?,
+                Err(_) => defaults.compression,
+            },
+            max_sigs_per_message: read_string_from_env_then_parse::<u32>(
+                "GRPC_MAX_SIGS_PER_MESSAGE",
+            )
+            .unwrap_or(defaults.max_sigs_per_message),
         })
     }
 }
 
+/// Parses a comma separated list of gRPC compressions. `none` or an empty
+/// value disables compression.
+fn parse_grpc_compression(value: &str) -> Result<Vec<GrpcCompression>, BridgeError> {
+    if value.trim().eq_ignore_ascii_case("none") {
+        return Ok(Vec::new());
+    }
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|compression| !compression.is_empty())
+        .map(|compression| {
+            compression
+                .parse()
+                .map_err(|e| BridgeError::EnvVarMalformed("GRPC_COMPRESSION", e))
+        })
+        .collect()
+}
+
 impl TxSenderLimitsExt for TxSenderLimits {
     fn from_env() -> Result<TxSenderLimits, BridgeError> {
         let defaults = default_tx_sender_limits();
@@ -80,6 +123,13 @@ impl TxSenderLimitsExt for TxSenderLimits {
             .unwrap_or(defaults.fee_bump_after_blocks),
             min_bump_kvb: read_string_from_env_then_parse::<u64>("TX_SENDER_MIN_BUMP_KVB")
                 .unwrap_or(defaults.min_bump_kvb),
+            // Unlike the other limits, a malformed fee budget is rejected
+            // instead of silently falling back to no budget. Only the tx
+            // sender uses it.
+            #[cfg(feature = "automation")]
+            fee_budget: clementine_tx_sender::config::fee_budget_from_env()?,
+            #[cfg(not(feature = "automation"))]
+            fee_budget: defaults.fee_budget,
         })
     }
 }
@@ -190,6 +240,28 @@ impl BridgeConfig {
             .and_then(|timeout| timeout.parse::<u64>().ok())
             .map(Duration::from_secs);
 
+        let bitcoin_rpc_user: SecretString = read_string_from_env("BITCOIN_RPC_USER")?.into();
+        let bitcoin_rpc_password: SecretString =
+            read_string_from_env("BITCOIN_RPC_PASSWORD")?.into();
+
+        // A malformed quorum must not silently disable the consistency checks.
+        let citrea_rpc_quorum = match std::env::var("CITREA_RPC_QUORUM") {
+            Ok(_) => Some(read_string_from_env_then_parse::<usize>(
+                "CITREA_RPC_QUORUM",
+            )?),
+            Err(_) => None,
+        };
+        let min_deposit_verifiers = match std::env::var("MIN_DEPOSIT_VERIFIERS") {
+            Ok(_) => Some(read_string_from_env_then_parse::<usize>(
+                "MIN_DEPOSIT_VERIFIERS",
+            )?),
+            Err(_) => None,
+        };
+        let citrea_indexer_start_height = match std::env::var("CITREA_INDEXER_START_HEIGHT") {
+            Ok(_) => read_string_from_env_then_parse::<u64>("CITREA_INDEXER_START_HEIGHT")?,
+            Err(_) => 0,
+        };
+
         let config = BridgeConfig {
             // Protocol paramset's source is independently defined
             protocol_paramset: Default::default(),
@@ -200,8 +272,21 @@ impl BridgeConfig {
             operator_reimbursement_address,
             operator_collateral_funding_outpoint,
             bitcoin_rpc_url: read_string_from_env("BITCOIN_RPC_URL")?,
-            bitcoin_rpc_user: read_string_from_env("BITCOIN_RPC_USER")?.into(),
-            bitcoin_rpc_password: read_string_from_env("BITCOIN_RPC_PASSWORD")?.into(),
+            bitcoin_rpc_user: bitcoin_rpc_user.clone(),
+            bitcoin_rpc_password: bitcoin_rpc_password.clone(),
+            // Fallback backends share the credentials of the primary one.
+            bitcoin_rpc_fallbacks: read_url_list_from_env("BITCOIN_RPC_FALLBACK_URLS")
+                .into_iter()
+                .map(|url| BitcoindBackend {
+                    url,
+                    user: bitcoin_rpc_user.clone(),
+                    password: bitcoin_rpc_password.clone(),
+                })
+                .collect(),
+            bitcoin_rpc_max_tip_divergence: read_string_from_env_then_parse::<u32>(
+                "BITCOIN_RPC_MAX_TIP_DIVERGENCE",
+            )
+            .unwrap_or(default_bitcoin_rpc_max_tip_divergence()),
             mempool_api_host: read_string_from_env("MEMPOOL_API_HOST").ok(),
             mempool_api_endpoint: read_string_from_env("MEMPOOL_API_ENDPOINT").ok(),
             db_host: read_string_from_env("DB_HOST")?,
@@ -213,13 +298,24 @@ impl BridgeConfig {
             citrea_light_client_prover_url: read_string_from_env("CITREA_LIGHT_CLIENT_PROVER_URL")?,
             citrea_chain_id: read_string_from_env_then_parse::<u32>("CITREA_CHAIN_ID")?,
             citrea_request_timeout,
+            citrea_rpc_fallback_urls: read_url_list_from_env("CITREA_RPC_FALLBACK_URLS"),
+            citrea_light_client_prover_fallback_urls: read_url_list_from_env(
+                "CITREA_LIGHT_CLIENT_PROVER_FALLBACK_URLS",
+            ),
+            citrea_rpc_quorum,
+            citrea_indexer: read_string_from_env("CITREA_INDEXER")
+                .is_ok_and(|s| s == "true" || s == "1"),
+            citrea_indexer_start_height,
             bridge_contract_address: read_string_from_env("BRIDGE_CONTRACT_ADDRESS")?,
             header_chain_proof_batch_size: read_string_from_env_then_parse::<u32>(
                 "HEADER_CHAIN_PROOF_BATCH_SIZE",
             )?,
+            external_prover_workers: read_string_from_env("EXTERNAL_PROVER_WORKERS")
+                .is_ok_and(|s| s == "true" || s == "1"),
             header_chain_proof_path,
             verifier_endpoints,
             operator_endpoints,
+            min_deposit_verifiers,
             security_council,
             aggregator_verification_address,
             client_verification,
@@ -241,8 +337,11 @@ impl BridgeConfig {
             }),
 
             telemetry: TelemetryConfig::from_env().ok(),
+            health: HealthConfig::from_env().ok(),
             grpc: GrpcLimits::from_env()?,
             tx_sender_limits: TxSenderLimits::from_env()?,
+            tx_sender_dry_run: read_string_from_env("TX_SENDER_DRY_RUN")
+                .is_ok_and(|s| s == "true" || s == "1"),
 
             time_to_send_watchtower_challenge: read_string_from_env_then_parse::<u16>(
                 "TIME_TO_SEND_WATCHTOWER_CHALLENGE",
@@ -340,6 +439,9 @@ mod tests {
         if let Some(ref operator_endpoints) = default_config.operator_endpoints {
             std::env::set_var("OPERATOR_ENDPOINTS", operator_endpoints.join(","));
         }
+        if let Some(min_deposit_verifiers) = default_config.min_deposit_verifiers {
+            std::env::set_var("MIN_DEPOSIT_VERIFIERS", min_deposit_verifiers.to_string());
+        }
 
         if let Some(ref operator_reimbursement_address) =
             default_config.operator_reimbursement_address
@@ -420,6 +522,20 @@ mod tests {
             "GRPC_RATELIMIT_REQ_COUNT",
             default_config.grpc.ratelimit_req_count.to_string(),
         );
+        std::env::set_var(
+            "GRPC_COMPRESSION",
+            default_config
+                .grpc
+                .compression
+                .iter()
+                .map(|compression| compression.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        std::env::set_var(
+            "GRPC_MAX_SIGS_PER_MESSAGE",
+            default_config.grpc.max_sigs_per_message.to_string(),
+        );
         if let Some(ref aggregator_verification_address) =
             default_config.aggregator_verification_address
         {
@@ -449,6 +565,17 @@ mod tests {
         );
 
         assert_eq!(super::BridgeConfig::from_env().unwrap(), default_config);
+
+        // A malformed start height must not silently index from genesis.
+        std::env::set_var("CITREA_INDEXER_START_HEIGHT", "latest");
+        assert!(matches!(
+            super::BridgeConfig::from_env(),
+            Err(clementine_errors::BridgeError::EnvVarMalformed(
+                "CITREA_INDEXER_START_HEIGHT",
+                _
+            ))
+        ));
+        std::env::remove_var("CITREA_INDEXER_START_HEIGHT");
     }
 
     #[test]
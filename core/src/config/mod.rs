@@ -14,7 +14,7 @@ use crate::cli;
 use crate::config::env::{read_string_from_env, read_string_from_env_then_parse};
 use crate::config::protocol::BLOCKS_PER_HOUR;
 use crate::deposit::SecurityCouncil;
-use crate::extended_bitcoin_rpc::ExtendedBitcoinRpc;
+use crate::extended_bitcoin_rpc::{BitcoindBackend, ExtendedBitcoinRpc};
 use crate::header_chain_prover::HeaderChainProver;
 use bitcoin::address::NetworkUnchecked;
 use bitcoin::secp256k1::SecretKey;
@@ -63,6 +63,15 @@ pub struct BridgeConfig {
     pub bitcoin_rpc_user: SecretString,
     /// Bitcoin RPC user password.
     pub bitcoin_rpc_password: SecretString,
+    /// Additional bitcoind backends. Non-wallet calls fail over to them when
+    /// the primary backend at `bitcoin_rpc_url` is unavailable, wallet calls
+    /// always go to the primary.
+    #[serde(default)]
+    pub bitcoin_rpc_fallbacks: Vec<BitcoindBackend>,
+    /// Number of blocks the tips of the bitcoind backends can diverge before
+    /// an alarm is raised.
+    #[serde(default = "default_bitcoin_rpc_max_tip_divergence")]
+    pub bitcoin_rpc_max_tip_divergence: u32,
     /// mempool.space API host for retrieving the fee rate. If None, Bitcoin Core RPC will be used.
     pub mempool_api_host: Option<String>,
     /// mempool.space API endpoint for retrieving the fee rate. If None, Bitcoin Core RPC will be used.
@@ -86,12 +95,37 @@ pub struct BridgeConfig {
     pub citrea_chain_id: u32,
     /// Timeout in seconds for Citrea RPC calls.
     pub citrea_request_timeout: Option<Duration>,
+    /// Additional Citrea RPC URLs, tried in order when the primary
+    /// `citrea_rpc_url` is unhealthy.
+    #[serde(default)]
+    pub citrea_rpc_fallback_urls: Vec<String>,
+    /// Additional Citrea light client prover RPC URLs, tried in order when the
+    /// primary `citrea_light_client_prover_url` is unhealthy.
+    #[serde(default)]
+    pub citrea_light_client_prover_fallback_urls: Vec<String>,
+    /// If set, critical reads (storage proofs, withdrawal UTXOs and the N-of-N
+    /// key in the contract) are only trusted if this many Citrea RPC endpoints
+    /// return the same result.
+    #[serde(default)]
+    pub citrea_rpc_quorum: Option<usize>,
+    /// If true, verifiers index bridge contract events in the database and the
+    /// Citrea client reads deposits, withdrawals and deposit replacements from
+    /// that index whenever it covers the request.
+    #[serde(default)]
+    pub citrea_indexer: bool,
+    /// L2 height the Citrea indexer starts from when the index is empty.
+    #[serde(default)]
+    pub citrea_indexer_start_height: u64,
     /// Bridge contract address.
     pub bridge_contract_address: String,
     // Initial header chain proof receipt's file path.
     pub header_chain_proof_path: Option<PathBuf>,
     /// Batch size of the header chain proofs
     pub header_chain_proof_batch_size: u32,
+    /// If true, the node doesn't prove the jobs in its proving queue itself
+    /// and `clementine prover-worker` processes must be run for it.
+    #[serde(default)]
+    pub external_prover_workers: bool,
 
     /// Security council.
     pub security_council: SecurityCouncil,
@@ -100,6 +134,11 @@ pub struct BridgeConfig {
     pub verifier_endpoints: Option<Vec<String>>,
     /// Operator endpoint. For the aggregator only
     pub operator_endpoints: Option<Vec<String>>,
+    /// Minimum number of verifiers a deposit can be signed with. If set, the
+    /// aggregator signs new deposits with the reachable verifiers instead of
+    /// failing when some of them are offline. For the aggregator only
+    #[serde(default)]
+    pub min_deposit_verifiers: Option<usize>,
 
     /// Own operator's reimbursement address.
     pub operator_reimbursement_address: Option<Address<NetworkUnchecked>>,
@@ -145,6 +184,10 @@ pub struct BridgeConfig {
     /// Telemetry configuration
     pub telemetry: Option<TelemetryConfig>,
 
+    /// HTTP health and readiness endpoint configuration. The endpoints are
+    /// not served if this is not set.
+    pub health: Option<HealthConfig>,
+
     /// The ECDSA address of the citrea/aggregator that will sign the withdrawal params
     /// after manual verification of the optimistic payout and operator's withdrawal.
     /// Used for both an extra verification of aggregator's identity and to force citrea
@@ -168,10 +211,19 @@ pub struct BridgeConfig {
     /// Hard cap on tx sender fee rate (sat/vB).
     #[serde(default = "default_tx_sender_limits")]
     pub tx_sender_limits: TxSenderLimits,
+
+    /// If true, the tx sender builds and checks transactions but never
+    /// broadcasts them, see [`clementine_tx_sender::dry_run`].
+    #[serde(default)]
+    pub tx_sender_dry_run: bool,
 }
 
 // Re-export types from clementine-config
-pub use clementine_config::{GrpcLimits, TxSenderLimits};
+pub use clementine_config::{GrpcCompression, GrpcLimits, TxSenderLimits};
+
+pub(crate) fn default_bitcoin_rpc_max_tip_divergence() -> u32 {
+    2
+}
 
 fn default_grpc_limits() -> GrpcLimits {
     GrpcLimits::default()
@@ -233,6 +285,23 @@ impl BridgeConfig {
         }
     }
 
+    /// Connects to the primary bitcoind backend and the configured fallbacks.
+    pub async fn connect_bitcoin_rpc(&self) -> Result<ExtendedBitcoinRpc, BridgeError> {
+        let primary = BitcoindBackend {
+            url: self.bitcoin_rpc_url.clone(),
+            user: self.bitcoin_rpc_user.clone(),
+            password: self.bitcoin_rpc_password.clone(),
+        };
+
+        Ok(ExtendedBitcoinRpc::connect_backends(
+            std::iter::once(primary)
+                .chain(self.bitcoin_rpc_fallbacks.iter().cloned())
+                .collect(),
+            None,
+        )
+        .await?)
+    }
+
     /// Check general requirements for the configuration irrespective of the network.
     pub async fn check_general_requirements(&self) -> Result<(), BridgeError> {
         // check genesis state hash
@@ -271,6 +340,25 @@ impl BridgeConfig {
             ));
         }
 
+        if let Some(quorum) = self.citrea_rpc_quorum {
+            let endpoint_count = 1 + self.citrea_rpc_fallback_urls.len();
+            if quorum == 0 || quorum > endpoint_count {
+                reasons.push(format!(
+                    "Citrea RPC quorum ({quorum}) must be between 1 and the number of Citrea RPC endpoints ({endpoint_count})"
+                ));
+            }
+        }
+
+        if let Some(min_verifiers) = self.min_deposit_verifiers {
+            let verifier_count = self.verifier_endpoints.as_ref().map(Vec::len);
+            if min_verifiers == 0 || verifier_count.is_some_and(|count| min_verifiers > count) {
+                reasons.push(format!(
+                    "Minimum deposit verifiers ({min_verifiers}) must be between 1 and the number of verifier endpoints ({})",
+                    verifier_count.map_or("unset".to_string(), |count| count.to_string())
+                ));
+            }
+        }
+
         if self.protocol_paramset().finality_depth < 1 {
             reasons.push(format!(
                 "Finality depth ({}) cannot be less than 1",
@@ -373,6 +461,8 @@ impl BridgeConfig {
             poll_delay_ms: 60_000,
             include_unsafe: false,
             jsonrpc: None,
+            metrics: None,
+            dry_run: self.tx_sender_dry_run,
         }
     }
 }
@@ -392,6 +482,8 @@ impl PartialEq for BridgeConfig {
             && self.bitcoin_rpc_user.expose_secret() == other.bitcoin_rpc_user.expose_secret()
             && self.bitcoin_rpc_password.expose_secret()
                 == other.bitcoin_rpc_password.expose_secret()
+            && self.bitcoin_rpc_fallbacks == other.bitcoin_rpc_fallbacks
+            && self.bitcoin_rpc_max_tip_divergence == other.bitcoin_rpc_max_tip_divergence
             && self.db_host == other.db_host
             && self.db_port == other.db_port
             && self.db_user.expose_secret() == other.db_user.expose_secret()
@@ -400,11 +492,19 @@ impl PartialEq for BridgeConfig {
             && self.citrea_rpc_url == other.citrea_rpc_url
             && self.citrea_light_client_prover_url == other.citrea_light_client_prover_url
             && self.citrea_chain_id == other.citrea_chain_id
+            && self.citrea_rpc_fallback_urls == other.citrea_rpc_fallback_urls
+            && self.citrea_light_client_prover_fallback_urls
+                == other.citrea_light_client_prover_fallback_urls
+            && self.citrea_rpc_quorum == other.citrea_rpc_quorum
+            && self.citrea_indexer == other.citrea_indexer
+            && self.citrea_indexer_start_height == other.citrea_indexer_start_height
+            && self.external_prover_workers == other.external_prover_workers
             && self.bridge_contract_address == other.bridge_contract_address
             && self.header_chain_proof_path == other.header_chain_proof_path
             && self.security_council == other.security_council
             && self.verifier_endpoints == other.verifier_endpoints
             && self.operator_endpoints == other.operator_endpoints
+            && self.min_deposit_verifiers == other.min_deposit_verifiers
             && self.operator_reimbursement_address == other.operator_reimbursement_address
             && self.operator_collateral_funding_outpoint
                 == other.operator_collateral_funding_outpoint
@@ -416,7 +516,8 @@ impl PartialEq for BridgeConfig {
             && self.client_verification == other.client_verification
             && self.aggregator_cert_path == other.aggregator_cert_path
             && self.test_params == other.test_params
-            && self.grpc == other.grpc;
+            && self.grpc == other.grpc
+            && self.tx_sender_dry_run == other.tx_sender_dry_run;
 
         all_eq
     }
@@ -439,6 +540,8 @@ impl Default for BridgeConfig {
             bitcoin_rpc_url: "http://127.0.0.1:18443/wallet/admin".to_string(),
             bitcoin_rpc_user: "admin".to_string().into(),
             bitcoin_rpc_password: "admin".to_string().into(),
+            bitcoin_rpc_fallbacks: vec![],
+            bitcoin_rpc_max_tip_divergence: default_bitcoin_rpc_max_tip_divergence(),
             mempool_api_host: None,
             mempool_api_endpoint: None,
 
@@ -453,9 +556,15 @@ impl Default for BridgeConfig {
             citrea_chain_id: 5655,
             bridge_contract_address: "3100000000000000000000000000000000000002".to_string(),
             citrea_request_timeout: None,
+            citrea_rpc_fallback_urls: vec![],
+            citrea_light_client_prover_fallback_urls: vec![],
+            citrea_rpc_quorum: None,
+            citrea_indexer: false,
+            citrea_indexer_start_height: 0,
 
             header_chain_proof_path: None,
             header_chain_proof_batch_size: 100,
+            external_prover_workers: false,
 
             operator_reimbursement_address: None,
             operator_collateral_funding_outpoint: None,
@@ -476,6 +585,7 @@ impl Default for BridgeConfig {
 
             verifier_endpoints: None,
             operator_endpoints: None,
+            min_deposit_verifiers: None,
 
             server_cert_path: PathBuf::from("certs/server/server.pem"),
             server_key_path: PathBuf::from("certs/server/server.key"),
@@ -496,6 +606,7 @@ impl Default for BridgeConfig {
             ),
 
             telemetry: Some(TelemetryConfig::default()),
+            health: None,
 
             time_to_send_watchtower_challenge: 4 * BLOCKS_PER_HOUR * 3 / 2,
 
@@ -505,6 +616,7 @@ impl Default for BridgeConfig {
             // New hardening parameters, optional so they don't break existing configs.
             grpc: default_grpc_limits(),
             tx_sender_limits: default_tx_sender_limits(),
+            tx_sender_dry_run: false,
         }
     }
 }
@@ -526,6 +638,35 @@ impl TelemetryConfigExt for TelemetryConfig {
     }
 }
 
+// Re-export HealthConfig from clementine-config
+pub use clementine_config::HealthConfig;
+
+/// Extension trait for HealthConfig to add from_env method
+pub trait HealthConfigExt {
+    /// Create a HealthConfig from environment variables.
+    fn from_env() -> Result<HealthConfig, BridgeError>;
+}
+
+impl HealthConfigExt for HealthConfig {
+    fn from_env() -> Result<HealthConfig, BridgeError> {
+        let defaults = HealthConfig::default();
+        let host = read_string_from_env("HEALTH_HOST")?;
+        let port = read_string_from_env_then_parse::<u16>("HEALTH_PORT")?;
+        let max_sync_lag_blocks =
+            read_string_from_env_then_parse::<u32>("HEALTH_MAX_SYNC_LAG_BLOCKS")
+                .unwrap_or(defaults.max_sync_lag_blocks);
+        let min_wallet_balance_sats =
+            read_string_from_env_then_parse::<u64>("HEALTH_MIN_WALLET_BALANCE_SATS")
+                .unwrap_or(defaults.min_wallet_balance_sats);
+        Ok(HealthConfig {
+            host,
+            port,
+            max_sync_lag_blocks,
+            min_wallet_balance_sats,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::BridgeConfig;
@@ -687,6 +828,7 @@ mod tests {
         // Use regtest node but set wrong/invalid paramset values to trigger multiple errors
         let _regtest = create_regtest_rpc(&mut config).await;
         config.protocol_paramset = &BAD_PARAMSET;
+        config.min_deposit_verifiers = Some(0);
 
         let res = config.check_general_requirements().await;
         assert!(res.is_err());
@@ -697,5 +839,9 @@ mod tests {
         );
         assert!(err.contains("Start height"), "unexpected error: {err}");
         assert!(err.contains("Finality depth"), "unexpected error: {err}");
+        assert!(
+            err.contains("Minimum deposit verifiers"),
+            "unexpected error: {err}"
+        );
     }
 }
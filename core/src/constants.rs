@@ -10,6 +10,11 @@ pub const TEN_MINUTES_IN_SECS: u32 = 600;
 
 pub const DEFAULT_CHANNEL_SIZE: usize = 1280;
 
+/// Upper bound on the signatures packed into one message of a signing stream.
+/// The deposit signing pipeline buffers [`DEFAULT_CHANNEL_SIZE`] items between
+/// its stages, so a batch must fill well before the buffers do.
+pub const MAX_SIGS_PER_MESSAGE: u32 = 512;
+
 /// Maximum extra watchtowers that can be added to the deposit (in addition to verifiers).
 /// It is limited because each extra watchtower requires 2 additional utxos in the kickoff tx.
 pub const MAX_EXTRA_WATCHTOWERS: usize = 5;
@@ -29,6 +34,10 @@ pub const MAX_ALL_SESSIONS_BYTES: usize = 2_000_000_000;
 /// It is used so that the allsessions do not store too many small (1 nonce) sessions.
 pub const MAX_NUM_SESSIONS: usize = 2000;
 
+/// The maximum number of deposits of a `NewDeposits` batch that the aggregator
+/// signs at the same time. Every deposit uses its own signing session.
+pub const MAX_CONCURRENT_BATCH_DEPOSITS: usize = 4;
+
 use secp256k1::ffi::MUSIG_SECNONCE_LEN;
 /// The maximum number of Winternitz digits per key.
 /// This is used to limit the size of the Winternitz public keys in the protocol
@@ -82,6 +91,8 @@ mod timeout {
 
     pub const PUBLIC_KEY_COLLECTION_TIMEOUT: Duration = Duration::from_secs(30);
 
+    pub const DEPOSIT_VERIFIER_PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
     pub const WITHDRAWAL_TIMEOUT: Duration = Duration::from_secs(120); // 2 minutes
 }
 
@@ -2,12 +2,34 @@
 //!
 //! This module includes database functions which are mainly used by a verifier.
 
-use super::{wrapper::TxidDB, Database, DatabaseTransaction};
+use super::{
+    wrapper::{OutPointDB, PublicKeyDB, TxidDB},
+    Database, DatabaseTransaction,
+};
+use crate::aggregator::ExcludedVerifier;
 use crate::execute_query_with_tx;
-use bitcoin::Txid;
+use crate::membership::{MemberKind, MembershipAction, MembershipChange};
+use bitcoin::{OutPoint, Txid};
 use clementine_errors::BridgeError;
-use eyre;
+use eyre::{self, Context};
 use sqlx::QueryBuilder;
+use std::ops::DerefMut;
+
+type MembershipChangeRow = (i64, String, String, String, Vec<u8>, i32);
+
+fn membership_change_from_row(
+    (epoch, kind, action, endpoint, public_key, effective_height): MembershipChangeRow,
+) -> Result<MembershipChange, BridgeError> {
+    Ok(MembershipChange {
+        epoch: u64::try_from(epoch).wrap_err("Invalid epoch in database")?,
+        kind: kind.parse()?,
+        action: action.parse()?,
+        endpoint,
+        public_key,
+        effective_height: u32::try_from(effective_height)
+            .wrap_err("Invalid effective height in database")?,
+    })
+}
 
 impl Database {
     /// Sets a signed emergency stop transaction for a given move transaction ID
@@ -59,6 +81,149 @@ impl Database {
             .map(|(txid, tx_data)| Ok((txid.0, tx_data)))
             .collect::<Result<_, eyre::Report>>()?)
     }
+
+    /// Records a verifier or operator set change and returns the epoch it starts.
+    pub async fn insert_membership_change(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        kind: MemberKind,
+        action: MembershipAction,
+        endpoint: &str,
+        public_key: &[u8],
+        effective_height: u32,
+    ) -> Result<u64, BridgeError> {
+        let query = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO aggregator_membership_changes
+                (kind, action, endpoint, public_key, effective_height)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING epoch;",
+        )
+        .bind(kind.as_str())
+        .bind(action.as_str())
+        .bind(endpoint)
+        .bind(public_key)
+        .bind(i32::try_from(effective_height).wrap_err("Effective height overflow")?);
+
+        let epoch = execute_query_with_tx!(self.connection, tx, query, fetch_one)?;
+
+        Ok(u64::try_from(epoch).wrap_err("Invalid epoch in database")?)
+    }
+
+    /// Returns all recorded membership changes, in epoch order.
+    pub async fn get_membership_changes(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+    ) -> Result<Vec<MembershipChange>, BridgeError> {
+        let query = sqlx::query_as::<_, MembershipChangeRow>(
+            "SELECT epoch, kind, action, endpoint, public_key, effective_height
+             FROM aggregator_membership_changes ORDER BY epoch ASC;",
+        );
+
+        execute_query_with_tx!(self.connection, tx, query, fetch_all)?
+            .into_iter()
+            .map(membership_change_from_row)
+            .collect()
+    }
+
+    /// Appends membership changes sent by the aggregator to the ones a
+    /// verifier knows.
+    pub async fn insert_verifier_membership_changes(
+        &self,
+        tx: DatabaseTransaction<'_>,
+        changes: &[MembershipChange],
+    ) -> Result<(), BridgeError> {
+        for change in changes {
+            sqlx::query(
+                "INSERT INTO verifier_membership_changes
+                    (epoch, kind, action, endpoint, public_key, effective_height)
+                 VALUES ($1, $2, $3, $4, $5, $6);",
+            )
+            .bind(i64::try_from(change.epoch).wrap_err("Epoch overflow")?)
+            .bind(change.kind.as_str())
+            .bind(change.action.as_str())
+            .bind(&change.endpoint)
+            .bind(&change.public_key)
+            .bind(i32::try_from(change.effective_height).wrap_err("Effective height overflow")?)
+            .execute(tx.deref_mut())
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the membership changes sent to this verifier by the
+    /// aggregator, in epoch order.
+    pub async fn get_verifier_membership_changes(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+    ) -> Result<Vec<MembershipChange>, BridgeError> {
+        let query = sqlx::query_as::<_, MembershipChangeRow>(
+            "SELECT epoch, kind, action, endpoint, public_key, effective_height
+             FROM verifier_membership_changes ORDER BY epoch ASC;",
+        );
+
+        execute_query_with_tx!(self.connection, tx, query, fetch_all)?
+            .into_iter()
+            .map(membership_change_from_row)
+            .collect()
+    }
+
+    /// Records the verifiers that were left out of the deposit at
+    /// `deposit_outpoint`.
+    pub async fn insert_deposit_excluded_verifiers(
+        &self,
+        mut tx: Option<DatabaseTransaction<'_>>,
+        deposit_outpoint: OutPoint,
+        excluded_verifiers: &[ExcludedVerifier],
+    ) -> Result<(), BridgeError> {
+        for verifier in excluded_verifiers {
+            let query = sqlx::query(
+                "INSERT INTO deposit_excluded_verifiers
+                    (deposit_outpoint, endpoint, public_key, reason)
+                 VALUES ($1, $2, $3, $4);",
+            )
+            .bind(OutPointDB(deposit_outpoint))
+            .bind(&verifier.endpoint)
+            .bind(verifier.public_key.map(PublicKeyDB))
+            .bind(&verifier.reason);
+
+            execute_query_with_tx!(self.connection, tx.as_deref_mut(), query, execute)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the verifiers that were left out of deposits, optionally only
+    /// of the deposit at `deposit_outpoint`.
+    pub async fn get_deposit_excluded_verifiers(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        deposit_outpoint: Option<OutPoint>,
+    ) -> Result<Vec<(OutPoint, ExcludedVerifier)>, BridgeError> {
+        let query = sqlx::query_as::<_, (OutPointDB, String, Option<PublicKeyDB>, String)>(
+            "SELECT deposit_outpoint, endpoint, public_key, reason
+             FROM deposit_excluded_verifiers
+             WHERE $1::TEXT IS NULL OR deposit_outpoint = $1
+             ORDER BY id ASC;",
+        )
+        .bind(deposit_outpoint.map(OutPointDB));
+
+        Ok(
+            execute_query_with_tx!(self.connection, tx, query, fetch_all)?
+                .into_iter()
+                .map(|(deposit_outpoint, endpoint, public_key, reason)| {
+                    (
+                        deposit_outpoint.0,
+                        ExcludedVerifier {
+                            endpoint,
+                            public_key: public_key.map(|pk| pk.0),
+                            reason,
+                        },
+                    )
+                })
+                .collect(),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -154,4 +319,141 @@ mod tests {
         assert_eq!(results[0].0, move_txid);
         assert_eq!(results[0].1, consensus::serialize(&updated_tx));
     }
+
+    #[tokio::test]
+    async fn test_membership_changes() {
+        let config = create_test_config_with_thread_name().await;
+        let database = Database::new(&config).await.unwrap();
+
+        assert!(database
+            .get_membership_changes(None)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let first = database
+            .insert_membership_change(
+                None,
+                MemberKind::Operator,
+                MembershipAction::Add,
+                "https://127.0.0.1:17010",
+                &[2u8; 32],
+                100,
+            )
+            .await
+            .unwrap();
+        let second = database
+            .insert_membership_change(
+                None,
+                MemberKind::Verifier,
+                MembershipAction::Remove,
+                "https://127.0.0.1:17001",
+                &[3u8; 33],
+                120,
+            )
+            .await
+            .unwrap();
+        assert!(second > first);
+
+        let changes = database.get_membership_changes(None).await.unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].epoch, first);
+        assert_eq!(changes[0].kind, MemberKind::Operator);
+        assert_eq!(changes[0].action, MembershipAction::Add);
+        assert_eq!(changes[0].public_key, vec![2u8; 32]);
+        assert_eq!(changes[1].epoch, second);
+        assert_eq!(changes[1].kind, MemberKind::Verifier);
+        assert_eq!(changes[1].action, MembershipAction::Remove);
+        assert_eq!(changes[1].endpoint, "https://127.0.0.1:17001");
+        assert_eq!(changes[1].effective_height, 120);
+
+        // A verifier appends the changes it receives from the aggregator.
+        let mut dbtx = database.begin_transaction().await.unwrap();
+        database
+            .insert_verifier_membership_changes(&mut dbtx, &changes[..1])
+            .await
+            .unwrap();
+        dbtx.commit().await.unwrap();
+        assert_eq!(
+            database
+                .get_verifier_membership_changes(None)
+                .await
+                .unwrap(),
+            changes[..1]
+        );
+
+        let mut dbtx = database.begin_transaction().await.unwrap();
+        database
+            .insert_verifier_membership_changes(&mut dbtx, &changes[1..])
+            .await
+            .unwrap();
+        dbtx.commit().await.unwrap();
+        assert_eq!(
+            database
+                .get_verifier_membership_changes(None)
+                .await
+                .unwrap(),
+            changes
+        );
+
+        // Stored changes can't be overwritten.
+        let mut dbtx = database.begin_transaction().await.unwrap();
+        assert!(database
+            .insert_verifier_membership_changes(&mut dbtx, &changes[..1])
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_deposit_excluded_verifiers() {
+        let config = create_test_config_with_thread_name().await;
+        let database = Database::new(&config).await.unwrap();
+
+        let secp = bitcoin::secp256k1::Secp256k1::new();
+        let public_key = bitcoin::secp256k1::SecretKey::from_slice(&[1u8; 32])
+            .unwrap()
+            .public_key(&secp);
+        let excluded = vec![
+            ExcludedVerifier {
+                endpoint: "https://127.0.0.1:17001".to_string(),
+                public_key: Some(public_key),
+                reason: "Compatibility params retrieval failed".to_string(),
+            },
+            ExcludedVerifier {
+                endpoint: "https://127.0.0.1:17002".to_string(),
+                public_key: None,
+                reason: "Verifier key was not able to be collected".to_string(),
+            },
+        ];
+        let deposit_outpoint = OutPoint::new(Txid::from_byte_array([4u8; 32]), 1);
+        let other_outpoint = OutPoint::new(Txid::from_byte_array([5u8; 32]), 0);
+
+        database
+            .insert_deposit_excluded_verifiers(None, deposit_outpoint, &excluded)
+            .await
+            .unwrap();
+        database
+            .insert_deposit_excluded_verifiers(None, other_outpoint, &excluded[..1])
+            .await
+            .unwrap();
+
+        let results = database
+            .get_deposit_excluded_verifiers(None, Some(deposit_outpoint))
+            .await
+            .unwrap();
+        assert_eq!(
+            results,
+            excluded
+                .iter()
+                .map(|verifier| (deposit_outpoint, verifier.clone()))
+                .collect::<Vec<_>>()
+        );
+
+        let all = database
+            .get_deposit_excluded_verifiers(None, None)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[2].0, other_outpoint);
+    }
 }
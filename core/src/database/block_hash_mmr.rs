@@ -0,0 +1,183 @@
+//! # Block Hash MMR Related Database Operations
+//!
+//! This module includes database functions for the Merkle Mountain Range of
+//! finalized block hashes, which is kept up to date by
+//! [`crate::task::block_hash_mmr::BlockHashMmrTask`] and used to serve block
+//! inclusion proofs.
+
+use super::{Database, DatabaseTransaction};
+use crate::execute_query_with_tx;
+use circuits_lib::common::hashes::hash_pair;
+use circuits_lib::header_chain::mmr_native::{inclusion_proof_positions, MMRInclusionProof};
+use clementine_errors::BridgeError;
+use eyre::{Context, OptionExt};
+
+impl Database {
+    /// Returns the number of block hashes in the block hash MMR.
+    pub async fn get_block_hash_mmr_size(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+    ) -> Result<u32, BridgeError> {
+        let query = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM block_hash_mmr_nodes WHERE level = 0",
+        );
+
+        let size: i64 = execute_query_with_tx!(self.connection, tx, query, fetch_one)?;
+
+        Ok(u32::try_from(size).wrap_err(BridgeError::IntConversionError)?)
+    }
+
+    /// Returns the block hash MMR node at the given level and index, if it
+    /// exists.
+    pub async fn get_block_hash_mmr_node(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        level: usize,
+        idx: u32,
+    ) -> Result<Option<[u8; 32]>, BridgeError> {
+        let query = sqlx::query_scalar::<_, Vec<u8>>(
+            "SELECT hash FROM block_hash_mmr_nodes WHERE level = $1 AND idx = $2",
+        )
+        .bind(i32::try_from(level).wrap_err(BridgeError::IntConversionError)?)
+        .bind(i32::try_from(idx).wrap_err(BridgeError::IntConversionError)?);
+
+        let hash: Option<Vec<u8>> =
+            execute_query_with_tx!(self.connection, tx, query, fetch_optional)?;
+
+        hash.map(|hash| {
+            <[u8; 32]>::try_from(hash.as_slice())
+                .wrap_err("Invalid block hash MMR node length")
+                .map_err(Into::into)
+        })
+        .transpose()
+    }
+
+    /// Appends a block hash to the block hash MMR, together with the parent
+    /// nodes it completes. Returns the leaf index of the block hash.
+    pub async fn append_block_hash_mmr_leaf(
+        &self,
+        tx: DatabaseTransaction<'_>,
+        block_hash: [u8; 32],
+    ) -> Result<u32, BridgeError> {
+        let leaf_idx = self.get_block_hash_mmr_size(Some(tx)).await?;
+
+        let mut node = block_hash;
+        let mut level = 0;
+        let mut idx = leaf_idx;
+        loop {
+            sqlx::query("INSERT INTO block_hash_mmr_nodes (level, idx, hash) VALUES ($1, $2, $3)")
+                .bind(i32::try_from(level).wrap_err(BridgeError::IntConversionError)?)
+                .bind(i32::try_from(idx).wrap_err(BridgeError::IntConversionError)?)
+                .bind(node.as_slice())
+                .execute(&mut **tx)
+                .await?;
+
+            // A node with an even index has no sibling yet.
+            if idx % 2 == 0 {
+                break;
+            }
+            let left = self
+                .get_block_hash_mmr_node(Some(tx), level, idx - 1)
+                .await?
+                .ok_or_eyre("Missing left sibling in block hash MMR")?;
+            node = hash_pair(left, node);
+            level += 1;
+            idx /= 2;
+        }
+
+        Ok(leaf_idx)
+    }
+
+    /// Generates the inclusion proof of the leaf at `leaf_idx` in the block
+    /// hash MMR as it was when it had `mmr_size` leaves. Returns the leaf and
+    /// the proof.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `leaf_idx` is not smaller than `mmr_size` or
+    /// `mmr_size` is larger than the current size of the MMR.
+    pub async fn get_block_hash_mmr_proof(
+        &self,
+        mut tx: Option<DatabaseTransaction<'_>>,
+        leaf_idx: u32,
+        mmr_size: u32,
+    ) -> Result<([u8; 32], MMRInclusionProof), BridgeError> {
+        let current_size = self.get_block_hash_mmr_size(tx.as_deref_mut()).await?;
+        if mmr_size > current_size {
+            return Err(eyre::eyre!(
+                "Requested block hash MMR size {mmr_size} is larger than the current size {current_size}"
+            )
+            .into());
+        }
+
+        let (positions, subroot_idx, internal_idx) = inclusion_proof_positions(mmr_size, leaf_idx)?;
+
+        let mut proof = Vec::with_capacity(positions.len());
+        for (level, idx) in positions {
+            let node = self
+                .get_block_hash_mmr_node(tx.as_deref_mut(), level, idx)
+                .await?
+                .ok_or_eyre("Missing node in block hash MMR")?;
+            proof.push(node);
+        }
+        let leaf = self
+            .get_block_hash_mmr_node(tx, 0, leaf_idx)
+            .await?
+            .ok_or_eyre("Missing leaf in block hash MMR")?;
+
+        Ok((
+            leaf,
+            MMRInclusionProof::new(subroot_idx, internal_idx, proof),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+    use crate::test::common::*;
+    use circuits_lib::header_chain::mmr_native::MMRNative;
+
+    #[tokio::test]
+    async fn block_hash_mmr_proofs_match_native_mmr() {
+        let config = create_test_config_with_thread_name().await;
+        let db = Database::new(&config).await.unwrap();
+
+        let mut native = MMRNative::new();
+        let mut dbtx = db.begin_transaction().await.unwrap();
+        for i in 0..13u8 {
+            let leaf = [i; 32];
+            native.append(leaf);
+            let idx = db
+                .append_block_hash_mmr_leaf(&mut dbtx, leaf)
+                .await
+                .unwrap();
+            assert_eq!(idx, i as u32);
+        }
+        dbtx.commit().await.unwrap();
+        assert_eq!(db.get_block_hash_mmr_size(None).await.unwrap(), 13);
+
+        for leaf_idx in 0..13 {
+            let (leaf, proof) = db
+                .get_block_hash_mmr_proof(None, leaf_idx, 13)
+                .await
+                .unwrap();
+            assert_eq!(
+                (leaf, proof.clone()),
+                native.generate_proof(leaf_idx).unwrap()
+            );
+            assert!(native.verify_proof(leaf, &proof));
+        }
+
+        // Proofs for an earlier size verify against the MMR of that size.
+        let mut prefix = MMRNative::new();
+        for i in 0..7u8 {
+            prefix.append([i; 32]);
+        }
+        let (leaf, proof) = db.get_block_hash_mmr_proof(None, 5, 7).await.unwrap();
+        assert!(prefix.verify_proof(leaf, &proof));
+
+        assert!(db.get_block_hash_mmr_proof(None, 13, 13).await.is_err());
+        assert!(db.get_block_hash_mmr_proof(None, 0, 14).await.is_err());
+    }
+}
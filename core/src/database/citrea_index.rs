@@ -0,0 +1,422 @@
+//! # Citrea Event Index Related Database Operations
+//!
+//! This module includes database functions of the Citrea indexer task and the
+//! queries that are served from its index.
+
+use super::{wrapper::TxidDB, Database, DatabaseTransaction};
+use crate::citrea::{CitreaBridgeEvent, CitreaBridgeEventKind};
+use crate::execute_query_with_tx;
+use alloy::primitives::B256;
+use bitcoin::{OutPoint, Txid};
+use clementine_errors::BridgeError;
+use eyre::Context;
+
+fn height_to_db(height: u64) -> Result<i64, BridgeError> {
+    Ok(i64::try_from(height).wrap_err("Failed to convert L2 height to i64")?)
+}
+
+fn height_from_db(height: i64) -> Result<u64, BridgeError> {
+    Ok(u64::try_from(height).wrap_err("Failed to convert L2 height to u64")?)
+}
+
+fn hash_from_db(hash: Vec<u8>) -> Result<B256, BridgeError> {
+    Ok(B256::try_from(hash.as_slice()).wrap_err("Invalid L2 block hash in database")?)
+}
+
+impl Database {
+    /// Returns the height and hash of the last indexed L2 block, or [`None`] if
+    /// nothing was indexed yet.
+    pub async fn get_citrea_index_tip(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+    ) -> Result<Option<(u64, B256)>, BridgeError> {
+        let query = sqlx::query_as::<_, (i64, Vec<u8>)>(
+            "SELECT height, block_hash FROM citrea_index_blocks ORDER BY height DESC LIMIT 1",
+        );
+
+        execute_query_with_tx!(self.connection, tx, query, fetch_optional)?
+            .map(|(height, hash)| Ok((height_from_db(height)?, hash_from_db(hash)?)))
+            .transpose()
+    }
+
+    /// Returns the first and last L2 heights covered by the index, or [`None`]
+    /// if nothing was indexed yet. Every block in between is indexed.
+    pub async fn get_citrea_index_range(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+    ) -> Result<Option<(u64, u64)>, BridgeError> {
+        let query = sqlx::query_as::<_, (i64, i64)>(
+            "SELECT s.height, b.height FROM citrea_index_start s
+             CROSS JOIN (SELECT MAX(height) AS height FROM citrea_index_blocks) b
+             WHERE b.height IS NOT NULL",
+        );
+
+        execute_query_with_tx!(self.connection, tx, query, fetch_optional)?
+            .map(|(start, tip)| Ok((height_from_db(start)?, height_from_db(tip)?)))
+            .transpose()
+    }
+
+    /// Returns all stored L2 block hashes, lowest first.
+    pub async fn list_citrea_index_blocks(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+    ) -> Result<Vec<(u64, B256)>, BridgeError> {
+        let query = sqlx::query_as::<_, (i64, Vec<u8>)>(
+            "SELECT height, block_hash FROM citrea_index_blocks ORDER BY height ASC",
+        );
+
+        execute_query_with_tx!(self.connection, tx, query, fetch_all)?
+            .into_iter()
+            .map(|(height, hash)| Ok((height_from_db(height)?, hash_from_db(hash)?)))
+            .collect()
+    }
+
+    /// Saves the events of an indexed range of L2 blocks and moves the index
+    /// tip to the last block of the range.
+    ///
+    /// # Parameters
+    ///
+    /// - `events`: Bridge events in the range.
+    /// - `from_height`: First block of the range. Stored as the start of the
+    ///   index if the index is empty.
+    /// - `block_hashes`: Hashes of blocks in the range, used for reorg
+    ///   handling. Must include the last block of the range.
+    /// - `kept_heights`: Hashes of blocks more than this many blocks below the
+    ///   new tip are pruned.
+    pub async fn save_citrea_index_range(
+        &self,
+        tx: DatabaseTransaction<'_>,
+        events: &[CitreaBridgeEvent],
+        from_height: u64,
+        block_hashes: &[(u64, B256)],
+        kept_heights: u64,
+    ) -> Result<(), BridgeError> {
+        sqlx::query(
+            "INSERT INTO citrea_index_start (id, height) VALUES (TRUE, $1)
+             ON CONFLICT (id) DO NOTHING",
+        )
+        .bind(height_to_db(from_height)?)
+        .execute(&mut **tx)
+        .await?;
+
+        for event in events {
+            let (kind, idx, txid, vout) = match event.kind {
+                CitreaBridgeEventKind::Deposit { idx, move_txid } => {
+                    ("deposit", idx, move_txid, None)
+                }
+                CitreaBridgeEventKind::Withdrawal { idx, utxo } => {
+                    ("withdrawal", idx, utxo.txid, Some(utxo.vout))
+                }
+                CitreaBridgeEventKind::DepositReplaced { idx, new_move_txid } => {
+                    ("deposit_replaced", idx, new_move_txid, None)
+                }
+            };
+
+            sqlx::query(
+                "INSERT INTO citrea_bridge_events
+                 (l2_height, log_index, block_hash, kind, idx, txid, vout)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            )
+            .bind(height_to_db(event.l2_height)?)
+            .bind(i64::try_from(event.log_index).wrap_err("Failed to convert log index to i64")?)
+            .bind(event.block_hash.as_slice())
+            .bind(kind)
+            .bind(i32::try_from(idx).wrap_err("Failed to convert event index to i32")?)
+            .bind(TxidDB(txid))
+            .bind(
+                vout.map(i32::try_from)
+                    .transpose()
+                    .wrap_err("Failed to convert vout to i32")?,
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        for (height, hash) in block_hashes {
+            sqlx::query(
+                "INSERT INTO citrea_index_blocks (height, block_hash) VALUES ($1, $2)
+                 ON CONFLICT (height) DO NOTHING",
+            )
+            .bind(height_to_db(*height)?)
+            .bind(hash.as_slice())
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        sqlx::query(
+            "DELETE FROM citrea_index_blocks
+             WHERE height < (SELECT MAX(height) FROM citrea_index_blocks) - $1",
+        )
+        .bind(height_to_db(kept_heights)?)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Removes all indexed blocks and events above `height`. Used to roll the
+    /// index back to the fork point after an L2 reorg. If `height` is [`None`],
+    /// the whole index is removed.
+    pub async fn rollback_citrea_index(
+        &self,
+        tx: DatabaseTransaction<'_>,
+        height: Option<u64>,
+    ) -> Result<(), BridgeError> {
+        let height = height.map(height_to_db).transpose()?.unwrap_or(-1);
+
+        sqlx::query("DELETE FROM citrea_bridge_events WHERE l2_height > $1")
+            .bind(height)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query("DELETE FROM citrea_index_blocks WHERE height > $1")
+            .bind(height)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query("DELETE FROM citrea_index_start WHERE height > $1")
+            .bind(height)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns the indexed deposits with an index of at least
+    /// `first_deposit_idx`, up to `to_height`. See
+    /// [`crate::task::citrea_indexer::CitreaEventIndex`] for when this equals
+    /// [`crate::citrea::CitreaClientT::collect_deposit_move_txids`].
+    pub async fn get_indexed_citrea_deposits(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        first_deposit_idx: u32,
+        to_height: u64,
+    ) -> Result<Vec<(u64, Txid)>, BridgeError> {
+        let query = sqlx::query_as::<_, (i32, TxidDB)>(
+            "SELECT idx, txid FROM citrea_bridge_events
+             WHERE kind = 'deposit' AND idx >= $1 AND l2_height <= $2
+             ORDER BY idx ASC",
+        )
+        .bind(i64::from(first_deposit_idx))
+        .bind(height_to_db(to_height)?);
+
+        execute_query_with_tx!(self.connection, tx, query, fetch_all)?
+            .into_iter()
+            .map(|(idx, txid)| {
+                Ok((
+                    u64::try_from(idx).wrap_err("Failed to convert deposit index to u64")?,
+                    txid.0,
+                ))
+            })
+            .collect()
+    }
+
+    /// Returns the indexed withdrawals with an index of at least
+    /// `first_withdrawal_idx`, up to `to_height`. See
+    /// [`crate::task::citrea_indexer::CitreaEventIndex`] for when this equals
+    /// [`crate::citrea::CitreaClientT::collect_withdrawal_utxos`].
+    pub async fn get_indexed_citrea_withdrawals(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        first_withdrawal_idx: u32,
+        to_height: u64,
+    ) -> Result<Vec<(u64, OutPoint)>, BridgeError> {
+        let query = sqlx::query_as::<_, (i32, TxidDB, Option<i32>)>(
+            "SELECT idx, txid, vout FROM citrea_bridge_events
+             WHERE kind = 'withdrawal' AND idx >= $1 AND l2_height <= $2
+             ORDER BY idx ASC",
+        )
+        .bind(i64::from(first_withdrawal_idx))
+        .bind(height_to_db(to_height)?);
+
+        execute_query_with_tx!(self.connection, tx, query, fetch_all)?
+            .into_iter()
+            .map(|(idx, txid, vout)| {
+                let vout = vout.ok_or_else(|| eyre::eyre!("Indexed withdrawal has no vout"))?;
+                Ok((
+                    u64::try_from(idx).wrap_err("Failed to convert withdrawal index to u64")?,
+                    OutPoint {
+                        txid: txid.0,
+                        vout: u32::try_from(vout).wrap_err("Failed to convert vout to u32")?,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    /// Indexed version of [`crate::citrea::CitreaClientT::get_replacement_deposit_move_txids`].
+    pub async fn get_indexed_citrea_deposit_replacements(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Vec<(u32, Txid)>, BridgeError> {
+        let query = sqlx::query_as::<_, (i32, TxidDB)>(
+            "SELECT idx, txid FROM citrea_bridge_events
+             WHERE kind = 'deposit_replaced' AND l2_height >= $1 AND l2_height <= $2
+             ORDER BY l2_height ASC, log_index ASC",
+        )
+        .bind(height_to_db(from_height)?)
+        .bind(height_to_db(to_height)?);
+
+        execute_query_with_tx!(self.connection, tx, query, fetch_all)?
+            .into_iter()
+            .map(|(idx, txid)| {
+                Ok((
+                    u32::try_from(idx).wrap_err("Failed to convert deposit index to u32")?,
+                    txid.0,
+                ))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        citrea::{CitreaBridgeEvent, CitreaBridgeEventKind},
+        database::Database,
+        test::common::create_test_config_with_thread_name,
+    };
+    use alloy::primitives::B256;
+    use bitcoin::{hashes::Hash, OutPoint, Txid};
+
+    fn event(l2_height: u64, log_index: u64, kind: CitreaBridgeEventKind) -> CitreaBridgeEvent {
+        CitreaBridgeEvent {
+            l2_height,
+            block_hash: B256::repeat_byte(l2_height as u8),
+            log_index,
+            kind,
+        }
+    }
+
+    #[tokio::test]
+    async fn save_query_and_rollback_citrea_index() {
+        let config = create_test_config_with_thread_name().await;
+        let db = Database::new(&config).await.unwrap();
+
+        assert_eq!(db.get_citrea_index_tip(None).await.unwrap(), None);
+
+        let deposit_0 = Txid::from_byte_array([1; 32]);
+        let deposit_1 = Txid::from_byte_array([2; 32]);
+        let replacement = Txid::from_byte_array([3; 32]);
+        let utxo = OutPoint {
+            txid: Txid::from_byte_array([4; 32]),
+            vout: 7,
+        };
+
+        let mut dbtx = db.begin_transaction().await.unwrap();
+        db.save_citrea_index_range(
+            &mut dbtx,
+            &[
+                event(
+                    5,
+                    0,
+                    CitreaBridgeEventKind::Deposit {
+                        idx: 0,
+                        move_txid: deposit_0,
+                    },
+                ),
+                event(8, 0, CitreaBridgeEventKind::Withdrawal { idx: 0, utxo }),
+            ],
+            3,
+            &[(5, B256::repeat_byte(5)), (10, B256::repeat_byte(10))],
+            100,
+        )
+        .await
+        .unwrap();
+        db.save_citrea_index_range(
+            &mut dbtx,
+            &[
+                event(
+                    12,
+                    0,
+                    CitreaBridgeEventKind::Deposit {
+                        idx: 1,
+                        move_txid: deposit_1,
+                    },
+                ),
+                event(
+                    12,
+                    1,
+                    CitreaBridgeEventKind::DepositReplaced {
+                        idx: 0,
+                        new_move_txid: replacement,
+                    },
+                ),
+            ],
+            11,
+            &[(12, B256::repeat_byte(12)), (20, B256::repeat_byte(20))],
+            10,
+        )
+        .await
+        .unwrap();
+        dbtx.commit().await.unwrap();
+
+        // Hashes more than 10 blocks below the tip are pruned.
+        assert_eq!(
+            db.list_citrea_index_blocks(None).await.unwrap(),
+            vec![
+                (10, B256::repeat_byte(10)),
+                (12, B256::repeat_byte(12)),
+                (20, B256::repeat_byte(20))
+            ]
+        );
+
+        assert_eq!(
+            db.get_citrea_index_tip(None).await.unwrap(),
+            Some((20, B256::repeat_byte(20)))
+        );
+        // The index starts where the first range started.
+        assert_eq!(
+            db.get_citrea_index_range(None).await.unwrap(),
+            Some((3, 20))
+        );
+        assert_eq!(
+            db.get_indexed_citrea_deposits(None, 0, 20).await.unwrap(),
+            vec![(0, deposit_0), (1, deposit_1)]
+        );
+        assert_eq!(
+            db.get_indexed_citrea_deposits(None, 1, 10).await.unwrap(),
+            vec![]
+        );
+        assert_eq!(
+            db.get_indexed_citrea_withdrawals(None, 0, 20)
+                .await
+                .unwrap(),
+            vec![(0, utxo)]
+        );
+        assert_eq!(
+            db.get_indexed_citrea_deposit_replacements(None, 11, 20)
+                .await
+                .unwrap(),
+            vec![(0, replacement)]
+        );
+
+        let mut dbtx = db.begin_transaction().await.unwrap();
+        db.rollback_citrea_index(&mut dbtx, Some(10)).await.unwrap();
+        dbtx.commit().await.unwrap();
+
+        assert_eq!(
+            db.list_citrea_index_blocks(None).await.unwrap(),
+            vec![(10, B256::repeat_byte(10))]
+        );
+        assert_eq!(
+            db.get_indexed_citrea_deposits(None, 0, 20).await.unwrap(),
+            vec![(0, deposit_0)]
+        );
+        assert_eq!(
+            db.get_indexed_citrea_deposit_replacements(None, 11, 20)
+                .await
+                .unwrap(),
+            vec![]
+        );
+        assert_eq!(
+            db.get_citrea_index_range(None).await.unwrap(),
+            Some((3, 10))
+        );
+
+        let mut dbtx = db.begin_transaction().await.unwrap();
+        db.rollback_citrea_index(&mut dbtx, None).await.unwrap();
+        dbtx.commit().await.unwrap();
+        assert_eq!(db.get_citrea_index_range(None).await.unwrap(), None);
+    }
+}
@@ -4,9 +4,11 @@
 //! chain prover.
 
 use super::{
+    proof_artifacts::proof_id_from_db,
     wrapper::{BlockHashDB, BlockHeaderDB},
     Database, DatabaseTransaction,
 };
+use crate::proof_store::ProofId;
 use crate::{execute_query_with_tx, extended_bitcoin_rpc::ExtendedBitcoinRpc};
 use bitcoin::{
     block::{self, Header},
@@ -14,7 +16,6 @@ use bitcoin::{
 };
 use clementine_errors::BridgeError;
 use eyre::Context;
-use risc0_zkvm::Receipt;
 
 impl Database {
     /// Adds a new finalized block to the database, later to be updated with a
@@ -183,7 +184,7 @@ impl Database {
         Ok(result.map(|height| height.0 as u64))
     }
 
-    /// Gets the first finalized block after the latest proven block (i.e. proof != null).
+    /// Gets the first finalized block after the latest proven block (i.e. proof_id != null).
     /// This block will be the candidate block for the prover.
     ///
     /// # Returns
@@ -194,11 +195,11 @@ impl Database {
     /// - [`BlockHash`] - Hash of the block
     /// - [`Header`] - Header of the block
     /// - [`u64`] - Height of the block
-    /// - [`Receipt`] - Previous block's proof
+    /// - [`ProofId`] - Previous block's proof in the proof store
     pub async fn get_next_unproven_block(
         &self,
         mut tx: Option<DatabaseTransaction<'_>>,
-    ) -> Result<Option<(BlockHash, Header, u64, Receipt)>, BridgeError> {
+    ) -> Result<Option<(BlockHash, Header, u64, ProofId)>, BridgeError> {
         let latest_proven_block_height = self
             .get_latest_proven_block_info(tx.as_deref_mut())
             .await?
@@ -208,10 +209,10 @@ impl Database {
             "SELECT h1.block_hash,
                     h1.block_header,
                     h1.height,
-                    h2.proof
+                    h2.proof_id
                 FROM header_chain_proofs h1
                 JOIN header_chain_proofs h2 ON h1.prev_block_hash = h2.block_hash
-                WHERE h2.proof IS NOT NULL AND h1.proof IS NULL
+                WHERE h2.proof_id IS NOT NULL AND h1.proof_id IS NULL
                 ORDER BY h1.height DESC
                 LIMIT 1",
         );
@@ -221,10 +222,13 @@ impl Database {
 
         let result = match result {
             Some(result) => {
-                let receipt: Receipt =
-                    borsh::from_slice(&result.3).wrap_err(BridgeError::BorshError)?;
                 let height: u64 = result.2.try_into().wrap_err("Can't convert i64 to u64")?;
-                Some((result.0 .0, result.1 .0, height, receipt))
+                Some((
+                    result.0 .0,
+                    result.1 .0,
+                    height,
+                    proof_id_from_db(result.3)?,
+                ))
             }
             None => None,
         };
@@ -253,11 +257,11 @@ impl Database {
     /// - [`BlockHash`] - Hash of last block in the batch
     /// - [`Header`] - Headers of the blocks
     /// - [`u64`] - Height of the last block in the batch
-    /// - [`Receipt`] - Previous block's proof
+    /// - [`ProofId`] - Previous block's proof in the proof store
     pub async fn get_next_n_non_proven_block(
         &self,
         count: u32,
-    ) -> Result<Option<(Vec<(BlockHash, Header, u64)>, Receipt)>, BridgeError> {
+    ) -> Result<Option<(Vec<(BlockHash, Header, u64)>, ProofId)>, BridgeError> {
         let Some(next_non_proven_block) = self.get_next_unproven_block(None).await? else {
             return Ok(None);
         };
@@ -318,7 +322,7 @@ impl Database {
         let query = sqlx::query_as(
             "SELECT block_hash, block_header, height
             FROM header_chain_proofs
-            WHERE proof IS NOT NULL
+            WHERE proof_id IS NOT NULL
             ORDER BY height DESC
             LIMIT 1;",
         );
@@ -354,7 +358,7 @@ impl Database {
         let query = sqlx::query_as(
             "SELECT block_hash, block_header, height
             FROM header_chain_proofs
-            WHERE proof IS NOT NULL AND height <= $1
+            WHERE proof_id IS NOT NULL AND height <= $1
             ORDER BY height DESC
             LIMIT 1;",
         )
@@ -375,49 +379,45 @@ impl Database {
     }
 
     /// Sets an existing block's (in database) proof by referring to it by it's
-    /// hash.
+    /// hash. The proof must already be in the proof store.
     pub async fn set_block_proof(
         &self,
         tx: Option<DatabaseTransaction<'_>>,
         hash: block::BlockHash,
-        proof: Receipt,
+        proof_id: ProofId,
     ) -> Result<(), BridgeError> {
-        let proof = borsh::to_vec(&proof).wrap_err(BridgeError::BorshError)?;
-
-        let query = sqlx::query("UPDATE header_chain_proofs SET proof = $1 WHERE block_hash = $2")
-            .bind(proof)
-            .bind(BlockHashDB(hash));
+        let query =
+            sqlx::query("UPDATE header_chain_proofs SET proof_id = $1 WHERE block_hash = $2")
+                .bind(proof_id.0.as_slice())
+                .bind(BlockHashDB(hash));
 
         execute_query_with_tx!(self.connection, tx, query, execute)?;
 
         Ok(())
     }
 
-    /// Gets a block's proof by referring to it by it's hash.
-    pub async fn get_block_proof_by_hash(
+    /// Gets the ID of a block's proof in the proof store by referring to it
+    /// by it's hash.
+    pub async fn get_block_proof_id_by_hash(
         &self,
         tx: Option<DatabaseTransaction<'_>>,
         hash: block::BlockHash,
-    ) -> Result<Option<Receipt>, BridgeError> {
-        let query = sqlx::query_as("SELECT proof FROM header_chain_proofs WHERE block_hash = $1")
-            .bind(BlockHashDB(hash));
+    ) -> Result<Option<ProofId>, BridgeError> {
+        let query =
+            sqlx::query_as("SELECT proof_id FROM header_chain_proofs WHERE block_hash = $1")
+                .bind(BlockHashDB(hash));
 
-        let receipt: (Option<Vec<u8>>,) =
+        let proof_id: (Option<Vec<u8>>,) =
             execute_query_with_tx!(self.connection, tx, query, fetch_one)?;
-        let receipt = match receipt.0 {
-            Some(r) => r,
-            None => return Ok(None),
-        };
-
-        let receipt: Receipt = borsh::from_slice(&receipt).wrap_err(BridgeError::BorshError)?;
 
-        Ok(Some(receipt))
+        proof_id.0.map(proof_id_from_db).transpose()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::database::Database;
+    use crate::proof_store::{PostgresProofStore, ProofId, ProofKind, ProofMetadata, ProofStore};
     use crate::test::common::*;
     use bitcoin::block::{self, Header, Version};
     use bitcoin::hashes::Hash;
@@ -425,6 +425,18 @@ mod tests {
     use borsh::BorshDeserialize;
     use risc0_zkvm::Receipt;
 
+    /// Saves the test header chain proof to the proof store and returns its ID.
+    async fn store_test_proof(db: &Database) -> ProofId {
+        let receipt = Receipt::try_from_slice(include_bytes!("../test/data/first_1.bin")).unwrap();
+        PostgresProofStore::new(db.clone())
+            .put(
+                &receipt,
+                ProofMetadata::new(ProofKind::HeaderChain, bitcoin::Network::Regtest, None),
+            )
+            .await
+            .unwrap()
+    }
+
     #[tokio::test]
     async fn save_get_new_block() {
         let config = create_test_config_with_thread_name().await;
@@ -460,8 +472,10 @@ mod tests {
                 .unwrap(),
             height
         );
-        let receipt = Receipt::try_from_slice(include_bytes!("../test/data/first_1.bin")).unwrap();
-        db.set_block_proof(None, block_hash, receipt).await.unwrap();
+        let proof_id = store_test_proof(&db).await;
+        db.set_block_proof(None, block_hash, proof_id)
+            .await
+            .unwrap();
         let latest_proven_block = db
             .get_latest_proven_block_info(None)
             .await
@@ -519,22 +533,23 @@ mod tests {
 
         // Requesting proof for an existing block without a proof should
         // return `None`.
-        let read_receipt = db.get_block_proof_by_hash(None, block_hash).await.unwrap();
-        assert!(read_receipt.is_none());
+        let read_proof_id = db
+            .get_block_proof_id_by_hash(None, block_hash)
+            .await
+            .unwrap();
+        assert!(read_proof_id.is_none());
 
         // Update it with a proof.
-        let receipt = Receipt::try_from_slice(include_bytes!("../test/data/first_1.bin")).unwrap();
-        db.set_block_proof(None, block_hash, receipt.clone())
+        let proof_id = store_test_proof(&db).await;
+        db.set_block_proof(None, block_hash, proof_id)
             .await
             .unwrap();
 
-        let read_receipt = db
-            .get_block_proof_by_hash(None, block_hash)
+        let read_proof_id = db
+            .get_block_proof_id_by_hash(None, block_hash)
             .await
-            .unwrap()
             .unwrap();
-        assert_eq!(receipt.journal, read_receipt.journal);
-        assert_eq!(receipt.metadata, read_receipt.metadata);
+        assert_eq!(read_proof_id, Some(proof_id));
     }
 
     #[tokio::test]
@@ -592,8 +607,8 @@ mod tests {
         db.save_unproven_finalized_block(None, block_hash1, block.header, height1)
             .await
             .unwrap();
-        let receipt = Receipt::try_from_slice(include_bytes!("../test/data/first_1.bin")).unwrap();
-        db.set_block_proof(None, block_hash1, receipt.clone())
+        let proof_id = store_test_proof(&db).await;
+        db.set_block_proof(None, block_hash1, proof_id)
             .await
             .unwrap();
         assert!(db.get_next_unproven_block(None).await.unwrap().is_none());
@@ -646,7 +661,7 @@ mod tests {
         db.save_unproven_finalized_block(None, block_hash3, block.header, height3)
             .await
             .unwrap();
-        db.set_block_proof(None, block_hash3, receipt.clone())
+        db.set_block_proof(None, block_hash3, proof_id)
             .await
             .unwrap();
 
@@ -743,8 +758,8 @@ mod tests {
         db.save_unproven_finalized_block(None, block_hash1, block.header, height)
             .await
             .unwrap();
-        let receipt = Receipt::try_from_slice(include_bytes!("../test/data/first_1.bin")).unwrap();
-        db.set_block_proof(None, block_hash1, receipt.clone())
+        let proof_id = store_test_proof(&db).await;
+        db.set_block_proof(None, block_hash1, proof_id)
             .await
             .unwrap();
         assert!(db
@@ -850,7 +865,7 @@ mod tests {
     async fn get_latest_proven_block_info() {
         let config = create_test_config_with_thread_name().await;
         let db = Database::new(&config).await.unwrap();
-        let proof = Receipt::try_from_slice(include_bytes!("../test/data/first_1.bin")).unwrap();
+        let proof_id = store_test_proof(&db).await;
 
         assert!(db
             .get_latest_proven_block_info(None)
@@ -898,7 +913,7 @@ mod tests {
             db.save_unproven_finalized_block(None, block_hash, block.header, height)
                 .await
                 .unwrap();
-            db.set_block_proof(None, block_hash, proof.clone())
+            db.set_block_proof(None, block_hash, proof_id)
                 .await
                 .unwrap();
 
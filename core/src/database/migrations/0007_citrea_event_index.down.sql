@@ -0,0 +1,4 @@
+-- Drop Citrea event index tables
+DROP TABLE IF EXISTS citrea_bridge_events;
+DROP TABLE IF EXISTS citrea_index_start;
+DROP TABLE IF EXISTS citrea_index_blocks;
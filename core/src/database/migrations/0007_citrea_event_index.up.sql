@@ -0,0 +1,32 @@
+-- Local index of Citrea bridge contract events, filled by the Citrea indexer task.
+--
+-- citrea_index_blocks holds hashes of indexed blocks: every block close to the
+-- chain tip, every block with a bridge event and the last block of each batch.
+-- The highest row is the indexer's cursor, older rows are used to find the fork
+-- point after an L2 reorg. Rows far below the cursor are pruned.
+CREATE TABLE IF NOT EXISTS citrea_index_blocks (
+    height BIGINT PRIMARY KEY,
+    block_hash BYTEA NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+-- First L2 height covered by the index. Together with the highest row of
+-- citrea_index_blocks it bounds the range the index can serve; reads outside
+-- of it go to the Citrea RPC instead.
+CREATE TABLE IF NOT EXISTS citrea_index_start (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    height BIGINT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS citrea_bridge_events (
+    l2_height BIGINT NOT NULL,
+    log_index BIGINT NOT NULL,
+    block_hash BYTEA NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('deposit', 'withdrawal', 'deposit_replaced')),
+    -- deposit or withdrawal index in the bridge contract
+    idx INT NOT NULL,
+    -- move txid for deposits and replacements, withdrawal utxo txid for withdrawals
+    txid BYTEA NOT NULL,
+    -- withdrawal utxo vout, NULL for other events
+    vout INT,
+    PRIMARY KEY (l2_height, log_index)
+);
+CREATE INDEX IF NOT EXISTS citrea_bridge_events_kind_idx ON citrea_bridge_events(kind, idx);
@@ -0,0 +1,3 @@
+-- Drop aggregator and verifier membership changes
+DROP TABLE IF EXISTS verifier_membership_changes;
+DROP TABLE IF EXISTS aggregator_membership_changes;
@@ -0,0 +1,27 @@
+-- Verifier and operator set changes made through the aggregator admin RPCs.
+-- Every change starts a new membership epoch, identified by the row id. The
+-- verifiers and operators in the aggregator config are the members of epoch 0.
+CREATE TABLE IF NOT EXISTS aggregator_membership_changes (
+    epoch BIGSERIAL PRIMARY KEY,
+    kind TEXT NOT NULL CHECK (kind IN ('verifier', 'operator')),
+    action TEXT NOT NULL CHECK (action IN ('add', 'remove')),
+    endpoint TEXT NOT NULL,
+    -- compressed public key of verifiers, x-only public key of operators
+    public_key BYTEA NOT NULL,
+    -- first Bitcoin block height the change applies to
+    effective_height INT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+-- Membership changes of the aggregator as received by this verifier. Used to
+-- check the verifiers and operators of a deposit against the epoch at the
+-- deposit height. Changes are only appended, a verifier rejects updates that
+-- modify the stored ones. Columns are the same as in
+-- aggregator_membership_changes.
+CREATE TABLE IF NOT EXISTS verifier_membership_changes (
+    epoch BIGINT PRIMARY KEY,
+    kind TEXT NOT NULL CHECK (kind IN ('verifier', 'operator')),
+    action TEXT NOT NULL CHECK (action IN ('add', 'remove')),
+    endpoint TEXT NOT NULL,
+    public_key BYTEA NOT NULL,
+    effective_height INT NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Drop excluded deposit verifiers
+DROP TABLE IF EXISTS deposit_excluded_verifiers;
@@ -0,0 +1,15 @@
+-- Verifiers that were left out of a deposit because they were unreachable or
+-- incompatible when the aggregator signed it with a subset of the verifiers
+-- (see min_deposit_verifiers). Kept for later reconciliation.
+CREATE TABLE IF NOT EXISTS deposit_excluded_verifiers (
+    id SERIAL PRIMARY KEY,
+    deposit_outpoint TEXT NOT NULL,
+    endpoint TEXT NOT NULL,
+    -- NULL if the key of the verifier could not be collected
+    public_key TEXT,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS deposit_excluded_verifiers_deposit_outpoint_idx
+    ON deposit_excluded_verifiers (deposit_outpoint);
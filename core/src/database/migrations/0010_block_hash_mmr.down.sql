@@ -0,0 +1,2 @@
+-- Drop block hash MMR
+DROP TABLE IF EXISTS block_hash_mmr_nodes;
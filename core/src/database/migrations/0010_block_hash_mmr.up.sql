@@ -0,0 +1,10 @@
+-- Merkle Mountain Range of finalized block hashes, starting from the genesis
+-- height of the protocol paramset. Level 0 holds the block hashes, leaf i being
+-- the block at genesis height + i, and every other node is the hash of its two
+-- children, so level l holds (number of leaves >> l) nodes.
+CREATE TABLE IF NOT EXISTS block_hash_mmr_nodes (
+    level INT NOT NULL,
+    idx INT NOT NULL,
+    hash BYTEA NOT NULL,
+    PRIMARY KEY (level, idx)
+);
@@ -0,0 +1,15 @@
+-- Drop proof store references, copying the referenced receipts back to the
+-- blob columns.
+ALTER TABLE header_chain_proofs ADD COLUMN IF NOT EXISTS proof BYTEA;
+UPDATE header_chain_proofs h SET proof = p.receipt
+    FROM proof_artifacts p WHERE p.claim_digest = h.proof_id;
+ALTER TABLE header_chain_proofs DROP COLUMN IF EXISTS proof_id;
+
+ALTER TABLE lcp_for_asserts ADD COLUMN IF NOT EXISTS lcp_receipt BYTEA;
+UPDATE lcp_for_asserts l SET lcp_receipt = p.receipt
+    FROM proof_artifacts p WHERE p.claim_digest = l.proof_id;
+ALTER TABLE lcp_for_asserts DROP COLUMN IF EXISTS proof_id;
+
+-- Drop proof artifact store
+DROP INDEX IF EXISTS proof_artifacts_kind_created_at_idx;
+DROP TABLE IF EXISTS proof_artifacts;
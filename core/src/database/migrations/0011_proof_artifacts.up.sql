@@ -0,0 +1,25 @@
+-- Content-addressed store of risc0 receipts, keyed by the digest of the
+-- receipt claim. See crate::proof_store.
+CREATE TABLE IF NOT EXISTS proof_artifacts (
+    claim_digest BYTEA PRIMARY KEY,
+    method_id BYTEA NOT NULL,
+    kind TEXT NOT NULL,
+    network TEXT NOT NULL,
+    start_height INT,
+    end_height INT,
+    created_at BIGINT NOT NULL,
+    receipt BYTEA NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS proof_artifacts_kind_created_at_idx
+    ON proof_artifacts (kind, created_at);
+
+-- Header chain proofs and light client proofs for asserts are kept in the proof
+-- store and referenced by their claim digest. Receipts saved in the old blob
+-- columns are moved to the store and the blob columns are dropped on startup,
+-- see Database::move_proof_blobs_to_store.
+ALTER TABLE header_chain_proofs
+    ADD COLUMN IF NOT EXISTS proof_id BYTEA REFERENCES proof_artifacts (claim_digest);
+
+ALTER TABLE lcp_for_asserts
+    ADD COLUMN IF NOT EXISTS proof_id BYTEA REFERENCES proof_artifacts (claim_digest);
@@ -0,0 +1,2 @@
+-- Drop proving jobs
+DROP TABLE IF EXISTS proving_jobs;
@@ -0,0 +1,20 @@
+-- Proving jobs run by prover workers. The IDs of jobs waiting to be proven
+-- are sent to the proving_jobs pgmq queue, their inputs and results are kept
+-- here. Jobs are identified by the digest of their input, so submitting the
+-- same input again resumes the existing job. The input is dropped once the job
+-- is done or failed, and finished jobs are deleted after a retention period,
+-- see crate::proving_queue::FINISHED_PROVING_JOB_RETENTION.
+CREATE TABLE IF NOT EXISTS proving_jobs (
+    id BIGSERIAL PRIMARY KEY,
+    kind TEXT NOT NULL,
+    input_digest BYTEA NOT NULL UNIQUE,
+    input BYTEA,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'running', 'done', 'failed')),
+    attempts INT NOT NULL DEFAULT 0,
+    worker TEXT,
+    result BYTEA,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
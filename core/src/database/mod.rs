@@ -19,8 +19,12 @@ use sqlx::{Pool, Postgres};
 
 mod aggregator;
 mod bitcoin_syncer;
+mod block_hash_mmr;
+mod citrea_index;
 mod header_chain_prover;
 mod operator;
+mod proof_artifacts;
+mod proving_jobs;
 #[cfg(feature = "automation")]
 mod state_machine;
 #[cfg(all(test, feature = "automation"))]
@@ -160,6 +164,10 @@ impl Database {
             .run(&database.connection)
             .await
             .wrap_err("Failed to run migrations")?;
+        database
+            .move_proof_blobs_to_store(config.protocol_paramset().network)
+            .await
+            .wrap_err("Failed to move proofs to the proof store")?;
 
         database.close().await;
         Ok(())
@@ -3,15 +3,15 @@
 //! This module includes database functions which are mainly used by an operator.
 
 use super::{
-    wrapper::{
-        AddressDB, DepositParamsDB, OutPointDB, ReceiptDB, SignaturesDB, TxidDB, XOnlyPublicKeyDB,
-    },
+    proof_artifacts::proof_id_from_db,
+    wrapper::{AddressDB, DepositParamsDB, OutPointDB, SignaturesDB, TxidDB, XOnlyPublicKeyDB},
     Database, DatabaseTransaction,
 };
 use crate::{
     builder::transaction::create_move_to_vault_txhandler,
     config::protocol::ProtocolParamset,
     deposit::{DepositData, KickoffData, OperatorData},
+    proof_store::ProofId,
 };
 use crate::{
     execute_query_with_tx,
@@ -23,7 +23,6 @@ use bitvm::signatures::winternitz::PublicKey as WinternitzPublicKey;
 use clementine_errors::BridgeError;
 use clementine_primitives::{PublicHash, RoundIndex};
 use eyre::{eyre, Context};
-use risc0_zkvm::Receipt;
 use std::str::FromStr;
 
 pub type RootHash = [u8; 32];
@@ -651,37 +650,39 @@ impl Database {
         }
     }
 
-    /// Retrieves the light client proof for a deposit to be used while sending an assert.
+    /// Retrieves the proof store id of the light client proof for a deposit to
+    /// be used while sending an assert.
     pub async fn get_lcp_for_assert(
         &self,
         tx: Option<DatabaseTransaction<'_>>,
         deposit_id: u32,
-    ) -> Result<Option<Receipt>, BridgeError> {
-        let query = sqlx::query_as::<_, (ReceiptDB,)>(
-            "SELECT lcp_receipt FROM lcp_for_asserts WHERE deposit_id = $1;",
+    ) -> Result<Option<ProofId>, BridgeError> {
+        let query = sqlx::query_as::<_, (Vec<u8>,)>(
+            "SELECT proof_id FROM lcp_for_asserts WHERE deposit_id = $1;",
         )
         .bind(i32::try_from(deposit_id).wrap_err("Failed to convert deposit id to i32")?);
 
         let result = execute_query_with_tx!(self.connection, tx, query, fetch_optional)?;
 
-        Ok(result.map(|(lcp,)| lcp.0))
+        result.map(|(id,)| proof_id_from_db(id)).transpose()
     }
 
-    /// Saves the light client proof for a deposit to be used while sending an assert.
-    /// We save first before sending kickoff to be sure we have the LCP available if we need to assert.
+    /// Saves the proof store id of the light client proof for a deposit to be
+    /// used while sending an assert. We save first before sending kickoff to be
+    /// sure we have the LCP available if we need to assert.
     pub async fn insert_lcp_for_assert(
         &self,
         tx: Option<DatabaseTransaction<'_>>,
         deposit_id: u32,
-        lcp: Receipt,
+        proof_id: ProofId,
     ) -> Result<(), BridgeError> {
         let query = sqlx::query(
-            "INSERT INTO lcp_for_asserts (deposit_id, lcp_receipt)
+            "INSERT INTO lcp_for_asserts (deposit_id, proof_id)
              VALUES ($1, $2)
              ON CONFLICT (deposit_id) DO NOTHING;",
         )
         .bind(i32::try_from(deposit_id).wrap_err("Failed to convert deposit id to i32")?)
-        .bind(ReceiptDB(lcp));
+        .bind(proof_id.0.to_vec());
 
         execute_query_with_tx!(self.connection, tx, query, execute)?;
 
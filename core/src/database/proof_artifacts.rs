@@ -0,0 +1,424 @@
+//! # Proof Artifact Related Database Operations
+//!
+//! This module includes database functions of the content-addressed receipt
+//! store, used through [`crate::proof_store::PostgresProofStore`].
+
+use super::{
+    wrapper::{BlockHashDB, ReceiptDB},
+    Database, DatabaseTransaction,
+};
+use crate::execute_query_with_tx;
+use crate::proof_store::{method_id, ProofEntry, ProofId, ProofKind, ProofMetadata};
+use bitcoin::Network;
+use clementine_errors::BridgeError;
+use eyre::Context;
+use risc0_zkvm::Receipt;
+use std::str::FromStr;
+
+type ProofArtifactRow = (
+    Vec<u8>,
+    Vec<u8>,
+    String,
+    String,
+    Option<i32>,
+    Option<i32>,
+    i64,
+);
+
+/// Converts a claim digest read from a column referencing `proof_artifacts`.
+pub(crate) fn proof_id_from_db(claim_digest: Vec<u8>) -> Result<ProofId, BridgeError> {
+    Ok(ProofId(claim_digest.try_into().map_err(|_| {
+        eyre::eyre!("Invalid proof artifact claim digest length")
+    })?))
+}
+
+fn proof_entry_from_row(row: ProofArtifactRow) -> Result<(ProofId, ProofEntry), BridgeError> {
+    let (claim_digest, method_id, kind, network, start_height, end_height, created_at) = row;
+
+    let block_range = match (start_height, end_height) {
+        (Some(start), Some(end)) => Some((
+            u32::try_from(start).wrap_err(BridgeError::IntConversionError)?,
+            u32::try_from(end).wrap_err(BridgeError::IntConversionError)?,
+        )),
+        _ => None,
+    };
+
+    Ok((
+        proof_id_from_db(claim_digest)?,
+        ProofEntry {
+            method_id: method_id
+                .try_into()
+                .map_err(|_| eyre::eyre!("Invalid proof artifact method ID length"))?,
+            metadata: ProofMetadata {
+                kind: ProofKind::from_str(&kind)?,
+                network: Network::from_str(&network).wrap_err("Invalid proof artifact network")?,
+                block_range,
+                created_at: u64::try_from(created_at).wrap_err(BridgeError::IntConversionError)?,
+            },
+        },
+    ))
+}
+
+impl Database {
+    /// Saves a receipt with the given ID and entry. Does nothing if a receipt
+    /// with the same ID is already saved.
+    pub async fn insert_proof_artifact(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        id: ProofId,
+        entry: &ProofEntry,
+        receipt: &Receipt,
+    ) -> Result<(), BridgeError> {
+        let (start_height, end_height) = match entry.metadata.block_range {
+            Some((start, end)) => (
+                Some(i32::try_from(start).wrap_err(BridgeError::IntConversionError)?),
+                Some(i32::try_from(end).wrap_err(BridgeError::IntConversionError)?),
+            ),
+            None => (None, None),
+        };
+
+        let query = sqlx::query(
+            "INSERT INTO proof_artifacts
+                (claim_digest, method_id, kind, network, start_height, end_height, created_at, receipt)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+             ON CONFLICT (claim_digest) DO NOTHING",
+        )
+        .bind(id.0.as_slice())
+        .bind(entry.method_id.as_slice())
+        .bind(entry.metadata.kind.as_str())
+        .bind(entry.metadata.network.to_string())
+        .bind(start_height)
+        .bind(end_height)
+        .bind(i64::try_from(entry.metadata.created_at).wrap_err(BridgeError::IntConversionError)?)
+        .bind(ReceiptDB(receipt.clone()));
+
+        execute_query_with_tx!(self.connection, tx, query, execute)?;
+
+        Ok(())
+    }
+
+    /// Returns the entry and receipt with the given ID, if saved.
+    pub async fn get_proof_artifact(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        id: ProofId,
+    ) -> Result<Option<(ProofEntry, Receipt)>, BridgeError> {
+        let query = sqlx::query_as::<
+            _,
+            (
+                Vec<u8>,
+                Vec<u8>,
+                String,
+                String,
+                Option<i32>,
+                Option<i32>,
+                i64,
+                ReceiptDB,
+            ),
+        >(
+            "SELECT claim_digest, method_id, kind, network, start_height, end_height, created_at, receipt
+             FROM proof_artifacts WHERE claim_digest = $1",
+        )
+        .bind(id.0.as_slice());
+
+        let row = execute_query_with_tx!(self.connection, tx, query, fetch_optional)?;
+
+        row.map(
+            |(
+                claim_digest,
+                method_id,
+                kind,
+                network,
+                start_height,
+                end_height,
+                created_at,
+                receipt,
+            )| {
+                let (_, entry) = proof_entry_from_row((
+                    claim_digest,
+                    method_id,
+                    kind,
+                    network,
+                    start_height,
+                    end_height,
+                    created_at,
+                ))?;
+                Ok((entry, receipt.0))
+            },
+        )
+        .transpose()
+    }
+
+    /// Lists the IDs and entries of saved receipts, optionally only the ones
+    /// of the given kind, oldest first.
+    pub async fn list_proof_artifacts(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        kind: Option<ProofKind>,
+    ) -> Result<Vec<(ProofId, ProofEntry)>, BridgeError> {
+        let query = sqlx::query_as::<_, ProofArtifactRow>(
+            "SELECT claim_digest, method_id, kind, network, start_height, end_height, created_at
+             FROM proof_artifacts WHERE $1::TEXT IS NULL OR kind = $1
+             ORDER BY created_at, claim_digest",
+        )
+        .bind(kind.map(|kind| kind.as_str()));
+
+        let rows = execute_query_with_tx!(self.connection, tx, query, fetch_all)?;
+
+        rows.into_iter().map(proof_entry_from_row).collect()
+    }
+
+    /// Returns true if `table` has a column named `column`.
+    async fn has_column(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        table: &str,
+        column: &str,
+    ) -> Result<bool, BridgeError> {
+        let query = sqlx::query_as::<_, (bool,)>(
+            "SELECT EXISTS (
+                SELECT 1 FROM information_schema.columns
+                WHERE table_schema = current_schema() AND table_name = $1 AND column_name = $2
+            )",
+        )
+        .bind(table)
+        .bind(column);
+
+        let (exists,) = execute_query_with_tx!(self.connection, tx, query, fetch_one)?;
+
+        Ok(exists)
+    }
+
+    /// Moves the receipts saved as blobs in `header_chain_proofs.proof` and
+    /// `lcp_for_asserts.lcp_receipt` before these tables referenced the proof
+    /// store to `proof_artifacts`, and drops the blob columns. Does nothing
+    /// once the columns are dropped. Run on startup after the migrations.
+    pub(crate) async fn move_proof_blobs_to_store(
+        &self,
+        network: Network,
+    ) -> Result<(), BridgeError> {
+        let mut dbtx = self.begin_transaction().await?;
+
+        if self
+            .has_column(Some(&mut dbtx), "header_chain_proofs", "proof")
+            .await?
+        {
+            let query = sqlx::query_as::<_, (BlockHashDB,)>(
+                "SELECT block_hash FROM header_chain_proofs WHERE proof IS NOT NULL",
+            );
+            let block_hashes =
+                execute_query_with_tx!(self.connection, Some(&mut dbtx), query, fetch_all)?;
+            tracing::info!(
+                "Moving {} header chain proofs to the proof store",
+                block_hashes.len()
+            );
+
+            for (block_hash,) in block_hashes {
+                let query = sqlx::query_as::<_, (ReceiptDB,)>(
+                    "SELECT proof FROM header_chain_proofs WHERE block_hash = $1",
+                )
+                .bind(BlockHashDB(block_hash.0));
+                let (receipt,) =
+                    execute_query_with_tx!(self.connection, Some(&mut dbtx), query, fetch_one)?;
+                // The range of the blocks proven last is not known for these.
+                let id = self
+                    .move_receipt_to_store(
+                        &mut dbtx,
+                        &receipt.0,
+                        ProofMetadata::new(ProofKind::HeaderChain, network, None),
+                    )
+                    .await?;
+
+                let query = sqlx::query(
+                    "UPDATE header_chain_proofs SET proof_id = $1 WHERE block_hash = $2",
+                )
+                .bind(id.0.as_slice())
+                .bind(BlockHashDB(block_hash.0));
+                execute_query_with_tx!(self.connection, Some(&mut dbtx), query, execute)?;
+            }
+
+            sqlx::query("ALTER TABLE header_chain_proofs DROP COLUMN proof")
+                .execute(&mut *dbtx)
+                .await?;
+        }
+
+        if self
+            .has_column(Some(&mut dbtx), "lcp_for_asserts", "lcp_receipt")
+            .await?
+        {
+            let query = sqlx::query_as::<_, (i32, ReceiptDB)>(
+                "SELECT deposit_id, lcp_receipt FROM lcp_for_asserts",
+            );
+            let lcps = execute_query_with_tx!(self.connection, Some(&mut dbtx), query, fetch_all)?;
+            tracing::info!(
+                "Moving {} light client proofs to the proof store",
+                lcps.len()
+            );
+
+            for (deposit_id, receipt) in lcps {
+                let id = self
+                    .move_receipt_to_store(
+                        &mut dbtx,
+                        &receipt.0,
+                        ProofMetadata::new(ProofKind::LightClient, network, None),
+                    )
+                    .await?;
+
+                let query =
+                    sqlx::query("UPDATE lcp_for_asserts SET proof_id = $1 WHERE deposit_id = $2")
+                        .bind(id.0.as_slice())
+                        .bind(deposit_id);
+                execute_query_with_tx!(self.connection, Some(&mut dbtx), query, execute)?;
+            }
+
+            sqlx::query(
+                "ALTER TABLE lcp_for_asserts
+                    DROP COLUMN lcp_receipt,
+                    ALTER COLUMN proof_id SET NOT NULL",
+            )
+            .execute(&mut *dbtx)
+            .await?;
+        }
+
+        dbtx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn move_receipt_to_store(
+        &self,
+        dbtx: DatabaseTransaction<'_>,
+        receipt: &Receipt,
+        metadata: ProofMetadata,
+    ) -> Result<ProofId, BridgeError> {
+        let id = ProofId::of(receipt)?;
+        let entry = ProofEntry {
+            method_id: method_id(receipt)?,
+            metadata,
+        };
+        self.insert_proof_artifact(Some(dbtx), id, &entry, receipt)
+            .await?;
+
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+    use crate::proof_store::{method_id, ProofEntry, ProofId, ProofKind, ProofMetadata};
+    use crate::test::common::*;
+    use borsh::BorshDeserialize;
+    use risc0_zkvm::Receipt;
+
+    #[tokio::test]
+    async fn save_get_and_list_proof_artifacts() {
+        let config = create_test_config_with_thread_name().await;
+        let db = Database::new(&config).await.unwrap();
+
+        let receipt = Receipt::try_from_slice(include_bytes!("../test/data/first_1.bin")).unwrap();
+        let id = ProofId::of(&receipt).unwrap();
+        let entry = ProofEntry {
+            method_id: method_id(&receipt).unwrap(),
+            metadata: ProofMetadata::new(
+                ProofKind::HeaderChain,
+                bitcoin::Network::Regtest,
+                Some((0, 1)),
+            ),
+        };
+
+        assert!(db.get_proof_artifact(None, id).await.unwrap().is_none());
+
+        db.insert_proof_artifact(None, id, &entry, &receipt)
+            .await
+            .unwrap();
+        // Saving the same receipt again keeps the first entry.
+        let mut other_entry = entry.clone();
+        other_entry.metadata.block_range = None;
+        db.insert_proof_artifact(None, id, &other_entry, &receipt)
+            .await
+            .unwrap();
+
+        let (read_entry, read_receipt) = db.get_proof_artifact(None, id).await.unwrap().unwrap();
+        assert_eq!(read_entry, entry);
+        assert_eq!(read_receipt.journal, receipt.journal);
+
+        assert_eq!(
+            db.list_proof_artifacts(None, None).await.unwrap(),
+            vec![(id, entry.clone())]
+        );
+        assert_eq!(
+            db.list_proof_artifacts(None, Some(ProofKind::HeaderChain))
+                .await
+                .unwrap(),
+            vec![(id, entry)]
+        );
+        assert!(db
+            .list_proof_artifacts(None, Some(ProofKind::LightClient))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn move_proof_blobs_to_store() {
+        let config = create_test_config_with_thread_name().await;
+        let db = Database::new(&config).await.unwrap();
+
+        let receipt = Receipt::try_from_slice(include_bytes!("../test/data/first_1.bin")).unwrap();
+        let id = ProofId::of(&receipt).unwrap();
+        let receipt_bytes = borsh::to_vec(&receipt).unwrap();
+
+        // Bring back the blob columns of a database created before the proof
+        // store.
+        sqlx::raw_sql(
+            "ALTER TABLE header_chain_proofs ADD COLUMN proof BYTEA;
+             ALTER TABLE lcp_for_asserts
+                ADD COLUMN lcp_receipt BYTEA,
+                ALTER COLUMN proof_id DROP NOT NULL;",
+        )
+        .execute(&db.connection)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO header_chain_proofs (block_hash, height, proof) VALUES ('hash', 1, $1)",
+        )
+        .bind(&receipt_bytes)
+        .execute(&db.connection)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO lcp_for_asserts (deposit_id, lcp_receipt) VALUES (1, $1)")
+            .bind(&receipt_bytes)
+            .execute(&db.connection)
+            .await
+            .unwrap();
+
+        db.move_proof_blobs_to_store(bitcoin::Network::Regtest)
+            .await
+            .unwrap();
+        // Moving again does nothing.
+        db.move_proof_blobs_to_store(bitcoin::Network::Regtest)
+            .await
+            .unwrap();
+
+        assert!(!db
+            .has_column(None, "header_chain_proofs", "proof")
+            .await
+            .unwrap());
+        assert!(!db
+            .has_column(None, "lcp_for_asserts", "lcp_receipt")
+            .await
+            .unwrap());
+
+        let (hcp_id,): (Vec<u8>,) =
+            sqlx::query_as("SELECT proof_id FROM header_chain_proofs WHERE block_hash = 'hash'")
+                .fetch_one(&db.connection)
+                .await
+                .unwrap();
+        assert_eq!(hcp_id, id.0);
+        assert_eq!(db.get_lcp_for_assert(None, 1).await.unwrap(), Some(id));
+
+        let (_, read_receipt) = db.get_proof_artifact(None, id).await.unwrap().unwrap();
+        assert_eq!(read_receipt.journal, receipt.journal);
+    }
+}
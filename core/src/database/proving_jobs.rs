@@ -0,0 +1,335 @@
+//! # Proving Job Related Database Operations
+//!
+//! This module includes database functions of the proving job queue, used
+//! through [`crate::proving_queue`].
+
+use super::{Database, DatabaseTransaction};
+use crate::execute_query_with_tx;
+use crate::proving_queue::{ProvingJobState, ProvingJobStatus};
+use clementine_errors::BridgeError;
+use eyre::Context;
+use std::str::FromStr;
+use std::time::Duration;
+
+impl Database {
+    /// Saves a new pending proving job. Returns the ID of the job, or `None`
+    /// if a job with the same input digest already exists.
+    pub async fn insert_proving_job(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        kind: &str,
+        input_digest: [u8; 32],
+        input: &[u8],
+    ) -> Result<Option<i64>, BridgeError> {
+        let query = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO proving_jobs (kind, input_digest, input) VALUES ($1, $2, $3)
+             ON CONFLICT (input_digest) DO NOTHING
+             RETURNING id",
+        )
+        .bind(kind)
+        .bind(input_digest.as_slice())
+        .bind(input);
+
+        Ok(execute_query_with_tx!(
+            self.connection,
+            tx,
+            query,
+            fetch_optional
+        )?)
+    }
+
+    /// Returns the ID and status of the proving job with the given input
+    /// digest, if it exists.
+    pub async fn get_proving_job_by_input_digest(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        input_digest: [u8; 32],
+    ) -> Result<Option<(i64, ProvingJobStatus)>, BridgeError> {
+        let query = sqlx::query_as::<_, (i64, String)>(
+            "SELECT id, status FROM proving_jobs WHERE input_digest = $1",
+        )
+        .bind(input_digest.as_slice());
+
+        let job: Option<(i64, String)> =
+            execute_query_with_tx!(self.connection, tx, query, fetch_optional)?;
+
+        job.map(|(id, status)| Ok((id, ProvingJobStatus::from_str(&status)?)))
+            .transpose()
+    }
+
+    /// Returns the state of the proving job with the given ID, if it exists.
+    pub async fn get_proving_job_state(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        id: i64,
+    ) -> Result<Option<ProvingJobState>, BridgeError> {
+        let query = sqlx::query_as::<_, (String, String, i32, Option<Vec<u8>>, Option<String>)>(
+            "SELECT kind, status, attempts, result, error FROM proving_jobs WHERE id = $1",
+        )
+        .bind(id);
+
+        let job = execute_query_with_tx!(self.connection, tx, query, fetch_optional)?;
+
+        job.map(|(kind, status, attempts, result, error)| {
+            Ok(ProvingJobState {
+                kind,
+                status: ProvingJobStatus::from_str(&status)?,
+                attempts: u32::try_from(attempts).wrap_err(BridgeError::IntConversionError)?,
+                result,
+                error,
+            })
+        })
+        .transpose()
+    }
+
+    /// Returns the serialized input of the proving job with the given ID, if
+    /// it exists and isn't done or failed.
+    pub async fn get_proving_job_input(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        id: i64,
+    ) -> Result<Option<Vec<u8>>, BridgeError> {
+        let query = sqlx::query_scalar::<_, Option<Vec<u8>>>(
+            "SELECT input FROM proving_jobs WHERE id = $1",
+        )
+        .bind(id);
+
+        let input = execute_query_with_tx!(self.connection, tx, query, fetch_optional)?;
+
+        Ok(input.flatten())
+    }
+
+    /// Marks a proving job as taken by `worker` and returns the number of
+    /// attempts made for it, including this one.
+    pub async fn start_proving_job(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        id: i64,
+        worker: &str,
+    ) -> Result<u32, BridgeError> {
+        let query = sqlx::query_scalar::<_, i32>(
+            "UPDATE proving_jobs
+             SET status = 'running', attempts = attempts + 1, worker = $2, updated_at = NOW()
+             WHERE id = $1
+             RETURNING attempts",
+        )
+        .bind(id)
+        .bind(worker);
+
+        let attempts: i32 = execute_query_with_tx!(self.connection, tx, query, fetch_one)?;
+
+        Ok(u32::try_from(attempts).wrap_err(BridgeError::IntConversionError)?)
+    }
+
+    /// Saves the serialized result of a proving job, marks it as done and
+    /// drops its input.
+    pub async fn finish_proving_job(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        id: i64,
+        result: &[u8],
+    ) -> Result<(), BridgeError> {
+        let query = sqlx::query(
+            "UPDATE proving_jobs
+             SET status = 'done', input = NULL, result = $2, error = NULL, updated_at = NOW()
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(result);
+
+        execute_query_with_tx!(self.connection, tx, query, execute)?;
+
+        Ok(())
+    }
+
+    /// Saves the error of a failed attempt of a proving job. The job is
+    /// marked as pending if it will be retried, or as failed with its input
+    /// dropped otherwise.
+    pub async fn fail_proving_job(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        id: i64,
+        error: &str,
+        retry: bool,
+    ) -> Result<(), BridgeError> {
+        let status = if retry {
+            ProvingJobStatus::Pending
+        } else {
+            ProvingJobStatus::Failed
+        };
+        let query = sqlx::query(
+            "UPDATE proving_jobs
+             SET status = $2,
+                 input = CASE WHEN $4 THEN input END,
+                 error = $3,
+                 updated_at = NOW()
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(status.as_str())
+        .bind(error)
+        .bind(retry);
+
+        execute_query_with_tx!(self.connection, tx, query, execute)?;
+
+        Ok(())
+    }
+
+    /// Marks a failed proving job as pending again with no attempts made,
+    /// saving its input again.
+    pub async fn reset_proving_job(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        id: i64,
+        input: &[u8],
+    ) -> Result<(), BridgeError> {
+        let query = sqlx::query(
+            "UPDATE proving_jobs
+             SET status = 'pending', input = $2, attempts = 0, error = NULL, updated_at = NOW()
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(input);
+
+        execute_query_with_tx!(self.connection, tx, query, execute)?;
+
+        Ok(())
+    }
+
+    /// Deletes the done and failed proving jobs last updated more than
+    /// `retention` ago and returns the number of deleted jobs.
+    pub async fn delete_finished_proving_jobs(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        retention: Duration,
+    ) -> Result<u64, BridgeError> {
+        let query = sqlx::query(
+            "DELETE FROM proving_jobs
+             WHERE status IN ('done', 'failed')
+               AND updated_at <= NOW() - make_interval(secs => $1)",
+        )
+        .bind(retention.as_secs_f64());
+
+        let result = execute_query_with_tx!(self.connection, tx, query, execute)?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Makes a message of a pgmq queue invisible to readers for `vt_seconds`
+    /// from now.
+    pub async fn set_pgmq_message_visibility_timeout(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        queue_name: &str,
+        msg_id: i64,
+        vt_seconds: i32,
+    ) -> Result<(), BridgeError> {
+        let query = sqlx::query("SELECT msg_id FROM pgmq.set_vt($1, $2, $3)")
+            .bind(queue_name)
+            .bind(msg_id)
+            .bind(vt_seconds);
+
+        execute_query_with_tx!(self.connection, tx, query, execute)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+    use crate::proving_queue::ProvingJobStatus;
+    use crate::test::common::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn proving_job_lifecycle() {
+        let config = create_test_config_with_thread_name().await;
+        let db = Database::new(&config).await.unwrap();
+
+        let digest = [1u8; 32];
+        let id = db
+            .insert_proving_job(None, "work_only", digest, &[1, 2, 3])
+            .await
+            .unwrap()
+            .unwrap();
+        // The same input is not saved twice.
+        assert!(db
+            .insert_proving_job(None, "work_only", digest, &[1, 2, 3])
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            db.get_proving_job_by_input_digest(None, digest)
+                .await
+                .unwrap(),
+            Some((id, ProvingJobStatus::Pending))
+        );
+        assert_eq!(
+            db.get_proving_job_input(None, id).await.unwrap(),
+            Some(vec![1, 2, 3])
+        );
+
+        assert_eq!(db.start_proving_job(None, id, "worker").await.unwrap(), 1);
+        db.fail_proving_job(None, id, "out of memory", true)
+            .await
+            .unwrap();
+        let state = db.get_proving_job_state(None, id).await.unwrap().unwrap();
+        assert_eq!(state.status, ProvingJobStatus::Pending);
+        assert_eq!(state.attempts, 1);
+        assert_eq!(state.error.as_deref(), Some("out of memory"));
+
+        assert_eq!(
+            db.get_proving_job_input(None, id).await.unwrap(),
+            Some(vec![1, 2, 3])
+        );
+
+        assert_eq!(db.start_proving_job(None, id, "worker").await.unwrap(), 2);
+        db.fail_proving_job(None, id, "out of memory", false)
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_proving_job_state(None, id)
+                .await
+                .unwrap()
+                .unwrap()
+                .status,
+            ProvingJobStatus::Failed
+        );
+        assert_eq!(db.get_proving_job_input(None, id).await.unwrap(), None);
+
+        db.reset_proving_job(None, id, &[1, 2, 3]).await.unwrap();
+        assert_eq!(
+            db.get_proving_job_input(None, id).await.unwrap(),
+            Some(vec![1, 2, 3])
+        );
+        assert_eq!(db.start_proving_job(None, id, "worker").await.unwrap(), 1);
+        db.finish_proving_job(None, id, &[4, 5]).await.unwrap();
+        let state = db.get_proving_job_state(None, id).await.unwrap().unwrap();
+        assert_eq!(state.status, ProvingJobStatus::Done);
+        assert_eq!(state.result, Some(vec![4, 5]));
+        assert_eq!(state.error, None);
+        assert_eq!(db.get_proving_job_input(None, id).await.unwrap(), None);
+
+        // Finished jobs are kept until they are older than the retention.
+        assert_eq!(
+            db.delete_finished_proving_jobs(None, Duration::from_secs(3600))
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            db.delete_finished_proving_jobs(None, Duration::ZERO)
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(db.get_proving_job_state(None, id).await.unwrap().is_none());
+
+        assert!(db
+            .get_proving_job_state(None, id + 1)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
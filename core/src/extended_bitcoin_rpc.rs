@@ -12,8 +12,8 @@
 
 // Re-export types from clementine-extended-rpc
 pub use clementine_extended_rpc::{
-    get_fee_rate_from_mempool_space, BitcoinRPCError, ExtendedBitcoinRpc, RetryConfig,
-    RetryableError,
+    get_fee_rate_from_mempool_space, BackendTips, BitcoinRPCError, BitcoindBackend,
+    ExtendedBitcoinRpc, RetryConfig, RetryableError,
 };
 
 use async_trait::async_trait;
@@ -379,6 +379,31 @@ mod tests {
         assert_eq!(cloned_rpc.get_block_hash(height).await.unwrap(), hash);
     }
 
+    #[tokio::test]
+    async fn rpc_with_unreachable_fallback_backend() {
+        let mut config = create_test_config_with_thread_name().await;
+        let regtest = create_regtest_rpc(&mut config).await;
+        regtest.rpc().mine_blocks(5).await.unwrap();
+
+        config.bitcoin_rpc_fallbacks = vec![crate::extended_bitcoin_rpc::BitcoindBackend {
+            url: "http://127.0.0.1:1".to_string(),
+            user: config.bitcoin_rpc_user.clone(),
+            password: config.bitcoin_rpc_password.clone(),
+        }];
+        let rpc = config.connect_bitcoin_rpc().await.unwrap();
+
+        let height = regtest.rpc().get_block_count().await.unwrap();
+        assert_eq!(rpc.get_block_count().await.unwrap(), height);
+        // Wallet calls go to the primary backend.
+        rpc.get_new_wallet_address().await.unwrap();
+
+        let tips = rpc.check_backend_tips(0).await;
+        assert_eq!(tips.heights.len(), 2);
+        assert_eq!(tips.heights[0].1, Some(height as u32));
+        assert_eq!(tips.heights[1].1, None);
+        assert_eq!(tips.divergence, 0);
+    }
+
     #[tokio::test]
     async fn test_rpc_call_retry_with_invalid_credentials() {
         use crate::extended_bitcoin_rpc::RetryableError;
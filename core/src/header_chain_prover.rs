@@ -6,6 +6,8 @@
 
 use crate::builder::block_cache::BlockCache;
 use crate::database::DatabaseTransaction;
+use crate::proof_store::{PostgresProofStore, ProofKind, ProofMetadata, ProofStore};
+use crate::proving_queue::ProvingQueue;
 use crate::{config::BridgeConfig, database::Database, extended_bitcoin_rpc::ExtendedBitcoinRpc};
 use bitcoin::block::Header;
 use bitcoin::{hashes::Hash, BlockHash, Network};
@@ -60,6 +62,8 @@ pub use clementine_errors::HeaderChainProverError;
 #[derive(Debug, Clone)]
 pub struct HeaderChainProver {
     db: Database,
+    proof_store: PostgresProofStore,
+    proving_queue: ProvingQueue,
     network: bitcoin::Network,
     batch_size: u64,
 }
@@ -72,6 +76,8 @@ impl HeaderChainProver {
         rpc: ExtendedBitcoinRpc,
     ) -> Result<Self, HeaderChainProverError> {
         let db = Database::new(config).await.map_to_eyre()?;
+        let proof_store = PostgresProofStore::new(db.clone());
+        let proving_queue = ProvingQueue::new(db.clone()).await.map_to_eyre()?;
         let tip_height = rpc.get_current_chain_height().await.map_to_eyre()?;
         if !config
             .protocol_paramset()
@@ -167,7 +173,14 @@ impl HeaderChainProver {
                     tracing::debug!("Can't set initial block info for header chain prover, because: {e}. Doesn't affect anything, continuing...");
                 });
 
-            db.set_block_proof(None, block_hash, proof)
+            let proof_id = proof_store
+                .put(
+                    &proof,
+                    ProofMetadata::new(ProofKind::HeaderChain, network, None),
+                )
+                .await
+                .map_to_eyre()?;
+            db.set_block_proof(None, block_hash, proof_id)
                 .await
                 .map_to_eyre()?;
         } else {
@@ -219,18 +232,37 @@ impl HeaderChainProver {
                 )
                 .await;
 
-            db.set_block_proof(None, genesis_block_hash, proof)
+            let genesis_height = config.protocol_paramset().genesis_height;
+            let proof_id = proof_store
+                .put(
+                    &proof,
+                    ProofMetadata::new(
+                        ProofKind::HeaderChain,
+                        config.protocol_paramset().network,
+                        Some((genesis_height, genesis_height)),
+                    ),
+                )
+                .await
+                .map_to_eyre()?;
+            db.set_block_proof(None, genesis_block_hash, proof_id)
                 .await
                 .map_to_eyre()?;
         }
 
         Ok(HeaderChainProver {
             db,
+            proof_store,
+            proving_queue,
             batch_size: config.header_chain_proof_batch_size.into(),
             network: config.protocol_paramset().network,
         })
     }
 
+    /// Returns the proving queue the prover submits its proving jobs to.
+    pub fn proving_queue(&self) -> &ProvingQueue {
+        &self.proving_queue
+    }
+
     pub async fn get_chain_state_from_height(
         rpc: &ExtendedBitcoinRpc,
         height: u64,
@@ -316,11 +348,48 @@ impl HeaderChainProver {
         Ok(chain_state)
     }
 
-    /// Proves the work only proof for the given HCP receipt.
+    /// Proves the work only proof for the given HCP receipt. Proving is done
+    /// by a prover worker through the proving queue.
     pub async fn prove_work_only(
         &self,
         hcp_receipt: Receipt,
     ) -> Result<(Receipt, WorkOnlyCircuitOutput), HeaderChainProverError> {
+        let block_header_circuit_output: BlockHeaderCircuitOutput =
+            borsh::from_slice(&hcp_receipt.journal.bytes)
+                .wrap_err(HeaderChainProverError::ProverDeSerializationError)?;
+        let block_height = block_header_circuit_output.chain_state.block_height;
+
+        tracing::warn!("Starting proving HCP work only proof for creating a watchtower challenge");
+        let receipt = self
+            .proving_queue
+            .prove_work_only(hcp_receipt, self.network)
+            .await
+            .map_to_eyre()?;
+        tracing::warn!("HCP work only proof generated for creating a watchtower challenge");
+        let work_output: WorkOnlyCircuitOutput = borsh::from_slice(&receipt.journal.bytes)
+            .wrap_err(HeaderChainProverError::ProverDeSerializationError)?;
+        self.proof_store
+            .put(
+                &receipt,
+                ProofMetadata::new(
+                    ProofKind::WorkOnly,
+                    self.network,
+                    Some((block_height, block_height)),
+                ),
+            )
+            .await
+            .map_to_eyre()?;
+
+        Ok((receipt, work_output))
+    }
+
+    /// Generates the work only proof for the given HCP receipt. Blocks until
+    /// the proof is generated, which can take a long time; used by prover
+    /// workers.
+    pub fn prove_work_only_circuit(
+        network: Network,
+        hcp_receipt: Receipt,
+    ) -> Result<Receipt, HeaderChainProverError> {
         let block_header_circuit_output: BlockHeaderCircuitOutput =
             borsh::from_slice(&hcp_receipt.journal.bytes)
                 .wrap_err(HeaderChainProverError::ProverDeSerializationError)?;
@@ -328,7 +397,6 @@ impl HeaderChainProver {
             header_chain_circuit_output: block_header_circuit_output,
         };
         let input_bytes = borsh::to_vec(&input).wrap_err(BridgeError::BorshError)?;
-        let network = self.network;
 
         let elf = match network {
             Network::Bitcoin => MAINNET_WORK_ONLY_ELF,
@@ -338,40 +406,32 @@ impl HeaderChainProver {
             _ => Err(BridgeError::UnsupportedNetwork.into_eyre())?,
         };
 
-        tracing::warn!("Starting proving HCP work only proof for creating a watchtower challenge");
-        let receipt = tokio::task::spawn_blocking(move || -> Result<Receipt, eyre::Report> {
-            let mut env = ExecutorEnv::builder();
-            env.write_slice(&input_bytes);
-            env.add_assumption(hcp_receipt);
-            let env = env
-                .build()
-                .map_err(|e| eyre::eyre!(e))
-                .wrap_err("Failed to build environment")?;
+        let mut env = ExecutorEnv::builder();
+        env.write_slice(&input_bytes);
+        env.add_assumption(hcp_receipt);
+        let env = env
+            .build()
+            .map_err(|e| eyre::eyre!(e))
+            .wrap_err("Failed to build environment")?;
 
-            let prover = risc0_zkvm::default_prover();
+        let prover = risc0_zkvm::default_prover();
 
-            if !is_dev_mode() {
-                prover
-                    .prove_with_opts(env, elf, &ProverOpts::groth16())
-                    .map_err(|e| eyre::eyre!(e))
-                    .map(|result| result.receipt)
-            } else {
-                let stark_receipt = prover
-                    .prove_with_opts(env, elf, &ProverOpts::succinct())
-                    .map_err(|e| eyre::eyre!(e))?
-                    .receipt;
-                let journal = stark_receipt.journal.bytes.clone();
-                dev_stark_to_risc0_g16(stark_receipt, &journal)
-            }
-        })
-        .await
-        .map_err(|e| eyre::eyre!("Failed to join the prove_work_only task: {}", e))?
+        let receipt = if !is_dev_mode() {
+            prover
+                .prove_with_opts(env, elf, &ProverOpts::groth16())
+                .map_err(|e| eyre::eyre!(e))
+                .map(|result| result.receipt)
+        } else {
+            let stark_receipt = prover
+                .prove_with_opts(env, elf, &ProverOpts::succinct())
+                .map_err(|e| eyre::eyre!(e))?
+                .receipt;
+            let journal = stark_receipt.journal.bytes.clone();
+            dev_stark_to_risc0_g16(stark_receipt, &journal)
+        }
         .wrap_err("Failed to prove work only")?;
-        tracing::warn!("HCP work only proof generated for creating a watchtower challenge");
-        let work_output: WorkOnlyCircuitOutput = borsh::from_slice(&receipt.journal.bytes)
-            .wrap_err(HeaderChainProverError::ProverDeSerializationError)?;
 
-        Ok((receipt, work_output))
+        Ok(receipt)
     }
 
     /// Proves blocks till the block with hash `current_block_hash`.
@@ -394,6 +454,8 @@ impl HeaderChainProver {
             current_block_hash
         );
 
+        let proven_blocks =
+            u32::try_from(block_headers.len()).wrap_err(BridgeError::IntConversionError)?;
         let headers: Vec<CircuitBlockHeader> = block_headers.into_iter().map(Into::into).collect();
         let network = self.network;
         let receipt = tokio::task::spawn_blocking(move || {
@@ -403,13 +465,42 @@ impl HeaderChainProver {
         .wrap_err("Failed to join the prove_block_headers task")?
         .wrap_err("Failed to prove block headers")?;
 
+        let output: BlockHeaderCircuitOutput = borsh::from_slice(&receipt.journal.bytes)
+            .wrap_err(HeaderChainProverError::ProverDeSerializationError)?;
+        let end_height = output.chain_state.block_height;
+        let proof_id = self
+            .proof_store
+            .put(
+                &receipt,
+                ProofMetadata::new(
+                    ProofKind::HeaderChain,
+                    network,
+                    Some((
+                        end_height.saturating_sub(proven_blocks.saturating_sub(1)),
+                        end_height,
+                    )),
+                ),
+            )
+            .await?;
         self.db
-            .set_block_proof(None, current_block_hash, receipt.clone())
+            .set_block_proof(None, current_block_hash, proof_id)
             .await?;
 
         Ok(receipt)
     }
 
+    /// Gets the proof of the block with the given hash from the proof store,
+    /// `None` if the block isn't proven.
+    pub async fn get_block_proof(&self, hash: BlockHash) -> Result<Option<Receipt>, BridgeError> {
+        let Some(proof_id) = self.db.get_block_proof_id_by_hash(None, hash).await? else {
+            return Ok(None);
+        };
+
+        Ok(Some(
+            self.proof_store.get_referenced(None, &proof_id).await?,
+        ))
+    }
+
     /// Proves given block headers.
     ///
     /// # Parameters
@@ -517,8 +608,7 @@ impl HeaderChainProver {
 
         if latest_proven_block.2 == height as u64 {
             let receipt = self
-                .db
-                .get_block_proof_by_hash(None, latest_proven_block.0)
+                .get_block_proof(latest_proven_block.0)
                 .await
                 .wrap_err("Failed to get block proof")?
                 .ok_or(eyre!("Failed to get block proof"))?;
@@ -534,8 +624,7 @@ impl HeaderChainProver {
             .collect::<Vec<_>>();
 
         let previous_proof = self
-            .db
-            .get_block_proof_by_hash(None, latest_proven_block.0)
+            .get_block_proof(latest_proven_block.0)
             .await?
             .ok_or(eyre::eyre!("No proven block found"))?;
         let receipt = self
@@ -640,7 +729,7 @@ impl HeaderChainProver {
                     .wrap_err("Can't convert u64 to u32")?,
             )
             .await?;
-        let (unproven_blocks, prev_proof) = match unproven_blocks {
+        let (unproven_blocks, prev_proof_id) = match unproven_blocks {
             Some(unproven_blocks) => unproven_blocks,
             None => {
                 tracing::debug!("No unproven blocks found");
@@ -655,6 +744,10 @@ impl HeaderChainProver {
             .map(|(_, header, _)| *header)
             .collect::<Vec<_>>();
 
+        let prev_proof = self
+            .proof_store
+            .get_referenced(None, &prev_proof_id)
+            .await?;
         let receipt = self
             .prove_and_save_block(current_block_hash, block_headers, prev_proof)
             .await?;
@@ -909,12 +1002,7 @@ mod tests {
         // Test assumption is for block 0.
         let hash = rpc.get_block_hash(0).await.unwrap();
         let (receipt, _) = prover.prove_till_hash(hash).await.unwrap();
-        let db_receipt = prover
-            .db
-            .get_block_proof_by_hash(None, hash)
-            .await
-            .unwrap()
-            .unwrap();
+        let db_receipt = prover.get_block_proof(hash).await.unwrap().unwrap();
         assert_eq!(receipt.journal, db_receipt.journal);
         assert_eq!(receipt.metadata, db_receipt.metadata);
     }
@@ -1008,7 +1096,6 @@ mod tests {
         let mut config = create_test_config_with_thread_name().await;
         let regtest = create_regtest_rpc(&mut config).await;
         let rpc = regtest.rpc().clone();
-        let db = Database::new(&config).await.unwrap();
 
         let prover = HeaderChainProver::new(&config, rpc.clone_inner().await.unwrap())
             .await
@@ -1017,20 +1104,12 @@ mod tests {
         for i in (0..3).rev() {
             let hash = rpc.get_block_hash(i).await.unwrap();
             let (proof, _) = prover.prove_till_hash(hash).await.unwrap();
-            let db_proof = db
-                .get_block_proof_by_hash(None, hash)
-                .await
-                .unwrap()
-                .unwrap();
+            let db_proof = prover.get_block_proof(hash).await.unwrap().unwrap();
             assert_eq!(proof.journal, db_proof.journal);
         }
         let hash = rpc.get_block_hash(5).await.unwrap();
         let (proof, _) = prover.prove_till_hash(hash).await.unwrap();
-        let db_proof = db
-            .get_block_proof_by_hash(None, hash)
-            .await
-            .unwrap()
-            .unwrap();
+        let db_proof = prover.get_block_proof(hash).await.unwrap().unwrap();
         assert_eq!(proof.journal, db_proof.journal);
     }
 
@@ -1049,11 +1128,7 @@ mod tests {
 
         let genesis_hash = rpc.get_block_hash(0).await.unwrap();
         let (genesis_block_proof, _) = prover.prove_till_hash(genesis_hash).await.unwrap();
-        let db_proof = db
-            .get_block_proof_by_hash(None, genesis_hash)
-            .await
-            .unwrap()
-            .unwrap();
+        let db_proof = prover.get_block_proof(genesis_hash).await.unwrap().unwrap();
         assert_eq!(genesis_block_proof.journal, db_proof.journal);
 
         assert!(
@@ -1096,8 +1171,7 @@ mod tests {
             .unwrap()
             .unwrap();
         let get_receipt = prover
-            .db
-            .get_block_proof_by_hash(None, latest_proof.0)
+            .get_block_proof(latest_proof.0)
             .await
             .unwrap()
             .unwrap();
@@ -1134,8 +1208,7 @@ mod tests {
             .unwrap()
             .unwrap();
         let get_receipt = prover
-            .db
-            .get_block_proof_by_hash(None, latest_proof.0)
+            .get_block_proof(latest_proof.0)
             .await
             .unwrap()
             .unwrap();
@@ -1146,11 +1219,7 @@ mod tests {
         let target_height = latest_proof.2 - 1;
         let target_hash = rpc.get_block_hash(target_height).await.unwrap();
 
-        assert!(db
-            .get_block_proof_by_hash(None, target_hash)
-            .await
-            .unwrap()
-            .is_none());
+        assert!(prover.get_block_proof(target_hash).await.unwrap().is_none());
 
         // get_header_chain_proof should calculate the proof for the block.
         let _receipt = prover.get_tip_header_chain_proof().await.unwrap();
@@ -1194,8 +1263,7 @@ mod tests {
                 Ok(verifier
                     .verifier
                     .header_chain_prover
-                    .db
-                    .get_block_proof_by_hash(None, hash)
+                    .get_block_proof(hash)
                     .await
                     .is_ok())
             },
@@ -0,0 +1,427 @@
+//! # Health Endpoints
+//!
+//! Plain HTTP `/healthz` and `/readyz` endpoints, so that orchestrators can
+//! probe an entity without a gRPC client or mTLS certificates.
+//!
+//! `/healthz` only reports that the process is serving requests. `/readyz`
+//! runs the entity's readiness checks and answers with `200` if all of them
+//! pass or `503` otherwise. Both return a JSON body; the readiness body lists
+//! every check with its outcome so that the failing one can be identified.
+
+use crate::citrea::CitreaClientT;
+use crate::config::{BridgeConfig, HealthConfig};
+use crate::database::Database;
+use crate::extended_bitcoin_rpc::ExtendedBitcoinRpc;
+use crate::metrics::{get_rpc_tip_height, SyncStatus, SyncStatusProvider};
+use crate::rpc::clementine::StoppedTasks;
+use crate::task::manager::BackgroundTaskManager;
+use async_trait::async_trait;
+use bitcoin::Amount;
+use clementine_errors::BridgeError;
+use eyre::Context;
+use http::header::{HeaderValue, CONTENT_TYPE};
+use http::{Method, Request, Response, StatusCode};
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use serde::Serialize;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Weak;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+/// Upper bound on the time a single readiness probe can take.
+const READINESS_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Timeout for the database connectivity check.
+const DATABASE_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Outcome of a single readiness check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HealthCheck {
+    pub name: &'static str,
+    pub healthy: bool,
+    pub detail: String,
+}
+
+impl HealthCheck {
+    pub fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            healthy: true,
+            detail: detail.into(),
+        }
+    }
+
+    pub fn fail(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            healthy: false,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Readiness of an entity along with the checks it was derived from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl Readiness {
+    /// The entity is ready only if every check passed.
+    pub fn from_checks(checks: Vec<HealthCheck>) -> Self {
+        Self {
+            ready: checks.iter().all(|check| check.healthy),
+            checks,
+        }
+    }
+}
+
+/// Implemented by the entity servers to report their readiness on `/readyz`.
+#[async_trait]
+pub trait ReadinessProvider: Send + Sync + 'static {
+    async fn readiness(&self) -> Readiness;
+}
+
+/// Fails if any background task has stopped.
+pub fn check_tasks(stopped_tasks: Result<StoppedTasks, BridgeError>) -> HealthCheck {
+    match stopped_tasks {
+        Ok(StoppedTasks { stopped_tasks }) if stopped_tasks.is_empty() => {
+            HealthCheck::pass("tasks", "All background tasks are running")
+        }
+        Ok(StoppedTasks { stopped_tasks }) => HealthCheck::fail(
+            "tasks",
+            format!("Stopped tasks: {}", stopped_tasks.join("; ")),
+        ),
+        Err(e) => HealthCheck::fail("tasks", format!("Failed to get task status: {e}")),
+    }
+}
+
+/// Fails if the database does not answer a trivial query.
+pub async fn check_database(db: &Database) -> HealthCheck {
+    let query = sqlx::query("SELECT 1").execute(&db.get_pool());
+    match tokio::time::timeout(DATABASE_CHECK_TIMEOUT, query).await {
+        Ok(Ok(_)) => HealthCheck::pass("database", "Database is reachable"),
+        Ok(Err(e)) => HealthCheck::fail("database", format!("Database query failed: {e}")),
+        Err(_) => HealthCheck::fail("database", "Database query timed out"),
+    }
+}
+
+/// Fails if the Bitcoin RPC tip could not be fetched.
+pub fn check_bitcoind(rpc_tip_height: Option<u32>) -> HealthCheck {
+    match rpc_tip_height {
+        Some(height) => HealthCheck::pass("bitcoind", format!("Tip height is {height}")),
+        None => HealthCheck::fail("bitcoind", "Bitcoin RPC is unreachable"),
+    }
+}
+
+/// Fails if the Citrea L2 block height could not be fetched.
+pub fn check_citrea(sync_status: &SyncStatus) -> HealthCheck {
+    match sync_status.citrea_l2_block_height {
+        Some(height) => HealthCheck::pass("citrea", format!("L2 block height is {height}")),
+        None => HealthCheck::fail("citrea", "Citrea RPC is unreachable"),
+    }
+}
+
+/// Fails if the Bitcoin syncer is more than `max_lag` blocks behind the
+/// Bitcoin RPC tip.
+pub fn check_sync_lag(sync_status: &SyncStatus, max_lag: u32) -> HealthCheck {
+    match (
+        sync_status.rpc_tip_height,
+        sync_status.btc_syncer_synced_height,
+    ) {
+        (Some(tip), Some(synced)) => {
+            let lag = tip.saturating_sub(synced);
+            let detail = format!("Bitcoin syncer is {lag} blocks behind tip (max {max_lag})");
+            if lag <= max_lag {
+                HealthCheck::pass("sync_lag", detail)
+            } else {
+                HealthCheck::fail("sync_lag", detail)
+            }
+        }
+        (None, _) => HealthCheck::fail("sync_lag", "Bitcoin RPC tip height is unknown"),
+        (_, None) => HealthCheck::fail("sync_lag", "Bitcoin syncer has not synced any blocks"),
+    }
+}
+
+/// Fails if the wallet balance is unknown or below `min_balance`.
+pub fn check_wallet_balance(sync_status: &SyncStatus, min_balance: Amount) -> HealthCheck {
+    match sync_status.wallet_balance {
+        Some(balance) if balance >= min_balance => HealthCheck::pass(
+            "wallet_balance",
+            format!("Wallet balance is {balance} (min {min_balance})"),
+        ),
+        Some(balance) => HealthCheck::fail(
+            "wallet_balance",
+            format!("Wallet balance is {balance} (min {min_balance})"),
+        ),
+        None => HealthCheck::fail("wallet_balance", "Wallet balance is unknown"),
+    }
+}
+
+/// Readiness of an entity that runs the full set of syncers (verifier and
+/// operator).
+pub(crate) async fn entity_readiness<T: SyncStatusProvider, C: CitreaClientT>(
+    background_tasks: &BackgroundTaskManager,
+    db: &Database,
+    rpc: &ExtendedBitcoinRpc,
+    config: &BridgeConfig,
+    citrea_client: &C,
+) -> Readiness {
+    let health_config = config.health.clone().unwrap_or_default();
+
+    let mut checks = vec![
+        check_tasks(background_tasks.get_stopped_tasks().await),
+        check_database(db).await,
+    ];
+
+    match T::get_sync_status(db, rpc, config, citrea_client).await {
+        Ok(sync_status) => {
+            checks.push(check_bitcoind(sync_status.rpc_tip_height));
+            checks.push(check_citrea(&sync_status));
+            checks.push(check_sync_lag(
+                &sync_status,
+                health_config.max_sync_lag_blocks,
+            ));
+            // Zero disables the check, e.g. for verifiers without a funded wallet.
+            if health_config.min_wallet_balance_sats > 0 {
+                checks.push(check_wallet_balance(
+                    &sync_status,
+                    Amount::from_sat(health_config.min_wallet_balance_sats),
+                ));
+            }
+        }
+        Err(e) => checks.push(HealthCheck::fail(
+            "sync_status",
+            format!("Failed to get sync status: {e}"),
+        )),
+    }
+
+    Readiness::from_checks(checks)
+}
+
+/// Readiness of the aggregator, which only depends on its background tasks,
+/// database and Bitcoin RPC.
+pub(crate) async fn aggregator_readiness(
+    background_tasks: &BackgroundTaskManager,
+    db: &Database,
+    rpc: &ExtendedBitcoinRpc,
+) -> Readiness {
+    Readiness::from_checks(vec![
+        check_tasks(background_tasks.get_stopped_tasks().await),
+        check_database(db).await,
+        check_bitcoind(get_rpc_tip_height(rpc).await.ok()),
+    ])
+}
+
+fn json_response(status: StatusCode, body: &impl Serialize) -> Response<Full<Bytes>> {
+    let body = serde_json::to_vec(body).unwrap_or_default();
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+async fn handle_request<P: ReadinessProvider>(
+    req: Request<Incoming>,
+    provider: Weak<P>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let response = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/healthz") => {
+            json_response(StatusCode::OK, &serde_json::json!({ "status": "ok" }))
+        }
+        (&Method::GET, "/readyz") => {
+            let readiness = match provider.upgrade() {
+                Some(provider) => {
+                    match tokio::time::timeout(READINESS_TIMEOUT, provider.readiness()).await {
+                        Ok(readiness) => readiness,
+                        Err(_) => Readiness::from_checks(vec![HealthCheck::fail(
+                            "readiness",
+                            "Readiness checks timed out",
+                        )]),
+                    }
+                }
+                None => Readiness::from_checks(vec![HealthCheck::fail(
+                    "server",
+                    "Server is shutting down",
+                )]),
+            };
+            let status = if readiness.ready {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            };
+            json_response(status, &readiness)
+        }
+        _ => json_response(
+            StatusCode::NOT_FOUND,
+            &serde_json::json!({ "error": "not found" }),
+        ),
+    };
+
+    Ok(response)
+}
+
+/// Starts serving the health endpoints for `provider` and returns the bound
+/// address.
+///
+/// Only a weak reference to the provider is held, so the server does not keep
+/// the entity (and its background tasks) alive after its gRPC server is
+/// dropped. The listener stops accepting connections once that happens.
+pub async fn spawn_health_server<P: ReadinessProvider>(
+    config: &HealthConfig,
+    provider: Weak<P>,
+) -> Result<SocketAddr, BridgeError> {
+    let addr: SocketAddr = format!("{}:{}", config.host, config.port)
+        .parse()
+        .wrap_err("Failed to parse health server address")?;
+    let listener = TcpListener::bind(addr)
+        .await
+        .wrap_err(format!("Failed to bind health server to {addr}"))?;
+    let local_addr = listener
+        .local_addr()
+        .wrap_err("Failed to get health server address")?;
+
+    tracing::info!("Starting health server at {}", local_addr);
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    tracing::warn!("Health server failed to accept connection: {e}");
+                    continue;
+                }
+            };
+
+            if provider.strong_count() == 0 {
+                tracing::info!("Health server at {} shutting down", local_addr);
+                break;
+            }
+
+            let provider = provider.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |req| handle_request(req, provider.clone()));
+                if let Err(e) = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    tracing::debug!("Health server connection error: {e}");
+                }
+            });
+        }
+    });
+
+    Ok(local_addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn sync_status() -> SyncStatus {
+        SyncStatus {
+            wallet_balance: Some(Amount::from_sat(50_000)),
+            rpc_tip_height: Some(110),
+            btc_syncer_synced_height: Some(108),
+            hcp_last_proven_height: None,
+            tx_sender_synced_height: None,
+            finalized_synced_height: None,
+            state_manager_next_height: None,
+            bitcoin_fee_rate_sat_vb: None,
+            lcp_synced_height: None,
+            citrea_l2_block_height: Some(7),
+        }
+    }
+
+    #[test]
+    fn readiness_checks() {
+        let status = sync_status();
+        assert!(check_sync_lag(&status, 2).healthy);
+        assert!(!check_sync_lag(&status, 1).healthy);
+        assert!(check_wallet_balance(&status, Amount::from_sat(50_000)).healthy);
+        assert!(!check_wallet_balance(&status, Amount::from_sat(50_001)).healthy);
+        assert!(check_citrea(&status).healthy);
+        assert!(!check_bitcoind(None).healthy);
+
+        let missing = SyncStatus {
+            btc_syncer_synced_height: None,
+            wallet_balance: None,
+            ..status
+        };
+        assert!(!check_sync_lag(&missing, 100).healthy);
+        assert!(!check_wallet_balance(&missing, Amount::ZERO).healthy);
+
+        assert!(check_tasks(Ok(StoppedTasks::default())).healthy);
+        let stopped = check_tasks(Ok(StoppedTasks {
+            stopped_tasks: vec!["BitcoinSyncer: Cancelled".to_string()],
+        }));
+        assert!(!stopped.healthy);
+        assert!(stopped.detail.contains("BitcoinSyncer"));
+
+        let readiness =
+            Readiness::from_checks(vec![HealthCheck::pass("a", ""), HealthCheck::fail("b", "")]);
+        assert!(!readiness.ready);
+        assert!(Readiness::from_checks(vec![HealthCheck::pass("a", "")]).ready);
+    }
+
+    struct FixedReadiness(Readiness);
+
+    #[async_trait]
+    impl ReadinessProvider for FixedReadiness {
+        async fn readiness(&self) -> Readiness {
+            self.0.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn health_endpoints() {
+        let provider = Arc::new(FixedReadiness(Readiness::from_checks(vec![
+            HealthCheck::pass("database", "Database is reachable"),
+            HealthCheck::fail("sync_lag", "Bitcoin syncer is 10 blocks behind tip"),
+        ])));
+        let config = HealthConfig {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            ..Default::default()
+        };
+        let addr = spawn_health_server(&config, Arc::downgrade(&provider))
+            .await
+            .unwrap();
+
+        let client = reqwest::Client::new();
+
+        let healthz = client
+            .get(format!("http://{addr}/healthz"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(healthz.status(), reqwest::StatusCode::OK);
+
+        let readyz = client
+            .get(format!("http://{addr}/readyz"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(readyz.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        let body: serde_json::Value = readyz.json().await.unwrap();
+        assert_eq!(body["ready"], false);
+        assert_eq!(body["checks"][1]["name"], "sync_lag");
+        assert_eq!(body["checks"][1]["healthy"], false);
+
+        let missing = client
+            .get(format!("http://{addr}/metrics"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+}
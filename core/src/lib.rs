@@ -72,6 +72,8 @@
 //!
 //! - The [`crate::header_chain_prover`] module accepts Bitcoin block headers
 //!   and prepares proofs for them.
+//! - The [`crate::proof_store`] module stores the produced and fetched proof
+//!   receipts by content address.
 //!
 //! ### Communicating with the Outside
 //!
@@ -112,6 +114,7 @@ pub mod actor;
 pub mod aggregator;
 pub mod bitcoin_syncer;
 pub mod bitvm_client;
+pub mod bridge_circuit_input;
 pub mod builder;
 pub mod citrea;
 pub mod cli;
@@ -124,11 +127,16 @@ pub mod encryption;
 pub mod errors;
 pub mod extended_bitcoin_rpc;
 pub mod header_chain_prover;
+pub mod health;
+pub mod membership;
 pub mod metrics;
 pub mod musig2;
 pub mod operator;
+pub mod proof_store;
+pub mod proving_queue;
 pub mod rpc;
 pub mod servers;
+pub mod spv_proof;
 pub mod task;
 pub mod utils;
 pub mod verifier;
@@ -14,10 +14,12 @@ use clementine_core::{
     cli::{self, get_cli_args, get_config, Command},
     database::Database,
     extended_bitcoin_rpc::ExtendedBitcoinRpc,
+    proving_queue::{default_worker_name, ProverWorker},
     servers::{
         create_aggregator_grpc_server, create_operator_grpc_server, create_verifier_grpc_server,
     },
-    utils::{initialize_logger, initialize_telemetry},
+    task::{IntoTask, TaskExt},
+    utils::{initialize_logger, initialize_telemetry, shutdown_tracer_provider},
 };
 use std::str::FromStr;
 use tracing::{level_filters::LevelFilter, Level};
@@ -56,6 +58,32 @@ async fn main() {
         }
     }
 
+    // Prover workers only need the database of the node they prove for.
+    if matches!(args.command, Command::ProverWorker) {
+        tracing::info!("Running schema script...");
+        Database::run_schema_script(&config, true)
+            .await
+            .expect("Can't run schema script");
+
+        let db = Database::new(&config)
+            .await
+            .expect("Database connection failed");
+        let worker = ProverWorker::new(db, default_worker_name())
+            .await
+            .expect("Can't create prover worker");
+
+        tracing::info!("Starting prover worker...");
+        let (worker, _cancel_tx) = worker.into_task().cancelable_loop();
+        worker
+            .into_bg()
+            .await
+            .expect("Prover worker panicked")
+            .expect("Prover worker failed");
+
+        shutdown_tracer_provider();
+        return;
+    }
+
     config
         .check_general_requirements()
         .await
@@ -67,9 +95,12 @@ async fn main() {
         .expect("Failed to load BitVM cache");
 
     tracing::info!("Running schema script...");
-    Database::run_schema_script(&config, matches!(args.command, Command::Verifier))
-        .await
-        .expect("Can't run schema script");
+    Database::run_schema_script(
+        &config,
+        matches!(args.command, Command::Verifier | Command::Operator),
+    )
+    .await
+    .expect("Can't run schema script");
 
     let mut handle = match args.command {
         Command::Verifier => {
@@ -148,8 +179,12 @@ async fn main() {
         Command::GenerateBitvmCache => {
             unreachable!("GenerateBitvmCache should be handled before this point");
         }
+        Command::ProverWorker => {
+            unreachable!("ProverWorker should be handled before this point");
+        }
     };
     println!("Server has started successfully.");
 
     handle.closed().await;
+    shutdown_tracer_provider();
 }
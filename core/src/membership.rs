@@ -0,0 +1,380 @@
+//! # Membership Epochs
+//!
+//! The verifiers and operators in `verifier_endpoints` and `operator_endpoints`
+//! form the initial membership of the aggregator, epoch 0. Verifiers and
+//! operators can later be added or removed through the aggregator's admin
+//! RPCs. Every change starts a new epoch and becomes effective at a Bitcoin
+//! block height that is recorded together with the change.
+//!
+//! A deposit uses the members that are active at the height of the block its
+//! deposit tx is included in, so the N-of-N key of a deposit is the
+//! aggregated key of that epoch's verifiers. Deposit addresses are created
+//! with the members of the next block. Existing deposits are not affected:
+//! [`crate::deposit::DepositData`] contains the verifiers and operators that
+//! signed the deposit, and removed members stay reachable for them.
+//!
+//! The aggregator sends its changes to the verifiers, which check the members
+//! of a deposit against the epoch at the deposit height before signing it.
+//! Verifiers only accept changes appended to the ones they already know, see
+//! [`appended_changes`]. Removing an operator doesn't exempt it from the
+//! verifiers' check that every operator with usable collateral is in the
+//! deposit; the operator has to spend its collateral to leave.
+
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::XOnlyPublicKey;
+use clementine_errors::BridgeError;
+use eyre::Context;
+
+/// Entity type a membership change applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemberKind {
+    Verifier,
+    Operator,
+}
+
+impl MemberKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MemberKind::Verifier => "verifier",
+            MemberKind::Operator => "operator",
+        }
+    }
+}
+
+impl std::str::FromStr for MemberKind {
+    type Err = BridgeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "verifier" => Ok(MemberKind::Verifier),
+            "operator" => Ok(MemberKind::Operator),
+            _ => Err(eyre::eyre!("Unknown member kind: {s}").into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MembershipAction {
+    Add,
+    Remove,
+}
+
+impl MembershipAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MembershipAction::Add => "add",
+            MembershipAction::Remove => "remove",
+        }
+    }
+}
+
+impl std::str::FromStr for MembershipAction {
+    type Err = BridgeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "add" => Ok(MembershipAction::Add),
+            "remove" => Ok(MembershipAction::Remove),
+            _ => Err(eyre::eyre!("Unknown membership action: {s}").into()),
+        }
+    }
+}
+
+/// A recorded change of the verifier or operator set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MembershipChange {
+    /// Epoch started by this change.
+    pub epoch: u64,
+    pub kind: MemberKind,
+    pub action: MembershipAction,
+    /// gRPC endpoint of the member.
+    pub endpoint: String,
+    /// Serialized key of the member, see [`MemberKey`].
+    pub public_key: Vec<u8>,
+    /// First Bitcoin block height the change applies to.
+    pub effective_height: u32,
+}
+
+/// Returns the epoch that is active at `height`.
+pub fn epoch_at(changes: &[MembershipChange], height: u32) -> u64 {
+    changes
+        .iter()
+        .filter(|change| change.effective_height <= height)
+        .map(|change| change.epoch)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Checks that a new change can be recorded. Changes can't affect the current
+/// block and must be effective in the order they are recorded, so that epochs
+/// are ordered by height.
+pub fn check_effective_height(
+    changes: &[MembershipChange],
+    current_height: u32,
+    effective_height: u32,
+) -> Result<(), BridgeError> {
+    if effective_height <= current_height {
+        return Err(eyre::eyre!(
+            "Membership change must be effective after the current height {current_height}, got {effective_height}"
+        )
+        .into());
+    }
+    if let Some(last) = changes.iter().map(|c| c.effective_height).max() {
+        if effective_height < last {
+            return Err(eyre::eyre!(
+                "Membership change must be effective at or after the last change at height {last}, got {effective_height}"
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `changes` only appends to the `stored` history: every stored
+/// change is unchanged and the new ones have increasing epochs and are
+/// effective in order. Returns the new changes.
+pub fn appended_changes<'a>(
+    stored: &[MembershipChange],
+    changes: &'a [MembershipChange],
+) -> Result<&'a [MembershipChange], BridgeError> {
+    for (idx, stored_change) in stored.iter().enumerate() {
+        if changes.get(idx) != Some(stored_change) {
+            return Err(eyre::eyre!(
+                "Membership change of epoch {} differs from the stored one, changes can only be appended",
+                stored_change.epoch
+            )
+            .into());
+        }
+    }
+
+    let new_changes = &changes[stored.len()..];
+    let mut last = stored.last();
+    for change in new_changes {
+        if let Some(last) = last {
+            if change.epoch <= last.epoch || change.effective_height < last.effective_height {
+                return Err(eyre::eyre!(
+                    "Membership change of epoch {} at height {} doesn't follow epoch {} at height {}",
+                    change.epoch,
+                    change.effective_height,
+                    last.epoch,
+                    last.effective_height
+                )
+                .into());
+            }
+        }
+        last = Some(change);
+    }
+
+    Ok(new_changes)
+}
+
+/// Heights at which a member joins and leaves the set. Members from the config
+/// have no `added_at`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemberSchedule {
+    pub added_at: Option<u32>,
+    pub removed_at: Option<u32>,
+}
+
+impl MemberSchedule {
+    /// Returns true if the member takes part in deposits created at `height`.
+    pub fn is_active(&self, height: u32) -> bool {
+        self.added_at.is_none_or(|added_at| height >= added_at)
+            && self.removed_at.is_none_or(|removed_at| height < removed_at)
+    }
+
+    /// Returns true if the member takes part in deposits created at `height`
+    /// or is added at a later height.
+    pub fn is_active_or_upcoming(&self, height: u32) -> bool {
+        self.removed_at.is_none_or(|removed_at| height < removed_at)
+    }
+}
+
+/// Returns the schedule of the member of `kind` with `public_key`, see
+/// [`MemberKey::to_member_bytes`]. Members without changes are from the
+/// config and always active.
+pub fn member_schedule(
+    changes: &[MembershipChange],
+    kind: MemberKind,
+    public_key: &[u8],
+) -> MemberSchedule {
+    let mut schedule = MemberSchedule::default();
+    for change in changes
+        .iter()
+        .filter(|change| change.kind == kind && change.public_key == public_key)
+    {
+        match change.action {
+            MembershipAction::Add => {
+                schedule = MemberSchedule {
+                    added_at: Some(change.effective_height),
+                    removed_at: None,
+                }
+            }
+            MembershipAction::Remove => schedule.removed_at = Some(change.effective_height),
+        }
+    }
+    schedule
+}
+
+/// Key that identifies a member in membership changes.
+pub trait MemberKey: Sized {
+    fn to_member_bytes(&self) -> Vec<u8>;
+    fn from_member_bytes(bytes: &[u8]) -> Result<Self, BridgeError>;
+}
+
+impl MemberKey for PublicKey {
+    fn to_member_bytes(&self) -> Vec<u8> {
+        self.serialize().to_vec()
+    }
+
+    fn from_member_bytes(bytes: &[u8]) -> Result<Self, BridgeError> {
+        Ok(PublicKey::from_slice(bytes).wrap_err("Invalid verifier public key")?)
+    }
+}
+
+impl MemberKey for XOnlyPublicKey {
+    fn to_member_bytes(&self) -> Vec<u8> {
+        self.serialize().to_vec()
+    }
+
+    fn from_member_bytes(bytes: &[u8]) -> Result<Self, BridgeError> {
+        Ok(XOnlyPublicKey::from_slice(bytes).wrap_err("Invalid operator x-only public key")?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(epoch: u64, effective_height: u32) -> MembershipChange {
+        MembershipChange {
+            epoch,
+            kind: MemberKind::Verifier,
+            action: MembershipAction::Add,
+            endpoint: "https://127.0.0.1:17001".to_string(),
+            public_key: vec![],
+            effective_height,
+        }
+    }
+
+    #[test]
+    fn schedule_is_active() {
+        let config_member = MemberSchedule::default();
+        assert!(config_member.is_active(0));
+
+        let added = MemberSchedule {
+            added_at: Some(100),
+            removed_at: None,
+        };
+        assert!(!added.is_active(99));
+        assert!(added.is_active(100));
+
+        let removed = MemberSchedule {
+            added_at: Some(100),
+            removed_at: Some(200),
+        };
+        assert!(removed.is_active(199));
+        assert!(!removed.is_active(200));
+    }
+
+    #[test]
+    fn schedules_from_changes() {
+        let key = vec![1; 33];
+        let other_key = vec![2; 33];
+        let change = |epoch, action, public_key: &Vec<u8>, effective_height| MembershipChange {
+            epoch,
+            kind: MemberKind::Verifier,
+            action,
+            endpoint: "https://127.0.0.1:17001".to_string(),
+            public_key: public_key.clone(),
+            effective_height,
+        };
+        let changes = vec![
+            change(1, MembershipAction::Add, &key, 100),
+            change(2, MembershipAction::Remove, &other_key, 120),
+            change(3, MembershipAction::Remove, &key, 150),
+            change(4, MembershipAction::Add, &key, 200),
+        ];
+
+        let schedule = member_schedule(&changes, MemberKind::Verifier, &key);
+        assert_eq!(
+            schedule,
+            MemberSchedule {
+                added_at: Some(200),
+                removed_at: None,
+            }
+        );
+        assert!(!schedule.is_active(199));
+        assert!(schedule.is_active_or_upcoming(199));
+
+        let other = member_schedule(&changes, MemberKind::Verifier, &other_key);
+        assert!(other.is_active(119));
+        assert!(!other.is_active(120));
+        assert!(!other.is_active_or_upcoming(120));
+
+        // Keys of other kinds and config members are not affected.
+        assert_eq!(
+            member_schedule(&changes, MemberKind::Operator, &key),
+            MemberSchedule::default()
+        );
+        assert_eq!(
+            member_schedule(&changes, MemberKind::Verifier, &[3; 33]),
+            MemberSchedule::default()
+        );
+    }
+
+    #[test]
+    fn epochs_by_height() {
+        let changes = vec![change(1, 100), change(2, 100), change(3, 150)];
+        assert_eq!(epoch_at(&changes, 99), 0);
+        assert_eq!(epoch_at(&changes, 100), 2);
+        assert_eq!(epoch_at(&changes, 149), 2);
+        assert_eq!(epoch_at(&changes, 1000), 3);
+    }
+
+    #[test]
+    fn effective_height_checks() {
+        let changes = vec![change(1, 100)];
+        assert!(check_effective_height(&changes, 90, 90).is_err());
+        assert!(check_effective_height(&changes, 90, 99).is_err());
+        assert!(check_effective_height(&changes, 90, 100).is_ok());
+        assert!(check_effective_height(&[], 90, 91).is_ok());
+    }
+
+    #[test]
+    fn only_appended_changes_are_accepted() {
+        let stored = vec![change(1, 100), change(2, 100)];
+
+        assert_eq!(appended_changes(&[], &stored).unwrap(), &stored[..]);
+        assert!(appended_changes(&stored, &stored).unwrap().is_empty());
+
+        let appended = vec![change(1, 100), change(2, 100), change(4, 150)];
+        assert_eq!(
+            appended_changes(&stored, &appended).unwrap(),
+            &appended[2..]
+        );
+
+        // Stored changes can't be removed or modified.
+        assert!(appended_changes(&stored, &stored[..1]).is_err());
+        assert!(appended_changes(&stored, &[change(1, 100), change(2, 101)]).is_err());
+        assert!(appended_changes(&stored, &[change(1, 100), change(3, 100)]).is_err());
+
+        // New changes must follow the stored ones.
+        assert!(
+            appended_changes(&stored, &[change(1, 100), change(2, 100), change(2, 150)]).is_err()
+        );
+        assert!(
+            appended_changes(&stored, &[change(1, 100), change(2, 100), change(3, 99)]).is_err()
+        );
+    }
+
+    #[test]
+    fn parse_kinds_and_actions() {
+        for kind in [MemberKind::Verifier, MemberKind::Operator] {
+            assert_eq!(kind.as_str().parse::<MemberKind>().unwrap(), kind);
+        }
+        for action in [MembershipAction::Add, MembershipAction::Remove] {
+            assert_eq!(action.as_str().parse::<MembershipAction>().unwrap(), action);
+        }
+    }
+}
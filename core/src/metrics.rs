@@ -4,12 +4,18 @@
 //! disprove, L2 state sync, etc.)
 //! SyncStatus tracks the latest processed block heights for each of these tasks.
 //!
-use std::{sync::LazyLock, time::Duration};
+//! It also defines the latency metrics of the deposit signing pipeline of the
+//! aggregator and verifiers.
+//!
+use std::{
+    sync::{LazyLock, Once},
+    time::{Duration, Instant},
+};
 
 use bitcoin::Amount;
 use bitcoincore_rpc::RpcApi;
 use eyre::Context;
-use metrics::Gauge;
+use metrics::{Counter, Gauge, Histogram, Label};
 use tokio::time::error::Elapsed;
 use tonic::async_trait;
 
@@ -48,6 +54,10 @@ pub struct SyncStatusMetrics {
     pub lcp_synced_height: Gauge,
     #[metric(describe = "The current Citrea L2 block height")]
     pub citrea_l2_block_height: Gauge,
+    #[metric(
+        describe = "The difference in blocks between the highest and lowest tip reported by the Bitcoin RPC backends"
+    )]
+    pub bitcoin_backend_tip_divergence: Gauge,
 }
 
 #[derive(Metrics)]
@@ -100,6 +110,139 @@ pub static ENTITY_SYNC_STATUS: LazyLock<SyncStatusMetrics> = LazyLock::new(|| {
     SyncStatusMetrics::default()
 });
 
+#[derive(Metrics, Clone)]
+#[metrics(scope = "deposit_signing")]
+/// Latency and failures of a phase of the aggregator deposit flow, labeled by
+/// `phase`. The `deposit` phase covers signing a whole deposit, so its count
+/// is the deposit throughput.
+pub struct DepositPhaseMetrics {
+    #[metric(describe = "The time a phase of the deposit flow took in seconds")]
+    pub duration_seconds: Histogram,
+    #[metric(describe = "The number of times a phase of the deposit flow failed")]
+    pub failures: Counter,
+}
+
+impl DepositPhaseMetrics {
+    pub fn for_phase(phase: &'static str) -> Self {
+        describe_deposit_metrics();
+        Self::new_with_labels(vec![Label::new("phase", phase)])
+    }
+
+    /// Starts timing the phase.
+    pub fn start(&self) -> PhaseTimer {
+        PhaseTimer::start(self.duration_seconds.clone(), self.failures.clone())
+    }
+}
+
+#[derive(Metrics, Clone)]
+#[metrics(scope = "deposit_signing_participant")]
+/// Response times of a verifier or operator taking part in a deposit, as seen
+/// by the aggregator. Labeled by `entity_id` and `phase`, so slow or failing
+/// participants can be told apart.
+pub struct DepositParticipantMetrics {
+    #[metric(describe = "The time a participant took to respond in a deposit phase in seconds")]
+    pub response_duration_seconds: Histogram,
+    #[metric(describe = "The number of failed responses of a participant in a deposit phase")]
+    pub failures: Counter,
+}
+
+impl DepositParticipantMetrics {
+    pub fn for_participant(entity_id: impl std::fmt::Display, phase: &'static str) -> Self {
+        describe_deposit_metrics();
+        Self::new_with_labels(vec![
+            Label::new("entity_id", entity_id.to_string()),
+            Label::new("phase", phase),
+        ])
+    }
+
+    /// Starts timing the participant's response.
+    pub fn start(&self) -> PhaseTimer {
+        PhaseTimer::start(
+            self.response_duration_seconds.clone(),
+            self.failures.clone(),
+        )
+    }
+}
+
+#[derive(Metrics, Clone)]
+#[metrics(scope = "verifier_deposit")]
+/// Latency and throughput of the verifier `deposit_sign` and
+/// `deposit_finalize` RPCs, labeled by `entity_id` and `rpc`.
+pub struct VerifierDepositMetrics {
+    #[metric(describe = "The time a deposit signing RPC of the verifier took in seconds")]
+    pub duration_seconds: Histogram,
+    #[metric(describe = "The number of failed deposit signing RPCs of the verifier")]
+    pub failures: Counter,
+    #[metric(describe = "The number of signatures the verifier created or verified")]
+    pub signatures: Counter,
+}
+
+impl VerifierDepositMetrics {
+    pub fn for_rpc(entity_id: impl std::fmt::Display, rpc: &'static str) -> Self {
+        describe_deposit_metrics();
+        Self::new_with_labels(vec![
+            Label::new("entity_id", entity_id.to_string()),
+            Label::new("rpc", rpc),
+        ])
+    }
+
+    /// Starts timing the RPC.
+    pub fn start(&self) -> PhaseTimer {
+        PhaseTimer::start(self.duration_seconds.clone(), self.failures.clone())
+    }
+}
+
+fn describe_deposit_metrics() {
+    static DESCRIBE: Once = Once::new();
+    DESCRIBE.call_once(|| {
+        DepositPhaseMetrics::describe();
+        DepositParticipantMetrics::describe();
+        VerifierDepositMetrics::describe();
+    });
+}
+
+/// Records the duration of a deposit phase when dropped. The phase counts as
+/// failed unless [`PhaseTimer::success`] was called, so early returns with
+/// `?` are recorded as failures.
+pub struct PhaseTimer {
+    duration: Histogram,
+    failures: Counter,
+    start: Instant,
+    succeeded: bool,
+}
+
+impl PhaseTimer {
+    fn start(duration: Histogram, failures: Counter) -> Self {
+        Self {
+            duration,
+            failures,
+            start: Instant::now(),
+            succeeded: false,
+        }
+    }
+
+    /// Records the phase as successful.
+    pub fn success(mut self) {
+        self.succeeded = true;
+    }
+
+    /// Records the phase as successful if `result` is `Ok`.
+    pub fn finish<T, E>(self, result: &Result<T, E>) {
+        if result.is_ok() {
+            self.success();
+        }
+    }
+}
+
+impl Drop for PhaseTimer {
+    fn drop(&mut self) {
+        self.duration.record(self.start.elapsed().as_secs_f64());
+        if !self.succeeded {
+            self.failures.increment(1);
+        }
+    }
+}
+
 /// A struct containing the current sync status of the entity.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SyncStatus {
@@ -400,6 +543,9 @@ impl<T: NamedEntity> SyncStatusProvider for T {
 #[cfg(test)]
 mod tests {
     use bitcoincore_rpc::RpcApi;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    use super::DepositPhaseMetrics;
 
     #[cfg(not(feature = "automation"))]
     use crate::rpc::clementine::EntityType;
@@ -412,6 +558,31 @@ mod tests {
     };
     use std::time::Duration;
 
+    #[test]
+    fn test_phase_timer_records_failures() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            let phase = DepositPhaseMetrics::for_phase("test");
+            phase.start().success();
+            // dropped without success, as after an early return
+            drop(phase.start());
+        });
+
+        let snapshot = snapshotter.snapshot().into_vec();
+        let value_of = |suffix: &str| {
+            snapshot
+                .iter()
+                .find(|(key, _, _, _)| key.key().name().ends_with(suffix))
+                .map(|(_, _, _, value)| value.clone())
+        };
+        assert_eq!(value_of("failures"), Some(DebugValue::Counter(1)));
+        match value_of("duration_seconds") {
+            Some(DebugValue::Histogram(durations)) => assert_eq!(durations.len(), 2),
+            other => panic!("Expected a histogram, got {other:?}"),
+        }
+    }
+
     #[tokio::test]
     async fn test_get_sync_status_should_not_fail() {
         let mut config = create_test_config_with_thread_name().await;
@@ -24,6 +24,7 @@ use crate::extended_bitcoin_rpc::ExtendedBitcoinRpc;
 use clementine_errors::BridgeError;
 use clementine_primitives::TransactionType;
 
+use crate::health::{entity_readiness, Readiness, ReadinessProvider};
 use crate::metrics::SyncStatusProvider;
 use crate::rpc::clementine::{EntityStatus, NormalSignatureKind, StoppedTasks};
 use crate::task::entity_metric_publisher::{
@@ -55,6 +56,7 @@ use {
     crate::{
         builder::script::extract_winternitz_commits,
         header_chain_prover::HeaderChainProver,
+        proving_queue::{default_worker_name, BridgeCircuitProof, ProverWorker},
         states::StateManager,
         task::IntoTask,
         tx_sender::{ActivatedWithOutpoint, ActivatedWithTxid, TxSenderClient},
@@ -63,12 +65,7 @@ use {
     bitcoin::Witness,
     bitvm::chunk::api::generate_assertions,
     bridge_circuit_host::{
-        bridge_circuit_host::{
-            create_spv, prove_bridge_circuit, MAINNET_BRIDGE_CIRCUIT_ELF,
-            REGTEST_BRIDGE_CIRCUIT_ELF, REGTEST_BRIDGE_CIRCUIT_ELF_TEST, SIGNET_BRIDGE_CIRCUIT_ELF,
-            SIGNET_BRIDGE_CIRCUIT_ELF_TEST, TESTNET4_BRIDGE_CIRCUIT_ELF,
-            TESTNET4_BRIDGE_CIRCUIT_ELF_TEST,
-        },
+        bridge_circuit_host::create_spv,
         structs::{BridgeCircuitHostParams, WatchtowerContext},
     },
     circuits_lib::bridge_circuit::structs::LightClientProof,
@@ -144,6 +141,14 @@ where
                     .ensure_task_looping(state_manager.into_task())
                     .await;
             }
+
+            if !self.operator.config.external_prover_workers {
+                self.background_tasks
+                    .ensure_task_looping(
+                        ProverWorker::new(self.operator.db.clone(), default_worker_name()).await?,
+                    )
+                    .await;
+            }
         }
 
         // run payout checker task
@@ -220,6 +225,23 @@ where
     }
 }
 
+#[tonic::async_trait]
+impl<C> ReadinessProvider for OperatorServer<C>
+where
+    C: CitreaClientT,
+{
+    async fn readiness(&self) -> Readiness {
+        entity_readiness::<Operator<C>, C>(
+            &self.background_tasks,
+            &self.operator.db,
+            &self.operator.rpc,
+            &self.operator.config,
+            &self.operator.citrea_client,
+        )
+        .await
+    }
+}
+
 impl<C> Operator<C>
 where
     C: CitreaClientT,
@@ -229,13 +251,7 @@ where
         let signer = Actor::new(config.secret_key, config.protocol_paramset().network);
 
         let db = Database::new(&config).await?;
-        let rpc = ExtendedBitcoinRpc::connect(
-            config.bitcoin_rpc_url.clone(),
-            config.bitcoin_rpc_user.clone(),
-            config.bitcoin_rpc_password.clone(),
-            None,
-        )
-        .await?;
+        let rpc = config.connect_bitcoin_rpc().await?;
 
         #[cfg(feature = "automation")]
         let tx_sender =
@@ -339,14 +355,7 @@ where
         )
         .await?;
         dbtx.commit().await?;
-        let citrea_client = C::new(
-            config.citrea_rpc_url.clone(),
-            config.citrea_light_client_prover_url.clone(),
-            config.citrea_chain_id,
-            None,
-            config.citrea_request_timeout,
-        )
-        .await?;
+        let citrea_client = C::from_config(&config).await?;
 
         tracing::info!(
             "Operator xonly pk: {:?}, db created with name: {:?}",
@@ -1503,37 +1512,6 @@ where
         )
         .wrap_err("Failed to create bridge circuit host params in send_asserts")?;
 
-        let bridge_circuit_elf = match self.config.protocol_paramset().network {
-            bitcoin::Network::Bitcoin => MAINNET_BRIDGE_CIRCUIT_ELF,
-            bitcoin::Network::Testnet4 => {
-                if is_dev_mode() {
-                    TESTNET4_BRIDGE_CIRCUIT_ELF_TEST
-                } else {
-                    TESTNET4_BRIDGE_CIRCUIT_ELF
-                }
-            }
-            bitcoin::Network::Signet => {
-                if is_dev_mode() {
-                    SIGNET_BRIDGE_CIRCUIT_ELF_TEST
-                } else {
-                    SIGNET_BRIDGE_CIRCUIT_ELF
-                }
-            }
-            bitcoin::Network::Regtest => {
-                if is_dev_mode() {
-                    REGTEST_BRIDGE_CIRCUIT_ELF_TEST
-                } else {
-                    REGTEST_BRIDGE_CIRCUIT_ELF
-                }
-            }
-            _ => {
-                return Err(eyre::eyre!(
-                    "Unsupported network {:?} in send_asserts",
-                    self.config.protocol_paramset().network
-                )
-                .into())
-            }
-        };
         tracing::info!("Starting proving bridge circuit to send asserts");
 
         #[cfg(test)]
@@ -1541,12 +1519,17 @@ where
             .test_params
             .maybe_dump_bridge_circuit_params_to_file(&bridge_circuit_host_params)?;
 
-        let (g16_proof, g16_output, public_inputs) = tokio::task::spawn_blocking(move || {
-            prove_bridge_circuit(bridge_circuit_host_params, bridge_circuit_elf)
-        })
-        .await
-        .wrap_err("Failed to join the prove_bridge_circuit task")?
-        .wrap_err("Failed to prove bridge circuit")?;
+        let BridgeCircuitProof {
+            proof: g16_proof,
+            output: g16_output,
+            public_inputs,
+            ..
+        } = self
+            .header_chain_prover
+            .proving_queue()
+            .prove_bridge_circuit(bridge_circuit_host_params)
+            .await
+            .wrap_err("Failed to prove bridge circuit")?;
 
         tracing::info!("Proved bridge circuit in send_asserts");
         let public_input_scalar = ark_bn254::Fr::from_be_bytes_mod_order(&g16_output);
@@ -0,0 +1,181 @@
+//! Proof store backed by a directory. Every receipt is stored in two files
+//! named after its ID: `<id>.receipt` holds the borsh-serialized receipt, the
+//! same format `header_chain_proof_path` expects, and `<id>.json` holds its
+//! entry. A receipt is only listed once its entry is written.
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use clementine_errors::BridgeError;
+use eyre::Context;
+use risc0_zkvm::Receipt;
+
+use super::{method_id, ProofEntry, ProofId, ProofKind, ProofMetadata, ProofStore};
+
+#[derive(Debug, Clone)]
+pub struct FsProofStore {
+    dir: PathBuf,
+}
+
+impl FsProofStore {
+    /// Creates a store in `dir`, creating the directory if it doesn't exist.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, BridgeError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .wrap_err_with(|| format!("Failed to create proof store directory {dir:?}"))?;
+
+        Ok(Self { dir })
+    }
+
+    fn receipt_path(&self, id: &ProofId) -> PathBuf {
+        self.dir.join(format!("{id}.receipt"))
+    }
+
+    fn entry_path(&self, id: &ProofId) -> PathBuf {
+        self.dir.join(format!("{id}.json"))
+    }
+
+    async fn read_entry(path: &Path) -> Result<ProofEntry, BridgeError> {
+        let entry = tokio::fs::read(path)
+            .await
+            .wrap_err_with(|| format!("Failed to read proof entry {path:?}"))?;
+
+        Ok(serde_json::from_slice(&entry)
+            .wrap_err_with(|| format!("Failed to parse proof entry {path:?}"))?)
+    }
+}
+
+#[async_trait]
+impl ProofStore for FsProofStore {
+    async fn put(
+        &self,
+        receipt: &Receipt,
+        metadata: ProofMetadata,
+    ) -> Result<ProofId, BridgeError> {
+        let id = ProofId::of(receipt)?;
+        let entry_path = self.entry_path(&id);
+        if tokio::fs::try_exists(&entry_path)
+            .await
+            .wrap_err_with(|| format!("Failed to check proof entry {entry_path:?}"))?
+        {
+            return Ok(id);
+        }
+
+        let entry = ProofEntry {
+            method_id: method_id(receipt)?,
+            metadata,
+        };
+        let receipt_path = self.receipt_path(&id);
+        tokio::fs::write(
+            &receipt_path,
+            borsh::to_vec(receipt).wrap_err(BridgeError::BorshError)?,
+        )
+        .await
+        .wrap_err_with(|| format!("Failed to write receipt {receipt_path:?}"))?;
+        tokio::fs::write(
+            &entry_path,
+            serde_json::to_vec_pretty(&entry).wrap_err("Failed to serialize proof entry")?,
+        )
+        .await
+        .wrap_err_with(|| format!("Failed to write proof entry {entry_path:?}"))?;
+
+        Ok(id)
+    }
+
+    async fn get(&self, id: &ProofId) -> Result<Option<(ProofEntry, Receipt)>, BridgeError> {
+        let entry_path = self.entry_path(id);
+        if !tokio::fs::try_exists(&entry_path)
+            .await
+            .wrap_err_with(|| format!("Failed to check proof entry {entry_path:?}"))?
+        {
+            return Ok(None);
+        }
+        let entry = Self::read_entry(&entry_path).await?;
+
+        let receipt_path = self.receipt_path(id);
+        let receipt = tokio::fs::read(&receipt_path)
+            .await
+            .wrap_err_with(|| format!("Failed to read receipt {receipt_path:?}"))?;
+        let receipt: Receipt = borsh::from_slice(&receipt)
+            .wrap_err_with(|| format!("Failed to deserialize receipt {receipt_path:?}"))?;
+
+        Ok(Some((entry, receipt)))
+    }
+
+    async fn list(
+        &self,
+        kind: Option<ProofKind>,
+    ) -> Result<Vec<(ProofId, ProofEntry)>, BridgeError> {
+        let mut dir = tokio::fs::read_dir(&self.dir)
+            .await
+            .wrap_err_with(|| format!("Failed to read proof store directory {:?}", self.dir))?;
+
+        let mut entries = Vec::new();
+        while let Some(file) = dir
+            .next_entry()
+            .await
+            .wrap_err_with(|| format!("Failed to read proof store directory {:?}", self.dir))?
+        {
+            let path = file.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            // Skip files that aren't named after a proof ID.
+            let Some(Ok(id)) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .map(ProofId::from_str)
+            else {
+                continue;
+            };
+
+            let entry = Self::read_entry(&path).await?;
+            if kind.is_none_or(|kind| kind == entry.metadata.kind) {
+                entries.push((id, entry));
+            }
+        }
+        entries.sort_by_key(|(id, entry)| (entry.metadata.created_at, *id));
+
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshDeserialize;
+
+    #[tokio::test]
+    async fn put_get_and_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FsProofStore::new(dir.path()).unwrap();
+
+        let receipt = Receipt::try_from_slice(include_bytes!("../test/data/first_1.bin")).unwrap();
+        let metadata = ProofMetadata::new(
+            ProofKind::HeaderChain,
+            bitcoin::Network::Regtest,
+            Some((0, 1)),
+        );
+
+        let id = store.put(&receipt, metadata.clone()).await.unwrap();
+        assert_eq!(id, ProofId::of(&receipt).unwrap());
+        // The receipt file can be used as a header chain proof assumption.
+        let assumption: Receipt =
+            borsh::from_slice(&std::fs::read(store.receipt_path(&id)).unwrap()).unwrap();
+        assert_eq!(assumption.journal, receipt.journal);
+
+        let (entry, read_receipt) = store.get(&id).await.unwrap().unwrap();
+        assert_eq!(entry.metadata, metadata);
+        assert_eq!(entry.method_id, method_id(&receipt).unwrap());
+        assert_eq!(read_receipt.journal, receipt.journal);
+
+        assert_eq!(store.list(None).await.unwrap(), vec![(id, entry)]);
+        assert!(store
+            .list(Some(ProofKind::WorkOnly))
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(store.get(&ProofId([0; 32])).await.unwrap().is_none());
+    }
+}
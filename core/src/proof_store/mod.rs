@@ -0,0 +1,223 @@
+//! # Proof Store
+//!
+//! Content-addressed storage for the risc0 [`Receipt`]s Clementine produces
+//! and consumes: header chain proofs, work-only proofs, light client proofs
+//! and bridge circuit proofs. A receipt is keyed by the digest of its claim, so the same proof is
+//! stored once no matter how many times it is generated or fetched, and is
+//! stored together with the method ID it was proven with and some metadata to
+//! find it again.
+//!
+//! Nodes keep their receipts in their database with [`PostgresProofStore`].
+//! Tables that need a proof, such as `header_chain_proofs` and
+//! `lcp_for_asserts`, only keep its [`ProofId`] and read the receipt from the
+//! store.
+//! [`FsProofStore`] keeps receipts in a directory and is used to move them
+//! between nodes with the `proof-store export` and `proof-store import`
+//! commands of the CLI.
+
+use std::fmt::{self, Debug, Display};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use bitcoin::Network;
+use clementine_errors::BridgeError;
+use eyre::Context;
+use risc0_zkvm::sha::Digestible;
+use risc0_zkvm::Receipt;
+use serde::{Deserialize, Serialize};
+
+mod fs;
+mod postgres;
+
+pub use fs::FsProofStore;
+pub use postgres::PostgresProofStore;
+
+/// Kind of a stored proof, which tells the circuit that produced it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofKind {
+    /// Header chain circuit proof.
+    HeaderChain,
+    /// Work-only circuit proof, committed in watchtower challenges.
+    WorkOnly,
+    /// Citrea light client proof.
+    LightClient,
+    /// Succinct bridge circuit proof, before it is wrapped to a BitVM Groth16
+    /// proof.
+    BridgeCircuit,
+}
+
+impl ProofKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProofKind::HeaderChain => "header_chain",
+            ProofKind::WorkOnly => "work_only",
+            ProofKind::LightClient => "light_client",
+            ProofKind::BridgeCircuit => "bridge_circuit",
+        }
+    }
+}
+
+impl Display for ProofKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ProofKind {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "header_chain" => Ok(ProofKind::HeaderChain),
+            "work_only" => Ok(ProofKind::WorkOnly),
+            "light_client" => Ok(ProofKind::LightClient),
+            "bridge_circuit" => Ok(ProofKind::BridgeCircuit),
+            _ => Err(eyre::eyre!("Unknown proof kind {s}")),
+        }
+    }
+}
+
+/// Content address of a receipt: the digest of its claim.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProofId(pub [u8; 32]);
+
+impl ProofId {
+    /// Computes the content address of `receipt`.
+    pub fn of(receipt: &Receipt) -> Result<Self, BridgeError> {
+        let claim = receipt
+            .claim()
+            .map_err(|e| eyre::eyre!(e))
+            .wrap_err("Failed to get receipt claim")?;
+
+        Ok(Self(claim.digest().into()))
+    }
+}
+
+impl Display for ProofId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.0))
+    }
+}
+
+impl FromStr for ProofId {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(s).wrap_err("Proof ID is not valid hex")?;
+
+        Ok(Self(bytes.try_into().map_err(|bytes: Vec<u8>| {
+            eyre::eyre!("Proof ID must be 32 bytes, got {}", bytes.len())
+        })?))
+    }
+}
+
+/// Returns the method (image) ID `receipt` claims to be proven with.
+pub fn method_id(receipt: &Receipt) -> Result<[u8; 32], BridgeError> {
+    let claim = receipt
+        .claim()
+        .map_err(|e| eyre::eyre!(e))
+        .wrap_err("Failed to get receipt claim")?;
+    let claim = claim
+        .as_value()
+        .map_err(|e| eyre::eyre!(e))
+        .wrap_err("Receipt claim is pruned")?;
+
+    Ok(claim.pre.digest().into())
+}
+
+/// Metadata stored with a receipt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofMetadata {
+    pub kind: ProofKind,
+    pub network: Network,
+    /// First and last Bitcoin block heights the proof is for. For header
+    /// chain proofs, the blocks proven in the last step. For proofs over a
+    /// single chain state, such as work-only and light client proofs, both are
+    /// the height of that state. `None` if not known, as for header chain
+    /// proof assumptions read from a file.
+    pub block_range: Option<(u32, u32)>,
+    /// Unix timestamp in seconds of when the proof was first stored.
+    pub created_at: u64,
+}
+
+impl ProofMetadata {
+    /// Creates the metadata of a proof stored now.
+    pub fn new(kind: ProofKind, network: Network, block_range: Option<(u32, u32)>) -> Self {
+        Self {
+            kind,
+            network,
+            block_range,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// A stored receipt's ID, method ID and metadata.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofEntry {
+    #[serde(with = "hex::serde")]
+    pub method_id: [u8; 32],
+    #[serde(flatten)]
+    pub metadata: ProofMetadata,
+}
+
+#[async_trait]
+pub trait ProofStore: Send + Sync + Debug {
+    /// Stores `receipt` and returns its ID. If the receipt is already stored,
+    /// the stored metadata is kept.
+    async fn put(&self, receipt: &Receipt, metadata: ProofMetadata)
+        -> Result<ProofId, BridgeError>;
+
+    /// Returns the receipt with the given ID and its entry, if stored.
+    async fn get(&self, id: &ProofId) -> Result<Option<(ProofEntry, Receipt)>, BridgeError>;
+
+    /// Lists the IDs and entries of stored receipts, optionally only the ones
+    /// of the given kind, oldest first.
+    async fn list(
+        &self,
+        kind: Option<ProofKind>,
+    ) -> Result<Vec<(ProofId, ProofEntry)>, BridgeError>;
+}
+
+/// Copies the receipts in `from`, optionally only the ones of the given kind
+/// or with the given IDs, to `to`. Every receipt is checked against its ID and
+/// verified against its method ID before it is copied. Returns the IDs of the
+/// copied receipts.
+pub async fn copy_proofs(
+    from: &dyn ProofStore,
+    to: &dyn ProofStore,
+    kind: Option<ProofKind>,
+    ids: &[ProofId],
+) -> Result<Vec<ProofId>, BridgeError> {
+    let mut copied = Vec::new();
+    for (id, _) in from.list(kind).await? {
+        if !ids.is_empty() && !ids.contains(&id) {
+            continue;
+        }
+        let (entry, receipt) = from
+            .get(&id)
+            .await?
+            .ok_or_else(|| eyre::eyre!("Proof {id} was removed while copying"))?;
+
+        if ProofId::of(&receipt)? != id {
+            return Err(eyre::eyre!("Proof {id} doesn't match its claim digest").into());
+        }
+        receipt
+            .verify(entry.method_id)
+            .map_err(|e| eyre::eyre!(e))
+            .wrap_err(format!(
+                "Proof {id} failed verification against method ID {}",
+                hex::encode(entry.method_id)
+            ))?;
+
+        to.put(&receipt, entry.metadata).await?;
+        copied.push(id);
+    }
+
+    Ok(copied)
+}
@@ -0,0 +1,84 @@
+//! Proof store backed by the `proof_artifacts` table of a node's database.
+
+use async_trait::async_trait;
+use clementine_errors::BridgeError;
+use risc0_zkvm::Receipt;
+
+use super::{method_id, ProofEntry, ProofId, ProofKind, ProofMetadata, ProofStore};
+use crate::database::{Database, DatabaseTransaction};
+
+#[derive(Debug, Clone)]
+pub struct PostgresProofStore {
+    db: Database,
+}
+
+impl PostgresProofStore {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Same as [`ProofStore::put`], but in the given database transaction.
+    pub async fn put_with_tx(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        receipt: &Receipt,
+        metadata: ProofMetadata,
+    ) -> Result<ProofId, BridgeError> {
+        let id = ProofId::of(receipt)?;
+        let entry = ProofEntry {
+            method_id: method_id(receipt)?,
+            metadata,
+        };
+        self.db
+            .insert_proof_artifact(tx, id, &entry, receipt)
+            .await?;
+
+        Ok(id)
+    }
+
+    /// Same as [`ProofStore::get`], but in the given database transaction.
+    pub async fn get_with_tx(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        id: &ProofId,
+    ) -> Result<Option<(ProofEntry, Receipt)>, BridgeError> {
+        self.db.get_proof_artifact(tx, *id).await
+    }
+
+    /// Returns the receipt with the given ID, which a table of the node
+    /// references and so must be stored.
+    pub async fn get_referenced(
+        &self,
+        tx: Option<DatabaseTransaction<'_>>,
+        id: &ProofId,
+    ) -> Result<Receipt, BridgeError> {
+        let (_, receipt) = self
+            .get_with_tx(tx, id)
+            .await?
+            .ok_or_else(|| eyre::eyre!("Referenced proof {id} is not in the proof store"))?;
+
+        Ok(receipt)
+    }
+}
+
+#[async_trait]
+impl ProofStore for PostgresProofStore {
+    async fn put(
+        &self,
+        receipt: &Receipt,
+        metadata: ProofMetadata,
+    ) -> Result<ProofId, BridgeError> {
+        self.put_with_tx(None, receipt, metadata).await
+    }
+
+    async fn get(&self, id: &ProofId) -> Result<Option<(ProofEntry, Receipt)>, BridgeError> {
+        self.get_with_tx(None, id).await
+    }
+
+    async fn list(
+        &self,
+        kind: Option<ProofKind>,
+    ) -> Result<Vec<(ProofId, ProofEntry)>, BridgeError> {
+        self.db.list_proof_artifacts(None, kind).await
+    }
+}
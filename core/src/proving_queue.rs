@@ -0,0 +1,756 @@
+//! # Proving Job Queue
+//!
+//! Proving work-only and bridge circuit proofs takes minutes to hours, so it
+//! is done by prover workers instead of the task that needs the proof.
+//! [`ProvingQueue`] submits a [`ProvingJob`] by saving its input to the
+//! `proving_jobs` table and sending its ID to the [`PROVING_QUEUE_NAME`] pgmq
+//! queue, then waits for a worker to write the result back.
+//!
+//! Workers ([`ProverWorker`]) read job IDs from the queue. While a worker
+//! proves a job, it keeps the job's message invisible to other workers. If the
+//! worker stops, the message becomes visible again and another worker retries
+//! the job. Failed attempts are retried up to [`MAX_PROVING_JOB_ATTEMPTS`]
+//! times.
+//!
+//! Jobs are identified by the digest of their input, so a node that restarts
+//! while waiting for a proof resumes waiting for the same job when it submits
+//! the same input again. The input of a job is dropped once it is done or
+//! failed, and idle workers delete jobs finished more than
+//! [`FINISHED_PROVING_JOB_RETENTION`] ago.
+//!
+//! Nodes run a worker in their own process unless `external_prover_workers`
+//! is set in their configuration, in which case `clementine prover-worker`
+//! processes connected to the node's database do the proving. Workers must be
+//! run with the same `RISC0_DEV_MODE` setting as the node.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use ark_bn254::Bn254;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::Network;
+use borsh::{BorshDeserialize, BorshSerialize};
+use bridge_circuit_host::bridge_circuit_host::{
+    prove_bridge_circuit, MAINNET_BRIDGE_CIRCUIT_ELF, REGTEST_BRIDGE_CIRCUIT_ELF,
+    REGTEST_BRIDGE_CIRCUIT_ELF_TEST, SIGNET_BRIDGE_CIRCUIT_ELF, SIGNET_BRIDGE_CIRCUIT_ELF_TEST,
+    TESTNET4_BRIDGE_CIRCUIT_ELF, TESTNET4_BRIDGE_CIRCUIT_ELF_TEST,
+};
+use bridge_circuit_host::structs::{
+    BridgeCircuitBitvmInputs, BridgeCircuitHostParams, CircuitNetwork,
+};
+use bridge_circuit_host::utils::is_dev_mode;
+use clementine_errors::BridgeError;
+use eyre::{Context, OptionExt};
+use pgmq::{Message, PGMQueueExt};
+use risc0_zkvm::Receipt;
+use serde::{Deserialize, Serialize};
+use tonic::async_trait;
+
+use crate::database::Database;
+use crate::header_chain_prover::HeaderChainProver;
+use crate::proof_store::{PostgresProofStore, ProofKind, ProofMetadata, ProofStore};
+use crate::task::{
+    BufferedErrors, IntoTask, RecoverableTask, Task, TaskExt, TaskVariant, WithDelay,
+};
+
+/// Name of the pgmq queue proving job IDs are sent to.
+pub const PROVING_QUEUE_NAME: &str = "proving_jobs";
+
+/// Number of attempts made for a proving job before it is marked as failed.
+pub const MAX_PROVING_JOB_ATTEMPTS: u32 = 3;
+
+/// Time in seconds a job's message is invisible to other workers after a
+/// worker reads it or last extends it.
+const VISIBILITY_TIMEOUT_SECS: i32 = 300;
+
+/// Interval at which a worker extends the visibility timeout of the job it
+/// proves.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Time in seconds before a failed job is retried.
+const RETRY_DELAY_SECS: i32 = if cfg!(test) { 1 } else { 60 };
+
+/// Time a done or failed job, and so the result of a done job, is kept for
+/// its submitter to read.
+pub const FINISHED_PROVING_JOB_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Polling delay of workers for new jobs and of submitters for results.
+const POLL_DELAY: Duration = if cfg!(test) {
+    Duration::from_millis(250)
+} else {
+    Duration::from_secs(5)
+};
+
+/// A proof to generate, with all of its inputs.
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub enum ProvingJob {
+    /// Work-only proof of a header chain proof.
+    WorkOnly {
+        hcp_receipt: Receipt,
+        network: CircuitNetwork,
+    },
+    /// Bridge circuit proof, wrapped to a BitVM Groth16 proof.
+    BridgeCircuit { params: BridgeCircuitHostParams },
+    /// Job that fails while at most `failures` mock job attempts were made in
+    /// the process, and returns `result` after that.
+    #[cfg(test)]
+    Mock { failures: u32, result: Vec<u8> },
+}
+
+/// Number of attempts made for [`ProvingJob::Mock`] jobs in the process.
+#[cfg(test)]
+static MOCK_JOB_ATTEMPTS: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+
+impl ProvingJob {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ProvingJob::WorkOnly { .. } => "work_only",
+            ProvingJob::BridgeCircuit { .. } => "bridge_circuit",
+            #[cfg(test)]
+            ProvingJob::Mock { .. } => "mock",
+        }
+    }
+
+    /// Generates the proof and returns the serialized result: the borsh
+    /// serialized receipt for work-only proofs, and the borsh serialized
+    /// [`BridgeCircuitProof`] for bridge circuit proofs.
+    pub fn prove(self) -> Result<Vec<u8>, BridgeError> {
+        match self {
+            ProvingJob::WorkOnly {
+                hcp_receipt,
+                network,
+            } => {
+                let receipt = HeaderChainProver::prove_work_only_circuit(network.0, hcp_receipt)
+                    .map_err(|e| eyre::eyre!(e))?;
+                Ok(borsh::to_vec(&receipt).wrap_err(BridgeError::BorshError)?)
+            }
+            ProvingJob::BridgeCircuit { params } => {
+                let elf = bridge_circuit_elf(params.network.0)?;
+                let (proof, output, public_inputs, receipt) =
+                    prove_bridge_circuit(params, elf).wrap_err("Failed to prove bridge circuit")?;
+                BridgeCircuitProof {
+                    proof,
+                    output,
+                    public_inputs,
+                    receipt,
+                }
+                .to_bytes()
+            }
+            #[cfg(test)]
+            ProvingJob::Mock { failures, result } => {
+                let attempts =
+                    MOCK_JOB_ATTEMPTS.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                if attempts <= failures {
+                    return Err(eyre::eyre!("Mock job failed at attempt {attempts}").into());
+                }
+                Ok(result)
+            }
+        }
+    }
+}
+
+/// Returns the bridge circuit ELF of the network, the test circuit in dev
+/// mode.
+pub fn bridge_circuit_elf(network: Network) -> Result<&'static [u8], BridgeError> {
+    Ok(match network {
+        Network::Bitcoin => MAINNET_BRIDGE_CIRCUIT_ELF,
+        Network::Testnet4 => {
+            if is_dev_mode() {
+                TESTNET4_BRIDGE_CIRCUIT_ELF_TEST
+            } else {
+                TESTNET4_BRIDGE_CIRCUIT_ELF
+            }
+        }
+        Network::Signet => {
+            if is_dev_mode() {
+                SIGNET_BRIDGE_CIRCUIT_ELF_TEST
+            } else {
+                SIGNET_BRIDGE_CIRCUIT_ELF
+            }
+        }
+        Network::Regtest => {
+            if is_dev_mode() {
+                REGTEST_BRIDGE_CIRCUIT_ELF_TEST
+            } else {
+                REGTEST_BRIDGE_CIRCUIT_ELF
+            }
+        }
+        _ => return Err(eyre::eyre!("Unsupported network {network:?} for bridge circuit").into()),
+    })
+}
+
+/// Result of a bridge circuit proving job.
+#[derive(Debug, Clone)]
+pub struct BridgeCircuitProof {
+    pub proof: ark_groth16::Proof<Bn254>,
+    pub output: [u8; 31],
+    pub public_inputs: BridgeCircuitBitvmInputs,
+    /// Succinct STARK receipt the Groth16 proof wraps.
+    pub receipt: Receipt,
+}
+
+impl BridgeCircuitProof {
+    fn to_bytes(&self) -> Result<Vec<u8>, BridgeError> {
+        let mut proof = Vec::new();
+        self.proof
+            .serialize_compressed(&mut proof)
+            .wrap_err("Failed to serialize bridge circuit proof")?;
+
+        Ok(
+            borsh::to_vec(&(proof, self.output, self.public_inputs, &self.receipt))
+                .wrap_err(BridgeError::BorshError)?,
+        )
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, BridgeError> {
+        let (proof, output, public_inputs, receipt): (
+            Vec<u8>,
+            [u8; 31],
+            BridgeCircuitBitvmInputs,
+            Receipt,
+        ) = borsh::from_slice(bytes).wrap_err(BridgeError::BorshError)?;
+
+        Ok(Self {
+            proof: ark_groth16::Proof::deserialize_compressed(proof.as_slice())
+                .wrap_err("Failed to deserialize bridge circuit proof")?,
+            output,
+            public_inputs,
+            receipt,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProvingJobStatus {
+    /// Waiting for a worker, either for the first time or to be retried.
+    Pending,
+    /// Being proven by a worker.
+    Running,
+    Done,
+    /// Failed [`MAX_PROVING_JOB_ATTEMPTS`] times.
+    Failed,
+}
+
+impl ProvingJobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProvingJobStatus::Pending => "pending",
+            ProvingJobStatus::Running => "running",
+            ProvingJobStatus::Done => "done",
+            ProvingJobStatus::Failed => "failed",
+        }
+    }
+}
+
+impl FromStr for ProvingJobStatus {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(ProvingJobStatus::Pending),
+            "running" => Ok(ProvingJobStatus::Running),
+            "done" => Ok(ProvingJobStatus::Done),
+            "failed" => Ok(ProvingJobStatus::Failed),
+            _ => Err(eyre::eyre!("Unknown proving job status {s}")),
+        }
+    }
+}
+
+/// State of a proving job, without its input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvingJobState {
+    pub kind: String,
+    pub status: ProvingJobStatus,
+    pub attempts: u32,
+    /// Serialized result, see [`ProvingJob::prove`].
+    pub result: Option<Vec<u8>>,
+    /// Error of the last failed attempt.
+    pub error: Option<String>,
+}
+
+/// Message sent to the proving queue for a job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProvingJobMessage {
+    job_id: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProvingQueue {
+    db: Database,
+    queue: PGMQueueExt,
+}
+
+impl ProvingQueue {
+    /// Creates the proving queue if it doesn't exist.
+    pub async fn new(db: Database) -> Result<Self, BridgeError> {
+        let queue = PGMQueueExt::new_with_pool(db.get_pool()).await;
+        queue.create(PROVING_QUEUE_NAME).await.wrap_err_with(|| {
+            format!("Error creating pgmq queue with name {PROVING_QUEUE_NAME}")
+        })?;
+
+        Ok(Self { db, queue })
+    }
+
+    /// Submits a proving job and returns its ID. If a job with the same input
+    /// was already submitted, returns its ID instead, and queues it again if
+    /// it had failed.
+    pub async fn submit(&self, job: &ProvingJob) -> Result<i64, BridgeError> {
+        let input = borsh::to_vec(job).wrap_err(BridgeError::BorshError)?;
+        let input_digest = sha256::Hash::hash(&input).to_byte_array();
+
+        let mut dbtx = self.db.begin_transaction().await?;
+        let job_id = if let Some(job_id) = self
+            .db
+            .insert_proving_job(Some(&mut dbtx), job.kind(), input_digest, &input)
+            .await?
+        {
+            tracing::info!("Submitted {} proving job {job_id}", job.kind());
+            job_id
+        } else {
+            let (job_id, status) = self
+                .db
+                .get_proving_job_by_input_digest(Some(&mut dbtx), input_digest)
+                .await?
+                .ok_or_eyre("Proving job with the same input not found")?;
+            tracing::info!(
+                "Resuming {} proving job {job_id} with status {}",
+                job.kind(),
+                status.as_str()
+            );
+            if status != ProvingJobStatus::Failed {
+                dbtx.commit().await?;
+                return Ok(job_id);
+            }
+            self.db
+                .reset_proving_job(Some(&mut dbtx), job_id, &input)
+                .await?;
+            job_id
+        };
+
+        self.queue
+            .send_with_cxn(
+                PROVING_QUEUE_NAME,
+                &ProvingJobMessage { job_id },
+                &mut *dbtx,
+            )
+            .await
+            .wrap_err("Error sending proving job to queue")?;
+        dbtx.commit().await?;
+
+        Ok(job_id)
+    }
+
+    /// Waits until the proving job with the given ID is done and returns its
+    /// serialized result.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the job failed [`MAX_PROVING_JOB_ATTEMPTS`] times.
+    pub async fn wait(&self, job_id: i64) -> Result<Vec<u8>, BridgeError> {
+        loop {
+            let state = self
+                .db
+                .get_proving_job_state(None, job_id)
+                .await?
+                .ok_or_eyre(format!("Proving job {job_id} not found"))?;
+            match state.status {
+                ProvingJobStatus::Done => {
+                    return Ok(state
+                        .result
+                        .ok_or_eyre(format!("Proving job {job_id} is done without a result"))?)
+                }
+                ProvingJobStatus::Failed => {
+                    return Err(eyre::eyre!(
+                        "{} proving job {job_id} failed after {} attempts: {}",
+                        state.kind,
+                        state.attempts,
+                        state.error.unwrap_or_default()
+                    )
+                    .into())
+                }
+                ProvingJobStatus::Pending | ProvingJobStatus::Running => {
+                    tokio::time::sleep(POLL_DELAY).await
+                }
+            }
+        }
+    }
+
+    /// Submits a work-only proving job for the header chain proof and waits
+    /// for the receipt.
+    pub async fn prove_work_only(
+        &self,
+        hcp_receipt: Receipt,
+        network: Network,
+    ) -> Result<Receipt, BridgeError> {
+        let job_id = self
+            .submit(&ProvingJob::WorkOnly {
+                hcp_receipt,
+                network: CircuitNetwork(network),
+            })
+            .await?;
+        let result = self.wait(job_id).await?;
+
+        Ok(borsh::from_slice(&result).wrap_err(BridgeError::BorshError)?)
+    }
+
+    /// Submits a bridge circuit proving job, waits for the proof and saves its
+    /// STARK receipt to the proof store.
+    pub async fn prove_bridge_circuit(
+        &self,
+        params: BridgeCircuitHostParams,
+    ) -> Result<BridgeCircuitProof, BridgeError> {
+        let network = params.network.0;
+        let block_height = params.block_header_circuit_output.chain_state.block_height;
+        let job_id = self.submit(&ProvingJob::BridgeCircuit { params }).await?;
+        let result = self.wait(job_id).await?;
+        let proof = BridgeCircuitProof::from_bytes(&result)?;
+
+        PostgresProofStore::new(self.db.clone())
+            .put(
+                &proof.receipt,
+                ProofMetadata::new(
+                    ProofKind::BridgeCircuit,
+                    network,
+                    Some((block_height, block_height)),
+                ),
+            )
+            .await?;
+
+        Ok(proof)
+    }
+}
+
+/// A prover worker, proving jobs from the proving queue of a database one at a
+/// time.
+#[derive(Debug)]
+pub struct ProverWorker {
+    db: Database,
+    queue: PGMQueueExt,
+    name: String,
+}
+
+/// Returns a worker name made of the host name, if known, and the process ID.
+pub fn default_worker_name() -> String {
+    match std::env::var("HOSTNAME") {
+        Ok(hostname) => format!("{hostname}-{}", std::process::id()),
+        Err(_) => std::process::id().to_string(),
+    }
+}
+
+impl ProverWorker {
+    /// Creates a worker for the proving queue in the database, creating the
+    /// queue if it doesn't exist. `name` is saved to the jobs the worker takes.
+    pub async fn new(db: Database, name: String) -> Result<Self, BridgeError> {
+        let ProvingQueue { db, queue } = ProvingQueue::new(db).await?;
+
+        Ok(Self { db, queue, name })
+    }
+}
+
+#[async_trait]
+impl Task for ProverWorker {
+    type Output = bool;
+    const VARIANT: TaskVariant = TaskVariant::ProverWorker;
+
+    async fn run_once(&mut self) -> Result<Self::Output, BridgeError> {
+        let mut dbtx = self.db.begin_transaction().await?;
+        let Some(Message {
+            msg_id,
+            message: ProvingJobMessage { job_id },
+            ..
+        }): Option<Message<ProvingJobMessage>> = self
+            .queue
+            .read_with_cxn(PROVING_QUEUE_NAME, VISIBILITY_TIMEOUT_SECS, &mut *dbtx)
+            .await
+            .wrap_err("Reading proving job from queue")?
+        else {
+            dbtx.commit().await?;
+            let deleted = self
+                .db
+                .delete_finished_proving_jobs(None, FINISHED_PROVING_JOB_RETENTION)
+                .await?;
+            if deleted > 0 {
+                tracing::info!("Deleted {deleted} finished proving jobs");
+            }
+            return Ok(false);
+        };
+        dbtx.commit().await?;
+
+        let status = self
+            .db
+            .get_proving_job_state(None, job_id)
+            .await?
+            .map(|state| state.status);
+        if matches!(
+            status,
+            None | Some(ProvingJobStatus::Done | ProvingJobStatus::Failed)
+        ) {
+            let mut dbtx = self.db.begin_transaction().await?;
+            self.queue
+                .archive_with_cxn(PROVING_QUEUE_NAME, msg_id, &mut *dbtx)
+                .await
+                .wrap_err("Archiving proving job message")?;
+            dbtx.commit().await?;
+            return Ok(true);
+        }
+
+        let attempts = self.db.start_proving_job(None, job_id, &self.name).await?;
+        if attempts > MAX_PROVING_JOB_ATTEMPTS {
+            // Workers stopped while proving the job in every attempt.
+            tracing::error!(
+                "Proving job {job_id} was interrupted {MAX_PROVING_JOB_ATTEMPTS} times, giving up"
+            );
+            let mut dbtx = self.db.begin_transaction().await?;
+            self.db
+                .fail_proving_job(
+                    Some(&mut dbtx),
+                    job_id,
+                    "Workers stopped while proving",
+                    false,
+                )
+                .await?;
+            self.queue
+                .archive_with_cxn(PROVING_QUEUE_NAME, msg_id, &mut *dbtx)
+                .await
+                .wrap_err("Archiving proving job message")?;
+            dbtx.commit().await?;
+            return Ok(true);
+        }
+        let input = self
+            .db
+            .get_proving_job_input(None, job_id)
+            .await?
+            .ok_or_eyre(format!("Proving job {job_id} not found"))?;
+        tracing::info!("Proving job {job_id}, attempt {attempts}");
+
+        // Keep the message invisible to other workers while proving.
+        let heartbeat = {
+            let db = self.db.clone();
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(HEARTBEAT_INTERVAL).await;
+                    if let Err(e) = db
+                        .set_pgmq_message_visibility_timeout(
+                            None,
+                            PROVING_QUEUE_NAME,
+                            msg_id,
+                            VISIBILITY_TIMEOUT_SECS,
+                        )
+                        .await
+                    {
+                        tracing::warn!("Failed to extend proving job {job_id} visibility: {e:?}");
+                    }
+                }
+            })
+        };
+        let result = tokio::task::spawn_blocking(move || {
+            let job: ProvingJob = borsh::from_slice(&input).wrap_err(BridgeError::BorshError)?;
+            job.prove()
+        })
+        .await;
+        heartbeat.abort();
+        let result = result
+            .map_err(|e| BridgeError::from(eyre::eyre!("Proving task panicked: {e}")))
+            .and_then(|result| result);
+
+        let mut dbtx = self.db.begin_transaction().await?;
+        match result {
+            Ok(output) => {
+                tracing::info!("Proving job {job_id} is done");
+                self.db
+                    .finish_proving_job(Some(&mut dbtx), job_id, &output)
+                    .await?;
+                self.queue
+                    .archive_with_cxn(PROVING_QUEUE_NAME, msg_id, &mut *dbtx)
+                    .await
+                    .wrap_err("Archiving proving job message")?;
+            }
+            Err(e) => {
+                let retry = attempts < MAX_PROVING_JOB_ATTEMPTS;
+                tracing::error!(
+                    "Proving job {job_id} failed at attempt {attempts}, {}: {e:?}",
+                    if retry { "retrying" } else { "giving up" }
+                );
+                self.db
+                    .fail_proving_job(Some(&mut dbtx), job_id, &format!("{e:?}"), retry)
+                    .await?;
+                if retry {
+                    self.db
+                        .set_pgmq_message_visibility_timeout(
+                            Some(&mut dbtx),
+                            PROVING_QUEUE_NAME,
+                            msg_id,
+                            RETRY_DELAY_SECS,
+                        )
+                        .await?;
+                } else {
+                    self.queue
+                        .archive_with_cxn(PROVING_QUEUE_NAME, msg_id, &mut *dbtx)
+                        .await
+                        .wrap_err("Archiving proving job message")?;
+                }
+            }
+        }
+        dbtx.commit().await?;
+
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl RecoverableTask for ProverWorker {
+    async fn recover_from_error(&mut self, _error: &BridgeError) -> Result<(), BridgeError> {
+        // A job interrupted by an error is retried once its message is
+        // visible again.
+        Ok(())
+    }
+}
+
+impl IntoTask for ProverWorker {
+    type Task = WithDelay<BufferedErrors<ProverWorker>>;
+
+    fn into_task(self) -> Self::Task {
+        self.into_buffered_errors(20, 3, Duration::from_secs(10))
+            .with_delay(POLL_DELAY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::common::*;
+
+    #[test]
+    fn bridge_circuit_proof_round_trip() {
+        let proof = BridgeCircuitProof {
+            proof: ark_groth16::Proof::default(),
+            output: [7; 31],
+            public_inputs: BridgeCircuitBitvmInputs {
+                payout_tx_block_hash: [1; 20],
+                latest_block_hash: [2; 20],
+                challenge_sending_watchtowers: [3; 20],
+                deposit_constant: [4; 32],
+                combined_method_id: [5; 32],
+            },
+            receipt: borsh::from_slice(include_bytes!("test/data/first_1.bin")).unwrap(),
+        };
+
+        let bytes = proof.to_bytes().unwrap();
+        let read_proof = BridgeCircuitProof::from_bytes(&bytes).unwrap();
+        assert_eq!(read_proof.proof, proof.proof);
+        assert_eq!(read_proof.receipt.journal, proof.receipt.journal);
+        assert_eq!(read_proof.to_bytes().unwrap(), bytes);
+    }
+
+    #[tokio::test]
+    async fn submit_resumes_and_resets_jobs() {
+        let config = create_test_config_with_thread_name().await;
+        let db = Database::new(&config).await.unwrap();
+        let queue = ProvingQueue::new(db.clone()).await.unwrap();
+
+        let job = ProvingJob::WorkOnly {
+            hcp_receipt: borsh::from_slice(include_bytes!("test/data/first_1.bin")).unwrap(),
+            network: CircuitNetwork(Network::Regtest),
+        };
+        let job_id = queue.submit(&job).await.unwrap();
+        // Submitting the same input again resumes the same job.
+        assert_eq!(queue.submit(&job).await.unwrap(), job_id);
+
+        db.start_proving_job(None, job_id, "worker").await.unwrap();
+        db.fail_proving_job(None, job_id, "out of memory", false)
+            .await
+            .unwrap();
+        assert!(queue.wait(job_id).await.is_err());
+
+        // A failed job is queued again with no attempts made.
+        assert_eq!(queue.submit(&job).await.unwrap(), job_id);
+        let state = db
+            .get_proving_job_state(None, job_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.status, ProvingJobStatus::Pending);
+        assert_eq!(state.attempts, 0);
+    }
+
+    #[tokio::test]
+    async fn worker_retries_and_requeues_jobs() {
+        let config = create_test_config_with_thread_name().await;
+        let db = Database::new(&config).await.unwrap();
+        let queue = ProvingQueue::new(db.clone()).await.unwrap();
+        let mut worker = ProverWorker::new(db.clone(), "worker".to_string())
+            .await
+            .unwrap();
+
+        // No job to prove.
+        assert!(!worker.run_once().await.unwrap());
+
+        // The first attempt fails and the job is retried after the retry
+        // delay.
+        let attempts = MOCK_JOB_ATTEMPTS.load(std::sync::atomic::Ordering::SeqCst);
+        let job_id = queue
+            .submit(&ProvingJob::Mock {
+                failures: attempts + 1,
+                result: vec![1, 2, 3],
+            })
+            .await
+            .unwrap();
+        assert!(worker.run_once().await.unwrap());
+        let state = db
+            .get_proving_job_state(None, job_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.status, ProvingJobStatus::Pending);
+        assert_eq!(state.attempts, 1);
+        assert!(state.error.is_some());
+        assert!(!worker.run_once().await.unwrap());
+
+        tokio::time::sleep(Duration::from_secs(RETRY_DELAY_SECS as u64 + 1)).await;
+        assert!(worker.run_once().await.unwrap());
+        assert_eq!(queue.wait(job_id).await.unwrap(), vec![1, 2, 3]);
+        let state = db
+            .get_proving_job_state(None, job_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.attempts, 2);
+        assert_eq!(db.get_proving_job_input(None, job_id).await.unwrap(), None);
+        assert!(!worker.run_once().await.unwrap());
+
+        // A job taken by a worker that stopped while proving is proven again
+        // once its message is visible again.
+        let job_id = queue
+            .submit(&ProvingJob::Mock {
+                failures: 0,
+                result: vec![4, 5],
+            })
+            .await
+            .unwrap();
+        let mut dbtx = db.begin_transaction().await.unwrap();
+        let message: Option<Message<ProvingJobMessage>> = queue
+            .queue
+            .read_with_cxn(PROVING_QUEUE_NAME, 1, &mut *dbtx)
+            .await
+            .unwrap();
+        dbtx.commit().await.unwrap();
+        assert_eq!(message.unwrap().message.job_id, job_id);
+        db.start_proving_job(None, job_id, "stopped worker")
+            .await
+            .unwrap();
+        assert!(!worker.run_once().await.unwrap());
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert!(worker.run_once().await.unwrap());
+        assert_eq!(queue.wait(job_id).await.unwrap(), vec![4, 5]);
+        assert_eq!(
+            db.get_proving_job_state(None, job_id)
+                .await
+                .unwrap()
+                .unwrap()
+                .attempts,
+            2
+        );
+    }
+}
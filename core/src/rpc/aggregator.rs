@@ -3,8 +3,8 @@ use super::clementine::{
     DepositParams, Empty, VerifierDepositFinalizeParams,
 };
 use super::clementine::{
-    AggregatorWithdrawResponse, Deposit, EntityStatuses, GetEntityStatusesRequest,
-    OptimisticPayoutParams, RawSignedTx, VergenResponse, VerifierPublicKeys,
+    AggregatorWithdrawResponse, Deposit, EntityStatuses, EntityType, GetEntityStatusesRequest,
+    OptimisticPayoutParams, RawSignedTx, SchnorrSig, VergenResponse, VerifierPublicKeys,
 };
 use crate::aggregator::{
     AggregatorServer, CompatibilityCheckScope, OperatorId, ParticipatingOperators,
@@ -20,13 +20,15 @@ use crate::compatibility::ActorWithConfig;
 use crate::config::BridgeConfig;
 use crate::constants::{
     DEPOSIT_FINALIZATION_TIMEOUT, DEPOSIT_FINALIZE_STREAM_CREATION_TIMEOUT,
-    KEY_DISTRIBUTION_TIMEOUT, NONCE_STREAM_CREATION_TIMEOUT, OPERATOR_SIGS_STREAM_CREATION_TIMEOUT,
-    OPERATOR_SIGS_TIMEOUT, OPTIMISTIC_PAYOUT_TIMEOUT, OVERALL_DEPOSIT_TIMEOUT,
-    PARTIAL_SIG_STREAM_CREATION_TIMEOUT, PIPELINE_COMPLETION_TIMEOUT, SEND_OPERATOR_SIGS_TIMEOUT,
-    SETUP_COMPLETION_TIMEOUT, WITHDRAWAL_TIMEOUT,
+    KEY_DISTRIBUTION_TIMEOUT, MAX_CONCURRENT_BATCH_DEPOSITS, NONCE_STREAM_CREATION_TIMEOUT,
+    OPERATOR_SIGS_STREAM_CREATION_TIMEOUT, OPERATOR_SIGS_TIMEOUT, OPTIMISTIC_PAYOUT_TIMEOUT,
+    OVERALL_DEPOSIT_TIMEOUT, PARTIAL_SIG_STREAM_CREATION_TIMEOUT, PIPELINE_COMPLETION_TIMEOUT,
+    SEND_OPERATOR_SIGS_TIMEOUT, SETUP_COMPLETION_TIMEOUT, WITHDRAWAL_TIMEOUT,
 };
 use crate::deposit::{Actors, DepositData, DepositInfo};
+use crate::metrics::{DepositParticipantMetrics, DepositPhaseMetrics};
 use crate::musig2::AggregateFromPublicKeys;
+use crate::rpc::batch::{SignatureBatcher, SignatureStream};
 use crate::rpc::clementine::{
     operator_withrawal_response, AggregatorWithdrawalInput, CompatibilityParamsRpc,
     EntitiesCompatibilityData, OperatorWithrawalResponse, VerifierDepositSignParams,
@@ -62,7 +64,8 @@ use futures::{
 use secp256k1::musig::{AggregatedNonce, PartialSignature, PublicNonce};
 use std::future::Future;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
-use tonic::{async_trait, Request, Response, Status, Streaming};
+use tonic::{async_trait, Request, Response, Status};
+use tracing::Instrument;
 struct AggNonceQueueItem {
     agg_nonce: AggregatedNonce,
     sighash: TapSighash,
@@ -202,7 +205,7 @@ async fn nonce_aggregator(
 async fn nonce_distributor(
     mut agg_nonce_receiver: Receiver<(AggNonceQueueItem, Vec<PublicNonce>)>,
     partial_sig_streams: Vec<(
-        Streaming<clementine::PartialSig>,
+        SignatureStream<clementine::PartialSig>,
         Sender<clementine::VerifierDepositSignParams>,
     )>,
     partial_sig_sender: Sender<(Vec<(PartialSignature, PublicNonce)>, AggNonceQueueItem)>,
@@ -325,7 +328,6 @@ async fn nonce_distributor(
                         })?;
                     let partial_sig = PartialSignature::from_byte_array(
                         &partial_sig
-                            .partial_sig
                             .as_slice()
                             .try_into()
                             .wrap_err("PartialSignature must be 32 bytes")?,
@@ -498,24 +500,35 @@ async fn signature_distributor(
     >,
     needed_nofn_sigs: usize,
     verifiers_ids: Vec<VerifierId>,
+    verifiers_max_sigs_per_message: Vec<u32>,
 ) -> Result<(), BridgeError> {
     use verifier_deposit_finalize_params::Params;
+    let mut batchers = verifiers_max_sigs_per_message
+        .into_iter()
+        .map(SignatureBatcher::new)
+        .collect::<Vec<_>>();
     let mut sig_count = 0;
     while let Some(queue_item) = final_sig_receiver.recv().await {
         sig_count += 1;
         tracing::trace!("Received signature {} in signature_distributor", sig_count);
-        let final_params = VerifierDepositFinalizeParams {
-            params: Some(Params::SchnorrSig(queue_item.final_sig)),
-        };
 
         try_join_all_combine_errors(
             deposit_finalize_sender
                 .iter()
                 .zip(verifiers_ids.iter())
-                .map(|(tx, id)| {
-                    let final_params = final_params.clone();
+                .zip(batchers.iter_mut())
+                .map(|((tx, id), batcher)| {
+                    let batch = batcher.push::<SchnorrSig>(queue_item.final_sig.clone());
+                    // the last batch is sent even if it is not full
+                    let batch = match batch {
+                        None if sig_count == needed_nofn_sigs => batcher.flush(),
+                        batch => batch,
+                    };
                     async move {
-                        tx.send(final_params).await.wrap_err_with(|| {
+                        let Some(batch) = batch else {
+                            return Ok(());
+                        };
+                        tx.send(batch.into()).await.wrap_err_with(|| {
                             AggregatorError::OutputStreamEndedEarly {
                                 stream_name: format!("Deposit finalize sender for {id}"),
                             }
@@ -704,6 +717,471 @@ where
 }
 
 impl Aggregator {
+    /// Verifiers are checked while choosing the ones that sign a deposit if
+    /// `min_deposit_verifiers` is set, otherwise all of them are checked
+    /// upfront.
+    fn deposit_compatibility_scope(&self) -> CompatibilityCheckScope {
+        if self.config.min_deposit_verifiers.is_some() {
+            CompatibilityCheckScope::OperatorsOnly
+        } else {
+            CompatibilityCheckScope::Both
+        }
+    }
+
+    /// Signs a new deposit with the members that are active at the height of
+    /// the deposit tx and returns the signed move transaction. Verifiers that
+    /// were left out are recorded once the deposit is signed.
+    async fn sign_deposit(&self, deposit: Deposit) -> Result<RawSignedTx, BridgeError> {
+        let deposit_timer = DepositPhaseMetrics::for_phase("deposit").start();
+        let deposit_info: DepositInfo = deposit.try_into()?;
+        tracing::info!(
+            "Parsed new deposit rpc params, deposit info: {:?}",
+            deposit_info
+        );
+
+        let height = self
+            .deposit_membership_height(deposit_info.deposit_outpoint)
+            .await?;
+        let verifier_selection = self.select_deposit_verifiers(&deposit_info, height).await?;
+        let deposit_data = DepositData {
+            deposit: deposit_info.clone(),
+            nofn_xonly_pk: None,
+            actors: Actors {
+                verifiers: verifier_selection.verifiers,
+                watchtowers: vec![],
+                operators: self
+                    .get_operators_active_at(height)
+                    .await?
+                    .into_iter()
+                    .map(|(_, key)| key)
+                    .collect(),
+            },
+            security_council: self.config.security_council.clone(),
+        };
+        tracing::info!(
+            "Created deposit data in new_deposit for deposit info: {:?}, deposit data: {:?}",
+            deposit_info,
+            deposit_data
+        );
+
+        let deposit_params = deposit_data.clone().into();
+
+        // Collect and distribute keys needed keys from operators and watchtowers to verifiers
+        let start = std::time::Instant::now();
+        let key_distribution_timer = DepositPhaseMetrics::for_phase("key_distribution").start();
+        let key_distribution = timed_request(
+            KEY_DISTRIBUTION_TIMEOUT,
+            "Key collection and distribution",
+            self.collect_and_distribute_keys(&deposit_params),
+        )
+        .await;
+        key_distribution_timer.finish(&key_distribution);
+        key_distribution?;
+        tracing::info!("Collected and distributed keys in {:?}", start.elapsed());
+
+        let verifiers = self.get_participating_verifiers(&deposit_data).await?;
+        let verifiers_ids = verifiers.ids();
+        let verifiers_max_sigs_per_message =
+            self.verifiers_max_sigs_per_message(&verifiers_ids).await;
+
+        // Generate nonce streams for all verifiers.
+        let num_required_sigs = self.config.get_num_required_nofn_sigs(&deposit_data);
+        let num_required_nonces = num_required_sigs as u32 + 2; // ask for +2 for the final movetx signature + emergency stop signature, but don't send it on deposit_sign stage
+        let (first_responses, nonce_streams) = create_nonce_streams(
+            verifiers.clone(),
+            num_required_nonces,
+            #[cfg(test)]
+            &self.config,
+        )
+        .await?;
+
+        // Create initial deposit session and send to verifiers
+        let deposit_sign_session = DepositSignSession {
+            deposit_params: Some(deposit_params.clone()),
+            nonce_gen_first_responses: first_responses,
+            max_sigs_per_message: self.config.grpc.max_sigs_per_message,
+        };
+
+        let deposit_sign_param: VerifierDepositSignParams = deposit_sign_session.clone().into();
+
+        #[allow(clippy::unused_enumerate_index)]
+        let partial_sig_streams = timed_try_join_all(
+            PARTIAL_SIG_STREAM_CREATION_TIMEOUT,
+            "Partial signature stream creation",
+            Some(verifiers.ids()),
+            verifiers
+                .clients()
+                .into_iter()
+                .enumerate()
+                .map(|(_idx, verifier_client)| {
+                    let mut verifier_client = verifier_client.clone();
+                    #[cfg(test)]
+                    let config = self.config.clone();
+
+                    let deposit_sign_param = deposit_sign_param.clone();
+
+                    async move {
+                        #[cfg(test)]
+                        config
+                            .test_params
+                            .timeout_params
+                            .hook_timeout_partial_sig_stream_creation_verifier(_idx)
+                            .await;
+
+                        let (tx, rx) = tokio::sync::mpsc::channel(num_required_nonces as usize + 1); // initial param + num_required_nonces nonces
+
+                        let stream = SignatureStream::new(
+                            verifier_client
+                                .deposit_sign(tokio_stream::wrappers::ReceiverStream::new(rx))
+                                .await?
+                                .into_inner(),
+                        );
+
+                        tx.send(deposit_sign_param).await.map_err(|e| {
+                            BridgeError::from(eyre::eyre!(
+                                "Failed to send deposit sign session: {e:?}"
+                            ))
+                        })?;
+
+                        Ok::<_, BridgeError>((stream, tx))
+                    }
+                }),
+        )
+        .await?;
+
+        // Set up deposit finalization streams
+        #[allow(clippy::unused_enumerate_index)]
+        let deposit_finalize_streams = verifiers
+            .clients()
+            .into_iter()
+            .zip(verifiers.ids())
+            .enumerate()
+            .map(|(_idx, (mut verifier, verifier_id))| {
+                let (tx, rx) = tokio::sync::mpsc::channel(num_required_nonces as usize + 1);
+                let receiver_stream = tokio_stream::wrappers::ReceiverStream::new(rx);
+                #[cfg(test)]
+                let config = self.config.clone();
+                // start deposit_finalize with tokio spawn
+                let deposit_finalize_future = tokio::spawn(
+                    async move {
+                        let response_timer = DepositParticipantMetrics::for_participant(
+                            verifier_id,
+                            "deposit_finalize",
+                        )
+                        .start();
+                        #[cfg(test)]
+                        config
+                            .test_params
+                            .timeout_params
+                            .hook_timeout_deposit_finalize_verifier(_idx)
+                            .await;
+
+                        let response = verifier.deposit_finalize(receiver_stream).await;
+                        response_timer.finish(&response);
+                        response
+                    }
+                    // keep the deposit's trace context for the outgoing request
+                    .in_current_span(),
+                );
+
+                Ok::<_, BridgeError>((deposit_finalize_future, tx))
+            })
+            .collect::<Result<Vec<_>, BridgeError>>()?;
+
+        tracing::info!(
+            "Sending deposit finalize streams to verifiers for deposit {:?}",
+            deposit_info
+        );
+
+        let (deposit_finalize_futures, deposit_finalize_sender): (Vec<_>, Vec<_>) =
+            deposit_finalize_streams.into_iter().unzip();
+
+        // Send initial finalization params
+        let deposit_finalize_first_param: VerifierDepositFinalizeParams =
+            deposit_sign_session.clone().into();
+
+        timed_try_join_all(
+            DEPOSIT_FINALIZE_STREAM_CREATION_TIMEOUT,
+            "Deposit finalization initial param send",
+            Some(verifiers.ids()),
+            deposit_finalize_sender.iter().cloned().map(|tx| {
+                let param = deposit_finalize_first_param.clone();
+                async move {
+                    tx.send(param).await.map_err(|e| {
+                        BridgeError::from(eyre::eyre!(
+                            "Failed to send deposit finalize first param: {e:?}"
+                        ))
+                    })
+                }
+            }),
+        )
+        .await?;
+
+        let deposit_blockhash = self
+            .rpc
+            .get_blockhash_of_tx(&deposit_data.get_deposit_outpoint().txid)
+            .await
+            .map_to_status()?;
+
+        let verifiers_public_keys = deposit_data.get_verifiers();
+
+        let needed_nofn_sigs = self.config.get_num_required_nofn_sigs(&deposit_data);
+
+        // Create sighash stream for transaction signing
+        let sighash_stream = Box::pin(create_nofn_sighash_stream(
+            self.db.clone(),
+            self.config.clone(),
+            deposit_data.clone(),
+            deposit_blockhash,
+            false,
+        ));
+
+        // Create channels for pipeline communication
+        let (agg_nonce_sender, agg_nonce_receiver) = channel(num_required_nonces as usize);
+        let (partial_sig_sender, partial_sig_receiver) = channel(num_required_nonces as usize);
+        let (final_sig_sender, final_sig_receiver) = channel(num_required_nonces as usize);
+
+        // Start the nonce aggregation pipe.
+        let nonce_agg_timer = DepositPhaseMetrics::for_phase("nonce_aggregation").start();
+        let nonce_agg_handle = tokio::spawn(
+            nonce_aggregator(
+                nonce_streams,
+                sighash_stream,
+                agg_nonce_sender,
+                needed_nofn_sigs,
+                verifiers_ids.clone(),
+            )
+            .inspect(move |result| nonce_agg_timer.finish(result)),
+        );
+
+        // Start the nonce distribution pipe.
+        let nonce_dist_handle = tokio::spawn(nonce_distributor(
+            agg_nonce_receiver,
+            partial_sig_streams,
+            partial_sig_sender,
+            needed_nofn_sigs,
+            verifiers_ids.clone(),
+        ));
+
+        // Start the signature aggregation pipe.
+        let sig_agg_handle = tokio::spawn(signature_aggregator(
+            partial_sig_receiver,
+            verifiers_public_keys,
+            final_sig_sender,
+            needed_nofn_sigs,
+        ));
+
+        tracing::debug!("Getting signatures from operators");
+        // Get sigs from each operator in background
+        let operators = self.get_participating_operators(&deposit_data).await?;
+
+        let config_clone = self.config.clone();
+        let operator_sigs_fut = tokio::spawn(
+            async move {
+                let operator_sigs_timer =
+                    DepositPhaseMetrics::for_phase("operator_sig_collection").start();
+                let operator_sigs = timed_request(
+                    OPERATOR_SIGS_TIMEOUT,
+                    "Operator signature collection",
+                    async {
+                        Aggregator::collect_operator_sigs(
+                            operators,
+                            config_clone,
+                            deposit_sign_session,
+                        )
+                        .await
+                    },
+                )
+                .await;
+                operator_sigs_timer.finish(&operator_sigs);
+                operator_sigs
+            }
+            .in_current_span(),
+        );
+
+        // Join the nonce aggregation handle to get the movetx agg nonce.
+        let nonce_agg_handle = nonce_agg_handle
+            .map_err(|_| Status::internal("panic when aggregating nonces"))
+            .map(
+                |res| -> Result<
+                    (
+                        (AggregatedNonce, Vec<PublicNonce>),
+                        (AggregatedNonce, Vec<PublicNonce>),
+                    ),
+                    Status,
+                > { res.and_then(|r| r.map_err(Into::into)) },
+            )
+            .shared();
+
+        // Start the deposit finalization pipe.
+        let sig_dist_timer = DepositPhaseMetrics::for_phase("signature_distribution").start();
+        let sig_dist_handle = tokio::spawn(
+            signature_distributor(
+                final_sig_receiver,
+                deposit_finalize_sender.clone(),
+                nonce_agg_handle.clone(),
+                needed_nofn_sigs,
+                verifiers_ids.clone(),
+                verifiers_max_sigs_per_message.clone(),
+            )
+            .inspect(move |result| sig_dist_timer.finish(result)),
+        );
+
+        // Right now we collect all operator sigs then start to send them, we can do it simultaneously in the future
+        // Need to change sig verification ordering in deposit_finalize() in verifiers so that we verify
+        // 1st signature of all operators, then 2nd of all operators etc.
+        let all_op_sigs = operator_sigs_fut.await.map_err(|_| {
+            BridgeError::from(eyre::eyre!("panic when collecting operator signatures"))
+        })??;
+
+        tracing::info!("Got all operator signatures for deposit {:?}", deposit_info);
+
+        // Wait for all pipeline tasks to complete
+        // join_all should be enough here as if one fails other tasks should fail too as they are connected through streams
+        // one should not hang if any other task fails, the others should finish
+        // this is needed because try_join_all can potentially not return the error of the first task that failed, just the one it polled first
+        // that returned an error
+        let task_outputs = timed_request(
+            PIPELINE_COMPLETION_TIMEOUT,
+            "MuSig2 signing pipeline",
+            async move {
+                Ok::<_, BridgeError>(
+                    futures::future::join_all([nonce_dist_handle, sig_agg_handle, sig_dist_handle])
+                        .await,
+                )
+            },
+        )
+        .await?;
+
+        let task_names = [
+            "Nonce distribution",
+            "Signature aggregation",
+            "Signature distribution",
+        ];
+
+        debug_assert_eq!(task_names.len(), task_outputs.len());
+
+        flatten_join_named_results(task_names.into_iter().zip(task_outputs.into_iter()))?;
+        tracing::info!("All deposit_sign related tasks completed for deposit {:?}, now sending operator signatures to verifiers for verification", deposit_info);
+
+        tracing::debug!("Pipeline tasks completed");
+        let verifiers_ids = verifiers.ids();
+
+        // send operators sigs to verifiers after all verifiers have signed
+        let deposit_finalize_futures = timed_request(
+            SEND_OPERATOR_SIGS_TIMEOUT,
+            "Sending operator signatures to verifiers",
+            async {
+                let send_operator_sigs: Vec<_> = deposit_finalize_sender
+                    .iter()
+                    .zip(verifiers_ids.iter())
+                    .zip(verifiers_max_sigs_per_message.iter())
+                    .zip(deposit_finalize_futures.into_iter())
+                    .map(|(((tx, id), max_sigs_per_message), dep_fin_fut)| async {
+                        for one_op_sigs in all_op_sigs.iter() {
+                            // batches don't mix the signatures of different operators
+                            let mut batcher = SignatureBatcher::new(*max_sigs_per_message);
+                            let mut batches = one_op_sigs
+                                .iter()
+                                .filter_map(|sig| {
+                                    batcher.push::<SchnorrSig>(sig.serialize().to_vec())
+                                })
+                                .collect::<Vec<_>>();
+                            batches.extend(batcher.flush::<SchnorrSig>());
+                            for batch in batches {
+                                let deposit_finalize_param: VerifierDepositFinalizeParams =
+                                    batch.into();
+
+                                let send = tx.send(deposit_finalize_param).await;
+                                match send {
+                                    Ok(()) => (),
+                                    Err(e) => {
+                                        // check exact error by awaiting the future
+                                        dep_fin_fut.await.wrap_err(format!("{} deposit finalize tokio task on aggregator returned error", id.clone()))?.wrap_err(format!("{} deposit finalize rpc call returned error", id.clone()))?;
+                                        return Err(BridgeError::from(eyre::eyre!(format!("{} deposit finalize stream sending returned error: {:?}", id.clone(), e))));
+                                    }
+                                }
+                            }
+                        }
+
+                        Ok::<_, BridgeError>(dep_fin_fut)
+                    })
+                    .collect();
+                try_join_all_combine_errors(send_operator_sigs).await
+            },
+        )
+        .await.wrap_err("Failed to send operator signatures to verifiers")?;
+
+        tracing::info!("All operator signatures sent to verifiers for verification, now waiting to collect movetx and emergency stop tx partial signatures from verifiers for deposit {:?}", deposit_info);
+
+        // Collect partial signatures for move transaction
+        let partial_sigs: Vec<(Vec<u8>, Vec<u8>)> = timed_try_join_all(
+            DEPOSIT_FINALIZATION_TIMEOUT,
+            "Deposit finalization",
+            Some(verifiers.ids()),
+            deposit_finalize_futures.into_iter().map(|fut| async move {
+                let inner = fut
+                    .await
+                    .map_err(|_| {
+                        BridgeError::from(eyre::eyre!("panic finishing deposit_finalize"))
+                    })??
+                    .into_inner();
+                Ok((
+                    inner.move_to_vault_partial_sig,
+                    inner.emergency_stop_partial_sig,
+                ))
+            }),
+        )
+        .await?;
+
+        let (move_to_vault_sigs, emergency_stop_sigs): (Vec<Vec<u8>>, Vec<Vec<u8>>) =
+            partial_sigs.into_iter().unzip();
+
+        tracing::info!(
+            "Received move tx and emergency stop tx partial signatures for deposit {:?}",
+            deposit_info
+        );
+
+        // Create the final move transaction and check the signatures
+        let (movetx_agg_nonce, emergency_stop_agg_nonce) = nonce_agg_handle.await?;
+        let move_tx_timer = DepositPhaseMetrics::for_phase("move_tx_creation").start();
+
+        // Verify emergency stop signatures
+        self.verify_and_save_emergency_stop_sigs(
+            emergency_stop_sigs,
+            emergency_stop_agg_nonce,
+            deposit_params.clone(),
+        )
+        .await?;
+
+        let signed_movetx_handler = self
+            .create_movetx(move_to_vault_sigs, movetx_agg_nonce, deposit_params)
+            .await?;
+
+        let raw_signed_tx = RawSignedTx {
+            raw_tx: bitcoin::consensus::serialize(&signed_movetx_handler.get_cached_tx()),
+        };
+        move_tx_timer.success();
+
+        tracing::info!(
+            "Created final move transaction for deposit {:?}",
+            deposit_info
+        );
+
+        if !verifier_selection.excluded.is_empty() {
+            self.db
+                .insert_deposit_excluded_verifiers(
+                    None,
+                    deposit_data.get_deposit_outpoint(),
+                    &verifier_selection.excluded,
+                )
+                .await?;
+        }
+
+        deposit_timer.success();
+        Ok(raw_signed_tx)
+    }
+
     // Extracts pub_nonce from given stream.
     fn extract_pub_nonce(
         response: Option<clementine::nonce_gen_response::Response>,
@@ -751,7 +1229,7 @@ impl Aggregator {
                         .await.wrap_err_with(|| AggregatorError::RequestFailed {
                             request_name: format!("Deposit sign stream for operator {idx}"),
                         })?;
-                    Ok::<_, BridgeError>(stream.into_inner())
+                    Ok::<_, BridgeError>(SignatureStream::new(stream.into_inner()))
                 }
             }))
                 .await?;
@@ -767,26 +1245,34 @@ impl Aggregator {
         let needed_sigs = config.get_num_required_operator_sigs(&deposit_data);
 
         // get signatures from each operator's signature streams
+        let operator_ids = operator_clients.ids();
         let operator_sigs =
             try_join_all_combine_errors(operator_sigs_streams.iter_mut().enumerate().map(
-                |(idx, stream)| async move {
-                    let mut sigs: Vec<Signature> = Vec::with_capacity(needed_sigs);
-                    while let Some(sig) =
-                        stream
-                            .message()
-                            .await
-                            .wrap_err_with(|| AggregatorError::RequestFailed {
+                |(idx, stream)| {
+                    let response_timer = DepositParticipantMetrics::for_participant(
+                        operator_ids[idx],
+                        "operator_sigs",
+                    )
+                    .start();
+                    async move {
+                        let mut sigs: Vec<Signature> = Vec::with_capacity(needed_sigs);
+                        while let Some(sig) = stream.message().await.wrap_err_with(|| {
+                            AggregatorError::RequestFailed {
                                 request_name: format!("Deposit sign stream for operator {idx}"),
-                            })?
-                    {
-                        sigs.push(Signature::from_slice(&sig.schnorr_sig).wrap_err_with(|| {
-                            format!("Failed to parse Schnorr signature from operator {idx}")
-                        })?);
+                            }
+                        })? {
+                            sigs.push(Signature::from_slice(&sig).wrap_err_with(|| {
+                                format!("Failed to parse Schnorr signature from operator {idx}")
+                            })?);
+                            if sigs.len() == needed_sigs {
+                                break;
+                            }
+                        }
                         if sigs.len() == needed_sigs {
-                            break;
+                            response_timer.success();
                         }
+                        Ok::<_, BridgeError>(sigs)
                     }
-                    Ok::<_, BridgeError>(sigs)
                 },
             ))
             .await
@@ -1323,16 +1809,33 @@ impl ClementineAggregator for AggregatorServer {
         const CHANNEL_CAPACITY: usize = 1024 * 16;
         let (operator_params_tx, operator_params_rx) =
             tokio::sync::broadcast::channel(CHANNEL_CAPACITY);
-        let operator_params_rx_handles = (0..self.get_verifier_clients().len())
+        // Members added for a later height are set up too, so that they can
+        // take part in deposits as soon as their change is effective
+        let (verifiers, verifier_pks): (Vec<_>, Vec<_>) =
+            self.get_setup_verifiers().await?.into_iter().unzip();
+        let operator_params_rx_handles = (0..verifiers.len())
             .map(|_| operator_params_rx.resubscribe())
             .collect::<Vec<_>>();
 
-        let operators = self.get_operator_clients().to_vec();
-        let operator_pks = self.fetch_operator_keys().await?;
+        let (operators, operator_pks): (Vec<_>, Vec<_>) =
+            self.get_setup_operators().await?.into_iter().unzip();
         let operator_ids = operator_pks
             .iter()
             .map(|key| OperatorId(*key))
             .collect::<Vec<_>>();
+        let verifier_ids = verifier_pks
+            .iter()
+            .map(|key| VerifierId(*key))
+            .collect::<Vec<_>>();
+        // Operator params are forwarded as they are, so the batches must be
+        // accepted by all verifiers
+        let max_sigs_per_message = self
+            .verifiers_max_sigs_per_message(&verifier_ids)
+            .await
+            .into_iter()
+            .chain([self.config.grpc.max_sigs_per_message])
+            .min()
+            .unwrap_or_default();
         let get_operator_params_chunked_handle = tokio::spawn(async move {
             tracing::info!(clients = operators.len(), "Collecting operator details...");
             try_join_all_combine_errors(operators.iter().zip(operator_ids.iter()).map(
@@ -1341,7 +1844,9 @@ impl ClementineAggregator for AggregatorServer {
                     let tx = operator_params_tx.clone();
                     async move {
                         let stream = operator
-                            .get_params(Request::new(Empty {}))
+                            .get_params(Request::new(clementine::GetOperatorParamsRequest {
+                                max_sigs_per_message,
+                            }))
                             .await
                             .wrap_err_with(|| AggregatorError::RequestFailed {
                                 request_name: format!("Operator get params for {id}"),
@@ -1364,12 +1869,6 @@ impl ClementineAggregator for AggregatorServer {
             Ok::<_, Status>(())
         });
 
-        let verifiers = self.get_verifier_clients().to_vec();
-        let verifier_pks = self.fetch_verifier_keys().await?;
-        let verifier_ids = verifier_pks
-            .iter()
-            .map(|key| VerifierId(*key))
-            .collect::<Vec<_>>();
         let set_operator_params_handle = tokio::spawn(async move {
             tracing::info!("Informing verifiers of existing operators...");
             try_join_all_combine_errors(
@@ -1424,7 +1923,11 @@ impl ClementineAggregator for AggregatorServer {
 
         flatten_join_named_results(task_names.into_iter().zip(task_outputs.into_iter()))?;
 
-        Ok(Response::new(VerifierPublicKeys::from(verifier_pks)))
+        self.sync_membership_changes().await?;
+
+        Ok(Response::new(VerifierPublicKeys::from(
+            self.fetch_verifier_keys().await?,
+        )))
     }
 
     /// Handles a new deposit request from a user. This function coordinates the signing process
@@ -1451,384 +1954,84 @@ impl ClementineAggregator for AggregatorServer {
         request: Request<Deposit>,
     ) -> Result<Response<clementine::RawSignedTx>, Status> {
         tracing::info!("New deposit rpc called");
-        self.check_compatibility_with_actors(CompatibilityCheckScope::Both)
+        self.check_compatibility_with_actors(self.deposit_compatibility_scope())
             .await?;
 
         timed_request(OVERALL_DEPOSIT_TIMEOUT, "Overall new deposit", async {
-            let deposit_info: DepositInfo = request.into_inner().try_into()?;
-            tracing::info!(
-                "Parsed new deposit rpc params, deposit info: {:?}",
-                deposit_info
-            );
-
-            let deposit_data = DepositData {
-                deposit: deposit_info.clone(),
-                nofn_xonly_pk: None,
-                actors: Actors {
-                    verifiers: self.fetch_verifier_keys().await?,
-                    watchtowers: vec![],
-                    operators: self.fetch_operator_keys().await?,
-                },
-                security_council: self.config.security_council.clone(),
-            };
-            tracing::info!(
-                "Created deposit data in new_deposit for deposit info: {:?}, deposit data: {:?}",
-                deposit_info,
-                deposit_data
-            );
+            self.sign_deposit(request.into_inner()).await
+        })
+        .await
+        .map(Response::new)
+        .map_err(Into::into)
+    }
 
-            let deposit_params = deposit_data.clone().into();
+    #[tracing::instrument(skip_all, err(level = tracing::Level::ERROR))]
+    async fn new_deposits(
+        &self,
+        request: Request<clementine::Deposits>,
+    ) -> Result<Response<clementine::NewDepositResults>, Status> {
+        let deposits = request.into_inner().deposits;
+        tracing::info!("New deposits rpc called with {} deposits", deposits.len());
+        if deposits.is_empty() {
+            return Ok(Response::new(clementine::NewDepositResults::default()));
+        }
 
-            // Collect and distribute keys needed keys from operators and watchtowers to verifiers
-            let start = std::time::Instant::now();
-            timed_request(
-                KEY_DISTRIBUTION_TIMEOUT,
-                "Key collection and distribution",
-                self.collect_and_distribute_keys(&deposit_params),
-            )
+        // Checks shared by all deposits of the batch
+        self.check_compatibility_with_actors(self.deposit_compatibility_scope())
             .await?;
-            tracing::info!("Collected and distributed keys in {:?}", start.elapsed());
-
-            let verifiers = self.get_participating_verifiers(&deposit_data).await?;
-            let verifiers_ids = verifiers.ids();
-
-            // Generate nonce streams for all verifiers.
-            let num_required_sigs = self.config.get_num_required_nofn_sigs(&deposit_data);
-            let num_required_nonces = num_required_sigs as u32 + 2; // ask for +2 for the final movetx signature + emergency stop signature, but don't send it on deposit_sign stage
-            let (first_responses, nonce_streams) =
-                    create_nonce_streams(
-                        verifiers.clone(),
-                        num_required_nonces,
-                        #[cfg(test)]
-                        &self.config,
-                    )
-                    .await?;
 
-            // Create initial deposit session and send to verifiers
-            let deposit_sign_session = DepositSignSession {
-                deposit_params: Some(deposit_params.clone()),
-                nonce_gen_first_responses: first_responses,
-            };
+        let results = futures::stream::iter(deposits)
+            .map(|deposit| async move {
+                let deposit_outpoint = deposit.deposit_outpoint.clone();
+                let result = timed_request(
+                    OVERALL_DEPOSIT_TIMEOUT,
+                    "Overall new deposit",
+                    self.sign_deposit(deposit),
+                )
+                .await;
+                let result = match result {
+                    Ok(move_tx) => clementine::new_deposit_result::Result::MoveTx(move_tx),
+                    Err(e) => {
+                        tracing::error!(
+                            "Failed to sign deposit {:?} of the batch: {e:?}",
+                            deposit_outpoint
+                        );
+                        clementine::new_deposit_result::Result::Error(e.to_string())
+                    }
+                };
+                clementine::NewDepositResult {
+                    deposit_outpoint,
+                    result: Some(result),
+                }
+            })
+            .buffered(MAX_CONCURRENT_BATCH_DEPOSITS)
+            .collect::<Vec<_>>()
+            .await;
 
-            let deposit_sign_param: VerifierDepositSignParams =
-                    deposit_sign_session.clone().into();
+        Ok(Response::new(clementine::NewDepositResults { results }))
+    }
 
-        #[allow(clippy::unused_enumerate_index)]
-            let partial_sig_streams = timed_try_join_all(
-                PARTIAL_SIG_STREAM_CREATION_TIMEOUT,
-                "Partial signature stream creation",
-                Some(verifiers.ids()),
-                verifiers.clients().into_iter().enumerate().map(|(_idx, verifier_client)| {
-                    let mut verifier_client = verifier_client.clone();
-                    #[cfg(test)]
-                    let config = self.config.clone();
+    #[tracing::instrument(skip(self), err(level = tracing::Level::ERROR))]
+    async fn withdraw(
+        &self,
+        request: Request<AggregatorWithdrawalInput>,
+    ) -> Result<Response<AggregatorWithdrawResponse>, Status> {
+        tracing::warn!("Withdraw rpc called");
+        let request = request.into_inner();
+        let (withdraw_params_with_sig, operator_xonly_pks) = (
+            request.withdrawal.ok_or(Status::invalid_argument(
+                "withdrawalParamsWithSig is missing",
+            ))?,
+            request.operator_xonly_pks,
+        );
+        // check compatibility with operators only
+        self.check_compatibility_with_actors(CompatibilityCheckScope::OperatorsOnly)
+            .await?;
 
-                    let deposit_sign_param =
-                    deposit_sign_param.clone();
-
-                    async move {
-                        #[cfg(test)]
-                        config
-                            .test_params
-                            .timeout_params
-                            .hook_timeout_partial_sig_stream_creation_verifier(_idx)
-                            .await;
-
-                        let (tx, rx) = tokio::sync::mpsc::channel(num_required_nonces as usize + 1); // initial param + num_required_nonces nonces
-
-                        let stream = verifier_client
-                            .deposit_sign(tokio_stream::wrappers::ReceiverStream::new(rx))
-                            .await?
-                            .into_inner();
-
-                        tx.send(deposit_sign_param).await.map_err(|e| {
-                            BridgeError::from(eyre::eyre!("Failed to send deposit sign session: {e:?}"))})?;
-
-                        Ok::<_, BridgeError>((stream, tx))
-                    }
-                })
-            )
-            .await?;
-
-            // Set up deposit finalization streams
-        #[allow(clippy::unused_enumerate_index)]
-            let deposit_finalize_streams = verifiers.clients().into_iter().enumerate().map(
-                    |(_idx, mut verifier)| {
-                        let (tx, rx) = tokio::sync::mpsc::channel(num_required_nonces as usize + 1);
-                        let receiver_stream = tokio_stream::wrappers::ReceiverStream::new(rx);
-                        #[cfg(test)]
-                        let config = self.config.clone();
-                        // start deposit_finalize with tokio spawn
-                        let deposit_finalize_future = tokio::spawn(async move {
-                            #[cfg(test)]
-                            config
-                                .test_params
-                                .timeout_params
-                                .hook_timeout_deposit_finalize_verifier(_idx)
-                                .await;
-
-                            verifier.deposit_finalize(receiver_stream).await
-                        });
-
-                        Ok::<_, BridgeError>((deposit_finalize_future, tx))
-                    },
-                ).collect::<Result<Vec<_>, BridgeError>>()?;
-
-            tracing::info!("Sending deposit finalize streams to verifiers for deposit {:?}", deposit_info);
-
-            let (deposit_finalize_futures, deposit_finalize_sender): (Vec<_>, Vec<_>) =
-                deposit_finalize_streams.into_iter().unzip();
-
-            // Send initial finalization params
-            let deposit_finalize_first_param: VerifierDepositFinalizeParams =
-                deposit_sign_session.clone().into();
-
-            timed_try_join_all(
-                DEPOSIT_FINALIZE_STREAM_CREATION_TIMEOUT,
-                "Deposit finalization initial param send",
-                Some(verifiers.ids()),
-                deposit_finalize_sender.iter().cloned().map(|tx| {
-                    let param = deposit_finalize_first_param.clone();
-                    async move {
-                        tx.send(param).await
-                        .map_err(|e| {
-                            BridgeError::from(eyre::eyre!(
-                                "Failed to send deposit finalize first param: {e:?}"))
-                        })
-                    }
-                })
-            ).await?;
-
-
-            let deposit_blockhash = self
-                .rpc
-                .get_blockhash_of_tx(&deposit_data.get_deposit_outpoint().txid)
-                .await
-                .map_to_status()?;
-
-            let verifiers_public_keys = deposit_data.get_verifiers();
-
-            let needed_nofn_sigs = self.config.get_num_required_nofn_sigs(&deposit_data);
-
-            // Create sighash stream for transaction signing
-            let sighash_stream = Box::pin(create_nofn_sighash_stream(
-                self.db.clone(),
-                self.config.clone(),
-                deposit_data.clone(),
-                deposit_blockhash,
-                false,
-            ));
-
-            // Create channels for pipeline communication
-            let (agg_nonce_sender, agg_nonce_receiver) = channel(num_required_nonces as usize);
-            let (partial_sig_sender, partial_sig_receiver) = channel(num_required_nonces as usize);
-            let (final_sig_sender, final_sig_receiver) = channel(num_required_nonces as usize);
-
-            // Start the nonce aggregation pipe.
-            let nonce_agg_handle = tokio::spawn(nonce_aggregator(
-                nonce_streams,
-                sighash_stream,
-                agg_nonce_sender,
-                needed_nofn_sigs,
-                verifiers_ids.clone(),
-            ));
-
-            // Start the nonce distribution pipe.
-            let nonce_dist_handle = tokio::spawn(nonce_distributor(
-                agg_nonce_receiver,
-                partial_sig_streams,
-                partial_sig_sender,
-                needed_nofn_sigs,
-                verifiers_ids.clone(),
-            ));
-
-            // Start the signature aggregation pipe.
-            let sig_agg_handle = tokio::spawn(signature_aggregator(
-                partial_sig_receiver,
-                verifiers_public_keys,
-                final_sig_sender,
-                needed_nofn_sigs,
-            ));
-
-            tracing::debug!("Getting signatures from operators");
-            // Get sigs from each operator in background
-            let operators = self.get_participating_operators(&deposit_data).await?;
-
-            let config_clone = self.config.clone();
-            let operator_sigs_fut = tokio::spawn(async move {
-                timed_request(
-                    OPERATOR_SIGS_TIMEOUT,
-                    "Operator signature collection",
-                    async {
-                        Aggregator::collect_operator_sigs(
-                            operators,
-                            config_clone,
-                            deposit_sign_session,
-                        )
-                        .await
-                    },
-                )
-                .await
-            });
-
-            // Join the nonce aggregation handle to get the movetx agg nonce.
-            let nonce_agg_handle = nonce_agg_handle
-                .map_err(|_| Status::internal("panic when aggregating nonces"))
-                .map(
-                    |res| -> Result<((AggregatedNonce, Vec<PublicNonce>), (AggregatedNonce, Vec<PublicNonce>)), Status> {
-                        res.and_then(|r| r.map_err(Into::into))
-                    },
-                )
-                .shared();
-
-            // Start the deposit finalization pipe.
-            let sig_dist_handle = tokio::spawn(signature_distributor(
-                final_sig_receiver,
-                deposit_finalize_sender.clone(),
-                nonce_agg_handle.clone(),
-                needed_nofn_sigs,
-                verifiers_ids.clone(),
-            ));
-
-            // Right now we collect all operator sigs then start to send them, we can do it simultaneously in the future
-            // Need to change sig verification ordering in deposit_finalize() in verifiers so that we verify
-            // 1st signature of all operators, then 2nd of all operators etc.
-            let all_op_sigs = operator_sigs_fut
-                .await
-                .map_err(|_| BridgeError::from(eyre::eyre!("panic when collecting operator signatures")))??;
-
-            tracing::info!("Got all operator signatures for deposit {:?}", deposit_info);
-
-            // Wait for all pipeline tasks to complete
-            // join_all should be enough here as if one fails other tasks should fail too as they are connected through streams
-            // one should not hang if any other task fails, the others should finish
-            // this is needed because try_join_all can potentially not return the error of the first task that failed, just the one it polled first
-            // that returned an error
-            let task_outputs =  timed_request(
-                PIPELINE_COMPLETION_TIMEOUT,
-                "MuSig2 signing pipeline",
-                async move {
-                    Ok::<_, BridgeError>(futures::future::join_all([nonce_dist_handle, sig_agg_handle, sig_dist_handle]).await)
-                },
-            )
-            .await?;
-
-            let task_names = ["Nonce distribution", "Signature aggregation", "Signature distribution"];
-
-            debug_assert_eq!(task_names.len(), task_outputs.len());
-
-            flatten_join_named_results(
-                task_names.into_iter().zip(task_outputs.into_iter()),
-            )?;
-            tracing::info!("All deposit_sign related tasks completed for deposit {:?}, now sending operator signatures to verifiers for verification", deposit_info);
-
-            tracing::debug!("Pipeline tasks completed");
-            let verifiers_ids = verifiers.ids();
-
-            // send operators sigs to verifiers after all verifiers have signed
-            let deposit_finalize_futures = timed_request(
-                SEND_OPERATOR_SIGS_TIMEOUT,
-                "Sending operator signatures to verifiers",
-                async {
-                    let send_operator_sigs: Vec<_> = deposit_finalize_sender
-                        .iter()
-                        .zip(verifiers_ids.iter())
-                        .zip(deposit_finalize_futures.into_iter())
-                        .map(|((tx, id), dep_fin_fut)| async {
-                            for one_op_sigs in all_op_sigs.iter() {
-                                for sig in one_op_sigs.iter() {
-                                    let deposit_finalize_param: VerifierDepositFinalizeParams =
-                                        sig.into();
-
-                                    let send = tx.send(deposit_finalize_param).await;
-                                    match send {
-                                        Ok(()) => (),
-                                        Err(e) => {
-                                            // check exact error by awaiting the future
-                                            dep_fin_fut.await.wrap_err(format!("{} deposit finalize tokio task on aggregator returned error", id.clone()))?.wrap_err(format!("{} deposit finalize rpc call returned error", id.clone()))?;
-                                            return Err(BridgeError::from(eyre::eyre!(format!("{} deposit finalize stream sending returned error: {:?}", id.clone(), e))));
-                                        }
-                                    }
-                                }
-                            }
-
-                            Ok::<_, BridgeError>(dep_fin_fut)
-                        })
-                        .collect();
-                    try_join_all_combine_errors(send_operator_sigs).await
-                },
-            )
-            .await.wrap_err("Failed to send operator signatures to verifiers")?;
-
-            tracing::info!("All operator signatures sent to verifiers for verification, now waiting to collect movetx and emergency stop tx partial signatures from verifiers for deposit {:?}", deposit_info);
-
-            // Collect partial signatures for move transaction
-            let partial_sigs: Vec<(Vec<u8>, Vec<u8>)> = timed_try_join_all(
-                    DEPOSIT_FINALIZATION_TIMEOUT,
-                    "Deposit finalization",
-                    Some(verifiers.ids()),
-                    deposit_finalize_futures.into_iter().map(|fut| async move {
-                        let inner = fut.await
-                            .map_err(|_| BridgeError::from(eyre::eyre!("panic finishing deposit_finalize")))??
-                            .into_inner();
-                        Ok((inner.move_to_vault_partial_sig, inner.emergency_stop_partial_sig))
-                    }),
-                )
-                .await?;
-
-
-            let (move_to_vault_sigs, emergency_stop_sigs): (Vec<Vec<u8>>, Vec<Vec<u8>>) =
-                partial_sigs.into_iter().unzip();
-
-            tracing::info!("Received move tx and emergency stop tx partial signatures for deposit {:?}", deposit_info);
-
-            // Create the final move transaction and check the signatures
-            let (movetx_agg_nonce, emergency_stop_agg_nonce) = nonce_agg_handle.await?;
-
-            // Verify emergency stop signatures
-            self.verify_and_save_emergency_stop_sigs(
-                emergency_stop_sigs,
-                emergency_stop_agg_nonce,
-                deposit_params.clone(),
-            )
-            .await?;
-
-            let signed_movetx_handler = self
-                .create_movetx(move_to_vault_sigs, movetx_agg_nonce, deposit_params)
-                .await?;
-
-            let raw_signed_tx = RawSignedTx {
-                raw_tx: bitcoin::consensus::serialize(&signed_movetx_handler.get_cached_tx()),
-            };
-
-            tracing::info!("Created final move transaction for deposit {:?}", deposit_info);
-
-            Ok(Response::new(raw_signed_tx))
-        })
-        .await.map_err(Into::into)
-    }
-
-    #[tracing::instrument(skip(self), err(level = tracing::Level::ERROR))]
-    async fn withdraw(
-        &self,
-        request: Request<AggregatorWithdrawalInput>,
-    ) -> Result<Response<AggregatorWithdrawResponse>, Status> {
-        tracing::warn!("Withdraw rpc called");
-        let request = request.into_inner();
-        let (withdraw_params_with_sig, operator_xonly_pks) = (
-            request.withdrawal.ok_or(Status::invalid_argument(
-                "withdrawalParamsWithSig is missing",
-            ))?,
-            request.operator_xonly_pks,
-        );
-        // check compatibility with operators only
-        self.check_compatibility_with_actors(CompatibilityCheckScope::OperatorsOnly)
-            .await?;
-
-        let withdraw_params = withdraw_params_with_sig
-            .clone()
-            .withdrawal
-            .ok_or(Status::invalid_argument("withdrawalParams is missing"))?;
+        let withdraw_params = withdraw_params_with_sig
+            .clone()
+            .withdrawal
+            .ok_or(Status::invalid_argument("withdrawalParams is missing"))?;
 
         // convert rpc xonly pks to bitcoin xonly pks
         let operator_xonly_pks_from_rpc: Vec<XOnlyPublicKey> = operator_xonly_pks
@@ -1856,7 +2059,11 @@ impl ClementineAggregator for AggregatorServer {
 
         // check if all given operator xonly pubkeys are a valid operator xonly pubkey, to warn the caller if
         // something is wrong with the given operator xonly pubkeys
-        let current_operator_xonly_pks = self.fetch_operator_keys().await?;
+        let current_operators = self.get_active_operators().await?;
+        let current_operator_xonly_pks = current_operators
+            .iter()
+            .map(|(_, xonly_pk)| *xonly_pk)
+            .collect::<Vec<_>>();
         let invalid_operator_xonly_pks = operator_xonly_pks_from_rpc
             .iter()
             .filter(|xonly_pk| !current_operator_xonly_pks.contains(xonly_pk))
@@ -1867,11 +2074,8 @@ impl ClementineAggregator for AggregatorServer {
             )));
         }
 
-        let operators = self
-            .get_operator_clients()
-            .iter()
-            .zip(current_operator_xonly_pks.into_iter());
-        let withdraw_futures = operators
+        let withdraw_futures = current_operators
+            .into_iter()
             .filter(|(_, xonly_pk)| {
                 // check if operator_xonly_pks is empty or contains the operator's xonly public key
                 operator_xonly_pks_from_rpc.is_empty()
@@ -1961,12 +2165,116 @@ impl ClementineAggregator for AggregatorServer {
 
         let emergency_stop_txs = self.db.get_emergency_stop_txs(None, txids).await?;
 
-        let (txids, encrypted_emergency_stop_txs): (Vec<Txid>, Vec<Vec<u8>>) =
-            emergency_stop_txs.into_iter().unzip();
+        let mut response = clementine::GetEmergencyStopTxResponse::default();
+        for (move_txid, encrypted_emergency_stop_tx) in emergency_stop_txs {
+            let mut deposit_data = self
+                .db
+                .get_deposit_data_with_move_tx(None, move_txid)
+                .await?
+                .ok_or_else(|| {
+                    Status::not_found(format!("Deposit data not found for move txid {move_txid}"))
+                })?;
+
+            response
+                .nofn_xonly_pks
+                .push(deposit_data.get_nofn_xonly_pk()?.into());
+            response
+                .security_councils
+                .push(deposit_data.security_council.clone().into());
+            response.txids.push(move_txid.into());
+            response
+                .encrypted_emergency_stop_txs
+                .push(encrypted_emergency_stop_tx);
+        }
+
+        Ok(Response::new(response))
+    }
+
+    #[tracing::instrument(skip(self), err(level = tracing::Level::ERROR))]
+    async fn add_member(
+        &self,
+        request: Request<clementine::AddMemberRequest>,
+    ) -> Result<Response<clementine::MembershipChange>, Status> {
+        tracing::warn!("Add member rpc called");
+        let request = request.into_inner();
+        let effective_height = (request.effective_height != 0).then_some(request.effective_height);
+
+        let change = match EntityType::try_from(request.kind) {
+            Ok(EntityType::Verifier) => {
+                self.add_verifier(request.endpoint, effective_height)
+                    .await?
+            }
+            Ok(EntityType::Operator) => {
+                self.add_operator(request.endpoint, effective_height)
+                    .await?
+            }
+            _ => {
+                return Err(Status::invalid_argument(
+                    "Only verifiers and operators can be added",
+                ))
+            }
+        };
+
+        // The new member needs the keys and operator params of the others, and
+        // the verifiers need to know about the new epoch before its deposits.
+        self.setup(Request::new(Empty {})).await.map_err(|e| {
+            Status::internal(format!(
+                "Membership change of epoch {} was recorded but setup failed, rerun setup: {}",
+                change.epoch,
+                e.message()
+            ))
+        })?;
+
+        Ok(Response::new(change.into()))
+    }
+
+    #[tracing::instrument(skip(self), err(level = tracing::Level::ERROR))]
+    async fn remove_member(
+        &self,
+        request: Request<clementine::RemoveMemberRequest>,
+    ) -> Result<Response<clementine::MembershipChange>, Status> {
+        tracing::warn!("Remove member rpc called");
+        let request = request.into_inner();
+        let effective_height = (request.effective_height != 0).then_some(request.effective_height);
+
+        let change = match EntityType::try_from(request.kind) {
+            Ok(EntityType::Verifier) => {
+                let public_key = PublicKey::from_slice(&request.public_key).map_err(|e| {
+                    Status::invalid_argument(format!("Invalid verifier public key: {e}"))
+                })?;
+                self.remove_verifier(public_key, effective_height).await?
+            }
+            Ok(EntityType::Operator) => {
+                let xonly_pk = XOnlyPublicKey::from_slice(&request.public_key).map_err(|e| {
+                    Status::invalid_argument(format!("Invalid operator xonly public key: {e}"))
+                })?;
+                self.remove_operator(xonly_pk, effective_height).await?
+            }
+            _ => {
+                return Err(Status::invalid_argument(
+                    "Only verifiers and operators can be removed",
+                ))
+            }
+        };
+
+        self.sync_membership_changes().await.map_err(|e| {
+            Status::internal(format!(
+                "Membership change of epoch {} was recorded but sending it to the verifiers failed, rerun setup: {e}",
+                change.epoch
+            ))
+        })?;
 
-        Ok(Response::new(clementine::GetEmergencyStopTxResponse {
-            txids: txids.into_iter().map(|txid| txid.into()).collect(),
-            encrypted_emergency_stop_txs,
+        Ok(Response::new(change.into()))
+    }
+
+    #[tracing::instrument(skip_all, err(level = tracing::Level::ERROR))]
+    async fn get_membership_changes(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<clementine::MembershipChanges>, Status> {
+        let changes = self.aggregator.get_membership_changes().await?;
+        Ok(Response::new(clementine::MembershipChanges {
+            changes: changes.into_iter().map(Into::into).collect(),
         }))
     }
 
@@ -2105,6 +2413,7 @@ impl ClementineAggregator for AggregatorServer {
 #[cfg(test)]
 mod tests {
     use crate::actor::Actor;
+    use crate::bitvm_client::SECP;
     use crate::builder;
     use crate::config::BridgeConfig;
     use crate::deposit::{BaseDepositData, DepositInfo, DepositType};
@@ -2114,9 +2423,11 @@ mod tests {
     use crate::rpc::get_clients;
     use crate::servers::create_aggregator_unix_server;
     use crate::test::common::citrea::MockCitreaClient;
+    use crate::test::common::test_actors::TestVerifier;
     use crate::test::common::tx_utils::ensure_tx_onchain;
     use crate::test::common::*;
     use bitcoin::hashes::Hash;
+    use bitcoin::secp256k1::SecretKey;
     use bitcoincore_rpc::RpcApi;
     use clementine_primitives::EVMAddress;
     use eyre::Context;
@@ -2319,6 +2630,250 @@ mod tests {
         .unwrap();
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn aggregator_deposit_with_offline_verifier() {
+        let mut config = create_test_config_with_thread_name().await;
+        let num_verifiers = config.test_params.all_verifiers_secret_keys.len();
+        config.min_deposit_verifiers = Some(num_verifiers - 1);
+        let regtest = create_regtest_rpc(&mut config).await;
+        let rpc = regtest.rpc();
+        let mut actors = create_actors::<MockCitreaClient>(&config).await;
+        let mut aggregator = actors.get_aggregator();
+
+        let evm_address = EVMAddress([1u8; 20]);
+        let signer = Actor::new(config.secret_key, config.protocol_paramset().network);
+
+        aggregator
+            .setup(tonic::Request::new(clementine::Empty {}))
+            .await
+            .unwrap();
+
+        // The deposit is signed by the verifiers that are still up
+        let offline_index = num_verifiers - 1;
+        let offline_pk = actors
+            .get_verifier_by_index(offline_index)
+            .unwrap()
+            .secret_key
+            .public_key(&SECP);
+        actors.stop_verifier(offline_index).unwrap();
+        let online_pks = actors
+            .get_verifiers_secret_keys()
+            .iter()
+            .map(|sk| sk.public_key(&SECP))
+            .collect::<Vec<_>>();
+
+        let nofn_xonly_pk = bitcoin::XOnlyPublicKey::from_musig2_pks(online_pks, None).unwrap();
+        let deposit_address = builder::address::generate_deposit_address(
+            nofn_xonly_pk,
+            signer.address.as_unchecked(),
+            evm_address,
+            config.protocol_paramset().network,
+            config.protocol_paramset().user_takes_after,
+        )
+        .unwrap()
+        .0;
+
+        let deposit_outpoint = rpc
+            .send_to_address(&deposit_address, config.protocol_paramset().bridge_amount)
+            .await
+            .unwrap();
+        rpc.mine_blocks(18).await.unwrap();
+
+        let deposit_info = DepositInfo {
+            deposit_outpoint,
+            deposit_type: DepositType::BaseDeposit(BaseDepositData {
+                evm_address,
+                recovery_taproot_address: signer.address.as_unchecked().clone(),
+            }),
+        };
+
+        let raw_move_tx = aggregator
+            .new_deposit(clementine::Deposit::from(deposit_info))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let movetx_txid = aggregator
+            .send_move_to_vault_tx(SendMoveTxRequest {
+                deposit_outpoint: Some(deposit_outpoint.into()),
+                raw_tx: Some(raw_move_tx),
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .try_into()
+            .unwrap();
+
+        poll_until_condition(
+            async || {
+                rpc.mine_blocks(1).await.unwrap();
+                Ok(rpc.is_tx_on_chain(&movetx_txid).await.unwrap_or_default())
+            },
+            None,
+            None,
+        )
+        .await
+        .wrap_err_with(|| eyre::eyre!("MoveTx did not land onchain"))
+        .unwrap();
+
+        let db = crate::database::Database::new(&actors.aggregator.config)
+            .await
+            .unwrap();
+        let excluded = db
+            .get_deposit_excluded_verifiers(None, Some(deposit_outpoint))
+            .await
+            .unwrap();
+        assert_eq!(excluded.len(), 1);
+        assert_eq!(excluded[0].1.public_key, Some(offline_pk));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn aggregator_deposit_to_unreachable_verifier_set_fails_fast() {
+        let mut config = create_test_config_with_thread_name().await;
+        let num_verifiers = config.test_params.all_verifiers_secret_keys.len();
+        config.min_deposit_verifiers = Some(num_verifiers - 1);
+        let regtest = create_regtest_rpc(&mut config).await;
+        let rpc = regtest.rpc();
+        let mut actors = create_actors::<MockCitreaClient>(&config).await;
+        let mut aggregator = actors.get_aggregator();
+
+        let evm_address = EVMAddress([1u8; 20]);
+        let signer = Actor::new(config.secret_key, config.protocol_paramset().network);
+
+        let verifiers_public_keys: Vec<bitcoin::secp256k1::PublicKey> = aggregator
+            .setup(tonic::Request::new(clementine::Empty {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .try_into()
+            .unwrap();
+
+        // The deposit pays to the N-of-N of all verifiers, but one of them is down
+        let nofn_xonly_pk =
+            bitcoin::XOnlyPublicKey::from_musig2_pks(verifiers_public_keys, None).unwrap();
+        let deposit_address = builder::address::generate_deposit_address(
+            nofn_xonly_pk,
+            signer.address.as_unchecked(),
+            evm_address,
+            config.protocol_paramset().network,
+            config.protocol_paramset().user_takes_after,
+        )
+        .unwrap()
+        .0;
+        actors.stop_verifier(num_verifiers - 1).unwrap();
+
+        let deposit_outpoint = rpc
+            .send_to_address(&deposit_address, config.protocol_paramset().bridge_amount)
+            .await
+            .unwrap();
+        rpc.mine_blocks(18).await.unwrap();
+
+        let deposit_info = DepositInfo {
+            deposit_outpoint,
+            deposit_type: DepositType::BaseDeposit(BaseDepositData {
+                evm_address,
+                recovery_taproot_address: signer.address.as_unchecked().clone(),
+            }),
+        };
+
+        let err = aggregator
+            .new_deposit(clementine::Deposit::from(deposit_info))
+            .await
+            .unwrap_err();
+        assert!(
+            err.message().contains("N-of-N key the deposit"),
+            "unexpected error: {err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn aggregator_new_deposits_batch() {
+        let mut config = create_test_config_with_thread_name().await;
+        let regtest = create_regtest_rpc(&mut config).await;
+        let rpc = regtest.rpc();
+        let actors = create_actors::<MockCitreaClient>(&config).await;
+        let mut aggregator = actors.get_aggregator();
+
+        let evm_address = EVMAddress([1u8; 20]);
+        let signer = Actor::new(config.secret_key, config.protocol_paramset().network);
+
+        let verifiers_public_keys: Vec<bitcoin::secp256k1::PublicKey> = aggregator
+            .setup(tonic::Request::new(clementine::Empty {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .try_into()
+            .unwrap();
+        sleep(Duration::from_secs(3)).await;
+
+        let nofn_xonly_pk =
+            bitcoin::XOnlyPublicKey::from_musig2_pks(verifiers_public_keys, None).unwrap();
+        let deposit_address = builder::address::generate_deposit_address(
+            nofn_xonly_pk,
+            signer.address.as_unchecked(),
+            evm_address,
+            config.protocol_paramset().network,
+            config.protocol_paramset().user_takes_after,
+        )
+        .unwrap()
+        .0;
+
+        let mut deposit_outpoints = Vec::new();
+        for _ in 0..2 {
+            deposit_outpoints.push(
+                rpc.send_to_address(&deposit_address, config.protocol_paramset().bridge_amount)
+                    .await
+                    .unwrap(),
+            );
+        }
+        rpc.mine_blocks(18).await.unwrap();
+        // never sent, signing it fails
+        deposit_outpoints.insert(
+            1,
+            bitcoin::OutPoint::new(bitcoin::Txid::from_byte_array([7u8; 32]), 0),
+        );
+
+        let deposits = deposit_outpoints
+            .iter()
+            .map(|deposit_outpoint| {
+                clementine::Deposit::from(DepositInfo {
+                    deposit_outpoint: *deposit_outpoint,
+                    deposit_type: DepositType::BaseDeposit(BaseDepositData {
+                        evm_address,
+                        recovery_taproot_address: signer.address.as_unchecked().clone(),
+                    }),
+                })
+            })
+            .collect();
+
+        let results = aggregator
+            .new_deposits(clementine::Deposits { deposits })
+            .await
+            .unwrap()
+            .into_inner()
+            .results;
+
+        assert_eq!(results.len(), 3);
+        for (result, deposit_outpoint) in results.iter().zip(deposit_outpoints.iter()) {
+            assert_eq!(
+                result.deposit_outpoint,
+                Some(clementine::Outpoint::from(*deposit_outpoint))
+            );
+        }
+        assert!(matches!(
+            results[0].result,
+            Some(clementine::new_deposit_result::Result::MoveTx(_))
+        ));
+        assert!(matches!(
+            results[1].result,
+            Some(clementine::new_deposit_result::Result::Error(_))
+        ));
+        assert!(matches!(
+            results[2].result,
+            Some(clementine::new_deposit_result::Result::MoveTx(_))
+        ));
+    }
+
     #[tokio::test]
     async fn aggregator_two_deposit_movetx_and_emergency_stop() {
         let mut config = create_test_config_with_thread_name().await;
@@ -2603,6 +3158,67 @@ mod tests {
         );
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn aggregator_deposit_after_membership_changes() {
+        let mut config = create_test_config_with_thread_name().await;
+        let regtest = create_regtest_rpc(&mut config).await;
+        let rpc = regtest.rpc();
+        let actors = create_actors::<MockCitreaClient>(&config).await;
+        let mut aggregator = actors.get_aggregator();
+
+        aggregator
+            .setup(Request::new(clementine::Empty {}))
+            .await
+            .unwrap();
+
+        // Start a verifier that isn't known by the aggregator yet
+        let socket_dir = tempfile::tempdir().unwrap();
+        let new_verifier = TestVerifier::<MockCitreaClient>::new(
+            &config,
+            socket_dir.path(),
+            config.test_params.all_verifiers_secret_keys.len(),
+            SecretKey::new(&mut bitcoin::secp256k1::rand::thread_rng()),
+        )
+        .await
+        .unwrap();
+
+        let effective_height = u32::try_from(rpc.get_block_count().await.unwrap()).unwrap() + 2;
+        aggregator
+            .add_member(clementine::AddMemberRequest {
+                kind: clementine::EntityType::Verifier as i32,
+                endpoint: format!("unix://{}", new_verifier.socket_path.display()),
+                effective_height,
+            })
+            .await
+            .unwrap();
+        let removed_operator = *actors.get_operators_xonly_pks().last().unwrap();
+        aggregator
+            .remove_member(clementine::RemoveMemberRequest {
+                kind: clementine::EntityType::Operator as i32,
+                public_key: removed_operator.serialize().to_vec(),
+                effective_height,
+            })
+            .await
+            .unwrap();
+
+        let changes = aggregator
+            .get_membership_changes(Request::new(clementine::Empty {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .changes;
+        assert_eq!(changes.len(), 2);
+
+        rpc.mine_blocks(2).await.unwrap();
+
+        let (_, _, _, verifiers_public_keys) =
+            run_single_deposit::<MockCitreaClient>(&mut config, rpc.clone(), None, &actors, None)
+                .await
+                .unwrap();
+        assert_eq!(verifiers_public_keys.len(), actors.get_num_verifiers() + 1);
+        assert!(verifiers_public_keys.contains(&new_verifier.secret_key.public_key(&SECP)));
+    }
+
     #[tokio::test]
     async fn aggregator_get_entity_statuses() {
         let mut config = create_test_config_with_thread_name().await;
@@ -0,0 +1,210 @@
+//! # Signature Batching
+//!
+//! Deposit signing streams carry tens of thousands of signatures. The receiver
+//! of a signature stream can ask for batches with `max_sigs_per_message` in
+//! [`DepositSignSession`](super::clementine::DepositSignSession) or
+//! [`GetOperatorParamsRequest`](super::clementine::GetOperatorParamsRequest),
+//! and senders that don't know the field keep sending one signature per
+//! message. Streams that the aggregator sends to verifiers, like
+//! `DepositFinalize`, are batched up to the `max_sigs_per_message` a verifier
+//! advertises in its compatibility params. Receivers read both forms, so
+//! mixed-version peers still interoperate.
+
+use super::clementine::{PartialSig, SchnorrSig};
+use crate::constants::MAX_SIGS_PER_MESSAGE;
+use std::collections::VecDeque;
+use tonic::{Status, Streaming};
+
+/// A signing stream message that carries a single signature or a batch.
+pub trait SignatureBatch {
+    /// Creates a message from signatures. A single signature is sent in the
+    /// single signature field.
+    fn from_sigs(sigs: Vec<Vec<u8>>) -> Self;
+
+    /// Returns the signatures of the message.
+    fn into_sigs(self) -> Vec<Vec<u8>>;
+}
+
+impl SignatureBatch for PartialSig {
+    fn from_sigs(mut sigs: Vec<Vec<u8>>) -> Self {
+        if sigs.len() == 1 {
+            PartialSig {
+                partial_sig: sigs.remove(0),
+                partial_sigs: Vec::new(),
+            }
+        } else {
+            PartialSig {
+                partial_sig: Vec::new(),
+                partial_sigs: sigs,
+            }
+        }
+    }
+
+    fn into_sigs(self) -> Vec<Vec<u8>> {
+        if self.partial_sigs.is_empty() {
+            vec![self.partial_sig]
+        } else {
+            self.partial_sigs
+        }
+    }
+}
+
+impl SignatureBatch for SchnorrSig {
+    fn from_sigs(mut sigs: Vec<Vec<u8>>) -> Self {
+        if sigs.len() == 1 {
+            SchnorrSig {
+                schnorr_sig: sigs.remove(0),
+                schnorr_sigs: Vec::new(),
+            }
+        } else {
+            SchnorrSig {
+                schnorr_sig: Vec::new(),
+                schnorr_sigs: sigs,
+            }
+        }
+    }
+
+    fn into_sigs(self) -> Vec<Vec<u8>> {
+        if self.schnorr_sigs.is_empty() {
+            vec![self.schnorr_sig]
+        } else {
+            self.schnorr_sigs
+        }
+    }
+}
+
+/// Packs signatures into messages of at most `max_sigs_per_message`
+/// signatures.
+#[derive(Debug)]
+pub struct SignatureBatcher {
+    max_sigs_per_message: usize,
+    pending: Vec<Vec<u8>>,
+}
+
+impl SignatureBatcher {
+    /// Creates a batcher for the `max_sigs_per_message` the receiver asked
+    /// for, clamped to `1..=`[`MAX_SIGS_PER_MESSAGE`].
+    pub fn new(max_sigs_per_message: u32) -> Self {
+        let max_sigs_per_message = max_sigs_per_message.clamp(1, MAX_SIGS_PER_MESSAGE) as usize;
+        Self {
+            max_sigs_per_message,
+            pending: Vec::with_capacity(max_sigs_per_message),
+        }
+    }
+
+    /// Adds a signature and returns a message once the batch is full.
+    pub fn push<T: SignatureBatch>(&mut self, sig: Vec<u8>) -> Option<T> {
+        self.pending.push(sig);
+        if self.pending.len() >= self.max_sigs_per_message {
+            self.flush()
+        } else {
+            None
+        }
+    }
+
+    /// Returns a message with the pending signatures, if there are any.
+    pub fn flush<T: SignatureBatch>(&mut self) -> Option<T> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let sigs = std::mem::replace(
+            &mut self.pending,
+            Vec::with_capacity(self.max_sigs_per_message),
+        );
+        Some(T::from_sigs(sigs))
+    }
+}
+
+/// Reads signatures one at a time from a stream of single signatures or
+/// batches.
+#[derive(Debug)]
+pub struct SignatureStream<T> {
+    stream: Streaming<T>,
+    pending: VecDeque<Vec<u8>>,
+}
+
+impl<T: SignatureBatch> SignatureStream<T> {
+    pub fn new(stream: Streaming<T>) -> Self {
+        Self {
+            stream,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Returns the next signature, or `None` if the stream ended.
+    pub async fn message(&mut self) -> Result<Option<Vec<u8>>, Status> {
+        loop {
+            if let Some(sig) = self.pending.pop_front() {
+                return Ok(Some(sig));
+            }
+            match self.stream.message().await? {
+                Some(message) => self.pending.extend(message.into_sigs()),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batcher_packs_signatures() {
+        let mut batcher = SignatureBatcher::new(3);
+        let sigs: Vec<Vec<u8>> = (0..7u8).map(|i| vec![i; 32]).collect();
+
+        let mut messages: Vec<PartialSig> = sigs
+            .iter()
+            .filter_map(|sig| batcher.push(sig.clone()))
+            .collect();
+        messages.extend(batcher.flush::<PartialSig>());
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].partial_sigs.len(), 3);
+        assert_eq!(messages[1].partial_sigs.len(), 3);
+        // a single remaining signature uses the single signature field
+        assert_eq!(messages[2].partial_sig, sigs[6]);
+        assert!(messages[2].partial_sigs.is_empty());
+
+        let unpacked: Vec<Vec<u8>> = messages
+            .into_iter()
+            .flat_map(SignatureBatch::into_sigs)
+            .collect();
+        assert_eq!(unpacked, sigs);
+        assert!(batcher.flush::<PartialSig>().is_none());
+    }
+
+    #[test]
+    fn deposit_finalize_params_from_batches() {
+        use super::super::clementine::verifier_deposit_finalize_params::Params;
+        use super::super::clementine::VerifierDepositFinalizeParams;
+
+        let mut batcher = SignatureBatcher::new(2);
+        assert!(batcher.push::<SchnorrSig>(vec![1; 64]).is_none());
+        let batch: SchnorrSig = batcher.push(vec![2; 64]).expect("message is full");
+        let params: VerifierDepositFinalizeParams = batch.into();
+        let Some(Params::SchnorrSigs(batch)) = params.params else {
+            panic!("expected a batch, got {:?}", params.params);
+        };
+        assert_eq!(batch.into_sigs(), vec![vec![1; 64], vec![2; 64]]);
+
+        // verifiers without batching get the signature in the field they read
+        batcher.push::<SchnorrSig>(vec![3; 64]);
+        let params: VerifierDepositFinalizeParams = batcher
+            .flush::<SchnorrSig>()
+            .expect("signature pending")
+            .into();
+        assert_eq!(params.params, Some(Params::SchnorrSig(vec![3; 64])));
+    }
+
+    #[test]
+    fn batcher_without_batching_sends_single_signatures() {
+        let mut batcher = SignatureBatcher::new(0);
+        let message: SchnorrSig = batcher.push(vec![1; 64]).expect("message is full");
+
+        assert_eq!(message.schnorr_sig, vec![1; 64]);
+        assert!(message.schnorr_sigs.is_empty());
+        assert_eq!(message.into_sigs(), vec![vec![1; 64]]);
+    }
+}
@@ -182,6 +182,11 @@ pub struct DepositSignSession {
     pub deposit_params: ::core::option::Option<DepositParams>,
     #[prost(message, repeated, tag = "2")]
     pub nonce_gen_first_responses: ::prost::alloc::vec::Vec<NonceGenFirstResponse>,
+    /// Maximum number of signatures the receiver of the signature stream
+    /// accepts in one message. 0 or 1 means one signature per message, which
+    /// is what older versions expect.
+    #[prost(uint32, tag = "3")]
+    pub max_sigs_per_message: u32,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct OperatorConfig {
@@ -212,6 +217,14 @@ pub mod operator_params {
         UnspentKickoffSig(super::SchnorrSig),
     }
 }
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct GetOperatorParamsRequest {
+    /// Maximum number of unspent kickoff signatures the receiver accepts in one
+    /// message. 0 or 1 means one signature per message, which is what older
+    /// versions expect.
+    #[prost(uint32, tag = "1")]
+    pub max_sigs_per_message: u32,
+}
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct OperatorKeysWithDeposit {
     #[prost(message, optional, tag = "1")]
@@ -234,6 +247,10 @@ pub struct OperatorKeys {
 pub struct SchnorrSig {
     #[prost(bytes = "vec", tag = "1")]
     pub schnorr_sig: ::prost::alloc::vec::Vec<u8>,
+    /// Batch of signatures, used instead of schnorr_sig if the receiver asked
+    /// for batches
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub schnorr_sigs: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct WithdrawParams {
@@ -347,6 +364,13 @@ pub struct CompatibilityParamsRpc {
     pub bridge_circuit_constant: ::prost::alloc::vec::Vec<u8>,
     #[prost(bytes = "vec", tag = "6")]
     pub sha256_bitvm_cache: ::prost::alloc::vec::Vec<u8>,
+    /// Supported gRPC compressions in order of preference
+    #[prost(string, repeated, tag = "7")]
+    pub grpc_compression: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Maximum number of signatures accepted in one message of the signature
+    /// streams the actor receives. 0 for versions without batching.
+    #[prost(uint32, tag = "8")]
+    pub max_sigs_per_message: u32,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EntityDataWithId {
@@ -384,6 +408,10 @@ pub struct VerifierParams {
 pub struct PartialSig {
     #[prost(bytes = "vec", tag = "1")]
     pub partial_sig: ::prost::alloc::vec::Vec<u8>,
+    /// Batch of signatures, used instead of partial_sig if the receiver asked
+    /// for batches
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub partial_sigs: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
 }
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct NonceGenRequest {
@@ -476,7 +504,7 @@ pub mod verifier_deposit_sign_params {
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct VerifierDepositFinalizeParams {
-    #[prost(oneof = "verifier_deposit_finalize_params::Params", tags = "1, 2, 3, 4")]
+    #[prost(oneof = "verifier_deposit_finalize_params::Params", tags = "1, 2, 3, 4, 5")]
     pub params: ::core::option::Option<verifier_deposit_finalize_params::Params>,
 }
 /// Nested message and enum types in `VerifierDepositFinalizeParams`.
@@ -491,6 +519,10 @@ pub mod verifier_deposit_finalize_params {
         MoveTxAggNonce(::prost::alloc::vec::Vec<u8>),
         #[prost(bytes, tag = "4")]
         EmergencyStopAggNonce(::prost::alloc::vec::Vec<u8>),
+        /// Batch of signatures, only sent to verifiers that advertised
+        /// max_sigs_per_message in their compatibility params
+        #[prost(message, tag = "5")]
+        SchnorrSigs(super::SchnorrSig),
     }
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -582,6 +614,35 @@ pub struct VergenResponse {
     pub response: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SpvProofRequest {
+    #[prost(message, optional, tag = "1")]
+    pub txid: ::core::option::Option<Txid>,
+    /// Number of blocks in the block hash MMR the proof is generated for. Defaults
+    /// to the current size of the MMR.
+    #[prost(uint32, optional, tag = "2")]
+    pub mmr_size: ::core::option::Option<u32>,
+}
+/// Proof that a transaction is included in a finalized block, and that the
+/// block is in the block hash MMR built from the genesis height of the protocol
+/// paramset.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SpvProof {
+    /// Borsh serialized MMRInclusionProof of the block hash
+    #[prost(bytes = "vec", tag = "1")]
+    pub mmr_inclusion_proof: ::prost::alloc::vec::Vec<u8>,
+    /// Borsh serialized BlockInclusionProof of the transaction
+    #[prost(bytes = "vec", tag = "2")]
+    pub block_inclusion_proof: ::prost::alloc::vec::Vec<u8>,
+    /// Consensus encoded block header
+    #[prost(bytes = "vec", tag = "3")]
+    pub block_header: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint32, tag = "4")]
+    pub block_height: u32,
+    /// Number of blocks in the block hash MMR the proof is generated for
+    #[prost(uint32, tag = "5")]
+    pub mmr_size: u32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RawSignedTx {
     #[prost(bytes = "vec", tag = "1")]
     pub raw_tx: ::prost::alloc::vec::Vec<u8>,
@@ -599,6 +660,33 @@ pub struct RawSignedTxs {
     pub raw_txs: ::prost::alloc::vec::Vec<RawSignedTx>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Deposits {
+    #[prost(message, repeated, tag = "1")]
+    pub deposits: ::prost::alloc::vec::Vec<Deposit>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NewDepositResult {
+    #[prost(message, optional, tag = "1")]
+    pub deposit_outpoint: ::core::option::Option<Outpoint>,
+    #[prost(oneof = "new_deposit_result::Result", tags = "2, 3")]
+    pub result: ::core::option::Option<new_deposit_result::Result>,
+}
+/// Nested message and enum types in `NewDepositResult`.
+pub mod new_deposit_result {
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Result {
+        #[prost(message, tag = "2")]
+        MoveTx(super::RawSignedTx),
+        #[prost(string, tag = "3")]
+        Error(::prost::alloc::string::String),
+    }
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NewDepositResults {
+    #[prost(message, repeated, tag = "1")]
+    pub results: ::prost::alloc::vec::Vec<NewDepositResult>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SignedTxWithType {
     #[prost(message, optional, tag = "1")]
     pub transaction_type: ::core::option::Option<GrpcTransactionId>,
@@ -685,6 +773,59 @@ pub struct GetEmergencyStopTxResponse {
     pub encrypted_emergency_stop_txs: ::prost::alloc::vec::Vec<
         ::prost::alloc::vec::Vec<u8>,
     >,
+    /// N-of-N key and security council of the deposit of each txid, needed to
+    /// validate its emergency stop tx.
+    #[prost(message, repeated, tag = "3")]
+    pub nofn_xonly_pks: ::prost::alloc::vec::Vec<XOnlyPublicKeyRpc>,
+    #[prost(message, repeated, tag = "4")]
+    pub security_councils: ::prost::alloc::vec::Vec<SecurityCouncil>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AddMemberRequest {
+    /// OPERATOR or VERIFIER.
+    #[prost(enumeration = "EntityType", tag = "1")]
+    pub kind: i32,
+    #[prost(string, tag = "2")]
+    pub endpoint: ::prost::alloc::string::String,
+    /// Bitcoin block height from which the member takes part in new deposits.
+    /// 0 means the next block.
+    #[prost(uint32, tag = "3")]
+    pub effective_height: u32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RemoveMemberRequest {
+    /// OPERATOR or VERIFIER.
+    #[prost(enumeration = "EntityType", tag = "1")]
+    pub kind: i32,
+    /// Compressed public key of a verifier or x-only public key of an operator.
+    #[prost(bytes = "vec", tag = "2")]
+    pub public_key: ::prost::alloc::vec::Vec<u8>,
+    /// Bitcoin block height from which the member doesn't take part in new
+    /// deposits. 0 means the next block.
+    #[prost(uint32, tag = "3")]
+    pub effective_height: u32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MembershipChange {
+    /// Epoch started by the change.
+    #[prost(uint64, tag = "1")]
+    pub epoch: u64,
+    #[prost(enumeration = "EntityType", tag = "2")]
+    pub kind: i32,
+    /// True if the member was removed, false if it was added.
+    #[prost(bool, tag = "3")]
+    pub removed: bool,
+    #[prost(string, tag = "4")]
+    pub endpoint: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "5")]
+    pub public_key: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint32, tag = "6")]
+    pub effective_height: u32,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MembershipChanges {
+    #[prost(message, repeated, tag = "1")]
+    pub changes: ::prost::alloc::vec::Vec<MembershipChange>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SendMoveTxRequest {
@@ -1197,12 +1338,13 @@ pub mod clementine_operator_client {
         /// # Returns
         ///
         /// Returns an [`OperatorParams`], which includes operator's configuration and
-        /// Watchtower parameters.
+        /// Watchtower parameters. Unspent kickoff signatures are batched up to the
+        /// requested `max_sigs_per_message`.
         ///
         /// Used by aggregator inside setup
         pub async fn get_params(
             &mut self,
-            request: impl tonic::IntoRequest<super::Empty>,
+            request: impl tonic::IntoRequest<super::GetOperatorParamsRequest>,
         ) -> std::result::Result<
             tonic::Response<tonic::codec::Streaming<super::OperatorParams>>,
             tonic::Status,
@@ -1600,6 +1742,29 @@ pub mod clementine_operator_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Returns the inclusion proof of a transaction in a finalized block and the
+        /// inclusion proof of the block in the block hash MMR.
+        pub async fn get_spv_proof(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SpvProofRequest>,
+        ) -> std::result::Result<tonic::Response<super::SpvProof>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/clementine.ClementineOperator/GetSpvProof",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("clementine.ClementineOperator", "GetSpvProof"));
+            self.inner.unary(req, path, codec).await
+        }
         pub async fn vergen(
             &mut self,
             request: impl tonic::IntoRequest<super::Empty>,
@@ -1823,6 +1988,37 @@ pub mod clementine_verifier_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Sends all membership changes to the verifier. Changes it already knows
+        /// must be unchanged, only new changes are stored. Deposits are checked
+        /// against the members of the epoch at the deposit height.
+        ///
+        /// Used by aggregator inside setup and after membership changes
+        pub async fn set_membership_changes(
+            &mut self,
+            request: impl tonic::IntoRequest<super::MembershipChanges>,
+        ) -> std::result::Result<tonic::Response<super::Empty>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/clementine.ClementineVerifier/SetMembershipChanges",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "clementine.ClementineVerifier",
+                        "SetMembershipChanges",
+                    ),
+                );
+            self.inner.unary(req, path, codec).await
+        }
         /// Generates nonces for a deposit.
         ///
         /// # Returns
@@ -2119,6 +2315,29 @@ pub mod clementine_verifier_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Returns the inclusion proof of a transaction in a finalized block and the
+        /// inclusion proof of the block in the block hash MMR.
+        pub async fn get_spv_proof(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SpvProofRequest>,
+        ) -> std::result::Result<tonic::Response<super::SpvProof>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/clementine.ClementineVerifier/GetSpvProof",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("clementine.ClementineVerifier", "GetSpvProof"));
+            self.inner.unary(req, path, codec).await
+        }
         pub async fn vergen(
             &mut self,
             request: impl tonic::IntoRequest<super::Empty>,
@@ -2326,6 +2545,9 @@ pub mod clementine_aggregator_client {
         /// 2. Distributes these verifier keys to all verifiers
         /// 3. Collects all operator configs from each operator
         /// 4. Distributes these operator configs to all verifiers
+        /// 5. Sends the membership changes to all verifiers
+        ///
+        /// Members added for a later effective height are included.
         ///
         /// Used by the clementine-backend service
         pub async fn setup(
@@ -2383,6 +2605,36 @@ pub mod clementine_aggregator_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Signs several deposits. The compatibility check is done once for the
+        /// batch, then every deposit is signed in its own signing session, the same
+        /// way as NewDeposit, with a few deposits signed concurrently. Returns a
+        /// result for every deposit, in request order; a failing deposit does not
+        /// fail the others.
+        ///
+        /// Deposits are not multiplexed over shared streams: each one still runs its
+        /// own key distribution, nonce and signature streams, so a batch saves only
+        /// the request round trips and the repeated compatibility checks.
+        pub async fn new_deposits(
+            &mut self,
+            request: impl tonic::IntoRequest<super::Deposits>,
+        ) -> std::result::Result<tonic::Response<super::NewDepositResults>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/clementine.ClementineAggregator/NewDeposits",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("clementine.ClementineAggregator", "NewDeposits"));
+            self.inner.unary(req, path, codec).await
+        }
         /// Call's withdraw on all operators
         /// Used by the clementine-backend service to initiate a withdrawal
         /// If the operator's xonly public keys list is empty, the withdrawal will be
@@ -2550,6 +2802,80 @@ pub mod clementine_aggregator_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Adds a verifier or operator. The change starts a new membership epoch that
+        /// is used for deposits from the effective height on, existing deposits keep
+        /// their participants. Setup is run afterwards so that the new member and the
+        /// verifiers exchange keys and operator params; if it fails, the change stays
+        /// recorded and Setup should be rerun.
+        pub async fn add_member(
+            &mut self,
+            request: impl tonic::IntoRequest<super::AddMemberRequest>,
+        ) -> std::result::Result<tonic::Response<super::MembershipChange>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/clementine.ClementineAggregator/AddMember",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("clementine.ClementineAggregator", "AddMember"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Removes a verifier or operator from new deposits. Deposits it took part in
+        /// keep using it. The change is sent to the verifiers, which reject deposits
+        /// using members that aren't active at the deposit height.
+        /// Verifiers keep requiring every operator with usable collateral in new
+        /// deposits, so a removed operator also has to spend its collateral.
+        pub async fn remove_member(
+            &mut self,
+            request: impl tonic::IntoRequest<super::RemoveMemberRequest>,
+        ) -> std::result::Result<tonic::Response<super::MembershipChange>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/clementine.ClementineAggregator/RemoveMember",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("clementine.ClementineAggregator", "RemoveMember"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Returns all recorded membership changes, oldest first.
+        pub async fn get_membership_changes(
+            &mut self,
+            request: impl tonic::IntoRequest<super::Empty>,
+        ) -> std::result::Result<tonic::Response<super::MembershipChanges>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/clementine.ClementineAggregator/GetMembershipChanges",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("clementine.ClementineAggregator", "GetMembershipChanges"));
+            self.inner.unary(req, path, codec).await
+        }
         pub async fn vergen(
             &mut self,
             request: impl tonic::IntoRequest<super::Empty>,
@@ -2617,12 +2943,13 @@ pub mod clementine_operator_server {
         /// # Returns
         ///
         /// Returns an [`OperatorParams`], which includes operator's configuration and
-        /// Watchtower parameters.
+        /// Watchtower parameters. Unspent kickoff signatures are batched up to the
+        /// requested `max_sigs_per_message`.
         ///
         /// Used by aggregator inside setup
         async fn get_params(
             &self,
-            request: tonic::Request<super::Empty>,
+            request: tonic::Request<super::GetOperatorParamsRequest>,
         ) -> std::result::Result<tonic::Response<Self::GetParamsStream>, tonic::Status>;
         /// Returns an operator's deposit keys.
         /// Deposit keys include Assert BitVM winternitz keys, and challenge ACK
@@ -2764,6 +3091,12 @@ pub mod clementine_operator_server {
             &self,
             request: tonic::Request<super::Empty>,
         ) -> std::result::Result<tonic::Response<super::Empty>, tonic::Status>;
+        /// Returns the inclusion proof of a transaction in a finalized block and the
+        /// inclusion proof of the block in the block hash MMR.
+        async fn get_spv_proof(
+            &self,
+            request: tonic::Request<super::SpvProofRequest>,
+        ) -> std::result::Result<tonic::Response<super::SpvProof>, tonic::Status>;
         async fn vergen(
             &self,
             request: tonic::Request<super::Empty>,
@@ -2948,7 +3281,7 @@ pub mod clementine_operator_server {
                     struct GetParamsSvc<T: ClementineOperator>(pub Arc<T>);
                     impl<
                         T: ClementineOperator,
-                    > tonic::server::ServerStreamingService<super::Empty>
+                    > tonic::server::ServerStreamingService<super::GetOperatorParamsRequest>
                     for GetParamsSvc<T> {
                         type Response = super::OperatorParams;
                         type ResponseStream = T::GetParamsStream;
@@ -2958,7 +3291,7 @@ pub mod clementine_operator_server {
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::Empty>,
+                            request: tonic::Request<super::GetOperatorParamsRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
@@ -3564,6 +3897,52 @@ pub mod clementine_operator_server {
                     };
                     Box::pin(fut)
                 }
+                "/clementine.ClementineOperator/GetSpvProof" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSpvProofSvc<T: ClementineOperator>(pub Arc<T>);
+                    impl<
+                        T: ClementineOperator,
+                    > tonic::server::UnaryService<super::SpvProofRequest>
+                    for GetSpvProofSvc<T> {
+                        type Response = super::SpvProof;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SpvProofRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ClementineOperator>::get_spv_proof(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetSpvProofSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/clementine.ClementineOperator/Vergen" => {
                     #[allow(non_camel_case_types)]
                     struct VergenSvc<T: ClementineOperator>(pub Arc<T>);
@@ -3692,6 +4071,15 @@ pub mod clementine_verifier_server {
             &self,
             request: tonic::Request<super::OperatorKeysWithDeposit>,
         ) -> std::result::Result<tonic::Response<super::Empty>, tonic::Status>;
+        /// Sends all membership changes to the verifier. Changes it already knows
+        /// must be unchanged, only new changes are stored. Deposits are checked
+        /// against the members of the epoch at the deposit height.
+        ///
+        /// Used by aggregator inside setup and after membership changes
+        async fn set_membership_changes(
+            &self,
+            request: tonic::Request<super::MembershipChanges>,
+        ) -> std::result::Result<tonic::Response<super::Empty>, tonic::Status>;
         /// Server streaming response type for the NonceGen method.
         type NonceGenStream: tonic::codegen::tokio_stream::Stream<
                 Item = std::result::Result<super::NonceGenResponse, tonic::Status>,
@@ -3796,6 +4184,12 @@ pub mod clementine_verifier_server {
             tonic::Response<super::RawTxWithRbfInfo>,
             tonic::Status,
         >;
+        /// Returns the inclusion proof of a transaction in a finalized block and the
+        /// inclusion proof of the block in the block hash MMR.
+        async fn get_spv_proof(
+            &self,
+            request: tonic::Request<super::SpvProofRequest>,
+        ) -> std::result::Result<tonic::Response<super::SpvProof>, tonic::Status>;
         async fn vergen(
             &self,
             request: tonic::Request<super::Empty>,
@@ -4064,6 +4458,55 @@ pub mod clementine_verifier_server {
                     };
                     Box::pin(fut)
                 }
+                "/clementine.ClementineVerifier/SetMembershipChanges" => {
+                    #[allow(non_camel_case_types)]
+                    struct SetMembershipChangesSvc<T: ClementineVerifier>(pub Arc<T>);
+                    impl<
+                        T: ClementineVerifier,
+                    > tonic::server::UnaryService<super::MembershipChanges>
+                    for SetMembershipChangesSvc<T> {
+                        type Response = super::Empty;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::MembershipChanges>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ClementineVerifier>::set_membership_changes(
+                                        &inner,
+                                        request,
+                                    )
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SetMembershipChangesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/clementine.ClementineVerifier/NonceGen" => {
                     #[allow(non_camel_case_types)]
                     struct NonceGenSvc<T: ClementineVerifier>(pub Arc<T>);
@@ -4543,6 +4986,52 @@ pub mod clementine_verifier_server {
                     };
                     Box::pin(fut)
                 }
+                "/clementine.ClementineVerifier/GetSpvProof" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSpvProofSvc<T: ClementineVerifier>(pub Arc<T>);
+                    impl<
+                        T: ClementineVerifier,
+                    > tonic::server::UnaryService<super::SpvProofRequest>
+                    for GetSpvProofSvc<T> {
+                        type Response = super::SpvProof;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SpvProofRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ClementineVerifier>::get_spv_proof(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetSpvProofSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/clementine.ClementineVerifier/Vergen" => {
                     #[allow(non_camel_case_types)]
                     struct VergenSvc<T: ClementineVerifier>(pub Arc<T>);
@@ -4664,6 +5153,9 @@ pub mod clementine_aggregator_server {
         /// 2. Distributes these verifier keys to all verifiers
         /// 3. Collects all operator configs from each operator
         /// 4. Distributes these operator configs to all verifiers
+        /// 5. Sends the membership changes to all verifiers
+        ///
+        /// Members added for a later effective height are included.
         ///
         /// Used by the clementine-backend service
         async fn setup(
@@ -4685,6 +5177,19 @@ pub mod clementine_aggregator_server {
             &self,
             request: tonic::Request<super::Deposit>,
         ) -> std::result::Result<tonic::Response<super::RawSignedTx>, tonic::Status>;
+        /// Signs several deposits. The compatibility check is done once for the
+        /// batch, then every deposit is signed in its own signing session, the same
+        /// way as NewDeposit, with a few deposits signed concurrently. Returns a
+        /// result for every deposit, in request order; a failing deposit does not
+        /// fail the others.
+        ///
+        /// Deposits are not multiplexed over shared streams: each one still runs its
+        /// own key distribution, nonce and signature streams, so a batch saves only
+        /// the request round trips and the repeated compatibility checks.
+        async fn new_deposits(
+            &self,
+            request: tonic::Request<super::Deposits>,
+        ) -> std::result::Result<tonic::Response<super::NewDepositResults>, tonic::Status>;
         /// Call's withdraw on all operators
         /// Used by the clementine-backend service to initiate a withdrawal
         /// If the operator's xonly public keys list is empty, the withdrawal will be
@@ -4728,6 +5233,29 @@ pub mod clementine_aggregator_server {
             tonic::Response<super::GetEmergencyStopTxResponse>,
             tonic::Status,
         >;
+        /// Adds a verifier or operator. The change starts a new membership epoch that
+        /// is used for deposits from the effective height on, existing deposits keep
+        /// their participants. Setup is run afterwards so that the new member and the
+        /// verifiers exchange keys and operator params; if it fails, the change stays
+        /// recorded and Setup should be rerun.
+        async fn add_member(
+            &self,
+            request: tonic::Request<super::AddMemberRequest>,
+        ) -> std::result::Result<tonic::Response<super::MembershipChange>, tonic::Status>;
+        /// Removes a verifier or operator from new deposits. Deposits it took part in
+        /// keep using it. The change is sent to the verifiers, which reject deposits
+        /// using members that aren't active at the deposit height.
+        /// Verifiers keep requiring every operator with usable collateral in new
+        /// deposits, so a removed operator also has to spend its collateral.
+        async fn remove_member(
+            &self,
+            request: tonic::Request<super::RemoveMemberRequest>,
+        ) -> std::result::Result<tonic::Response<super::MembershipChange>, tonic::Status>;
+        /// Returns all recorded membership changes, oldest first.
+        async fn get_membership_changes(
+            &self,
+            request: tonic::Request<super::Empty>,
+        ) -> std::result::Result<tonic::Response<super::MembershipChanges>, tonic::Status>;
         async fn vergen(
             &self,
             request: tonic::Request<super::Empty>,
@@ -5052,6 +5580,50 @@ pub mod clementine_aggregator_server {
                     };
                     Box::pin(fut)
                 }
+                "/clementine.ClementineAggregator/NewDeposits" => {
+                    #[allow(non_camel_case_types)]
+                    struct NewDepositsSvc<T: ClementineAggregator>(pub Arc<T>);
+                    impl<
+                        T: ClementineAggregator,
+                    > tonic::server::UnaryService<super::Deposits> for NewDepositsSvc<T> {
+                        type Response = super::NewDepositResults;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::Deposits>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ClementineAggregator>::new_deposits(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = NewDepositsSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/clementine.ClementineAggregator/Withdraw" => {
                     #[allow(non_camel_case_types)]
                     struct WithdrawSvc<T: ClementineAggregator>(pub Arc<T>);
@@ -5344,6 +5916,138 @@ pub mod clementine_aggregator_server {
                     };
                     Box::pin(fut)
                 }
+                "/clementine.ClementineAggregator/AddMember" => {
+                    #[allow(non_camel_case_types)]
+                    struct AddMemberSvc<T: ClementineAggregator>(pub Arc<T>);
+                    impl<
+                        T: ClementineAggregator,
+                    > tonic::server::UnaryService<super::AddMemberRequest> for AddMemberSvc<T> {
+                        type Response = super::MembershipChange;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::AddMemberRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ClementineAggregator>::add_member(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = AddMemberSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/clementine.ClementineAggregator/RemoveMember" => {
+                    #[allow(non_camel_case_types)]
+                    struct RemoveMemberSvc<T: ClementineAggregator>(pub Arc<T>);
+                    impl<
+                        T: ClementineAggregator,
+                    > tonic::server::UnaryService<super::RemoveMemberRequest> for RemoveMemberSvc<T> {
+                        type Response = super::MembershipChange;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::RemoveMemberRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ClementineAggregator>::remove_member(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = RemoveMemberSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/clementine.ClementineAggregator/GetMembershipChanges" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetMembershipChangesSvc<T: ClementineAggregator>(pub Arc<T>);
+                    impl<
+                        T: ClementineAggregator,
+                    > tonic::server::UnaryService<super::Empty> for GetMembershipChangesSvc<T> {
+                        type Response = super::MembershipChanges;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::Empty>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as ClementineAggregator>::get_membership_changes(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = GetMembershipChangesSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/clementine.ClementineAggregator/Vergen" => {
                     #[allow(non_camel_case_types)]
                     struct VergenSvc<T: ClementineAggregator>(pub Arc<T>);
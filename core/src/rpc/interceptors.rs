@@ -1,4 +1,7 @@
+use opentelemetry::propagation::Injector;
+use tonic::metadata::{MetadataKey, MetadataMap, MetadataValue};
 use tonic::{service::Interceptor, transport::CertificateDer, Request, Status};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 #[derive(Debug, Clone)]
 pub enum Interceptors {
@@ -66,3 +69,33 @@ fn only_aggregator_and_self(
         ))
     }
 }
+
+/// Client interceptor that adds the trace context of the current span to
+/// outgoing requests as W3C `traceparent`/`tracestate` metadata. The server
+/// side continues the trace in [`AddMethodMiddleware`](crate::utils::AddMethodMiddleware).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TraceContextInterceptor;
+
+impl Interceptor for TraceContextInterceptor {
+    #[allow(clippy::result_large_err)]
+    fn call(&mut self, mut req: Request<()>) -> Result<Request<()>, Status> {
+        let context = tracing::Span::current().context();
+        opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&context, &mut MetadataInjector(req.metadata_mut()))
+        });
+        Ok(req)
+    }
+}
+
+struct MetadataInjector<'a>(&'a mut MetadataMap);
+
+impl Injector for MetadataInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(key), Ok(value)) = (
+            MetadataKey::from_bytes(key.as_bytes()),
+            MetadataValue::try_from(value.as_str()),
+        ) {
+            self.0.insert(key, value);
+        }
+    }
+}
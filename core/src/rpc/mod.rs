@@ -1,5 +1,5 @@
 use crate::{
-    config::BridgeConfig,
+    config::{BridgeConfig, GrpcCompression},
     rpc::clementine::{
         clementine_operator_client::ClementineOperatorClient,
         clementine_verifier_client::ClementineVerifierClient,
@@ -9,8 +9,11 @@ use clementine::*;
 use clementine_errors::BridgeError;
 use eyre::Context;
 use hyper_util::rt::TokioIo;
+use interceptors::TraceContextInterceptor;
 use std::{path::PathBuf, time::Duration};
 use tagged_signature::SignatureId;
+use tonic::codec::CompressionEncoding;
+use tonic::service::interceptor::InterceptedService;
 use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity, Uri};
 
 #[cfg(test)]
@@ -21,6 +24,7 @@ use crate::test::common::ensure_test_certificates;
 pub mod clementine;
 
 pub mod aggregator;
+pub mod batch;
 pub mod ecdsa_verification_sig;
 mod error;
 pub mod interceptors;
@@ -179,24 +183,70 @@ where
     .await
 }
 
+/// Channel of verifier and operator clients. Requests carry the trace context
+/// of the calling span, see [`TraceContextInterceptor`].
+pub type TracedChannel = InterceptedService<Channel, TraceContextInterceptor>;
+
+/// Returns the [`tonic`] encoding of a configured compression.
+pub fn compression_encoding(compression: GrpcCompression) -> CompressionEncoding {
+    match compression {
+        GrpcCompression::Gzip => CompressionEncoding::Gzip,
+        GrpcCompression::Zstd => CompressionEncoding::Zstd,
+    }
+}
+
+/// gRPC clients whose requests can be compressed once a compression is
+/// negotiated with the peer.
+pub trait RequestCompression {
+    /// Compresses requests with `compression`, if any. Requests are not
+    /// compressed by default, as older peers can't decompress them.
+    fn with_request_compression(self, compression: Option<GrpcCompression>) -> Self;
+}
+
+impl RequestCompression for ClementineOperatorClient<TracedChannel> {
+    fn with_request_compression(self, compression: Option<GrpcCompression>) -> Self {
+        match compression {
+            Some(compression) => self.send_compressed(compression_encoding(compression)),
+            None => self,
+        }
+    }
+}
+
+impl RequestCompression for ClementineVerifierClient<TracedChannel> {
+    fn with_request_compression(self, compression: Option<GrpcCompression>) -> Self {
+        match compression {
+            Some(compression) => self.send_compressed(compression_encoding(compression)),
+            None => self,
+        }
+    }
+}
+
 pub fn operator_client_builder(
     config: &BridgeConfig,
-) -> impl Fn(Channel) -> ClementineOperatorClient<Channel> {
+) -> impl Fn(Channel) -> ClementineOperatorClient<TracedChannel> {
     let max_msg_size = config.grpc.max_message_size;
+    let compression = config.grpc.compression.clone();
     move |channel| {
-        ClementineOperatorClient::new(channel)
-            .max_decoding_message_size(max_msg_size)
-            .max_encoding_message_size(max_msg_size)
+        compression.iter().fold(
+            ClementineOperatorClient::with_interceptor(channel, TraceContextInterceptor)
+                .max_decoding_message_size(max_msg_size)
+                .max_encoding_message_size(max_msg_size),
+            |client, compression| client.accept_compressed(compression_encoding(*compression)),
+        )
     }
 }
 
 pub fn verifier_client_builder(
     config: &BridgeConfig,
-) -> impl Fn(Channel) -> ClementineVerifierClient<Channel> {
+) -> impl Fn(Channel) -> ClementineVerifierClient<TracedChannel> {
     let max_msg_size = config.grpc.max_message_size;
+    let compression = config.grpc.compression.clone();
     move |channel| {
-        ClementineVerifierClient::new(channel)
-            .max_decoding_message_size(max_msg_size)
-            .max_encoding_message_size(max_msg_size)
+        compression.iter().fold(
+            ClementineVerifierClient::with_interceptor(channel, TraceContextInterceptor)
+                .max_decoding_message_size(max_msg_size)
+                .max_encoding_message_size(max_msg_size),
+            |client, compression| client.accept_compressed(compression_encoding(*compression)),
+        )
     }
 }
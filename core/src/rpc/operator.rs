@@ -1,8 +1,9 @@
+use super::batch::SignatureBatcher;
 use super::clementine::clementine_operator_server::ClementineOperator;
 use super::clementine::{
     self, ChallengeAckDigest, DepositParams, DepositSignSession, Empty, FinalizedPayoutParams,
-    OperatorKeys, OperatorParams, SchnorrSig, SignedTxWithType, SignedTxsWithType,
-    TransactionRequest, VergenResponse, WithdrawParams, XOnlyPublicKeyRpc,
+    GetOperatorParamsRequest, OperatorKeys, OperatorParams, SchnorrSig, SignedTxWithType,
+    SignedTxsWithType, TransactionRequest, VergenResponse, WithdrawParams, XOnlyPublicKeyRpc,
 };
 use super::error::*;
 use crate::bitvm_client::ClementineBitVMPublicKeys;
@@ -18,6 +19,7 @@ use crate::rpc::ecdsa_verification_sig::{
     recover_address_from_ecdsa_signature, OperatorWithdrawalMessage,
 };
 use crate::rpc::parser;
+use crate::spv_proof::generate_tx_spv_proof;
 use crate::utils::{get_vergen_response, monitor_standalone_task, timed_request};
 use alloy::primitives::PrimitiveSignature;
 use bitcoin::hashes::Hash;
@@ -75,9 +77,10 @@ where
     #[tracing::instrument(skip_all, err(level = tracing::Level::ERROR), ret(level = tracing::Level::TRACE))]
     async fn get_params(
         &self,
-        _request: Request<Empty>,
+        request: Request<GetOperatorParamsRequest>,
     ) -> Result<Response<Self::GetParamsStream>, Status> {
         tracing::info!("Get params rpc called");
+        let mut batcher = SignatureBatcher::new(request.into_inner().max_sigs_per_message);
         let operator = self.operator.clone();
         let (tx, rx) = mpsc::channel(DEFAULT_CHANNEL_SIZE);
         let out_stream: Self::GetParamsStream = ReceiverStream::new(rx);
@@ -99,8 +102,17 @@ where
             }
 
             while let Some(operator_sig) = signature_receiver.recv().await {
-                let unspent_kickoff_sig: OperatorParams = operator_sig.into();
-                tx.send(Ok(unspent_kickoff_sig))
+                let Some(unspent_kickoff_sigs) =
+                    batcher.push::<SchnorrSig>(operator_sig.serialize().to_vec())
+                else {
+                    continue;
+                };
+                tx.send(Ok(unspent_kickoff_sigs.into()))
+                    .await
+                    .map_err(output_stream_ended_prematurely)?;
+            }
+            if let Some(unspent_kickoff_sigs) = batcher.flush::<SchnorrSig>() {
+                tx.send(Ok(unspent_kickoff_sigs.into()))
                     .await
                     .map_err(output_stream_ended_prematurely)?;
             }
@@ -121,6 +133,7 @@ where
         let (tx, rx) = mpsc::channel(DEFAULT_CHANNEL_SIZE);
 
         let deposit_sign_session = request.into_inner();
+        let mut batcher = SignatureBatcher::new(deposit_sign_session.max_sigs_per_message);
         let deposit_params: DepositParams = deposit_sign_session.try_into()?;
         let deposit_data: DepositData = deposit_params.try_into()?;
         tracing::info!(
@@ -137,19 +150,25 @@ where
         let monitor_err_sender = tx.clone();
 
         let handle = tokio::spawn(async move {
-            let mut sent_sigs = 0;
+            let mut signed_sigs = 0;
             while let Some(sig) = deposit_signatures_rx.recv().await {
                 let sig = sig?;
-                let operator_burn_sig = SchnorrSig {
-                    schnorr_sig: sig.serialize().to_vec(),
+                signed_sigs += 1;
+                let batch = match batcher.push::<SchnorrSig>(sig.serialize().to_vec()) {
+                    Some(batch) => Some(batch),
+                    // the last batch is sent even if it is not full
+                    None if signed_sigs == expected_sigs => batcher.flush(),
+                    None => None,
+                };
+                let Some(operator_burn_sigs) = batch else {
+                    continue;
                 };
 
-                tx.send(Ok(operator_burn_sig))
+                tx.send(Ok(operator_burn_sigs))
                     .inspect_ok(|_| {
-                        sent_sigs += 1;
                         tracing::debug!(
                             "Sent signature {}/{} in deposit_sign()",
-                            sent_sigs,
+                            signed_sigs,
                             expected_sigs
                         );
                     })
@@ -157,6 +176,12 @@ where
                     .wrap_err("Failed to send signature in operator rpc deposit sign")
                     .map_to_status()?;
             }
+            if let Some(operator_burn_sigs) = batcher.flush::<SchnorrSig>() {
+                tx.send(Ok(operator_burn_sigs))
+                    .await
+                    .wrap_err("Failed to send signature in operator rpc deposit sign")
+                    .map_to_status()?;
+            }
             Ok::<(), Status>(())
         });
 
@@ -447,6 +472,32 @@ where
         ))
     }
 
+    #[tracing::instrument(skip_all, err(level = tracing::Level::ERROR))]
+    async fn get_spv_proof(
+        &self,
+        request: Request<clementine::SpvProofRequest>,
+    ) -> Result<Response<clementine::SpvProof>, Status> {
+        let request = request.into_inner();
+        let txid = bitcoin::Txid::try_from(
+            request
+                .txid
+                .ok_or(Status::invalid_argument("No txid received"))?,
+        )
+        .map_err(|e| {
+            Status::invalid_argument(format!("Failed to convert txid to bitcoin::Txid: {e}"))
+        })?;
+        tracing::debug!("Called get_spv_proof rpc for txid: {txid}");
+
+        let spv_proof = generate_tx_spv_proof(
+            &self.operator.db,
+            self.operator.config.protocol_paramset(),
+            txid,
+            request.mmr_size,
+        )
+        .await?;
+        Ok(Response::new(spv_proof.try_into()?))
+    }
+
     #[tracing::instrument(skip_all, err(level = tracing::Level::ERROR), ret(level = tracing::Level::TRACE))]
     async fn get_x_only_public_key(
         &self,
@@ -10,7 +10,9 @@ use crate::deposit::{
     Actors, BaseDepositData, DepositData, DepositInfo, DepositType, ReplacementDepositData,
     SecurityCouncil,
 };
+use crate::membership::{MemberKind, MembershipAction, MembershipChange};
 use crate::rpc::clementine::{SignedTxWithType, SignedTxsWithType};
+use crate::spv_proof::TxSpvProof;
 use crate::utils::{FeePayingType, RbfSigningInfo};
 use bitcoin::hashes::{sha256d, FromSliceError, Hash};
 use bitcoin::secp256k1::schnorr::Signature;
@@ -189,6 +191,22 @@ impl From<OutPoint> for Outpoint {
     }
 }
 
+impl TryFrom<TxSpvProof> for clementine::SpvProof {
+    type Error = BridgeError;
+
+    fn try_from(value: TxSpvProof) -> Result<Self, Self::Error> {
+        Ok(clementine::SpvProof {
+            mmr_inclusion_proof: borsh::to_vec(&value.mmr_inclusion_proof)
+                .wrap_err("Failed to serialize MMR inclusion proof")?,
+            block_inclusion_proof: borsh::to_vec(&value.block_inclusion_proof)
+                .wrap_err("Failed to serialize block inclusion proof")?,
+            block_header: bitcoin::consensus::encode::serialize(&value.block_header),
+            block_height: value.block_height,
+            mmr_size: value.mmr_size,
+        })
+    }
+}
+
 impl TryFrom<WinternitzPubkey> for winternitz::PublicKey {
     type Error = BridgeError;
 
@@ -471,6 +489,53 @@ impl From<SecurityCouncil> for clementine::SecurityCouncil {
     }
 }
 
+impl From<MembershipChange> for clementine::MembershipChange {
+    fn from(value: MembershipChange) -> Self {
+        let kind = match value.kind {
+            MemberKind::Verifier => clementine::EntityType::Verifier,
+            MemberKind::Operator => clementine::EntityType::Operator,
+        };
+        clementine::MembershipChange {
+            epoch: value.epoch,
+            kind: kind as i32,
+            removed: value.action == MembershipAction::Remove,
+            endpoint: value.endpoint,
+            public_key: value.public_key,
+            effective_height: value.effective_height,
+        }
+    }
+}
+
+impl TryFrom<clementine::MembershipChange> for MembershipChange {
+    type Error = Status;
+
+    fn try_from(value: clementine::MembershipChange) -> Result<Self, Self::Error> {
+        let kind = match clementine::EntityType::try_from(value.kind) {
+            Ok(clementine::EntityType::Verifier) => MemberKind::Verifier,
+            Ok(clementine::EntityType::Operator) => MemberKind::Operator,
+            _ => {
+                return Err(Status::invalid_argument(format!(
+                    "Invalid membership change kind: {}",
+                    value.kind
+                )))
+            }
+        };
+
+        Ok(MembershipChange {
+            epoch: value.epoch,
+            kind,
+            action: if value.removed {
+                MembershipAction::Remove
+            } else {
+                MembershipAction::Add
+            },
+            endpoint: value.endpoint,
+            public_key: value.public_key,
+            effective_height: value.effective_height,
+        })
+    }
+}
+
 impl TryFrom<clementine::SecurityCouncil> for SecurityCouncil {
     type Error = Status;
 
@@ -3,6 +3,7 @@ use crate::{
     fetch_next_message_from_stream,
     operator::Operator,
     rpc::{
+        batch::SignatureBatch,
         clementine::{
             operator_params, DepositParams, DepositSignSession, OperatorConfig, OperatorParams,
             Outpoint, SchnorrSig, WithdrawParams, XOnlyPublicKeyRpc,
@@ -52,10 +53,18 @@ impl From<winternitz::PublicKey> for OperatorParams {
 
 impl From<Signature> for OperatorParams {
     fn from(sig: Signature) -> Self {
+        SchnorrSig {
+            schnorr_sig: sig.serialize().to_vec(),
+            schnorr_sigs: Vec::new(),
+        }
+        .into()
+    }
+}
+
+impl From<SchnorrSig> for OperatorParams {
+    fn from(sigs: SchnorrSig) -> Self {
         OperatorParams {
-            response: Some(operator_params::Response::UnspentKickoffSig(SchnorrSig {
-                schnorr_sig: sig.serialize().to_vec(),
-            })),
+            response: Some(operator_params::Response::UnspentKickoffSig(sigs)),
         }
     }
 }
@@ -146,16 +155,34 @@ pub async fn parse_winternitz_public_keys(
     }
 }
 
-pub async fn parse_schnorr_sig(
+/// Reads `count` unspent kickoff signatures, sent one per message or in
+/// batches.
+pub async fn parse_unspent_kickoff_sigs(
     stream: &mut tonic::Streaming<OperatorParams>,
-) -> Result<Signature, Status> {
-    let operator_param = fetch_next_message_from_stream!(stream, response)?;
+    count: usize,
+) -> Result<Vec<Signature>, Status> {
+    let mut sigs = Vec::with_capacity(count);
+    while sigs.len() < count {
+        let operator_param = fetch_next_message_from_stream!(stream, response)?;
+
+        let operator_params::Response::UnspentKickoffSig(batch) = operator_param else {
+            return Err(expected_msg_got_none("UnspentKickoffSig")());
+        };
+        for sig in batch.into_sigs() {
+            sigs.push(Signature::from_slice(&sig).map_err(|e| {
+                Status::invalid_argument(format!("Invalid unspent kickoff signature: {e}"))
+            })?);
+        }
+    }
 
-    if let operator_params::Response::UnspentKickoffSig(wpk) = operator_param {
-        Ok(wpk.try_into()?)
-    } else {
-        Err(expected_msg_got_none("UnspentKickoffSig")())
+    if sigs.len() != count {
+        return Err(Status::invalid_argument(format!(
+            "Expected {count} unspent kickoff signatures, got {}",
+            sigs.len()
+        )));
     }
+
+    Ok(sigs)
 }
 
 #[allow(clippy::result_large_err)]
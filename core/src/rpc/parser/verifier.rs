@@ -2,9 +2,11 @@ use super::ParserError;
 use crate::citrea::CitreaClientT;
 use crate::deposit::DepositData;
 use crate::fetch_next_optional_message_from_stream;
+use crate::rpc::batch::SignatureBatch;
 use crate::rpc::clementine::{
     nonce_gen_response, verifier_deposit_sign_params, DepositSignSession, NonceGenFirstResponse,
-    OperatorKeys, OperatorKeysWithDeposit, PartialSig, VerifierDepositSignParams, VerifierParams,
+    OperatorKeys, OperatorKeysWithDeposit, PartialSig, SchnorrSig, VerifierDepositSignParams,
+    VerifierParams,
 };
 use crate::verifier::Verifier;
 use crate::{
@@ -24,6 +26,7 @@ use bitcoin::XOnlyPublicKey;
 use clementine_errors::BridgeError;
 use eyre::Context;
 use secp256k1::musig::{AggregatedNonce, PartialSignature, PublicNonce};
+use std::collections::VecDeque;
 use tonic::Status;
 
 impl<C> TryFrom<&Verifier<C>> for VerifierParams
@@ -96,6 +99,21 @@ impl From<&Signature> for VerifierDepositFinalizeParams {
     }
 }
 
+impl From<SchnorrSig> for VerifierDepositFinalizeParams {
+    /// A single signature is sent in the field that verifiers without
+    /// batching read.
+    fn from(value: SchnorrSig) -> Self {
+        let params = if value.schnorr_sigs.is_empty() {
+            verifier_deposit_finalize_params::Params::SchnorrSig(value.schnorr_sig)
+        } else {
+            verifier_deposit_finalize_params::Params::SchnorrSigs(value)
+        };
+        VerifierDepositFinalizeParams {
+            params: Some(params),
+        }
+    }
+}
+
 impl From<NonceGenFirstResponse> for NonceGenResponse {
     fn from(value: NonceGenFirstResponse) -> Self {
         NonceGenResponse {
@@ -118,6 +136,7 @@ impl From<PartialSignature> for PartialSig {
     fn from(value: PartialSignature) -> Self {
         PartialSig {
             partial_sig: value.serialize().to_vec(),
+            partial_sigs: Vec::new(),
         }
     }
 }
@@ -191,27 +210,38 @@ pub fn parse_op_keys_with_deposit(
     Ok((deposit_data, op_keys, operator_xonly_pk))
 }
 
+/// Returns the next signature of the stream. The remaining signatures of a
+/// batch are kept in `pending` and returned first by the next calls.
 pub async fn parse_next_deposit_finalize_param_schnorr_sig(
     stream: &mut tonic::Streaming<VerifierDepositFinalizeParams>,
+    pending: &mut VecDeque<schnorr::Signature>,
 ) -> Result<Option<schnorr::Signature>, Status> {
+    if let Some(final_sig) = pending.pop_front() {
+        return Ok(Some(final_sig));
+    }
+
     let sig = match fetch_next_optional_message_from_stream!(stream, params) {
         Some(sig) => sig,
         None => return Ok(None),
     };
 
-    let final_sig = match sig {
-        verifier_deposit_finalize_params::Params::SchnorrSig(final_sig) => {
-            schnorr::Signature::from_slice(&final_sig)
-                .map_err(invalid_argument("FinalSig", "Invalid signature length"))?
-        }
+    let final_sigs = match sig {
+        verifier_deposit_finalize_params::Params::SchnorrSig(final_sig) => vec![final_sig],
+        verifier_deposit_finalize_params::Params::SchnorrSigs(final_sigs) => final_sigs.into_sigs(),
         _ => {
             return Err(Status::internal(format!(
                 "Expected SchnorrSig, got {sig:?}",
             )));
         }
     };
+    for final_sig in final_sigs {
+        pending.push_back(
+            schnorr::Signature::from_slice(&final_sig)
+                .map_err(invalid_argument("FinalSig", "Invalid signature length"))?,
+        );
+    }
 
-    Ok(Some(final_sig))
+    Ok(pending.pop_front())
 }
 
 pub async fn parse_deposit_finalize_param_move_tx_agg_nonce(
@@ -1,5 +1,7 @@
+use std::collections::VecDeque;
 use std::str::FromStr;
 
+use super::batch::SignatureBatcher;
 use super::clementine::{
     self, clementine_verifier_server::ClementineVerifier, Empty, NonceGenRequest, NonceGenResponse,
     OperatorParams, OptimisticPayoutParams, PartialSig, RawTxWithRbfInfo, VergenResponse,
@@ -7,12 +9,16 @@ use super::clementine::{
 };
 use super::error;
 use super::parser::ParserError;
+use crate::aggregator::VerifierId;
 use crate::builder::transaction::sign::{create_and_sign_txs, TransactionRequestData};
 use crate::builder::transaction::ContractContext;
 use crate::citrea::CitreaClientT;
 use crate::compatibility::ActorWithConfig;
 use crate::constants::RESTART_BACKGROUND_TASKS_TIMEOUT;
+use crate::membership::MembershipChange;
+use crate::metrics::VerifierDepositMetrics;
 use crate::rpc::clementine::{CompatibilityParamsRpc, VerifierDepositFinalizeResponse};
+use crate::spv_proof::generate_tx_spv_proof;
 #[cfg(feature = "automation")]
 use crate::tx_sender_ext::TxSenderClientExt;
 use crate::utils::{get_vergen_response, monitor_standalone_task, timed_request};
@@ -222,11 +228,11 @@ where
                 .push(parser::operator::parse_winternitz_public_keys(&mut in_stream).await?);
         }
 
-        let mut unspent_kickoff_sigs =
-            Vec::with_capacity(self.verifier.config.get_num_unspent_kickoff_sigs());
-        for _ in 0..self.verifier.config.get_num_unspent_kickoff_sigs() {
-            unspent_kickoff_sigs.push(parser::operator::parse_schnorr_sig(&mut in_stream).await?);
-        }
+        let unspent_kickoff_sigs = parser::operator::parse_unspent_kickoff_sigs(
+            &mut in_stream,
+            self.verifier.config.get_num_unspent_kickoff_sigs(),
+        )
+        .await?;
 
         if in_stream.message().await?.is_some() {
             return Err(Status::invalid_argument(
@@ -297,6 +303,9 @@ where
         let mut in_stream = req.into_inner();
         let verifier = self.verifier.clone();
         tracing::info!("Verifier deposit sign rpc called");
+        let metrics =
+            VerifierDepositMetrics::for_rpc(VerifierId(verifier.signer.public_key), "deposit_sign");
+        let rpc_timer = metrics.start();
 
         let (tx, rx) = mpsc::channel(constants::DEFAULT_CHANNEL_SIZE);
         let out_stream: Self::DepositSignStream = ReceiverStream::new(rx);
@@ -308,13 +317,17 @@ where
         // Send incoming data to deposit sign job.
         let handle = tokio::spawn(async move {
             let params = fetch_next_message_from_stream!(in_stream, params)?;
-            let (deposit_data, session_id) = match params {
+            let (deposit_data, session_id, max_sigs_per_message) = match params {
                 clementine::verifier_deposit_sign_params::Params::DepositSignFirstParam(
                     deposit_sign_session,
-                ) => parser::verifier::parse_deposit_sign_session(
-                    deposit_sign_session,
-                    &verifier.signer.public_key,
-                )?,
+                ) => {
+                    let max_sigs_per_message = deposit_sign_session.max_sigs_per_message;
+                    let (deposit_data, session_id) = parser::verifier::parse_deposit_sign_session(
+                        deposit_sign_session,
+                        &verifier.signer.public_key,
+                    )?;
+                    (deposit_data, session_id, max_sigs_per_message)
+                }
                 _ => return Err(Status::invalid_argument("Expected DepositOutpoint")),
             };
 
@@ -322,7 +335,7 @@ where
             let needed_agg_nonces = config.get_num_required_nofn_sigs(&deposit_data);
 
             param_tx
-                .send((deposit_data, session_id))
+                .send((deposit_data, session_id, max_sigs_per_message))
                 .await
                 .map_err(error::output_stream_ended_prematurely)?;
 
@@ -358,7 +371,7 @@ where
         // Start partial sig job and return partial sig responses.
         let tx_for_monitor = tx.clone();
         let handle = tokio::spawn(async move {
-            let (deposit_data, session_id) = param_rx
+            let (deposit_data, session_id, max_sigs_per_message) = param_rx
                 .recv()
                 .await
                 .ok_or(error::expected_msg_got_none("parameters")())?;
@@ -371,18 +384,24 @@ where
 
             let mut nonce_idx = 0;
             let num_required_sigs = verifier.config.get_num_required_nofn_sigs(&deposit_data);
+            let mut batcher = SignatureBatcher::new(max_sigs_per_message);
             while let Some(partial_sig_result) = partial_sig_receiver.recv().await {
                 match partial_sig_result {
                     Ok(partial_sig) => {
-                        tx.send(Ok(PartialSig {
-                            partial_sig: partial_sig.serialize().to_vec(),
-                        }))
-                        .await
-                        .map_err(|e| {
-                            Status::aborted(format!(
-                                "Error sending partial sig, stream ended prematurely: {e}"
-                            ))
-                        })?;
+                        let batch = batcher.push::<PartialSig>(partial_sig.serialize().to_vec());
+                        // the last batch is sent even if it is not full
+                        let batch = match batch {
+                            Some(batch) => Some(batch),
+                            None if nonce_idx + 1 == num_required_sigs => batcher.flush(),
+                            None => None,
+                        };
+                        if let Some(batch) = batch {
+                            tx.send(Ok(batch)).await.map_err(|e| {
+                                Status::aborted(format!(
+                                    "Error sending partial sig, stream ended prematurely: {e}"
+                                ))
+                            })?;
+                        }
                     }
                     Err(e) => {
                         tx
@@ -398,6 +417,7 @@ where
                 }
 
                 nonce_idx += 1;
+                metrics.signatures.increment(1);
                 tracing::trace!(
                     "Verifier {:?} signed and sent sighash {} of {} through rpc deposit_sign",
                     verifier.signer.public_key,
@@ -405,6 +425,7 @@ where
                     num_required_sigs
                 );
                 if nonce_idx == num_required_sigs {
+                    rpc_timer.success();
                     break;
                 }
             }
@@ -426,6 +447,11 @@ where
     ) -> Result<Response<VerifierDepositFinalizeResponse>, Status> {
         let mut in_stream = req.into_inner();
         tracing::info!("deposit finalize rpc called");
+        let metrics = VerifierDepositMetrics::for_rpc(
+            VerifierId(self.verifier.signer.public_key),
+            "deposit_finalize",
+        );
+        let rpc_timer = metrics.start();
 
         let (sig_tx, sig_rx) = mpsc::channel(constants::DEFAULT_CHANNEL_SIZE);
         let (agg_nonce_tx, agg_nonce_rx) = mpsc::channel(1);
@@ -472,17 +498,20 @@ where
                 num_required_nofn_sigs
             );
             let mut nonce_idx = 0;
-            while let Some(sig) =
-                parser::verifier::parse_next_deposit_finalize_param_schnorr_sig(&mut in_stream)
-                    .await
-                    .wrap_err_with(|| {
-                        format!(
-                            "While waiting for the {}th signature out of {}",
-                            nonce_idx + 1,
-                            num_required_nofn_sigs
-                        )
-                    })
-                    .map_to_status()?
+            let mut pending_sigs = VecDeque::new();
+            while let Some(sig) = parser::verifier::parse_next_deposit_finalize_param_schnorr_sig(
+                &mut in_stream,
+                &mut pending_sigs,
+            )
+            .await
+            .wrap_err_with(|| {
+                format!(
+                    "While waiting for the {}th signature out of {}",
+                    nonce_idx + 1,
+                    num_required_nofn_sigs
+                )
+            })
+            .map_to_status()?
             {
                 tracing::trace!(
                     "Received full nofn sig {} in deposit_finalize()",
@@ -492,6 +521,7 @@ where
                     .send(sig)
                     .await
                     .map_err(error::output_stream_ended_prematurely)?;
+                metrics.signatures.increment(1);
                 tracing::debug!(
                     "Sent full nofn sig {} to src/verifier in deposit_finalize()",
                     nonce_idx + 1
@@ -508,6 +538,11 @@ where
                 tracing::error!("{err_msg}");
                 return Err(Status::invalid_argument(err_msg));
             }
+            if !pending_sigs.is_empty() {
+                return Err(Status::invalid_argument(
+                    "Signature batch contains more than the required N-of-N signatures",
+                ));
+            }
 
             let move_tx_agg_nonce =
                 parser::verifier::parse_deposit_finalize_param_move_tx_agg_nonce(&mut in_stream)
@@ -537,8 +572,11 @@ where
                 let mut op_sig_count = 0;
 
                 while let Some(operator_sig) =
-                    parser::verifier::parse_next_deposit_finalize_param_schnorr_sig(&mut in_stream)
-                        .await?
+                    parser::verifier::parse_next_deposit_finalize_param_schnorr_sig(
+                        &mut in_stream,
+                        &mut pending_sigs,
+                    )
+                    .await?
                 {
                     tracing::trace!(
                         "Received full operator sig {} in deposit_finalize()",
@@ -559,6 +597,11 @@ where
                         break;
                     }
                 }
+                if !pending_sigs.is_empty() {
+                    return Err(Status::invalid_argument(
+                        "Signature batch contains signatures of more than one operator",
+                    ));
+                }
             }
 
             if total_op_sig_count < num_required_total_op_sigs {
@@ -589,6 +632,7 @@ where
             "deposit finalize rpc completed successfully for deposit outpoint: {:?}",
             deposit_outpoint
         );
+        rpc_timer.success();
 
         Ok(Response::new(response))
     }
@@ -613,6 +657,22 @@ where
         Ok(Response::new(Empty {}))
     }
 
+    #[tracing::instrument(skip_all, err(level = tracing::Level::ERROR))]
+    async fn set_membership_changes(
+        &self,
+        request: tonic::Request<super::MembershipChanges>,
+    ) -> std::result::Result<tonic::Response<super::Empty>, tonic::Status> {
+        tracing::info!("set_membership_changes rpc called");
+        let changes = request
+            .into_inner()
+            .changes
+            .into_iter()
+            .map(MembershipChange::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.verifier.set_membership_changes(changes).await?;
+        Ok(Response::new(Empty {}))
+    }
+
     #[tracing::instrument(skip(self), err(level = tracing::Level::ERROR))]
     async fn internal_create_signed_txs(
         &self,
@@ -717,4 +777,30 @@ where
         tracing::debug!("Get current status rpc completed successfully");
         Ok(Response::new(status))
     }
+
+    #[tracing::instrument(skip_all, err(level = tracing::Level::ERROR))]
+    async fn get_spv_proof(
+        &self,
+        request: Request<clementine::SpvProofRequest>,
+    ) -> Result<Response<clementine::SpvProof>, Status> {
+        let request = request.into_inner();
+        let txid = bitcoin::Txid::try_from(
+            request
+                .txid
+                .ok_or(Status::invalid_argument("No txid received"))?,
+        )
+        .map_err(|e| {
+            Status::invalid_argument(format!("Failed to convert txid to bitcoin::Txid: {e}"))
+        })?;
+        tracing::debug!("Called get_spv_proof rpc for txid: {txid}");
+
+        let spv_proof = generate_tx_spv_proof(
+            &self.verifier.db,
+            self.verifier.config.protocol_paramset(),
+            txid,
+            request.mmr_size,
+        )
+        .await?;
+        Ok(Response::new(spv_proof.try_into()?))
+    }
 }
@@ -5,16 +5,19 @@ use crate::aggregator::AggregatorServer;
 use crate::citrea::CitreaClientT;
 use crate::config::BridgeConfig;
 use crate::extended_bitcoin_rpc::ExtendedBitcoinRpc;
+use crate::health::spawn_health_server;
 use crate::operator::OperatorServer;
 use crate::rpc::clementine::clementine_aggregator_server::ClementineAggregatorServer;
 use crate::rpc::clementine::clementine_operator_server::ClementineOperatorServer;
 use crate::rpc::clementine::clementine_verifier_server::ClementineVerifierServer;
+use crate::rpc::compression_encoding;
 use crate::rpc::interceptors::Interceptors::{Noop, OnlyAggregatorAndSelf};
 use crate::utils::AddMethodMiddlewareLayer;
 use crate::verifier::VerifierServer;
 use clementine_errors::BridgeError;
 use eyre::Context;
 use rustls_pki_types::pem::PemObject;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::oneshot;
 use tonic::server::NamedService;
@@ -26,6 +29,19 @@ use tower::limit::RateLimitLayer;
 #[cfg(test)]
 use crate::test::common::ensure_test_certificates;
 
+/// Enables the configured compressions on a generated gRPC service. Responses
+/// are only compressed with an encoding the client accepts.
+macro_rules! with_grpc_compression {
+    ($svc:expr, $config:expr) => {{
+        let mut svc = $svc;
+        for compression in &$config.grpc.compression {
+            let encoding = compression_encoding(*compression);
+            svc = svc.accept_compressed(encoding).send_compressed(encoding);
+        }
+        svc
+    }};
+}
+
 /// Represents a network address that can be either TCP or Unix socket
 #[derive(Debug, Clone)]
 pub enum ServerAddr {
@@ -244,12 +260,17 @@ pub async fn create_verifier_grpc_server<C: CitreaClientT>(
     let addr: std::net::SocketAddr = format!("{}:{}", config.host, config.port)
         .parse()
         .wrap_err("Failed to parse address")?;
-    let verifier = VerifierServer::<C>::new(config.clone()).await?;
+    let verifier = Arc::new(VerifierServer::<C>::new(config.clone()).await?);
     verifier.start_background_tasks().await?;
 
-    let svc = ClementineVerifierServer::new(verifier)
+    if let Some(health) = &config.health {
+        spawn_health_server(health, Arc::downgrade(&verifier)).await?;
+    }
+
+    let svc = ClementineVerifierServer::from_arc(verifier)
         .max_encoding_message_size(config.grpc.max_message_size)
         .max_decoding_message_size(config.grpc.max_message_size);
+    let svc = with_grpc_compression!(svc, config);
 
     let (server_addr, shutdown_tx) =
         create_grpc_server(addr.into(), svc, "Verifier", &config).await?;
@@ -273,13 +294,18 @@ pub async fn create_operator_grpc_server<C: CitreaClientT>(
         .wrap_err("Failed to parse address")?;
 
     tracing::info!("Creating operator server");
-    let operator = OperatorServer::<C>::new(config.clone()).await?;
+    let operator = Arc::new(OperatorServer::<C>::new(config.clone()).await?);
     operator.start_background_tasks().await?;
 
+    if let Some(health) = &config.health {
+        spawn_health_server(health, Arc::downgrade(&operator)).await?;
+    }
+
     tracing::info!("Creating ClementineOperatorServer");
-    let svc = ClementineOperatorServer::new(operator)
+    let svc = ClementineOperatorServer::from_arc(operator)
         .max_encoding_message_size(config.grpc.max_message_size)
         .max_decoding_message_size(config.grpc.max_message_size);
+    let svc = with_grpc_compression!(svc, config);
     let (server_addr, shutdown_tx) =
         create_grpc_server(addr.into(), svc, "Operator", &config).await?;
     tracing::info!("Operator gRPC server created");
@@ -296,12 +322,17 @@ pub async fn create_aggregator_grpc_server(
     let addr: std::net::SocketAddr = format!("{}:{}", config.host, config.port)
         .parse()
         .wrap_err("Failed to parse address")?;
-    let aggregator_server = AggregatorServer::new(config.clone()).await?;
+    let aggregator_server = Arc::new(AggregatorServer::new(config.clone()).await?);
     aggregator_server.start_background_tasks().await?;
 
-    let svc = ClementineAggregatorServer::new(aggregator_server)
+    if let Some(health) = &config.health {
+        spawn_health_server(health, Arc::downgrade(&aggregator_server)).await?;
+    }
+
+    let svc = ClementineAggregatorServer::from_arc(aggregator_server)
         .max_encoding_message_size(config.grpc.max_message_size)
         .max_decoding_message_size(config.grpc.max_message_size);
+    let svc = with_grpc_compression!(svc, config);
 
     if config.client_verification {
         tracing::warn!("Client verification is enabled on aggregator gRPC server",);
@@ -337,6 +368,7 @@ pub async fn create_verifier_unix_server<C: CitreaClientT>(
     let svc = ClementineVerifierServer::new(verifier)
         .max_encoding_message_size(config.grpc.max_message_size)
         .max_decoding_message_size(config.grpc.max_message_size);
+    let svc = with_grpc_compression!(svc, config);
 
     let (server_addr, shutdown_tx) =
         create_grpc_server(socket_path.into(), svc, "Verifier", &config).await?;
@@ -377,6 +409,7 @@ pub async fn create_operator_unix_server<C: CitreaClientT>(
     let svc = ClementineOperatorServer::new(operator)
         .max_encoding_message_size(config.grpc.max_message_size)
         .max_decoding_message_size(config.grpc.max_message_size);
+    let svc = with_grpc_compression!(svc, config);
 
     let (server_addr, shutdown_tx) =
         create_grpc_server(socket_path.into(), svc, "Operator", &config).await?;
@@ -408,6 +441,7 @@ pub async fn create_aggregator_unix_server(
     let svc = ClementineAggregatorServer::new(aggregator_server)
         .max_encoding_message_size(config.grpc.max_message_size)
         .max_decoding_message_size(config.grpc.max_message_size);
+    let svc = with_grpc_compression!(svc, config);
 
     let (server_addr, shutdown_tx) =
         create_grpc_server(socket_path.into(), svc, "Aggregator", &config).await?;
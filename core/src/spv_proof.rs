@@ -0,0 +1,112 @@
+//! # SPV Proofs
+//!
+//! Generates SPV proofs of transactions in finalized blocks from the Bitcoin
+//! syncer's blocks and the block hash MMR in the database (see
+//! [`crate::task::block_hash_mmr`]). The proofs are the ones the bridge
+//! circuit expects for the payout transaction, verified against the MMR the
+//! header chain circuit builds from the genesis height of the protocol
+//! paramset.
+
+use bitcoin::block::Header;
+use bitcoin::hashes::Hash;
+use bitcoin::Txid;
+use circuits_lib::bridge_circuit::merkle_tree::{BitcoinMerkleTree, BlockInclusionProof};
+use circuits_lib::bridge_circuit::transaction::CircuitTransaction;
+use circuits_lib::header_chain::mmr_native::MMRInclusionProof;
+use clementine_errors::BridgeError;
+use eyre::OptionExt;
+
+use crate::config::protocol::ProtocolParamset;
+use crate::database::Database;
+
+/// Inclusion proofs of a transaction in its block and of the block in the
+/// block hash MMR.
+#[derive(Debug, Clone)]
+pub struct TxSpvProof {
+    pub mmr_inclusion_proof: MMRInclusionProof,
+    pub block_inclusion_proof: BlockInclusionProof,
+    pub block_header: Header,
+    pub block_height: u32,
+    /// Number of blocks in the block hash MMR the MMR inclusion proof is for.
+    pub mmr_size: u32,
+}
+
+/// Generates the SPV proof of `txid` against the block hash MMR with
+/// `mmr_size` blocks, or the current block hash MMR if `mmr_size` is `None`.
+///
+/// # Errors
+///
+/// Returns an error if the transaction is not in a canonical block synced by
+/// the Bitcoin syncer, or its block is not in the block hash MMR of the given
+/// size yet.
+pub async fn generate_tx_spv_proof(
+    db: &Database,
+    paramset: &'static ProtocolParamset,
+    txid: Txid,
+    mmr_size: Option<u32>,
+) -> Result<TxSpvProof, BridgeError> {
+    let mut dbtx = db.begin_transaction().await?;
+
+    let block_height = db
+        .get_canonical_block_height_for_txid(Some(&mut dbtx), txid)
+        .await?
+        .ok_or_eyre(format!("Transaction {txid} not found in a canonical block"))?;
+    let leaf_idx = block_height
+        .checked_sub(paramset.genesis_height)
+        .ok_or_eyre(format!(
+            "Block height {block_height} of transaction {txid} is below genesis height {}",
+            paramset.genesis_height
+        ))?;
+
+    let mmr_size = match mmr_size {
+        Some(mmr_size) => mmr_size,
+        None => db.get_block_hash_mmr_size(Some(&mut dbtx)).await?,
+    };
+    if leaf_idx >= mmr_size {
+        return Err(eyre::eyre!(
+            "Block at height {block_height} is not in the block hash MMR with {mmr_size} blocks"
+        )
+        .into());
+    }
+
+    let block = db
+        .get_full_block(Some(&mut dbtx), block_height)
+        .await?
+        .ok_or_eyre(format!("Block at height {block_height} not found"))?;
+    let (leaf, mmr_inclusion_proof) = db
+        .get_block_hash_mmr_proof(Some(&mut dbtx), leaf_idx, mmr_size)
+        .await?;
+    dbtx.commit().await?;
+
+    if leaf != block.block_hash().to_byte_array() {
+        return Err(eyre::eyre!(
+            "Block hash MMR leaf {leaf_idx} doesn't match block {}",
+            block.block_hash()
+        )
+        .into());
+    }
+
+    let tx_idx = block
+        .txdata
+        .iter()
+        .position(|tx| tx.compute_txid() == txid)
+        .ok_or_eyre(format!(
+            "Transaction {txid} not found in block {}",
+            block.block_hash()
+        ))?;
+    let block_txids: Vec<CircuitTransaction> = block
+        .txdata
+        .iter()
+        .map(|tx| CircuitTransaction(tx.clone()))
+        .collect();
+    let block_inclusion_proof =
+        BitcoinMerkleTree::new_mid_state(&block_txids).generate_proof(tx_idx as u32);
+
+    Ok(TxSpvProof {
+        mmr_inclusion_proof,
+        block_inclusion_proof,
+        block_header: block.header,
+        block_height,
+        mmr_size,
+    })
+}
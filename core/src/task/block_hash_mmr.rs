@@ -0,0 +1,118 @@
+//! # Block Hash MMR Task
+//!
+//! Appends the hashes of finalized blocks to the block hash MMR in the
+//! database (see [`crate::database::Database::append_block_hash_mmr_leaf`]).
+//! Leaf `i` of the MMR is the hash of the block at `genesis_height + i`, the
+//! same MMR the header chain circuit builds, so inclusion proofs served from
+//! it can be used in SPV proofs for the bridge circuit.
+//!
+//! Finalized blocks are received from the Bitcoin syncer. Blocks between the
+//! genesis height and the first block received from the syncer are fetched
+//! from Bitcoin RPC.
+
+use bitcoin::hashes::Hash;
+use bitcoincore_rpc::RpcApi;
+use eyre::Context;
+use tonic::async_trait;
+
+use crate::bitcoin_syncer::{BlockHandler, FinalizedBlockFetcherTask};
+use crate::config::protocol::ProtocolParamset;
+use crate::database::{Database, DatabaseTransaction};
+use crate::extended_bitcoin_rpc::ExtendedBitcoinRpc;
+use clementine_errors::BridgeError;
+
+use super::{RecoverableTask, Task, TaskVariant};
+
+pub const BLOCK_HASH_MMR_CONSUMER_ID: &str = "block_hash_mmr";
+
+#[derive(Debug, Clone)]
+pub struct BlockHashMmrUpdater {
+    db: Database,
+    rpc: ExtendedBitcoinRpc,
+    paramset: &'static ProtocolParamset,
+}
+
+#[async_trait]
+impl BlockHandler for BlockHashMmrUpdater {
+    async fn handle_new_block(
+        &mut self,
+        dbtx: DatabaseTransaction<'_>,
+        _block_id: u32,
+        block: bitcoin::Block,
+        height: u32,
+    ) -> Result<(), BridgeError> {
+        let Some(leaf_idx) = height.checked_sub(self.paramset.genesis_height) else {
+            return Ok(());
+        };
+        let mut mmr_size = self.db.get_block_hash_mmr_size(Some(dbtx)).await?;
+        // The block was already appended before a restart.
+        if mmr_size > leaf_idx {
+            return Ok(());
+        }
+
+        while mmr_size < leaf_idx {
+            let missing_height = self.paramset.genesis_height + mmr_size;
+            let block_hash = self
+                .rpc
+                .get_block_hash(missing_height.into())
+                .await
+                .wrap_err(format!(
+                    "Failed to get block hash at height {missing_height}"
+                ))?;
+            self.db
+                .append_block_hash_mmr_leaf(dbtx, block_hash.to_byte_array())
+                .await?;
+            mmr_size += 1;
+        }
+
+        self.db
+            .append_block_hash_mmr_leaf(dbtx, block.block_hash().to_byte_array())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct BlockHashMmrTask(FinalizedBlockFetcherTask<BlockHashMmrUpdater>);
+
+impl BlockHashMmrTask {
+    pub async fn new(
+        db: Database,
+        rpc: ExtendedBitcoinRpc,
+        paramset: &'static ProtocolParamset,
+    ) -> Result<Self, BridgeError> {
+        let next_height = db
+            .get_next_finalized_block_height_for_consumer(
+                None,
+                BLOCK_HASH_MMR_CONSUMER_ID,
+                paramset,
+            )
+            .await?;
+
+        Ok(Self(FinalizedBlockFetcherTask::new(
+            db.clone(),
+            BLOCK_HASH_MMR_CONSUMER_ID.to_string(),
+            paramset,
+            next_height,
+            BlockHashMmrUpdater { db, rpc, paramset },
+        )))
+    }
+}
+
+#[async_trait]
+impl Task for BlockHashMmrTask {
+    type Output = bool;
+    const VARIANT: TaskVariant = TaskVariant::BlockHashMmr;
+
+    async fn run_once(&mut self) -> Result<Self::Output, BridgeError> {
+        self.0.run_once().await
+    }
+}
+
+#[async_trait]
+impl RecoverableTask for BlockHashMmrTask {
+    async fn recover_from_error(&mut self, error: &BridgeError) -> Result<(), BridgeError> {
+        self.0.recover_from_error(error).await
+    }
+}
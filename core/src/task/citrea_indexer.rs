@@ -0,0 +1,531 @@
+//! # Citrea Indexer Task
+//!
+//! Follows Citrea L2 blocks and stores the deposit, withdrawal and deposit
+//! replacement events of the bridge contract in the database (see
+//! [`crate::database::Database::save_citrea_index_range`]). Once the index
+//! has caught up, [`CitreaEventIndex`] serves the bridge event queries of
+//! [`CitreaClient`](crate::citrea::CitreaClient) from it instead of polling
+//! the contract storage for every finalized Bitcoin block.
+//!
+//! The index covers every block from the height it was started at (see
+//! [`crate::database::Database::get_citrea_index_range`]); reads outside that
+//! range go to the Citrea RPC.
+//!
+//! The hashes of every block close to the chain tip, of every block with a
+//! bridge event and of the last block of every indexed range are stored.
+//! Before indexing the next range, the stored tip is compared with the chain.
+//! If it changed, the index is rolled back to the highest stored block that is
+//! still part of the chain and indexing continues from there.
+
+use std::cmp::{max, min};
+use std::collections::BTreeMap;
+
+use alloy::primitives::B256;
+use bitcoin::{OutPoint, Txid};
+use tokio::time::Duration;
+use tonic::async_trait;
+
+use crate::{citrea::CitreaClientT, database::Database};
+use clementine_errors::BridgeError;
+
+use super::{RecoverableTask, Task, TaskVariant};
+
+pub const CITREA_INDEXER_POLL_DELAY: Duration = if cfg!(test) {
+    Duration::from_millis(250)
+} else {
+    Duration::from_secs(5)
+};
+
+/// Maximum number of L2 blocks indexed in one iteration.
+const CITREA_INDEXER_BATCH_SIZE: u64 = 1000;
+
+/// The hash of every indexed block this close to the chain tip is stored.
+const CITREA_INDEXER_RECENT_BLOCKS: u64 = 100;
+
+/// Stored block hashes further than this below the index tip are pruned. A
+/// deeper reorg rebuilds the index.
+const CITREA_INDEXER_KEPT_HEIGHTS: u64 = 10_000;
+
+/// Serves the bridge event queries of [`CitreaClientT`] from the index built
+/// by [`CitreaIndexerTask`].
+///
+/// Every query returns [`None`] if the index can't answer it, in which case
+/// the caller reads the contract over RPC.
+#[derive(Debug, Clone)]
+pub struct CitreaEventIndex {
+    db: Database,
+}
+
+impl CitreaEventIndex {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Indexed version of [`CitreaClientT::collect_deposit_move_txids`].
+    pub async fn collect_deposit_move_txids(
+        &self,
+        last_deposit_idx: Option<u32>,
+        to_height: u64,
+    ) -> Result<Option<Vec<(u64, Txid)>>, BridgeError> {
+        let deposits = self
+            .db
+            .get_indexed_citrea_deposits(None, last_deposit_idx.unwrap_or(0), to_height)
+            .await?;
+        // Read the range after the events, so that a rollback in between is seen.
+        let Some((start, tip)) = self.db.get_citrea_index_range(None).await? else {
+            return Ok(None);
+        };
+
+        Ok((tip >= to_height)
+            .then(|| indexed_events_after(start, last_deposit_idx, deposits))
+            .flatten())
+    }
+
+    /// Indexed version of [`CitreaClientT::collect_withdrawal_utxos`].
+    pub async fn collect_withdrawal_utxos(
+        &self,
+        last_withdrawal_idx: Option<u32>,
+        to_height: u64,
+    ) -> Result<Option<Vec<(u64, OutPoint)>>, BridgeError> {
+        let withdrawals = self
+            .db
+            .get_indexed_citrea_withdrawals(None, last_withdrawal_idx.unwrap_or(0), to_height)
+            .await?;
+        let Some((start, tip)) = self.db.get_citrea_index_range(None).await? else {
+            return Ok(None);
+        };
+
+        Ok((tip >= to_height)
+            .then(|| indexed_events_after(start, last_withdrawal_idx, withdrawals))
+            .flatten())
+    }
+
+    /// Indexed version of [`CitreaClientT::get_replacement_deposit_move_txids`].
+    pub async fn get_replacement_deposit_move_txids(
+        &self,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Option<Vec<(u32, Txid)>>, BridgeError> {
+        let replacements = self
+            .db
+            .get_indexed_citrea_deposit_replacements(None, from_height, to_height)
+            .await?;
+        let Some((start, tip)) = self.db.get_citrea_index_range(None).await? else {
+            return Ok(None);
+        };
+
+        Ok((start <= from_height && tip >= to_height).then_some(replacements))
+    }
+}
+
+/// Returns the deposits or withdrawals after `last_idx` if the index is known
+/// to contain all of them, [`None`] otherwise.
+///
+/// # Parameters
+///
+/// - `start_height`: First L2 height covered by the index, which also covers
+///   every height up to the queried one.
+/// - `indexed`: Indexed events from index `last_idx` on (or from 0), ordered
+///   by index.
+///
+/// The contract assigns indices in order, so events after `last_idx` can only
+/// be missing if they are below `start_height` or if they are not contiguous.
+/// They are not below `start_height` if the index contains event `last_idx`,
+/// the first event after it or starts at height 0.
+fn indexed_events_after<T>(
+    start_height: u64,
+    last_idx: Option<u32>,
+    mut indexed: Vec<(u64, T)>,
+) -> Option<Vec<(u64, T)>> {
+    let anchored = match last_idx {
+        Some(idx)
+            if indexed
+                .first()
+                .is_some_and(|(first, _)| *first == u64::from(idx)) =>
+        {
+            indexed.remove(0);
+            true
+        }
+        Some(_) => false,
+        None => start_height == 0,
+    };
+
+    let first_idx = last_idx.map_or(0, |idx| u64::from(idx) + 1);
+    let contiguous = indexed
+        .iter()
+        .zip(first_idx..)
+        .all(|((idx, _), expected)| *idx == expected);
+
+    (contiguous && (anchored || !indexed.is_empty())).then_some(indexed)
+}
+
+#[derive(Debug, Clone)]
+pub struct CitreaIndexerTask<C: CitreaClientT> {
+    db: Database,
+    citrea_client: C,
+    start_height: u64,
+}
+
+impl<C> CitreaIndexerTask<C>
+where
+    C: CitreaClientT,
+{
+    /// # Parameters
+    ///
+    /// - `start_height`: L2 height to start indexing from if the index is empty.
+    pub fn new(db: Database, citrea_client: C, start_height: u64) -> Self {
+        Self {
+            db,
+            citrea_client,
+            start_height,
+        }
+    }
+
+    async fn block_hash(&self, l2_height: u64) -> Result<Option<B256>, BridgeError> {
+        Ok(self
+            .citrea_client
+            .get_l2_block_ref(l2_height)
+            .await?
+            .map(|block| block.hash))
+    }
+
+    /// Returns the hashes of the blocks in `from_height..=to_height`, or
+    /// [`None`] if they are not a single chain ending at `to_hash`, i.e. the
+    /// chain changed while they were fetched.
+    async fn chain_hashes(
+        &self,
+        from_height: u64,
+        to_height: u64,
+        to_hash: B256,
+    ) -> Result<Option<Vec<(u64, B256)>>, BridgeError> {
+        let mut hashes = vec![];
+        let mut last_hash = None;
+        for height in from_height..=to_height {
+            let Some(block) = self.citrea_client.get_l2_block_ref(height).await? else {
+                return Ok(None);
+            };
+            if last_hash.is_some_and(|hash| hash != block.parent_hash) {
+                return Ok(None);
+            }
+            last_hash = Some(block.hash);
+            hashes.push((height, block.hash));
+        }
+
+        Ok((last_hash == Some(to_hash)).then_some(hashes))
+    }
+
+    /// Rolls the index back to the highest stored block that is still part of
+    /// the chain. If none of them is, the whole index is removed and rebuilt.
+    async fn rollback_to_fork_point(&self) -> Result<(), BridgeError> {
+        let stored_blocks = self.db.list_citrea_index_blocks(None).await?;
+
+        // Stored blocks below the fork point are still part of the chain and
+        // the ones above it are not, so the fork point is binary searched.
+        let (mut canonical, mut reorged) = (0, stored_blocks.len());
+        while canonical < reorged {
+            let mid = canonical + (reorged - canonical) / 2;
+            let (height, hash) = stored_blocks[mid];
+            if self.block_hash(height).await? == Some(hash) {
+                canonical = mid + 1;
+            } else {
+                reorged = mid;
+            }
+        }
+        let fork_point = canonical
+            .checked_sub(1)
+            .map(|last_canonical| stored_blocks[last_canonical].0);
+
+        tracing::warn!(
+            ?fork_point,
+            "Citrea reorg detected, rolling back the Citrea event index"
+        );
+        if fork_point.is_none() {
+            tracing::error!(
+                "Citrea reorg is deeper than all stored index blocks, reindexing from height {}",
+                self.start_height
+            );
+        }
+
+        let mut dbtx = self.db.begin_transaction().await?;
+        self.db.rollback_citrea_index(&mut dbtx, fork_point).await?;
+        dbtx.commit().await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C> Task for CitreaIndexerTask<C>
+where
+    C: CitreaClientT,
+{
+    type Output = bool;
+    const VARIANT: TaskVariant = TaskVariant::CitreaIndexer;
+
+    async fn run_once(&mut self) -> Result<Self::Output, BridgeError> {
+        let chain_tip = u64::from(self.citrea_client.get_current_l2_block_height().await?);
+        let index_tip = self.db.get_citrea_index_tip(None).await?;
+
+        let from_height = match index_tip {
+            Some((height, hash)) => {
+                if self.block_hash(height).await? != Some(hash) {
+                    self.rollback_to_fork_point().await?;
+                    return Ok(true);
+                }
+                height + 1
+            }
+            None => self.start_height,
+        };
+
+        if from_height > chain_tip {
+            return Ok(false);
+        }
+        let to_height = min(from_height + CITREA_INDEXER_BATCH_SIZE - 1, chain_tip);
+
+        let Some(from_block) = self.citrea_client.get_l2_block_ref(from_height).await? else {
+            return Ok(false);
+        };
+        if let Some((_, tip_hash)) = index_tip {
+            if from_block.parent_hash != tip_hash {
+                self.rollback_to_fork_point().await?;
+                return Ok(true);
+            }
+        }
+
+        let Some(to_hash) = self.block_hash(to_height).await? else {
+            return Ok(false);
+        };
+        let events = self
+            .citrea_client
+            .get_bridge_events(from_height, to_height)
+            .await?;
+        // Logs may belong to another fork if the chain changed while they were
+        // fetched. Checking that the recent blocks still lead to `to_hash`
+        // also checks `to_height` itself.
+        let recent_from = max(
+            from_height,
+            (chain_tip + 1).saturating_sub(CITREA_INDEXER_RECENT_BLOCKS),
+        );
+        let Some(recent_hashes) = self
+            .chain_hashes(min(recent_from, to_height), to_height, to_hash)
+            .await?
+        else {
+            tracing::debug!(
+                "Citrea blocks up to {to_height} changed while fetching logs, retrying"
+            );
+            return Ok(true);
+        };
+
+        let mut block_hashes: BTreeMap<u64, B256> = recent_hashes.into_iter().collect();
+        for event in &events {
+            if *block_hashes
+                .entry(event.l2_height)
+                .or_insert(event.block_hash)
+                != event.block_hash
+            {
+                tracing::debug!(
+                    "Citrea block {} changed while fetching logs, retrying",
+                    event.l2_height
+                );
+                return Ok(true);
+            }
+        }
+        let block_hashes: Vec<(u64, B256)> = block_hashes.into_iter().collect();
+
+        if !events.is_empty() {
+            tracing::info!(
+                "Indexed {} Citrea bridge events in L2 blocks {from_height}..={to_height}",
+                events.len()
+            );
+        }
+
+        let mut dbtx = self.db.begin_transaction().await?;
+        self.db
+            .save_citrea_index_range(
+                &mut dbtx,
+                &events,
+                from_height,
+                &block_hashes,
+                CITREA_INDEXER_KEPT_HEIGHTS,
+            )
+            .await?;
+        dbtx.commit().await?;
+
+        Ok(to_height < chain_tip)
+    }
+}
+
+#[async_trait]
+impl<C> RecoverableTask for CitreaIndexerTask<C>
+where
+    C: CitreaClientT,
+{
+    async fn recover_from_error(&mut self, _error: &BridgeError) -> Result<(), BridgeError> {
+        // Every iteration starts from the state in the database, nothing to recover.
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::common::{citrea::MockCitreaClient, create_test_config_with_thread_name};
+    use bitcoin::{hashes::Hash, OutPoint, Txid};
+
+    /// Runs the indexer until it caught up with the mock chain.
+    async fn index_all(task: &mut CitreaIndexerTask<MockCitreaClient>) {
+        while task.run_once().await.unwrap() {}
+    }
+
+    #[test]
+    fn test_indexed_events_after() {
+        assert_eq!(indexed_events_after::<()>(0, None, vec![]), Some(vec![]));
+        assert_eq!(indexed_events_after::<()>(3, None, vec![]), None);
+        assert_eq!(
+            indexed_events_after(3, None, vec![(0, ()), (1, ())]),
+            Some(vec![(0, ()), (1, ())])
+        );
+        assert_eq!(
+            indexed_events_after(3, Some(4), vec![(4, ()), (5, ()), (6, ())]),
+            Some(vec![(5, ()), (6, ())])
+        );
+        assert_eq!(
+            indexed_events_after(3, Some(4), vec![(4, ())]),
+            Some(vec![])
+        );
+        assert_eq!(
+            indexed_events_after(3, Some(4), vec![(5, ())]),
+            Some(vec![(5, ())])
+        );
+        assert_eq!(indexed_events_after::<()>(3, Some(4), vec![]), None);
+        assert_eq!(indexed_events_after(3, None, vec![(1, ())]), None);
+        assert_eq!(
+            indexed_events_after(3, Some(4), vec![(4, ()), (5, ()), (7, ())]),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn index_follows_chain_and_reorgs() {
+        let config = create_test_config_with_thread_name().await;
+        let db = Database::new(&config).await.unwrap();
+        let mut citrea = MockCitreaClient::new(
+            config.citrea_rpc_url.clone(),
+            "".to_string(),
+            config.citrea_chain_id,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let mut task = CitreaIndexerTask::new(db.clone(), citrea.clone(), 3);
+
+        let deposit_0 = Txid::from_byte_array([1; 32]);
+        let deposit_1 = Txid::from_byte_array([2; 32]);
+        let reorged_deposit_1 = Txid::from_byte_array([3; 32]);
+        let utxo = OutPoint::new(Txid::from_byte_array([4; 32]), 0);
+
+        citrea.insert_deposit_move_txid(5, deposit_0).await;
+        citrea.insert_withdrawal_utxo(8, utxo).await;
+        citrea.set_l2_block_height(10).await;
+        index_all(&mut task).await;
+
+        assert_eq!(
+            db.get_citrea_index_range(None).await.unwrap(),
+            Some((3, 10))
+        );
+        assert_eq!(
+            db.get_indexed_citrea_deposits(None, 0, 10).await.unwrap(),
+            vec![(0, deposit_0)]
+        );
+        assert_eq!(
+            db.get_indexed_citrea_withdrawals(None, 0, 10)
+                .await
+                .unwrap(),
+            vec![(0, utxo)]
+        );
+
+        // The index follows the chain as it advances.
+        citrea.insert_deposit_move_txid(15, deposit_1).await;
+        citrea.set_l2_block_height(20).await;
+        index_all(&mut task).await;
+
+        assert_eq!(
+            db.get_citrea_index_range(None).await.unwrap(),
+            Some((3, 20))
+        );
+        assert_eq!(
+            db.get_indexed_citrea_deposits(None, 1, 20).await.unwrap(),
+            vec![(1, deposit_1)]
+        );
+
+        // Queries are served from the index only if it covers them.
+        let index = CitreaEventIndex::new(db.clone());
+        assert_eq!(
+            index.collect_deposit_move_txids(None, 20).await.unwrap(),
+            Some(vec![(0, deposit_0), (1, deposit_1)])
+        );
+        assert_eq!(
+            index.collect_deposit_move_txids(Some(0), 20).await.unwrap(),
+            Some(vec![(1, deposit_1)])
+        );
+        assert_eq!(
+            index.collect_withdrawal_utxos(Some(0), 20).await.unwrap(),
+            Some(vec![])
+        );
+        // Withdrawals before the index start could be missing.
+        assert_eq!(index.collect_withdrawal_utxos(None, 7).await.unwrap(), None);
+        assert_eq!(
+            index.collect_deposit_move_txids(Some(1), 21).await.unwrap(),
+            None
+        );
+        assert_eq!(
+            index
+                .get_replacement_deposit_move_txids(3, 20)
+                .await
+                .unwrap(),
+            Some(vec![])
+        );
+        assert_eq!(
+            index
+                .get_replacement_deposit_move_txids(2, 20)
+                .await
+                .unwrap(),
+            None
+        );
+
+        // Blocks after the fork point are reindexed with the events of the new fork.
+        citrea.reorg(12).await;
+        citrea.insert_deposit_move_txid(13, reorged_deposit_1).await;
+        citrea.set_l2_block_height(25).await;
+        index_all(&mut task).await;
+
+        assert_eq!(
+            db.get_citrea_index_range(None).await.unwrap(),
+            Some((3, 25))
+        );
+        assert_eq!(
+            db.get_indexed_citrea_deposits(None, 0, 25).await.unwrap(),
+            vec![(0, deposit_0), (1, reorged_deposit_1)]
+        );
+        assert_eq!(
+            db.get_indexed_citrea_withdrawals(None, 0, 25)
+                .await
+                .unwrap(),
+            vec![(0, utxo)]
+        );
+
+        // A reorg below every stored block rebuilds the index from the start height.
+        citrea.reorg(2).await;
+        index_all(&mut task).await;
+
+        assert_eq!(
+            db.get_citrea_index_range(None).await.unwrap(),
+            Some((3, 25))
+        );
+        assert_eq!(
+            db.get_indexed_citrea_deposits(None, 0, 25).await.unwrap(),
+            vec![]
+        );
+    }
+}
@@ -59,6 +59,14 @@ impl<T: NamedEntity, C: CitreaClientT> Task for EntityMetricPublisher<T, C> {
             return Ok(false);
         }
 
+        let backend_tips = self
+            .rpc
+            .check_backend_tips(self.config.bitcoin_rpc_max_tip_divergence)
+            .await;
+        LazyLock::force(&ENTITY_SYNC_STATUS)
+            .bitcoin_backend_tip_divergence
+            .set(backend_tips.divergence as f64);
+
         let sync_status = match T::get_sync_status(
             &self.db,
             &self.rpc,
@@ -8,6 +8,8 @@ use tonic::async_trait;
 use clementine_errors::BridgeError;
 
 pub mod aggregator_metric_publisher;
+pub mod block_hash_mmr;
+pub mod citrea_indexer;
 pub mod entity_metric_publisher;
 pub mod lcp_syncer;
 pub mod manager;
@@ -28,6 +30,9 @@ pub enum TaskVariant {
     TxSender,
     BitcoinSyncer,
     TaskStatusMonitor,
+    CitreaIndexer,
+    BlockHashMmr,
+    ProverWorker,
     #[cfg(test)]
     Counter,
     #[cfg(test)]
@@ -1,9 +1,9 @@
 use crate::{
-    citrea::CitreaClientT,
+    citrea::{CitreaBridgeEvent, CitreaBridgeEventKind, CitreaClientT, L2BlockRef},
     config::protocol::ProtocolParamset,
     database::{Database, DatabaseTransaction},
 };
-use alloy::signers::local::PrivateKeySigner;
+use alloy::{primitives::B256, signers::local::PrivateKeySigner};
 use bitcoin::{OutPoint, Txid};
 use circuits_lib::bridge_circuit::structs::{LightClientProof, StorageProof};
 use clementine_errors::BridgeError;
@@ -35,6 +35,9 @@ pub struct MockCitreaStorage {
     name: String,
     deposits: Vec<Deposit>,
     withdrawals: Vec<Withdrawal>,
+    l2_height: u64,
+    /// Heights at which the mock chain was reorged, see [`MockCitreaClient::reorg`].
+    reorg_heights: Vec<u64>,
 }
 
 impl MockCitreaStorage {
@@ -43,8 +46,21 @@ impl MockCitreaStorage {
             name,
             deposits: vec![],
             withdrawals: vec![],
+            l2_height: 0,
+            reorg_heights: vec![],
         }
     }
+
+    /// Block hashes are derived from the height and the number of reorgs
+    /// that replaced the block.
+    fn block_hash(&self, l2_height: u64) -> B256 {
+        let reorgs = self
+            .reorg_heights
+            .iter()
+            .filter(|&&height| height <= l2_height)
+            .count() as u64;
+        alloy::primitives::keccak256([l2_height.to_be_bytes(), reorgs.to_be_bytes()].concat())
+    }
 }
 
 #[allow(clippy::type_complexity)]
@@ -225,7 +241,66 @@ impl CitreaClientT for MockCitreaClient {
     }
 
     async fn get_current_l2_block_height(&self) -> Result<u32, BridgeError> {
-        Ok(0)
+        let storage = self.storage.lock().await;
+        Ok(u32::try_from(storage.l2_height).wrap_err("Mock L2 height doesn't fit in u32")?)
+    }
+
+    async fn get_l2_block_ref(&self, l2_height: u64) -> Result<Option<L2BlockRef>, BridgeError> {
+        let storage = self.storage.lock().await;
+        Ok(Some(L2BlockRef {
+            hash: storage.block_hash(l2_height),
+            parent_hash: storage.block_hash(l2_height.wrapping_sub(1)),
+        }))
+    }
+
+    async fn get_bridge_events(
+        &self,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Vec<CitreaBridgeEvent>, BridgeError> {
+        let storage = self.storage.lock().await;
+        let in_range = |height: u64| from_height <= height && height <= to_height;
+
+        let deposits = storage
+            .deposits
+            .iter()
+            .filter(|deposit| in_range(deposit.height))
+            .map(|deposit| {
+                (
+                    deposit.height,
+                    CitreaBridgeEventKind::Deposit {
+                        idx: deposit.idx,
+                        move_txid: deposit.move_txid,
+                    },
+                )
+            });
+        let withdrawals = storage
+            .withdrawals
+            .iter()
+            .filter(|withdrawal| in_range(withdrawal.height))
+            .map(|withdrawal| {
+                (
+                    withdrawal.height,
+                    CitreaBridgeEventKind::Withdrawal {
+                        idx: withdrawal.idx,
+                        utxo: withdrawal.utxo,
+                    },
+                )
+            });
+
+        let mut events: Vec<CitreaBridgeEvent> = deposits
+            .chain(withdrawals)
+            .enumerate()
+            .map(|(log_index, (height, kind))| CitreaBridgeEvent {
+                l2_height: height,
+                block_hash: storage.block_hash(height),
+                log_index: log_index as u64,
+                kind,
+            })
+            .collect();
+        events.sort_by_key(|event| (event.l2_height, event.log_index));
+
+        Ok(events)
     }
 }
 
@@ -251,6 +326,24 @@ impl MockCitreaClient {
         tracing::debug!("Inserting withdrawal utxo {utxo:?} at height {height} with index {idx}");
         storage.withdrawals.push(Withdrawal { idx, height, utxo });
     }
+
+    /// Sets the height returned by [`CitreaClientT::get_current_l2_block_height`].
+    pub async fn set_l2_block_height(&mut self, height: u64) {
+        self.storage.lock().await.l2_height = height;
+    }
+
+    /// Replaces the blocks from `height` on with a new fork, dropping the
+    /// deposits and withdrawals in them.
+    pub async fn reorg(&mut self, height: u64) {
+        let mut storage = self.storage.lock().await;
+
+        tracing::debug!("Reorging mock Citrea chain from height {height}");
+        storage.reorg_heights.push(height);
+        storage.deposits.retain(|deposit| deposit.height < height);
+        storage
+            .withdrawals
+            .retain(|withdrawal| withdrawal.height < height);
+    }
 }
 
 #[cfg(all(test, feature = "integration-tests"))]
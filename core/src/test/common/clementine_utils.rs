@@ -30,7 +30,7 @@ use super::tx_utils::{
 /// operator_xonly_pk and operator_db should match the operator client ClementineOperatorClient
 #[allow(clippy::too_many_arguments)]
 pub async fn payout_and_start_kickoff(
-    mut operator: ClementineOperatorClient<tonic::transport::Channel>,
+    mut operator: ClementineOperatorClient<crate::rpc::TracedChannel>,
     operator_xonly_pk: XOnlyPublicKey,
     operator_db: &Database,
     withdrawal_id: u32,
@@ -8,7 +8,8 @@ use crate::musig2::AggregateFromPublicKeys;
 use crate::rpc::clementine::clementine_aggregator_client::ClementineAggregatorClient;
 use crate::rpc::clementine::clementine_operator_client::ClementineOperatorClient;
 use crate::rpc::clementine::clementine_verifier_client::ClementineVerifierClient;
-use crate::rpc::get_clients;
+use crate::rpc::interceptors::TraceContextInterceptor;
+use crate::rpc::{get_clients, TracedChannel};
 use crate::servers::{
     create_aggregator_unix_server, create_operator_unix_server, create_verifier_unix_server,
 };
@@ -24,7 +25,7 @@ use super::initialize_database;
 
 #[derive(Debug)]
 pub struct TestVerifier<C: CitreaClientT> {
-    pub verifier: ClementineVerifierClient<Channel>,
+    pub verifier: ClementineVerifierClient<TracedChannel>,
     pub config: BridgeConfig,
     pub shutdown_tx: oneshot::Sender<()>,
     pub socket_path: std::path::PathBuf,
@@ -34,7 +35,7 @@ pub struct TestVerifier<C: CitreaClientT> {
 
 #[derive(Debug)]
 pub struct TestOperator<C: CitreaClientT> {
-    pub operator: ClementineOperatorClient<Channel>,
+    pub operator: ClementineOperatorClient<TracedChannel>,
     pub config: BridgeConfig,
     pub shutdown_tx: oneshot::Sender<()>,
     pub socket_path: std::path::PathBuf,
@@ -116,7 +117,7 @@ impl<C: CitreaClientT> TestVerifier<C> {
 
         let verifier_client = get_clients(
             vec![format!("unix://{}", socket_path.display())],
-            ClementineVerifierClient::new,
+            |channel| ClementineVerifierClient::with_interceptor(channel, TraceContextInterceptor),
             &config_with_new_db,
             false,
         )
@@ -163,7 +164,7 @@ impl<C: CitreaClientT> TestOperator<C> {
 
         let operator_client = get_clients(
             vec![format!("unix://{}", socket_path.display())],
-            ClementineOperatorClient::new,
+            |channel| ClementineOperatorClient::with_interceptor(channel, TraceContextInterceptor),
             &operator_config,
             false,
         )
@@ -309,11 +310,17 @@ impl<C: CitreaClientT> TestActors<C> {
         })
     }
 
-    pub fn get_operator_client_by_index(&self, index: usize) -> ClementineOperatorClient<Channel> {
+    pub fn get_operator_client_by_index(
+        &self,
+        index: usize,
+    ) -> ClementineOperatorClient<TracedChannel> {
         self.operators[&index].operator.clone()
     }
 
-    pub fn get_verifier_client_by_index(&self, index: usize) -> ClementineVerifierClient<Channel> {
+    pub fn get_verifier_client_by_index(
+        &self,
+        index: usize,
+    ) -> ClementineVerifierClient<TracedChannel> {
         self.verifiers[&index].verifier.clone()
     }
 
@@ -346,14 +353,14 @@ impl<C: CitreaClientT> TestActors<C> {
         self.verifiers.get(&index)
     }
 
-    pub fn get_verifiers(&self) -> Vec<ClementineVerifierClient<Channel>> {
+    pub fn get_verifiers(&self) -> Vec<ClementineVerifierClient<TracedChannel>> {
         self.verifiers
             .values()
             .map(|v| v.verifier.clone())
             .collect()
     }
 
-    pub fn get_operators(&self) -> Vec<ClementineOperatorClient<Channel>> {
+    pub fn get_operators(&self) -> Vec<ClementineOperatorClient<TracedChannel>> {
         self.operators
             .values()
             .map(|o| o.operator.clone())
@@ -417,6 +424,17 @@ impl<C: CitreaClientT> TestActors<C> {
         Ok(())
     }
 
+    /// Shuts down the verifier with the given index without restarting the
+    /// aggregator, so the aggregator still has it in its config.
+    pub fn stop_verifier(&mut self, index: usize) -> eyre::Result<()> {
+        let verifier = self
+            .verifiers
+            .remove(&index)
+            .ok_or_else(|| eyre::eyre!("Verifier {} does not exist", index))?;
+        let _ = verifier.shutdown_tx.send(());
+        Ok(())
+    }
+
     /// Remove an operator with the given index and restarts the aggregator with the current actor set.
     pub async fn remove_operator(&mut self, index: usize) -> eyre::Result<()> {
         self.operators.remove(&index);
@@ -38,7 +38,7 @@ async fn test_mtls_connection() -> Result<(), eyre::Report> {
     let endpoint = format!("https://{host}:{port}");
 
     let clients =
-        crate::rpc::get_clients::<ClementineOperatorClient<tonic::transport::Channel>, _>(
+        crate::rpc::get_clients::<ClementineOperatorClient<crate::rpc::TracedChannel>, _>(
             vec![endpoint],
             crate::rpc::operator_client_builder(&config),
             &config,
@@ -6,6 +6,7 @@ use bitcoin::transaction::Version;
 use bitcoin::{Amount, TxOut};
 use bitcoincore_rpc::json::GetRawTransactionResult;
 use bitcoincore_rpc::RpcApi;
+use clementine_tx_sender::db::fee_budget::FeeBudgetRaise;
 use clementine_tx_sender::TxSenderDb;
 use std::ops::Mul;
 use std::sync::Arc;
@@ -1036,6 +1037,214 @@ async fn test_send_rbf() -> Result<(), BridgeError> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_send_rbf_held_by_fee_budget() -> Result<(), BridgeError> {
+    let mut config = create_test_config_with_thread_name().await;
+    let rpc_cleanup = create_regtest_rpc(&mut config).await;
+    let rpc = rpc_cleanup.rpc().clone();
+
+    config.tx_sender_limits.fee_budget.global.max_sats_per_day = Some(1);
+    let (tx_sender, db, signer, network) = create_local_tx_sender(&mut config).await;
+
+    let tx = create_rbf_tx(&rpc, &signer, network, false).await?;
+    let rbf_signing_info = RbfSigningInfo::new(
+        0,
+        RbfSigningSpendPath::KeyPath {
+            tweak_merkle_root: None,
+        },
+        TapSighashType::Default,
+    );
+
+    let mut dbtx = db.begin_transaction().await?;
+    let try_to_send_id = tx_sender
+        .client()
+        .insert_try_to_send(
+            &mut dbtx,
+            None,
+            &tx,
+            FeePayingType::RBF,
+            Some(rbf_signing_info.clone()),
+            &[],
+            &[],
+            &[],
+            &[],
+        )
+        .await?;
+    dbtx.commit().await?;
+
+    let current_fee_rate = tx_sender.get_fee_rate().await?;
+    let current_tip_height = rpc.get_current_chain_height().await?;
+
+    tx_sender
+        .send_rbf_tx(
+            try_to_send_id,
+            tx.clone(),
+            None,
+            current_fee_rate,
+            Some(rbf_signing_info.clone()),
+            current_tip_height,
+            false,
+        )
+        .await
+        .expect("Held RBF tx should not return an error");
+
+    assert!(
+        tx_sender
+            .db
+            .get_last_rbf_txid(None, try_to_send_id)
+            .await?
+            .is_none(),
+        "Transaction over the fee budget should not be sent"
+    );
+    let tx_debug_info = tx_sender.client().debug_tx(try_to_send_id).await?;
+    assert_eq!(tx_debug_info.current_state, "held_fee_budget");
+
+    tx_sender
+        .db
+        .save_fee_budget_raise(
+            None,
+            None,
+            FeeBudgetRaise {
+                extra_per_hour: Amount::ZERO,
+                extra_per_day: Amount::from_sat(10_000_000),
+            },
+            Duration::from_secs(60),
+            Some("test"),
+        )
+        .await?;
+
+    tx_sender
+        .send_rbf_tx(
+            try_to_send_id,
+            tx.clone(),
+            None,
+            current_fee_rate,
+            Some(rbf_signing_info),
+            current_tip_height,
+            false,
+        )
+        .await
+        .expect("RBF should succeed after raising the fee budget");
+
+    let tx_debug_info = tx_sender.client().debug_tx(try_to_send_id).await?;
+    rpc.get_tx_of_txid(&bitcoin::Txid::from_byte_array(
+        tx_debug_info.txid.unwrap().txid.try_into().unwrap(),
+    ))
+    .await
+    .expect("Transaction should be in mempool");
+
+    let spent = tx_sender
+        .db
+        .get_fee_spent_in_window(None, None, Duration::from_secs(60 * 60))
+        .await?;
+    assert!(spent > Amount::ZERO);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_cpfp_fee_payer_held_by_fee_budget() -> Result<(), BridgeError> {
+    let mut config = create_test_config_with_thread_name().await;
+    let rpc_cleanup = create_regtest_rpc(&mut config).await;
+    let rpc = rpc_cleanup.rpc().clone();
+
+    config.tx_sender_limits.fee_budget.global.max_sats_per_day = Some(1);
+    let (tx_sender, db, signer, network) = create_local_tx_sender(&mut config).await;
+
+    let tx = create_bumpable_tx(&rpc, &signer, network, FeePayingType::CPFP, false).await?;
+
+    let mut dbtx = db.begin_transaction().await?;
+    let try_to_send_id = tx_sender
+        .client()
+        .insert_try_to_send(
+            &mut dbtx,
+            None,
+            &tx,
+            FeePayingType::CPFP,
+            None,
+            &[],
+            &[],
+            &[],
+            &[],
+        )
+        .await?;
+    dbtx.commit().await?;
+
+    let fee_rate = tx_sender.get_fee_rate().await?;
+    let current_tip_height = rpc.get_current_chain_height().await?;
+    let send = || {
+        tx_sender.send_cpfp_tx(
+            try_to_send_id,
+            tx.clone(),
+            None,
+            fee_rate,
+            current_tip_height,
+        )
+    };
+
+    // The fee payer funding tx is paid by the wallet and held like any other tx.
+    send()
+        .await
+        .expect("Held fee payer tx should not return an error");
+    assert!(tx_sender
+        .db
+        .get_unconfirmed_fee_payer_txs(None, try_to_send_id)
+        .await?
+        .is_empty());
+    let tx_debug_info = tx_sender.client().debug_tx(try_to_send_id).await?;
+    assert_eq!(tx_debug_info.current_state, "held_fee_budget");
+    assert_eq!(
+        tx_sender
+            .db
+            .get_fee_spent_in_window(None, None, Duration::from_secs(60 * 60))
+            .await?,
+        Amount::ZERO
+    );
+
+    tx_sender
+        .db
+        .save_fee_budget_raise(
+            None,
+            None,
+            FeeBudgetRaise {
+                extra_per_hour: Amount::ZERO,
+                extra_per_day: Amount::from_sat(10_000_000),
+            },
+            Duration::from_secs(60),
+            Some("test"),
+        )
+        .await?;
+
+    send()
+        .await
+        .expect("Fee payer tx should be sent after raising the fee budget");
+    assert_eq!(
+        tx_sender
+            .db
+            .get_unconfirmed_fee_payer_txs(None, try_to_send_id)
+            .await?
+            .len(),
+        1
+    );
+    // The fee payer's fee is counted, but not as a broadcast of the tx itself.
+    assert!(
+        tx_sender
+            .db
+            .get_fee_spent_in_window(None, None, Duration::from_secs(60 * 60))
+            .await?
+            > Amount::ZERO
+    );
+    assert_eq!(
+        tx_sender
+            .db
+            .get_max_recorded_fee(None, try_to_send_id)
+            .await?,
+        Amount::ZERO
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_send_no_funding_tx() -> Result<(), BridgeError> {
     let mut config = create_test_config_with_thread_name().await;
@@ -1093,6 +1302,130 @@ async fn test_send_no_funding_tx() -> Result<(), BridgeError> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_send_no_funding_tx_dry_run() -> Result<(), BridgeError> {
+    let mut config = create_test_config_with_thread_name().await;
+    let rpc_cleanup = create_regtest_rpc(&mut config).await;
+    let rpc = rpc_cleanup.rpc().clone();
+
+    let (_, db, signer, network) = create_local_tx_sender(&mut config).await;
+    let mut tx_sender_config = config.tx_sender_config();
+    tx_sender_config.dry_run = true;
+    let tx_sender = TxSenderWithCore::new(tx_sender_config).await.unwrap();
+
+    let tx = create_rbf_tx(&rpc, &signer, network, false).await?;
+
+    let mut dbtx = db.begin_transaction().await?;
+    let try_to_send_id = tx_sender
+        .client()
+        .insert_try_to_send(
+            &mut dbtx,
+            None,
+            &tx,
+            FeePayingType::NoFunding,
+            None,
+            &[],
+            &[],
+            &[],
+            &[],
+        )
+        .await?;
+    dbtx.commit().await?;
+
+    tx_sender
+        .send_no_funding_tx(try_to_send_id, tx.clone(), None)
+        .await
+        .expect("Dry run should succeed");
+
+    assert!(
+        rpc.get_mempool_entry(&tx.compute_txid()).await.is_err(),
+        "Transaction should not be broadcast in dry run mode"
+    );
+
+    let recorded = tx_sender.db.list_dry_run_broadcasts(None).await?;
+    assert_eq!(recorded.len(), 1);
+    assert_eq!(recorded[0].try_to_send_id, Some(try_to_send_id));
+    assert_eq!(recorded[0].kind, "no_funding");
+    assert_eq!(recorded[0].txid, tx.compute_txid());
+    assert_eq!(recorded[0].mempool_allowed, Some(true));
+    assert!(recorded[0].fee.is_some());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_send_cpfp_tx_dry_run() -> Result<(), BridgeError> {
+    let mut config = create_test_config_with_thread_name().await;
+    config.tx_sender_dry_run = true;
+    let rpc_cleanup = create_regtest_rpc(&mut config).await;
+    let rpc = rpc_cleanup.rpc().clone();
+
+    let (tx_sender, db, signer, network) = create_local_tx_sender(&mut config).await;
+    assert!(tx_sender.is_dry_run());
+
+    let tx = create_bumpable_tx(&rpc, &signer, network, FeePayingType::CPFP, false).await?;
+
+    let mut dbtx = db.begin_transaction().await?;
+    let try_to_send_id = tx_sender
+        .client()
+        .insert_try_to_send(
+            &mut dbtx,
+            None,
+            &tx,
+            FeePayingType::CPFP,
+            None,
+            &[],
+            &[],
+            &[],
+            &[],
+        )
+        .await?;
+    dbtx.commit().await?;
+
+    let fee_rate = tx_sender.get_fee_rate().await?;
+    let current_tip_height = rpc.get_current_chain_height().await?;
+    // Polling twice replaces the records of the first poll.
+    for _ in 0..2 {
+        tx_sender
+            .send_cpfp_tx(
+                try_to_send_id,
+                tx.clone(),
+                None,
+                fee_rate,
+                current_tip_height,
+            )
+            .await
+            .expect("Dry run should succeed");
+    }
+
+    assert!(
+        rpc.get_mempool_entry(&tx.compute_txid()).await.is_err(),
+        "Transaction should not be broadcast in dry run mode"
+    );
+    // No fee payer UTXO is created for the tx.
+    assert!(tx_sender
+        .db
+        .get_unconfirmed_fee_payer_txs(None, try_to_send_id)
+        .await?
+        .is_empty());
+
+    // The fee payer tx, the parent and the CPFP child built with the
+    // synthetic fee payer UTXO are recorded.
+    let recorded = tx_sender.db.list_dry_run_broadcasts(None).await?;
+    let kinds: Vec<&str> = recorded.iter().map(|r| r.kind.as_str()).collect();
+    assert_eq!(
+        kinds,
+        vec!["cpfp_fee_payer", "cpfp_package", "cpfp_package"]
+    );
+    assert!(recorded
+        .iter()
+        .all(|r| r.try_to_send_id == Some(try_to_send_id)));
+    assert_eq!(recorded[1].txid, tx.compute_txid());
+    assert!(recorded[2].fee.is_some());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_bg_send_rbf() -> Result<(), BridgeError> {
     let mut config = create_test_config_with_thread_name().await;
@@ -4,7 +4,7 @@ use clementine_errors::BridgeError;
 use eyre::Context as _;
 use futures::future::join_all;
 use http::HeaderValue;
-use metrics_exporter_prometheus::PrometheusBuilder;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
 use std::fmt::{Debug, Display};
 use std::future::Future;
 use std::net::{Ipv4Addr, SocketAddr};
@@ -16,14 +16,22 @@ use tokio::time::error::Elapsed;
 use tokio::time::timeout;
 use tonic::Status;
 use tower::{Layer, Service};
-use tracing::{debug_span, Instrument};
+use tracing::{debug_span, info_span, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 
 // Re-export types from clementine-utils
 pub use clementine_utils::{
-    tracing::initialize_logger, FeePayingType, Last20Bytes, NamedEntity, RbfSigningInfo,
-    RbfSigningSpendPath, ScriptBufExt, TryLast20Bytes, TxMetadata,
+    tracing::{initialize_logger, shutdown_tracer_provider},
+    FeePayingType, Last20Bytes, NamedEntity, RbfSigningInfo, RbfSigningSpendPath, ScriptBufExt,
+    TryLast20Bytes, TxMetadata,
 };
 
+/// Histogram buckets for durations, from sub-second RPCs to deposits that take
+/// several minutes.
+const DURATION_SECONDS_BUCKETS: &[f64] = &[
+    0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1200.0,
+];
+
 pub fn initialize_telemetry(config: &TelemetryConfig) -> Result<(), BridgeError> {
     let telemetry_addr: SocketAddr = format!("{}:{}", config.host, config.port)
         .parse()
@@ -38,7 +46,13 @@ pub fn initialize_telemetry(config: &TelemetryConfig) -> Result<(), BridgeError>
 
     tracing::debug!("Initializing telemetry at {}", telemetry_addr);
 
-    let builder = PrometheusBuilder::new().with_http_listener(telemetry_addr);
+    let builder = PrometheusBuilder::new()
+        .with_http_listener(telemetry_addr)
+        .set_buckets_for_metric(
+            Matcher::Suffix("duration_seconds".to_string()),
+            DURATION_SECONDS_BUCKETS,
+        )
+        .map_err(|e| eyre::eyre!("Failed to set histogram buckets: {}", e))?;
 
     builder
         .install()
@@ -237,6 +251,10 @@ macro_rules! delayed_panic {
 
 pub(crate) use delayed_panic;
 
+/// Adds the `grpc-method` header used by [`Interceptors`](crate::rpc::interceptors::Interceptors)
+/// and runs each request in a `grpc_request` span. The span continues the
+/// W3C trace context sent by the caller, if any, so a request handled by several
+/// entities is exported as a single trace.
 #[derive(Debug, Clone, Default)]
 pub struct AddMethodMiddlewareLayer;
 
@@ -284,20 +302,42 @@ where
                     None
                 };
 
+            let span = info_span!(
+                "grpc_request",
+                otel.name = path.trim_start_matches('/'),
+                otel.kind = "server",
+                rpc.system = "grpc",
+            );
+            let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+                propagator.extract(&HeaderExtractor(req.headers()))
+            });
+            span.set_parent(parent);
+
             if let Some(grpc_method) = grpc_method {
                 if let Ok(grpc_method) = HeaderValue::from_str(&grpc_method) {
                     req.headers_mut().insert("grpc-method", grpc_method);
                 }
             }
 
-            // Do extra async work here...
-            let response = inner.call(req).await?;
+            let response = inner.call(req).instrument(span).await?;
 
             Ok(response)
         })
     }
 }
 
+struct HeaderExtractor<'a>(&'a http::HeaderMap);
+
+impl opentelemetry::propagation::Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
 // NOTE: NamedEntity, TxMetadata, FeePayingType, RbfSigningInfo,
 // Last20Bytes, TryLast20Bytes, ScriptBufExt are now re-exported
 // from clementine-utils at the top of this file.
@@ -32,16 +32,24 @@ use crate::deposit::{DepositData, KickoffData, OperatorData};
 use crate::extended_bitcoin_rpc::{BridgeRpcQueries, ExtendedBitcoinRpc};
 #[cfg(feature = "automation")]
 use crate::header_chain_prover::HeaderChainProver;
+use crate::health::{entity_readiness, Readiness, ReadinessProvider};
+use crate::membership::{
+    appended_changes, epoch_at, member_schedule, MemberKey, MemberKind, MembershipChange,
+};
 use crate::metrics::SyncStatusProvider;
 use crate::musig2;
 #[cfg(feature = "automation")]
 use crate::operator::Operator;
+#[cfg(feature = "automation")]
+use crate::proving_queue::{default_worker_name, ProverWorker};
 use crate::rpc::clementine::{EntityStatus, NormalSignatureKind, OperatorKeys, TaggedSignature};
 use crate::rpc::ecdsa_verification_sig::{
     recover_address_from_ecdsa_signature, OptimisticPayoutMessage,
 };
 #[cfg(feature = "automation")]
 use crate::states::StateManager;
+use crate::task::block_hash_mmr::BlockHashMmrTask;
+use crate::task::citrea_indexer::{CitreaIndexerTask, CITREA_INDEXER_POLL_DELAY};
 use crate::task::entity_metric_publisher::{
     EntityMetricPublisher, ENTITY_METRIC_PUBLISHER_INTERVAL,
 };
@@ -257,13 +265,7 @@ where
     /// Starts the background tasks for the verifier.
     /// If called multiple times, it will restart only the tasks that are not already running.
     pub async fn start_background_tasks(&self) -> Result<(), BridgeError> {
-        let rpc = ExtendedBitcoinRpc::connect(
-            self.verifier.config.bitcoin_rpc_url.clone(),
-            self.verifier.config.bitcoin_rpc_user.clone(),
-            self.verifier.config.bitcoin_rpc_password.clone(),
-            None,
-        )
-        .await?;
+        let rpc = self.verifier.config.connect_bitcoin_rpc().await?;
 
         // initialize and run automation features
         #[cfg(feature = "automation")]
@@ -319,6 +321,14 @@ where
                     .ensure_task_looping(state_manager.into_task())
                     .await;
             }
+
+            if !self.verifier.config.external_prover_workers {
+                self.background_tasks
+                    .ensure_task_looping(
+                        ProverWorker::new(self.verifier.db.clone(), default_worker_name()).await?,
+                    )
+                    .await;
+            }
         }
 
         self.background_tasks
@@ -335,6 +345,33 @@ where
             )
             .await;
 
+        self.background_tasks
+            .ensure_task_looping(
+                BlockHashMmrTask::new(
+                    self.verifier.db.clone(),
+                    rpc.clone(),
+                    self.verifier.config.protocol_paramset(),
+                )
+                .await?
+                .into_buffered_errors(20, 3, Duration::from_secs(10))
+                .with_delay(crate::bitcoin_syncer::BTC_SYNCER_POLL_DELAY),
+            )
+            .await;
+
+        if self.verifier.config.citrea_indexer {
+            self.background_tasks
+                .ensure_task_looping(
+                    CitreaIndexerTask::new(
+                        self.verifier.db.clone(),
+                        self.verifier.citrea_client.clone(),
+                        self.verifier.config.citrea_indexer_start_height,
+                    )
+                    .into_buffered_errors(20, 3, Duration::from_secs(10))
+                    .with_delay(CITREA_INDEXER_POLL_DELAY),
+                )
+                .await;
+        }
+
         let syncer = BitcoinSyncer::new(
             self.verifier.db.clone(),
             rpc.clone(),
@@ -393,6 +430,23 @@ where
     }
 }
 
+#[tonic::async_trait]
+impl<C> ReadinessProvider for VerifierServer<C>
+where
+    C: CitreaClientT,
+{
+    async fn readiness(&self) -> Readiness {
+        entity_readiness::<Verifier<C>, C>(
+            &self.background_tasks,
+            &self.verifier.db,
+            &self.verifier.rpc,
+            &self.verifier.config,
+            &self.verifier.citrea_client,
+        )
+        .await
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Verifier<C: CitreaClientT> {
     rpc: ExtendedBitcoinRpc,
@@ -415,24 +469,11 @@ where
     pub async fn new(config: BridgeConfig) -> Result<Self, BridgeError> {
         let signer = Actor::new(config.secret_key, config.protocol_paramset().network);
 
-        let rpc = ExtendedBitcoinRpc::connect(
-            config.bitcoin_rpc_url.clone(),
-            config.bitcoin_rpc_user.clone(),
-            config.bitcoin_rpc_password.clone(),
-            None,
-        )
-        .await?;
+        let rpc = config.connect_bitcoin_rpc().await?;
 
         let db = Database::new(&config).await?;
 
-        let citrea_client = C::new(
-            config.citrea_rpc_url.clone(),
-            config.citrea_light_client_prover_url.clone(),
-            config.citrea_chain_id,
-            None,
-            config.citrea_request_timeout,
-        )
-        .await?;
+        let citrea_client = C::from_config(&config).await?;
 
         let all_sessions = AllSessions::new();
 
@@ -528,7 +569,9 @@ where
         Ok(tagged_sigs)
     }
 
-    /// Checks if all operators in verifier's db that are still in protocol are in the deposit.
+    /// Checks if the verifiers and operators in the deposit are members in the
+    /// membership epoch at the height of the deposit tx.
+    /// Checks if all operators of the epoch in verifier's db that are still in protocol are in the deposit.
     /// Checks if all operators in the deposit data from aggregator are in the verifier's DB.
     /// Afterwards, it checks if the given deposit outpoint is valid. First it checks if the tx exists on chain,
     /// then it checks if the amount in TxOut is equal to bridge_amount and if the script is correct.
@@ -600,8 +643,64 @@ where
             return Err(BridgeError::InvalidDeposit(reason));
         }
 
+        // check the verifiers and operators against the membership epoch at the deposit height
+        let tx_info = self
+            .rpc
+            .get_raw_transaction_info(&deposit_data.get_deposit_outpoint().txid, None)
+            .await
+            .wrap_err("Failed to get deposit transaction info")?;
+        let blockhash = tx_info.blockhash.ok_or_else(|| {
+            BridgeError::InvalidDeposit("Deposit transaction is not confirmed".to_string())
+        })?;
+        let block_height = u32::try_from(
+            self.rpc
+                .get_block_info(&blockhash)
+                .await
+                .wrap_err(format!(
+                    "Failed to get block info for deposit tx block hash: {blockhash}",
+                ))?
+                .height,
+        )
+        .wrap_err("Deposit block height overflow")?;
+        let membership_changes = self.db.get_verifier_membership_changes(None).await?;
+        let epoch = epoch_at(&membership_changes, block_height);
+        for verifier_pk in deposit_data.get_verifiers() {
+            if !member_schedule(
+                &membership_changes,
+                MemberKind::Verifier,
+                &verifier_pk.to_member_bytes(),
+            )
+            .is_active(block_height)
+            {
+                let reason = format!(
+                    "Verifier {verifier_pk} in deposit is not a member in epoch {epoch} at deposit height {block_height}"
+                );
+                tracing::error!("{reason}");
+                return Err(BridgeError::InvalidDeposit(reason));
+            }
+        }
+        let is_active_operator = |xonly_pk: &XOnlyPublicKey| {
+            member_schedule(
+                &membership_changes,
+                MemberKind::Operator,
+                &xonly_pk.to_member_bytes(),
+            )
+            .is_active(block_height)
+        };
+
         let operators_in_deposit_data = deposit_data.get_operators();
-        // check if all operators that still have collateral are in the deposit
+        for xonly_pk in operators_in_deposit_data.iter() {
+            if !is_active_operator(xonly_pk) {
+                let reason = format!(
+                    "Operator {xonly_pk:?} in deposit is not a member in epoch {epoch} at deposit height {block_height}"
+                );
+                tracing::error!("{reason}");
+                return Err(BridgeError::InvalidDeposit(reason));
+            }
+        }
+        // check if all operators that still have collateral are in the deposit. Operator
+        // removals sent by the aggregator don't exempt an operator, only spending its
+        // collateral, which the operator has to sign itself, does.
         let operators_in_db = self.db.get_operators(None).await?;
         for (xonly_pk, reimburse_addr, collateral_funding_outpoint) in operators_in_db.iter() {
             let operator_data = OperatorData {
@@ -704,24 +803,8 @@ where
             return Err(BridgeError::InvalidDeposit(reason));
         }
         // check if deposit outpoint is included in a block with height >= start_height
-        let tx_info = self
-            .rpc
-            .get_raw_transaction_info(&deposit_txid, None)
-            .await
-            .wrap_err("Failed to get deposit transaction info")?;
-        let blockhash = tx_info.blockhash.ok_or_else(|| {
-            BridgeError::InvalidDeposit("Deposit transaction is not confirmed".to_string())
-        })?;
-        let block_height = self
-            .rpc
-            .get_block_info(&blockhash)
-            .await
-            .wrap_err(format!(
-                "Failed to get block info for deposit tx block hash: {blockhash}",
-            ))?
-            .height;
         let start_height = self.config.protocol_paramset().start_height;
-        if (block_height as u32) < start_height {
+        if block_height < start_height {
             let reason = format!(
                 "Deposit transaction is included in a block with height {block_height} which is less than start_height {start_height}",
             );
@@ -731,6 +814,30 @@ where
         Ok(())
     }
 
+    /// Saves the membership changes sent by the aggregator. The changes
+    /// received before must be unchanged, see [`appended_changes`].
+    pub async fn set_membership_changes(
+        &self,
+        changes: Vec<MembershipChange>,
+    ) -> Result<(), BridgeError> {
+        let mut dbtx = self.db.begin_transaction().await?;
+        let stored = self
+            .db
+            .get_verifier_membership_changes(Some(&mut dbtx))
+            .await?;
+        let new_changes = appended_changes(&stored, &changes)?;
+        tracing::info!(
+            "Appending {} membership changes, last epoch {:?}",
+            new_changes.len(),
+            changes.last().map(|change| change.epoch)
+        );
+        self.db
+            .insert_verifier_membership_changes(&mut dbtx, new_changes)
+            .await?;
+        dbtx.commit().await?;
+        Ok(())
+    }
+
     pub async fn set_operator(
         &self,
         collateral_funding_outpoint: OutPoint,
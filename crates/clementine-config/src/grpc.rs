@@ -1,6 +1,7 @@
 //! gRPC configuration.
 
 use serde::Deserialize;
+use std::str::FromStr;
 
 /// gRPC client/server limits configuration.
 #[derive(Debug, Clone, Deserialize, PartialEq)]
@@ -17,6 +18,59 @@ pub struct GrpcLimits {
     pub ratelimit_req_count: usize,
     /// Rate limit request interval in seconds.
     pub ratelimit_req_interval_secs: u64,
+    /// Supported message compressions, in order of preference. Requests are
+    /// only compressed with an encoding the peer advertised in its
+    /// compatibility params. An empty list disables compression.
+    #[serde(default = "default_compression")]
+    pub compression: Vec<GrpcCompression>,
+    /// Maximum number of signatures peers may pack into one message of a
+    /// signing stream sent to us. `0` or `1` asks for one signature per
+    /// message.
+    #[serde(default = "default_max_sigs_per_message")]
+    pub max_sigs_per_message: u32,
+}
+
+/// Compression encodings for gRPC messages.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GrpcCompression {
+    Gzip,
+    Zstd,
+}
+
+impl GrpcCompression {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GrpcCompression::Gzip => "gzip",
+            GrpcCompression::Zstd => "zstd",
+        }
+    }
+}
+
+impl std::fmt::Display for GrpcCompression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for GrpcCompression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "gzip" => Ok(GrpcCompression::Gzip),
+            "zstd" => Ok(GrpcCompression::Zstd),
+            other => Err(format!("Unknown gRPC compression: {other}")),
+        }
+    }
+}
+
+fn default_compression() -> Vec<GrpcCompression> {
+    vec![GrpcCompression::Zstd, GrpcCompression::Gzip]
+}
+
+fn default_max_sigs_per_message() -> u32 {
+    256
 }
 
 impl Default for GrpcLimits {
@@ -28,6 +82,8 @@ impl Default for GrpcLimits {
             req_concurrency_limit: 300, // 100 deposits at the same time
             ratelimit_req_count: 1000,
             ratelimit_req_interval_secs: 60,
+            compression: default_compression(),
+            max_sigs_per_message: default_max_sigs_per_message(),
         }
     }
 }
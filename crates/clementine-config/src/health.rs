@@ -0,0 +1,35 @@
+//! Health endpoint configuration.
+
+use serde::Deserialize;
+
+/// Configuration for the HTTP health and readiness endpoints.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HealthConfig {
+    /// Host address for the health server.
+    pub host: String,
+    /// Port number for the health server.
+    pub port: u16,
+    /// Maximum number of blocks the Bitcoin syncer may lag behind the
+    /// Bitcoin RPC tip before the entity is reported as not ready.
+    #[serde(default = "default_max_sync_lag_blocks")]
+    pub max_sync_lag_blocks: u32,
+    /// Minimum wallet balance in satoshis. Below this, the entity is reported
+    /// as not ready. Zero disables the check.
+    #[serde(default)]
+    pub min_wallet_balance_sats: u64,
+}
+
+fn default_max_sync_lag_blocks() -> u32 {
+    6
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            host: "0.0.0.0".to_string(),
+            port: 8082,
+            max_sync_lag_blocks: default_max_sync_lag_blocks(),
+            min_wallet_balance_sats: 0,
+        }
+    }
+}
@@ -1,6 +1,8 @@
 //! Transaction sender configuration.
 
-use serde::Deserialize;
+use clementine_primitives::TransactionType;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
 
 /// Transaction sender limits and fee configuration.
 #[derive(Debug, Clone, Deserialize, PartialEq)]
@@ -18,6 +20,9 @@ pub struct TxSenderLimits {
     pub fee_bump_after_blocks: u32,
     /// Minimum fee bump increment in sat/kvB. If current fee rate is smaller than previously sent fee rate + min_bump_kvb, we do not bump at all. This is so that we do not do tiny fee bumps constantly.
     pub min_bump_kvb: u64,
+    /// Rolling budget on the fees spent by the tx sender. Disabled by default.
+    #[serde(default)]
+    pub fee_budget: TxSenderFeeBudget,
 }
 
 impl Default for TxSenderLimits {
@@ -30,6 +35,74 @@ impl Default for TxSenderLimits {
             fee_bump_after_blocks: 10,
             // 0.2 sat/vB ~= 200 sat/kvB
             min_bump_kvb: 200,
+            fee_budget: TxSenderFeeBudget::default(),
         }
     }
 }
+
+/// Maximum amount of fees in sats that can be spent in a rolling window.
+/// `None` means no limit for that window.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub struct FeeBudgetLimits {
+    #[serde(default)]
+    pub max_sats_per_hour: Option<u64>,
+    #[serde(default)]
+    pub max_sats_per_day: Option<u64>,
+}
+
+impl FeeBudgetLimits {
+    pub fn is_limited(&self) -> bool {
+        self.max_sats_per_hour.is_some() || self.max_sats_per_day.is_some()
+    }
+}
+
+/// Fee spending budget of the tx sender.
+///
+/// The budget is checked before every RBF and CPFP broadcast. Transactions
+/// that would exceed it are held until enough of the window has passed or
+/// the budget is raised temporarily.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+pub struct TxSenderFeeBudget {
+    /// Limits on the total fees of all transactions.
+    #[serde(default)]
+    pub global: FeeBudgetLimits,
+    /// Limits per transaction type, keyed by the `TransactionType` name
+    /// without its index (e.g. `Kickoff`, `WatchtowerChallenge`). Unknown
+    /// names are rejected.
+    #[serde(default, deserialize_with = "deserialize_per_tx_type")]
+    pub per_tx_type: BTreeMap<String, FeeBudgetLimits>,
+}
+
+/// Returns an error for the first key of `per_tx_type` that is not a
+/// [`TransactionType::name`], as a typo would silently leave a tx type
+/// without its limit.
+pub fn check_per_tx_type_keys(
+    per_tx_type: &BTreeMap<String, FeeBudgetLimits>,
+) -> Result<(), String> {
+    match per_tx_type
+        .keys()
+        .find(|key| !TransactionType::is_name(key))
+    {
+        Some(key) => Err(format!(
+            "Unknown transaction type {key:?} in the fee budget, expected one of {:?}",
+            TransactionType::ALL.map(|tx_type| tx_type.name())
+        )),
+        None => Ok(()),
+    }
+}
+
+fn deserialize_per_tx_type<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, FeeBudgetLimits>, D::Error> {
+    let per_tx_type = BTreeMap::deserialize(deserializer)?;
+    check_per_tx_type_keys(&per_tx_type).map_err(serde::de::Error::custom)?;
+
+    Ok(per_tx_type)
+}
+
+impl TxSenderFeeBudget {
+    /// Returns true if any limit is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.global.is_limited() || self.per_tx_type.values().any(FeeBudgetLimits::is_limited)
+    }
+}
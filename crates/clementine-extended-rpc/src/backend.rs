@@ -0,0 +1,199 @@
+//! Multiple bitcoind backends with health scoring and failover.
+//!
+//! The first backend is the primary one. It owns the wallet, so wallet calls
+//! are always routed to it. Other calls go to the healthiest backend and fail
+//! over to the next one on connection errors.
+
+use bitcoincore_rpc::{Client, RpcApi};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::retry::RetryableError;
+
+/// RPC commands that depend on the wallet of the node and can't be served by
+/// another backend.
+const WALLET_COMMANDS: &[&str] = &[
+    "abandontransaction",
+    "abortrescan",
+    "backupwallet",
+    "bumpfee",
+    "createwallet",
+    "fundrawtransaction",
+    "getaddressesbylabel",
+    "getaddressinfo",
+    "getbalance",
+    "getbalances",
+    "getnewaddress",
+    "getrawchangeaddress",
+    "getreceivedbyaddress",
+    "getreceivedbylabel",
+    "gettransaction",
+    "getunconfirmedbalance",
+    "getwalletinfo",
+    "importaddress",
+    "importdescriptors",
+    "importmulti",
+    "importprivkey",
+    "importpubkey",
+    "keypoolrefill",
+    "listaddressgroupings",
+    "listdescriptors",
+    "listlabels",
+    "listlockunspent",
+    "listreceivedbyaddress",
+    "listsinceblock",
+    "listtransactions",
+    "listunspent",
+    "listwallets",
+    "loadwallet",
+    "lockunspent",
+    "psbtbumpfee",
+    "rescanblockchain",
+    "send",
+    "sendall",
+    "sendmany",
+    "sendtoaddress",
+    "setlabel",
+    "settxfee",
+    "signmessage",
+    "signrawtransactionwithwallet",
+    "unloadwallet",
+    "walletcreatefundedpsbt",
+    "walletlock",
+    "walletpassphrase",
+    "walletprocesspsbt",
+];
+
+/// Returns true if `cmd` must be sent to the node that owns the wallet.
+pub(crate) fn is_wallet_command(cmd: &str) -> bool {
+    WALLET_COMMANDS.contains(&cmd)
+}
+
+/// Connection settings of a bitcoind backend.
+#[derive(Clone, Debug, Deserialize)]
+pub struct BitcoindBackend {
+    pub url: String,
+    pub user: SecretString,
+    pub password: SecretString,
+}
+
+impl PartialEq for BitcoindBackend {
+    fn eq(&self, other: &Self) -> bool {
+        self.url == other.url
+            && self.user.expose_secret() == other.user.expose_secret()
+            && self.password.expose_secret() == other.password.expose_secret()
+    }
+}
+
+/// Tip heights reported by the backends, see
+/// [`crate::ExtendedBitcoinRpc::check_backend_tips`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackendTips {
+    /// URL and reported tip height of each backend, [`None`] if the backend
+    /// did not respond.
+    pub heights: Vec<(String, Option<u32>)>,
+    /// Difference between the highest and lowest reported tip.
+    pub divergence: u32,
+}
+
+#[derive(Debug, Default)]
+struct BackendHealth {
+    /// Number of failed requests since the last successful one. Backends with
+    /// fewer failures are preferred.
+    consecutive_failures: u32,
+}
+
+pub(crate) struct Backend {
+    pub(crate) url: String,
+    pub(crate) client: Arc<Client>,
+    health: Mutex<BackendHealth>,
+}
+
+/// The backends of an [`crate::ExtendedBitcoinRpc`], primary first.
+pub(crate) struct Backends(Vec<Backend>);
+
+impl Backends {
+    pub(crate) fn new(backends: Vec<(String, Arc<Client>)>) -> Self {
+        Self(
+            backends
+                .into_iter()
+                .map(|(url, client)| Backend {
+                    url,
+                    client,
+                    health: Mutex::new(BackendHealth::default()),
+                })
+                .collect(),
+        )
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Backend> {
+        self.0.iter()
+    }
+
+    fn health(&self, idx: usize) -> MutexGuard<'_, BackendHealth> {
+        self.0[idx]
+            .health
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn record_success(&self, idx: usize) {
+        self.health(idx).consecutive_failures = 0;
+    }
+
+    pub(crate) fn record_failure(&self, idx: usize) {
+        let mut health = self.health(idx);
+        health.consecutive_failures = health.consecutive_failures.saturating_add(1);
+    }
+
+    /// Returns backend indexes from the healthiest to the least healthy one.
+    /// Ties are broken by configuration order.
+    pub(crate) fn order(&self) -> Vec<usize> {
+        let mut order: Vec<(u32, usize)> = (0..self.0.len())
+            .map(|idx| (self.health(idx).consecutive_failures, idx))
+            .collect();
+        order.sort_unstable();
+        order.into_iter().map(|(_, idx)| idx).collect()
+    }
+
+    /// Sends a non-wallet call to the backends in health order until one of
+    /// them responds. Errors that are not retryable are returned as is, as
+    /// they come from a backend that is up.
+    pub(crate) async fn call_with_failover<T: for<'a> serde::de::Deserialize<'a>>(
+        &self,
+        cmd: &str,
+        args: &[serde_json::Value],
+    ) -> Result<T, bitcoincore_rpc::Error> {
+        let mut last_error = None;
+        for idx in self.order() {
+            let backend = &self.0[idx];
+            match backend.client.call(cmd, args).await {
+                Ok(result) => {
+                    self.record_success(idx);
+                    return Ok(result);
+                }
+                Err(e) if e.is_retryable() => {
+                    tracing::warn!(
+                        "Bitcoin RPC backend {} failed {cmd}, trying the next one: {e}",
+                        backend.url
+                    );
+                    self.record_failure(idx);
+                    last_error = Some(e);
+                }
+                Err(e) => {
+                    self.record_success(idx);
+                    return Err(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or(bitcoincore_rpc::Error::ReturnedError(
+            "No Bitcoin RPC backend configured".to_string(),
+        )))
+    }
+}
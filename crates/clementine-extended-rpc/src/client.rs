@@ -16,6 +16,8 @@ use tokio::sync::RwLock;
 use tokio::time::timeout;
 use tokio_retry::RetryIf;
 
+use crate::backend::{is_wallet_command, Backends};
+pub use crate::backend::{BackendTips, BitcoindBackend};
 pub use crate::retry::{RetryConfig, RetryableError};
 
 /// Result type for RPC operations.
@@ -25,10 +27,15 @@ type Result<T> = std::result::Result<T, BitcoinRPCError>;
 ///
 /// Provides useful wrapper functions for common operations, as well as
 /// direct access to Bitcoin RPC through the `RpcApi` trait.
+///
+/// Several bitcoind backends can be given with
+/// [`ExtendedBitcoinRpc::connect_backends`]. Wallet calls always go to the
+/// primary backend, other calls fail over between backends.
 #[derive(Clone)]
 pub struct ExtendedBitcoinRpc {
     url: String,
     client: Arc<Client>,
+    backends: Arc<Backends>,
     retry_config: RetryConfig,
 
     #[cfg(any(test, feature = "test-utils"))]
@@ -39,6 +46,10 @@ impl std::fmt::Debug for ExtendedBitcoinRpc {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("ExtendedBitcoinRpc")
             .field("url", &self.url)
+            .field(
+                "backends",
+                &self.backends.iter().map(|b| &b.url).collect::<Vec<_>>(),
+            )
             .finish()
     }
 }
@@ -106,9 +117,11 @@ impl ExtendedBitcoinRpc {
                     .await
                     .map_err(|e| eyre::eyre!("Failed to ping Bitcoin RPC: {}", e))?;
 
+                let client = Arc::new(rpc);
                 let result: Result<ExtendedBitcoinRpc> = Ok(Self {
                     url: url_clone.clone(),
-                    client: Arc::new(rpc),
+                    backends: Arc::new(Backends::new(vec![(url_clone.clone(), client.clone())])),
+                    client,
                     retry_config,
                     #[cfg(any(test, feature = "test-utils"))]
                     cached_mining_address: Arc::new(RwLock::new(None)),
@@ -132,6 +145,110 @@ impl ExtendedBitcoinRpc {
         .await
     }
 
+    /// Connects to several bitcoind backends.
+    ///
+    /// The first backend is the primary one: it must be reachable and it owns
+    /// the wallet used by wallet calls. The other backends only serve
+    /// non-wallet calls when a backend with a better health score fails, so
+    /// they are allowed to be down while connecting.
+    ///
+    /// # Errors
+    ///
+    /// - [`BitcoinRPCError`]: If no backend is given, the primary can't be
+    ///   reached or a client can't be created for a fallback backend.
+    pub async fn connect_backends(
+        backends: Vec<BitcoindBackend>,
+        retry_config: Option<RetryConfig>,
+    ) -> Result<Self> {
+        let mut backends = backends.into_iter();
+        let primary = backends
+            .next()
+            .ok_or_eyre("At least one Bitcoin RPC backend is required")?;
+
+        let mut rpc =
+            Self::connect(primary.url, primary.user, primary.password, retry_config).await?;
+
+        let mut clients = vec![(rpc.url.clone(), rpc.client.clone())];
+        let mut unreachable = vec![];
+        for backend in backends {
+            let auth = Auth::UserPass(
+                backend.user.expose_secret().to_string(),
+                backend.password.expose_secret().to_string(),
+            );
+            let client = Client::new(&backend.url, auth).await.wrap_err_with(|| {
+                format!("Failed to create Bitcoin RPC client for {}", backend.url)
+            })?;
+
+            if let Err(e) = client.ping().await {
+                tracing::warn!(
+                    "Fallback Bitcoin RPC backend {} is unreachable: {e}",
+                    backend.url
+                );
+                unreachable.push(clients.len());
+            }
+            clients.push((backend.url, Arc::new(client)));
+        }
+
+        let backends = Backends::new(clients);
+        for idx in unreachable {
+            backends.record_failure(idx);
+        }
+        rpc.backends = Arc::new(backends);
+
+        Ok(rpc)
+    }
+
+    /// Queries the tip of every backend and raises an alarm if they are more
+    /// than `max_divergence` blocks apart. Backends behind the highest tip by
+    /// more than `max_divergence` get a worse health score, the others are
+    /// marked healthy again. Calling this periodically lets a backend that
+    /// failed earlier, like the primary, be preferred again once it recovers.
+    pub async fn check_backend_tips(&self, max_divergence: u32) -> BackendTips {
+        let mut heights = Vec::with_capacity(self.backends.len());
+        for (idx, backend) in self.backends.iter().enumerate() {
+            let height = match backend.client.get_block_count().await {
+                Ok(height) => u32::try_from(height).ok(),
+                Err(e) => {
+                    tracing::warn!(
+                        "Bitcoin RPC backend {} did not report its tip: {e}",
+                        backend.url
+                    );
+                    self.backends.record_failure(idx);
+                    None
+                }
+            };
+            heights.push((backend.url.clone(), height));
+        }
+
+        let reported = heights.iter().filter_map(|(_, height)| *height);
+        let divergence = match (reported.clone().max(), reported.min()) {
+            (Some(max), Some(min)) => max - min,
+            _ => 0,
+        };
+
+        if divergence > max_divergence {
+            tracing::error!(
+                "Bitcoin RPC backend tips diverged by {divergence} blocks (threshold {max_divergence}): {heights:?}"
+            );
+        }
+
+        let max = heights.iter().filter_map(|(_, height)| *height).max();
+        for (idx, (_, height)) in heights.iter().enumerate() {
+            if let (Some(height), Some(max)) = (height, max) {
+                if max - height > max_divergence {
+                    self.backends.record_failure(idx);
+                } else {
+                    self.backends.record_success(idx);
+                }
+            }
+        }
+
+        BackendTips {
+            heights,
+            divergence,
+        }
+    }
+
     /// Returns the URL of the RPC server.
     pub fn url(&self) -> &str {
         &self.url
@@ -663,6 +780,7 @@ impl ExtendedBitcoinRpc {
         Ok(Self {
             url: self.url.clone(),
             client: self.client.clone(),
+            backends: self.backends.clone(),
             retry_config: self.retry_config.clone(),
             #[cfg(any(test, feature = "test-utils"))]
             cached_mining_address: self.cached_mining_address.clone(),
@@ -935,9 +1053,26 @@ impl RpcApi for ExtendedBitcoinRpc {
 
         let condition = |error: &bitcoincore_rpc::Error| error.is_retryable();
 
+        // The wallet only exists on the primary backend.
+        if self.backends.len() == 1 || is_wallet_command(cmd) {
+            return RetryIf::spawn(
+                strategy,
+                || async {
+                    let result = self.client.call(cmd, args).await;
+                    match &result {
+                        Err(e) if e.is_retryable() => self.backends.record_failure(0),
+                        _ => self.backends.record_success(0),
+                    }
+                    result
+                },
+                condition,
+            )
+            .await;
+        }
+
         RetryIf::spawn(
             strategy,
-            || async { self.client.call(cmd, args).await },
+            || self.backends.call_with_failover(cmd, args),
             condition,
         )
         .await
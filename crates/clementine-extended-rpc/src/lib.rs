@@ -3,10 +3,12 @@
 //! This crate provides [`ExtendedBitcoinRpc`], a wrapper around the Bitcoin RPC client
 //! that includes retry logic for transient errors and utility methods for common operations.
 
+mod backend;
 mod client;
 mod retry;
 
 pub use clementine_errors::BitcoinRPCError;
 pub use client::{
-    get_fee_rate_from_mempool_space, ExtendedBitcoinRpc, RetryConfig, RetryableError,
+    get_fee_rate_from_mempool_space, BackendTips, BitcoindBackend, ExtendedBitcoinRpc, RetryConfig,
+    RetryableError,
 };
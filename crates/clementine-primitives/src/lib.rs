@@ -356,6 +356,79 @@ pub enum TransactionType {
     Dummy,
 }
 
+impl TransactionType {
+    /// Every transaction type, with index 0 for the indexed ones.
+    pub const ALL: [TransactionType; 27] = [
+        TransactionType::AssertTimeout(0),
+        TransactionType::BurnUnusedKickoffConnectors,
+        TransactionType::Challenge,
+        TransactionType::ChallengeTimeout,
+        TransactionType::Disprove,
+        TransactionType::DisproveTimeout,
+        TransactionType::EmergencyStop,
+        TransactionType::Kickoff,
+        TransactionType::KickoffNotFinalized,
+        TransactionType::LatestBlockhash,
+        TransactionType::LatestBlockhashTimeout,
+        TransactionType::MiniAssert(0),
+        TransactionType::MoveToVault,
+        TransactionType::OperatorChallengeAck(0),
+        TransactionType::OperatorChallengeNack(0),
+        TransactionType::OptimisticPayout,
+        TransactionType::Payout,
+        TransactionType::ReadyToReimburse,
+        TransactionType::Reimburse,
+        TransactionType::ReplacementDeposit,
+        TransactionType::Round,
+        TransactionType::UnspentKickoff(0),
+        TransactionType::WatchtowerChallenge(0),
+        TransactionType::WatchtowerChallengeTimeout(0),
+        TransactionType::AllNeededForDeposit,
+        TransactionType::YieldKickoffTxid,
+        TransactionType::Dummy,
+    ];
+
+    /// Name of the transaction type without its index, e.g.
+    /// `WatchtowerChallenge` for `WatchtowerChallenge(3)`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransactionType::AssertTimeout(_) => "AssertTimeout",
+            TransactionType::BurnUnusedKickoffConnectors => "BurnUnusedKickoffConnectors",
+            TransactionType::Challenge => "Challenge",
+            TransactionType::ChallengeTimeout => "ChallengeTimeout",
+            TransactionType::Disprove => "Disprove",
+            TransactionType::DisproveTimeout => "DisproveTimeout",
+            TransactionType::EmergencyStop => "EmergencyStop",
+            TransactionType::Kickoff => "Kickoff",
+            TransactionType::KickoffNotFinalized => "KickoffNotFinalized",
+            TransactionType::LatestBlockhash => "LatestBlockhash",
+            TransactionType::LatestBlockhashTimeout => "LatestBlockhashTimeout",
+            TransactionType::MiniAssert(_) => "MiniAssert",
+            TransactionType::MoveToVault => "MoveToVault",
+            TransactionType::OperatorChallengeAck(_) => "OperatorChallengeAck",
+            TransactionType::OperatorChallengeNack(_) => "OperatorChallengeNack",
+            TransactionType::OptimisticPayout => "OptimisticPayout",
+            TransactionType::Payout => "Payout",
+            TransactionType::ReadyToReimburse => "ReadyToReimburse",
+            TransactionType::Reimburse => "Reimburse",
+            TransactionType::ReplacementDeposit => "ReplacementDeposit",
+            TransactionType::Round => "Round",
+            TransactionType::UnspentKickoff(_) => "UnspentKickoff",
+            TransactionType::WatchtowerChallenge(_) => "WatchtowerChallenge",
+            TransactionType::WatchtowerChallengeTimeout(_) => "WatchtowerChallengeTimeout",
+            TransactionType::AllNeededForDeposit => "AllNeededForDeposit",
+            TransactionType::YieldKickoffTxid => "YieldKickoffTxid",
+            TransactionType::Dummy => "Dummy",
+        }
+    }
+
+    /// Returns true if `name` is the [`TransactionType::name`] of a
+    /// transaction type.
+    pub fn is_name(name: &str) -> bool {
+        Self::ALL.iter().any(|tx_type| tx_type.name() == name)
+    }
+}
+
 /// Events emitted by the Bitcoin syncer.
 /// It emits the block_id of the block in the db that was saved.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -402,6 +475,22 @@ mod tests {
         assert_eq!(RoundIndex::Round(0).next_round(), RoundIndex::Round(1));
     }
 
+    #[test]
+    fn test_transaction_type_names() {
+        for tx_type in TransactionType::ALL {
+            let debug = format!("{tx_type:?}");
+            assert_eq!(tx_type.name(), debug.split('(').next().unwrap());
+            assert!(TransactionType::is_name(tx_type.name()));
+        }
+        let names: std::collections::BTreeSet<_> = TransactionType::ALL
+            .iter()
+            .map(TransactionType::name)
+            .collect();
+        assert_eq!(names.len(), TransactionType::ALL.len());
+        assert!(!TransactionType::is_name("kickoff"));
+        assert!(!TransactionType::is_name("WatchtowerChallenge(0)"));
+    }
+
     #[test]
     fn test_round_index_iter() {
         let rounds: Vec<_> = RoundIndex::iter_rounds(3).collect();
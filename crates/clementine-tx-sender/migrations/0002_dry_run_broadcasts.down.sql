@@ -0,0 +1,2 @@
+-- Drop tx_sender_dry_run_broadcasts table
+DROP TABLE IF EXISTS tx_sender_dry_run_broadcasts;
@@ -0,0 +1,35 @@
+-- Would-be broadcasts recorded by tx-sender when running in dry run mode.
+--
+-- Each row is a single transaction that tx-sender would have broadcast, together
+-- with the fee rate it targeted and the result of testmempoolaccept for it.
+-- There is one record per would-be broadcast, replaced on every poll with the
+-- latest evaluation. Rows of a tx-sender row are keyed by kind and the position
+-- of the tx in the broadcast (0 for single txs, 0/1 for CPFP parent/child), rows
+-- without a try_to_send_id are keyed by txid.
+CREATE TABLE IF NOT EXISTS tx_sender_dry_run_broadcasts (
+    id SERIAL PRIMARY KEY,
+    -- NULL for broadcasts that are not tied to a tx-sender row (e.g. Citrea commit txs)
+    try_to_send_id INT REFERENCES tx_sender_try_to_send_txs(id),
+    -- 'no_funding', 'rbf_initial', 'rbf_bump', 'cpfp_package', 'cpfp_fee_payer', ...
+    kind TEXT NOT NULL,
+    -- position of the tx in the broadcast
+    package_index INT NOT NULL DEFAULT 0,
+    txid BYTEA NOT NULL,
+    raw_tx BYTEA NOT NULL,
+    -- target fee rate in sat/kvB
+    fee_rate BIGINT NOT NULL,
+    -- absolute fee paid by this tx in sats, if it could be computed
+    fee BIGINT,
+    -- NULL if testmempoolaccept could not be run
+    mempool_allowed BOOLEAN,
+    mempool_reject_reason TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS tx_sender_dry_run_broadcasts_try_to_send_id_idx ON tx_sender_dry_run_broadcasts(try_to_send_id);
+CREATE UNIQUE INDEX IF NOT EXISTS tx_sender_dry_run_broadcasts_tx_idx
+    ON tx_sender_dry_run_broadcasts(try_to_send_id, kind, package_index)
+    WHERE try_to_send_id IS NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS tx_sender_dry_run_broadcasts_txid_idx
+    ON tx_sender_dry_run_broadcasts(kind, txid)
+    WHERE try_to_send_id IS NULL;
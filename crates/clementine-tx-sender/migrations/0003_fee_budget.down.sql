@@ -0,0 +1,3 @@
+-- Drop tx-sender fee budget tables
+DROP TABLE IF EXISTS tx_sender_fee_budget_overrides;
+DROP TABLE IF EXISTS tx_sender_fee_spending;
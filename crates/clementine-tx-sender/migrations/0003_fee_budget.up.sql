@@ -0,0 +1,35 @@
+-- Fee spending budget of tx-sender.
+--
+-- Every RBF and CPFP broadcast records how much it adds to the fees tx-sender
+-- has committed to for its try_to_send_id. Since a replacement only pays the
+-- difference to the tx it replaces, `spent` is the increase over the highest
+-- fee already recorded for that id, and the rolling budget windows sum `spent`.
+CREATE TABLE IF NOT EXISTS tx_sender_fee_spending (
+    id SERIAL PRIMARY KEY,
+    try_to_send_id INT NOT NULL REFERENCES tx_sender_try_to_send_txs(id),
+    -- TransactionType name without index, NULL if the tx has no metadata
+    tx_type TEXT,
+    -- fee in sats paid by the broadcast tx (the CPFP child for packages)
+    fee BIGINT NOT NULL,
+    -- sats counted against the budget
+    spent BIGINT NOT NULL,
+    -- fee payer funding txs and their bumpfee replacements are paid by the
+    -- wallet too, but are separate txs from the one tracked by try_to_send_id,
+    -- so their rows are excluded from the highest fee replacements of that tx
+    -- are compared against
+    fee_payer BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+CREATE INDEX IF NOT EXISTS tx_sender_fee_spending_created_at_idx ON tx_sender_fee_spending(created_at);
+CREATE INDEX IF NOT EXISTS tx_sender_fee_spending_try_to_send_id_idx ON tx_sender_fee_spending(try_to_send_id);
+-- Temporary raises of the fee budget, added on top of the configured limits until they expire.
+CREATE TABLE IF NOT EXISTS tx_sender_fee_budget_overrides (
+    id SERIAL PRIMARY KEY,
+    -- NULL raises the global budget, otherwise the budget of this tx type
+    tx_type TEXT,
+    extra_sats_per_hour BIGINT NOT NULL DEFAULT 0,
+    extra_sats_per_day BIGINT NOT NULL DEFAULT 0,
+    reason TEXT,
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
@@ -37,6 +37,11 @@ export TX_SENDER_JSONRPC_PORT="${TX_SENDER_JSONRPC_PORT:-3030}"
 export TX_SENDER_POLL_DELAY_MS="${TX_SENDER_POLL_DELAY_MS:-500}"
 export TX_SENDER_FINALITY_DEPTH="${TX_SENDER_FINALITY_DEPTH:-1}"
 export TX_SENDER_INCLUDE_UNSAFE="${TX_SENDER_INCLUDE_UNSAFE:-true}"
+# Uncomment to export Prometheus metrics at http://${TX_SENDER_METRICS_BIND}:${TX_SENDER_METRICS_PORT}/metrics.
+# export TX_SENDER_METRICS_BIND="${TX_SENDER_METRICS_BIND:-127.0.0.1}"
+# export TX_SENDER_METRICS_PORT="${TX_SENDER_METRICS_PORT:-9464}"
+# Set to true to build and check txs without broadcasting them.
+export TX_SENDER_DRY_RUN="${TX_SENDER_DRY_RUN:-false}"
 
 # Extra bitcoin-cli flags used for the smoke test.
 export BITCOIN_CLI_RPCPORT="${BITCOIN_CLI_RPCPORT:-18443}"
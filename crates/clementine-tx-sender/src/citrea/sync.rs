@@ -509,6 +509,18 @@ impl TxSender {
 
         let commit_txid = signed_commit_tx.compute_txid();
 
+        if self.dry_run {
+            let fee = self.get_tx_fee(&signed_commit_tx).await.ok();
+            self.record_dry_run_broadcast(
+                None,
+                crate::dry_run::DryRunBroadcastKind::CitreaCommit,
+                &[(&signed_commit_tx, fee)],
+                fee_rate,
+            )
+            .await?;
+            return Ok(None);
+        }
+
         if let Err(e) = self.rpc.send_raw_transaction(&signed_commit_tx).await {
             tracing::warn!(
                 insertion_id,
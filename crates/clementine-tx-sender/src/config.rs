@@ -3,7 +3,9 @@
 use crate::MempoolConfig;
 use bitcoin::secp256k1::SecretKey;
 use bitcoin::Network;
-use clementine_config::tx_sender::TxSenderLimits;
+use clementine_config::tx_sender::{
+    check_per_tx_type_keys, FeeBudgetLimits, TxSenderFeeBudget, TxSenderLimits,
+};
 use clementine_errors::BridgeError;
 use secrecy::SecretString;
 use std::str::FromStr;
@@ -34,6 +36,14 @@ pub struct TxSenderJsonRpcConfig {
     pub port: u16,
 }
 
+#[derive(Clone, Debug)]
+pub struct TxSenderMetricsConfig {
+    /// Bind address for the Prometheus exporter. Restricted to 127.0.0.1 or 0.0.0.0.
+    pub bind: String,
+    /// TCP port for the Prometheus exporter.
+    pub port: u16,
+}
+
 /// Configuration for running the tx-sender service standalone.
 #[derive(Clone, Debug)]
 pub struct TxSenderConfig {
@@ -66,6 +76,16 @@ pub struct TxSenderConfig {
 
     /// Optional JSON-RPC configuration, will not be used if json-rpc feature is not .
     pub jsonrpc: Option<TxSenderJsonRpcConfig>,
+
+    /// Optional Prometheus exporter for the standalone binary.
+    ///
+    /// Not used in clementine_core, which exports tx-sender metrics with its own exporter.
+    pub metrics: Option<TxSenderMetricsConfig>,
+
+    /// If true, tx-sender computes fee rates and builds RBF/CPFP transactions as usual
+    /// but never broadcasts them. Would-be broadcasts are checked with `testmempoolaccept`
+    /// and recorded in `tx_sender_dry_run_broadcasts` instead.
+    pub dry_run: bool,
 }
 
 fn env_required(name: &'static str) -> Result<String, BridgeError> {
@@ -107,6 +127,45 @@ where
     Ok(env_parse_optional::<T>(name)?.unwrap_or(default))
 }
 
+impl TxSenderPostgresConfig {
+    /// Reads the `DB_*` env vars, which are shared with clementine_core.
+    pub fn from_env() -> Result<Self, BridgeError> {
+        Ok(Self {
+            host: env_required("DB_HOST")?,
+            port: env_parse_required::<u16>("DB_PORT")?,
+            user: env_required("DB_USER")?.into(),
+            password: env_required("DB_PASSWORD")?.into(),
+            dbname: env_required("DB_NAME")?,
+        })
+    }
+}
+
+/// Reads the fee budget from `TX_SENDER_FEE_BUDGET_*` env vars.
+///
+/// Per tx type limits are given as JSON in `TX_SENDER_FEE_BUDGET_PER_TX_TYPE`, e.g.
+/// `{"Kickoff": {"max_sats_per_day": 500000}}`.
+pub fn fee_budget_from_env() -> Result<TxSenderFeeBudget, BridgeError> {
+    let per_tx_type = match env_optional("TX_SENDER_FEE_BUDGET_PER_TX_TYPE") {
+        Some(value) => {
+            let per_tx_type = serde_json::from_str(&value).map_err(|e| {
+                BridgeError::EnvVarMalformed("TX_SENDER_FEE_BUDGET_PER_TX_TYPE", format!("{e:?}"))
+            })?;
+            check_per_tx_type_keys(&per_tx_type)
+                .map_err(|e| BridgeError::EnvVarMalformed("TX_SENDER_FEE_BUDGET_PER_TX_TYPE", e))?;
+            per_tx_type
+        }
+        None => Default::default(),
+    };
+
+    Ok(TxSenderFeeBudget {
+        global: FeeBudgetLimits {
+            max_sats_per_hour: env_parse_optional::<u64>("TX_SENDER_FEE_BUDGET_MAX_SATS_PER_HOUR")?,
+            max_sats_per_day: env_parse_optional::<u64>("TX_SENDER_FEE_BUDGET_MAX_SATS_PER_DAY")?,
+        },
+        per_tx_type,
+    })
+}
+
 impl TxSenderConfig {
     pub fn from_env() -> Result<Self, BridgeError> {
         let network_str = env_required("NETWORK")?;
@@ -125,13 +184,7 @@ impl TxSenderConfig {
                 None => None,
             };
 
-        let postgres = TxSenderPostgresConfig {
-            host: env_required("DB_HOST")?,
-            port: env_parse_required::<u16>("DB_PORT")?,
-            user: env_required("DB_USER")?.into(),
-            password: env_required("DB_PASSWORD")?.into(),
-            dbname: env_required("DB_NAME")?,
-        };
+        let postgres = TxSenderPostgresConfig::from_env()?;
 
         let bitcoin_rpc = TxSenderBitcoinRpcConfig {
             url: env_required("BITCOIN_RPC_URL")?,
@@ -172,6 +225,7 @@ impl TxSenderConfig {
                 "TX_SENDER_MIN_BUMP_KVB",
                 defaults.min_bump_kvb,
             )?,
+            fee_budget: fee_budget_from_env()?,
         };
 
         let finality_depth = env_parse_required::<u32>("TX_SENDER_FINALITY_DEPTH")?;
@@ -187,6 +241,8 @@ impl TxSenderConfig {
 
         let include_unsafe = env_parse_required::<bool>("TX_SENDER_INCLUDE_UNSAFE")?;
 
+        let dry_run = env_parse_optional_or::<bool>("TX_SENDER_DRY_RUN", false)?;
+
         if finality_depth < 1 {
             return Err(BridgeError::EnvVarMalformed(
                 "TX_SENDER_FINALITY_DEPTH",
@@ -214,6 +270,20 @@ impl TxSenderConfig {
         #[cfg(not(feature = "json-rpc"))]
         let jsonrpc = None;
 
+        let metrics = env_parse_optional::<u16>("TX_SENDER_METRICS_PORT")?
+            .map(|port| {
+                let bind = env_optional("TX_SENDER_METRICS_BIND")
+                    .unwrap_or_else(|| "127.0.0.1".to_string());
+                if bind != "127.0.0.1" && bind != "0.0.0.0" {
+                    return Err(BridgeError::EnvVarMalformed(
+                        "TX_SENDER_METRICS_BIND",
+                        "bind must be either 127.0.0.1 or 0.0.0.0".to_string(),
+                    ));
+                }
+                Ok(TxSenderMetricsConfig { bind, port })
+            })
+            .transpose()?;
+
         Ok(Self {
             network,
             secret_key,
@@ -226,6 +296,8 @@ impl TxSenderConfig {
             poll_delay_ms,
             include_unsafe,
             jsonrpc,
+            metrics,
+            dry_run,
         })
     }
 }
@@ -18,6 +18,10 @@
 //! send.
 
 use super::Result;
+use crate::dry_run::DryRunBroadcastKind;
+use crate::metrics::{
+    record_fee_payer_eviction, record_rejection, record_submission, SubmissionKind,
+};
 use crate::{TxSender, TxSenderTransaction};
 use bitcoin::absolute::LockTime;
 use bitcoin::sighash::{Prevouts, SighashCache};
@@ -32,7 +36,7 @@ use clementine_primitives::FeeRateKvb;
 use clementine_primitives::{MIN_TAPROOT_AMOUNT, NON_STANDARD_V3};
 use clementine_utils::{FeePayingType, TxMetadata};
 use eyre::{eyre, Context};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::env;
 
 impl TxSender {
@@ -143,15 +147,84 @@ impl TxSender {
     /// * `fee_rate` - The target fee rate for the CPFP package.
     /// * `total_fee_payer_amount` - The sum of amounts in currently available confirmed fee payer UTXOs.
     /// * `fee_payer_utxos_len` - The number of currently available confirmed fee payer UTXOs.
+    ///
+    /// If a fee budget is configured, the fee payer tx is not sent while its fee
+    /// would exceed it (see [`crate::fee_budget`]).
+    ///
+    /// # Returns
+    /// `false` if the fee payer tx is held by the fee budget.
+    #[allow(clippy::too_many_arguments)]
     async fn create_fee_payer_utxo(
         &self,
         bumped_id: u32,
         dbtx: Option<&mut TxSenderTransaction>,
         tx: &Transaction,
+        tx_metadata: Option<&TxMetadata>,
         fee_rate: FeeRateKvb,
         total_fee_payer_amount: Amount,
         fee_payer_utxos_len: usize,
-    ) -> Result<()> {
+    ) -> Result<bool> {
+        let Some((signed_fee_payer_tx, outpoint_vout, new_fee_payer_amount)) = self
+            .build_fee_payer_tx(
+                bumped_id,
+                tx,
+                fee_rate,
+                total_fee_payer_amount,
+                fee_payer_utxos_len,
+            )
+            .await?
+        else {
+            return Ok(true);
+        };
+
+        let fee = self.get_tx_fee(&signed_fee_payer_tx).await?;
+        let Some(fee_budget_spend) = self
+            .check_fee_payer_fee_budget(bumped_id, tx_metadata, fee, fee)
+            .await?
+        else {
+            return Ok(false);
+        };
+
+        if let Err(e) = self
+            .rpc
+            .send_raw_transaction(&signed_fee_payer_tx)
+            .await
+            .wrap_err("Failed to send signed fee payer tx")
+        {
+            self.release_fee_budget(fee_budget_spend).await;
+            return Err(e.into());
+        }
+
+        self.db
+            .save_fee_payer_tx(
+                dbtx,
+                bumped_id,
+                signed_fee_payer_tx.compute_txid(),
+                outpoint_vout,
+                new_fee_payer_amount,
+                None,
+            )
+            .await
+            .map_to_eyre()?;
+
+        Ok(true)
+    }
+
+    /// Builds and signs a fee payer transaction for a CPFP transaction, see
+    /// [`Self::create_fee_payer_utxo`], without broadcasting it.
+    ///
+    /// # Returns
+    ///
+    /// The signed fee payer transaction, the output index of the new fee payer
+    /// UTXO and its amount, or `None` if no new fee payer UTXO is needed.
+    async fn build_fee_payer_tx(
+        &self,
+        bumped_id: u32,
+        tx: &Transaction,
+        fee_rate: FeeRateKvb,
+        total_fee_payer_amount: Amount,
+        fee_payer_utxos_len: usize,
+    ) -> Result<Option<(Transaction, u32, Amount)>> {
         tracing::debug!(
             "Creating fee payer UTXO for txid {} with bump id {}",
             &tx.compute_txid().to_string(),
@@ -182,7 +255,7 @@ impl TxSender {
             // if underflow, no new fee payer utxo is needed, log it anyway in case its a bug
             None => {
                 tracing::debug!("create_fee_payer_utxo was called but no new fee payer utxo is needed for tx: {:?}, required fee: {}, total fee payer amount: {}, current fee rate: {}", tx, required_fee, total_fee_payer_amount, fee_rate);
-                return Ok(());
+                return Ok(None);
             }
         };
 
@@ -248,24 +321,11 @@ impl TxSender {
             })
             .ok_or(eyre!("Failed to find outpoint vout"))?;
 
-        self.rpc
-            .send_raw_transaction(&signed_fee_payer_tx)
-            .await
-            .wrap_err("Failed to send signed fee payer tx")?;
-
-        self.db
-            .save_fee_payer_tx(
-                dbtx,
-                bumped_id,
-                signed_fee_payer_tx.compute_txid(),
-                outpoint_vout as u32,
-                new_fee_payer_amount,
-                None,
-            )
-            .await
-            .map_to_eyre()?;
-
-        Ok(())
+        Ok(Some((
+            signed_fee_payer_tx,
+            u32::try_from(outpoint_vout).wrap_err("Failed to convert vout to u32")?,
+            new_fee_payer_amount,
+        )))
     }
 
     /// Creates a Child-Pays-For-Parent (CPFP) child transaction.
@@ -432,13 +492,20 @@ impl TxSender {
     /// * `fee_rate` - The target fee rate for bumping the fee payer transactions.
     #[tracing::instrument(skip_all, fields(fee_rate))]
     pub async fn bump_fees_of_unconfirmed_fee_payer_txs(&self, fee_rate: FeeRateKvb) -> Result<()> {
+        // bumpfee broadcasts the replacement itself, so there is nothing to simulate here.
+        // In dry run mode no fee payer txs are ever broadcast anyway.
+        if self.dry_run {
+            tracing::debug!("DRY RUN: skipping fee payer fee bumps");
+            return Ok(());
+        }
+
         let bumpable_txs = self
             .db
             .get_all_unconfirmed_fee_payer_txs(None)
             .await
             .map_err(|e: BridgeError| SendTxError::Other(e.into()))?;
         let mut not_evicted_ids = HashSet::new();
-        let mut all_parent_ids = HashSet::new();
+        let mut all_parent_ids = HashMap::new();
 
         for (id, try_to_send_id, fee_payer_txid, vout, amount, replacement_of_id) in bumpable_txs {
             tracing::debug!(
@@ -448,9 +515,9 @@ impl TxSender {
                 fee_rate
             );
             let parent_id = replacement_of_id.unwrap_or(id);
-            all_parent_ids.insert(parent_id);
+            all_parent_ids.insert(parent_id, try_to_send_id);
 
-            match self.rpc.get_mempool_entry(&fee_payer_txid).await {
+            let mempool_entry = match self.rpc.get_mempool_entry(&fee_payer_txid).await {
                 Ok(info) => {
                     not_evicted_ids.insert(parent_id);
                     // if it has descendants, it cannot be bumped, or if it was bumped recently, we should not bump it again
@@ -464,6 +531,7 @@ impl TxSender {
                     {
                         continue;
                     }
+                    info
                 }
                 Err(e) => {
                     // If not in mempool we should ignore, it was either evicted or replaced by a bumped feepayer tx
@@ -482,7 +550,28 @@ impl TxSender {
                     }
                     continue;
                 }
-            }
+            };
+
+            // bumpfee builds the replacement itself, so its fee is estimated
+            // from the size of the tx it replaces.
+            let new_fee = fee_rate.fee_vb(mempool_entry.vsize).ok_or_else(|| {
+                eyre!("Fee overflow for fee payer tx {fee_payer_txid} at {fee_rate}")
+            })?;
+            let spent = new_fee
+                .checked_sub(mempool_entry.fees.base)
+                .unwrap_or(Amount::ZERO);
+            let tx_metadata = self
+                .db
+                .get_try_to_send_tx(None, try_to_send_id)
+                .await
+                .ok()
+                .and_then(|(tx_metadata, ..)| tx_metadata);
+            let Some(fee_budget_spend) = self
+                .check_fee_payer_fee_budget(try_to_send_id, tx_metadata.as_ref(), new_fee, spent)
+                .await?
+            else {
+                continue;
+            };
 
             match self
                 .rpc
@@ -507,47 +596,134 @@ impl TxSender {
                             "Fee payer tx {} has enough fee, no need to bump",
                             fee_payer_txid
                         );
+                        self.release_fee_budget(fee_budget_spend).await;
                     }
                 }
-                Err(e) => match e {
-                    BitcoinRPCError::TransactionAlreadyInBlock(block_hash) => {
-                        tracing::debug!(
-                            "Fee payer tx {} is already in block {}, skipping",
-                            fee_payer_txid,
-                            block_hash
-                        );
-                        continue;
-                    }
-                    BitcoinRPCError::BumpFeeUTXOSpent(outpoint) => {
-                        tracing::debug!(
-                            "Fee payer tx {} is already onchain, skipping: {:?}",
-                            fee_payer_txid,
-                            outpoint
-                        );
-                        continue;
-                    }
-                    _ => {
-                        tracing::warn!(
-                            "Failed to bump fee the fee payer tx {} with error {e}, skipping",
-                            fee_payer_txid
-                        );
-                        continue;
+                Err(e) => {
+                    self.release_fee_budget(fee_budget_spend).await;
+                    match e {
+                        BitcoinRPCError::TransactionAlreadyInBlock(block_hash) => {
+                            tracing::debug!(
+                                "Fee payer tx {} is already in block {}, skipping",
+                                fee_payer_txid,
+                                block_hash
+                            );
+                            continue;
+                        }
+                        BitcoinRPCError::BumpFeeUTXOSpent(outpoint) => {
+                            tracing::debug!(
+                                "Fee payer tx {} is already onchain, skipping: {:?}",
+                                fee_payer_txid,
+                                outpoint
+                            );
+                            continue;
+                        }
+                        _ => {
+                            tracing::warn!(
+                                "Failed to bump fee the fee payer tx {} with error {e}, skipping",
+                                fee_payer_txid
+                            );
+                            continue;
+                        }
                     }
-                },
+                }
             }
         }
 
-        for parent_id in all_parent_ids {
+        for (parent_id, try_to_send_id) in all_parent_ids {
             if !not_evicted_ids.contains(&parent_id) {
                 self.db
                     .mark_fee_payer_utxo_as_evicted(None, parent_id)
                     .await
                     .map_err(|e: BridgeError| SendTxError::Other(e.into()))?;
+                let tx_metadata = self
+                    .db
+                    .get_try_to_send_tx(None, try_to_send_id)
+                    .await
+                    .ok()
+                    .and_then(|(tx_metadata, ..)| tx_metadata);
+                record_fee_payer_eviction(tx_metadata.as_ref());
             }
         }
         Ok(())
     }
 
+    /// Returns the fee paid by the child of a CPFP `package`, given the total
+    /// amount of the fee payer UTXOs it spends. The child spends the parent's
+    /// anchor and the fee payer UTXOs, neither of which can be looked up through
+    /// RPC before the parent is broadcast. `None` if the child spends more than
+    /// its inputs.
+    fn cpfp_child_fee(
+        &self,
+        package: &[Transaction],
+        fee_payer_amount: Amount,
+    ) -> Result<Option<Amount>> {
+        let anchor_sat = self
+            .find_p2a_vout(&package[0])
+            .map(|vout| package[0].output[vout].value)
+            .map_err(|e: BridgeError| SendTxError::Other(e.into()))?;
+
+        Ok((fee_payer_amount + anchor_sat)
+            .checked_sub(package[1].output.iter().map(|o| o.value).sum()))
+    }
+
+    /// Dry run counterpart of creating a fee payer UTXO for a CPFP tx and
+    /// waiting for it to confirm: builds the fee payer tx, uses its output as a
+    /// synthetic fee payer UTXO to build the CPFP package right away, and
+    /// records all three txs.
+    async fn dry_run_cpfp_with_new_fee_payer(
+        &self,
+        try_to_send_id: u32,
+        tx: Transaction,
+        fee_rate: FeeRateKvb,
+        mut fee_payer_utxos: Vec<crate::SpendableUtxo>,
+    ) -> Result<()> {
+        let total_amount: Amount = fee_payer_utxos.iter().map(|u| u.txout.value).sum();
+        let Some((fee_payer_tx, vout, amount)) = self
+            .build_fee_payer_tx(
+                try_to_send_id,
+                &tx,
+                fee_rate,
+                total_amount,
+                fee_payer_utxos.len(),
+            )
+            .await?
+        else {
+            return Ok(());
+        };
+        let fee_payer_fee = self.get_tx_fee(&fee_payer_tx).await.ok();
+
+        fee_payer_utxos.push(crate::SpendableUtxo {
+            outpoint: OutPoint {
+                txid: fee_payer_tx.compute_txid(),
+                vout,
+            },
+            txout: TxOut {
+                value: amount,
+                script_pubkey: self.signer.address().script_pubkey(),
+            },
+            spend_info: None,
+        });
+        let package = self.create_package(tx, fee_rate, fee_payer_utxos).await?;
+        let child_fee = self.cpfp_child_fee(&package, total_amount + amount)?;
+        let parent_fee = self.get_tx_fee(&package[0]).await.ok();
+
+        self.record_dry_run_broadcasts(
+            Some(try_to_send_id),
+            &[
+                (
+                    DryRunBroadcastKind::CpfpFeePayer,
+                    &fee_payer_tx,
+                    fee_payer_fee,
+                ),
+                (DryRunBroadcastKind::CpfpPackage, &package[0], parent_fee),
+                (DryRunBroadcastKind::CpfpPackage, &package[1], child_fee),
+            ],
+            fee_rate,
+        )
+        .await
+    }
+
     /// Sends a transaction using the Child-Pays-For-Parent (CPFP) strategy.
     ///
     /// # Logic:
@@ -565,6 +741,12 @@ impl TxSender {
     /// 6.  **Handle Results:** Checks the `submitpackage` result. If successful or already in
     ///     mempool, updates the effective fee rate in the database. If failed, returns an error.
     ///
+    /// In dry run mode the package is recorded right before step 5 and nothing is submitted.
+    /// Missing fee payer UTXOs are replaced by a synthetic one instead of waiting for a fee
+    /// payer tx to confirm (see [`crate::dry_run`]).
+    /// If a fee budget is configured, the package is held before step 5 while the child's
+    /// fee would exceed it (see [`crate::fee_budget`]).
+    ///
     /// # Arguments
     /// * `try_to_send_id` - The database ID tracking this send attempt.
     /// * `tx` - The parent transaction requiring the fee bump.
@@ -617,24 +799,33 @@ impl TxSender {
             .await
         {
             Ok(p) => p,
+            Err(SendTxError::InsufficientFeePayerAmount) if self.dry_run => {
+                return self
+                    .dry_run_cpfp_with_new_fee_payer(try_to_send_id, tx, fee_rate, confirmed)
+                    .await;
+            }
             Err(SendTxError::InsufficientFeePayerAmount) => {
-                self.create_fee_payer_utxo(
-                    try_to_send_id,
-                    None,
-                    &tx,
-                    fee_rate,
-                    total_amount,
-                    confirmed.len(),
-                )
-                .await?;
-                let _ = self
-                    .db
-                    .update_tx_debug_sending_state(
+                let created = self
+                    .create_fee_payer_utxo(
                         try_to_send_id,
-                        "waiting_for_fee_payer_utxos",
-                        true,
+                        None,
+                        &tx,
+                        tx_metadata.as_ref(),
+                        fee_rate,
+                        total_amount,
+                        confirmed.len(),
                     )
-                    .await;
+                    .await?;
+                if created {
+                    let _ = self
+                        .db
+                        .update_tx_debug_sending_state(
+                            try_to_send_id,
+                            "waiting_for_fee_payer_utxos",
+                            true,
+                        )
+                        .await;
+                }
                 return Ok(());
             }
             Err(e) => {
@@ -658,6 +849,30 @@ impl TxSender {
             }
         );
 
+        let child_fee = self.cpfp_child_fee(&package, total_amount)?;
+
+        if self.dry_run {
+            let parent_fee = self.get_tx_fee(&package[0]).await.ok();
+            return self
+                .record_dry_run_broadcast(
+                    Some(try_to_send_id),
+                    DryRunBroadcastKind::CpfpPackage,
+                    &[(&package[0], parent_fee), (&package[1], child_fee)],
+                    fee_rate,
+                )
+                .await;
+        }
+
+        // Only the child is paid for by the tx sender's wallet.
+        let child_fee = child_fee
+            .ok_or_else(|| SendTxError::Other(eyre!("CPFP child spends more than its inputs")))?;
+        let Some(fee_budget_spend) = self
+            .check_fee_budget(try_to_send_id, tx_metadata.as_ref(), child_fee)
+            .await?
+        else {
+            return Ok(());
+        };
+
         // Save the effective fee rate before attempting to send
         // This ensures that even if the send fails, we track the attempt
         // so the 10-block stuck logic can trigger a bump
@@ -672,14 +887,22 @@ impl TxSender {
             .update_tx_debug_sending_state(try_to_send_id, "submitting_package", true)
             .await;
 
-        let submit_result = self
+        let submit_result = match self
             .rpc
             .submit_package(&package_refs, Some(Amount::ZERO), None)
             .await
-            .wrap_err("Failed to submit package")?;
+            .wrap_err("Failed to submit package")
+        {
+            Ok(submit_result) => submit_result,
+            Err(e) => {
+                self.release_fee_budget(fee_budget_spend).await;
+                return Err(e.into());
+            }
+        };
 
         // If tx_results is empty, it means the txs were already accepted by the network.
         if submit_result.tx_results.is_empty() {
+            record_submission(SubmissionKind::CpfpPackage, tx_metadata.as_ref());
             return Ok(());
         }
 
@@ -688,6 +911,7 @@ impl TxSender {
 
         for result in submit_result.tx_results.into_values() {
             if let PackageTransactionResult::Failure { error, .. } = result {
+                record_rejection(SubmissionKind::CpfpPackage, tx_metadata.as_ref(), &error);
                 if crate::rpc_errors::is_rejecting_replacement_error(&error) {
                     has_replacement_error = true;
                 }
@@ -695,6 +919,10 @@ impl TxSender {
             }
         }
 
+        if !package_errors.is_empty() {
+            self.release_fee_budget(fee_budget_spend).await;
+        }
+
         if has_replacement_error {
             tracing::debug!(
                 try_to_send_id,
@@ -715,6 +943,7 @@ impl TxSender {
             )));
         }
 
+        record_submission(SubmissionKind::CpfpPackage, tx_metadata.as_ref());
         Ok(())
     }
 }
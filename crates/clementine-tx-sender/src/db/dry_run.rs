@@ -0,0 +1,248 @@
+//! SQLx queries for tx-sender dry run records.
+
+use super::wrapper::TxidDB;
+use super::{TxSenderDb, TxSenderDbTx};
+use crate::txsender_execute_query_with_tx;
+use bitcoin::consensus::serialize;
+use bitcoin::{Amount, Transaction};
+use clementine_errors::BridgeError;
+use clementine_primitives::FeeRateKvb;
+use eyre::Context;
+
+/// A would-be broadcast recorded while tx-sender runs in dry run mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DryRunBroadcast {
+    pub try_to_send_id: Option<u32>,
+    pub kind: String,
+    pub txid: bitcoin::Txid,
+    pub fee_rate: FeeRateKvb,
+    pub fee: Option<Amount>,
+    pub mempool_allowed: Option<bool>,
+    pub mempool_reject_reason: Option<String>,
+}
+
+impl TxSenderDb {
+    /// Records a transaction that would have been broadcast if dry run mode
+    /// was disabled. A previous record of the same broadcast (same
+    /// `try_to_send_id`, `kind` and `package_index`, or same `kind` and txid if
+    /// there is no `try_to_send_id`) is replaced.
+    ///
+    /// # Arguments
+    /// * `try_to_send_id` - The tx-sender row the broadcast belongs to, if any.
+    /// * `kind` - What kind of broadcast this is (see [`crate::dry_run::DryRunBroadcastKind`]).
+    /// * `package_index` - Position of the tx among the txs broadcast together.
+    /// * `fee_rate` - The target fee rate used while building the transaction.
+    /// * `fee` - The absolute fee paid by the transaction, if known.
+    /// * `mempool_allowed` - Result of `testmempoolaccept`, `None` if it could not be run.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn save_dry_run_broadcast(
+        &self,
+        tx: Option<TxSenderDbTx<'_>>,
+        try_to_send_id: Option<u32>,
+        kind: &str,
+        package_index: u32,
+        raw_tx: &Transaction,
+        fee_rate: FeeRateKvb,
+        fee: Option<Amount>,
+        mempool_allowed: Option<bool>,
+        mempool_reject_reason: Option<&str>,
+    ) -> Result<(), BridgeError> {
+        let conflict_target = if try_to_send_id.is_some() {
+            "(try_to_send_id, kind, package_index) WHERE try_to_send_id IS NOT NULL"
+        } else {
+            "(kind, txid) WHERE try_to_send_id IS NULL"
+        };
+        let sql = format!(
+            "INSERT INTO tx_sender_dry_run_broadcasts
+             (try_to_send_id, kind, txid, raw_tx, fee_rate, fee, mempool_allowed, mempool_reject_reason, package_index)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             ON CONFLICT {conflict_target} DO UPDATE SET
+                txid = EXCLUDED.txid,
+                raw_tx = EXCLUDED.raw_tx,
+                fee_rate = EXCLUDED.fee_rate,
+                fee = EXCLUDED.fee,
+                mempool_allowed = EXCLUDED.mempool_allowed,
+                mempool_reject_reason = EXCLUDED.mempool_reject_reason,
+                updated_at = NOW()"
+        );
+        let query = sqlx::query(&sql)
+            .bind(
+                try_to_send_id
+                    .map(i32::try_from)
+                    .transpose()
+                    .wrap_err("Failed to convert try_to_send_id to i32")?,
+            )
+            .bind(kind)
+            .bind(TxidDB(raw_tx.compute_txid()))
+            .bind(serialize(raw_tx))
+            .bind(
+                i64::try_from(fee_rate.to_sat_per_kvb())
+                    .wrap_err("Failed to convert fee rate to i64")?,
+            )
+            .bind(
+                fee.map(|fee| i64::try_from(fee.to_sat()))
+                    .transpose()
+                    .wrap_err("Failed to convert fee to i64")?,
+            )
+            .bind(mempool_allowed)
+            .bind(mempool_reject_reason)
+            .bind(i32::try_from(package_index).wrap_err("Failed to convert package index to i32")?);
+
+        txsender_execute_query_with_tx!(&self.pool, tx, query, execute)?;
+        Ok(())
+    }
+
+    /// Returns all recorded dry run broadcasts, ordered by insertion.
+    pub async fn list_dry_run_broadcasts(
+        &self,
+        tx: Option<TxSenderDbTx<'_>>,
+    ) -> Result<Vec<DryRunBroadcast>, BridgeError> {
+        let query = sqlx::query_as::<
+            _,
+            (
+                Option<i32>,
+                String,
+                TxidDB,
+                i64,
+                Option<i64>,
+                Option<bool>,
+                Option<String>,
+            ),
+        >(
+            "SELECT try_to_send_id, kind, txid, fee_rate, fee, mempool_allowed, mempool_reject_reason
+             FROM tx_sender_dry_run_broadcasts
+             ORDER BY id ASC",
+        );
+
+        let rows = txsender_execute_query_with_tx!(&self.pool, tx, query, fetch_all)?;
+
+        rows.into_iter()
+            .map(
+                |(try_to_send_id, kind, txid, fee_rate, fee, allowed, reject_reason)| {
+                    Ok(DryRunBroadcast {
+                        try_to_send_id: try_to_send_id
+                            .map(u32::try_from)
+                            .transpose()
+                            .wrap_err("Failed to convert try_to_send_id to u32")?,
+                        kind,
+                        txid: txid.0,
+                        fee_rate: FeeRateKvb::from_sat_per_kvb(
+                            u64::try_from(fee_rate)
+                                .wrap_err("Failed to convert fee rate to u64")?,
+                        ),
+                        fee: fee
+                            .map(|fee| u64::try_from(fee).map(Amount::from_sat))
+                            .transpose()
+                            .wrap_err("Failed to convert fee to u64")?,
+                        mempool_allowed: allowed,
+                        mempool_reject_reason: reject_reason,
+                    })
+                },
+            )
+            .collect::<Result<Vec<_>, BridgeError>>()
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_environment;
+    use bitcoin::hashes::Hash as _;
+    use bitcoin::transaction::Version;
+    use bitcoin::{absolute, Txid};
+    use clementine_utils::FeePayingType;
+
+    #[tokio::test]
+    async fn save_and_list_dry_run_broadcasts() {
+        let db = create_test_environment(true, false).await.1.unwrap();
+
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![],
+            output: vec![],
+        };
+
+        let mut dbtx = db.begin_transaction().await.unwrap();
+        let try_to_send_id = db
+            .save_tx(
+                &mut dbtx,
+                None,
+                &tx,
+                FeePayingType::RBF,
+                Txid::from_byte_array([1; 32]),
+                None,
+            )
+            .await
+            .unwrap();
+        db.commit_transaction(dbtx).await.unwrap();
+
+        db.save_dry_run_broadcast(
+            None,
+            Some(try_to_send_id),
+            "rbf_initial",
+            0,
+            &tx,
+            FeeRateKvb::from_sat_per_kvb(1000),
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        // Polling the same tx again replaces its record.
+        db.save_dry_run_broadcast(
+            None,
+            Some(try_to_send_id),
+            "rbf_initial",
+            0,
+            &tx,
+            FeeRateKvb::from_sat_per_kvb(2000),
+            Some(Amount::from_sat(500)),
+            Some(false),
+            Some("min relay fee not met"),
+        )
+        .await
+        .unwrap();
+        for _ in 0..2 {
+            db.save_dry_run_broadcast(
+                None,
+                None,
+                "citrea_commit",
+                0,
+                &tx,
+                FeeRateKvb::from_sat_per_kvb(1000),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        let recorded = db.list_dry_run_broadcasts(None).await.unwrap();
+        assert_eq!(
+            recorded,
+            vec![
+                DryRunBroadcast {
+                    try_to_send_id: Some(try_to_send_id),
+                    kind: "rbf_initial".to_string(),
+                    txid: tx.compute_txid(),
+                    fee_rate: FeeRateKvb::from_sat_per_kvb(2000),
+                    fee: Some(Amount::from_sat(500)),
+                    mempool_allowed: Some(false),
+                    mempool_reject_reason: Some("min relay fee not met".to_string()),
+                },
+                DryRunBroadcast {
+                    try_to_send_id: None,
+                    kind: "citrea_commit".to_string(),
+                    txid: tx.compute_txid(),
+                    fee_rate: FeeRateKvb::from_sat_per_kvb(1000),
+                    fee: None,
+                    mempool_allowed: None,
+                    mempool_reject_reason: None,
+                },
+            ]
+        );
+    }
+}
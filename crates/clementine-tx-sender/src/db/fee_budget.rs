@@ -0,0 +1,325 @@
+//! SQLx queries for the tx-sender fee budget.
+
+use super::{TxSenderDb, TxSenderDbTx};
+use crate::txsender_execute_query_with_tx;
+use bitcoin::Amount;
+use clementine_errors::BridgeError;
+use eyre::Context;
+use std::time::Duration;
+
+/// Extra sats allowed on top of the configured fee budget by active overrides.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeBudgetRaise {
+    pub extra_per_hour: Amount,
+    pub extra_per_day: Amount,
+}
+
+pub(super) fn sats_from_db(value: i64, what: &'static str) -> Result<Amount, BridgeError> {
+    Ok(Amount::from_sat(
+        u64::try_from(value).wrap_err(format!("Failed to convert {what} to u64"))?,
+    ))
+}
+
+fn sats_to_db(value: Amount, what: &'static str) -> Result<i64, BridgeError> {
+    Ok(i64::try_from(value.to_sat()).wrap_err(format!("Failed to convert {what} to i64"))?)
+}
+
+impl TxSenderDb {
+    /// Serializes fee budget checks until `tx` ends.
+    ///
+    /// Concurrent readers are not blocked, but a second caller waits here until
+    /// the spending checked and saved under the first lock is committed.
+    pub async fn lock_fee_spending(&self, tx: TxSenderDbTx<'_>) -> Result<(), BridgeError> {
+        sqlx::query("LOCK TABLE tx_sender_fee_spending IN SHARE ROW EXCLUSIVE MODE")
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    /// Records the fee of a broadcast tx and how much of it is counted against the budget.
+    ///
+    /// # Arguments
+    /// * `fee_payer` - Whether the fee is paid by a CPFP fee payer funding tx
+    ///   created for `try_to_send_id` instead of by the tx itself or its CPFP child.
+    ///
+    /// # Returns
+    /// The id of the created row.
+    pub async fn save_fee_spending(
+        &self,
+        tx: Option<TxSenderDbTx<'_>>,
+        try_to_send_id: u32,
+        tx_type: Option<&str>,
+        fee: Amount,
+        spent: Amount,
+        fee_payer: bool,
+    ) -> Result<u32, BridgeError> {
+        let query = sqlx::query_as::<_, (i32,)>(
+            "INSERT INTO tx_sender_fee_spending (try_to_send_id, tx_type, fee, spent, fee_payer)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING id",
+        )
+        .bind(i32::try_from(try_to_send_id).wrap_err("Failed to convert try_to_send_id to i32")?)
+        .bind(tx_type)
+        .bind(sats_to_db(fee, "fee")?)
+        .bind(sats_to_db(spent, "spent")?)
+        .bind(fee_payer);
+
+        let (id,) = txsender_execute_query_with_tx!(&self.pool, tx, query, fetch_one)?;
+        Ok(u32::try_from(id).wrap_err("Failed to convert fee spending id to u32")?)
+    }
+
+    /// Removes a fee spending row whose broadcast failed.
+    pub async fn delete_fee_spending(
+        &self,
+        tx: Option<TxSenderDbTx<'_>>,
+        id: u32,
+    ) -> Result<(), BridgeError> {
+        let query = sqlx::query("DELETE FROM tx_sender_fee_spending WHERE id = $1")
+            .bind(i32::try_from(id).wrap_err("Failed to convert fee spending id to i32")?);
+
+        txsender_execute_query_with_tx!(&self.pool, tx, query, execute)?;
+        Ok(())
+    }
+
+    /// Returns the highest fee recorded for a tx-sender row, zero if nothing was recorded.
+    ///
+    /// Fees of fee payer funding txs are not included.
+    pub async fn get_max_recorded_fee(
+        &self,
+        tx: Option<TxSenderDbTx<'_>>,
+        try_to_send_id: u32,
+    ) -> Result<Amount, BridgeError> {
+        let query = sqlx::query_as::<_, (i64,)>(
+            "SELECT COALESCE(MAX(fee), 0)::BIGINT FROM tx_sender_fee_spending
+             WHERE try_to_send_id = $1 AND NOT fee_payer",
+        )
+        .bind(i32::try_from(try_to_send_id).wrap_err("Failed to convert try_to_send_id to i32")?);
+
+        let (fee,) = txsender_execute_query_with_tx!(&self.pool, tx, query, fetch_one)?;
+        sats_from_db(fee, "fee")
+    }
+
+    /// Returns the sats counted against the budget in the last `window`.
+    ///
+    /// If `tx_type` is `None`, spending of all tx types is summed.
+    pub async fn get_fee_spent_in_window(
+        &self,
+        tx: Option<TxSenderDbTx<'_>>,
+        tx_type: Option<&str>,
+        window: Duration,
+    ) -> Result<Amount, BridgeError> {
+        let query = sqlx::query_as::<_, (i64,)>(
+            "SELECT COALESCE(SUM(spent), 0)::BIGINT FROM tx_sender_fee_spending
+             WHERE created_at > NOW() - make_interval(secs => $1)
+               AND ($2::TEXT IS NULL OR tx_type = $2)",
+        )
+        .bind(window.as_secs_f64())
+        .bind(tx_type);
+
+        let (spent,) = txsender_execute_query_with_tx!(&self.pool, tx, query, fetch_one)?;
+        sats_from_db(spent, "spent fees")
+    }
+
+    /// Temporarily raises the fee budget for `duration`.
+    ///
+    /// # Arguments
+    /// * `tx_type` - The tx type whose budget is raised, `None` raises the global budget.
+    /// * `raise` - The sats added to the hourly and daily limits.
+    /// * `reason` - Free form note for later audits.
+    ///
+    /// # Returns
+    /// The id of the created override.
+    pub async fn save_fee_budget_raise(
+        &self,
+        tx: Option<TxSenderDbTx<'_>>,
+        tx_type: Option<&str>,
+        raise: FeeBudgetRaise,
+        duration: Duration,
+        reason: Option<&str>,
+    ) -> Result<u32, BridgeError> {
+        let query = sqlx::query_as::<_, (i32,)>(
+            "INSERT INTO tx_sender_fee_budget_overrides
+             (tx_type, extra_sats_per_hour, extra_sats_per_day, reason, expires_at)
+             VALUES ($1, $2, $3, $4, NOW() + make_interval(secs => $5))
+             RETURNING id",
+        )
+        .bind(tx_type)
+        .bind(sats_to_db(raise.extra_per_hour, "extra sats per hour")?)
+        .bind(sats_to_db(raise.extra_per_day, "extra sats per day")?)
+        .bind(reason)
+        .bind(duration.as_secs_f64());
+
+        let (id,) = txsender_execute_query_with_tx!(&self.pool, tx, query, fetch_one)?;
+        Ok(u32::try_from(id).wrap_err("Failed to convert override id to u32")?)
+    }
+
+    /// Returns the sum of all unexpired budget raises for `tx_type` (`None` for global).
+    pub async fn get_active_fee_budget_raise(
+        &self,
+        tx: Option<TxSenderDbTx<'_>>,
+        tx_type: Option<&str>,
+    ) -> Result<FeeBudgetRaise, BridgeError> {
+        let query = sqlx::query_as::<_, (i64, i64)>(
+            "SELECT COALESCE(SUM(extra_sats_per_hour), 0)::BIGINT,
+                    COALESCE(SUM(extra_sats_per_day), 0)::BIGINT
+             FROM tx_sender_fee_budget_overrides
+             WHERE expires_at > NOW() AND tx_type IS NOT DISTINCT FROM $1",
+        )
+        .bind(tx_type);
+
+        let (per_hour, per_day) =
+            txsender_execute_query_with_tx!(&self.pool, tx, query, fetch_one)?;
+        Ok(FeeBudgetRaise {
+            extra_per_hour: sats_from_db(per_hour, "extra sats per hour")?,
+            extra_per_day: sats_from_db(per_day, "extra sats per day")?,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_environment;
+    use bitcoin::hashes::Hash as _;
+    use bitcoin::transaction::Version;
+    use bitcoin::{absolute, Transaction, Txid};
+    use clementine_utils::FeePayingType;
+
+    #[tokio::test]
+    async fn fee_spending_and_budget_raises() {
+        let db = create_test_environment(true, false).await.1.unwrap();
+
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![],
+            output: vec![],
+        };
+
+        let mut dbtx = db.begin_transaction().await.unwrap();
+        let id = db
+            .save_tx(
+                &mut dbtx,
+                None,
+                &tx,
+                FeePayingType::RBF,
+                Txid::from_byte_array([2; 32]),
+                None,
+            )
+            .await
+            .unwrap();
+        db.commit_transaction(dbtx).await.unwrap();
+
+        assert_eq!(
+            db.get_max_recorded_fee(None, id).await.unwrap(),
+            Amount::ZERO
+        );
+
+        db.save_fee_spending(
+            None,
+            id,
+            Some("Kickoff"),
+            Amount::from_sat(1000),
+            Amount::from_sat(1000),
+            false,
+        )
+        .await
+        .unwrap();
+        db.save_fee_spending(
+            None,
+            id,
+            Some("Kickoff"),
+            Amount::from_sat(1500),
+            Amount::from_sat(500),
+            false,
+        )
+        .await
+        .unwrap();
+        // A fee payer funding tx is spent in full and isn't compared against by replacements.
+        let fee_payer_id = db
+            .save_fee_spending(
+                None,
+                id,
+                Some("Kickoff"),
+                Amount::from_sat(2000),
+                Amount::from_sat(2000),
+                true,
+            )
+            .await
+            .unwrap();
+
+        let hour = Duration::from_secs(3600);
+        assert_eq!(
+            db.get_max_recorded_fee(None, id).await.unwrap(),
+            Amount::from_sat(1500)
+        );
+        assert_eq!(
+            db.get_fee_spent_in_window(None, None, hour).await.unwrap(),
+            Amount::from_sat(3500)
+        );
+
+        db.delete_fee_spending(None, fee_payer_id).await.unwrap();
+        assert_eq!(
+            db.get_fee_spent_in_window(None, None, hour).await.unwrap(),
+            Amount::from_sat(1500)
+        );
+        // The stats count the replacement's increase, not both fees.
+        assert_eq!(
+            db.get_tx_sender_stats(None).await.unwrap().fees_spent,
+            vec![(Some("Kickoff".to_string()), Amount::from_sat(1500))]
+        );
+
+        // Holding the lock doesn't block reads through other connections.
+        let mut dbtx = db.begin_transaction().await.unwrap();
+        db.lock_fee_spending(&mut dbtx).await.unwrap();
+        assert_eq!(
+            db.get_fee_spent_in_window(None, None, hour).await.unwrap(),
+            Amount::from_sat(1500)
+        );
+        db.commit_transaction(dbtx).await.unwrap();
+        assert_eq!(
+            db.get_fee_spent_in_window(None, Some("Kickoff"), hour)
+                .await
+                .unwrap(),
+            Amount::from_sat(1500)
+        );
+        assert_eq!(
+            db.get_fee_spent_in_window(None, Some("Challenge"), hour)
+                .await
+                .unwrap(),
+            Amount::ZERO
+        );
+
+        let raise = FeeBudgetRaise {
+            extra_per_hour: Amount::from_sat(10_000),
+            extra_per_day: Amount::from_sat(50_000),
+        };
+        db.save_fee_budget_raise(None, None, raise, hour, Some("incident"))
+            .await
+            .unwrap();
+        db.save_fee_budget_raise(None, None, raise, Duration::ZERO, None)
+            .await
+            .unwrap();
+        db.save_fee_budget_raise(None, Some("Kickoff"), raise, hour, None)
+            .await
+            .unwrap();
+
+        // The expired raise is not counted, and type raises don't count for the global budget.
+        assert_eq!(
+            db.get_active_fee_budget_raise(None, None).await.unwrap(),
+            raise
+        );
+        assert_eq!(
+            db.get_active_fee_budget_raise(None, Some("Kickoff"))
+                .await
+                .unwrap(),
+            raise
+        );
+        assert_eq!(
+            db.get_active_fee_budget_raise(None, Some("Challenge"))
+                .await
+                .unwrap(),
+            FeeBudgetRaise::default()
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! SQLx queries for the tx-sender gauges.
+
+use super::fee_budget::sats_from_db;
+use super::{TxSenderDb, TxSenderDbTx};
+use crate::txsender_execute_query_with_tx;
+use bitcoin::Amount;
+use clementine_errors::BridgeError;
+use eyre::Context;
+use std::time::Duration;
+
+/// Snapshot of the tx-sender tables, exported as gauges by [`crate::metrics`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TxSenderStats {
+    /// Number of txs that were not seen onchain yet.
+    pub unconfirmed_txs: u64,
+    /// Time since the oldest unconfirmed tx was added, `None` if there is none.
+    pub oldest_unconfirmed_age: Option<Duration>,
+    /// Sum of confirmed fee payer UTXOs whose bumped tx is not confirmed yet.
+    pub fee_payer_balance: Amount,
+    /// Total sats spent on fees per tx type, `None` for txs without metadata.
+    /// A replacement only adds what it pays over the tx it replaces.
+    pub fees_spent: Vec<(Option<String>, Amount)>,
+}
+
+impl TxSenderDb {
+    /// Returns the current [`TxSenderStats`].
+    pub async fn get_tx_sender_stats(
+        &self,
+        mut tx: Option<TxSenderDbTx<'_>>,
+    ) -> Result<TxSenderStats, BridgeError> {
+        let query = sqlx::query_as::<_, (i64, Option<f64>)>(
+            "SELECT COUNT(*), EXTRACT(EPOCH FROM NOW() - MIN(created_at))::FLOAT8
+             FROM tx_sender_try_to_send_txs
+             WHERE seen_at_height IS NULL",
+        );
+        let (unconfirmed_txs, oldest_unconfirmed_age) =
+            txsender_execute_query_with_tx!(&self.pool, tx.as_deref_mut(), query, fetch_one)?;
+
+        let query = sqlx::query_as::<_, (i64,)>(
+            "SELECT COALESCE(SUM(fpu.amount), 0)::BIGINT
+             FROM tx_sender_fee_payer_utxos fpu
+             JOIN tx_sender_try_to_send_txs txs ON txs.id = fpu.bumped_id
+             WHERE fpu.is_evicted = false
+               AND fpu.seen_at_height IS NOT NULL
+               AND txs.seen_at_height IS NULL",
+        );
+        let (fee_payer_balance,) =
+            txsender_execute_query_with_tx!(&self.pool, tx.as_deref_mut(), query, fetch_one)?;
+
+        let query = sqlx::query_as::<_, (Option<String>, i64)>(
+            "SELECT tx_type, COALESCE(SUM(spent), 0)::BIGINT
+             FROM tx_sender_fee_spending
+             GROUP BY tx_type",
+        );
+        let fees_spent: Vec<(Option<String>, i64)> =
+            txsender_execute_query_with_tx!(&self.pool, tx, query, fetch_all)?;
+
+        Ok(TxSenderStats {
+            unconfirmed_txs: u64::try_from(unconfirmed_txs)
+                .wrap_err("Failed to convert unconfirmed tx count to u64")?,
+            oldest_unconfirmed_age: oldest_unconfirmed_age
+                .map(|secs| Duration::from_secs_f64(secs.max(0.0))),
+            fee_payer_balance: sats_from_db(fee_payer_balance, "fee payer balance")?,
+            fees_spent: fees_spent
+                .into_iter()
+                .map(|(tx_type, fee)| Ok((tx_type, sats_from_db(fee, "fee")?)))
+                .collect::<Result<_, BridgeError>>()?,
+        })
+    }
+}
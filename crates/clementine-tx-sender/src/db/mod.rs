@@ -21,6 +21,9 @@ pub type TxSenderDbTx<'a> = &'a mut TxSenderTransaction;
 
 #[cfg(feature = "citrea")]
 pub mod citrea;
+pub mod dry_run;
+pub mod fee_budget;
+pub mod metrics;
 pub mod tx_sender;
 pub mod wrapper;
 
@@ -0,0 +1,168 @@
+//! # Dry Run Mode For Transaction Sender
+//!
+//! When [`crate::config::TxSenderConfig::dry_run`] is set, the tx-sender loop
+//! still computes target fee rates and builds RBF replacements, CPFP packages
+//! and fee payer transactions exactly as it would normally. Right before a
+//! broadcast, the transactions are instead checked with `testmempoolaccept`
+//! and recorded in the `tx_sender_dry_run_broadcasts` table together with the
+//! targeted fee rate and the fee they pay.
+//!
+//! Nothing is sent via `sendrawtransaction`, `submitpackage` or `bumpfee`, and
+//! the tx-sender state of a transaction (RBF txids, effective fee rate, fee
+//! payer UTXOs) is left untouched. This means a dry run does not progress: the
+//! same transactions are re-evaluated on every loop iteration with the current
+//! network fee rate, which is what is needed to check fee decisions after a
+//! config change. Each evaluation replaces the previous record of the same
+//! broadcast.
+//!
+//! A CPFP tx without enough confirmed fee payer UTXOs would normally wait for a
+//! new fee payer tx to confirm. In dry run mode, the fee payer tx is recorded
+//! and its output is used as a synthetic fee payer UTXO to build the CPFP child
+//! in the same iteration. The fee payer tx, parent and child are checked
+//! together with `testmempoolaccept`.
+
+use crate::{Result, TxSender};
+use bitcoin::{Amount, Transaction};
+use bitcoincore_rpc::RpcApi;
+use clementine_primitives::FeeRateKvb;
+use eyre::Context;
+
+/// Kind of a broadcast that was skipped because of dry run mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DryRunBroadcastKind {
+    /// A fully funded tx sent as is.
+    NoFunding,
+    /// First RBF attempt of a tx.
+    RbfInitial,
+    /// Fee bump of an RBF tx already in the mempool.
+    RbfBump,
+    /// Parent + child package of a CPFP tx.
+    CpfpPackage,
+    /// Tx creating a fee payer UTXO for CPFP.
+    CpfpFeePayer,
+    /// Nonstandard tx that would be sent to the testnet4 mempool.space accelerator.
+    Testnet4Nonstandard,
+    /// Citrea DA commit tx.
+    CitreaCommit,
+}
+
+impl DryRunBroadcastKind {
+    /// Returns the value stored in the `kind` column of `tx_sender_dry_run_broadcasts`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DryRunBroadcastKind::NoFunding => "no_funding",
+            DryRunBroadcastKind::RbfInitial => "rbf_initial",
+            DryRunBroadcastKind::RbfBump => "rbf_bump",
+            DryRunBroadcastKind::CpfpPackage => "cpfp_package",
+            DryRunBroadcastKind::CpfpFeePayer => "cpfp_fee_payer",
+            DryRunBroadcastKind::Testnet4Nonstandard => "testnet4_nonstandard",
+            DryRunBroadcastKind::CitreaCommit => "citrea_commit",
+        }
+    }
+}
+
+impl std::fmt::Display for DryRunBroadcastKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Returns the fee rate `tx` pays given its absolute fee, for txs that are
+/// not built against a target fee rate (e.g. fully funded no funding txs).
+pub(crate) fn paid_fee_rate(tx: &Transaction, fee: Option<Amount>) -> FeeRateKvb {
+    let vsize = (tx.vsize() as u64).max(1);
+    FeeRateKvb::from_sat_per_kvb(fee.map_or(0, |fee| fee.to_sat().saturating_mul(1000) / vsize))
+}
+
+impl TxSender {
+    /// Records transactions of a single kind that would have been broadcast,
+    /// instead of broadcasting them. See [`Self::record_dry_run_broadcasts`].
+    pub(crate) async fn record_dry_run_broadcast(
+        &self,
+        try_to_send_id: Option<u32>,
+        kind: DryRunBroadcastKind,
+        txs: &[(&Transaction, Option<Amount>)],
+        fee_rate: FeeRateKvb,
+    ) -> Result<()> {
+        let txs: Vec<_> = txs.iter().map(|(tx, fee)| (kind, *tx, *fee)).collect();
+        self.record_dry_run_broadcasts(try_to_send_id, &txs, fee_rate)
+            .await
+    }
+
+    /// Records transactions that would have been broadcast, instead of
+    /// broadcasting them.
+    ///
+    /// All transactions are checked together with `testmempoolaccept` (so a
+    /// CPFP parent and child are evaluated as a package), and one row per
+    /// transaction is written to `tx_sender_dry_run_broadcasts`, replacing the
+    /// row of the previous evaluation. If `testmempoolaccept` itself fails, the
+    /// transactions are still recorded without a mempool result.
+    ///
+    /// # Arguments
+    /// * `try_to_send_id` - The tx-sender row the broadcast belongs to, if any.
+    /// * `txs` - The transactions in broadcast order, with the kind of broadcast
+    ///   each one belongs to and the absolute fee it pays, if known.
+    /// * `fee_rate` - The target fee rate used while building the transactions.
+    pub(crate) async fn record_dry_run_broadcasts(
+        &self,
+        try_to_send_id: Option<u32>,
+        txs: &[(DryRunBroadcastKind, &Transaction, Option<Amount>)],
+        fee_rate: FeeRateKvb,
+    ) -> Result<()> {
+        let raw_txs: Vec<&Transaction> = txs.iter().map(|(_, tx, _)| *tx).collect();
+
+        let mempool_results = match self.rpc.test_mempool_accept(&raw_txs).await {
+            Ok(results) => Some(results),
+            Err(e) => {
+                tracing::warn!(?try_to_send_id, "DRY RUN: testmempoolaccept failed: {e}");
+                None
+            }
+        };
+
+        for (i, (kind, tx, fee)) in txs.iter().enumerate() {
+            let txid = tx.compute_txid();
+            let mempool_result = mempool_results
+                .as_ref()
+                .and_then(|results| results.iter().find(|res| res.txid == txid));
+            let allowed = mempool_result.and_then(|res| res.allowed);
+            let reject_reason = mempool_result.and_then(|res| res.reject_reason.as_deref());
+            // Position among the txs of the same kind, e.g. CPFP parent and child.
+            let package_index = txs[..i].iter().filter(|(k, ..)| k == kind).count();
+
+            tracing::info!(
+                ?try_to_send_id,
+                %kind,
+                %txid,
+                fee_rate = %fee_rate,
+                fee = ?fee,
+                allowed = ?allowed,
+                reject_reason = ?reject_reason,
+                "DRY RUN: skipping broadcast"
+            );
+
+            self.db
+                .save_dry_run_broadcast(
+                    None,
+                    try_to_send_id,
+                    kind.as_str(),
+                    u32::try_from(package_index).wrap_err("Too many txs in dry run broadcast")?,
+                    tx,
+                    fee_rate,
+                    *fee,
+                    allowed,
+                    reject_reason,
+                )
+                .await
+                .wrap_err("Failed to save dry run broadcast")?;
+        }
+
+        if let Some(try_to_send_id) = try_to_send_id {
+            let _ = self
+                .db
+                .update_tx_debug_sending_state(try_to_send_id, "dry_run_recorded", true)
+                .await;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+//! # Fee Budget For Transaction Sender
+//!
+//! `fee_rate_hard_cap` only limits a single transaction. The fee budget in
+//! [`clementine_config::TxSenderFeeBudget`] additionally limits how many sats
+//! the tx-sender spends on fees in a rolling hour and day, globally and per
+//! [`TransactionType`].
+//!
+//! Right before the tx-sender broadcasts a tx paid by its wallet, the fee it
+//! adds is checked against every applicable window. This covers RBF txs, CPFP
+//! children, fee payer funding txs and their `bumpfee` replacements. For the
+//! tracked tx and its CPFP child, only the increase over what was already
+//! spent for the same `try_to_send_id` is counted, as a replacement does not
+//! pay for the tx it replaces. If any window would be exceeded, the broadcast
+//! is skipped and retried on the next loop iteration, so held transactions go
+//! out on their own once older spending leaves the window. During incident
+//! response the budget can be raised temporarily with
+//! [`TxSenderDb::save_fee_budget_raise`].
+//!
+//! The check and the spending it allows are committed in one database
+//! transaction under a lock on the spending table, so tx-senders sharing a
+//! database can't both fit into the same remaining budget. The spending is
+//! removed again if the broadcast fails.
+//!
+//! Fees are recorded even while no budget is configured, so the spending still
+//! shows up in the tx-sender stats. Only the window checks are skipped then.
+//!
+//! [`TxSenderDb::save_fee_budget_raise`]: crate::db::TxSenderDb::save_fee_budget_raise
+
+use crate::db::TxSenderTransaction;
+use crate::{Result, TxSender};
+use bitcoin::{Amount, Transaction};
+use clementine_config::FeeBudgetLimits;
+use clementine_primitives::TransactionType;
+use clementine_utils::TxMetadata;
+use eyre::Context;
+use std::time::Duration;
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Name of a tx type as used in the fee budget config, without its index
+/// (e.g. `WatchtowerChallenge(3)` becomes `WatchtowerChallenge`).
+pub fn tx_type_budget_key(tx_type: &TransactionType) -> String {
+    tx_type.name().to_string()
+}
+
+/// A broadcast allowed by the fee budget. Its spending is already recorded and
+/// must be released with [`TxSender::release_fee_budget`] if the broadcast fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FeeBudgetSpend {
+    /// The row of the recorded spending.
+    id: u32,
+}
+
+/// The fee of a tx about to be broadcast.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FeeBudgetCharge {
+    /// Fee of the tracked tx or its CPFP child, replacing earlier broadcasts.
+    Tx(Amount),
+    /// Fee of a fee payer funding tx, `spent` of which was not paid before.
+    FeePayer { fee: Amount, spent: Amount },
+}
+
+impl TxSender {
+    /// Checks the fee budget for an RBF tx, whose whole fee is paid by the wallet.
+    ///
+    /// # Returns
+    /// `None` if the tx must be held.
+    pub(crate) async fn check_rbf_fee_budget(
+        &self,
+        try_to_send_id: u32,
+        tx_metadata: Option<&TxMetadata>,
+        tx: &Transaction,
+    ) -> Result<Option<FeeBudgetSpend>> {
+        let fee = self.get_tx_fee(tx).await?;
+        self.check_fee_budget(try_to_send_id, tx_metadata, fee)
+            .await
+    }
+
+    /// Checks whether broadcasting a tx paying `fee` fits in the fee budget.
+    ///
+    /// Only the increase over the highest fee already recorded for
+    /// `try_to_send_id` is counted, as a replacement does not pay for the tx it
+    /// replaces. If the budget would be exceeded the tx is marked as held and
+    /// `tx_sender_fee_budget_held_total` is incremented.
+    ///
+    /// # Returns
+    /// `None` if the tx must be held.
+    pub(crate) async fn check_fee_budget(
+        &self,
+        try_to_send_id: u32,
+        tx_metadata: Option<&TxMetadata>,
+        fee: Amount,
+    ) -> Result<Option<FeeBudgetSpend>> {
+        self.reserve_fee_budget(try_to_send_id, tx_metadata, FeeBudgetCharge::Tx(fee))
+            .await
+    }
+
+    /// Checks whether a fee payer funding tx for `try_to_send_id`, or a
+    /// `bumpfee` replacement of one, fits in the fee budget.
+    ///
+    /// # Arguments
+    /// * `fee` - The fee of the funding tx.
+    /// * `spent` - The part of `fee` not already paid by the tx it replaces.
+    ///
+    /// # Returns
+    /// `None` if the tx must be held.
+    pub(crate) async fn check_fee_payer_fee_budget(
+        &self,
+        try_to_send_id: u32,
+        tx_metadata: Option<&TxMetadata>,
+        fee: Amount,
+        spent: Amount,
+    ) -> Result<Option<FeeBudgetSpend>> {
+        self.reserve_fee_budget(
+            try_to_send_id,
+            tx_metadata,
+            FeeBudgetCharge::FeePayer { fee, spent },
+        )
+        .await
+    }
+
+    /// Records `charge` if it fits in every applicable budget window. Without a
+    /// configured budget, `charge` is always recorded.
+    async fn reserve_fee_budget(
+        &self,
+        try_to_send_id: u32,
+        tx_metadata: Option<&TxMetadata>,
+        charge: FeeBudgetCharge,
+    ) -> Result<Option<FeeBudgetSpend>> {
+        let budget = &self.tx_sender_limits.fee_budget;
+
+        let mut dbtx = self
+            .db
+            .begin_transaction()
+            .await
+            .wrap_err("Failed to begin fee budget transaction")?;
+        self.db
+            .lock_fee_spending(&mut dbtx)
+            .await
+            .wrap_err("Failed to lock fee spending")?;
+
+        let (fee, spent, fee_payer) = match charge {
+            FeeBudgetCharge::Tx(fee) => {
+                let already_spent = self
+                    .db
+                    .get_max_recorded_fee(Some(&mut dbtx), try_to_send_id)
+                    .await
+                    .wrap_err("Failed to get recorded fee")?;
+                let spent = fee.checked_sub(already_spent).unwrap_or(Amount::ZERO);
+                (fee, spent, false)
+            }
+            FeeBudgetCharge::FeePayer { fee, spent } => (fee, spent, true),
+        };
+
+        let tx_type = tx_metadata.map(|metadata| tx_type_budget_key(&metadata.tx_type));
+
+        if budget.is_enabled() && spent != Amount::ZERO {
+            let mut scopes = vec![(None, &budget.global)];
+            if let Some(limits) = tx_type.as_ref().and_then(|t| budget.per_tx_type.get(t)) {
+                scopes.push((tx_type.as_deref(), limits));
+            }
+
+            for (scope_tx_type, limits) in scopes {
+                if let Some(window) = self
+                    .exceeded_fee_budget_window(&mut dbtx, scope_tx_type, limits, spent)
+                    .await?
+                {
+                    let scope = scope_tx_type.unwrap_or("global");
+                    tracing::warn!(
+                        try_to_send_id,
+                        ?tx_metadata,
+                        %fee,
+                        %spent,
+                        fee_payer,
+                        scope,
+                        window,
+                        "Fee budget exceeded, holding tx"
+                    );
+                    metrics::counter!(
+                        "tx_sender_fee_budget_held_total",
+                        "scope" => scope.to_string(),
+                        "window" => window
+                    )
+                    .increment(1);
+                    let _ = self
+                        .db
+                        .update_tx_debug_sending_state(try_to_send_id, "held_fee_budget", true)
+                        .await;
+                    return Ok(None);
+                }
+            }
+        }
+
+        let id = self
+            .db
+            .save_fee_spending(
+                Some(&mut dbtx),
+                try_to_send_id,
+                tx_type.as_deref(),
+                fee,
+                spent,
+                fee_payer,
+            )
+            .await
+            .wrap_err("Failed to record fee spending")?;
+        self.db
+            .commit_transaction(dbtx)
+            .await
+            .wrap_err("Failed to commit fee spending")?;
+
+        Ok(Some(FeeBudgetSpend { id }))
+    }
+
+    /// Returns the name of the first window in which spending `spent` more sats
+    /// would go over `limits` plus the active raises for `tx_type`.
+    async fn exceeded_fee_budget_window(
+        &self,
+        dbtx: &mut TxSenderTransaction,
+        tx_type: Option<&str>,
+        limits: &FeeBudgetLimits,
+        spent: Amount,
+    ) -> Result<Option<&'static str>> {
+        if !limits.is_limited() {
+            return Ok(None);
+        }
+
+        let raise = self
+            .db
+            .get_active_fee_budget_raise(Some(&mut *dbtx), tx_type)
+            .await
+            .wrap_err("Failed to get fee budget raises")?;
+
+        for (name, window, limit, extra) in [
+            ("hour", HOUR, limits.max_sats_per_hour, raise.extra_per_hour),
+            ("day", DAY, limits.max_sats_per_day, raise.extra_per_day),
+        ] {
+            let Some(limit) = limit else {
+                continue;
+            };
+            let spent_in_window = self
+                .db
+                .get_fee_spent_in_window(Some(&mut *dbtx), tx_type, window)
+                .await
+                .wrap_err("Failed to get spent fees")?;
+            if spent_in_window + spent > Amount::from_sat(limit) + extra {
+                return Ok(Some(name));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Removes the spending recorded for a broadcast that failed, so it doesn't
+    /// count against the budget.
+    pub(crate) async fn release_fee_budget(&self, spend: FeeBudgetSpend) {
+        let FeeBudgetSpend { id } = spend;
+        if let Err(e) = self.db.delete_fee_spending(None, id).await {
+            tracing::warn!(id, "Failed to release fee spending: {e:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tx_type_budget_key_strips_index() {
+        assert_eq!(tx_type_budget_key(&TransactionType::Kickoff), "Kickoff");
+        assert_eq!(
+            tx_type_budget_key(&TransactionType::WatchtowerChallenge(3)),
+            "WatchtowerChallenge"
+        );
+    }
+
+    #[test]
+    fn fee_budget_rejects_unknown_tx_types() {
+        let budget: clementine_config::TxSenderFeeBudget = serde_json::from_str(
+            r#"{"per_tx_type": {"WatchtowerChallenge": {"max_sats_per_day": 1000}}}"#,
+        )
+        .unwrap();
+        assert!(budget.is_enabled());
+
+        // A typo would otherwise leave watchtower challenges without a limit.
+        assert!(
+            serde_json::from_str::<clementine_config::TxSenderFeeBudget>(
+                r#"{"per_tx_type": {"WatchtowerChallenges": {"max_sats_per_day": 1000}}}"#,
+            )
+            .is_err()
+        );
+    }
+}
@@ -10,8 +10,11 @@ pub mod config;
 mod confirmations;
 pub mod cpfp;
 pub mod db;
+pub mod dry_run;
+pub mod fee_budget;
 #[cfg(feature = "json-rpc")]
 pub mod jsonrpc;
+pub mod metrics;
 pub mod nonstandard;
 pub mod rbf;
 mod rpc_errors;
@@ -137,6 +140,8 @@ pub struct TxSender {
     mempool_config: MempoolConfig,
     /// Whether to include unsafe UTXOs when funding transactions.
     include_unsafe: bool,
+    /// If true, transactions are built and checked but never broadcast.
+    dry_run: bool,
 }
 
 impl std::fmt::Debug for TxSender {
@@ -150,6 +155,7 @@ impl std::fmt::Debug for TxSender {
             .field("network", &self.network)
             .field("tx_sender_limits", &self.tx_sender_limits)
             .field("include_unsafe", &self.include_unsafe)
+            .field("dry_run", &self.dry_run)
             .finish()
     }
 }
@@ -163,6 +169,11 @@ impl TxSender {
         self.signer.xonly_public_key()
     }
 
+    /// Returns true if the tx-sender is running in dry run mode.
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
     /// Creates a new TxSender.
     pub async fn new(
         tx_sender_config: crate::config::TxSenderConfig,
@@ -199,6 +210,7 @@ impl TxSender {
             http_client: reqwest::Client::new(),
             mempool_config: tx_sender_config.mempool,
             include_unsafe: tx_sender_config.include_unsafe,
+            dry_run: tx_sender_config.dry_run,
         })
     }
 
@@ -427,7 +439,8 @@ impl TxSender {
                 _ if self.network == bitcoin::Network::Testnet4
                     && self.is_bridge_tx_nonstandard(&tx) =>
                 {
-                    self.send_testnet4_nonstandard_tx(&tx, id).await
+                    self.send_testnet4_nonstandard_tx(&tx, id, tx_metadata.as_ref())
+                        .await
                 }
                 FeePayingType::CPFP => {
                     self.send_cpfp_tx(id, tx, tx_metadata, adjusted_fee_rate, current_tip_height)
@@ -561,6 +574,7 @@ impl TxSender {
     /// * `tx_metadata` - Optional metadata associated with the transaction for debugging.
     ///
     /// # Behavior
+    /// 0. In dry run mode, records the transaction instead of broadcasting it.
     /// 1. Attempts to broadcast the transaction using `send_raw_transaction` RPC.
     /// 2. Updates the database with success/failure state for debugging purposes.
     /// 3. Logs appropriate messages for monitoring and troubleshooting.
@@ -575,6 +589,18 @@ impl TxSender {
         tx: Transaction,
         tx_metadata: Option<TxMetadata>,
     ) -> Result<()> {
+        if self.dry_run {
+            let fee = self.get_tx_fee(&tx).await.ok();
+            return self
+                .record_dry_run_broadcast(
+                    Some(try_to_send_id),
+                    dry_run::DryRunBroadcastKind::NoFunding,
+                    &[(&tx, fee)],
+                    dry_run::paid_fee_rate(&tx, fee),
+                )
+                .await;
+        }
+
         match self.rpc.send_raw_transaction(&tx).await {
             Ok(sent_txid) => {
                 tracing::debug!(
@@ -582,6 +608,10 @@ impl TxSender {
                     "Successfully sent no funding tx with txid {}",
                     sent_txid
                 );
+                crate::metrics::record_submission(
+                    crate::metrics::SubmissionKind::NoFunding,
+                    tx_metadata.as_ref(),
+                );
                 let _ = self
                     .db
                     .update_tx_debug_sending_state(try_to_send_id, "no_funding_send_success", true)
@@ -589,6 +619,11 @@ impl TxSender {
             }
             Err(e) => {
                 let err_str = e.to_string();
+                crate::metrics::record_rejection(
+                    crate::metrics::SubmissionKind::NoFunding,
+                    tx_metadata.as_ref(),
+                    &err_str,
+                );
                 if rpc_errors::is_rejecting_replacement_error(&err_str) {
                     tracing::debug!(
                         try_to_send_id,
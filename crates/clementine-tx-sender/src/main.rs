@@ -14,6 +14,16 @@ async fn main() -> Result<(), eyre::Report> {
         ));
     }
 
+    if let Some(metrics) = &config.metrics {
+        let bind: std::net::IpAddr = metrics.bind.parse()?;
+        let addr = std::net::SocketAddr::new(bind, metrics.port);
+        tracing::info!("Exporting Prometheus metrics at {addr}");
+        metrics_exporter_prometheus::PrometheusBuilder::new()
+            .with_http_listener(addr)
+            .install()
+            .map_err(|e| eyre::eyre!("Failed to install Prometheus exporter: {e}"))?;
+    }
+
     let db = clementine_tx_sender::TxSenderDb::connect(&config.postgres).await?;
     db.run_migrations().await?;
     db.pool().close().await;
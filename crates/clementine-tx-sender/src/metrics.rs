@@ -0,0 +1,246 @@
+//! # Metrics
+//!
+//! Prometheus metrics for tx-sender, recorded through the `metrics` facade.
+//! Inside clementine_core they are exported by the core Prometheus exporter,
+//! the standalone binary exports them itself when `TX_SENDER_METRICS_PORT` is
+//! set.
+//!
+//! Counters are labelled by tx type, named as in the fee budget config (see
+//! [`tx_type_budget_key`]), or `unknown` for txs sent without metadata. Gauges
+//! are refreshed from the database once per tx-sender loop iteration.
+
+use crate::db::metrics::TxSenderStats;
+use crate::fee_budget::tx_type_budget_key;
+use crate::rpc_errors::rejection_reason;
+use crate::TxSender;
+use clementine_errors::BridgeError;
+use clementine_utils::TxMetadata;
+use std::sync::Once;
+
+const UNKNOWN_TX_TYPE: &str = "unknown";
+
+/// How a tx was handed to the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmissionKind {
+    /// A CPFP parent and child submitted with `submitpackage`.
+    CpfpPackage,
+    /// The first funded version of an RBF tx.
+    RbfInitial,
+    /// A fee bump replacing a previous version of an RBF tx.
+    RbfBump,
+    NoFunding,
+    /// Submitted to the mempool.space accelerator on testnet4.
+    Testnet4Nonstandard,
+}
+
+impl SubmissionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubmissionKind::CpfpPackage => "cpfp_package",
+            SubmissionKind::RbfInitial => "rbf_initial",
+            SubmissionKind::RbfBump => "rbf_bump",
+            SubmissionKind::NoFunding => "no_funding",
+            SubmissionKind::Testnet4Nonstandard => "testnet4_nonstandard",
+        }
+    }
+}
+
+/// Returns the `tx_type` label of a tx.
+pub fn tx_type_label(tx_metadata: Option<&TxMetadata>) -> String {
+    tx_metadata
+        .map(|metadata| tx_type_budget_key(&metadata.tx_type))
+        .unwrap_or_else(|| UNKNOWN_TX_TYPE.to_string())
+}
+
+/// Registers descriptions for the tx-sender metrics. Only the first call has an effect.
+pub fn describe_metrics() {
+    static DESCRIBE: Once = Once::new();
+    DESCRIBE.call_once(|| {
+        metrics::describe_counter!(
+            "tx_sender_submissions_total",
+            "Txs accepted for broadcast, by submission kind and tx type"
+        );
+        metrics::describe_counter!(
+            "tx_sender_rbf_bumps_total",
+            "RBF fee bumps broadcast, by tx type"
+        );
+        metrics::describe_counter!(
+            "tx_sender_cpfp_packages_total",
+            "CPFP packages submitted, by tx type"
+        );
+        metrics::describe_counter!(
+            "tx_sender_rejections_total",
+            "Broadcasts rejected by the node or accelerator, by submission kind, tx type and reason"
+        );
+        metrics::describe_counter!(
+            "tx_sender_fee_payer_evictions_total",
+            "CPFP fee payer txs found evicted from the mempool, by tx type of the bumped tx"
+        );
+        metrics::describe_counter!(
+            "tx_sender_fee_budget_held_total",
+            "Broadcasts held because they would exceed the fee budget"
+        );
+        metrics::describe_gauge!(
+            "tx_sender_unconfirmed_txs",
+            "Txs that were not seen onchain yet"
+        );
+        metrics::describe_gauge!(
+            "tx_sender_oldest_unconfirmed_tx_age_seconds",
+            "Seconds since the oldest unconfirmed tx was added, 0 if there is none"
+        );
+        metrics::describe_gauge!(
+            "tx_sender_fee_payer_balance_sats",
+            "Confirmed fee payer UTXOs waiting to be spent by a CPFP child"
+        );
+        metrics::describe_gauge!(
+            "tx_sender_fees_spent_sats",
+            "Total fees paid by broadcast txs, by tx type, without the fees of replaced txs"
+        );
+    });
+}
+
+/// Counts a tx accepted by the network.
+pub(crate) fn record_submission(kind: SubmissionKind, tx_metadata: Option<&TxMetadata>) {
+    let tx_type = tx_type_label(tx_metadata);
+    match kind {
+        SubmissionKind::RbfBump => {
+            metrics::counter!("tx_sender_rbf_bumps_total", "tx_type" => tx_type.clone())
+                .increment(1);
+        }
+        SubmissionKind::CpfpPackage => {
+            metrics::counter!("tx_sender_cpfp_packages_total", "tx_type" => tx_type.clone())
+                .increment(1);
+        }
+        _ => {}
+    }
+    metrics::counter!(
+        "tx_sender_submissions_total",
+        "kind" => kind.as_str(),
+        "tx_type" => tx_type
+    )
+    .increment(1);
+}
+
+/// Counts a rejected broadcast, classifying `error` into a reason.
+pub(crate) fn record_rejection(
+    kind: SubmissionKind,
+    tx_metadata: Option<&TxMetadata>,
+    error: &str,
+) {
+    metrics::counter!(
+        "tx_sender_rejections_total",
+        "kind" => kind.as_str(),
+        "tx_type" => tx_type_label(tx_metadata),
+        "reason" => rejection_reason(error)
+    )
+    .increment(1);
+}
+
+/// Counts a fee payer tx found evicted from the mempool.
+pub(crate) fn record_fee_payer_eviction(tx_metadata: Option<&TxMetadata>) {
+    metrics::counter!(
+        "tx_sender_fee_payer_evictions_total",
+        "tx_type" => tx_type_label(tx_metadata)
+    )
+    .increment(1);
+}
+
+/// Sets the tx-sender gauges from `stats`.
+pub fn publish_stats(stats: &TxSenderStats) {
+    metrics::gauge!("tx_sender_unconfirmed_txs").set(stats.unconfirmed_txs as f64);
+    metrics::gauge!("tx_sender_oldest_unconfirmed_tx_age_seconds").set(
+        stats
+            .oldest_unconfirmed_age
+            .map_or(0.0, |age| age.as_secs_f64()),
+    );
+    metrics::gauge!("tx_sender_fee_payer_balance_sats")
+        .set(stats.fee_payer_balance.to_sat() as f64);
+    for (tx_type, fee) in &stats.fees_spent {
+        metrics::gauge!(
+            "tx_sender_fees_spent_sats",
+            "tx_type" => tx_type.clone().unwrap_or_else(|| UNKNOWN_TX_TYPE.to_string())
+        )
+        .set(fee.to_sat() as f64);
+    }
+}
+
+impl TxSender {
+    /// Refreshes the tx-sender gauges from the database.
+    pub async fn publish_metrics(&self) -> Result<(), BridgeError> {
+        let stats = self.db.get_tx_sender_stats(None).await?;
+        publish_stats(&stats);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::Amount;
+    use clementine_primitives::TransactionType;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    use std::time::Duration;
+
+    #[test]
+    fn test_counters_and_gauges_are_labelled_by_tx_type() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let metadata = TxMetadata {
+            deposit_outpoint: None,
+            operator_xonly_pk: None,
+            round_idx: None,
+            kickoff_idx: None,
+            tx_type: TransactionType::WatchtowerChallenge(3),
+        };
+        metrics::with_local_recorder(&recorder, || {
+            record_submission(SubmissionKind::RbfBump, Some(&metadata));
+            record_rejection(
+                SubmissionKind::NoFunding,
+                None,
+                "bad-txns-inputs-missingorspent",
+            );
+            publish_stats(&TxSenderStats {
+                unconfirmed_txs: 2,
+                oldest_unconfirmed_age: Some(Duration::from_secs(30)),
+                fee_payer_balance: Amount::from_sat(5_000),
+                fees_spent: vec![(Some("Kickoff".to_string()), Amount::from_sat(700))],
+            });
+        });
+
+        let snapshot = snapshotter.snapshot().into_vec();
+        let value_of = |name: &str, label: (&str, &str)| {
+            snapshot
+                .iter()
+                .find(|(key, _, _, _)| {
+                    key.key().name() == name
+                        && key
+                            .key()
+                            .labels()
+                            .any(|l| l.key() == label.0 && l.value() == label.1)
+                })
+                .map(|(_, _, _, value)| value.clone())
+        };
+        assert_eq!(
+            value_of(
+                "tx_sender_rbf_bumps_total",
+                ("tx_type", "WatchtowerChallenge")
+            ),
+            Some(DebugValue::Counter(1))
+        );
+        assert_eq!(
+            value_of("tx_sender_submissions_total", ("kind", "rbf_bump")),
+            Some(DebugValue::Counter(1))
+        );
+        assert_eq!(
+            value_of(
+                "tx_sender_rejections_total",
+                ("reason", "inputs_missing_or_spent")
+            ),
+            Some(DebugValue::Counter(1))
+        );
+        assert_eq!(
+            value_of("tx_sender_fees_spent_sats", ("tx_type", "Kickoff")),
+            Some(DebugValue::Gauge(700.0.into()))
+        );
+    }
+}
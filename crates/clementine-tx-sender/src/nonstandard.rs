@@ -1,7 +1,10 @@
+use crate::dry_run::DryRunBroadcastKind;
+use crate::metrics::{record_rejection, record_submission, SubmissionKind};
 use crate::{log_error_for_tx, TxSender};
 use bitcoin::consensus::serialize;
 use bitcoin::Transaction;
 use clementine_errors::SendTxError;
+use clementine_utils::TxMetadata;
 use std::collections::HashMap;
 
 impl TxSender {
@@ -29,6 +32,8 @@ impl TxSender {
     ///
     /// Arguments:
     /// * `tx` - The transaction to send.
+    /// * `try_to_send_id` - The database ID tracking this send attempt.
+    /// * `tx_metadata` - Optional metadata associated with the transaction.
     ///
     /// Returns:
     /// * `Ok(())` if the transaction is sent successfully to the accelerator.
@@ -43,7 +48,20 @@ impl TxSender {
         &self,
         tx: &Transaction,
         try_to_send_id: u32,
+        tx_metadata: Option<&TxMetadata>,
     ) -> Result<(), SendTxError> {
+        if self.dry_run {
+            let fee = self.get_tx_fee(tx).await.ok();
+            return self
+                .record_dry_run_broadcast(
+                    Some(try_to_send_id),
+                    DryRunBroadcastKind::Testnet4Nonstandard,
+                    &[(tx, fee)],
+                    crate::dry_run::paid_fee_rate(tx, fee),
+                )
+                .await;
+        }
+
         // Get API key from environment variable
         let api_key = std::env::var("MEMPOOL_SPACE_API_KEY").map_err(|_| {
             SendTxError::Other(eyre::eyre!(
@@ -136,6 +154,7 @@ impl TxSender {
                 txid,
                 response_text
             );
+            record_submission(SubmissionKind::Testnet4Nonstandard, tx_metadata);
 
             let _ = self
                 .db
@@ -153,6 +172,11 @@ impl TxSender {
                 .text()
                 .await
                 .unwrap_or_else(|_| "Unknown error".to_string());
+            record_rejection(
+                SubmissionKind::Testnet4Nonstandard,
+                tx_metadata,
+                &error_text,
+            );
 
             log_error_for_tx!(
                 self.db,
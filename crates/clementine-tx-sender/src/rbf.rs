@@ -1,3 +1,5 @@
+use crate::dry_run::DryRunBroadcastKind;
+use crate::metrics::{record_rejection, record_submission, SubmissionKind};
 use crate::{log_error_for_tx, TxSender};
 use bitcoin::absolute::{LockTime, LOCK_TIME_THRESHOLD};
 use bitcoin::hashes::Hash;
@@ -547,6 +549,12 @@ impl TxSender {
     ///     - Uses `send_raw_transaction` RPC to broadcast the initial RBF transaction.
     ///     - Saves the resulting `txid` to the database as the `last_rbf_txid`.
     ///
+    /// In dry run mode the final transaction is recorded instead of broadcast and
+    /// neither the RBF txid nor the effective fee rate is saved.
+    ///
+    /// If a fee budget is configured, the final transaction is held without being
+    /// broadcast while its fee would exceed the budget (see [`crate::fee_budget`]).
+    ///
     /// # Arguments
     /// * `try_to_send_id` - The database ID tracking this send attempt.
     /// * `tx` - The original transaction intended for RBF (used only on the first attempt).
@@ -788,11 +796,33 @@ impl TxSender {
                 }
             };
 
+            if self.dry_run {
+                let fee = self.get_tx_fee(&final_tx).await.ok();
+                return self
+                    .record_dry_run_broadcast(
+                        Some(try_to_send_id),
+                        DryRunBroadcastKind::RbfBump,
+                        &[(&final_tx, fee)],
+                        effective_feerate,
+                    )
+                    .await;
+            }
+
+            let fee_budget_spend = self
+                .check_rbf_fee_budget(try_to_send_id, tx_metadata.as_ref(), &final_tx)
+                .await?;
+            let Some(fee_budget_spend) = fee_budget_spend else {
+                return Ok(());
+            };
+
             let bumped_txid = final_tx.compute_txid();
 
             // Broadcast the finalized transaction
             let sent_txid = match self.rpc.send_raw_transaction(&final_tx).await {
-                Ok(sent_txid) if sent_txid == bumped_txid => sent_txid,
+                Ok(sent_txid) if sent_txid == bumped_txid => {
+                    record_submission(SubmissionKind::RbfBump, tx_metadata.as_ref());
+                    sent_txid
+                }
                 Ok(other_txid) => {
                     log_error_for_tx!(
                         self.db,
@@ -815,6 +845,11 @@ impl TxSender {
                     )));
                 }
                 Err(e) => {
+                    record_rejection(
+                        SubmissionKind::RbfBump,
+                        tx_metadata.as_ref(),
+                        &e.to_string(),
+                    );
                     log_error_for_tx!(
                         self.db,
                         try_to_send_id,
@@ -824,6 +859,7 @@ impl TxSender {
                         .db
                         .update_tx_debug_sending_state(try_to_send_id, "rbf_bump_send_failed", true)
                         .await;
+                    self.release_fee_budget(fee_budget_spend).await;
                     return Err(SendTxError::Other(eyre!(e)));
                 }
             };
@@ -1035,6 +1071,25 @@ impl TxSender {
                 }
             };
 
+            if self.dry_run {
+                let fee = self.get_tx_fee(&final_tx).await.ok();
+                return self
+                    .record_dry_run_broadcast(
+                        Some(try_to_send_id),
+                        DryRunBroadcastKind::RbfInitial,
+                        &[(&final_tx, fee)],
+                        fee_rate,
+                    )
+                    .await;
+            }
+
+            let fee_budget_spend = self
+                .check_rbf_fee_budget(try_to_send_id, tx_metadata.as_ref(), &final_tx)
+                .await?;
+            let Some(fee_budget_spend) = fee_budget_spend else {
+                return Ok(());
+            };
+
             let initial_txid = final_tx.compute_txid();
 
             // 4. Broadcast the finalized transaction
@@ -1059,10 +1114,16 @@ impl TxSender {
                         try_to_send_id,
                         "Successfully sent initial RBF tx with txid {sent_txid}"
                     );
+                    record_submission(SubmissionKind::RbfInitial, tx_metadata.as_ref());
                     sent_txid
                 }
                 Err(e) => {
                     tracing::error!("RBF failed for: {:?}", final_tx);
+                    record_rejection(
+                        SubmissionKind::RbfInitial,
+                        tx_metadata.as_ref(),
+                        &e.to_string(),
+                    );
                     let err_msg = format!("send_raw_transaction error for initial RBF tx: {e}");
                     log_error_for_tx!(self.db, try_to_send_id, err_msg);
                     let _ = self
@@ -1073,6 +1134,7 @@ impl TxSender {
                             true,
                         )
                         .await;
+                    self.release_fee_budget(fee_budget_spend).await;
                     return Err(SendTxError::Other(eyre!(e)));
                 }
             };
@@ -15,3 +15,56 @@ pub(crate) fn is_mempool_not_found_error(err: &Error) -> bool {
 pub(crate) fn is_rejecting_replacement_error(s: &str) -> bool {
     s.contains("insufficient fee, rejecting replacement")
 }
+
+/// Classifies a rejected broadcast for the `reason` label of
+/// `tx_sender_rejections_total`.
+pub(crate) fn rejection_reason(s: &str) -> &'static str {
+    if is_rejecting_replacement_error(s) {
+        "replacement_insufficient_fee"
+    } else if s.contains("min relay fee not met") || s.contains("mempool min fee not met") {
+        "fee_too_low"
+    } else if s.contains("mempool full") {
+        "mempool_full"
+    } else if s.contains("missingorspent") || s.contains("missing-inputs") {
+        "inputs_missing_or_spent"
+    } else if s.contains("Transaction already in block chain")
+        || s.contains("txn-already-known")
+        || s.contains("txn-already-in-mempool")
+    {
+        "already_known"
+    } else if s.contains("non-final") || s.contains("non-BIP68-final") {
+        "non_final"
+    } else if s.contains("too-long-mempool-chain") {
+        "too_long_mempool_chain"
+    } else if s.contains("script-verify-flag") {
+        "script_verification_failed"
+    } else {
+        "other"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejection_reason_classifies_bitcoind_errors() {
+        assert_eq!(
+            rejection_reason("insufficient fee, rejecting replacement abc; new feerate"),
+            "replacement_insufficient_fee"
+        );
+        assert_eq!(
+            rejection_reason("JSON-RPC error: RPC error response: RpcError { code: -26, message: \"min relay fee not met, 100 < 110\" }"),
+            "fee_too_low"
+        );
+        assert_eq!(
+            rejection_reason("bad-txns-inputs-missingorspent"),
+            "inputs_missing_or_spent"
+        );
+        assert_eq!(
+            rejection_reason("mandatory-script-verify-flag-failed (Invalid Schnorr signature)"),
+            "script_verification_failed"
+        );
+        assert_eq!(rejection_reason("connection refused"), "other");
+    }
+}
@@ -12,6 +12,7 @@ pub struct TxSenderTaskInternal {
 
 impl TxSenderTaskInternal {
     pub fn new(inner: TxSender) -> Self {
+        crate::metrics::describe_metrics();
         Self {
             current_tip_height: 0,
             last_processed_tip_height: 0,
@@ -54,6 +55,11 @@ impl TxSenderTaskInternal {
             .update_synced_height(self.current_tip_height)
             .await?;
 
+        // Gauges are best effort, a failed refresh shouldn't stop the loop.
+        if let Err(e) = self.inner.publish_metrics().await {
+            tracing::warn!("Failed to publish tx-sender metrics: {e:?}");
+        }
+
         Ok(false)
     }
 }
@@ -43,6 +43,8 @@ pub async fn create_test_environment(
         poll_delay_ms: 500,
         include_unsafe: true,
         jsonrpc: None,
+        metrics: None,
+        dry_run: false,
         mempool: MempoolConfig {
             host: None,
             endpoint: None,
@@ -1,10 +1,22 @@
 use clementine_errors::BridgeError;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Tracer, TracerProvider};
+use opentelemetry_sdk::{runtime, Resource};
 use std::fs::File;
+use std::sync::OnceLock;
 use tracing::level_filters::LevelFilter;
 use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
 use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::{fmt, EnvFilter, Layer as TracingLayer, Registry};
 
+/// Service name reported to the OTLP collector if `OTEL_SERVICE_NAME` is not set.
+const DEFAULT_OTEL_SERVICE_NAME: &str = "clementine";
+
 /// Initializes a [`tracing`] subscriber depending on the environment.
 /// [`EnvFilter`] is used with an optional default level. Sets up the
 /// [`color_eyre`] handler.
@@ -15,6 +27,16 @@ use tracing_subscriber::{fmt, EnvFilter, Layer as TracingLayer, Registry};
 /// - `human` **Human-readable** direct logs are used when `LOG_FORMAT` is not
 ///   set to `json`.
 ///
+/// # Distributed Tracing
+///
+/// Spans are additionally exported over OTLP/gRPC when
+/// `OTEL_EXPORTER_OTLP_ENDPOINT` is set (e.g. `http://localhost:4317`), with
+/// `OTEL_SERVICE_NAME` as the service name. The W3C trace context propagator is
+/// always installed, so trace context received from peers is passed on even if
+/// this process doesn't export spans itself. The exporter runs in the
+/// background, so this must be called inside a Tokio runtime when it is
+/// enabled. Call [`shutdown_tracer_provider`] before exiting to flush spans.
+///
 /// ## CI
 ///
 /// In CI, logging is always in the human-readable format with output to the
@@ -94,6 +116,8 @@ pub fn initialize_logger(default_level: Option<LevelFilter>) -> Result<(), Bridg
 
     let _ = hook_builder.install();
 
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
     if is_ci {
         let info_log_file = std::env::var("INFO_LOG_FILE").ok();
         if let Some(file_path) = info_log_file {
@@ -118,6 +142,57 @@ pub fn initialize_logger(default_level: Option<LevelFilter>) -> Result<(), Bridg
     Ok(())
 }
 
+/// Flushes and shuts down the OTLP span exporter, if it was enabled.
+pub fn shutdown_tracer_provider() {
+    if matches!(OTLP_TRACER.get(), Some(Some(_))) {
+        opentelemetry::global::shutdown_tracer_provider();
+    }
+}
+
+static OTLP_TRACER: OnceLock<Option<Tracer>> = OnceLock::new();
+
+/// Returns the OTLP tracer, creating the exporter on the first call. `None` if
+/// `OTEL_EXPORTER_OTLP_ENDPOINT` is not set or the exporter can't be created.
+fn otlp_tracer() -> Option<Tracer> {
+    OTLP_TRACER
+        .get_or_init(|| {
+            let endpoint = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()?;
+            let service_name = std::env::var("OTEL_SERVICE_NAME")
+                .unwrap_or_else(|_| DEFAULT_OTEL_SERVICE_NAME.to_string());
+
+            let exporter = match opentelemetry_otlp::SpanExporter::builder()
+                .with_tonic()
+                .with_endpoint(endpoint)
+                .build()
+            {
+                Ok(exporter) => exporter,
+                Err(e) => {
+                    // The subscriber isn't set yet, so this can't be logged with tracing
+                    eprintln!("Failed to create OTLP span exporter, spans won't be exported: {e}");
+                    return None;
+                }
+            };
+
+            let provider = TracerProvider::builder()
+                .with_batch_exporter(exporter, runtime::Tokio)
+                .with_resource(Resource::new([KeyValue::new("service.name", service_name)]))
+                .build();
+            let tracer = provider.tracer("clementine");
+            opentelemetry::global::set_tracer_provider(provider);
+
+            Some(tracer)
+        })
+        .clone()
+}
+
+/// Layer exporting spans over OTLP, `None` if the export is disabled.
+fn otlp_layer<S>() -> Option<OpenTelemetryLayer<S, Tracer>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    otlp_tracer().map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
 fn try_set_global_subscriber<S>(subscriber: S)
 where
     S: Subscriber + Send + Sync + 'static,
@@ -177,7 +252,10 @@ fn env_subscriber_with_file(path: &str) -> Result<Box<dyn Subscriber + Send + Sy
         .boxed();
 
     Ok(Box::new(
-        Registry::default().with(file_layer).with(console_layer),
+        Registry::default()
+            .with(file_layer)
+            .with(console_layer)
+            .with(otlp_layer()),
     ))
 }
 
@@ -203,7 +281,12 @@ fn env_subscriber_to_json(level: Option<LevelFilter>) -> Box<dyn Subscriber + Se
     // To see how long each span takes, uncomment this.
     // .with_span_events(FmtSpan::CLOSE)
 
-    Box::new(tracing_subscriber::registry().with(json_layer).with(filter))
+    Box::new(
+        tracing_subscriber::registry()
+            .with(json_layer)
+            .with(otlp_layer())
+            .with(filter),
+    )
 }
 
 fn env_subscriber_to_human(level: Option<LevelFilter>) -> Box<dyn Subscriber + Send + Sync> {
@@ -226,6 +309,7 @@ fn env_subscriber_to_human(level: Option<LevelFilter>) -> Box<dyn Subscriber + S
     Box::new(
         tracing_subscriber::registry()
             .with(standard_layer)
+            .with(otlp_layer())
             .with(filter),
     )
 }